@@ -7,7 +7,7 @@ use pricing::simulation::monte_carlo::{MonteCarloPathSimulator, PathEvaluator};
 use pricing::simulation::sde::gbm::GeometricBrownianMotion;
 use pricing::simulation::sde::multivariate_gbm::MultivariateGeometricBrownianMotion;
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
 use ndarray::{arr1, arr2, Array2};
 use rand_distr::StandardNormal;
 
@@ -26,6 +26,8 @@ criterion_main!(benches);
 
 pub fn criterion_stock_price_simulation(c: &mut Criterion) {
     let mut group = c.benchmark_group("Stock price Monte Carlo simulation");
+    // the throughput in samples, i.e. paths times steps
+    group.throughput(Throughput::Elements(30_000 * 200));
 
     group.bench_function(
         "apply a path function on the stored paths with Hc128 RNG",
@@ -130,11 +132,16 @@ fn simulate_paths_with_path_generator_gbm((nr_paths, nr_steps): (usize, usize))
 
 pub fn criterion_basket_stock_price_simulation(c: &mut Criterion) {
     let mut group = c.benchmark_group("Basket stock price Monte Carlo simulation");
+    group.throughput(Throughput::Elements(5_000 * 200 * 3));
 
     group.bench_function("direct multivariate gbm sampler", |b| {
         b.iter(|| basket_stock_price_simulation(black_box((5_000, 200))))
     });
 
+    group.bench_function("vectorized multivariate gbm ensemble", |b| {
+        b.iter(|| basket_stock_price_ensemble_simulation(black_box((5_000, 200))))
+    });
+
     group.finish()
 }
 
@@ -164,9 +171,28 @@ fn basket_stock_price_simulation((nr_paths, nr_steps): (usize, usize)) {
     assert!(avg_price.is_some());
 }
 
+fn basket_stock_price_ensemble_simulation((nr_paths, nr_steps): (usize, usize)) {
+    let initial_values = arr1(&[110.0, 120.0, 130.0]);
+    let drifts = arr1(&[0.1, 0.2, 0.3]);
+    let cholesky_factor = arr2(&[[1.0, 0.05, 0.1], [0.0, 0.6, 0.7], [0.0, 0.0, 0.8]]);
+    let dt = 1.0;
+
+    let mv_gbm =
+        MultivariateGeometricBrownianMotion::new(initial_values, drifts, cholesky_factor, dt);
+    let mut rn_generator = <rand_hc::Hc128Rng as rand::SeedableRng>::seed_from_u64(42);
+    let paths = mv_gbm.sample_ensemble(&mut rn_generator, nr_paths, nr_steps);
+
+    let avg_price = paths
+        .slice(ndarray::s![.., .., nr_steps])
+        .sum_axis(ndarray::Axis(1))
+        .mean();
+    assert!(avg_price.is_some());
+}
+
 pub fn criterion_multivariate_normal_distr(c: &mut Criterion) {
     let mut group =
         c.benchmark_group("Monte Carlo simulation for Multivariate Normal Distribution paths");
+    group.throughput(Throughput::Elements(5_000 * 300 * 3));
 
     group.bench_function("modelled with array2 and Hc128 RNG", |b| {
         b.iter(|| {
@@ -0,0 +1,273 @@
+// Throughput profiles of the Monte Carlo subsystems with timings per stage:
+// sampling of the random numbers (rng), their transformation into paths (transform)
+// and the evaluation of the payoffs (evaluation).
+//
+// Run with `cargo bench --bench mc_profile`; the profiles are printed and exported as JSON to
+// `target/mc_profile.json`, or to the path of the environment variable `MC_PROFILE_OUTPUT`,
+// such that the throughput of different revisions can be compared.
+
+extern crate pricing;
+use pricing::simulation::monte_carlo::{MonteCarloPathSimulator, PathEvaluator};
+use pricing::simulation::sde::gbm::GeometricBrownianMotion;
+use pricing::simulation::sde::heston::{Heston, HestonParameters};
+use pricing::simulation::sde::multivariate_gbm::MultivariateGeometricBrownianMotion;
+
+use criterion::black_box;
+use ndarray::{arr1, arr2, Array2};
+use rand::{Rng, SeedableRng};
+use rand_distr::StandardNormal;
+use std::time::{Duration, Instant};
+
+/// The number of repetitions of each profile, of which the fastest timings per stage are reported.
+const NR_REPETITIONS: usize = 5;
+
+fn main() {
+    let profiles = [
+        profile_univariate_gbm(50_000, 250),
+        profile_multivariate_gbm(10_000, 250),
+        profile_heston(50_000, 250),
+        profile_payoff_evaluation(50_000, 250),
+    ];
+
+    for profile in &profiles {
+        println!("{profile}");
+    }
+
+    let output = std::env::var("MC_PROFILE_OUTPUT").unwrap_or_else(|_| {
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../target/mc_profile.json").to_string()
+    });
+    let json = format!(
+        "[\n{}\n]\n",
+        profiles
+            .iter()
+            .map(Profile::to_json)
+            .collect::<Vec<_>>()
+            .join(",\n")
+    );
+    match std::fs::write(&output, json) {
+        Ok(()) => println!("exported the profiles to {output}"),
+        Err(err) => eprintln!("failed to export the profiles to {output}: {err}"),
+    }
+}
+
+/// The timings of the stages of a subsystem for the simulation of `nr_paths` paths.
+struct Profile {
+    name: &'static str,
+    nr_paths: usize,
+    nr_steps: usize,
+    nr_assets: usize,
+    stages: Vec<(&'static str, Duration)>,
+}
+
+impl Profile {
+    /// Repeats the stages and keeps the fastest timing per stage.
+    fn measure(
+        name: &'static str,
+        (nr_paths, nr_steps, nr_assets): (usize, usize, usize),
+        mut run_stages: impl FnMut() -> Vec<(&'static str, Duration)>,
+    ) -> Self {
+        let mut stages = run_stages();
+        for _ in 1..NR_REPETITIONS {
+            for (fastest, (_, timing)) in stages.iter_mut().zip(run_stages()) {
+                fastest.1 = fastest.1.min(timing);
+            }
+        }
+        Self {
+            name,
+            nr_paths,
+            nr_steps,
+            nr_assets,
+            stages,
+        }
+    }
+
+    fn total(&self) -> Duration {
+        self.stages.iter().map(|(_, timing)| *timing).sum()
+    }
+
+    fn paths_per_sec(&self) -> f64 {
+        self.nr_paths as f64 / self.total().as_secs_f64()
+    }
+
+    /// The throughput of the (univariate) samples, i.e. per asset and step.
+    fn samples_per_sec(&self) -> f64 {
+        (self.nr_paths * self.nr_steps * self.nr_assets) as f64 / self.total().as_secs_f64()
+    }
+
+    fn to_json(&self) -> String {
+        let stages = self
+            .stages
+            .iter()
+            .map(|(stage, timing)| format!("\"{stage}\": {}", timing.as_secs_f64()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            "  {{\"name\": \"{}\", \"nr_paths\": {}, \"nr_steps\": {}, \"nr_assets\": {}, \
+             \"total_secs\": {}, \"paths_per_sec\": {}, \"samples_per_sec\": {}, \
+             \"stage_secs\": {{{stages}}}}}",
+            self.name,
+            self.nr_paths,
+            self.nr_steps,
+            self.nr_assets,
+            self.total().as_secs_f64(),
+            self.paths_per_sec(),
+            self.samples_per_sec()
+        )
+    }
+}
+
+impl std::fmt::Display for Profile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} ({} paths, {} steps, {} assets): {:.0} paths/sec, {:.0} samples/sec",
+            self.name,
+            self.nr_paths,
+            self.nr_steps,
+            self.nr_assets,
+            self.paths_per_sec(),
+            self.samples_per_sec()
+        )?;
+        for (stage, timing) in &self.stages {
+            writeln!(f, "  {stage:<12} {:>10.3} ms", 1e3 * timing.as_secs_f64())?;
+        }
+        Ok(())
+    }
+}
+
+fn timed<T>(stage: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = black_box(stage());
+    (result, start.elapsed())
+}
+
+fn profile_univariate_gbm(nr_paths: usize, nr_steps: usize) -> Profile {
+    let s0 = 300.0;
+    let gbm = GeometricBrownianMotion::new(s0, 0.01, 0.2, 1.0 / nr_steps as f64);
+
+    Profile::measure("univariate gbm", (nr_paths, nr_steps, 1), || {
+        let mut rn_generator = rand_hc::Hc128Rng::seed_from_u64(42);
+        let (normals, rng) = timed(|| {
+            (0..nr_paths)
+                .map(|_| {
+                    (&mut rn_generator)
+                        .sample_iter(StandardNormal)
+                        .take(nr_steps)
+                        .collect::<Vec<f64>>()
+                })
+                .collect::<Vec<_>>()
+        });
+        let (paths, transform) = timed(|| {
+            normals
+                .iter()
+                .map(|z| gbm.generate_path(s0, z))
+                .collect::<Vec<_>>()
+        });
+        let (_, evaluation) = timed(|| {
+            PathEvaluator::new(&paths).evaluate_average(|path| path.last().map(|s| s.max(s0)))
+        });
+        vec![
+            ("rng", rng),
+            ("transform", transform),
+            ("evaluation", evaluation),
+        ]
+    })
+}
+
+fn multivariate_gbm(nr_steps: usize) -> MultivariateGeometricBrownianMotion {
+    MultivariateGeometricBrownianMotion::new(
+        arr1(&[110.0, 120.0, 130.0]),
+        arr1(&[0.01, 0.02, 0.03]),
+        arr2(&[[0.2, 0.05, 0.1], [0.0, 0.15, 0.1], [0.0, 0.0, 0.25]]),
+        1.0 / nr_steps as f64,
+    )
+}
+
+fn profile_multivariate_gbm(nr_paths: usize, nr_steps: usize) -> Profile {
+    let mv_gbm = multivariate_gbm(nr_steps);
+
+    Profile::measure("multivariate gbm", (nr_paths, nr_steps, 3), || {
+        let mut rn_generator = rand_hc::Hc128Rng::seed_from_u64(42);
+        let (normals, rng) = timed(|| {
+            (0..nr_paths)
+                .map(|_| {
+                    Array2::from_shape_simple_fn((3, nr_steps + 1), || {
+                        rn_generator.sample::<f64, _>(StandardNormal)
+                    })
+                })
+                .collect::<Vec<_>>()
+        });
+        let (paths, transform) = timed(|| {
+            normals
+                .iter()
+                .map(|z| mv_gbm.transform_path(z, nr_steps + 1))
+                .collect::<Vec<_>>()
+        });
+        let (_, evaluation) = timed(|| {
+            PathEvaluator::new(&paths)
+                .evaluate_average(|path| path.columns().into_iter().last().map(|s| s.sum()))
+        });
+        vec![
+            ("rng", rng),
+            ("transform", transform),
+            ("evaluation", evaluation),
+        ]
+    })
+}
+
+/// The variances are sampled within the path generation, such that only the simulation and the evaluation are split.
+fn profile_heston(nr_paths: usize, nr_steps: usize) -> Profile {
+    let s0 = 100.0;
+    let parameters = HestonParameters {
+        initial_variance: 0.04,
+        mean_reversion: 1.5,
+        long_term_variance: 0.04,
+        vol_of_vol: 0.5,
+        correlation: -0.7,
+    };
+    let heston = Heston::new(s0, 0.01, parameters, 1.0 / nr_steps as f64).unwrap();
+    let simulator: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, Vec<f64>> =
+        MonteCarloPathSimulator::new(heston, Some(42));
+
+    Profile::measure("heston", (nr_paths, nr_steps, 1), || {
+        let (paths, simulation) = timed(|| simulator.simulate_paths(nr_paths, nr_steps));
+        let (_, evaluation) = timed(|| {
+            PathEvaluator::new(&paths)
+                .evaluate_average(|path| path.last().map(|s| (s - s0).max(0.0)))
+        });
+        vec![("simulation", simulation), ("evaluation", evaluation)]
+    })
+}
+
+/// The evaluation of path-dependent payoffs on simulated paths.
+fn profile_payoff_evaluation(nr_paths: usize, nr_steps: usize) -> Profile {
+    let s0 = 100.0;
+    let gbm = GeometricBrownianMotion::new(s0, 0.01, 0.2, 1.0 / nr_steps as f64);
+    let simulator: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, Vec<f64>> =
+        MonteCarloPathSimulator::new(gbm, Some(42));
+    let paths = simulator.simulate_paths(nr_paths, nr_steps);
+
+    Profile::measure("payoff evaluation", (nr_paths, nr_steps, 1), || {
+        let evaluator = PathEvaluator::new(&paths);
+        let (_, european) =
+            timed(|| evaluator.evaluate_average(|path| path.last().map(|s| (s - s0).max(0.0))));
+        let (_, asian) = timed(|| {
+            evaluator.evaluate_average(|path| {
+                let average = path.iter().sum::<f64>() / path.len() as f64;
+                Some((average - s0).max(0.0))
+            })
+        });
+        let (_, barrier) = timed(|| {
+            evaluator.evaluate_average(|path| {
+                let knocked_out = path.iter().any(|s| *s > 1.3 * s0);
+                path.last()
+                    .map(|s| if knocked_out { 0.0 } else { (s - s0).max(0.0) })
+            })
+        });
+        vec![
+            ("european", european),
+            ("asian", asian),
+            ("barrier", barrier),
+        ]
+    })
+}
@@ -0,0 +1,232 @@
+use crate::common::models::{DerivativeParameter, ExerciseType};
+use crate::numerics::normal::cdf;
+
+/// Approximations of the prices of European options on the arithmetic average of the asset prices
+/// $A = \frac{1}{n} \sum_{i=1}^n S_{t_i}$ at the equally spaced fixings $t_i = i T / n$ under Black-Scholes-Merton,
+/// for which there is no closed form.
+/// See https://en.wikipedia.org/wiki/Asian_option
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ArithmeticAsian {
+    pub nr_fixings: usize,
+}
+
+impl ArithmeticAsian {
+    pub fn new(nr_fixings: usize) -> Self {
+        assert!(nr_fixings > 0);
+        Self { nr_fixings }
+    }
+
+    fn fixing_times(&self, dp: &DerivativeParameter) -> Vec<f64> {
+        let dt = dp.time_to_expiration / self.nr_fixings as f64;
+        (1..=self.nr_fixings).map(|i| i as f64 * dt).collect()
+    }
+
+    /// The first two moments $E[A]$ and $E[A^2]$ of the arithmetic average.
+    pub fn moments(&self, dp: &DerivativeParameter) -> (f64, f64) {
+        let times = self.fixing_times(dp);
+        let n = self.nr_fixings as f64;
+        let first = dp.asset_price * times.iter().map(|t| (dp.rfr * t).exp()).sum::<f64>() / n;
+        let second = dp.asset_price.powi(2)
+            * times
+                .iter()
+                .flat_map(|t_i| {
+                    times.iter().map(move |t_j| {
+                        (dp.rfr * (t_i + t_j) + dp.vola.powi(2) * t_i.min(*t_j)).exp()
+                    })
+                })
+                .sum::<f64>()
+            / (n * n);
+        (first, second)
+    }
+
+    /// The Turnbull-Wakeman approximation, which prices the average as a lognormal variable with the
+    /// first two moments of the (discrete) arithmetic average.
+    /// See Turnbull and Wakeman, A quick algorithm for pricing European average options (1991)
+    pub fn turnbull_wakeman(&self, dp: &DerivativeParameter, exercise_type: &ExerciseType) -> f64 {
+        let (first, second) = self.moments(dp);
+        let t = dp.time_to_expiration;
+        let disc_factor = (-dp.rfr * t).exp();
+        let sigma_sqrt_t = (second / first.powi(2)).ln().max(0.0).sqrt();
+        if sigma_sqrt_t == 0.0 {
+            return disc_factor * exercise_type.payoff(first, dp.strike);
+        }
+        let d1 = ((first / dp.strike).ln() + 0.5 * sigma_sqrt_t * sigma_sqrt_t) / sigma_sqrt_t;
+        let d2 = d1 - sigma_sqrt_t;
+        disc_factor
+            * match exercise_type {
+                ExerciseType::Call => first * cdf(d1) - dp.strike * cdf(d2),
+                ExerciseType::Put => dp.strike * cdf(-d2) - first * cdf(-d1),
+            }
+    }
+
+    /// The mean and the variance of the log of the geometric average $G$ and the covariances
+    /// of the log asset prices at the fixings with it.
+    fn log_geometric_average(&self, dp: &DerivativeParameter) -> (f64, f64, Vec<f64>) {
+        let times = self.fixing_times(dp);
+        let n = self.nr_fixings as f64;
+        let variance_rate = dp.vola.powi(2);
+        let mean =
+            dp.asset_price.ln() + (dp.rfr - 0.5 * variance_rate) * times.iter().sum::<f64>() / n;
+        let covariances: Vec<f64> = times
+            .iter()
+            .map(|t_i| variance_rate * times.iter().map(|t_j| t_i.min(*t_j)).sum::<f64>() / n)
+            .collect();
+        let variance = covariances.iter().sum::<f64>() / n;
+        (mean, variance, covariances)
+    }
+
+    /// The exact price of the option on the geometric average, which is lognormal.
+    pub fn geometric(&self, dp: &DerivativeParameter, exercise_type: &ExerciseType) -> f64 {
+        let (mean, variance, _) = self.log_geometric_average(dp);
+        let disc_factor = (-dp.rfr * dp.time_to_expiration).exp();
+        let forward = (mean + 0.5 * variance).exp();
+        if variance == 0.0 {
+            return disc_factor * exercise_type.payoff(forward, dp.strike);
+        }
+        let std = variance.sqrt();
+        let d1 = (mean - dp.strike.ln() + variance) / std;
+        let d2 = d1 - std;
+        disc_factor
+            * match exercise_type {
+                ExerciseType::Call => forward * cdf(d1) - dp.strike * cdf(d2),
+                ExerciseType::Put => dp.strike * cdf(-d2) - forward * cdf(-d1),
+            }
+    }
+
+    /// Curran's approximation, which conditions on the geometric average: the call is exercised
+    /// for all geometric averages above a critical value, which is approximated in closed form.
+    /// Puts follow from the put-call parity of the average.
+    /// See Curran, Valuing Asian and portfolio options by conditioning on the geometric mean price (1994)
+    pub fn curran(&self, dp: &DerivativeParameter, exercise_type: &ExerciseType) -> f64 {
+        let (first, _) = self.moments(dp);
+        let disc_factor = (-dp.rfr * dp.time_to_expiration).exp();
+        let forward_value = disc_factor * (first - dp.strike);
+        let call = self.curran_call(dp).unwrap_or(forward_value);
+        match exercise_type {
+            ExerciseType::Call => call,
+            ExerciseType::Put => call - forward_value,
+        }
+    }
+
+    /// None if the critical value is not positive, i.e. the call is exercised almost surely.
+    fn curran_call(&self, dp: &DerivativeParameter) -> Option<f64> {
+        let (mean_g, variance_g, covariances) = self.log_geometric_average(dp);
+        if variance_g == 0.0 {
+            return None;
+        }
+        let std_g = variance_g.sqrt();
+        let times = self.fixing_times(dp);
+        let n = self.nr_fixings as f64;
+        let variance_rate = dp.vola.powi(2);
+        let log_means: Vec<f64> = times
+            .iter()
+            .map(|t| dp.asset_price.ln() + (dp.rfr - 0.5 * variance_rate) * t)
+            .collect();
+
+        let log_strike = dp.strike.ln();
+        let critical_value = 2.0 * dp.strike
+            - log_means
+                .iter()
+                .zip(&times)
+                .zip(&covariances)
+                .map(|((mu_i, t_i), cov_i)| {
+                    (mu_i
+                        + cov_i * (log_strike - mean_g) / variance_g
+                        + 0.5 * (variance_rate * t_i - cov_i * cov_i / variance_g))
+                        .exp()
+                })
+                .sum::<f64>()
+                / n;
+        if critical_value <= 0.0 {
+            return None;
+        }
+        let log_critical_value = critical_value.ln();
+        let conditional_average = log_means
+            .iter()
+            .zip(&times)
+            .zip(&covariances)
+            .map(|((mu_i, t_i), cov_i)| {
+                (mu_i + 0.5 * variance_rate * t_i).exp()
+                    * cdf((mean_g - log_critical_value + cov_i) / std_g)
+            })
+            .sum::<f64>()
+            / n;
+        let disc_factor = (-dp.rfr * dp.time_to_expiration).exp();
+        Some(
+            disc_factor
+                * (conditional_average - dp.strike * cdf((mean_g - log_critical_value) / std_g)),
+        )
+    }
+}
+
+#[cfg(all(test, feature = "mc"))]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::BsmComputation;
+    use crate::common::results::PriceResult;
+    use crate::simulation::greeks::SharedNormals;
+    use crate::simulation::sde::gbm::GeometricBrownianMotion;
+    use crate::simulation::verification::assert_within_std_errors;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn single_fixing_is_european() {
+        let dp = DerivativeParameter::new(100.0, 105.0, 0.75, 0.03, 0.25);
+        let asian = ArithmeticAsian::new(1);
+        let bsm = BsmComputation::new(&dp);
+        for exercise_type in [ExerciseType::Call, ExerciseType::Put] {
+            let european = bsm.price(&exercise_type);
+            assert_approx_eq!(asian.turnbull_wakeman(&dp, &exercise_type), european, 1e-10);
+            assert_approx_eq!(asian.curran(&dp, &exercise_type), european, 1e-10);
+            assert_approx_eq!(asian.geometric(&dp, &exercise_type), european, 1e-10);
+        }
+    }
+
+    #[test]
+    fn approximations_against_monte_carlo() {
+        let (spot, rfr, vola, t, nr_fixings) = (100.0, 0.05, 0.3, 1.0, 12);
+        let asian = ArithmeticAsian::new(nr_fixings);
+        let gbm = GeometricBrownianMotion::new(spot, rfr, vola, t / nr_fixings as f64);
+        let normals = SharedNormals::new::<rand_hc::Hc128Rng>(50_000, nr_fixings, 42);
+        let fixings: Vec<Vec<f64>> = normals
+            .paths()
+            .iter()
+            .map(|z| {
+                let mut s = spot;
+                z.iter()
+                    .map(|z_i| {
+                        s = gbm.step_analytic(s, *z_i);
+                        s
+                    })
+                    .collect()
+            })
+            .collect();
+        let disc_factor = (-rfr * t).exp();
+
+        for strike in [90.0, 100.0, 110.0] {
+            let dp = DerivativeParameter::new(spot, strike, t, rfr, vola);
+            for exercise_type in [ExerciseType::Call, ExerciseType::Put] {
+                let mc_price = |average: fn(&[f64]) -> f64| {
+                    let payoffs: Vec<f64> = fixings
+                        .iter()
+                        .map(|path| disc_factor * exercise_type.payoff(average(path), strike))
+                        .collect();
+                    PriceResult::from_samples(&payoffs).unwrap()
+                };
+                let arithmetic = mc_price(|path| path.iter().sum::<f64>() / path.len() as f64);
+                let geometric = mc_price(|path| {
+                    (path.iter().map(|s| s.ln()).sum::<f64>() / path.len() as f64).exp()
+                });
+
+                assert_within_std_errors(&geometric, asian.geometric(&dp, &exercise_type), 4.0);
+                assert_within_std_errors(&arithmetic, asian.curran(&dp, &exercise_type), 4.0);
+                // the lognormal approximation is less accurate, yet within a few cents
+                assert_approx_eq!(
+                    asian.turnbull_wakeman(&dp, &exercise_type),
+                    arithmetic.value,
+                    0.15
+                );
+            }
+        }
+    }
+}
@@ -1,15 +1,50 @@
-use crate::common::models::DerivativeParameter;
-use probability::distribution::{Distribution, Gaussian};
-
-pub(crate) fn cdf(d: f64) -> f64 {
-    let normal = Gaussian::new(0.0, 1.0);
-    normal.distribution(d)
-}
+use crate::common::models::{DerivativeParameter, ExerciseType};
+use crate::common::results::{GreeksResult, PriceResult, PricingError};
+use crate::numerics::dual::Scalar;
+use crate::numerics::solvers::{newton, Solution, SolverOptions};
 
 pub trait OptionPrice {
     type Params;
     fn put(params: &Self::Params) -> f64;
     fn call(params: &Self::Params) -> f64;
+
+    /// The price of the call or put as an exact `PriceResult` with its runtime, or the reason the
+    /// pricing fails: invalid parameters or a non-finite price.
+    fn try_price(
+        dp: &DerivativeParameter,
+        exercise_type: &ExerciseType,
+    ) -> Result<PriceResult, PricingError>
+    where
+        Self: OptionPrice<Params = DerivativeParameter>,
+    {
+        dp.validate()?;
+        PriceResult::timed(|| {
+            let value = match exercise_type {
+                ExerciseType::Call => Self::call(dp),
+                ExerciseType::Put => Self::put(dp),
+            };
+            if !value.is_finite() {
+                return Err(PricingError::NonFiniteValue(value));
+            }
+            Ok(PriceResult::exact(value))
+        })
+    }
+
+    /// The price of the call as by `try_price`.
+    fn try_call(dp: &DerivativeParameter) -> Result<PriceResult, PricingError>
+    where
+        Self: OptionPrice<Params = DerivativeParameter>,
+    {
+        Self::try_price(dp, &ExerciseType::Call)
+    }
+
+    /// The price of the put as by `try_price`.
+    fn try_put(dp: &DerivativeParameter) -> Result<PriceResult, PricingError>
+    where
+        Self: OptionPrice<Params = DerivativeParameter>,
+    {
+        Self::try_price(dp, &ExerciseType::Put)
+    }
 }
 
 /// European Put and Call option prices for stocks.
@@ -20,24 +55,217 @@ impl OptionPrice for BlackScholesMerton {
     type Params = DerivativeParameter;
 
     fn call(dp: &DerivativeParameter) -> f64 {
-        let sigma_exp = dp.vola * dp.time_to_expiration.sqrt();
-        let d1 = ((dp.asset_price / dp.strike).ln()
-            + (dp.rfr + dp.vola.powi(2) / 2.0) * dp.time_to_expiration)
-            / sigma_exp;
-        let d2 = d1 - sigma_exp;
-        cdf(d1) * dp.asset_price - cdf(d2) * dp.strike * (-dp.rfr * dp.time_to_expiration).exp()
+        BsmComputation::new(dp).call()
     }
 
     fn put(dp: &DerivativeParameter) -> f64 {
-        let sigma_exp = dp.vola * dp.time_to_expiration.sqrt();
-        let d1 = ((dp.asset_price / dp.strike).ln()
-            + (dp.rfr + dp.vola.powi(2) / 2.0) * dp.time_to_expiration)
-            / sigma_exp;
-        let d2 = d1 - sigma_exp;
-        cdf(-d2) * dp.strike * (-dp.rfr * dp.time_to_expiration).exp() - cdf(-d1) * dp.asset_price
+        BsmComputation::new(dp).put()
     }
 }
 
+/// The Black-Scholes-Merton quantities $d_1$, $d_2$, the discount factor and the normal cdf and pdf values,
+/// computed once for the evaluation of prices and greeks of the same parameters. Generic over the
+/// scalar, such that dual numbers yield exact derivatives of the prices.
+/// See https://en.wikipedia.org/wiki/Black-Scholes_model#Black-Scholes_formula
+#[derive(Clone, Copy, Debug)]
+pub struct BsmComputation<T = f64> {
+    asset_price: T,
+    strike: T,
+    time_to_expiration: T,
+    rfr: T,
+    sigma_sqrt_t: T,
+    pub d1: T,
+    pub d2: T,
+    pub disc_factor: T,
+    cdf_d1: T,
+    cdf_d2: T,
+    pdf_d1: T,
+}
+
+impl<T: Scalar> BsmComputation<T> {
+    /// The quantities of the parameters, where an expired option or a vanishing volatility have
+    /// infinite $d_1 = d_2$ by the sign of the forward moneyness, such that the prices are the
+    /// discounted intrinsic values of the forward.
+    pub fn from_parameters(
+        asset_price: T,
+        strike: T,
+        time_to_expiration: T,
+        rfr: T,
+        vola: T,
+    ) -> Self {
+        let sigma_sqrt_t = vola * time_to_expiration.sqrt();
+        let log_moneyness = (asset_price / strike).ln()
+            + (rfr + vola * vola / T::constant(2.0)) * time_to_expiration;
+        let d1 = if sigma_sqrt_t.real() > 0.0 {
+            log_moneyness / sigma_sqrt_t
+        } else if log_moneyness.real() > 0.0 {
+            T::constant(f64::INFINITY)
+        } else {
+            T::constant(f64::NEG_INFINITY)
+        };
+        let d2 = d1 - sigma_sqrt_t;
+        Self {
+            asset_price,
+            strike,
+            time_to_expiration,
+            rfr,
+            sigma_sqrt_t,
+            d1,
+            d2,
+            disc_factor: (-rfr * time_to_expiration).exp(),
+            cdf_d1: d1.norm_cdf(),
+            cdf_d2: d2.norm_cdf(),
+            pdf_d1: d1.norm_pdf(),
+        }
+    }
+
+    pub fn call(&self) -> T {
+        self.cdf_d1 * self.asset_price - self.cdf_d2 * self.strike * self.disc_factor
+    }
+
+    pub fn put(&self) -> T {
+        (-self.d2).norm_cdf() * self.strike * self.disc_factor
+            - (-self.d1).norm_cdf() * self.asset_price
+    }
+
+    pub fn price(&self, exercise_type: &ExerciseType) -> T {
+        match exercise_type {
+            ExerciseType::Call => self.call(),
+            ExerciseType::Put => self.put(),
+        }
+    }
+
+    /// The undiscounted price, i.e. the expected payoff under the forward measure of the expiration,
+    /// e.g. $F N(d_1) - K N(d_2)$ of a call on the forward $F = S e^{rT}$.
+    pub fn undiscounted_price(&self, exercise_type: &ExerciseType) -> T {
+        self.price(exercise_type) / self.disc_factor
+    }
+}
+
+impl BsmComputation {
+    /// The quantities of the derivative parameters as by `from_parameters`.
+    pub fn new(dp: &DerivativeParameter) -> Self {
+        Self::from_parameters(
+            dp.asset_price,
+            dp.strike,
+            dp.time_to_expiration,
+            dp.rfr,
+            dp.vola,
+        )
+    }
+
+    pub fn delta(&self, exercise_type: &ExerciseType) -> f64 {
+        match exercise_type {
+            ExerciseType::Call => self.cdf_d1,
+            ExerciseType::Put => self.cdf_d1 - 1.0,
+        }
+    }
+
+    /// The gamma, equal for calls and puts.
+    pub fn gamma(&self) -> f64 {
+        self.pdf_d1 / (self.asset_price * self.sigma_sqrt_t)
+    }
+
+    /// The vega (per unit volatility), equal for calls and puts.
+    pub fn vega(&self) -> f64 {
+        self.asset_price * self.pdf_d1 * self.time_to_expiration.sqrt()
+    }
+
+    /// The theta (per year) as the derivative with respect to the calendar time.
+    pub fn theta(&self, exercise_type: &ExerciseType) -> f64 {
+        let time_decay =
+            -self.asset_price * self.pdf_d1 * self.sigma_sqrt_t / (2.0 * self.time_to_expiration);
+        let carry = self.rfr * self.strike * self.disc_factor;
+        match exercise_type {
+            ExerciseType::Call => time_decay - carry * self.cdf_d2,
+            ExerciseType::Put => time_decay + carry * (1.0 - self.cdf_d2),
+        }
+    }
+
+    /// The rho (per unit rate).
+    pub fn rho(&self, exercise_type: &ExerciseType) -> f64 {
+        let discounted_strike = self.strike * self.time_to_expiration * self.disc_factor;
+        match exercise_type {
+            ExerciseType::Call => discounted_strike * self.cdf_d2,
+            ExerciseType::Put => -discounted_strike * (1.0 - self.cdf_d2),
+        }
+    }
+
+    /// The price with all greeks.
+    pub fn greeks(&self, exercise_type: &ExerciseType) -> GreeksResult {
+        GreeksResult {
+            value: self.price(exercise_type),
+            delta: Some(self.delta(exercise_type)),
+            gamma: Some(self.gamma()),
+            vega: Some(self.vega()),
+            theta: Some(self.theta(exercise_type)),
+            rho: Some(self.rho(exercise_type)),
+        }
+    }
+}
+
+/// The Black-Scholes-Merton implied volatility of the option `price` by the safeguarded Newton's method
+/// on the vega. Returns None for invalid parameters and prices outside of the no-arbitrage bounds.
+/// See https://en.wikipedia.org/wiki/Implied_volatility
+pub fn implied_volatility(
+    price: f64,
+    dp: &DerivativeParameter,
+    exercise_type: &ExerciseType,
+) -> Option<f64> {
+    implied_volatility_with_options(price, dp, exercise_type, &SolverOptions::default())
+}
+
+/// The implied volatility as by `implied_volatility` with the options of the root search, which is
+/// None unless the search converges.
+pub fn implied_volatility_with_options(
+    price: f64,
+    dp: &DerivativeParameter,
+    exercise_type: &ExerciseType,
+    options: &SolverOptions,
+) -> Option<f64> {
+    // the vola of the parameters is not used
+    DerivativeParameter { vola: 0.0, ..*dp }.validate().ok()?;
+    let discounted_strike = dp.strike * (-dp.rfr * dp.time_to_expiration).exp();
+    let (lower_bound, upper_bound) = match exercise_type {
+        ExerciseType::Call => (
+            ExerciseType::Call.payoff(dp.asset_price, discounted_strike),
+            dp.asset_price,
+        ),
+        ExerciseType::Put => (
+            ExerciseType::Put.payoff(dp.asset_price, discounted_strike),
+            discounted_strike,
+        ),
+    };
+    if price <= lower_bound || price >= upper_bound {
+        return None;
+    }
+
+    let price_error = |vola: f64| {
+        let computation = BsmComputation::new(&DerivativeParameter::new(
+            dp.asset_price,
+            dp.strike,
+            dp.time_to_expiration,
+            dp.rfr,
+            vola,
+        ));
+        (computation.price(exercise_type) - price, computation.vega())
+    };
+    newton(price_error, 0.2, 1e-8, 10.0, options).and_then(Solution::converged)
+}
+
+/// The Black implied volatility of the undiscounted option price on the forward, e.g. of the quotes
+/// of a surface calibration, which is the Black-Scholes-Merton implied volatility without rates.
+pub fn implied_volatility_undiscounted(
+    undiscounted_price: f64,
+    forward: f64,
+    strike: f64,
+    time_to_expiration: f64,
+    exercise_type: &ExerciseType,
+) -> Option<f64> {
+    let dp = DerivativeParameter::new(forward, strike, time_to_expiration, 0.0, 0.2);
+    implied_volatility(undiscounted_price, &dp, exercise_type)
+}
+
 /// European Put and Call option prices for futures.
 /// https://en.wikipedia.org/wiki/Black_model
 pub struct Black76;
@@ -46,36 +274,204 @@ impl OptionPrice for Black76 {
     type Params = DerivativeParameter;
 
     fn call(dp: &DerivativeParameter) -> f64 {
-        let sigma_exp = dp.vola * dp.time_to_expiration.sqrt();
-        let d1 = ((dp.asset_price / dp.strike).ln() + (dp.vola.powi(2) / 2.0)) / sigma_exp;
-        let d2 = d1 - sigma_exp;
-        (-dp.rfr * dp.time_to_expiration).exp() * (cdf(d1) * dp.asset_price - cdf(d2) * dp.strike)
+        ShiftedBlack76::new(0.0)
+            .price(dp, &ExerciseType::Call)
+            .unwrap_or(f64::NAN)
+    }
+
+    fn put(dp: &DerivativeParameter) -> f64 {
+        ShiftedBlack76::new(0.0)
+            .price(dp, &ExerciseType::Put)
+            .unwrap_or(f64::NAN)
+    }
+}
+
+/// Black76 prices of the shifted forward $F + s$ struck at $K + s$ (displaced diffusion), where the
+/// shifted forward is lognormal such that forwards and strikes above $-s$ are priced, e.g. negative
+/// EUR or JPY rates with a shift of 1% to 3%. The `asset_price` of the parameters is the forward.
+/// See https://en.wikipedia.org/wiki/Black_model
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShiftedBlack76 {
+    pub shift: f64,
+}
+
+impl ShiftedBlack76 {
+    pub fn new(shift: f64) -> Self {
+        Self { shift }
+    }
+
+    /// Checks that the shifted forward and strike lie in the lognormal domain, i.e. that the shifted
+    /// parameters are valid, see `DerivativeParameter::validate`.
+    pub fn validate(&self, dp: &DerivativeParameter) -> Result<(), PricingError> {
+        let shifted = DerivativeParameter {
+            asset_price: dp.asset_price + self.shift,
+            strike: dp.strike + self.shift,
+            ..*dp
+        };
+        shifted.validate()?;
+        PricingError::check(shifted.strike > 0.0, "shifted strike", shifted.strike)
+    }
+
+    pub fn price(
+        &self,
+        dp: &DerivativeParameter,
+        exercise_type: &ExerciseType,
+    ) -> Result<f64, PricingError> {
+        self.validate(dp)?;
+        let (forward, strike) = (dp.asset_price + self.shift, dp.strike + self.shift);
+        let disc_factor = (-dp.rfr * dp.time_to_expiration).exp();
+        let sigma_sqrt_t = dp.vola * dp.time_to_expiration.sqrt();
+        if sigma_sqrt_t == 0.0 {
+            return Ok(disc_factor * exercise_type.payoff(forward, strike));
+        }
+        Ok(black76_price(
+            forward,
+            strike,
+            dp.time_to_expiration,
+            dp.rfr,
+            dp.vola,
+            exercise_type,
+        ))
+    }
+
+    /// The price without the discount factor, i.e. with the rate of the parameters ignored.
+    pub fn undiscounted_price(
+        &self,
+        dp: &DerivativeParameter,
+        exercise_type: &ExerciseType,
+    ) -> Result<f64, PricingError> {
+        self.price(&DerivativeParameter { rfr: 0.0, ..*dp }, exercise_type)
+    }
+
+    pub fn call(&self, dp: &DerivativeParameter) -> Result<f64, PricingError> {
+        self.price(dp, &ExerciseType::Call)
+    }
+
+    pub fn put(&self, dp: &DerivativeParameter) -> Result<f64, PricingError> {
+        self.price(dp, &ExerciseType::Put)
+    }
+}
+
+/// European Put and Call option prices of the normal model, where the forward follows an arithmetic
+/// Brownian motion, e.g. for rates near or below zero. The `asset_price` of the parameters is the
+/// forward and the `vola` the absolute (normal) volatility.
+/// See https://en.wikipedia.org/wiki/Bachelier_model
+pub struct Bachelier;
+
+impl OptionPrice for Bachelier {
+    type Params = DerivativeParameter;
+
+    fn call(dp: &DerivativeParameter) -> f64 {
+        bachelier_price(
+            dp.asset_price,
+            dp.strike,
+            dp.time_to_expiration,
+            dp.rfr,
+            dp.vola,
+            &ExerciseType::Call,
+        )
     }
 
     fn put(dp: &DerivativeParameter) -> f64 {
-        let sigma_exp = dp.vola * dp.time_to_expiration.sqrt();
-        let d1 = ((dp.asset_price / dp.strike).ln() + (dp.vola.powi(2) / 2.0)) / sigma_exp;
-        let d2 = d1 - sigma_exp;
-        (-dp.rfr * dp.time_to_expiration).exp() * (cdf(-d2) * dp.strike - cdf(-d1) * dp.asset_price)
+        bachelier_price(
+            dp.asset_price,
+            dp.strike,
+            dp.time_to_expiration,
+            dp.rfr,
+            dp.vola,
+            &ExerciseType::Put,
+        )
     }
 }
 
+/// The Black-Scholes-Merton price, generic over the scalar such that dual numbers yield exact
+/// derivatives with respect to any of the parameters.
+pub fn black_scholes_price<T: Scalar>(
+    asset_price: T,
+    strike: T,
+    time_to_expiration: T,
+    rfr: T,
+    vola: T,
+    exercise_type: &ExerciseType,
+) -> T {
+    BsmComputation::from_parameters(asset_price, strike, time_to_expiration, rfr, vola)
+        .price(exercise_type)
+}
+
+/// The Black76 price of the (shifted) forward, generic over the scalar.
+pub fn black76_price<T: Scalar>(
+    forward: T,
+    strike: T,
+    time_to_expiration: T,
+    rfr: T,
+    vola: T,
+    exercise_type: &ExerciseType,
+) -> T {
+    let disc_factor = (-rfr * time_to_expiration).exp();
+    disc_factor * undiscounted_black_price(forward, strike, time_to_expiration, vola, exercise_type)
+}
+
+/// The undiscounted Black price $F N(d_1) - K N(d_2)$ of a call on the forward, i.e. the expected
+/// payoff under the forward measure of the expiration, generic over the scalar.
+pub fn undiscounted_black_price<T: Scalar>(
+    forward: T,
+    strike: T,
+    time_to_expiration: T,
+    vola: T,
+    exercise_type: &ExerciseType,
+) -> T {
+    BsmComputation::from_parameters(forward, strike, time_to_expiration, T::constant(0.0), vola)
+        .price(exercise_type)
+}
+
+/// The Bachelier price $e^{-rT} ((F - K) N(d) + \sigma \sqrt{T} n(d))$ of a call with
+/// $d = (F - K) / (\sigma \sqrt{T})$, generic over the scalar.
+pub fn bachelier_price<T: Scalar>(
+    forward: T,
+    strike: T,
+    time_to_expiration: T,
+    rfr: T,
+    vola: T,
+    exercise_type: &ExerciseType,
+) -> T {
+    let disc_factor = (-rfr * time_to_expiration).exp();
+    disc_factor
+        * undiscounted_bachelier_price(forward, strike, time_to_expiration, vola, exercise_type)
+}
+
+/// The undiscounted Bachelier price $(F - K) N(d) + \sigma \sqrt{T} n(d)$ of a call on the forward,
+/// generic over the scalar. A vanishing volatility yields the intrinsic value of the forward.
+pub fn undiscounted_bachelier_price<T: Scalar>(
+    forward: T,
+    strike: T,
+    time_to_expiration: T,
+    vola: T,
+    exercise_type: &ExerciseType,
+) -> T {
+    let sigma_sqrt_t = vola * time_to_expiration.sqrt();
+    let moneyness = match exercise_type {
+        ExerciseType::Call => forward - strike,
+        ExerciseType::Put => strike - forward,
+    };
+    if sigma_sqrt_t.real() <= 0.0 {
+        return if moneyness.real() > 0.0 {
+            moneyness
+        } else {
+            T::constant(0.0)
+        };
+    }
+    let d = moneyness / sigma_sqrt_t;
+    moneyness * d.norm_cdf() + sigma_sqrt_t * d.norm_pdf()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::lattice::trinomial_tree::TrinomialTree;
     use assert_approx_eq::assert_approx_eq;
 
     const TOLERANCE: f64 = 1e-4;
 
-    #[test]
-    fn normal_cdf() {
-        let center_value = cdf(0.0);
-        assert_eq!(center_value, 0.5);
-
-        let sigma_top = cdf(1.0); // mu + 1 sigma
-        assert_approx_eq!(sigma_top, 0.8413, 0.0001); // table value for 1.0
-    }
-
     #[test]
     fn european_call() {
         let dp = DerivativeParameter::new(300.0, 250.0, 1.0, 0.03, 0.15);
@@ -103,4 +499,258 @@ mod tests {
             dp.asset_price - dp.strike * (-dp.rfr * dp.time_to_expiration).exp()
         );
     }
+
+    #[test]
+    fn greeks_match_finite_differences() {
+        let dp = DerivativeParameter::new(100.0, 95.0, 0.75, 0.04, 0.25);
+        let computation = BsmComputation::new(&dp);
+        let h = 1e-4;
+        let bumped = |asset_price: f64, time_to_expiration: f64, rfr: f64, vola: f64| {
+            BsmComputation::new(&DerivativeParameter::new(
+                asset_price,
+                dp.strike,
+                time_to_expiration,
+                rfr,
+                vola,
+            ))
+        };
+
+        for exercise_type in [ExerciseType::Call, ExerciseType::Put] {
+            let price = |c: BsmComputation| c.price(&exercise_type);
+            let delta = (price(bumped(100.0 + h, 0.75, 0.04, 0.25))
+                - price(bumped(100.0 - h, 0.75, 0.04, 0.25)))
+                / (2.0 * h);
+            assert_approx_eq!(computation.delta(&exercise_type), delta, 1e-6);
+
+            let vega = (price(bumped(100.0, 0.75, 0.04, 0.25 + h))
+                - price(bumped(100.0, 0.75, 0.04, 0.25 - h)))
+                / (2.0 * h);
+            assert_approx_eq!(computation.vega(), vega, 1e-6);
+
+            let theta = -(price(bumped(100.0, 0.75 + h, 0.04, 0.25))
+                - price(bumped(100.0, 0.75 - h, 0.04, 0.25)))
+                / (2.0 * h);
+            assert_approx_eq!(computation.theta(&exercise_type), theta, 1e-6);
+
+            let rho = (price(bumped(100.0, 0.75, 0.04 + h, 0.25))
+                - price(bumped(100.0, 0.75, 0.04 - h, 0.25)))
+                / (2.0 * h);
+            assert_approx_eq!(computation.rho(&exercise_type), rho, 1e-6);
+        }
+
+        let gamma = (BlackScholesMerton::call(&DerivativeParameter::new(
+            100.0 + h,
+            95.0,
+            0.75,
+            0.04,
+            0.25,
+        )) - 2.0 * computation.call()
+            + BlackScholesMerton::call(&DerivativeParameter::new(
+                100.0 - h,
+                95.0,
+                0.75,
+                0.04,
+                0.25,
+            )))
+            / h.powi(2);
+        assert_approx_eq!(computation.gamma(), gamma, 1e-4);
+    }
+
+    #[test]
+    fn price_results() {
+        let dp = DerivativeParameter::new(100.0, 110.0, 0.5, 0.02, 0.2);
+        let call = BlackScholesMerton::try_call(&dp).unwrap();
+        assert_eq!(call.value, BlackScholesMerton::call(&dp));
+        assert_eq!((call.std_error, call.nr_samples), (None, None));
+        assert!(call.runtime.is_some());
+        assert_eq!(Bachelier::try_put(&dp).unwrap().value, Bachelier::put(&dp));
+        assert_eq!(
+            Black76::try_price(&dp, &ExerciseType::Call).unwrap().value,
+            Black76::call(&dp)
+        );
+        let invalid = DerivativeParameter { vola: -0.2, ..dp };
+        assert!(matches!(
+            BlackScholesMerton::try_call(&invalid),
+            Err(PricingError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn implied_volatility_round_trip() {
+        for vola in [0.05, 0.2, 0.8] {
+            let dp = DerivativeParameter::new(100.0, 110.0, 0.5, 0.02, vola);
+            let call = BlackScholesMerton::call(&dp);
+            let put = BlackScholesMerton::put(&dp);
+            assert_approx_eq!(
+                implied_volatility(call, &dp, &ExerciseType::Call).unwrap(),
+                vola,
+                1e-8
+            );
+            assert_approx_eq!(
+                implied_volatility(put, &dp, &ExerciseType::Put).unwrap(),
+                vola,
+                1e-8
+            );
+        }
+        let dp = DerivativeParameter::new(100.0, 110.0, 0.5, 0.02, 0.2);
+        assert!(implied_volatility(0.0, &dp, &ExerciseType::Call).is_none());
+        assert!(implied_volatility(100.0, &dp, &ExerciseType::Call).is_none());
+        let invalid = DerivativeParameter {
+            rfr: f64::NAN,
+            ..dp
+        };
+        assert!(implied_volatility(10.0, &invalid, &ExerciseType::Call).is_none());
+        // the last iterate of a search without convergence is no implied volatility
+        let call = BlackScholesMerton::call(&DerivativeParameter { vola: 0.6, ..dp });
+        let options = SolverOptions::new(1e-12, 1e-12, 1);
+        assert!(
+            implied_volatility_with_options(call, &dp, &ExerciseType::Call, &options).is_none()
+        );
+    }
+
+    #[test]
+    fn expired_options() {
+        let tree = TrinomialTree::new(10);
+        for (asset_price, call, put) in [(110.0, 10.0, 0.0), (90.0, 0.0, 10.0), (100.0, 0.0, 0.0)] {
+            let dp = DerivativeParameter::new(asset_price, 100.0, 0.0, 0.05, 0.2);
+            assert!(dp.validate().is_ok() && dp.is_expired());
+            let bsm = BsmComputation::new(&dp);
+            assert_eq!((bsm.call(), bsm.put()), (call, put));
+            assert_eq!(tree.price(&dp, &ExerciseType::Call, true), call);
+            assert_eq!(dp.intrinsic_value(&ExerciseType::Put), put);
+        }
+        // without volatility the price is the discounted intrinsic value of the forward
+        let dp = DerivativeParameter::new(100.0, 100.0, 1.0, 0.05, 0.0);
+        assert_approx_eq!(
+            BsmComputation::new(&dp).call(),
+            100.0 - 100.0 * (-0.05_f64).exp(),
+            1e-12
+        );
+        let dp = DerivativeParameter::new(100.0, 100.0, -0.1, 0.05, 0.2);
+        assert!(dp.validate().is_err());
+    }
+
+    #[test]
+    fn undiscounted_prices() {
+        let dp = DerivativeParameter::new(105.0, 100.0, 2.0, 0.03, 0.2);
+        let (forward, disc_factor) = (dp.forward_price(), (-0.06_f64).exp());
+        let bsm = BsmComputation::new(&dp);
+        for exercise_type in [ExerciseType::Call, ExerciseType::Put] {
+            let undiscounted = undiscounted_black_price(forward, 100.0, 2.0, 0.2, &exercise_type);
+            assert_approx_eq!(bsm.undiscounted_price(&exercise_type), undiscounted, 1e-10);
+            assert_approx_eq!(disc_factor * undiscounted, bsm.price(&exercise_type), 1e-10);
+            let on_forward = DerivativeParameter::new(forward, 100.0, 2.0, 0.03, 0.2);
+            assert_approx_eq!(
+                ShiftedBlack76::new(0.0)
+                    .undiscounted_price(&on_forward, &exercise_type)
+                    .unwrap(),
+                undiscounted,
+                1e-10
+            );
+            assert_approx_eq!(
+                implied_volatility_undiscounted(undiscounted, forward, 100.0, 2.0, &exercise_type)
+                    .unwrap(),
+                0.2,
+                1e-8
+            );
+        }
+        // the undiscounted put-call parity $C - P = F - K$
+        assert_approx_eq!(
+            bsm.undiscounted_price(&ExerciseType::Call)
+                - bsm.undiscounted_price(&ExerciseType::Put),
+            forward - 100.0,
+            1e-10
+        );
+        assert_approx_eq!(
+            undiscounted_bachelier_price(0.01, 0.01, 1.0, 0.005, &ExerciseType::Call),
+            0.005 / (2.0 * std::f64::consts::PI).sqrt(),
+            1e-15
+        );
+    }
+
+    #[test]
+    fn shifted_black76() {
+        // the unshifted price is the Black76 price, i.e. the BSM price of the discounted forward
+        let dp = DerivativeParameter::new(105.0, 100.0, 2.0, 0.03, 0.2);
+        let undiscounted =
+            DerivativeParameter::new(105.0 * (-0.06_f64).exp(), 100.0, 2.0, 0.03, 0.2);
+        assert_approx_eq!(
+            Black76::call(&dp),
+            BlackScholesMerton::call(&undiscounted),
+            1e-10
+        );
+
+        // negative forward rates are priced with a shift
+        let shifted = ShiftedBlack76::new(0.02);
+        let dp = DerivativeParameter::new(-0.005, -0.002, 1.0, -0.005, 0.15);
+        let call = shifted.call(&dp).unwrap();
+        let put = shifted.put(&dp).unwrap();
+        assert!(call > 0.0 && put > 0.0);
+        let disc_factor = (0.005_f64).exp();
+        assert_approx_eq!(
+            call - put,
+            disc_factor * (dp.asset_price - dp.strike),
+            1e-15
+        );
+        assert!(Black76::call(&dp).is_nan());
+
+        assert_approx_eq!(
+            black_scholes_price(105.0, 100.0, 2.0, 0.03, 0.2, &ExerciseType::Put),
+            BlackScholesMerton::put(&DerivativeParameter::new(105.0, 100.0, 2.0, 0.03, 0.2)),
+            1e-12
+        );
+
+        let below_shift = DerivativeParameter::new(-0.005, -0.03, 1.0, -0.005, 0.15);
+        assert!(matches!(
+            shifted.put(&below_shift),
+            Err(PricingError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn bachelier() {
+        // at the money, the normal price is $e^{-rT} \sigma \sqrt{T / (2 \pi)}$
+        let dp = DerivativeParameter::new(0.01, 0.01, 4.0, 0.02, 0.008);
+        let atm = (-0.08_f64).exp() * 0.008 * 2.0 / (2.0 * std::f64::consts::PI).sqrt();
+        assert_approx_eq!(Bachelier::call(&dp), atm, 1e-15);
+        assert_approx_eq!(Bachelier::put(&dp), atm, 1e-15);
+
+        // put-call parity of negative forwards and strikes
+        let dp = DerivativeParameter::new(-0.004, 0.002, 1.0, 0.0, 0.006);
+        assert_approx_eq!(
+            Bachelier::call(&dp) - Bachelier::put(&dp),
+            dp.asset_price - dp.strike,
+            1e-15
+        );
+
+        // a vanishing volatility yields the intrinsic value, also at the money
+        let dp = DerivativeParameter::new(0.01, 0.01, 1.0, 0.0, 0.0);
+        assert_eq!(Bachelier::call(&dp), 0.0);
+        assert_eq!(Bachelier::put(&dp), 0.0);
+        let dp = DerivativeParameter::new(0.012, 0.01, 1.0, 0.0, 0.0);
+        assert_approx_eq!(Bachelier::call(&dp), 0.002, 1e-15);
+        assert_eq!(Bachelier::put(&dp), 0.0);
+    }
+
+    #[test]
+    fn generic_price_matches_bsm_computation() {
+        let dp = DerivativeParameter::new(300.0, 250.0, 1.0, 0.03, 0.15);
+        for exercise_type in [ExerciseType::Call, ExerciseType::Put] {
+            assert_eq!(
+                black_scholes_price(
+                    dp.asset_price,
+                    dp.strike,
+                    dp.time_to_expiration,
+                    dp.rfr,
+                    dp.vola,
+                    &exercise_type
+                ),
+                BsmComputation::new(&dp).price(&exercise_type)
+            );
+        }
+
+        // the zero volatility limit is the discounted intrinsic value of the forward
+        let value = black_scholes_price(300.0, 250.0, 1.0, 0.03, 0.0, &ExerciseType::Call);
+        assert_approx_eq!(value, 300.0 - 250.0 * (-0.03_f64).exp(), 1e-12);
+    }
 }
@@ -0,0 +1,134 @@
+/// The predicted P&L over the horizon split into its sources, where each component is the change of
+/// value from revaluing with one more input rolled forward, so the components add up to the total.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CarryReport {
+    /// the passage of time with unchanged spot and volatility
+    pub time_decay: f64,
+    /// the spot rolling to its forward by the rate less the dividend yield
+    pub carry: f64,
+    /// the implied volatility rolling down the term structure to the shorter time to expiration
+    pub vol_roll_down: f64,
+}
+
+impl CarryReport {
+    pub fn total(&self) -> f64 {
+        self.time_decay + self.carry + self.vol_roll_down
+    }
+}
+
+/// Decomposition of the theta of a position over a horizon (one day by default) into time decay,
+/// carry and vol roll-down, for any pricer (analytic or Monte Carlo with common random numbers).
+#[derive(Clone, Copy, Debug)]
+pub struct CarryDecomposition {
+    spot: f64,
+    rfr: f64,
+    dividend_yield: f64,
+    /// the horizon in years
+    horizon: f64,
+}
+
+impl CarryDecomposition {
+    pub fn new(spot: f64, rfr: f64, dividend_yield: f64) -> Self {
+        Self {
+            spot,
+            rfr,
+            dividend_yield,
+            horizon: 1.0 / 365.0,
+        }
+    }
+
+    pub fn with_horizon(self, horizon: f64) -> Self {
+        assert!(horizon > 0.0);
+        Self { horizon, ..self }
+    }
+
+    /// The carry report of a position expiring in `time_to_expiration`, where `vola_at` is the implied
+    /// volatility for a time to expiration (e.g. of a volatility surface at the strike) and
+    /// `pricer(spot, time_to_expiration, vola)` values the position.
+    pub fn decompose(
+        &self,
+        time_to_expiration: f64,
+        vola_at: impl Fn(f64) -> f64,
+        pricer: impl Fn(f64, f64, f64) -> f64,
+    ) -> CarryReport {
+        assert!(self.horizon < time_to_expiration);
+        let rolled_time = time_to_expiration - self.horizon;
+        let forward_spot = self.spot * ((self.rfr - self.dividend_yield) * self.horizon).exp();
+        let vola = vola_at(time_to_expiration);
+
+        let value = pricer(self.spot, time_to_expiration, vola);
+        let value_decayed = pricer(self.spot, rolled_time, vola);
+        let value_carried = pricer(forward_spot, rolled_time, vola);
+        let value_rolled = pricer(forward_spot, rolled_time, vola_at(rolled_time));
+
+        CarryReport {
+            time_decay: value_decayed - value,
+            carry: value_carried - value_decayed,
+            vol_roll_down: value_rolled - value_carried,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::BsmComputation;
+    use crate::common::models::{DerivativeParameter, ExerciseType};
+    use crate::common::vol_surface::VolatilitySurface;
+    use assert_approx_eq::assert_approx_eq;
+    use ndarray::arr2;
+
+    const STRIKE: f64 = 105.0;
+    const RFR: f64 = 0.03;
+
+    fn call(spot: f64, time_to_expiration: f64, vola: f64) -> f64 {
+        BsmComputation::new(&DerivativeParameter::new(
+            spot,
+            STRIKE,
+            time_to_expiration,
+            RFR,
+            vola,
+        ))
+        .call()
+    }
+
+    #[test]
+    fn flat_vol_matches_theta_and_delta_carry() {
+        let (spot, t, vola) = (100.0, 0.5, 0.2);
+        let decomposition = CarryDecomposition::new(spot, RFR, 0.0);
+        let report = decomposition.decompose(t, |_| vola, call);
+
+        let bsm = BsmComputation::new(&DerivativeParameter::new(spot, STRIKE, t, RFR, vola));
+        let dt = 1.0 / 365.0;
+        assert_eq!(report.vol_roll_down, 0.0);
+        assert!(report.time_decay < 0.0 && report.carry > 0.0);
+        assert_approx_eq!(
+            report.time_decay + report.carry,
+            (bsm.theta(&ExerciseType::Call) + RFR * spot * bsm.delta(&ExerciseType::Call)) * dt,
+            1e-4
+        );
+    }
+
+    #[test]
+    fn vol_roll_down_on_term_structure() {
+        let surface = VolatilitySurface::new(
+            vec![0.25, 0.5, 1.0],
+            vec![100.0],
+            arr2(&[[0.3], [0.25], [0.2]]),
+        );
+        let decomposition = CarryDecomposition::new(100.0, RFR, 0.01).with_horizon(0.1);
+        let report = decomposition.decompose(1.0, |t| surface.effective_vola(t, STRIKE), call);
+
+        // rolling to shorter maturities with higher implied volatility gains value for a long option
+        assert!(report.vol_roll_down > 0.0);
+        assert_approx_eq!(
+            report.total(),
+            call(
+                100.0 * (0.02_f64 * 0.1).exp(),
+                0.9,
+                surface.effective_vola(0.9, STRIKE)
+            ) - call(100.0, 1.0, surface.effective_vola(1.0, STRIKE)),
+            1e-12
+        );
+    }
+}
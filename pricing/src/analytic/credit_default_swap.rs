@@ -0,0 +1,240 @@
+use crate::common::results::PricingError;
+use crate::curves::cashflows::payment_schedule;
+use crate::curves::hazard_rate::{PiecewiseHazardCurve, SurvivalCurve};
+use crate::curves::YieldCurve;
+use crate::numerics::solvers::{brent, SolverOptions};
+
+/// The number of integration steps per premium period for the protection leg.
+const PROTECTION_STEPS_PER_PERIOD: usize = 20;
+
+/// Credit default swap per unit notional, paying the running `spread` (annualized)
+/// `payments_per_year` times until default or maturity, against the loss $1 - R$ at default.
+/// See https://en.wikipedia.org/wiki/Credit_default_swap
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CreditDefaultSwap {
+    /// the maturity in years
+    pub maturity: f64,
+    pub spread: f64,
+    pub recovery_rate: f64,
+    pub payments_per_year: usize,
+}
+
+impl CreditDefaultSwap {
+    /// The CDS, or the invalid parameter: a non-positive or non-finite maturity, no payments per year,
+    /// a non-finite spread, or a recovery rate outside of [0, 1).
+    pub fn try_new(
+        maturity: f64,
+        spread: f64,
+        recovery_rate: f64,
+        payments_per_year: usize,
+    ) -> Result<Self, PricingError> {
+        PricingError::check(maturity > 0.0 && maturity.is_finite(), "maturity", maturity)?;
+        PricingError::check(
+            payments_per_year > 0,
+            "payments per year",
+            payments_per_year as f64,
+        )?;
+        PricingError::check(spread.is_finite(), "spread", spread)?;
+        PricingError::check(
+            (0.0..1.0).contains(&recovery_rate),
+            "recovery rate",
+            recovery_rate,
+        )?;
+        Ok(Self {
+            maturity,
+            spread,
+            recovery_rate,
+            payments_per_year,
+        })
+    }
+
+    /// The premium payment times, with a short first period if the maturity is not a multiple of the period.
+    pub fn payment_times(&self) -> Vec<f64> {
+        payment_schedule(self.maturity, self.payments_per_year)
+    }
+
+    /// The risky annuity (risky PV01 per unit spread), including the premium accrued until default,
+    /// which is approximated by half a period.
+    pub fn risky_annuity(
+        &self,
+        discount_curve: &impl YieldCurve,
+        survival_curve: &impl SurvivalCurve,
+    ) -> f64 {
+        let mut prev = 0.0;
+        let mut annuity = 0.0;
+        for t in self.payment_times() {
+            let accrual = t - prev;
+            let survival = survival_curve.survival_probability(t);
+            let default = survival_curve.default_probability(prev, t);
+            annuity += accrual * discount_curve.discount_factor(t) * (survival + 0.5 * default);
+            prev = t;
+        }
+        annuity
+    }
+
+    /// The value of the premium leg.
+    pub fn premium_leg(
+        &self,
+        discount_curve: &impl YieldCurve,
+        survival_curve: &impl SurvivalCurve,
+    ) -> f64 {
+        self.spread * self.risky_annuity(discount_curve, survival_curve)
+    }
+
+    /// The value of the protection leg $(1 - R) \int_0^T P(0, t) dQ(t)$,
+    /// integrated by the midpoint rule on a sub-grid of the premium periods.
+    pub fn protection_leg(
+        &self,
+        discount_curve: &impl YieldCurve,
+        survival_curve: &impl SurvivalCurve,
+    ) -> f64 {
+        let mut prev = 0.0;
+        let mut protection = 0.0;
+        for t in self.payment_times() {
+            let dt = (t - prev) / PROTECTION_STEPS_PER_PERIOD as f64;
+            for step in 0..PROTECTION_STEPS_PER_PERIOD {
+                let start = prev + step as f64 * dt;
+                protection += discount_curve.discount_factor(start + dt / 2.0)
+                    * survival_curve.default_probability(start, start + dt);
+            }
+            prev = t;
+        }
+        (1.0 - self.recovery_rate) * protection
+    }
+
+    /// The value for the protection buyer.
+    pub fn value(
+        &self,
+        discount_curve: &impl YieldCurve,
+        survival_curve: &impl SurvivalCurve,
+    ) -> f64 {
+        self.protection_leg(discount_curve, survival_curve)
+            - self.premium_leg(discount_curve, survival_curve)
+    }
+
+    /// The spread for which the CDS has zero value.
+    pub fn par_spread(
+        &self,
+        discount_curve: &impl YieldCurve,
+        survival_curve: &impl SurvivalCurve,
+    ) -> f64 {
+        self.protection_leg(discount_curve, survival_curve)
+            / self.risky_annuity(discount_curve, survival_curve)
+    }
+}
+
+/// Bootstraps piecewise constant hazard rates from par CDS quotes with increasing maturities,
+/// such that each CDS reprices to zero. Returns None if a quote can not be matched by a
+/// non-negative hazard rate, e.g. for inconsistent spreads.
+pub fn bootstrap_hazard_curve(
+    quotes: &[CreditDefaultSwap],
+    discount_curve: &impl YieldCurve,
+) -> Option<PiecewiseHazardCurve> {
+    const MAX_HAZARD_RATE: f64 = 10.0;
+
+    if quotes.is_empty() || quotes.windows(2).any(|w| w[0].maturity >= w[1].maturity) {
+        return None;
+    }
+
+    let options = SolverOptions::new(1e-14, 0.0, 200);
+    let mut times = Vec::with_capacity(quotes.len());
+    let mut hazard_rates: Vec<f64> = Vec::with_capacity(quotes.len());
+    for quote in quotes {
+        times.push(quote.maturity);
+        let curve_with = |hazard_rate: f64| {
+            let mut rates = hazard_rates.clone();
+            rates.push(hazard_rate);
+            PiecewiseHazardCurve::new(times.clone(), rates)
+        };
+        // the value for the protection buyer is increasing in the hazard rate
+        let value = |hazard_rate: f64| quote.value(discount_curve, &curve_with(hazard_rate));
+
+        hazard_rates.push(brent(value, 0.0, MAX_HAZARD_RATE, &options)?.x);
+    }
+    Some(PiecewiseHazardCurve::new(times, hazard_rates))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curves::yield_curve::FlatCurve;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn payment_schedule() {
+        let cds = CreditDefaultSwap::try_new(1.1, 0.01, 0.4, 4).unwrap();
+        let times = cds.payment_times();
+        assert_eq!(times.len(), 5);
+        assert_approx_eq!(times[0], 0.1, 1e-12);
+        assert_eq!(*times.last().unwrap(), 1.1);
+        assert_eq!(
+            CreditDefaultSwap::try_new(1.0, 0.01, 0.4, 4)
+                .unwrap()
+                .payment_times()
+                .len(),
+            4
+        );
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        for (maturity, spread, recovery_rate, payments_per_year) in [
+            (0.0, 0.01, 0.4, 4),
+            (f64::NAN, 0.01, 0.4, 4),
+            (1.0, f64::INFINITY, 0.4, 4),
+            (1.0, 0.01, 1.0, 4),
+            (1.0, 0.01, -0.1, 4),
+            (1.0, 0.01, 0.4, 0),
+        ] {
+            assert!(matches!(
+                CreditDefaultSwap::try_new(maturity, spread, recovery_rate, payments_per_year),
+                Err(PricingError::InvalidParameter(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn credit_triangle() {
+        // for flat hazard rates, the par spread is approximately (1 - R) * lambda
+        let discount_curve = FlatCurve::new(0.03);
+        let survival_curve = PiecewiseHazardCurve::flat(0.02);
+        let cds = CreditDefaultSwap::try_new(5.0, 0.0, 0.4, 4).unwrap();
+        assert_approx_eq!(
+            cds.par_spread(&discount_curve, &survival_curve),
+            0.6 * 0.02,
+            1e-4
+        );
+    }
+
+    #[test]
+    fn bootstrap_reprices_quotes() {
+        let discount_curve = FlatCurve::new(0.02);
+        let quotes: Vec<CreditDefaultSwap> = [(1.0, 0.005), (3.0, 0.008), (5.0, 0.012)]
+            .iter()
+            .map(|(maturity, spread)| {
+                CreditDefaultSwap::try_new(*maturity, *spread, 0.4, 4).unwrap()
+            })
+            .collect();
+
+        let hazard_curve = bootstrap_hazard_curve(&quotes, &discount_curve).unwrap();
+        assert_eq!(hazard_curve.times(), &[1.0, 3.0, 5.0]);
+        for quote in quotes.iter() {
+            assert_approx_eq!(quote.value(&discount_curve, &hazard_curve), 0.0, 1e-12);
+            assert_approx_eq!(
+                quote.par_spread(&discount_curve, &hazard_curve),
+                quote.spread,
+                1e-10
+            );
+        }
+        // increasing spreads imply increasing hazard rates
+        let rates = hazard_curve.hazard_rates();
+        assert!(rates[0] < rates[1] && rates[1] < rates[2]);
+
+        // a steeply inverted spread curve requires negative hazard rates
+        let inverted = [
+            CreditDefaultSwap::try_new(1.0, 0.05, 0.4, 4).unwrap(),
+            CreditDefaultSwap::try_new(5.0, 0.001, 0.4, 4).unwrap(),
+        ];
+        assert!(bootstrap_hazard_curve(&inverted, &discount_curve).is_none());
+    }
+}
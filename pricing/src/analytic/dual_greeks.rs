@@ -0,0 +1,253 @@
+use crate::analytic::black_scholes::{
+    bachelier_price, black76_price, black_scholes_price, ShiftedBlack76,
+};
+use crate::common::models::{DerivativeParameter, ExerciseType};
+use crate::common::results::{GreeksResult, PricingError};
+use crate::numerics::dual::{Dual, Scalar};
+
+/// The parameters of the analytic formulas to differentiate by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Parameter {
+    AssetPrice,
+    Strike,
+    TimeToExpiration,
+    Rfr,
+    Vola,
+}
+
+impl Parameter {
+    const ALL: [Parameter; 5] = [
+        Parameter::AssetPrice,
+        Parameter::Strike,
+        Parameter::TimeToExpiration,
+        Parameter::Rfr,
+        Parameter::Vola,
+    ];
+
+    fn value(&self, dp: &DerivativeParameter) -> f64 {
+        match self {
+            Parameter::AssetPrice => dp.asset_price,
+            Parameter::Strike => dp.strike,
+            Parameter::TimeToExpiration => dp.time_to_expiration,
+            Parameter::Rfr => dp.rfr,
+            Parameter::Vola => dp.vola,
+        }
+    }
+}
+
+/// The analytic European option formulas, evaluated with (nested) dual numbers for exact derivatives
+/// of any order instead of finite differences or hand-derived greeks.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AnalyticFormula {
+    BlackScholesMerton,
+    /// Black76 of the forward shifted by `shift`, unshifted for 0
+    Black76 {
+        shift: f64,
+    },
+    /// the normal model with the absolute vola
+    Bachelier,
+}
+
+impl AnalyticFormula {
+    /// Checks the parameters for positive times and volas, and positive (shifted) forwards and strikes of
+    /// the lognormal formulas.
+    pub fn validate(&self, dp: &DerivativeParameter) -> Result<(), PricingError> {
+        PricingError::check(dp.vola > 0.0, "vola", dp.vola)?;
+        match self {
+            AnalyticFormula::BlackScholesMerton => ShiftedBlack76::new(0.0).validate(dp),
+            AnalyticFormula::Black76 { shift } => ShiftedBlack76::new(*shift).validate(dp),
+            AnalyticFormula::Bachelier => {
+                PricingError::check(
+                    dp.time_to_expiration > 0.0 && dp.time_to_expiration.is_finite(),
+                    "time to expiration",
+                    dp.time_to_expiration,
+                )?;
+                PricingError::check(dp.rfr.is_finite(), "rfr", dp.rfr)
+            }
+        }
+    }
+
+    /// The price of the parameters in the order of `Parameter`.
+    pub fn price<T: Scalar>(&self, parameters: [T; 5], exercise_type: &ExerciseType) -> T {
+        let [asset_price, strike, time_to_expiration, rfr, vola] = parameters;
+        match self {
+            AnalyticFormula::BlackScholesMerton => black_scholes_price(
+                asset_price,
+                strike,
+                time_to_expiration,
+                rfr,
+                vola,
+                exercise_type,
+            ),
+            AnalyticFormula::Black76 { shift } => black76_price(
+                asset_price + T::constant(*shift),
+                strike + T::constant(*shift),
+                time_to_expiration,
+                rfr,
+                vola,
+                exercise_type,
+            ),
+            AnalyticFormula::Bachelier => bachelier_price(
+                asset_price,
+                strike,
+                time_to_expiration,
+                rfr,
+                vola,
+                exercise_type,
+            ),
+        }
+    }
+
+    /// The exact first derivative of the price with respect to the parameter.
+    pub fn derivative(
+        &self,
+        dp: &DerivativeParameter,
+        exercise_type: &ExerciseType,
+        parameter: Parameter,
+    ) -> Result<f64, PricingError> {
+        self.validate(dp)?;
+        let parameters =
+            Parameter::ALL.map(|p| Dual::new(p.value(dp), if p == parameter { 1.0 } else { 0.0 }));
+        Ok(self.price(parameters, exercise_type).derivative)
+    }
+
+    /// The exact second derivative of the price with respect to both parameters, e.g. the gamma for the
+    /// asset price twice, the vanna for the asset price and the vola, or the volga for the vola twice.
+    pub fn second_derivative(
+        &self,
+        dp: &DerivativeParameter,
+        exercise_type: &ExerciseType,
+        first: Parameter,
+        second: Parameter,
+    ) -> Result<f64, PricingError> {
+        self.validate(dp)?;
+        let unit = |is_variable: bool| if is_variable { 1.0 } else { 0.0 };
+        let parameters = Parameter::ALL.map(|p| {
+            Dual::new(
+                Dual::new(p.value(dp), unit(p == first)),
+                Dual::new(unit(p == second), 0.0),
+            )
+        });
+        Ok(self.price(parameters, exercise_type).derivative.derivative)
+    }
+
+    /// The price with the exact greeks, where the theta is the derivative with respect to the calendar
+    /// time, i.e. the negative derivative with respect to the time to expiration.
+    pub fn greeks(
+        &self,
+        dp: &DerivativeParameter,
+        exercise_type: &ExerciseType,
+    ) -> Result<GreeksResult, PricingError> {
+        self.validate(dp)?;
+        let value = self.price(Parameter::ALL.map(|p| p.value(dp)), exercise_type);
+        let derivative = |parameter| self.derivative(dp, exercise_type, parameter);
+        Ok(GreeksResult {
+            value,
+            delta: Some(derivative(Parameter::AssetPrice)?),
+            gamma: Some(self.second_derivative(
+                dp,
+                exercise_type,
+                Parameter::AssetPrice,
+                Parameter::AssetPrice,
+            )?),
+            vega: Some(derivative(Parameter::Vola)?),
+            theta: Some(-derivative(Parameter::TimeToExpiration)?),
+            rho: Some(derivative(Parameter::Rfr)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::{Bachelier, BsmComputation, OptionPrice};
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn black_scholes_greeks_match_closed_form() {
+        let dp = DerivativeParameter::new(105.0, 100.0, 1.5, 0.03, 0.25);
+        for exercise_type in [ExerciseType::Call, ExerciseType::Put] {
+            let dual = AnalyticFormula::BlackScholesMerton
+                .greeks(&dp, &exercise_type)
+                .unwrap();
+            let closed_form = BsmComputation::new(&dp).greeks(&exercise_type);
+            assert_approx_eq!(dual.value, closed_form.value, 1e-10);
+            assert_approx_eq!(dual.delta.unwrap(), closed_form.delta.unwrap(), 1e-12);
+            assert_approx_eq!(dual.gamma.unwrap(), closed_form.gamma.unwrap(), 1e-12);
+            assert_approx_eq!(dual.vega.unwrap(), closed_form.vega.unwrap(), 1e-10);
+            assert_approx_eq!(dual.theta.unwrap(), closed_form.theta.unwrap(), 1e-10);
+            assert_approx_eq!(dual.rho.unwrap(), closed_form.rho.unwrap(), 1e-10);
+        }
+        let invalid = DerivativeParameter::new(105.0, 100.0, 1.5, 0.03, 0.0);
+        assert!(AnalyticFormula::BlackScholesMerton
+            .greeks(&invalid, &ExerciseType::Call)
+            .is_err());
+    }
+
+    #[test]
+    fn second_order_greeks() {
+        // the vanna $-e^{-qT} n(d_1) d_2 / \sigma$ and the volga $S n(d_1) \sqrt{T} d_1 d_2 / \sigma$
+        let dp = DerivativeParameter::new(95.0, 100.0, 0.75, 0.01, 0.3);
+        let computation = BsmComputation::new(&dp);
+        let pdf_d1 =
+            (-0.5 * computation.d1 * computation.d1).exp() / (2.0 * std::f64::consts::PI).sqrt();
+        let formula = AnalyticFormula::BlackScholesMerton;
+        let vanna = formula
+            .second_derivative(
+                &dp,
+                &ExerciseType::Call,
+                Parameter::AssetPrice,
+                Parameter::Vola,
+            )
+            .unwrap();
+        assert_approx_eq!(vanna, -pdf_d1 * computation.d2 / dp.vola, 1e-12);
+        let volga = formula
+            .second_derivative(&dp, &ExerciseType::Put, Parameter::Vola, Parameter::Vola)
+            .unwrap();
+        let exact = dp.asset_price
+            * pdf_d1
+            * dp.time_to_expiration.sqrt()
+            * computation.d1
+            * computation.d2
+            / dp.vola;
+        assert_approx_eq!(volga, exact, 1e-10);
+        // the mixed derivatives are symmetric
+        let volga_vanna = formula
+            .second_derivative(
+                &dp,
+                &ExerciseType::Call,
+                Parameter::Vola,
+                Parameter::AssetPrice,
+            )
+            .unwrap();
+        assert_approx_eq!(volga_vanna, vanna, 1e-12);
+    }
+
+    #[test]
+    fn bachelier_and_black76_greeks() {
+        // the normal delta of the undiscounted at-the-money call is 1/2 and its vega $\sqrt{T / (2 \pi)}$
+        let dp = DerivativeParameter::new(0.01, 0.01, 2.0, 0.0, 0.007);
+        let greeks = AnalyticFormula::Bachelier
+            .greeks(&dp, &ExerciseType::Call)
+            .unwrap();
+        assert_approx_eq!(greeks.value, Bachelier::call(&dp), 1e-15);
+        assert_approx_eq!(greeks.delta.unwrap(), 0.5, 1e-15);
+        assert_approx_eq!(
+            greeks.vega.unwrap(),
+            (2.0 / (2.0 * std::f64::consts::PI)).sqrt(),
+            1e-12
+        );
+
+        // the shifted Black76 delta is the BSM delta of the shifted, discounted forward
+        let dp = DerivativeParameter::new(-0.002, 0.001, 1.0, 0.01, 0.2);
+        let shifted = AnalyticFormula::Black76 { shift: 0.02 }
+            .greeks(&dp, &ExerciseType::Put)
+            .unwrap();
+        let discounted = DerivativeParameter::new(0.018 * (-0.01_f64).exp(), 0.021, 1.0, 0.01, 0.2);
+        let bsm_delta = BsmComputation::new(&discounted).delta(&ExerciseType::Put);
+        assert_approx_eq!(shifted.delta.unwrap(), bsm_delta * (-0.01_f64).exp(), 1e-12);
+        assert!(AnalyticFormula::Black76 { shift: 0.0 }
+            .greeks(&dp, &ExerciseType::Put)
+            .is_err());
+    }
+}
@@ -0,0 +1,172 @@
+use ndarray::{s, Array1, Array2};
+
+use crate::common::results::PricingError;
+use crate::numerics::least_squares::solve_linear_system;
+
+/// The minimum-variance hedge of a position by one or more hedge instruments, e.g. futures or options,
+/// where the hedged P&L is the position's P&L plus $\sum_i h_i$ times the P&L of hedge instrument i
+/// (per unit notional). The ratios $h = -\Sigma_{HH}^{-1} \sigma_{HP}$ minimize the variance of the hedged P&L,
+/// e.g. $h = -\rho \sigma_P / \sigma_H$ for a single hedge.
+/// See https://en.wikipedia.org/wiki/Hedge_(finance)#Minimum_variance_hedge_ratio
+#[derive(Clone, Debug, PartialEq)]
+pub struct MinimumVarianceHedge {
+    /// the notionals of the hedge instruments, negative for short positions
+    pub hedge_notionals: Vec<f64>,
+    /// the standard deviation of the unhedged P&L
+    pub position_risk: f64,
+    /// the standard deviation of the hedged P&L
+    pub residual_risk: f64,
+}
+
+impl MinimumVarianceHedge {
+    /// The hedge from the covariance matrix of the P&Ls of the position (first) and the hedge instruments.
+    pub fn from_covariance(covariance: &Array2<f64>) -> Result<Self, PricingError> {
+        let dim = covariance.nrows();
+        PricingError::check(
+            dim >= 2 && covariance.ncols() == dim,
+            "covariance dimension",
+            dim as f64,
+        )?;
+        let position_variance = covariance[[0, 0]];
+        let hedge_covariance = covariance.slice(s![1.., 1..]).to_owned();
+        let cross_covariance = covariance.slice(s![1.., 0]).to_owned();
+        let ratios =
+            solve_linear_system(&hedge_covariance, &cross_covariance).ok_or_else(|| {
+                PricingError::InvalidParameter("singular covariance of the hedges".to_string())
+            })?;
+        // the variance reduction is $\sigma_{HP}^T \Sigma_{HH}^{-1} \sigma_{HP}$
+        let residual_variance = position_variance - cross_covariance.dot(&ratios);
+        Ok(Self {
+            hedge_notionals: ratios.iter().map(|h| -h).collect(),
+            position_risk: position_variance.sqrt(),
+            residual_risk: residual_variance.max(0.0).sqrt(),
+        })
+    }
+
+    /// The hedge from simulated or historical joint P&L samples of the position and the hedge instruments.
+    pub fn from_pnl(position_pnl: &[f64], hedge_pnls: &[Vec<f64>]) -> Result<Self, PricingError> {
+        let nr_samples = position_pnl.len();
+        PricingError::check(nr_samples >= 2, "nr samples", nr_samples as f64)?;
+        for pnl in hedge_pnls {
+            PricingError::check(
+                pnl.len() == nr_samples,
+                "nr hedge samples",
+                pnl.len() as f64,
+            )?;
+        }
+        let series: Vec<&[f64]> = std::iter::once(position_pnl)
+            .chain(hedge_pnls.iter().map(|pnl| pnl.as_slice()))
+            .collect();
+        let means: Array1<f64> = series
+            .iter()
+            .map(|pnl| pnl.iter().sum::<f64>() / nr_samples as f64)
+            .collect();
+        let covariance = Array2::from_shape_fn((series.len(), series.len()), |(i, j)| {
+            series[i]
+                .iter()
+                .zip(series[j])
+                .map(|(x, y)| (x - means[i]) * (y - means[j]))
+                .sum::<f64>()
+                / (nr_samples - 1) as f64
+        });
+        Self::from_covariance(&covariance)
+    }
+
+    /// The fraction of the P&L variance removed by the hedge.
+    pub fn effectiveness(&self) -> f64 {
+        1.0 - (self.residual_risk / self.position_risk).powi(2)
+    }
+
+    /// The P&L samples of the hedged position.
+    pub fn hedged_pnl(&self, position_pnl: &[f64], hedge_pnls: &[Vec<f64>]) -> Vec<f64> {
+        (0..position_pnl.len())
+            .map(|k| {
+                position_pnl[k]
+                    + self
+                        .hedge_notionals
+                        .iter()
+                        .zip(hedge_pnls)
+                        .map(|(notional, pnl)| notional * pnl[k])
+                        .sum::<f64>()
+            })
+            .collect()
+    }
+
+    /// The loss distribution of the hedged P&L samples for the VaR and ES of the residual risk.
+    #[cfg(feature = "risk-integration")]
+    pub fn residual_losses(
+        &self,
+        position_pnl: &[f64],
+        hedge_pnls: &[Vec<f64>],
+    ) -> Result<risk::value_at_risk::EmpiricalLosses, PricingError> {
+        Ok(risk::value_at_risk::EmpiricalLosses::from_pnl(
+            &self.hedged_pnl(position_pnl, hedge_pnls),
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::{BlackScholesMerton, BsmComputation, OptionPrice};
+    use crate::common::models::{DerivativeParameter, ExerciseType};
+    use assert_approx_eq::assert_approx_eq;
+    use ndarray::arr2;
+
+    #[test]
+    fn futures_hedge_from_covariance() {
+        // spot vola 20%, futures vola 25% and correlation 0.9
+        let (sigma_s, sigma_f, rho) = (0.2, 0.25, 0.9);
+        let covariance = arr2(&[
+            [sigma_s * sigma_s, rho * sigma_s * sigma_f],
+            [rho * sigma_s * sigma_f, sigma_f * sigma_f],
+        ]);
+        let hedge = MinimumVarianceHedge::from_covariance(&covariance).unwrap();
+        assert_approx_eq!(hedge.hedge_notionals[0], -rho * sigma_s / sigma_f, 1e-15);
+        assert_approx_eq!(
+            hedge.residual_risk,
+            sigma_s * (1.0 - rho * rho).sqrt(),
+            1e-15
+        );
+        assert_approx_eq!(hedge.effectiveness(), rho * rho, 1e-12);
+
+        let singular = arr2(&[[1.0, 0.5, 0.5], [0.5, 1.0, 1.0], [0.5, 1.0, 1.0]]);
+        assert!(MinimumVarianceHedge::from_covariance(&singular).is_err());
+    }
+
+    #[test]
+    fn option_hedge_from_simulated_pnl() {
+        // the minimum-variance hedge of a short call over a day is about the delta in the underlying
+        let dp = DerivativeParameter::new(100.0, 100.0, 0.5, 0.0, 0.2);
+        let dt: f64 = 1.0 / 252.0;
+        let moves: Vec<f64> = (0..201).map(|k| (k as f64 - 100.0) / 100.0).collect();
+        let spots: Vec<f64> = moves
+            .iter()
+            .map(|z| 100.0 * (0.2 * dt.sqrt() * z).exp())
+            .collect();
+        let call = BlackScholesMerton::call(&dp);
+        let position_pnl: Vec<f64> = spots
+            .iter()
+            .map(|s| {
+                let shifted = DerivativeParameter::new(*s, 100.0, 0.5 - dt, 0.0, 0.2);
+                call - BlackScholesMerton::call(&shifted)
+            })
+            .collect();
+        let hedge_pnl = vec![spots.iter().map(|s| s - 100.0).collect::<Vec<f64>>()];
+        let hedge = MinimumVarianceHedge::from_pnl(&position_pnl, &hedge_pnl).unwrap();
+        let delta = BsmComputation::new(&dp).delta(&ExerciseType::Call);
+        assert_approx_eq!(hedge.hedge_notionals[0], delta, 0.01);
+        assert!(hedge.effectiveness() > 0.99);
+
+        let hedged = hedge.hedged_pnl(&position_pnl, &hedge_pnl);
+        let hedged_risk = MinimumVarianceHedge::from_pnl(&hedged, &hedge_pnl).unwrap();
+        assert_approx_eq!(hedged_risk.position_risk, hedge.residual_risk, 1e-12);
+        #[cfg(feature = "risk-integration")]
+        {
+            let residual = hedge.residual_losses(&position_pnl, &hedge_pnl).unwrap();
+            let unhedged = risk::value_at_risk::EmpiricalLosses::from_pnl(&position_pnl).unwrap();
+            assert!(residual.value_at_risk(0.99).unwrap() < unhedged.value_at_risk(0.99).unwrap());
+        }
+        assert!(MinimumVarianceHedge::from_pnl(&position_pnl, &[vec![0.0; 3]]).is_err());
+    }
+}
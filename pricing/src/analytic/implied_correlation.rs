@@ -0,0 +1,288 @@
+use std::cell::Cell;
+
+use ndarray::Array1;
+
+use crate::analytic::black_scholes::BsmComputation;
+use crate::common::models::{DerivativeParameter, ExerciseType};
+use crate::common::results::PricingError;
+use crate::numerics::solvers::{brent, SolverOptions};
+
+/// The undiscounted Black price of an option on the forward.
+fn black_forward_price(
+    forward: f64,
+    strike: f64,
+    time_to_expiration: f64,
+    vola: f64,
+    exercise_type: &ExerciseType,
+) -> f64 {
+    BsmComputation::new(&DerivativeParameter::new(
+        forward,
+        strike,
+        time_to_expiration,
+        0.0,
+        vola,
+    ))
+    .price(exercise_type)
+}
+
+/// European basket option on the weighted sum of assets with a flat correlation, approximated by
+/// matching the first two moments of the basket with a lognormal (Levy's approximation):
+/// '''math
+/// M_1 = \sum_i w_i F_i, \quad M_2 = \sum_{i,j} w_i w_j F_i F_j e^{\rho_{ij} \sigma_i \sigma_j T},
+/// \quad \sigma_B^2 = \frac{1}{T} \ln \frac{M_2}{M_1^2}
+/// '''
+/// for the forwards $F_i$ and positive weights.
+#[derive(Clone, Debug)]
+pub struct BasketApproximation {
+    pub weights: Array1<f64>,
+    pub asset_prices: Array1<f64>,
+    pub volas: Array1<f64>,
+    pub rfr: f64,
+    pub time_to_expiration: f64,
+}
+
+impl BasketApproximation {
+    /// The approximation of the basket, or the reason the inputs do not describe one.
+    pub fn new(
+        weights: Array1<f64>,
+        asset_prices: Array1<f64>,
+        volas: Array1<f64>,
+        rfr: f64,
+        time_to_expiration: f64,
+    ) -> Result<Self, PricingError> {
+        if weights.is_empty() || weights.len() != asset_prices.len() || weights.len() != volas.len()
+        {
+            return Err(PricingError::InvalidParameter(format!(
+                "{} weights, {} asset prices and {} volatilities of the basket",
+                weights.len(),
+                asset_prices.len(),
+                volas.len()
+            )));
+        }
+        for weight in &weights {
+            PricingError::check(*weight > 0.0, "weight", *weight)?;
+        }
+        for asset_price in &asset_prices {
+            PricingError::check(*asset_price > 0.0, "asset price", *asset_price)?;
+        }
+        for vola in &volas {
+            PricingError::check(vola.is_finite() && *vola >= 0.0, "volatility", *vola)?;
+        }
+        PricingError::check(rfr.is_finite(), "risk-free rate", rfr)?;
+        PricingError::check(
+            time_to_expiration.is_finite() && time_to_expiration > 0.0,
+            "time to expiration",
+            time_to_expiration,
+        )?;
+        Ok(Self {
+            weights,
+            asset_prices,
+            volas,
+            rfr,
+            time_to_expiration,
+        })
+    }
+
+    /// The lowest flat correlation of a valid (positive semi-definite) correlation matrix.
+    pub fn min_correlation(&self) -> f64 {
+        -1.0 / (self.weights.len().max(2) - 1) as f64
+    }
+
+    pub fn price(&self, strike: f64, correlation: f64, exercise_type: &ExerciseType) -> f64 {
+        let t = self.time_to_expiration;
+        let growth = (self.rfr * t).exp();
+        let weighted_forwards = &self.weights * &self.asset_prices * growth;
+        let m1 = weighted_forwards.sum();
+        let mut m2 = 0.0;
+        for i in 0..weighted_forwards.len() {
+            for j in 0..weighted_forwards.len() {
+                let rho = if i == j { 1.0 } else { correlation };
+                m2 += weighted_forwards[i]
+                    * weighted_forwards[j]
+                    * (rho * self.volas[i] * self.volas[j] * t).exp();
+            }
+        }
+        let basket_vola = ((m2 / (m1 * m1)).ln() / t).sqrt();
+        black_forward_price(m1, strike, t, basket_vola, exercise_type) / growth
+    }
+}
+
+/// European spread option on $S_1 - S_2$ by Kirk's approximation, treating $F_2 + K$ as lognormal:
+/// '''math
+/// \sigma^2 = \sigma_1^2 - 2 \rho \sigma_1 \sigma_2 \frac{F_2}{F_2 + K} + \sigma_2^2 \left(\frac{F_2}{F_2 + K}\right)^2
+/// '''
+/// See https://en.wikipedia.org/wiki/Spread_option
+#[derive(Clone, Copy, Debug)]
+pub struct SpreadApproximation {
+    pub asset_prices: (f64, f64),
+    pub volas: (f64, f64),
+    pub rfr: f64,
+    pub time_to_expiration: f64,
+}
+
+impl SpreadApproximation {
+    pub fn new(
+        asset_prices: (f64, f64),
+        volas: (f64, f64),
+        rfr: f64,
+        time_to_expiration: f64,
+    ) -> Self {
+        Self {
+            asset_prices,
+            volas,
+            rfr,
+            time_to_expiration,
+        }
+    }
+
+    /// The call price for non-negative strikes.
+    pub fn call(&self, strike: f64, correlation: f64) -> f64 {
+        let t = self.time_to_expiration;
+        let growth = (self.rfr * t).exp();
+        let (f1, f2) = (self.asset_prices.0 * growth, self.asset_prices.1 * growth);
+        let (vola1, vola2) = self.volas;
+        let ratio = f2 / (f2 + strike);
+        let vola = (vola1 * vola1 - 2.0 * correlation * vola1 * vola2 * ratio
+            + (vola2 * ratio).powi(2))
+        .sqrt();
+        black_forward_price(f1, f2 + strike, t, vola, &ExerciseType::Call) / growth
+    }
+}
+
+/// The flat correlation in `[lower_bound, upper_bound]` for which `pricer` reproduces the market `price`,
+/// by Brent's method for pricers monotone in the correlation (increasing for basket calls and puts,
+/// decreasing for spread calls). Pricers may be approximations or Monte Carlo pricers with fixed seeds.
+/// Returns None if the price is not attained within the bounds or the pricer fails.
+pub fn implied_correlation(
+    price: f64,
+    lower_bound: f64,
+    upper_bound: f64,
+    pricer: impl Fn(f64) -> Option<f64>,
+) -> Option<f64> {
+    let options = SolverOptions::new(1e-10, 1e-12, 100);
+    let failed = Cell::new(false);
+    let solution = brent(
+        |correlation| {
+            pricer(correlation).map_or_else(
+                || {
+                    failed.set(true);
+                    f64::NAN
+                },
+                |value| value - price,
+            )
+        },
+        lower_bound,
+        upper_bound,
+        &options,
+    )?;
+    (!failed.get()).then_some(solution.x)
+}
+
+#[cfg(all(test, feature = "multi-asset"))]
+mod tests {
+    use super::*;
+    use crate::simulation::products::basket_option::MonteCarloEuropeanBasketOption;
+    use assert_approx_eq::assert_approx_eq;
+    use ndarray::{arr1, arr2};
+
+    #[test]
+    fn basket_implied_correlation() {
+        let basket = BasketApproximation::new(
+            arr1(&[0.3, 0.3, 0.4]),
+            arr1(&[100.0, 90.0, 110.0]),
+            arr1(&[0.2, 0.3, 0.25]),
+            0.02,
+            1.0,
+        )
+        .unwrap();
+        let price = basket.price(100.0, 0.6, &ExerciseType::Call);
+        let implied = implied_correlation(price, basket.min_correlation(), 1.0, |rho| {
+            Some(basket.price(100.0, rho, &ExerciseType::Call))
+        });
+        assert_approx_eq!(implied.unwrap(), 0.6, 1e-7);
+
+        // prices above the perfectly correlated basket can not be attained
+        let max_price = basket.price(100.0, 1.0, &ExerciseType::Call);
+        assert!(
+            implied_correlation(max_price + 0.1, -0.5, 1.0, |rho| Some(basket.price(
+                100.0,
+                rho,
+                &ExerciseType::Call
+            )))
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn invalid_basket() {
+        let basket = |weights: &[f64], volas: &[f64]| {
+            BasketApproximation::new(arr1(weights), arr1(&[100.0, 90.0]), arr1(volas), 0.02, 1.0)
+        };
+        assert!(basket(&[0.5, 0.5], &[0.2, 0.3]).is_ok());
+        assert!(matches!(
+            basket(&[0.5, 0.5], &[0.2]),
+            Err(PricingError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            basket(&[1.5, -0.5], &[0.2, 0.3]),
+            Err(PricingError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            basket(&[0.5, 0.5], &[0.2, f64::NAN]),
+            Err(PricingError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn spread_implied_correlation() {
+        let spread = SpreadApproximation::new((110.0, 100.0), (0.3, 0.2), 0.03, 0.5);
+        let price = spread.call(5.0, -0.3);
+        let implied =
+            implied_correlation(price, -1.0, 1.0, |rho| Some(spread.call(5.0, rho))).unwrap();
+        assert_approx_eq!(implied, -0.3, 1e-7);
+        assert!(spread.call(5.0, 0.5) < spread.call(5.0, -0.5));
+    }
+
+    #[test]
+    fn monte_carlo_implied_correlation() {
+        let (volas, t, strike) = ((0.2, 0.3), 1.0, 100.0);
+        let mc_price = |rho: f64| {
+            // the normals are correlated by C z, so C C^T is the covariance of the returns
+            let cholesky_factor = arr2(&[
+                [volas.0, 0.0],
+                [rho * volas.1, volas.1 * (1.0 - rho * rho).sqrt()],
+            ]);
+            MonteCarloEuropeanBasketOption::<rand_hc::Hc128Rng>::new(
+                arr1(&[0.5, 0.5]),
+                arr1(&[100.0, 100.0]),
+                arr1(&[0.02, 0.02]),
+                cholesky_factor,
+                strike,
+                t,
+                2_000,
+                4,
+                42,
+            )
+            .try_call()
+            .ok()
+            .map(|result| result.value)
+        };
+        let implied = implied_correlation(mc_price(0.4).unwrap(), -1.0, 1.0, mc_price).unwrap();
+        assert_approx_eq!(implied, 0.4, 1e-6);
+
+        // the approximation is close to the simulation
+        let basket = BasketApproximation::new(
+            arr1(&[0.5, 0.5]),
+            arr1(&[100.0, 100.0]),
+            arr1(&[volas.0, volas.1]),
+            0.02,
+            t,
+        )
+        .unwrap();
+        let approximated = implied_correlation(mc_price(0.4).unwrap(), -1.0, 1.0, |rho| {
+            Some(basket.price(strike, rho, &ExerciseType::Call))
+        })
+        .unwrap();
+        assert_approx_eq!(approximated, 0.4, 0.2);
+    }
+}
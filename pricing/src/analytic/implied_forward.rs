@@ -0,0 +1,180 @@
+use ndarray::{Array1, Array2};
+
+use crate::common::results::PricingError;
+use crate::numerics::least_squares::least_squares;
+
+/// Market prices of a call and a put with the same strike and expiry.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PutCallQuote {
+    pub strike: f64,
+    pub call: f64,
+    pub put: f64,
+}
+
+impl PutCallQuote {
+    pub fn new(strike: f64, call: f64, put: f64) -> Self {
+        Self { strike, call, put }
+    }
+}
+
+/// The forward and the discount factor of an expiry implied by the put-call parity of the quotes,
+/// with the implied rate and the implied dividend (and repo) yield for the spot.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImpliedForward {
+    pub forward: f64,
+    pub discount_factor: f64,
+    /// the continuously compounded rate implied by the discount factor
+    pub rfr: f64,
+    /// the continuous yield $q$ of dividends and repo with $F = S e^{(r - q) T}$
+    pub carry_yield: f64,
+    /// the root mean square deviation of the quotes from the fitted parity
+    pub rmse: f64,
+}
+
+/// Extraction of the implied forward, discount factor and dividend yield of one expiry from the put-call
+/// parity $C - P = D (F - K)$, which is linear in the strike: a regression of the differences
+/// $C_i - P_i$ on the strikes yields the discount factor as minus the slope and the forward from the intercept.
+/// See https://en.wikipedia.org/wiki/Put-call_parity
+#[derive(Clone, Copy, Debug)]
+pub struct ParityRegression {
+    spot: f64,
+    time_to_expiration: f64,
+    /// a known discount factor, e.g. from the OIS curve, such that only the forward is fitted
+    discount_factor: Option<f64>,
+}
+
+impl ParityRegression {
+    pub fn new(spot: f64, time_to_expiration: f64) -> Self {
+        Self {
+            spot,
+            time_to_expiration,
+            discount_factor: None,
+        }
+    }
+
+    /// Fits the forward only, for the given discount factor.
+    pub fn with_discount_factor(self, discount_factor: f64) -> Self {
+        Self {
+            discount_factor: Some(discount_factor),
+            ..self
+        }
+    }
+
+    pub fn fit(&self, quotes: &[PutCallQuote]) -> Result<ImpliedForward, PricingError> {
+        PricingError::check(self.spot > 0.0, "spot", self.spot)?;
+        PricingError::check(
+            self.time_to_expiration > 0.0,
+            "time to expiration",
+            self.time_to_expiration,
+        )?;
+        let min_nr_quotes = if self.discount_factor.is_some() { 1 } else { 2 };
+        PricingError::check(
+            quotes.len() >= min_nr_quotes,
+            "number of quotes",
+            quotes.len() as f64,
+        )?;
+
+        let differences: Array1<f64> = quotes.iter().map(|q| q.call - q.put).collect();
+        let (discount_factor, forward) = match self.discount_factor {
+            Some(discount_factor) => {
+                // the least squares forward for the fixed slope
+                let forward = quotes
+                    .iter()
+                    .zip(&differences)
+                    .map(|(q, difference)| q.strike + difference / discount_factor)
+                    .sum::<f64>()
+                    / quotes.len() as f64;
+                (discount_factor, forward)
+            }
+            None => {
+                let design = Array2::from_shape_fn((quotes.len(), 2), |(i, j)| match j {
+                    0 => 1.0,
+                    _ => quotes[i].strike,
+                });
+                let coefficients = least_squares(&design, &differences).ok_or_else(|| {
+                    PricingError::InvalidParameter("strikes must not all be equal".to_string())
+                })?;
+                let discount_factor = -coefficients[1];
+                (discount_factor, coefficients[0] / discount_factor)
+            }
+        };
+        PricingError::check(discount_factor > 0.0, "discount factor", discount_factor)?;
+        PricingError::check(forward > 0.0, "forward", forward)?;
+
+        let rmse = (quotes
+            .iter()
+            .zip(&differences)
+            .map(|(q, difference)| (difference - discount_factor * (forward - q.strike)).powi(2))
+            .sum::<f64>()
+            / quotes.len() as f64)
+            .sqrt();
+        let rfr = -discount_factor.ln() / self.time_to_expiration;
+        Ok(ImpliedForward {
+            forward,
+            discount_factor,
+            rfr,
+            carry_yield: rfr - (forward / self.spot).ln() / self.time_to_expiration,
+            rmse,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::BsmComputation;
+    use crate::common::models::DerivativeParameter;
+    use assert_approx_eq::assert_approx_eq;
+
+    /// BSM quotes with a dividend yield, i.e. on the spot discounted by the yield.
+    fn quotes(spot: f64, rfr: f64, dividend_yield: f64, t: f64) -> Vec<PutCallQuote> {
+        [80.0, 90.0, 100.0, 110.0, 120.0]
+            .iter()
+            .map(|strike| {
+                let dp = DerivativeParameter::new(
+                    spot * (-dividend_yield * t).exp(),
+                    *strike,
+                    t,
+                    rfr,
+                    0.25,
+                );
+                let bsm = BsmComputation::new(&dp);
+                PutCallQuote::new(*strike, bsm.call(), bsm.put())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn implied_forward_and_dividend_yield() {
+        let (spot, rfr, dividend_yield, t) = (100.0, 0.04, 0.015, 0.5);
+        let quotes = quotes(spot, rfr, dividend_yield, t);
+
+        let implied = ParityRegression::new(spot, t).fit(&quotes).unwrap();
+        assert_approx_eq!(implied.rfr, rfr, 1e-10);
+        assert_approx_eq!(implied.carry_yield, dividend_yield, 1e-10);
+        assert_approx_eq!(
+            implied.forward,
+            spot * ((rfr - dividend_yield) * t).exp(),
+            1e-8
+        );
+        assert!(implied.rmse < 1e-10);
+
+        // a known discount factor and noisy quotes
+        let noisy: Vec<PutCallQuote> = quotes
+            .iter()
+            .enumerate()
+            .map(|(i, q)| PutCallQuote::new(q.strike, q.call + 0.01 * (i % 2) as f64, q.put))
+            .collect();
+        let implied = ParityRegression::new(spot, t)
+            .with_discount_factor((-rfr * t).exp())
+            .fit(&noisy)
+            .unwrap();
+        assert_approx_eq!(implied.carry_yield, dividend_yield, 1e-3);
+        assert!(implied.rmse > 0.0);
+
+        assert!(matches!(
+            ParityRegression::new(spot, t).fit(&quotes[..1]),
+            Err(PricingError::InvalidParameter(_))
+        ));
+    }
+}
@@ -0,0 +1,403 @@
+use std::collections::BTreeMap;
+
+use crate::analytic::vega_buckets::VegaReport;
+
+/// The risk classes of the margin calculation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RiskClass {
+    InterestRate,
+    Credit,
+    Equity,
+    Commodity,
+    Fx,
+}
+
+impl RiskClass {
+    pub const ALL: [RiskClass; 5] = [
+        RiskClass::InterestRate,
+        RiskClass::Credit,
+        RiskClass::Equity,
+        RiskClass::Commodity,
+        RiskClass::Fx,
+    ];
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SensitivityType {
+    Delta,
+    Vega,
+}
+
+/// A sensitivity to a risk factor, e.g. the value change of a 1% spot move or a vega times the volatility.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sensitivity {
+    pub risk_class: RiskClass,
+    pub sensitivity_type: SensitivityType,
+    /// the bucket within the risk class, e.g. a sector or a currency group
+    pub bucket: usize,
+    /// e.g. the underlying or the currency
+    pub risk_factor: String,
+    /// the index of the tenor or maturity bucket of the risk factor
+    pub tenor: usize,
+    pub amount: f64,
+}
+
+impl Sensitivity {
+    pub fn new(
+        risk_class: RiskClass,
+        sensitivity_type: SensitivityType,
+        bucket: usize,
+        risk_factor: &str,
+        tenor: usize,
+        amount: f64,
+    ) -> Self {
+        Self {
+            risk_class,
+            sensitivity_type,
+            bucket,
+            risk_factor: risk_factor.to_string(),
+            tenor,
+            amount,
+        }
+    }
+
+    /// The equity delta sensitivity $\Delta S / 100$, i.e. the value change of a 1% relative spot move.
+    pub fn equity_delta(bucket: usize, underlying: &str, delta: f64, spot: f64) -> Self {
+        Self::new(
+            RiskClass::Equity,
+            SensitivityType::Delta,
+            bucket,
+            underlying,
+            0,
+            delta * spot / 100.0,
+        )
+    }
+
+    /// The vega sensitivities $\partial V / \partial \sigma \cdot \sigma$ per maturity bucket of the report,
+    /// for the volatility of the underlying.
+    pub fn from_vega_report(
+        risk_class: RiskClass,
+        bucket: usize,
+        underlying: &str,
+        report: &VegaReport,
+        vola: f64,
+    ) -> Vec<Self> {
+        report
+            .by_maturity()
+            .into_iter()
+            .map(|(tenor, vega)| {
+                Self::new(
+                    risk_class,
+                    SensitivityType::Vega,
+                    bucket,
+                    underlying,
+                    tenor,
+                    vega * vola,
+                )
+            })
+            .collect()
+    }
+}
+
+/// Risk weights and correlations of the margin calculation per risk class. The defaults are
+/// illustrative values of the order of magnitude of ISDA SIMM, not its calibrated parameters.
+#[derive(Clone, Debug)]
+pub struct SimmParameters {
+    /// the delta risk weights per risk class, overridden per bucket by `bucket_risk_weights`
+    delta_risk_weights: [f64; 5],
+    bucket_risk_weights: BTreeMap<(RiskClass, usize), f64>,
+    vega_risk_weights: [f64; 5],
+    /// the correlation of different risk factors within a bucket
+    intra_bucket_correlations: [f64; 5],
+    /// the correlation of the same risk factor at different tenors
+    tenor_correlations: [f64; 5],
+    /// the correlation of the aggregated sensitivities of different buckets
+    inter_bucket_correlations: [f64; 5],
+    /// the correlation of the margins of the risk classes
+    risk_class_correlations: [[f64; 5]; 5],
+}
+
+impl Default for SimmParameters {
+    fn default() -> Self {
+        Self {
+            delta_risk_weights: [50.0, 75.0, 30.0, 20.0, 8.0],
+            bucket_risk_weights: BTreeMap::new(),
+            vega_risk_weights: [0.2, 0.3, 0.3, 0.4, 0.3],
+            intra_bucket_correlations: [0.3, 0.4, 0.2, 0.3, 0.5],
+            tenor_correlations: [0.8, 0.9, 0.9, 0.9, 0.9],
+            inter_bucket_correlations: [0.3, 0.4, 0.15, 0.2, 0.5],
+            risk_class_correlations: [
+                [1.0, 0.3, 0.2, 0.3, 0.3],
+                [0.3, 1.0, 0.5, 0.3, 0.2],
+                [0.2, 0.5, 1.0, 0.3, 0.2],
+                [0.3, 0.3, 0.3, 1.0, 0.2],
+                [0.3, 0.2, 0.2, 0.2, 1.0],
+            ],
+        }
+    }
+}
+
+impl SimmParameters {
+    pub fn with_delta_risk_weight(mut self, risk_class: RiskClass, risk_weight: f64) -> Self {
+        self.delta_risk_weights[risk_class.index()] = risk_weight;
+        self
+    }
+
+    pub fn with_bucket_risk_weight(
+        mut self,
+        risk_class: RiskClass,
+        bucket: usize,
+        risk_weight: f64,
+    ) -> Self {
+        self.bucket_risk_weights
+            .insert((risk_class, bucket), risk_weight);
+        self
+    }
+
+    pub fn with_vega_risk_weight(mut self, risk_class: RiskClass, risk_weight: f64) -> Self {
+        self.vega_risk_weights[risk_class.index()] = risk_weight;
+        self
+    }
+
+    /// The correlations of different risk factors within a bucket, of the same risk factor at different
+    /// tenors and of different buckets.
+    pub fn with_correlations(
+        mut self,
+        risk_class: RiskClass,
+        intra_bucket: f64,
+        tenor: f64,
+        inter_bucket: f64,
+    ) -> Self {
+        let idx = risk_class.index();
+        self.intra_bucket_correlations[idx] = intra_bucket;
+        self.tenor_correlations[idx] = tenor;
+        self.inter_bucket_correlations[idx] = inter_bucket;
+        self
+    }
+
+    pub fn with_risk_class_correlation(mut self, a: RiskClass, b: RiskClass, psi: f64) -> Self {
+        assert!(a != b && (-1.0..=1.0).contains(&psi));
+        self.risk_class_correlations[a.index()][b.index()] = psi;
+        self.risk_class_correlations[b.index()][a.index()] = psi;
+        self
+    }
+
+    fn risk_weight(&self, sensitivity: &Sensitivity) -> f64 {
+        let class = sensitivity.risk_class;
+        match sensitivity.sensitivity_type {
+            SensitivityType::Delta => self
+                .bucket_risk_weights
+                .get(&(class, sensitivity.bucket))
+                .copied()
+                .unwrap_or(self.delta_risk_weights[class.index()]),
+            SensitivityType::Vega => self.vega_risk_weights[class.index()],
+        }
+    }
+
+    fn correlation(&self, a: &Sensitivity, b: &Sensitivity) -> f64 {
+        let idx = a.risk_class.index();
+        match (a.risk_factor == b.risk_factor, a.tenor == b.tenor) {
+            (true, true) => 1.0,
+            (true, false) => self.tenor_correlations[idx],
+            (false, _) => self.intra_bucket_correlations[idx],
+        }
+    }
+}
+
+/// The initial margin of the portfolio and the margins per risk class.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InitialMargin {
+    pub total: f64,
+    pub by_risk_class: BTreeMap<RiskClass, f64>,
+}
+
+/// Simplified ISDA SIMM: the weighted sensitivities $WS_k = RW_k s_k$ are aggregated per bucket
+/// '''math
+/// K_b = \sqrt{\sum_k WS_k^2 + \sum_{k \neq l} \rho_{kl} WS_k WS_l}, \quad S_b = max(min(\sum_k WS_k, K_b), -K_b)
+/// '''
+/// and over the buckets to $\sqrt{\sum_b K_b^2 + \sum_{b \neq c} \gamma S_b S_c}$ per risk class and
+/// sensitivity type. The delta and vega margins add up per risk class, which aggregate with the
+/// correlations $\psi$ to the total margin. Curvature and concentration add-ons are omitted.
+/// See https://www.isda.org/category/margin/isda-simm/
+#[derive(Clone, Debug, Default)]
+pub struct SimmCalculator {
+    parameters: SimmParameters,
+}
+
+impl SimmCalculator {
+    pub fn new(parameters: SimmParameters) -> Self {
+        Self { parameters }
+    }
+
+    /// The margin of the sensitivities of a single risk class and sensitivity type.
+    fn margin_of(&self, sensitivities: &[&Sensitivity]) -> f64 {
+        let mut buckets: BTreeMap<usize, Vec<(&Sensitivity, f64)>> = BTreeMap::new();
+        for s in sensitivities {
+            let weighted = self.parameters.risk_weight(s) * s.amount;
+            buckets.entry(s.bucket).or_default().push((s, weighted));
+        }
+
+        let bucket_aggregates: Vec<(f64, f64)> = buckets
+            .values()
+            .map(|weighted| {
+                let variance: f64 = weighted
+                    .iter()
+                    .flat_map(|(a, ws_a)| {
+                        weighted
+                            .iter()
+                            .map(move |(b, ws_b)| self.parameters.correlation(a, b) * ws_a * ws_b)
+                    })
+                    .sum();
+                let k_b = variance.max(0.0).sqrt();
+                let s_b = weighted
+                    .iter()
+                    .map(|(_, ws)| ws)
+                    .sum::<f64>()
+                    .clamp(-k_b, k_b);
+                (k_b, s_b)
+            })
+            .collect();
+
+        let gamma = sensitivities.first().map_or(0.0, |s| {
+            self.parameters.inter_bucket_correlations[s.risk_class.index()]
+        });
+        let mut variance = 0.0;
+        for (b, (k_b, s_b)) in bucket_aggregates.iter().enumerate() {
+            variance += k_b * k_b;
+            for (c, (_, s_c)) in bucket_aggregates.iter().enumerate() {
+                if b != c {
+                    variance += gamma * s_b * s_c;
+                }
+            }
+        }
+        variance.max(0.0).sqrt()
+    }
+
+    pub fn initial_margin(&self, sensitivities: &[Sensitivity]) -> InitialMargin {
+        let mut by_risk_class = BTreeMap::new();
+        for risk_class in RiskClass::ALL {
+            let margin: f64 = [SensitivityType::Delta, SensitivityType::Vega]
+                .iter()
+                .map(|sensitivity_type| {
+                    let selected: Vec<&Sensitivity> = sensitivities
+                        .iter()
+                        .filter(|s| {
+                            s.risk_class == risk_class && s.sensitivity_type == *sensitivity_type
+                        })
+                        .collect();
+                    self.margin_of(&selected)
+                })
+                .sum();
+            if margin > 0.0 {
+                by_risk_class.insert(risk_class, margin);
+            }
+        }
+
+        let psi = &self.parameters.risk_class_correlations;
+        let total = by_risk_class
+            .iter()
+            .flat_map(|(r, im_r)| {
+                by_risk_class
+                    .iter()
+                    .map(move |(s, im_s)| psi[r.index()][s.index()] * im_r * im_s)
+            })
+            .sum::<f64>()
+            .max(0.0)
+            .sqrt();
+        InitialMargin {
+            total,
+            by_risk_class,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::vega_buckets::VegaBucket;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn equity_delta(bucket: usize, underlying: &str, amount: f64) -> Sensitivity {
+        Sensitivity::new(
+            RiskClass::Equity,
+            SensitivityType::Delta,
+            bucket,
+            underlying,
+            0,
+            amount,
+        )
+    }
+
+    #[test]
+    fn bucket_aggregation() {
+        let calculator = SimmCalculator::default();
+        let single = calculator.initial_margin(&[Sensitivity::equity_delta(1, "ABC", 0.5, 200.0)]);
+        assert_approx_eq!(single.total, 30.0, 1e-12);
+
+        // offsetting deltas of the same underlying net out, of different underlyings only partially
+        let hedged =
+            calculator.initial_margin(&[equity_delta(1, "ABC", 1.0), equity_delta(1, "ABC", -1.0)]);
+        assert_approx_eq!(hedged.total, 0.0, 1e-12);
+        let partially =
+            calculator.initial_margin(&[equity_delta(1, "ABC", 1.0), equity_delta(1, "XYZ", -1.0)]);
+        assert_approx_eq!(partially.total, 30.0 * (2.0 - 2.0 * 0.2_f64).sqrt(), 1e-12);
+
+        // across buckets with the inter bucket correlation
+        let buckets =
+            calculator.initial_margin(&[equity_delta(1, "ABC", 1.0), equity_delta(2, "XYZ", 1.0)]);
+        assert_approx_eq!(buckets.total, 30.0 * (2.0 + 2.0 * 0.15_f64).sqrt(), 1e-12);
+
+        let parameters =
+            SimmParameters::default().with_bucket_risk_weight(RiskClass::Equity, 2, 60.0);
+        let custom = SimmCalculator::new(parameters).initial_margin(&[equity_delta(2, "XYZ", 1.0)]);
+        assert_approx_eq!(custom.total, 60.0, 1e-12);
+    }
+
+    #[test]
+    fn risk_class_aggregation() {
+        let mut report = VegaReport::default();
+        for (maturity_bucket, vega) in [(0, 10.0), (1, 20.0)] {
+            report.add(
+                VegaBucket {
+                    maturity_bucket,
+                    strike_bucket: None,
+                },
+                vega,
+            );
+        }
+        let mut sensitivities =
+            Sensitivity::from_vega_report(RiskClass::Equity, 1, "ABC", &report, 0.2);
+        assert_eq!(sensitivities.len(), 2);
+        assert_approx_eq!(sensitivities[1].amount, 4.0, 1e-12);
+
+        let calculator = SimmCalculator::default();
+        let vega_margin = calculator.initial_margin(&sensitivities).total;
+        // vegas of the same underlying at different maturities
+        let expected = 0.3 * (2.0_f64.powi(2) + 4.0_f64.powi(2) + 2.0 * 0.9 * 2.0 * 4.0).sqrt();
+        assert_approx_eq!(vega_margin, expected, 1e-12);
+
+        sensitivities.push(equity_delta(1, "ABC", 1.0));
+        sensitivities.push(Sensitivity::new(
+            RiskClass::Fx,
+            SensitivityType::Delta,
+            0,
+            "EURUSD",
+            0,
+            10.0,
+        ));
+        let margin = calculator.initial_margin(&sensitivities);
+        let equity = margin.by_risk_class[&RiskClass::Equity];
+        let fx = margin.by_risk_class[&RiskClass::Fx];
+        assert_approx_eq!(equity, vega_margin + 30.0, 1e-12);
+        assert_approx_eq!(fx, 80.0, 1e-12);
+        assert_approx_eq!(
+            margin.total,
+            (equity * equity + fx * fx + 2.0 * 0.2 * equity * fx).sqrt(),
+            1e-12
+        );
+    }
+}
@@ -1 +1,11 @@
+pub mod asian;
 pub mod black_scholes;
+pub mod carry;
+pub mod credit_default_swap;
+pub mod dual_greeks;
+pub mod hedge_ratio;
+pub mod implied_correlation;
+pub mod implied_forward;
+pub mod initial_margin;
+pub mod pnl_explain;
+pub mod vega_buckets;
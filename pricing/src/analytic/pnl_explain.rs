@@ -0,0 +1,188 @@
+use crate::common::market_data::MarketDataSet;
+
+/// The attribution of the value change between two market data snapshots.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PnlExplain {
+    pub delta: f64,
+    pub gamma: f64,
+    pub vega: f64,
+    pub theta: f64,
+    pub rates: f64,
+    /// the cross effects and the unexplained change
+    pub residual: f64,
+}
+
+impl PnlExplain {
+    pub fn explained(&self) -> f64 {
+        self.delta + self.gamma + self.vega + self.theta + self.rates
+    }
+
+    /// The actual value change.
+    pub fn total(&self) -> f64 {
+        self.explained() + self.residual
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttributionMethod {
+    /// Taylor expansion with the greeks at t0, where the residual collects the higher order
+    /// and cross terms
+    RiskBased,
+    /// revaluation with the market data moved to t1 one factor at a time (time, spot, vola, rates),
+    /// where the spot move is split into its first order and remaining (gamma) effect; the residual is zero
+    Sequential,
+}
+
+/// Explains the P&L of a product between two market data snapshots, where `pricer` values the product
+/// for a market data set. The greeks are finite differences with relative spot bump and absolute
+/// volatility and rate bumps.
+pub struct PnlExplainEngine<Pricer>
+where
+    Pricer: Fn(&MarketDataSet) -> f64,
+{
+    pricer: Pricer,
+    spot_bump: f64,
+    vola_bump: f64,
+    rate_bump: f64,
+}
+
+impl<Pricer> PnlExplainEngine<Pricer>
+where
+    Pricer: Fn(&MarketDataSet) -> f64,
+{
+    pub fn new(pricer: Pricer) -> Self {
+        Self {
+            pricer,
+            spot_bump: 1e-4,
+            vola_bump: 1e-4,
+            rate_bump: 1e-4,
+        }
+    }
+
+    pub fn with_bump_sizes(self, spot_bump: f64, vola_bump: f64, rate_bump: f64) -> Self {
+        Self {
+            spot_bump,
+            vola_bump,
+            rate_bump,
+            ..self
+        }
+    }
+
+    /// The central finite difference delta and gamma.
+    fn spot_greeks(&self, market: &MarketDataSet) -> (f64, f64) {
+        let h = market.spot * self.spot_bump;
+        let value = (self.pricer)(market);
+        let up = (self.pricer)(&market.with_spot(market.spot + h));
+        let down = (self.pricer)(&market.with_spot(market.spot - h));
+        ((up - down) / (2.0 * h), (up - 2.0 * value + down) / (h * h))
+    }
+
+    fn vega(&self, market: &MarketDataSet) -> f64 {
+        let h = self.vola_bump;
+        ((self.pricer)(&market.with_vola(market.vola + h))
+            - (self.pricer)(&market.with_vola(market.vola - h)))
+            / (2.0 * h)
+    }
+
+    fn rho(&self, market: &MarketDataSet) -> f64 {
+        let h = self.rate_bump;
+        ((self.pricer)(&market.with_rfr(market.rfr + h))
+            - (self.pricer)(&market.with_rfr(market.rfr - h)))
+            / (2.0 * h)
+    }
+
+    pub fn explain(
+        &self,
+        t0: &MarketDataSet,
+        t1: &MarketDataSet,
+        method: AttributionMethod,
+    ) -> PnlExplain {
+        let value_t0 = (self.pricer)(t0);
+        let value_t1 = (self.pricer)(t1);
+        let d_spot = t1.spot - t0.spot;
+
+        let explain = match method {
+            AttributionMethod::RiskBased => {
+                let (delta, gamma) = self.spot_greeks(t0);
+                PnlExplain {
+                    delta: delta * d_spot,
+                    gamma: 0.5 * gamma * d_spot * d_spot,
+                    vega: self.vega(t0) * (t1.vola - t0.vola),
+                    // the time decay by revaluation, as the products may jump in time (e.g. expiries)
+                    theta: (self.pricer)(&t0.with_time(t1.time)) - value_t0,
+                    rates: self.rho(t0) * (t1.rfr - t0.rfr),
+                    residual: 0.0,
+                }
+            }
+            AttributionMethod::Sequential => {
+                let time_moved = t0.with_time(t1.time);
+                let spot_moved = time_moved.with_spot(t1.spot);
+                let vola_moved = spot_moved.with_vola(t1.vola);
+
+                let value_time_moved = (self.pricer)(&time_moved);
+                let value_spot_moved = (self.pricer)(&spot_moved);
+                let value_vola_moved = (self.pricer)(&vola_moved);
+                let delta = self.spot_greeks(&time_moved).0 * d_spot;
+                PnlExplain {
+                    delta,
+                    gamma: value_spot_moved - value_time_moved - delta,
+                    vega: value_vola_moved - value_spot_moved,
+                    theta: value_time_moved - value_t0,
+                    rates: value_t1 - value_vola_moved,
+                    residual: 0.0,
+                }
+            }
+        };
+        PnlExplain {
+            residual: value_t1 - value_t0 - explain.explained(),
+            ..explain
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::BsmComputation;
+    use crate::common::models::DerivativeParameter;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn call(market: &MarketDataSet) -> f64 {
+        let (strike, maturity) = (100.0, 1.0);
+        BsmComputation::new(&DerivativeParameter::new(
+            market.spot,
+            strike,
+            maturity - market.time,
+            market.rfr,
+            market.vola,
+        ))
+        .call()
+    }
+
+    #[test]
+    fn daily_explain() {
+        let t0 = MarketDataSet::new(0.0, 100.0, 0.2, 0.03);
+        let t1 = MarketDataSet::new(1.0 / 252.0, 101.5, 0.21, 0.031);
+        let engine = PnlExplainEngine::new(call);
+        let actual = call(&t1) - call(&t0);
+
+        let risk_based = engine.explain(&t0, &t1, AttributionMethod::RiskBased);
+        assert_approx_eq!(risk_based.total(), actual, 1e-12);
+        let bsm = BsmComputation::new(&DerivativeParameter::new(100.0, 100.0, 1.0, 0.03, 0.2));
+        assert_approx_eq!(risk_based.gamma, 0.5 * bsm.gamma() * 1.5 * 1.5, 1e-5);
+        assert_approx_eq!(risk_based.vega, bsm.vega() * 0.01, 1e-6);
+        assert!(risk_based.theta < 0.0 && risk_based.rates > 0.0);
+        // small residual for small moves
+        assert!(risk_based.residual.abs() < 0.02 * actual.abs());
+
+        let sequential = engine.explain(&t0, &t1, AttributionMethod::Sequential);
+        assert_approx_eq!(sequential.residual, 0.0, 1e-12);
+        assert_approx_eq!(sequential.total(), actual, 1e-12);
+        assert_approx_eq!(sequential.theta, risk_based.theta, 1e-15);
+        assert_approx_eq!(
+            sequential.delta + sequential.gamma,
+            risk_based.delta + risk_based.gamma,
+            0.01
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+
+use crate::analytic::black_scholes::BsmComputation;
+use crate::common::models::DerivativeParameter;
+use crate::common::vol_surface::VolatilitySurface;
+
+/// A vega bucket given by the index of the maturity (time) bucket and,
+/// for sensitivities to surface nodes, the index of the strike node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VegaBucket {
+    pub maturity_bucket: usize,
+    pub strike_bucket: Option<usize>,
+}
+
+/// Bucketed vegas (per unit volatility) keyed by bucket, e.g. for limits per maturity.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VegaReport {
+    pub vegas: BTreeMap<VegaBucket, f64>,
+}
+
+impl VegaReport {
+    pub fn add(&mut self, bucket: VegaBucket, vega: f64) {
+        *self.vegas.entry(bucket).or_insert(0.0) += vega;
+    }
+
+    /// Aggregates the reports, e.g. of the positions of a book.
+    pub fn merge(&mut self, other: &VegaReport) {
+        for (bucket, vega) in other.vegas.iter() {
+            self.add(*bucket, *vega);
+        }
+    }
+
+    pub fn get(&self, bucket: &VegaBucket) -> f64 {
+        self.vegas.get(bucket).copied().unwrap_or(0.0)
+    }
+
+    pub fn total(&self) -> f64 {
+        self.vegas.values().sum()
+    }
+
+    /// The vegas aggregated over the strikes per maturity bucket.
+    pub fn by_maturity(&self) -> BTreeMap<usize, f64> {
+        let mut by_maturity = BTreeMap::new();
+        for (bucket, vega) in self.vegas.iter() {
+            *by_maturity.entry(bucket.maturity_bucket).or_insert(0.0) += vega;
+        }
+        by_maturity
+    }
+}
+
+/// The Black-Scholes-Merton vega of a European option per node of the volatility surface,
+/// where the option is priced with the effective volatility of its strike until expiration:
+/// '''math
+/// \frac{\partial V}{\partial \sigma_{k,l}} = \mathcal{V} \frac{\sigma_k(K) \Delta t_k}{T \sigma_{eff}} w_l(K)
+/// '''
+/// for the bucket lengths $\Delta t_k$ and the strike interpolation weights $w_l$.
+pub fn surface_vega(
+    surface: &VolatilitySurface,
+    asset_price: f64,
+    strike: f64,
+    time_to_expiration: f64,
+    rfr: f64,
+) -> VegaReport {
+    let effective_vola = surface.effective_vola(time_to_expiration, strike);
+    let vega = BsmComputation::new(&DerivativeParameter::new(
+        asset_price,
+        strike,
+        time_to_expiration,
+        rfr,
+        effective_vola,
+    ))
+    .vega();
+
+    let mut report = VegaReport::default();
+    let strike_weights = surface.strike_weights(strike);
+    for (k, length) in surface
+        .bucket_lengths(time_to_expiration)
+        .iter()
+        .enumerate()
+    {
+        if *length == 0.0 {
+            continue;
+        }
+        let bucket_vega =
+            vega * surface.vola(k, strike) * length / (time_to_expiration * effective_vola);
+        for (l, weight) in strike_weights.iter() {
+            report.add(
+                VegaBucket {
+                    maturity_bucket: k,
+                    strike_bucket: Some(*l),
+                },
+                bucket_vega * weight,
+            );
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::{BlackScholesMerton, OptionPrice};
+    use assert_approx_eq::assert_approx_eq;
+    use ndarray::arr2;
+
+    fn surface() -> VolatilitySurface {
+        VolatilitySurface::new(
+            vec![0.5, 1.0, 2.0],
+            vec![80.0, 100.0, 120.0],
+            arr2(&[[0.3, 0.25, 0.22], [0.28, 0.24, 0.21], [0.26, 0.23, 0.2]]),
+        )
+    }
+
+    fn price(surface: &VolatilitySurface, strike: f64, t: f64) -> f64 {
+        let vola = surface.effective_vola(t, strike);
+        BlackScholesMerton::call(&DerivativeParameter::new(100.0, strike, t, 0.03, vola))
+    }
+
+    #[test]
+    fn flat_surface_total_vega() {
+        let report = surface_vega(&VolatilitySurface::flat(0.2), 100.0, 105.0, 1.5, 0.03);
+        let dp = DerivativeParameter::new(100.0, 105.0, 1.5, 0.03, 0.2);
+        assert_approx_eq!(report.total(), BsmComputation::new(&dp).vega(), 1e-10);
+    }
+
+    #[test]
+    fn node_vegas_match_bumps() {
+        let surface = surface();
+        let (strike, t, h) = (110.0, 1.5, 1e-5);
+        let report = surface_vega(&surface, 100.0, strike, t, 0.03);
+
+        for k in 0..3 {
+            for l in 0..3 {
+                let bumped_vega = (price(&surface.bumped(k, l, h), strike, t)
+                    - price(&surface.bumped(k, l, -h), strike, t))
+                    / (2.0 * h);
+                let bucket = VegaBucket {
+                    maturity_bucket: k,
+                    strike_bucket: Some(l),
+                };
+                assert_approx_eq!(report.get(&bucket), bumped_vega, 1e-5);
+            }
+        }
+        // the 80 strike node does not affect the 110 strike
+        assert_eq!(report.by_maturity().len(), 3);
+        assert!(!report.vegas.keys().any(|b| b.strike_bucket == Some(0)));
+    }
+
+    #[test]
+    fn book_aggregation() {
+        let surface = surface();
+        let mut book = surface_vega(&surface, 100.0, 90.0, 0.75, 0.03);
+        let other = surface_vega(&surface, 100.0, 100.0, 1.5, 0.03);
+        let total = book.total() + other.total();
+        book.merge(&other);
+        assert_approx_eq!(book.total(), total, 1e-12);
+    }
+}
@@ -0,0 +1,153 @@
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::market_context::MarketSnapshot;
+use crate::common::models::DerivativeParameter;
+
+/// An input of a pricing, resolved from the market snapshot or from the settings of the engine.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AuditEntry {
+    Spot {
+        underlying: String,
+        value: f64,
+    },
+    /// the zero rate of a pillar the curve is built from
+    CurveNode {
+        curve: String,
+        maturity: f64,
+        zero_rate: f64,
+    },
+    /// the zero rate and the discount factor of the curve at the maturity of the product
+    CurveValue {
+        curve: String,
+        maturity: f64,
+        zero_rate: f64,
+        discount_factor: f64,
+    },
+    /// the effective volatility interpolated from the surface, or extrapolated flat
+    Volatility {
+        underlying: String,
+        time: f64,
+        strike: f64,
+        vola: f64,
+        extrapolated: bool,
+    },
+    /// a parameter of the model the engine prices with
+    ModelParameter {
+        name: String,
+        value: f64,
+    },
+    /// a setting of the engine, e.g. its number of paths or its random number generator
+    EngineSetting {
+        name: String,
+        value: String,
+    },
+    /// the seed of the random numbers
+    Seed(u64),
+}
+
+impl fmt::Display for AuditEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditEntry::Spot { underlying, value } => write!(f, "spot {underlying} = {value}"),
+            AuditEntry::CurveNode {
+                curve,
+                maturity,
+                zero_rate,
+            } => write!(f, "curve {curve} node {maturity}: zero rate {zero_rate}"),
+            AuditEntry::CurveValue {
+                curve,
+                maturity,
+                zero_rate,
+                discount_factor,
+            } => write!(
+                f,
+                "curve {curve} at {maturity}: zero rate {zero_rate}, discount factor {discount_factor}"
+            ),
+            AuditEntry::Volatility {
+                underlying,
+                time,
+                strike,
+                vola,
+                extrapolated,
+            } => write!(
+                f,
+                "volatility {underlying} at time {time} and strike {strike} = {vola}{}",
+                if *extrapolated { " (extrapolated)" } else { "" }
+            ),
+            AuditEntry::ModelParameter { name, value } => write!(f, "model {name} = {value}"),
+            AuditEntry::EngineSetting { name, value } => write!(f, "engine {name} = {value}"),
+            AuditEntry::Seed(seed) => write!(f, "seed = {seed}"),
+        }
+    }
+}
+
+/// The inputs a pricing resolved, with its engine, product and market snapshot, e.g. to reproduce and
+/// justify a price reported to a regulator. Serializable with the `serde` feature.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AuditRecord {
+    pub engine: String,
+    /// the description of the product
+    pub product: String,
+    pub valuation_time: f64,
+    /// the version of the market snapshot, see `MarketContext`
+    pub market_version: u64,
+    pub entries: Vec<AuditEntry>,
+}
+
+impl AuditRecord {
+    pub fn new(engine: &str, product: &str, market: &MarketSnapshot) -> Self {
+        Self {
+            engine: engine.to_string(),
+            product: product.to_string(),
+            valuation_time: market.time,
+            market_version: market.version,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn with_entries(mut self, entries: impl IntoIterator<Item = AuditEntry>) -> Self {
+        self.entries.extend(entries);
+        self
+    }
+
+    pub fn with_setting(self, name: &str, value: impl ToString) -> Self {
+        self.with_entries([AuditEntry::EngineSetting {
+            name: name.to_string(),
+            value: value.to_string(),
+        }])
+    }
+
+    /// The record with the flat parameters of a Black-Scholes type model.
+    pub fn with_derivative_parameter(self, dp: &DerivativeParameter) -> Self {
+        let parameter = |name: &str, value: f64| AuditEntry::ModelParameter {
+            name: name.to_string(),
+            value,
+        };
+        self.with_entries([
+            parameter("asset price", dp.asset_price),
+            parameter("strike", dp.strike),
+            parameter("time to expiration", dp.time_to_expiration),
+            parameter("rfr", dp.rfr),
+            parameter("vola", dp.vola),
+        ])
+    }
+}
+
+impl fmt::Display for AuditRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} of {} at time {} (market version {})",
+            self.engine, self.product, self.valuation_time, self.market_version
+        )?;
+        for entry in &self.entries {
+            writeln!(f, "  {entry}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::fmt;
+
+/// ISO 4217 currency code, e.g. EUR.
+/// See https://en.wikipedia.org/wiki/ISO_4217
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Currency([u8; 3]);
+
+impl Currency {
+    pub const CHF: Currency = Currency(*b"CHF");
+    pub const EUR: Currency = Currency(*b"EUR");
+    pub const GBP: Currency = Currency(*b"GBP");
+    pub const JPY: Currency = Currency(*b"JPY");
+    pub const USD: Currency = Currency(*b"USD");
+
+    /// The currency of a three letter (upper case) code.
+    pub fn new(code: &str) -> Option<Self> {
+        let bytes: [u8; 3] = code.as_bytes().try_into().ok()?;
+        bytes
+            .iter()
+            .all(|b| b.is_ascii_uppercase())
+            .then_some(Currency(bytes))
+    }
+
+    pub fn code(&self) -> &str {
+        std::str::from_utf8(&self.0).unwrap()
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+/// An amount denominated in a currency.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Money {
+    pub amount: f64,
+    pub currency: Currency,
+}
+
+impl Money {
+    pub fn new(amount: f64, currency: Currency) -> Self {
+        Self { amount, currency }
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.amount, self.currency)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FxError {
+    /// no rate is given for the currency
+    MissingRate(Currency),
+}
+
+impl fmt::Display for FxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FxError::MissingRate(currency) => write!(f, "missing fx rate for {}", currency),
+        }
+    }
+}
+
+impl std::error::Error for FxError {}
+
+/// Spot FX rates against a base currency, converting between any two quoted currencies by triangulation.
+#[derive(Clone, Debug)]
+pub struct FxRates {
+    base: Currency,
+    /// the value of one unit of the currency in the base currency
+    rates: HashMap<Currency, f64>,
+}
+
+impl FxRates {
+    pub fn new(base: Currency) -> Self {
+        Self {
+            base,
+            rates: HashMap::new(),
+        }
+    }
+
+    /// Adds the rate as the value of one unit of `currency` in the base currency, e.g. 1.08 for EURUSD
+    /// with base USD.
+    pub fn with_rate(mut self, currency: Currency, rate: f64) -> Self {
+        assert!(rate > 0.0);
+        self.rates.insert(currency, rate);
+        self
+    }
+
+    pub fn base(&self) -> Currency {
+        self.base
+    }
+
+    /// The value of one unit of `from` in units of `to`.
+    pub fn rate(&self, from: Currency, to: Currency) -> Result<f64, FxError> {
+        let in_base = |currency: Currency| {
+            if currency == self.base {
+                Ok(1.0)
+            } else {
+                self.rates
+                    .get(&currency)
+                    .copied()
+                    .ok_or(FxError::MissingRate(currency))
+            }
+        };
+        if from == to {
+            return Ok(1.0);
+        }
+        Ok(in_base(from)? / in_base(to)?)
+    }
+
+    pub fn convert(&self, money: &Money, to: Currency) -> Result<Money, FxError> {
+        Ok(Money::new(
+            money.amount * self.rate(money.currency, to)?,
+            to,
+        ))
+    }
+
+    /// The sum of the amounts converted to the target currency, or the error of the first missing rate.
+    pub fn aggregate(&self, amounts: &[Money], to: Currency) -> Result<Money, FxError> {
+        amounts
+            .iter()
+            .try_fold(Money::new(0.0, to), |total, money| {
+                Ok(Money::new(
+                    total.amount + self.convert(money, to)?.amount,
+                    to,
+                ))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn multi_currency_aggregation() {
+        assert_eq!(Currency::new("EUR"), Some(Currency::EUR));
+        assert_eq!(Currency::new("eur"), None);
+        assert_eq!(Currency::new("EURO"), None);
+
+        let fx_rates = FxRates::new(Currency::USD)
+            .with_rate(Currency::EUR, 1.1)
+            .with_rate(Currency::CHF, 1.25);
+        assert_approx_eq!(
+            fx_rates.rate(Currency::EUR, Currency::CHF).unwrap(),
+            0.88,
+            1e-15
+        );
+
+        let positions = [
+            Money::new(100.0, Currency::EUR),
+            Money::new(-50.0, Currency::USD),
+            Money::new(40.0, Currency::CHF),
+        ];
+        let total = fx_rates.aggregate(&positions, Currency::EUR).unwrap();
+        assert_eq!(total.currency, Currency::EUR);
+        assert_approx_eq!(total.amount, 100.0 - 50.0 / 1.1 + 40.0 * 1.25 / 1.1, 1e-12);
+
+        let missing = fx_rates.aggregate(&[Money::new(1.0, Currency::JPY)], Currency::EUR);
+        assert_eq!(missing, Err(FxError::MissingRate(Currency::JPY)));
+        assert_eq!(missing.unwrap_err().to_string(), "missing fx rate for JPY");
+    }
+}
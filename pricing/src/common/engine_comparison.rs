@@ -0,0 +1,191 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::analytic::black_scholes::{BlackScholesMerton, OptionPrice};
+use crate::common::models::{DerivativeParameter, ExerciseType};
+use crate::lattice::trinomial_tree::TrinomialTree;
+use crate::simulation::products::european_option::MonteCarloEuropeanOption;
+
+/// A pricing engine with its discretization settings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PricingEngine {
+    Analytic,
+    TrinomialTree { nr_steps: usize },
+    MonteCarlo { nr_paths: usize, nr_steps: usize },
+}
+
+impl fmt::Display for PricingEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PricingEngine::Analytic => write!(f, "analytic"),
+            PricingEngine::TrinomialTree { nr_steps } => write!(f, "tree({nr_steps} steps)"),
+            PricingEngine::MonteCarlo { nr_paths, nr_steps } => {
+                write!(f, "mc({nr_paths} paths, {nr_steps} steps)")
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct EngineResult {
+    pub engine: PricingEngine,
+    pub value: Option<f64>,
+    /// the difference to the analytic reference value
+    pub difference: Option<f64>,
+    pub runtime: Duration,
+}
+
+/// The values of one product on several engines compared to the analytic value.
+#[derive(Clone, Debug)]
+pub struct ConvergenceReport {
+    pub reference: f64,
+    pub results: Vec<EngineResult>,
+}
+
+impl ConvergenceReport {
+    /// The largest absolute difference to the reference over all engines with a value.
+    pub fn max_abs_difference(&self) -> f64 {
+        self.results
+            .iter()
+            .filter_map(|r| r.difference)
+            .fold(0.0, |acc, d| acc.max(d.abs()))
+    }
+
+    pub fn result(&self, engine: &PricingEngine) -> Option<&EngineResult> {
+        self.results.iter().find(|r| &r.engine == engine)
+    }
+}
+
+impl fmt::Display for ConvergenceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "reference {:.6}", self.reference)?;
+        for result in &self.results {
+            let format = |x: Option<f64>| x.map_or("-".to_string(), |x| format!("{x:.6}"));
+            writeln!(
+                f,
+                "{:<32} {:>12} {:>12} {:>10.3?}",
+                result.engine.to_string(),
+                format(result.value),
+                format(result.difference),
+                result.runtime
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Prices a European vanilla on all available engines (analytic, trinomial tree, Monte Carlo) to assess
+/// the discretization settings against the Black-Scholes value.
+pub struct EngineComparison {
+    option_params: DerivativeParameter,
+    exercise_type: ExerciseType,
+    engines: Vec<PricingEngine>,
+    seed_nr: u64,
+}
+
+impl EngineComparison {
+    pub fn new(
+        option_params: DerivativeParameter,
+        exercise_type: ExerciseType,
+        seed_nr: u64,
+    ) -> Self {
+        Self {
+            option_params,
+            exercise_type,
+            engines: vec![PricingEngine::Analytic],
+            seed_nr,
+        }
+    }
+
+    pub fn with_engine(mut self, engine: PricingEngine) -> Self {
+        self.engines.push(engine);
+        self
+    }
+
+    fn analytic_value(&self) -> f64 {
+        match self.exercise_type {
+            ExerciseType::Call => BlackScholesMerton::call(&self.option_params),
+            ExerciseType::Put => BlackScholesMerton::put(&self.option_params),
+        }
+    }
+
+    fn value<SeedRng>(&self, engine: &PricingEngine) -> Option<f64>
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        let dp = &self.option_params;
+        match *engine {
+            PricingEngine::Analytic => Some(self.analytic_value()),
+            PricingEngine::TrinomialTree { nr_steps } => {
+                Some(TrinomialTree::new(nr_steps).price(dp, &self.exercise_type, false))
+            }
+            PricingEngine::MonteCarlo { nr_paths, nr_steps } => {
+                let mc_option: MonteCarloEuropeanOption<SeedRng> = MonteCarloEuropeanOption::new(
+                    dp.asset_price,
+                    dp.strike,
+                    dp.time_to_expiration,
+                    dp.rfr,
+                    dp.vola,
+                    nr_paths,
+                    nr_steps,
+                    self.seed_nr,
+                );
+                mc_option
+                    .price(&self.exercise_type)
+                    .ok()
+                    .map(|result| result.value)
+            }
+        }
+    }
+
+    pub fn run<SeedRng>(&self) -> ConvergenceReport
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        let reference = self.analytic_value();
+        let results = self
+            .engines
+            .iter()
+            .map(|engine| {
+                let start = Instant::now();
+                let value = self.value::<SeedRng>(engine);
+                EngineResult {
+                    engine: *engine,
+                    value,
+                    difference: value.map(|v| v - reference),
+                    runtime: start.elapsed(),
+                }
+            })
+            .collect();
+        ConvergenceReport { reference, results }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn engines_agree_on_vanilla_call() {
+        let dp = DerivativeParameter::new(100.0, 105.0, 1.0, 0.03, 0.2);
+        let tree = PricingEngine::TrinomialTree { nr_steps: 200 };
+        let mc = PricingEngine::MonteCarlo {
+            nr_paths: 20_000,
+            nr_steps: 10,
+        };
+        let report = EngineComparison::new(dp, ExerciseType::Call, 42)
+            .with_engine(tree)
+            .with_engine(mc)
+            .run::<rand_hc::Hc128Rng>();
+
+        assert_eq!(report.results.len(), 3);
+        assert_eq!(
+            report.result(&PricingEngine::Analytic).unwrap().difference,
+            Some(0.0)
+        );
+        assert_approx_eq!(report.result(&tree).unwrap().difference.unwrap(), 0.0, 2e-2);
+        assert!(report.max_abs_difference() < 0.3);
+        assert!(report.to_string().contains("tree(200 steps)"));
+    }
+}
@@ -0,0 +1,232 @@
+use std::fmt::Write;
+
+use ndarray::Array2;
+
+use crate::common::vol_surface::VolatilitySurface;
+use crate::curves::yield_curve::YieldCurve;
+
+/// A yield curve sampled at maturities, e.g. to plot a calibrated curve in a notebook.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CurveGrid {
+    pub times: Vec<f64>,
+    pub zero_rates: Vec<f64>,
+    pub discount_factors: Vec<f64>,
+}
+
+impl CurveGrid {
+    pub fn sample(curve: &(impl YieldCurve + ?Sized), times: &[f64]) -> Self {
+        Self {
+            times: times.to_vec(),
+            zero_rates: times.iter().map(|t| curve.zero_rate(*t)).collect(),
+            discount_factors: curve.discount_factors(times),
+        }
+    }
+
+    /// The grid as CSV with a header and one line per maturity.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("time,zero_rate,discount_factor\n");
+        for ((t, rate), df) in self
+            .times
+            .iter()
+            .zip(&self.zero_rates)
+            .zip(&self.discount_factors)
+        {
+            writeln!(csv, "{t},{rate},{df}").unwrap();
+        }
+        csv
+    }
+
+    /// The grid as a JSON object of the columns, with non-finite numbers as null.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        serde_json::json!({
+            "times": self.times,
+            "zero_rates": self.zero_rates,
+            "discount_factors": self.discount_factors,
+        })
+        .to_string()
+    }
+
+    /// The zero rates by the maturity as an SVG line chart.
+    #[cfg(feature = "svg")]
+    pub fn to_svg(&self, width: f64, height: f64) -> String {
+        let points: Vec<(f64, f64)> = self
+            .times
+            .iter()
+            .copied()
+            .zip(self.zero_rates.iter().copied())
+            .collect();
+        svg_line_chart(&[points], width, height)
+    }
+}
+
+/// The effective volatilities of a surface sampled at maturities and strikes, `vols[[k, l]]` for the
+/// time $t_k$ and the strike $K_l$, e.g. to inspect a calibrated surface for arbitrage or artifacts.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SurfaceGrid {
+    pub times: Vec<f64>,
+    pub strikes: Vec<f64>,
+    pub vols: Array2<f64>,
+}
+
+impl SurfaceGrid {
+    pub fn sample(surface: &VolatilitySurface, times: &[f64], strikes: &[f64]) -> Self {
+        let vols = Array2::from_shape_fn((times.len(), strikes.len()), |(k, l)| {
+            surface.effective_vola(times[k], strikes[l])
+        });
+        Self {
+            times: times.to_vec(),
+            strikes: strikes.to_vec(),
+            vols,
+        }
+    }
+
+    /// The grid as CSV with a header and one line per maturity and strike.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("time,strike,vola\n");
+        for (k, t) in self.times.iter().enumerate() {
+            for (l, strike) in self.strikes.iter().enumerate() {
+                writeln!(csv, "{t},{strike},{}", self.vols[[k, l]]).unwrap();
+            }
+        }
+        csv
+    }
+
+    /// The grid as a JSON object with the volatilities as rows by the maturity, with non-finite
+    /// numbers as null.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        let rows: Vec<Vec<f64>> = self
+            .vols
+            .rows()
+            .into_iter()
+            .map(|row| row.to_vec())
+            .collect();
+        serde_json::json!({
+            "times": self.times,
+            "strikes": self.strikes,
+            "vols": rows,
+        })
+        .to_string()
+    }
+
+    /// The smiles of the maturities, i.e. the volatilities by the strike, as an SVG line chart.
+    #[cfg(feature = "svg")]
+    pub fn to_svg(&self, width: f64, height: f64) -> String {
+        let smiles: Vec<Vec<(f64, f64)>> = self
+            .vols
+            .rows()
+            .into_iter()
+            .map(|row| {
+                self.strikes
+                    .iter()
+                    .copied()
+                    .zip(row.iter().copied())
+                    .collect()
+            })
+            .collect();
+        svg_line_chart(&smiles, width, height)
+    }
+}
+
+/// The series as polylines in a chart of the size, scaled to the finite range of all points, and an
+/// empty chart without finite points.
+#[cfg(feature = "svg")]
+fn svg_line_chart(series: &[Vec<(f64, f64)>], width: f64, height: f64) -> String {
+    const COLORS: [&str; 6] = [
+        "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b",
+    ];
+    let finite = || {
+        series
+            .iter()
+            .flatten()
+            .filter(|(x, y)| x.is_finite() && y.is_finite())
+    };
+    let range = |coordinate: fn(&&(f64, f64)) -> f64| {
+        let (min, max) = finite()
+            .map(|point| coordinate(&point))
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+                (min.min(v), max.max(v))
+            });
+        // a constant series is drawn in the middle
+        if min < max {
+            (min, max)
+        } else {
+            (min - 0.5, min + 0.5)
+        }
+    };
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    svg.push('\n');
+    if finite().next().is_none() {
+        svg.push_str("</svg>\n");
+        return svg;
+    }
+    let (x_min, x_max) = range(|point| point.0);
+    let (y_min, y_max) = range(|point| point.1);
+
+    for (idx, points) in series.iter().enumerate() {
+        let coordinates: Vec<String> = points
+            .iter()
+            .filter(|(x, y)| x.is_finite() && y.is_finite())
+            .map(|(x, y)| {
+                let px = (x - x_min) / (x_max - x_min) * width;
+                let py = height - (y - y_min) / (y_max - y_min) * height;
+                format!("{px:.2},{py:.2}")
+            })
+            .collect();
+        writeln!(
+            svg,
+            r#"<polyline fill="none" stroke="{}" points="{}"/>"#,
+            COLORS[idx % COLORS.len()],
+            coordinates.join(" ")
+        )
+        .unwrap();
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curves::yield_curve::FlatCurve;
+    use crate::pde::grid::uniform_grid;
+    use ndarray::arr2;
+
+    #[test]
+    fn curve_and_surface_export() {
+        let curve = CurveGrid::sample(&FlatCurve::new(0.02), &uniform_grid(0.0, 2.0, 3));
+        assert_eq!(curve.times, vec![0.0, 1.0, 2.0]);
+        let csv = curve.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "time,zero_rate,discount_factor");
+        assert_eq!(lines[1], "0,0.02,1");
+        assert_eq!(lines.len(), 4);
+        #[cfg(feature = "serde")]
+        assert!(curve.to_json().starts_with(r#"{"discount_factors":[1.0,"#));
+
+        let surface = VolatilitySurface::new(
+            vec![1.0, 2.0],
+            vec![90.0, 110.0],
+            arr2(&[[0.25, 0.2], [0.25, 0.2]]),
+        );
+        let grid = SurfaceGrid::sample(&surface, &[1.0, 2.0], &[90.0, 100.0, 110.0]);
+        assert!((grid.vols[[1, 1]] - 0.225).abs() < 1e-15);
+        assert_eq!(grid.to_csv().lines().count(), 1 + 6);
+        // the effective volatility until 0 is undefined
+        let undefined = SurfaceGrid::sample(&surface, &[0.0], &[90.0]);
+        assert!(undefined.vols[[0, 0]].is_nan());
+        #[cfg(feature = "serde")]
+        assert_eq!(
+            undefined.to_json(),
+            r#"{"strikes":[90.0],"times":[0.0],"vols":[[null]]}"#
+        );
+        #[cfg(feature = "svg")]
+        {
+            assert_eq!(grid.to_svg(400.0, 300.0).matches("<polyline").count(), 2);
+            assert!(!undefined.to_svg(400.0, 300.0).contains("<polyline"));
+        }
+    }
+}
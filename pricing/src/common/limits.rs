@@ -0,0 +1,453 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::common::market_context::MarketSnapshot;
+use crate::common::pricer::{PricerRegistry, Product};
+use crate::common::results::{GreeksResult, PricingError, VarResult};
+
+/// The relative bump of the spots of the delta and gamma of a priced book.
+const SPOT_BUMP: f64 = 0.01;
+
+/// A risk figure of a book which is limited.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RiskMeasure {
+    Delta,
+    Gamma,
+    Vega,
+    Theta,
+    Rho,
+    ValueAtRisk,
+}
+
+/// The aggregated risk of a book, i.e. the greeks of its positions weighted by their quantities and
+/// the VaR of its P&L scenarios.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BookRisk {
+    pub book: String,
+    pub greeks: GreeksResult,
+    pub var: Option<VarResult>,
+}
+
+impl BookRisk {
+    /// The risk of the positions, given by their quantities and unit greeks, where a greek is None if
+    /// any position lacks it.
+    pub fn aggregate(book: &str, positions: impl IntoIterator<Item = (f64, GreeksResult)>) -> Self {
+        let add = |total: Option<f64>, quantity: f64, greek: Option<f64>| {
+            Some(total? + quantity * greek?)
+        };
+        let greeks = positions.into_iter().fold(
+            GreeksResult {
+                value: 0.0,
+                delta: Some(0.0),
+                gamma: Some(0.0),
+                vega: Some(0.0),
+                theta: Some(0.0),
+                rho: Some(0.0),
+            },
+            |total, (quantity, greeks)| GreeksResult {
+                value: total.value + quantity * greeks.value,
+                delta: add(total.delta, quantity, greeks.delta),
+                gamma: add(total.gamma, quantity, greeks.gamma),
+                vega: add(total.vega, quantity, greeks.vega),
+                theta: add(total.theta, quantity, greeks.theta),
+                rho: add(total.rho, quantity, greeks.rho),
+            },
+        );
+        Self {
+            book: book.to_string(),
+            greeks,
+            var: None,
+        }
+    }
+
+    /// The risk of the positions, given by their quantities and products, priced by the registry: the
+    /// value, and the delta and gamma by central differences of the prices of the book with the spot of
+    /// each underlying bumped by 1%. The other greeks are unavailable. Fails with the first failing price.
+    pub fn from_priced_book(
+        book: &str,
+        positions: &[(f64, Product)],
+        registry: &PricerRegistry,
+        market: &MarketSnapshot,
+    ) -> Result<Self, PricingError> {
+        let products: Vec<Product> = positions
+            .iter()
+            .map(|(_, product)| product.clone())
+            .collect();
+        let mut greeks = registry
+            .price_book(&products, market)
+            .into_iter()
+            .map(|result| {
+                Ok(GreeksResult {
+                    value: result?.value,
+                    ..Default::default()
+                })
+            })
+            .collect::<Result<Vec<GreeksResult>, PricingError>>()?;
+
+        let underlyings: BTreeSet<&str> = products.iter().map(Product::underlying).collect();
+        for underlying in underlyings {
+            let spot = market
+                .spot(underlying)
+                .ok_or_else(|| PricingError::MissingMarketData(format!("spot of {underlying}")))?;
+            let (indices, sub_book): (Vec<usize>, Vec<Product>) = products
+                .iter()
+                .enumerate()
+                .filter(|(_, product)| product.underlying() == underlying)
+                .map(|(i, product)| (i, product.clone()))
+                .unzip();
+            let bumped_prices = |bump: f64| -> Result<Vec<f64>, PricingError> {
+                let bumped = market.clone().with_spot(underlying, spot * (1.0 + bump));
+                registry
+                    .price_book(&sub_book, &bumped)
+                    .into_iter()
+                    .map(|result| Ok(result?.value))
+                    .collect()
+            };
+            let (up, down) = (bumped_prices(SPOT_BUMP)?, bumped_prices(-SPOT_BUMP)?);
+            let h = SPOT_BUMP * spot;
+            for (k, i) in indices.into_iter().enumerate() {
+                let value = greeks[i].value;
+                greeks[i].delta = Some((up[k] - down[k]) / (2.0 * h));
+                greeks[i].gamma = Some((up[k] - 2.0 * value + down[k]) / (h * h));
+            }
+        }
+        Ok(Self::aggregate(
+            book,
+            positions.iter().map(|(quantity, _)| *quantity).zip(greeks),
+        ))
+    }
+
+    pub fn with_var(self, var: VarResult) -> Self {
+        Self {
+            var: Some(var),
+            ..self
+        }
+    }
+
+    /// The usage of the limits of the measure, i.e. the absolute greek or the VaR, if available.
+    pub fn usage(&self, measure: RiskMeasure) -> Option<f64> {
+        let figure = match measure {
+            RiskMeasure::Delta => self.greeks.delta,
+            RiskMeasure::Gamma => self.greeks.gamma,
+            RiskMeasure::Vega => self.greeks.vega,
+            RiskMeasure::Theta => self.greeks.theta,
+            RiskMeasure::Rho => self.greeks.rho,
+            RiskMeasure::ValueAtRisk => self.var.map(|var| var.value_at_risk),
+        };
+        figure.map(f64::abs)
+    }
+}
+
+/// The limit of a measure of a book: a usage above the soft threshold is escalated, above the hard
+/// threshold it requires reducing the risk.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Limit {
+    pub book: String,
+    pub measure: RiskMeasure,
+    pub soft: f64,
+    pub hard: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LimitStatus {
+    Within,
+    SoftBreach,
+    HardBreach,
+    /// the book or its measure is not available, e.g. an engine without vega
+    Unavailable,
+}
+
+/// The usage of a limit with its headroom $hard - usage$, negative on a hard breach.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LimitCheck {
+    pub limit: Limit,
+    pub usage: Option<f64>,
+    pub status: LimitStatus,
+}
+
+impl LimitCheck {
+    pub fn headroom(&self) -> Option<f64> {
+        Some(self.limit.hard - self.usage?)
+    }
+
+    /// The usage relative to the hard threshold.
+    pub fn utilization(&self) -> Option<f64> {
+        Some(self.usage? / self.limit.hard)
+    }
+}
+
+/// The checks of all limits, in the order of their definition.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BreachReport {
+    pub checks: Vec<LimitCheck>,
+}
+
+impl BreachReport {
+    /// The checks of breached or unavailable limits, the most severe first.
+    pub fn breaches(&self) -> Vec<&LimitCheck> {
+        let mut breaches: Vec<&LimitCheck> = self
+            .checks
+            .iter()
+            .filter(|check| check.status != LimitStatus::Within)
+            .collect();
+        breaches.sort_by_key(|check| match check.status {
+            LimitStatus::HardBreach => 0,
+            LimitStatus::Unavailable => 1,
+            _ => 2,
+        });
+        breaches
+    }
+
+    pub fn has_hard_breach(&self) -> bool {
+        self.checks
+            .iter()
+            .any(|check| check.status == LimitStatus::HardBreach)
+    }
+
+    pub fn of_book<'a>(&'a self, book: &'a str) -> impl Iterator<Item = &'a LimitCheck> + 'a {
+        self.checks
+            .iter()
+            .filter(move |check| check.limit.book == book)
+    }
+}
+
+impl fmt::Display for BreachReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let format = |x: Option<f64>| x.map_or("-".to_string(), |x| format!("{x:.4}"));
+        writeln!(
+            f,
+            "{:<16} {:<12} {:>14} {:>14} {:>14} {:>14} status",
+            "book", "measure", "usage", "soft", "hard", "headroom"
+        )?;
+        for check in &self.checks {
+            writeln!(
+                f,
+                "{:<16} {:<12} {:>14} {:>14.4} {:>14.4} {:>14} {:?}",
+                check.limit.book,
+                format!("{:?}", check.limit.measure),
+                format(check.usage),
+                check.limit.soft,
+                check.limit.hard,
+                format(check.headroom()),
+                check.status
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The limits of the books, which are evaluated against their current risk.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LimitSet {
+    pub limits: Vec<Limit>,
+}
+
+impl LimitSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the limit of the measure of the book with the soft and hard thresholds, which requires
+    /// $0 \leq soft \leq hard$.
+    pub fn with_limit(
+        mut self,
+        book: &str,
+        measure: RiskMeasure,
+        soft: f64,
+        hard: f64,
+    ) -> Result<Self, PricingError> {
+        PricingError::check(soft >= 0.0, "soft limit", soft)?;
+        PricingError::check(hard >= soft, "hard limit", hard)?;
+        self.limits.push(Limit {
+            book: book.to_string(),
+            measure,
+            soft,
+            hard,
+        });
+        Ok(self)
+    }
+
+    /// The usage of each limit by the risk of its book, where a NaN usage, e.g. of a failed greek, is
+    /// unavailable and an infinite usage a hard breach.
+    pub fn evaluate(&self, risks: &[BookRisk]) -> BreachReport {
+        let checks = self
+            .limits
+            .iter()
+            .map(|limit| {
+                let usage = risks
+                    .iter()
+                    .find(|risk| risk.book == limit.book)
+                    .and_then(|risk| risk.usage(limit.measure))
+                    .filter(|usage| !usage.is_nan());
+                let status = match usage {
+                    None => LimitStatus::Unavailable,
+                    Some(usage) if usage > limit.hard => LimitStatus::HardBreach,
+                    Some(usage) if usage > limit.soft => LimitStatus::SoftBreach,
+                    Some(_) => LimitStatus::Within,
+                };
+                LimitCheck {
+                    limit: limit.clone(),
+                    usage,
+                    status,
+                }
+            })
+            .collect();
+        BreachReport { checks }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::BsmComputation;
+    use crate::common::models::{DerivativeParameter, ExerciseType};
+    use crate::common::pricer::VanillaOption;
+    use crate::common::vol_surface::VolatilitySurface;
+    use crate::curves::yield_curve::FlatCurve;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn limit_breaches() {
+        let call = BsmComputation::new(&DerivativeParameter::new(100.0, 100.0, 1.0, 0.03, 0.2))
+            .greeks(&ExerciseType::Call);
+        let put = BsmComputation::new(&DerivativeParameter::new(100.0, 90.0, 1.0, 0.03, 0.2))
+            .greeks(&ExerciseType::Put);
+        let options = BookRisk::aggregate("options", [(1_000.0, call), (-500.0, put)]);
+        assert_approx_eq!(
+            options.greeks.delta.unwrap(),
+            1_000.0 * call.delta.unwrap() - 500.0 * put.delta.unwrap(),
+            1e-9
+        );
+        let pnl: Vec<f64> = (0..100).map(|i| i as f64 - 50.0).collect();
+        let options = options.with_var(VarResult::from_pnl(&pnl, 0.99).unwrap());
+        let delta_one = BookRisk::aggregate(
+            "delta one",
+            [(
+                1.0,
+                GreeksResult {
+                    value: 100.0,
+                    delta: Some(1.0),
+                    ..Default::default()
+                },
+            )],
+        );
+
+        let limits = LimitSet::new()
+            .with_limit("options", RiskMeasure::Delta, 500.0, 1_000.0)
+            .and_then(|limits| {
+                limits.with_limit("options", RiskMeasure::Vega, 100_000.0, 200_000.0)
+            })
+            .and_then(|limits| limits.with_limit("options", RiskMeasure::ValueAtRisk, 40.0, 60.0))
+            .and_then(|limits| limits.with_limit("delta one", RiskMeasure::Delta, 10.0, 20.0))
+            .and_then(|limits| limits.with_limit("delta one", RiskMeasure::Vega, 10.0, 20.0))
+            .unwrap();
+        let report = limits.evaluate(&[options, delta_one]);
+        let statuses: Vec<LimitStatus> = report.checks.iter().map(|check| check.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                LimitStatus::SoftBreach,
+                LimitStatus::Within,
+                LimitStatus::SoftBreach,
+                LimitStatus::Within,
+                LimitStatus::Unavailable
+            ]
+        );
+        assert!(!report.has_hard_breach());
+        assert_eq!(report.breaches()[0].status, LimitStatus::Unavailable);
+        assert_approx_eq!(report.checks[2].headroom().unwrap(), 60.0 - 49.0, 1e-12);
+        assert_eq!(report.of_book("delta one").count(), 2);
+        assert_eq!(report.to_string().lines().count(), 6);
+
+        let tight = LimitSet::new()
+            .with_limit("options", RiskMeasure::ValueAtRisk, 10.0, 20.0)
+            .unwrap();
+        let report = tight
+            .evaluate(&[BookRisk::aggregate("options", [])
+                .with_var(VarResult::from_pnl(&pnl, 0.99).unwrap())]);
+        assert!(report.has_hard_breach());
+        assert!(report.checks[0].utilization().unwrap() > 1.0);
+
+        // a failed greek is unavailable, an infinite one a hard breach
+        let limits = LimitSet::new()
+            .with_limit("options", RiskMeasure::Delta, 10.0, 20.0)
+            .and_then(|limits| limits.with_limit("options", RiskMeasure::Gamma, 10.0, 20.0))
+            .unwrap();
+        let failed = BookRisk::aggregate(
+            "options",
+            [(
+                1.0,
+                GreeksResult {
+                    delta: Some(f64::NAN),
+                    gamma: Some(f64::INFINITY),
+                    ..Default::default()
+                },
+            )],
+        );
+        let statuses: Vec<LimitStatus> = limits
+            .evaluate(&[failed])
+            .checks
+            .iter()
+            .map(|check| check.status)
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![LimitStatus::Unavailable, LimitStatus::HardBreach]
+        );
+        for (soft, hard) in [(-1.0, 1.0), (2.0, 1.0), (f64::NAN, 1.0)] {
+            assert!(LimitSet::new()
+                .with_limit("options", RiskMeasure::Delta, soft, hard)
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn risk_of_priced_book() {
+        let market = MarketSnapshot::new(0.0)
+            .with_spot("ABC", 100.0)
+            .with_curve("USD", FlatCurve::new(0.03))
+            .with_vol_surface("ABC", VolatilitySurface::flat(0.2));
+        let call = VanillaOption::new("ABC", "USD", 100.0, 1.0, ExerciseType::Call);
+        let forward = Product::Forward {
+            underlying: "ABC".to_string(),
+            curve: "USD".to_string(),
+            strike: 100.0,
+            expiry: 1.0,
+        };
+        let positions = [(10.0, Product::Option(call)), (-5.0, forward)];
+        let risk =
+            BookRisk::from_priced_book("options", &positions, &PricerRegistry::default(), &market)
+                .unwrap();
+        let bsm = BsmComputation::new(&DerivativeParameter::new(100.0, 100.0, 1.0, 0.03, 0.2));
+        assert_approx_eq!(
+            risk.greeks.value,
+            10.0 * bsm.call() - 5.0 * (100.0 - 100.0 * (-0.03_f64).exp()),
+            1e-9
+        );
+        assert_approx_eq!(
+            risk.greeks.delta.unwrap(),
+            10.0 * bsm.delta(&ExerciseType::Call) - 5.0,
+            1e-3
+        );
+        assert_approx_eq!(risk.greeks.gamma.unwrap(), 10.0 * bsm.gamma(), 1e-4);
+        assert_eq!(risk.usage(RiskMeasure::Vega), None);
+
+        let unknown = [(
+            1.0,
+            Product::Option(VanillaOption::new(
+                "XYZ",
+                "USD",
+                100.0,
+                1.0,
+                ExerciseType::Call,
+            )),
+        )];
+        assert!(BookRisk::from_priced_book(
+            "options",
+            &unknown,
+            &PricerRegistry::default(),
+            &market
+        )
+        .is_err());
+    }
+}
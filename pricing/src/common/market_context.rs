@@ -0,0 +1,250 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use crate::common::market_data::MarketDataSet;
+use crate::common::results::PricingError;
+use crate::common::vol_surface::VolatilitySurface;
+use crate::curves::yield_curve::YieldCurve;
+
+/// An immutable state of the market: spots, yield curves and volatility surfaces by name at the
+/// valuation time (in years). Pricers borrow a snapshot, which shares the curves and surfaces with
+/// the other snapshots, instead of copying them.
+#[derive(Clone)]
+pub struct MarketSnapshot {
+    pub time: f64,
+    /// increases with each update of the `MarketContext`
+    pub version: u64,
+    spots: BTreeMap<String, f64>,
+    curves: BTreeMap<String, Arc<dyn YieldCurve + Send + Sync>>,
+    vol_surfaces: BTreeMap<String, Arc<VolatilitySurface>>,
+}
+
+impl MarketSnapshot {
+    pub fn new(time: f64) -> Self {
+        Self {
+            time,
+            version: 0,
+            spots: BTreeMap::new(),
+            curves: BTreeMap::new(),
+            vol_surfaces: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_time(mut self, time: f64) -> Self {
+        self.time = time;
+        self
+    }
+
+    pub fn with_spot(mut self, underlying: &str, spot: f64) -> Self {
+        self.spots.insert(underlying.to_string(), spot);
+        self
+    }
+
+    pub fn with_curve(
+        mut self,
+        name: &str,
+        curve: impl YieldCurve + Send + Sync + 'static,
+    ) -> Self {
+        self.curves.insert(name.to_string(), Arc::new(curve));
+        self
+    }
+
+    pub fn with_vol_surface(mut self, underlying: &str, surface: VolatilitySurface) -> Self {
+        self.vol_surfaces
+            .insert(underlying.to_string(), Arc::new(surface));
+        self
+    }
+
+    pub fn spot(&self, underlying: &str) -> Option<f64> {
+        self.spots.get(underlying).copied()
+    }
+
+    pub fn curve(&self, name: &str) -> Option<&(dyn YieldCurve + Send + Sync)> {
+        self.curves.get(name).map(|c| c.as_ref())
+    }
+
+    /// The curve shared with the snapshot, e.g. to build a deformed curve on top of it.
+    pub fn shared_curve(&self, name: &str) -> Option<Arc<dyn YieldCurve + Send + Sync>> {
+        self.curves.get(name).cloned()
+    }
+
+    pub fn vol_surface(&self, underlying: &str) -> Option<&VolatilitySurface> {
+        self.vol_surfaces.get(underlying).map(|s| s.as_ref())
+    }
+
+    /// Checks that the market holds the spot of the underlying, the curve up to the maturity (in years
+    /// from the valuation time) and, if required, the volatility surface of the underlying, such that
+    /// pricers fail with the missing data before they start computing.
+    pub fn check_market_data(
+        &self,
+        underlying: &str,
+        curve: &str,
+        maturity: f64,
+        requires_vol_surface: bool,
+    ) -> Result<(), PricingError> {
+        if self.spot(underlying).is_none() {
+            return Err(PricingError::MissingMarketData(format!(
+                "the spot of {underlying}"
+            )));
+        }
+        let yield_curve = self
+            .curve(curve)
+            .ok_or_else(|| PricingError::MissingMarketData(format!("the curve {curve}")))?;
+        if let Some(horizon) = yield_curve.horizon().filter(|horizon| maturity > *horizon) {
+            return Err(PricingError::CurveHorizonExceeded {
+                curve: curve.to_string(),
+                maturity,
+                horizon,
+            });
+        }
+        if requires_vol_surface && self.vol_surface(underlying).is_none() {
+            return Err(PricingError::MissingMarketData(format!(
+                "the volatility surface of {underlying}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// The flat market data of an option on the underlying with the maturity (in years from the
+    /// valuation time) and strike: the zero rate of the curve and the effective vola of the surface.
+    pub fn market_data_set(
+        &self,
+        underlying: &str,
+        curve: &str,
+        maturity: f64,
+        strike: f64,
+    ) -> Option<MarketDataSet> {
+        let spot = self.spot(underlying)?;
+        let rfr = self.curve(curve)?.zero_rate(maturity);
+        let vola = self
+            .vol_surface(underlying)?
+            .effective_vola(maturity, strike);
+        Some(MarketDataSet::new(self.time, spot, vola, rfr))
+    }
+}
+
+impl fmt::Debug for MarketSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MarketSnapshot")
+            .field("time", &self.time)
+            .field("version", &self.version)
+            .field("spots", &self.spots)
+            .field("curves", &self.curves.keys().collect::<Vec<_>>())
+            .field(
+                "vol_surfaces",
+                &self.vol_surfaces.keys().collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+/// The current market of a long-running service. Updates atomically swap in a new snapshot, while
+/// in-flight pricings keep the consistent snapshot they started with.
+#[derive(Debug)]
+pub struct MarketContext {
+    current: RwLock<Arc<MarketSnapshot>>,
+}
+
+impl MarketContext {
+    pub fn new(snapshot: MarketSnapshot) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(snapshot)),
+        }
+    }
+
+    /// The current snapshot, unaffected by later updates.
+    pub fn snapshot(&self) -> Arc<MarketSnapshot> {
+        // a snapshot is swapped in whole, such that a panicked writer leaves a consistent one
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Replaces the current snapshot by the update of it, e.g. a bumped spot or a rebuilt curve.
+    /// Concurrent updates are serialized, such that none is lost. Returns the new version.
+    pub fn update(&self, update: impl FnOnce(&MarketSnapshot) -> MarketSnapshot) -> u64 {
+        let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+        let mut snapshot = update(&current);
+        snapshot.version = current.version + 1;
+        let version = snapshot.version;
+        *current = Arc::new(snapshot);
+        version
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::BsmComputation;
+    use crate::common::models::DerivativeParameter;
+    use crate::curves::yield_curve::FlatCurve;
+
+    fn call_price(snapshot: &MarketSnapshot, strike: f64, maturity: f64) -> f64 {
+        let market = snapshot
+            .market_data_set("ABC", "USD", maturity, strike)
+            .unwrap();
+        let dp = DerivativeParameter::new(market.spot, strike, maturity, market.rfr, market.vola);
+        BsmComputation::new(&dp).call()
+    }
+
+    #[test]
+    fn snapshot_isolation() {
+        let context = MarketContext::new(
+            MarketSnapshot::new(0.0)
+                .with_spot("ABC", 100.0)
+                .with_curve("USD", FlatCurve::new(0.03))
+                .with_vol_surface("ABC", VolatilitySurface::flat(0.2)),
+        );
+
+        let in_flight = context.snapshot();
+        let price = call_price(&in_flight, 100.0, 1.0);
+        let version = context.update(|s| s.clone().with_spot("ABC", 110.0));
+        assert_eq!(version, 1);
+
+        assert_eq!(call_price(&in_flight, 100.0, 1.0), price);
+        assert_eq!(in_flight.version, 0);
+        let updated = context.snapshot();
+        assert!(call_price(&updated, 100.0, 1.0) > price);
+        // the unchanged surface is shared, not copied
+        assert!(std::ptr::eq(
+            in_flight.vol_surface("ABC").unwrap(),
+            updated.vol_surface("ABC").unwrap()
+        ));
+        assert!(updated.market_data_set("XYZ", "USD", 1.0, 100.0).is_none());
+    }
+
+    #[test]
+    fn concurrent_updates() {
+        let context = MarketContext::new(MarketSnapshot::new(0.0).with_spot("ABC", 0.0));
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        let snapshot = context.snapshot();
+                        // spot and version are updated together
+                        assert_eq!(snapshot.spot("ABC").unwrap(), snapshot.version as f64);
+                        context.update(|s| {
+                            let spot = s.spot("ABC").unwrap();
+                            s.clone().with_spot("ABC", spot + 1.0)
+                        });
+                    }
+                });
+            }
+        });
+        let snapshot = context.snapshot();
+        assert_eq!(snapshot.version, 400);
+        assert_eq!(snapshot.spot("ABC"), Some(400.0));
+
+        // an update panicking while holding the lock leaves the snapshot unchanged and usable
+        let failed = std::thread::scope(|scope| {
+            scope
+                .spawn(|| context.update(|_| panic!("the curve does not build")))
+                .join()
+        });
+        assert!(failed.is_err());
+        assert_eq!(context.snapshot().version, 400);
+        assert_eq!(context.update(|s| s.clone()), 401);
+    }
+}
@@ -0,0 +1,36 @@
+/// A snapshot of the market data of a single underlying at the valuation time (in years),
+/// e.g. the end-of-day data of consecutive business days.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MarketDataSet {
+    pub time: f64,
+    pub spot: f64,
+    pub vola: f64,
+    pub rfr: f64,
+}
+
+impl MarketDataSet {
+    pub fn new(time: f64, spot: f64, vola: f64, rfr: f64) -> Self {
+        Self {
+            time,
+            spot,
+            vola,
+            rfr,
+        }
+    }
+
+    pub fn with_time(self, time: f64) -> Self {
+        Self { time, ..self }
+    }
+
+    pub fn with_spot(self, spot: f64) -> Self {
+        Self { spot, ..self }
+    }
+
+    pub fn with_vola(self, vola: f64) -> Self {
+        Self { vola, ..self }
+    }
+
+    pub fn with_rfr(self, rfr: f64) -> Self {
+        Self { rfr, ..self }
+    }
+}
@@ -0,0 +1,291 @@
+use std::sync::Arc;
+
+use ndarray::Array2;
+
+use crate::common::market_context::MarketSnapshot;
+use crate::common::results::PricingError;
+use crate::common::vol_surface::VolatilitySurface;
+use crate::curves::yield_curve::YieldCurve;
+
+const BASIS_POINT: f64 = 1e-4;
+const VOL_POINT: f64 = 0.01;
+
+/// The linear interpolation of the values at the increasing nodes, flat beyond the first and last node.
+fn piecewise_linear(nodes: &[f64], values: &[f64], x: f64) -> f64 {
+    let idx = nodes.partition_point(|node| *node <= x);
+    if idx == 0 {
+        return values[0];
+    }
+    if idx == nodes.len() {
+        return values[idx - 1];
+    }
+    let weight = (x - nodes[idx - 1]) / (nodes[idx] - nodes[idx - 1]);
+    (1.0 - weight) * values[idx - 1] + weight * values[idx]
+}
+
+/// A deformation of the zero rates of a curve by the maturity, given by the shifts in basis points at
+/// the tenors (in years), interpolated linearly between and flat beyond the tenors.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CurveShift {
+    tenors: Vec<f64>,
+    shifts_bp: Vec<f64>,
+}
+
+impl CurveShift {
+    pub fn new(tenors: Vec<f64>, shifts_bp: Vec<f64>) -> Self {
+        assert!(!tenors.is_empty() && tenors.len() == shifts_bp.len());
+        assert!(tenors.windows(2).all(|w| w[0] < w[1]));
+        Self { tenors, shifts_bp }
+    }
+
+    /// All zero rates shifted by the basis points.
+    pub fn parallel(bp: f64) -> Self {
+        Self::new(vec![0.0], vec![bp])
+    }
+
+    /// The zero rates shifted by `short_bp` up to the short tenor and by `long_bp` from the long tenor on.
+    pub fn twist(short_tenor: f64, long_tenor: f64, short_bp: f64, long_bp: f64) -> Self {
+        Self::new(vec![short_tenor, long_tenor], vec![short_bp, long_bp])
+    }
+
+    /// The 2y-10y spread widened by the basis points, half of it at each end.
+    pub fn steepener(bp: f64) -> Self {
+        Self::twist(2.0, 10.0, -0.5 * bp, 0.5 * bp)
+    }
+
+    /// The 2y-10y spread narrowed by the basis points, half of it at each end.
+    pub fn flattener(bp: f64) -> Self {
+        Self::steepener(-bp)
+    }
+
+    /// The wings (2y and 10y) up and the belly (5y) down by the basis points.
+    pub fn butterfly(bp: f64) -> Self {
+        Self::new(vec![2.0, 5.0, 10.0], vec![bp, -bp, bp])
+    }
+
+    /// The shift of the zero rate of the maturity t (as a rate, not in basis points).
+    pub fn rate_shift(&self, t: f64) -> f64 {
+        piecewise_linear(&self.tenors, &self.shifts_bp, t) * BASIS_POINT
+    }
+}
+
+/// A curve with the zero rates of the base curve deformed by the shift.
+#[derive(Clone)]
+pub struct ShiftedCurve {
+    base: Arc<dyn YieldCurve + Send + Sync>,
+    shift: CurveShift,
+}
+
+impl ShiftedCurve {
+    pub fn new(base: Arc<dyn YieldCurve + Send + Sync>, shift: CurveShift) -> Self {
+        Self { base, shift }
+    }
+}
+
+impl YieldCurve for ShiftedCurve {
+    fn zero_rate(&self, t: f64) -> f64 {
+        self.base.zero_rate(t) + self.shift.rate_shift(t)
+    }
+
+    fn horizon(&self) -> Option<f64> {
+        self.base.horizon()
+    }
+
+    fn pillars(&self) -> Vec<f64> {
+        self.base.pillars()
+    }
+}
+
+/// A deformation of the volatilities of a surface in vol points (0.01 of volatility), floored at zero.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SurfaceShift {
+    /// all volatilities shifted by the vol points
+    Level { vol_points: f64 },
+    /// the volatilities shifted by the vol points per 10% of the strike above the reference strike,
+    /// and opposite below; surfaces with a single strike have no skew and remain unchanged
+    Skew {
+        reference_strike: f64,
+        vol_points: f64,
+    },
+    /// the volatilities of the time buckets (by their end) shifted by `short_vol_points` up to the
+    /// short tenor and by `long_vol_points` from the long tenor on, linearly between
+    Term {
+        short_tenor: f64,
+        long_tenor: f64,
+        short_vol_points: f64,
+        long_vol_points: f64,
+    },
+}
+
+impl SurfaceShift {
+    /// The shift of the volatility of the time bucket ending at t at the strike.
+    fn vol_shift(&self, t: f64, strike: f64) -> f64 {
+        let vol_points = match self {
+            SurfaceShift::Level { vol_points } => *vol_points,
+            SurfaceShift::Skew {
+                reference_strike,
+                vol_points,
+            } => vol_points * (strike / reference_strike - 1.0) / 0.1,
+            SurfaceShift::Term {
+                short_tenor,
+                long_tenor,
+                short_vol_points,
+                long_vol_points,
+            } => piecewise_linear(
+                &[*short_tenor, *long_tenor],
+                &[*short_vol_points, *long_vol_points],
+                t,
+            ),
+        };
+        vol_points * VOL_POINT
+    }
+
+    pub fn apply(&self, surface: &VolatilitySurface) -> VolatilitySurface {
+        let (times, strikes) = (surface.times(), surface.strikes());
+        let has_skew = strikes.len() > 1;
+        let vols = Array2::from_shape_fn(surface.vols().raw_dim(), |(k, l)| {
+            let shift = match self {
+                SurfaceShift::Skew { .. } if !has_skew => 0.0,
+                _ => self.vol_shift(times[k], strikes[l]),
+            };
+            (surface.vols()[[k, l]] + shift).max(0.0)
+        });
+        VolatilitySurface::new(times.to_vec(), strikes.to_vec(), vols)
+    }
+}
+
+/// A deformation of a curve or a volatility surface of a market snapshot by name.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MarketShift {
+    Curve {
+        name: String,
+        shift: CurveShift,
+    },
+    VolSurface {
+        underlying: String,
+        shift: SurfaceShift,
+    },
+}
+
+/// A named scenario of deformations of the market, e.g. a steepener with a vol level shift, which
+/// yields the shifted snapshot to price books in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scenario {
+    pub name: String,
+    pub shifts: Vec<MarketShift>,
+}
+
+impl Scenario {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            shifts: Vec::new(),
+        }
+    }
+
+    pub fn with_curve_shift(mut self, name: &str, shift: CurveShift) -> Self {
+        self.shifts.push(MarketShift::Curve {
+            name: name.to_string(),
+            shift,
+        });
+        self
+    }
+
+    pub fn with_vol_surface_shift(mut self, underlying: &str, shift: SurfaceShift) -> Self {
+        self.shifts.push(MarketShift::VolSurface {
+            underlying: underlying.to_string(),
+            shift,
+        });
+        self
+    }
+
+    /// The market with the shifts applied in order, or the missing data of a shift.
+    pub fn apply(&self, market: &MarketSnapshot) -> Result<MarketSnapshot, PricingError> {
+        self.shifts
+            .iter()
+            .try_fold(market.clone(), |market, shift| match shift {
+                MarketShift::Curve { name, shift } => {
+                    let base = market.shared_curve(name).ok_or_else(|| {
+                        PricingError::MissingMarketData(format!("the curve {name}"))
+                    })?;
+                    Ok(market.with_curve(name, ShiftedCurve::new(base, shift.clone())))
+                }
+                MarketShift::VolSurface { underlying, shift } => {
+                    let surface = market.vol_surface(underlying).ok_or_else(|| {
+                        PricingError::MissingMarketData(format!(
+                            "the volatility surface of {underlying}"
+                        ))
+                    })?;
+                    let shifted = shift.apply(surface);
+                    Ok(market.with_vol_surface(underlying, shifted))
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curves::yield_curve::FlatCurve;
+    use assert_approx_eq::assert_approx_eq;
+    use ndarray::arr2;
+
+    #[test]
+    fn curve_and_surface_scenarios() {
+        let steepener = CurveShift::steepener(50.0);
+        assert_approx_eq!(steepener.rate_shift(1.0), -0.0025, 1e-15);
+        assert_approx_eq!(steepener.rate_shift(6.0), 0.0, 1e-15);
+        assert_approx_eq!(steepener.rate_shift(30.0), 0.0025, 1e-15);
+        assert_eq!(CurveShift::flattener(50.0).rate_shift(30.0), -0.0025);
+        let butterfly = CurveShift::butterfly(10.0);
+        assert_approx_eq!(butterfly.rate_shift(5.0), -0.001, 1e-15);
+        assert_approx_eq!(butterfly.rate_shift(3.5), 0.0, 1e-15);
+
+        let market = MarketSnapshot::new(0.0)
+            .with_spot("ABC", 100.0)
+            .with_curve("USD", FlatCurve::new(0.03))
+            .with_vol_surface(
+                "ABC",
+                VolatilitySurface::new(
+                    vec![1.0, 5.0],
+                    vec![90.0, 110.0],
+                    arr2(&[[0.25, 0.2], [0.22, 0.18]]),
+                ),
+            );
+        let scenario = Scenario::new("bear steepener, vols up")
+            .with_curve_shift("USD", CurveShift::parallel(25.0))
+            .with_curve_shift("USD", steepener)
+            .with_vol_surface_shift("ABC", SurfaceShift::Level { vol_points: 2.0 })
+            .with_vol_surface_shift(
+                "ABC",
+                SurfaceShift::Skew {
+                    reference_strike: 100.0,
+                    vol_points: -1.0,
+                },
+            );
+        let shifted = scenario.apply(&market).unwrap();
+        let curve = shifted.curve("USD").unwrap();
+        assert_approx_eq!(curve.zero_rate(30.0), 0.03 + 0.0025 + 0.0025, 1e-15);
+        assert_approx_eq!(curve.zero_rate(1.0), 0.03, 1e-15);
+        let surface = shifted.vol_surface("ABC").unwrap();
+        assert_approx_eq!(surface.vols()[[0, 0]], 0.25 + 0.02 + 0.01, 1e-15);
+        assert_approx_eq!(surface.vols()[[1, 1]], 0.18 + 0.02 - 0.01, 1e-15);
+        // the base market is unchanged
+        assert_eq!(market.vol_surface("ABC").unwrap().vols()[[0, 0]], 0.25);
+
+        let term = SurfaceShift::Term {
+            short_tenor: 1.0,
+            long_tenor: 5.0,
+            short_vol_points: 3.0,
+            long_vol_points: 1.0,
+        };
+        let flat = term.apply(&VolatilitySurface::flat(0.2));
+        assert_approx_eq!(flat.vols()[[0, 0]], 0.21, 1e-15);
+
+        let missing = Scenario::new("EUR up").with_curve_shift("EUR", CurveShift::parallel(1.0));
+        assert!(matches!(
+            missing.apply(&market),
+            Err(PricingError::MissingMarketData(_))
+        ));
+    }
+}
@@ -1 +1,20 @@
+pub mod audit;
+pub mod currency;
+#[cfg(feature = "mc")]
+pub mod engine_comparison;
+pub mod export;
+pub mod limits;
+pub mod market_context;
+pub mod market_data;
+pub mod market_shifts;
 pub mod models;
+pub mod pricer;
+pub mod quotation;
+pub mod results;
+#[cfg(feature = "proptest")]
+pub mod strategies;
+pub mod term_structure;
+pub mod time_grid;
+pub mod trade;
+pub mod units;
+pub mod vol_surface;
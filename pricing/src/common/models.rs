@@ -1,3 +1,7 @@
+use crate::common::results::PricingError;
+use crate::common::units::{Rate, Volatility};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct DerivativeParameter {
     /// the asset's price at time t
     pub asset_price: f64,
@@ -5,13 +9,15 @@ pub struct DerivativeParameter {
     pub strike: f64,
     /// (T - t) in years, where T is the time of the option's expiration and t is the current time
     pub time_to_expiration: f64,
-    /// the annualized risk-free interest rate
+    /// the annualized risk-free interest rate as decimal (0.05 for 5%)
     pub rfr: f64,
-    /// the annualized standard deviation of the stock's returns
+    /// the annualized standard deviation of the stock's returns as decimal (0.2 for 20%)
     pub vola: f64,
 }
 
 impl DerivativeParameter {
+    /// The parameters with the rate and volatility given as decimals, see `with_units` for unit-safe
+    /// construction.
     pub fn new(
         asset_price: f64,
         strike: f64,
@@ -27,13 +33,171 @@ impl DerivativeParameter {
             vola,
         }
     }
+
+    /// The parameters with explicit units for the rate and the volatility.
+    pub fn with_units(
+        asset_price: f64,
+        strike: f64,
+        time_to_expiration: f64,
+        rfr: Rate,
+        vola: Volatility,
+    ) -> Self {
+        Self::new(
+            asset_price,
+            strike,
+            time_to_expiration,
+            rfr.into(),
+            vola.into(),
+        )
+    }
+
+    /// Checks for a positive spot, a non-negative strike and volatility, a finite rate and a finite
+    /// non-negative time to expiration, where 0 is an expired option.
+    pub fn validate(&self) -> Result<(), PricingError> {
+        PricingError::check(
+            self.asset_price > 0.0 && self.asset_price.is_finite(),
+            "asset price",
+            self.asset_price,
+        )?;
+        PricingError::check(self.strike >= 0.0, "strike", self.strike)?;
+        PricingError::check(
+            self.time_to_expiration >= 0.0 && self.time_to_expiration.is_finite(),
+            "time to expiration",
+            self.time_to_expiration,
+        )?;
+        PricingError::check(self.vola >= 0.0 && self.vola.is_finite(), "vola", self.vola)?;
+        PricingError::check(self.rfr.is_finite(), "rfr", self.rfr)
+    }
+
+    /// Whether the option expires now, such that its value is the intrinsic value.
+    pub fn is_expired(&self) -> bool {
+        self.time_to_expiration == 0.0
+    }
+
+    /// The payoff of exercising now, i.e. the value of an expired option.
+    pub fn intrinsic_value(&self, exercise_type: &ExerciseType) -> f64 {
+        exercise_type.payoff(self.asset_price, self.strike)
+    }
+
+    /// The spot moneyness $S / K$.
+    pub fn moneyness(&self) -> f64 {
+        self.asset_price / self.strike
+    }
+
+    /// The log-moneyness $\ln(S / K)$.
+    pub fn log_moneyness(&self) -> f64 {
+        self.moneyness().ln()
+    }
+
+    /// The forward price $F = S e^{rT}$ of the underlying at the expiration.
+    pub fn forward_price(&self) -> f64 {
+        self.asset_price * (self.rfr * self.time_to_expiration).exp()
+    }
+
+    /// The classification of the option by its intrinsic value, at the money if the spot deviates from
+    /// the strike by at most the relative band, e.g. 0.01 for 1%.
+    pub fn moneyness_class(&self, exercise_type: &ExerciseType, atm_band: f64) -> Moneyness {
+        if (self.moneyness() - 1.0).abs() <= atm_band {
+            Moneyness::AtTheMoney
+        } else if self.intrinsic_value(exercise_type) > 0.0 {
+            Moneyness::InTheMoney
+        } else {
+            Moneyness::OutOfTheMoney
+        }
+    }
+
+    pub fn is_itm(&self, exercise_type: &ExerciseType) -> bool {
+        self.moneyness_class(exercise_type, 0.0) == Moneyness::InTheMoney
+    }
+
+    pub fn is_atm(&self) -> bool {
+        self.asset_price == self.strike
+    }
+
+    pub fn is_otm(&self, exercise_type: &ExerciseType) -> bool {
+        self.moneyness_class(exercise_type, 0.0) == Moneyness::OutOfTheMoney
+    }
+
+    pub fn rate(&self) -> Rate {
+        Rate::from_decimal(self.rfr)
+    }
+
+    /// The volatility, which fails for a negative or non-finite `vola`.
+    pub fn volatility(&self) -> Result<Volatility, PricingError> {
+        Volatility::try_from(self.vola)
+    }
+}
+
+/// Whether exercising an option now pays (in the money), is indifferent (at the money) or does not pay.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Moneyness {
+    InTheMoney,
+    AtTheMoney,
+    OutOfTheMoney,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ExerciseType {
     Put,
     Call,
 }
 
+impl ExerciseType {
+    /// The payoff of exercising on the value of the underlying, e.g. a terminal spot or an average.
+    pub fn payoff(&self, underlying: f64, strike: f64) -> f64 {
+        match self {
+            ExerciseType::Call => (underlying - strike).max(0.0),
+            ExerciseType::Put => (strike - underlying).max(0.0),
+        }
+    }
+}
+
+/// The parameters of the CIR variance $dv_t = \kappa (\theta - v_t) dt + \sigma \sqrt{v_t} dW^v_t$
+/// of the Heston model and its correlation ρ with the spot.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HestonParameters {
+    pub initial_variance: f64,
+    /// κ
+    pub mean_reversion: f64,
+    /// θ
+    pub long_term_variance: f64,
+    /// σ
+    pub vol_of_vol: f64,
+    /// ρ
+    pub correlation: f64,
+}
+
+impl HestonParameters {
+    /// Whether $2 \kappa \theta \geq \sigma^2$, such that the variance stays positive.
+    pub fn feller_condition(&self) -> bool {
+        2.0 * self.mean_reversion * self.long_term_variance >= self.vol_of_vol.powi(2)
+    }
+
+    pub fn validate(&self) -> Result<(), PricingError> {
+        PricingError::check(
+            self.initial_variance >= 0.0,
+            "initial variance",
+            self.initial_variance,
+        )?;
+        PricingError::check(
+            self.mean_reversion > 0.0,
+            "mean reversion",
+            self.mean_reversion,
+        )?;
+        PricingError::check(
+            self.long_term_variance > 0.0,
+            "long-term variance",
+            self.long_term_variance,
+        )?;
+        PricingError::check(self.vol_of_vol > 0.0, "vol of vol", self.vol_of_vol)?;
+        PricingError::check(
+            self.correlation.abs() <= 1.0,
+            "correlation",
+            self.correlation,
+        )
+    }
+}
+
 pub type Underlying = String;
 
 pub enum Greek {
@@ -47,3 +211,29 @@ pub enum Greek {
 pub struct GreekConfig {
     pub shift_size: f64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moneyness() {
+        let dp = DerivativeParameter::new(105.0, 100.0, 2.0, 0.03, 0.2);
+        assert_eq!(dp.moneyness(), 1.05);
+        assert!((dp.log_moneyness() - 1.05_f64.ln()).abs() < 1e-15);
+        assert!((dp.forward_price() - 105.0 * 0.06_f64.exp()).abs() < 1e-12);
+        assert_eq!(dp.intrinsic_value(&ExerciseType::Call), 5.0);
+        assert_eq!(ExerciseType::Put.payoff(95.0, 100.0), 5.0);
+        assert_eq!(ExerciseType::Call.payoff(95.0, 100.0), 0.0);
+        assert!(dp.is_itm(&ExerciseType::Call) && dp.is_otm(&ExerciseType::Put));
+        assert!(!dp.is_atm());
+        assert_eq!(
+            dp.moneyness_class(&ExerciseType::Put, 0.1),
+            Moneyness::AtTheMoney
+        );
+        assert_eq!(
+            dp.moneyness_class(&ExerciseType::Put, 0.01),
+            Moneyness::OutOfTheMoney
+        );
+    }
+}
@@ -0,0 +1,977 @@
+use std::collections::BTreeMap;
+#[cfg(feature = "mc")]
+use std::marker::PhantomData;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::analytic::black_scholes::BsmComputation;
+use crate::common::audit::{AuditEntry, AuditRecord};
+use crate::common::market_context::MarketSnapshot;
+use crate::common::models::{DerivativeParameter, ExerciseType, Moneyness};
+#[cfg(feature = "mc")]
+use crate::common::results::NonFinitePolicy;
+use crate::common::results::{PriceResult, PricingError, PricingWarning};
+use crate::lattice::trinomial_tree::TrinomialTree;
+#[cfg(feature = "parallel")]
+use crate::numerics::summation::Reproducibility;
+#[cfg(feature = "mc")]
+use crate::simulation::products::european_option::MonteCarloEuropeanOption;
+
+/// The types of products by which the registry dispatches to the pricers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ProductType {
+    EuropeanOption,
+    AmericanOption,
+    Forward,
+}
+
+/// A vanilla option on an underlying of the market snapshot, discounted by the named curve.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VanillaOption {
+    pub underlying: String,
+    pub curve: String,
+    pub strike: f64,
+    /// the time of the expiration in years, on the time axis of the market snapshots
+    pub expiry: f64,
+    pub exercise_type: ExerciseType,
+    pub is_american: bool,
+}
+
+impl VanillaOption {
+    pub fn new(
+        underlying: &str,
+        curve: &str,
+        strike: f64,
+        expiry: f64,
+        exercise_type: ExerciseType,
+    ) -> Self {
+        Self {
+            underlying: underlying.to_string(),
+            curve: curve.to_string(),
+            strike,
+            expiry,
+            exercise_type,
+            is_american: false,
+        }
+    }
+
+    /// The option with early exercise at any time until the expiry.
+    pub fn with_american_exercise(self) -> Self {
+        Self {
+            is_american: true,
+            ..self
+        }
+    }
+
+    /// The flat parameters of the option in the market.
+    pub fn derivative_parameter(
+        &self,
+        market: &MarketSnapshot,
+    ) -> Result<DerivativeParameter, PricingError> {
+        let time_to_expiration = time_to_expiry(self.expiry, market)?;
+        let market_data = market
+            .market_data_set(
+                &self.underlying,
+                &self.curve,
+                time_to_expiration,
+                self.strike,
+            )
+            .ok_or_else(|| missing_market_data(&self.underlying, &self.curve))?;
+        Ok(DerivativeParameter::new(
+            market_data.spot,
+            self.strike,
+            time_to_expiration,
+            market_data.rfr,
+            market_data.vola,
+        ))
+    }
+
+    /// The classification of the option by the spot of the market, at the money within the relative
+    /// band around the strike.
+    pub fn moneyness(
+        &self,
+        market: &MarketSnapshot,
+        atm_band: f64,
+    ) -> Result<Moneyness, PricingError> {
+        Ok(self
+            .derivative_parameter(market)?
+            .moneyness_class(&self.exercise_type, atm_band))
+    }
+
+    /// The warnings of the market data of the option, i.e. of an extrapolated volatility.
+    pub fn warnings(&self, market: &MarketSnapshot) -> Vec<PricingWarning> {
+        market
+            .vol_surface(&self.underlying)
+            .and_then(|surface| {
+                surface.extrapolation_warning(self.expiry - market.time, self.strike)
+            })
+            .into_iter()
+            .collect()
+    }
+}
+
+/// The products of a book, which may be priced by different engines.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Product {
+    Option(VanillaOption),
+    /// the obligation to buy the underlying for the strike at the expiry
+    Forward {
+        underlying: String,
+        curve: String,
+        strike: f64,
+        expiry: f64,
+    },
+}
+
+impl Product {
+    /// The name of the underlying in the market snapshots.
+    pub fn underlying(&self) -> &str {
+        match self {
+            Product::Option(option) => &option.underlying,
+            Product::Forward { underlying, .. } => underlying,
+        }
+    }
+
+    pub fn product_type(&self) -> ProductType {
+        match self {
+            Product::Option(option) if option.is_american => ProductType::AmericanOption,
+            Product::Option(_) => ProductType::EuropeanOption,
+            Product::Forward { .. } => ProductType::Forward,
+        }
+    }
+
+    /// Checks that the product expires after the valuation time and that the market holds its data
+    /// until the expiry, see `MarketSnapshot::check_market_data`.
+    pub fn check_market(&self, market: &MarketSnapshot) -> Result<(), PricingError> {
+        match self {
+            Product::Option(option) => market.check_market_data(
+                &option.underlying,
+                &option.curve,
+                time_to_expiry(option.expiry, market)?,
+                true,
+            ),
+            Product::Forward {
+                underlying,
+                curve,
+                expiry,
+                ..
+            } => {
+                market.check_market_data(underlying, curve, time_to_expiry(*expiry, market)?, false)
+            }
+        }
+    }
+
+    /// The market data the product resolves from the snapshot: the spot, the pillars of the curve and
+    /// its value at the expiry, and the volatility of an option.
+    pub fn audit_entries(&self, market: &MarketSnapshot) -> Result<Vec<AuditEntry>, PricingError> {
+        self.check_market(market)?;
+        let (underlying, curve_name, expiry) = match self {
+            Product::Option(option) => (&option.underlying, &option.curve, option.expiry),
+            Product::Forward {
+                underlying,
+                curve,
+                expiry,
+                ..
+            } => (underlying, curve, *expiry),
+        };
+        let maturity = time_to_expiry(expiry, market)?;
+        let missing = || missing_market_data(underlying, curve_name);
+        let curve = market.curve(curve_name).ok_or_else(missing)?;
+        let mut entries = vec![AuditEntry::Spot {
+            underlying: underlying.clone(),
+            value: market.spot(underlying).ok_or_else(missing)?,
+        }];
+        entries.extend(
+            curve
+                .pillars()
+                .into_iter()
+                .map(|pillar| AuditEntry::CurveNode {
+                    curve: curve_name.clone(),
+                    maturity: pillar,
+                    zero_rate: curve.zero_rate(pillar),
+                }),
+        );
+        entries.push(AuditEntry::CurveValue {
+            curve: curve_name.clone(),
+            maturity,
+            zero_rate: curve.zero_rate(maturity),
+            discount_factor: curve.discount_factor(maturity),
+        });
+        if let Product::Option(option) = self {
+            let surface = market.vol_surface(underlying).ok_or_else(missing)?;
+            entries.push(AuditEntry::Volatility {
+                underlying: underlying.clone(),
+                time: maturity,
+                strike: option.strike,
+                vola: surface.effective_vola(maturity, option.strike),
+                extrapolated: surface
+                    .extrapolation_warning(maturity, option.strike)
+                    .is_some(),
+            });
+        }
+        Ok(entries)
+    }
+
+    /// The audit record of the engine pricing the product, with the market data of the product.
+    fn audit_record(
+        &self,
+        engine: &str,
+        market: &MarketSnapshot,
+    ) -> Result<AuditRecord, PricingError> {
+        Ok(AuditRecord::new(engine, &format!("{self:?}"), market)
+            .with_entries(self.audit_entries(market)?))
+    }
+}
+
+fn time_to_expiry(expiry: f64, market: &MarketSnapshot) -> Result<f64, PricingError> {
+    let time_to_expiration = expiry - market.time;
+    PricingError::check(
+        time_to_expiration > 0.0,
+        "time to expiry",
+        time_to_expiration,
+    )?;
+    Ok(time_to_expiration)
+}
+
+fn missing_market_data(underlying: &str, curve: &str) -> PricingError {
+    PricingError::MissingMarketData(format!("{underlying} with curve {curve}"))
+}
+
+fn unsupported(engine: &str, product: &Product) -> PricingError {
+    PricingError::UnsupportedProduct(format!("{:?} by the {engine}", product.product_type()))
+}
+
+/// An engine bound to a product, which prices it in any market snapshot.
+pub trait Pricer {
+    fn price(&self, market: &MarketSnapshot) -> Result<PriceResult, PricingError>;
+
+    /// The price with the record of the inputs the pricer resolved from the market and from its
+    /// settings while pricing, such that the record is the one of the price. Pricers without an audit
+    /// trail fail in audit mode instead of unaudited prices.
+    fn price_audited(&self, _market: &MarketSnapshot) -> Result<PriceResult, PricingError> {
+        Err(PricingError::UnsupportedProduct(
+            "the audit trail of the pricer".to_string(),
+        ))
+    }
+
+    /// The price, audited as by `price_audited` in audit mode, with the samples of the pricer summed in
+    /// the reproducibility mode. Pricers without sampling price identically in all modes.
+    #[cfg(feature = "parallel")]
+    fn price_reproducible(
+        &self,
+        market: &MarketSnapshot,
+        audit: bool,
+        _reproducibility: Reproducibility,
+    ) -> Result<PriceResult, PricingError> {
+        if audit {
+            self.price_audited(market)
+        } else {
+            self.price(market)
+        }
+    }
+}
+
+/// The result with the audit record, if collected.
+fn with_record(result: PriceResult, record: Option<AuditRecord>) -> PriceResult {
+    match record {
+        Some(record) => result.with_audit(record),
+        None => result,
+    }
+}
+
+/// Closed-form prices: Black-Scholes-Merton for European options and American calls
+/// (without dividends early exercise is never optimal) and the discounted forward payoff.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnalyticPricer {
+    product: Product,
+}
+
+impl AnalyticPricer {
+    pub fn new(product: &Product) -> Self {
+        Self {
+            product: product.clone(),
+        }
+    }
+}
+
+impl AnalyticPricer {
+    /// The price, with the record of the resolved inputs in audit mode.
+    fn price_recorded(
+        &self,
+        market: &MarketSnapshot,
+        audit: bool,
+    ) -> Result<PriceResult, PricingError> {
+        self.product.check_market(market)?;
+        let record = audit
+            .then(|| self.product.audit_record("analytic pricer", market))
+            .transpose()?;
+        PriceResult::timed(|| match &self.product {
+            Product::Option(option) => {
+                if option.is_american && option.exercise_type == ExerciseType::Put {
+                    return Err(unsupported("analytic pricer", &self.product));
+                }
+                let dp = option.derivative_parameter(market)?;
+                let record = record.map(|record| {
+                    record
+                        .with_setting("model", "Black-Scholes-Merton")
+                        .with_derivative_parameter(&dp)
+                });
+                Ok(with_record(
+                    PriceResult::exact(BsmComputation::new(&dp).price(&option.exercise_type))
+                        .with_warnings(option.warnings(market)),
+                    record,
+                ))
+            }
+            Product::Forward {
+                underlying,
+                curve,
+                strike,
+                expiry,
+            } => {
+                let time_to_expiration = time_to_expiry(*expiry, market)?;
+                let spot = market
+                    .spot(underlying)
+                    .ok_or_else(|| missing_market_data(underlying, curve))?;
+                let discount_factor = market
+                    .curve(curve)
+                    .ok_or_else(|| missing_market_data(underlying, curve))?
+                    .discount_factor(time_to_expiration);
+                let record =
+                    record.map(|record| record.with_setting("model", "discounted forward payoff"));
+                Ok(with_record(
+                    PriceResult::exact(spot - strike * discount_factor),
+                    record,
+                ))
+            }
+        })
+    }
+}
+
+impl Pricer for AnalyticPricer {
+    fn price(&self, market: &MarketSnapshot) -> Result<PriceResult, PricingError> {
+        self.price_recorded(market, false)
+    }
+
+    fn price_audited(&self, market: &MarketSnapshot) -> Result<PriceResult, PricingError> {
+        self.price_recorded(market, true)
+    }
+}
+
+/// Trinomial tree prices of European and American options.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TreePricer {
+    product: Product,
+    nr_steps: usize,
+}
+
+impl TreePricer {
+    pub fn new(product: &Product, nr_steps: usize) -> Self {
+        Self {
+            product: product.clone(),
+            nr_steps,
+        }
+    }
+}
+
+impl TreePricer {
+    /// The price, with the record of the resolved inputs in audit mode.
+    fn price_recorded(
+        &self,
+        market: &MarketSnapshot,
+        audit: bool,
+    ) -> Result<PriceResult, PricingError> {
+        self.product.check_market(market)?;
+        let Product::Option(option) = &self.product else {
+            return Err(unsupported("tree pricer", &self.product));
+        };
+        let record = audit
+            .then(|| self.product.audit_record("tree pricer", market))
+            .transpose()?;
+        PriceResult::timed(|| {
+            PricingError::check(self.nr_steps > 0, "nr steps", self.nr_steps as f64)?;
+            let dp = option.derivative_parameter(market)?;
+            let record = record.map(|record| {
+                record
+                    .with_setting("model", "trinomial tree")
+                    .with_setting("nr steps", self.nr_steps)
+                    .with_derivative_parameter(&dp)
+            });
+            Ok(with_record(
+                PriceResult::exact(TrinomialTree::new(self.nr_steps).price(
+                    &dp,
+                    &option.exercise_type,
+                    option.is_american,
+                ))
+                .with_warnings(option.warnings(market)),
+                record,
+            ))
+        })
+    }
+}
+
+impl Pricer for TreePricer {
+    fn price(&self, market: &MarketSnapshot) -> Result<PriceResult, PricingError> {
+        self.price_recorded(market, false)
+    }
+
+    fn price_audited(&self, market: &MarketSnapshot) -> Result<PriceResult, PricingError> {
+        self.price_recorded(market, true)
+    }
+}
+
+/// Monte Carlo prices of European options with their standard errors.
+#[cfg(feature = "mc")]
+pub struct MonteCarloPricer<SeedRng>
+where
+    SeedRng: rand::SeedableRng + rand::RngCore,
+{
+    product: Product,
+    nr_paths: usize,
+    nr_steps: usize,
+    seed_nr: u64,
+    non_finite_policy: NonFinitePolicy,
+    /// the name of the generator in the audit records, e.g. "Hc128"
+    rng_name: Option<String>,
+    _phantom_rng: PhantomData<SeedRng>,
+}
+
+#[cfg(feature = "mc")]
+impl<SeedRng> MonteCarloPricer<SeedRng>
+where
+    SeedRng: rand::SeedableRng + rand::RngCore,
+{
+    pub fn new(product: &Product, nr_paths: usize, nr_steps: usize, seed_nr: u64) -> Self {
+        Self {
+            product: product.clone(),
+            nr_paths,
+            nr_steps,
+            seed_nr,
+            non_finite_policy: NonFinitePolicy::default(),
+            rng_name: None,
+            _phantom_rng: PhantomData::<SeedRng>,
+        }
+    }
+
+    /// The name of the random number generator in the audit records, e.g. "Hc128", which is required
+    /// in audit mode, since the type name of the generator is not stable across compiler versions.
+    pub fn with_rng_name(self, rng_name: &str) -> Self {
+        Self {
+            rng_name: Some(rng_name.to_string()),
+            ..self
+        }
+    }
+
+    /// The handling of NaN or infinite path payoffs, which by default fail the pricing.
+    pub fn with_non_finite_policy(self, non_finite_policy: NonFinitePolicy) -> Self {
+        Self {
+            non_finite_policy,
+            ..self
+        }
+    }
+}
+
+#[cfg(feature = "mc")]
+impl<SeedRng> Pricer for MonteCarloPricer<SeedRng>
+where
+    SeedRng: rand::SeedableRng + rand::RngCore,
+{
+    fn price(&self, market: &MarketSnapshot) -> Result<PriceResult, PricingError> {
+        self.price_recorded(market, false, Reproducibility::default())
+    }
+
+    fn price_audited(&self, market: &MarketSnapshot) -> Result<PriceResult, PricingError> {
+        self.price_recorded(market, true, Reproducibility::default())
+    }
+
+    fn price_reproducible(
+        &self,
+        market: &MarketSnapshot,
+        audit: bool,
+        reproducibility: Reproducibility,
+    ) -> Result<PriceResult, PricingError> {
+        self.price_recorded(market, audit, reproducibility)
+    }
+}
+
+#[cfg(feature = "mc")]
+impl<SeedRng> MonteCarloPricer<SeedRng>
+where
+    SeedRng: rand::SeedableRng + rand::RngCore,
+{
+    /// The price with the payoffs summed in the reproducibility mode, with the record of the resolved
+    /// inputs in audit mode.
+    fn price_recorded(
+        &self,
+        market: &MarketSnapshot,
+        audit: bool,
+        reproducibility: Reproducibility,
+    ) -> Result<PriceResult, PricingError> {
+        self.product.check_market(market)?;
+        let option = match &self.product {
+            Product::Option(option) if !option.is_american => option,
+            _ => return Err(unsupported("Monte Carlo pricer", &self.product)),
+        };
+        let record = match (audit, &self.rng_name) {
+            (false, _) => None,
+            (true, None) => {
+                return Err(PricingError::InvalidParameter(
+                    "the rng name of the audit trail, see `with_rng_name`".to_string(),
+                ))
+            }
+            (true, Some(rng_name)) => Some(
+                self.product
+                    .audit_record("Monte Carlo pricer", market)?
+                    .with_setting("model", "geometric Brownian motion")
+                    .with_setting("nr paths", self.nr_paths)
+                    .with_setting("nr steps", self.nr_steps)
+                    .with_setting("rng", rng_name)
+                    .with_setting("summation", format!("{reproducibility:?}"))
+                    .with_entries([AuditEntry::Seed(self.seed_nr)]),
+            ),
+        };
+        let mc_option = MonteCarloEuropeanOption::<SeedRng>::from_market(
+            option,
+            market,
+            self.nr_paths,
+            self.nr_steps,
+            self.seed_nr,
+        )?
+        .with_non_finite_policy(self.non_finite_policy)
+        .with_reproducibility(reproducibility);
+        let record =
+            record.map(|record| record.with_derivative_parameter(&mc_option.option_params));
+        mc_option
+            .price(&option.exercise_type)
+            .map(|result| with_record(result.with_warnings(option.warnings(market)), record))
+    }
+}
+
+type PricerFactory = Box<dyn Fn(&Product) -> Box<dyn Pricer> + Send + Sync>;
+
+/// The pricers by product type, which price heterogeneous books uniformly.
+pub struct PricerRegistry {
+    factories: BTreeMap<ProductType, PricerFactory>,
+    audit: bool,
+    #[cfg(feature = "parallel")]
+    reproducibility: Reproducibility,
+}
+
+impl Default for PricerRegistry {
+    /// Analytic prices of European options and forwards, and tree prices of American options.
+    fn default() -> Self {
+        Self::new()
+            .with_pricer(ProductType::EuropeanOption, |product| {
+                Box::new(AnalyticPricer::new(product))
+            })
+            .with_pricer(ProductType::Forward, |product| {
+                Box::new(AnalyticPricer::new(product))
+            })
+            .with_pricer(ProductType::AmericanOption, |product| {
+                Box::new(TreePricer::new(product, 500))
+            })
+    }
+}
+
+impl PricerRegistry {
+    /// The registry without any pricers.
+    pub fn new() -> Self {
+        Self {
+            factories: BTreeMap::new(),
+            audit: false,
+            #[cfg(feature = "parallel")]
+            reproducibility: Reproducibility::default(),
+        }
+    }
+
+    /// The registry in audit mode, which attaches the record of the inputs each pricer resolved to its
+    /// price, such that the price can be reproduced and justified.
+    pub fn with_audit(self) -> Self {
+        Self {
+            audit: true,
+            ..self
+        }
+    }
+
+    /// The registry summing the samples of the pricers in the reproducibility mode, by default
+    /// sequential, such that e.g. the Monte Carlo prices of a book are bit-stable.
+    #[cfg(feature = "parallel")]
+    pub fn with_reproducibility(self, reproducibility: Reproducibility) -> Self {
+        Self {
+            reproducibility,
+            ..self
+        }
+    }
+
+    /// Registers (or replaces) the pricer of the product type, created per product.
+    pub fn with_pricer(
+        mut self,
+        product_type: ProductType,
+        factory: impl Fn(&Product) -> Box<dyn Pricer> + Send + Sync + 'static,
+    ) -> Self {
+        self.factories.insert(product_type, Box::new(factory));
+        self
+    }
+
+    pub fn pricer(&self, product: &Product) -> Result<Box<dyn Pricer>, PricingError> {
+        self.factories
+            .get(&product.product_type())
+            .map(|factory| factory(product))
+            .ok_or_else(|| unsupported("registry", product))
+    }
+
+    pub fn price(
+        &self,
+        product: &Product,
+        market: &MarketSnapshot,
+    ) -> Result<PriceResult, PricingError> {
+        let pricer = self.pricer(product)?;
+        #[cfg(feature = "parallel")]
+        return pricer.price_reproducible(market, self.audit, self.reproducibility);
+        #[cfg(not(feature = "parallel"))]
+        if self.audit {
+            pricer.price_audited(market)
+        } else {
+            pricer.price(market)
+        }
+    }
+
+    /// The prices of the products of the book (in parallel with the `parallel` feature), in the order
+    /// of the book. Each product is priced on its own with the reproducibility mode of the registry,
+    /// such that the prices do not depend on the scheduling of the products.
+    pub fn price_book(
+        &self,
+        book: &[Product],
+        market: &MarketSnapshot,
+    ) -> Vec<Result<PriceResult, PricingError>> {
+        #[cfg(feature = "parallel")]
+        let products = book.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let products = book.iter();
+        products
+            .map(|product| self.price(product, market))
+            .collect()
+    }
+}
+
+#[cfg(all(test, feature = "mc"))]
+mod tests {
+    use super::*;
+    use crate::common::vol_surface::VolatilitySurface;
+    use crate::curves::yield_curve::{FlatCurve, InterpolatedCurve};
+    use crate::numerics::interpolation::InterpolationMethod;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn market() -> MarketSnapshot {
+        MarketSnapshot::new(0.5)
+            .with_spot("ABC", 100.0)
+            .with_curve("USD", FlatCurve::new(0.03))
+            .with_vol_surface("ABC", VolatilitySurface::flat(0.2))
+    }
+
+    fn book() -> Vec<Product> {
+        vec![
+            Product::Option(VanillaOption::new(
+                "ABC",
+                "USD",
+                100.0,
+                1.5,
+                ExerciseType::Call,
+            )),
+            Product::Option(
+                VanillaOption::new("ABC", "USD", 110.0, 1.5, ExerciseType::Put)
+                    .with_american_exercise(),
+            ),
+            Product::Forward {
+                underlying: "ABC".to_string(),
+                curve: "USD".to_string(),
+                strike: 100.0,
+                expiry: 1.5,
+            },
+            Product::Option(VanillaOption::new(
+                "XYZ",
+                "USD",
+                100.0,
+                1.5,
+                ExerciseType::Call,
+            )),
+        ]
+    }
+
+    #[test]
+    fn heterogeneous_book() {
+        let market = market();
+        let prices = PricerRegistry::default().price_book(&book(), &market);
+
+        let dp = DerivativeParameter::new(100.0, 100.0, 1.0, 0.03, 0.2);
+        assert_approx_eq!(
+            prices[0].as_ref().unwrap().value,
+            BsmComputation::new(&dp).call(),
+            1e-12
+        );
+        // the early exercise premium of the put
+        let european_put =
+            BsmComputation::new(&DerivativeParameter::new(100.0, 110.0, 1.0, 0.03, 0.2)).put();
+        assert!(prices[1].as_ref().unwrap().value > european_put);
+        assert_approx_eq!(
+            prices[2].as_ref().unwrap().value,
+            100.0 - 100.0 * (-0.03_f64).exp(),
+            1e-12
+        );
+        assert!(matches!(prices[3], Err(PricingError::MissingMarketData(_))));
+        assert!(prices[0].as_ref().unwrap().runtime.is_some());
+        assert!(prices[0].as_ref().unwrap().warnings.is_empty());
+        let put = VanillaOption::new("ABC", "USD", 110.0, 1.5, ExerciseType::Put);
+        assert_eq!(put.moneyness(&market, 0.05), Ok(Moneyness::InTheMoney));
+        assert_eq!(put.moneyness(&market, 0.1), Ok(Moneyness::AtTheMoney));
+
+        // the strike beyond the strikes of the surface
+        let skewed = market.with_vol_surface(
+            "ABC",
+            VolatilitySurface::new(vec![2.0], vec![90.0, 110.0], ndarray::arr2(&[[0.25, 0.2]])),
+        );
+        let option = VanillaOption::new("ABC", "USD", 120.0, 1.5, ExerciseType::Call);
+        let price = AnalyticPricer::new(&Product::Option(option))
+            .price(&skewed)
+            .unwrap();
+        assert_eq!(
+            price.warnings,
+            vec![PricingWarning::VolatilityExtrapolated {
+                time: 1.0,
+                strike: 120.0
+            }]
+        );
+    }
+
+    #[test]
+    fn registered_engines() {
+        let market = market();
+        let book = book();
+        let registry =
+            PricerRegistry::default().with_pricer(ProductType::EuropeanOption, |product| {
+                Box::new(MonteCarloPricer::<rand_hc::Hc128Rng>::new(
+                    product, 20_000, 50, 42,
+                ))
+            });
+        let mc_price = registry.price(&book[0], &market).unwrap();
+        let analytic = AnalyticPricer::new(&book[0]).price(&market).unwrap();
+        let (lower, upper) = mc_price.confidence_interval.unwrap();
+        assert!(lower < analytic.value && analytic.value < upper);
+
+        let tree = TreePricer::new(&book[0], 500).price(&market).unwrap();
+        assert_approx_eq!(tree.value, analytic.value, 0.02);
+
+        assert!(matches!(
+            AnalyticPricer::new(&book[1]).price(&market),
+            Err(PricingError::UnsupportedProduct(_))
+        ));
+        assert!(matches!(
+            PricerRegistry::new().price(&book[2], &market),
+            Err(PricingError::UnsupportedProduct(_))
+        ));
+        assert!(matches!(
+            registry.price(&book[0], &market.with_time(2.0)),
+            Err(PricingError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn reproducible_book() {
+        let market = market();
+        let book = book();
+        let mc_registry = || {
+            PricerRegistry::default().with_pricer(ProductType::EuropeanOption, |product| {
+                Box::new(MonteCarloPricer::<rand_hc::Hc128Rng>::new(
+                    product, 5_000, 10, 42,
+                ))
+            })
+        };
+        let bits = |registry: PricerRegistry| -> Vec<u64> {
+            registry
+                .price_book(&book[..3], &market)
+                .into_iter()
+                .map(|result| result.unwrap().value.to_bits())
+                .collect()
+        };
+        let prices = |reproducibility| bits(mc_registry().with_reproducibility(reproducibility));
+        // the registry sums sequentially by default
+        let sequential = prices(Reproducibility::Sequential);
+        assert_eq!(sequential, bits(mc_registry()));
+        for reproducibility in [Reproducibility::Compensated, Reproducibility::Pairwise] {
+            let reproduced = prices(reproducibility);
+            assert_eq!(reproduced, prices(reproducibility));
+            // the exact prices do not depend on the summation
+            assert_eq!(reproduced[1..], sequential[1..]);
+            assert_approx_eq!(
+                f64::from_bits(reproduced[0]),
+                f64::from_bits(sequential[0]),
+                1e-9
+            );
+        }
+        let audited = mc_registry()
+            .with_reproducibility(Reproducibility::Pairwise)
+            .with_audit()
+            .with_pricer(ProductType::EuropeanOption, |product| {
+                Box::new(
+                    MonteCarloPricer::<rand_hc::Hc128Rng>::new(product, 1_000, 10, 42)
+                        .with_rng_name("Hc128"),
+                )
+            })
+            .price(&book[0], &market)
+            .unwrap();
+        assert!(audited
+            .audit
+            .unwrap()
+            .entries
+            .contains(&AuditEntry::EngineSetting {
+                name: "summation".to_string(),
+                value: "Pairwise".to_string()
+            }));
+    }
+
+    #[test]
+    fn audit_trail() {
+        let market = market().with_curve(
+            "EUR",
+            InterpolatedCurve::new(
+                vec![0.5, 2.0],
+                vec![0.02, 0.03],
+                InterpolationMethod::Linear,
+            ),
+        );
+        let option = Product::Option(VanillaOption::new(
+            "ABC",
+            "EUR",
+            100.0,
+            1.5,
+            ExerciseType::Call,
+        ));
+        let registry = PricerRegistry::default().with_audit();
+        let price = registry.price(&option, &market).unwrap();
+        assert!(PricerRegistry::default()
+            .price(&option, &market)
+            .unwrap()
+            .audit
+            .is_none());
+        let audit = price.audit.unwrap();
+        assert_eq!(audit.engine, "analytic pricer");
+        assert_eq!(audit.valuation_time, 0.5);
+        assert_eq!(
+            audit.entries[..3],
+            [
+                AuditEntry::Spot {
+                    underlying: "ABC".to_string(),
+                    value: 100.0
+                },
+                AuditEntry::CurveNode {
+                    curve: "EUR".to_string(),
+                    maturity: 0.5,
+                    zero_rate: 0.02
+                },
+                AuditEntry::CurveNode {
+                    curve: "EUR".to_string(),
+                    maturity: 2.0,
+                    zero_rate: 0.03
+                },
+            ]
+        );
+        // the price is reproduced from the recorded model parameters
+        let parameter = |name: &str| {
+            audit
+                .entries
+                .iter()
+                .find_map(|entry| match entry {
+                    AuditEntry::ModelParameter { name: n, value } if n == name => Some(*value),
+                    _ => None,
+                })
+                .unwrap()
+        };
+        let dp = DerivativeParameter::new(
+            parameter("asset price"),
+            parameter("strike"),
+            parameter("time to expiration"),
+            parameter("rfr"),
+            parameter("vola"),
+        );
+        assert_eq!(BsmComputation::new(&dp).call(), price.value);
+        assert_approx_eq!(dp.rfr, 0.02 + 0.01 * 0.5 / 1.5, 1e-15);
+        assert!(audit
+            .to_string()
+            .contains("volatility ABC at time 1 and strike 100 = 0.2"));
+
+        let mc_pricer = MonteCarloPricer::<rand_hc::Hc128Rng>::new(&option, 1_000, 10, 42);
+        assert!(matches!(
+            mc_pricer.price_audited(&market),
+            Err(PricingError::InvalidParameter(_))
+        ));
+        let mc_price = mc_pricer
+            .with_rng_name("Hc128")
+            .price_audited(&market)
+            .unwrap();
+        let mc_audit = mc_price.audit.unwrap();
+        assert!(mc_audit.entries.contains(&AuditEntry::Seed(42)));
+        for (name, value) in [
+            ("nr paths", "1000"),
+            ("rng", "Hc128"),
+            ("summation", "Sequential"),
+        ] {
+            assert!(mc_audit.entries.contains(&AuditEntry::EngineSetting {
+                name: name.to_string(),
+                value: value.to_string()
+            }));
+        }
+        // pricers without an audit trail fail in audit mode
+        let registry = registry.with_pricer(ProductType::Forward, |product| {
+            struct Unaudited(Product);
+            impl Pricer for Unaudited {
+                fn price(&self, market: &MarketSnapshot) -> Result<PriceResult, PricingError> {
+                    AnalyticPricer::new(&self.0).price(market)
+                }
+            }
+            Box::new(Unaudited(product.clone()))
+        });
+        assert!(matches!(
+            registry.price(&book()[2], &market),
+            Err(PricingError::UnsupportedProduct(_))
+        ));
+    }
+
+    #[test]
+    fn market_data_checked_before_pricing() {
+        let market = MarketSnapshot::new(0.5).with_spot("ABC", 100.0).with_curve(
+            "USD",
+            InterpolatedCurve::new(
+                vec![0.5, 1.0],
+                vec![0.02, 0.03],
+                InterpolationMethod::Linear,
+            ),
+        );
+        let forward = &book()[2];
+        assert!(AnalyticPricer::new(forward).price(&market).is_ok());
+        // the option lacks the volatility surface
+        let error = TreePricer::new(&book()[0], 100).price(&market).unwrap_err();
+        assert_eq!(
+            error,
+            PricingError::MissingMarketData("the volatility surface of ABC".to_string())
+        );
+
+        let market = market.with_vol_surface("ABC", VolatilitySurface::flat(0.2));
+        assert!(AnalyticPricer::new(&book()[0]).price(&market).is_ok());
+        let long_dated = Product::Option(VanillaOption::new(
+            "ABC",
+            "USD",
+            100.0,
+            2.0,
+            ExerciseType::Call,
+        ));
+        let mc_pricer = MonteCarloPricer::<rand_hc::Hc128Rng>::new(&long_dated, 100_000, 100, 42);
+        assert_eq!(
+            mc_pricer.price(&market).unwrap_err(),
+            PricingError::CurveHorizonExceeded {
+                curve: "USD".to_string(),
+                maturity: 1.5,
+                horizon: 1.0
+            }
+        );
+    }
+}
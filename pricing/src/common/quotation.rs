@@ -0,0 +1,220 @@
+use std::fmt;
+
+use crate::common::results::PricingError;
+
+/// The direction of rounding to the tick size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    Nearest,
+    Up,
+    Down,
+}
+
+/// The unit of a quotation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QuotationStyle {
+    PricePerUnit,
+    /// the price in percent of the given notional, as common for bonds and structured notes
+    PercentOfNotional {
+        notional: f64,
+    },
+}
+
+/// A quoted price as fixed-point decimal `mantissa * 10^-decimals`, which is exact in downstream systems.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Quote {
+    pub mantissa: i64,
+    pub decimals: u32,
+}
+
+impl Quote {
+    pub fn value(&self) -> f64 {
+        self.mantissa as f64 / 10_f64.powi(self.decimals as i32)
+    }
+}
+
+impl fmt::Display for Quote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let scale = 10_u64.pow(self.decimals);
+        let (integer, fraction) = (
+            self.mantissa.unsigned_abs() / scale,
+            self.mantissa.unsigned_abs() % scale,
+        );
+        if self.decimals == 0 {
+            write!(f, "{sign}{integer}")
+        } else {
+            write!(
+                f,
+                "{sign}{integer}.{fraction:0width$}",
+                width = self.decimals as usize
+            )
+        }
+    }
+}
+
+/// The market quotation convention of an instrument, converting theoretical values into quotes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuotationConvention {
+    /// the tick size in quotation units, which must be a multiple of $10^{-decimals}$
+    tick_size: f64,
+    decimals: u32,
+    rounding: Rounding,
+    style: QuotationStyle,
+    /// the accrued interest subtracted from the (dirty) value for clean price quotes
+    accrued_interest: Option<f64>,
+}
+
+impl QuotationConvention {
+    /// The convention, or the invalid parameter: a non-positive or non-finite tick size, or one which
+    /// is not a multiple of $10^{-decimals}$.
+    pub fn try_new(tick_size: f64, decimals: u32) -> Result<Self, PricingError> {
+        PricingError::check(
+            tick_size > 0.0 && tick_size.is_finite(),
+            "tick size",
+            tick_size,
+        )?;
+        let ticks_per_unit = tick_size * 10_f64.powi(decimals as i32);
+        PricingError::check(
+            (ticks_per_unit - ticks_per_unit.round()).abs() < 1e-9,
+            "tick size",
+            tick_size,
+        )?;
+        Ok(Self {
+            tick_size,
+            decimals,
+            rounding: Rounding::Nearest,
+            style: QuotationStyle::PricePerUnit,
+            accrued_interest: None,
+        })
+    }
+
+    pub fn with_rounding(self, rounding: Rounding) -> Self {
+        Self { rounding, ..self }
+    }
+
+    pub fn with_style(self, style: QuotationStyle) -> Self {
+        Self { style, ..self }
+    }
+
+    /// Quote clean prices, i.e. the value less the accrued interest (in the value's units).
+    pub fn with_clean_price(self, accrued_interest: f64) -> Self {
+        Self {
+            accrued_interest: Some(accrued_interest),
+            ..self
+        }
+    }
+
+    /// The quote of the (dirty) theoretical value, rounded to the tick size, which fails for a
+    /// non-finite value or a quote whose mantissa does not fit into an i64.
+    pub fn quote(&self, theoretical_value: f64) -> Result<Quote, PricingError> {
+        let value = theoretical_value - self.accrued_interest.unwrap_or(0.0);
+        let value = match self.style {
+            QuotationStyle::PricePerUnit => value,
+            QuotationStyle::PercentOfNotional { notional } => 100.0 * value / notional,
+        };
+        // the tolerance avoids rounding up or down values which are a multiple of the tick up to float errors
+        let ticks = value / self.tick_size;
+        let ticks = match self.rounding {
+            Rounding::Nearest => ticks.round(),
+            Rounding::Up => (ticks - 1e-9).ceil(),
+            Rounding::Down => (ticks + 1e-9).floor(),
+        };
+        if !ticks.is_finite() {
+            return Err(PricingError::NonFiniteValue(theoretical_value));
+        }
+        let unrepresentable = || {
+            PricingError::InvalidParameter(format!(
+                "quote of {theoretical_value} with {} decimals",
+                self.decimals
+            ))
+        };
+        let scale = 10_i64
+            .checked_pow(self.decimals)
+            .ok_or_else(unrepresentable)?;
+        let mantissa_per_tick = (self.tick_size * scale as f64).round() as i64;
+        // the bounds of i64 convert exactly to f64, unlike i64::MAX itself
+        if !(-(2.0_f64.powi(63))..2.0_f64.powi(63)).contains(&ticks) {
+            return Err(unrepresentable());
+        }
+        let mantissa = (ticks as i64)
+            .checked_mul(mantissa_per_tick)
+            .ok_or_else(unrepresentable)?;
+        Ok(Quote {
+            mantissa,
+            decimals: self.decimals,
+        })
+    }
+
+    /// The accrued interest $N c \tau$ of the notional N for the coupon rate c and the year fraction $\tau$
+    /// since the last coupon date.
+    pub fn accrued_interest(notional: f64, coupon_rate: f64, year_fraction: f64) -> f64 {
+        notional * coupon_rate * year_fraction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes() {
+        let convention = QuotationConvention::try_new(0.05, 2).unwrap();
+        assert_eq!(convention.quote(12.3374).unwrap().to_string(), "12.35");
+        assert_eq!(
+            convention
+                .with_rounding(Rounding::Down)
+                .quote(12.3374)
+                .unwrap()
+                .to_string(),
+            "12.30"
+        );
+        assert_eq!(
+            convention
+                .with_rounding(Rounding::Up)
+                .quote(12.35)
+                .unwrap()
+                .to_string(),
+            "12.35"
+        );
+        assert_eq!(convention.quote(-0.026).unwrap().to_string(), "-0.05");
+
+        // a bond with a notional of 1000 quoted clean in percent with a tick of 1/32
+        let accrued = QuotationConvention::accrued_interest(1000.0, 0.04, 0.25);
+        let bond = QuotationConvention::try_new(0.03125, 5)
+            .unwrap()
+            .with_style(QuotationStyle::PercentOfNotional { notional: 1000.0 })
+            .with_clean_price(accrued);
+        let quote = bond.quote(1_015.4 + accrued).unwrap();
+        assert_eq!(quote.to_string(), "101.53125");
+        assert_eq!(quote.value(), 101.53125);
+
+        assert!(matches!(
+            convention.quote(f64::NAN),
+            Err(PricingError::NonFiniteValue(_))
+        ));
+        assert!(convention.quote(f64::INFINITY).is_err());
+        // the mantissas beyond i64
+        assert!(convention.quote(1e18).is_err());
+        assert!(QuotationConvention::try_new(1.0, 19)
+            .unwrap()
+            .quote(1.0)
+            .is_err());
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        for (tick_size, decimals) in [
+            (0.0, 2),
+            (-0.05, 2),
+            (f64::NAN, 2),
+            (0.005, 2),
+            (0.03125, 4),
+        ] {
+            assert!(matches!(
+                QuotationConvention::try_new(tick_size, decimals),
+                Err(PricingError::InvalidParameter(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,728 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use ndarray::{Array1, Array2};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::audit::AuditRecord;
+use crate::numerics::correlation::covariance_to_correlation;
+#[cfg(feature = "parallel")]
+use crate::numerics::summation::Reproducibility;
+#[cfg(feature = "mc")]
+use crate::simulation::greeks::SpotGreeks;
+#[cfg(feature = "mc")]
+use crate::simulation::scenarios::{MarketScenario, ScenarioResults};
+
+/// The 97.5% quantile of the standard normal distribution for 95% confidence intervals.
+const Z_95: f64 = 1.959_963_984_540_054;
+
+/// The handling of NaN or infinite samples of an estimate, e.g. payoffs of paths whose discretization
+/// blew up.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum NonFinitePolicy {
+    /// the estimate fails as non-finite
+    #[default]
+    Fail,
+    /// the estimate is over the finite samples, and the others are reported as quarantined,
+    /// unless their fraction of the samples exceeds the maximum
+    Quarantine { max_fraction: f64 },
+}
+
+/// The samples excluded from an estimate as they were NaN or infinite, with the first of them
+/// to inspect or replay its path.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Quarantine {
+    pub nr_samples: usize,
+    /// the index of the first excluded sample, e.g. of its path
+    pub first_index: usize,
+    pub first_value: f64,
+}
+
+/// A non-fatal issue of a pricing, i.e. an adjustment of its inputs the engine made instead of failing.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PricingWarning {
+    /// weights summing to 1 up to the tolerance, which were normalized by their sum
+    WeightsNormalized { sum: f64 },
+    /// a volatility of the surface beyond its last time or outside of its strikes, extrapolated flat
+    VolatilityExtrapolated { time: f64, strike: f64 },
+    /// a correlation matrix which was not positive definite, with its smallest eigenvalue, replaced
+    /// by its repaired version
+    CorrelationRepaired { min_eigenvalue: f64 },
+}
+
+impl fmt::Display for PricingWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PricingWarning::WeightsNormalized { sum } => {
+                write!(f, "weights normalized from the sum {sum}")
+            }
+            PricingWarning::VolatilityExtrapolated { time, strike } => {
+                write!(
+                    f,
+                    "volatility extrapolated at time {time} and strike {strike}"
+                )
+            }
+            PricingWarning::CorrelationRepaired { min_eigenvalue } => write!(
+                f,
+                "correlation repaired from the smallest eigenvalue {min_eigenvalue}"
+            ),
+        }
+    }
+}
+
+/// A price with the statistics of its estimation; the standard error and the confidence interval
+/// are available for sampled (Monte Carlo) prices.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PriceResult {
+    pub value: f64,
+    pub std_error: Option<f64>,
+    /// the 95% confidence interval of the value
+    pub confidence_interval: Option<(f64, f64)>,
+    pub nr_samples: Option<usize>,
+    pub runtime: Option<Duration>,
+    /// the non-finite samples excluded from the estimate, see `NonFinitePolicy`
+    pub quarantine: Option<Quarantine>,
+    /// the adjustments of the inputs made by the engine
+    pub warnings: Vec<PricingWarning>,
+    /// the inputs resolved by the engine in audit mode, see `PricerRegistry::with_audit`
+    pub audit: Option<AuditRecord>,
+}
+
+fn sequential_sum(values: &[f64]) -> f64 {
+    values.iter().sum()
+}
+
+impl PriceResult {
+    /// The price of an exact (analytic or deterministic) engine.
+    pub fn exact(value: f64) -> Self {
+        Self {
+            value,
+            std_error: None,
+            confidence_interval: None,
+            nr_samples: None,
+            runtime: None,
+            quarantine: None,
+            warnings: Vec::new(),
+            audit: None,
+        }
+    }
+
+    /// The mean of the (discounted) payoff samples with its standard error and 95% confidence interval,
+    /// which require at least two samples.
+    pub fn from_samples(samples: &[f64]) -> Result<Self, PricingError> {
+        Self::estimate(samples, &sequential_sum)
+    }
+
+    /// The estimate of the samples as by `from_samples`, where the policy decides on NaN or infinite
+    /// samples. The number of samples of a quarantining estimate counts the finite samples only.
+    pub fn from_samples_with_policy(
+        samples: &[f64],
+        policy: NonFinitePolicy,
+    ) -> Result<Self, PricingError> {
+        Self::estimate_with_policy(samples, policy, &sequential_sum)
+    }
+
+    /// The estimate of the samples as by `from_samples_with_policy`, with the mean and the variance
+    /// summed in the reproducibility mode, see `Reproducibility`.
+    #[cfg(feature = "parallel")]
+    pub fn from_samples_reproducible(
+        samples: &[f64],
+        policy: NonFinitePolicy,
+        reproducibility: Reproducibility,
+    ) -> Result<Self, PricingError> {
+        Self::estimate_with_policy(samples, policy, &|values| reproducibility.sum(values))
+    }
+
+    fn estimate(samples: &[f64], sum: &dyn Fn(&[f64]) -> f64) -> Result<Self, PricingError> {
+        if samples.is_empty() {
+            return Err(PricingError::NoPaths);
+        }
+        let n = samples.len() as f64;
+        let mean = sum(samples) / n;
+        if !mean.is_finite() {
+            return Err(PricingError::NonFiniteValue(mean));
+        }
+        if samples.len() < 2 {
+            return Ok(Self {
+                nr_samples: Some(1),
+                ..Self::exact(mean)
+            });
+        }
+        let squared_deviations: Vec<f64> = samples.iter().map(|x| (x - mean).powi(2)).collect();
+        let variance = sum(&squared_deviations) / (n - 1.0);
+        let std_error = (variance / n).sqrt();
+        Ok(Self {
+            value: mean,
+            std_error: Some(std_error),
+            confidence_interval: Some((mean - Z_95 * std_error, mean + Z_95 * std_error)),
+            nr_samples: Some(samples.len()),
+            runtime: None,
+            quarantine: None,
+            warnings: Vec::new(),
+            audit: None,
+        })
+    }
+
+    fn estimate_with_policy(
+        samples: &[f64],
+        policy: NonFinitePolicy,
+        sum: &dyn Fn(&[f64]) -> f64,
+    ) -> Result<Self, PricingError> {
+        let NonFinitePolicy::Quarantine { max_fraction } = policy else {
+            return Self::estimate(samples, sum);
+        };
+        let Some(first_index) = samples.iter().position(|x| !x.is_finite()) else {
+            return Self::estimate(samples, sum);
+        };
+        let finite: Vec<f64> = samples.iter().copied().filter(|x| x.is_finite()).collect();
+        let nr_quarantined = samples.len() - finite.len();
+        if nr_quarantined as f64 > max_fraction * samples.len() as f64 {
+            return Err(PricingError::InvalidPayoffs(nr_quarantined));
+        }
+        Ok(Self {
+            quarantine: Some(Quarantine {
+                nr_samples: nr_quarantined,
+                first_index,
+                first_value: samples[first_index],
+            }),
+            ..Self::estimate(&finite, sum)?
+        })
+    }
+
+    /// The price of the path payoffs, where paths without a payoff contribute 0.
+    pub fn from_payoffs(payoffs: &[Option<f64>]) -> Result<Self, PricingError> {
+        Self::from_payoffs_with_policy(payoffs, NonFinitePolicy::Fail)
+    }
+
+    /// The price of the path payoffs as by `from_payoffs`, where the policy decides on NaN or
+    /// infinite payoffs.
+    pub fn from_payoffs_with_policy(
+        payoffs: &[Option<f64>],
+        policy: NonFinitePolicy,
+    ) -> Result<Self, PricingError> {
+        if !payoffs.is_empty() && payoffs.iter().all(Option::is_none) {
+            return Err(PricingError::NoPayoff);
+        }
+        let samples: Vec<f64> = payoffs.iter().map(|p| p.unwrap_or_default()).collect();
+        Self::from_samples_with_policy(&samples, policy)
+    }
+
+    /// The price of $c + a X$ for the price of X, e.g. of a note less an embedded option.
+    pub fn affine(self, constant: f64, factor: f64) -> Self {
+        let interval = self.confidence_interval.map(|(lower, upper)| {
+            let (lower, upper) = (constant + factor * lower, constant + factor * upper);
+            (lower.min(upper), lower.max(upper))
+        });
+        Self {
+            value: constant + factor * self.value,
+            std_error: self.std_error.map(|e| e * factor.abs()),
+            confidence_interval: interval,
+            ..self
+        }
+    }
+
+    pub fn with_runtime(self, runtime: Duration) -> Self {
+        Self {
+            runtime: Some(runtime),
+            ..self
+        }
+    }
+
+    /// The result with the warnings added to its warnings.
+    pub fn with_warnings(mut self, warnings: impl IntoIterator<Item = PricingWarning>) -> Self {
+        self.warnings.extend(warnings);
+        self
+    }
+
+    pub fn with_audit(self, audit: AuditRecord) -> Self {
+        Self {
+            audit: Some(audit),
+            ..self
+        }
+    }
+
+    /// The result of the pricing with its runtime.
+    pub fn timed<E>(pricing: impl FnOnce() -> Result<Self, E>) -> Result<Self, E> {
+        let start = Instant::now();
+        pricing().map(|result| result.with_runtime(start.elapsed()))
+    }
+}
+
+/// The joint estimate of several prices on the same samples, e.g. payoffs evaluated on the same paths,
+/// with the covariance of their estimation errors, such that the errors of spreads and ratios of the
+/// prices account for their correlation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JointEstimate {
+    pub means: Vec<f64>,
+    /// the covariance of the estimators of the means, i.e. the sample covariance divided by n
+    pub covariance: Array2<f64>,
+    pub nr_samples: usize,
+}
+
+impl JointEstimate {
+    /// The estimate of the samples of each price, which require at least two and equally many samples.
+    pub fn from_samples(samples: &[Vec<f64>]) -> Result<Self, PricingError> {
+        let nr_samples = samples.first().map_or(0, Vec::len);
+        if samples.is_empty() || nr_samples < 2 {
+            return Err(PricingError::NoPaths);
+        }
+        PricingError::check(
+            samples.iter().all(|s| s.len() == nr_samples),
+            "nr samples",
+            nr_samples as f64,
+        )?;
+        let n = nr_samples as f64;
+        let means: Vec<f64> = samples.iter().map(|s| s.iter().sum::<f64>() / n).collect();
+        if let Some(mean) = means.iter().find(|mean| !mean.is_finite()) {
+            return Err(PricingError::NonFiniteValue(*mean));
+        }
+        let covariance = Array2::from_shape_fn((samples.len(), samples.len()), |(i, j)| {
+            samples[i]
+                .iter()
+                .zip(&samples[j])
+                .map(|(x, y)| (x - means[i]) * (y - means[j]))
+                .sum::<f64>()
+                / ((n - 1.0) * n)
+        });
+        Ok(Self {
+            means,
+            covariance,
+            nr_samples,
+        })
+    }
+
+    pub fn std_errors(&self) -> Vec<f64> {
+        self.covariance.diag().iter().map(|v| v.sqrt()).collect()
+    }
+
+    /// The correlation of the estimation errors.
+    pub fn correlation(&self) -> Array2<f64> {
+        covariance_to_correlation(&self.covariance)
+    }
+
+    fn price(&self, value: f64, variance: f64) -> PriceResult {
+        let std_error = variance.max(0.0).sqrt();
+        PriceResult {
+            std_error: Some(std_error),
+            confidence_interval: Some((value - Z_95 * std_error, value + Z_95 * std_error)),
+            nr_samples: Some(self.nr_samples),
+            ..PriceResult::exact(value)
+        }
+    }
+
+    /// The price of $\sum_i w_i X_i$, e.g. a spread for the weights (1, -1), with the standard error
+    /// $\sqrt{w^T \Sigma w}$. Fails unless there is one weight per price.
+    pub fn linear_combination(&self, weights: &[f64]) -> Result<PriceResult, PricingError> {
+        PricingError::check(
+            weights.len() == self.means.len(),
+            "number of weights",
+            weights.len() as f64,
+        )?;
+        let weights = Array1::from(weights.to_vec());
+        let value = weights.dot(&Array1::from(self.means.clone()));
+        Ok(self.price(value, weights.dot(&self.covariance.dot(&weights))))
+    }
+
+    /// The ratio $X_i / X_j$ of the prices, e.g. a hedge ratio, with the standard error of the delta method
+    /// $\sqrt{g^T \Sigma g}$ for the gradient $g = (1 / X_j, -X_i / X_j^2)$ of the ratio.
+    /// Fails for indices beyond the prices or a denominator of zero price.
+    /// See https://en.wikipedia.org/wiki/Delta_method
+    pub fn ratio(&self, i: usize, j: usize) -> Result<PriceResult, PricingError> {
+        let nr_prices = self.means.len();
+        PricingError::check(i < nr_prices, "price index", i as f64)?;
+        PricingError::check(j < nr_prices, "price index", j as f64)?;
+        let (x, y) = (self.means[i], self.means[j]);
+        PricingError::check(y != 0.0, "denominator price", y)?;
+        let (gx, gy) = (1.0 / y, -x / (y * y));
+        let variance = gx * gx * self.covariance[[i, i]]
+            + 2.0 * gx * gy * self.covariance[[i, j]]
+            + gy * gy * self.covariance[[j, j]];
+        Ok(self.price(x / y, variance))
+    }
+}
+
+/// The reason a pricer fails to price a product.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PricingError {
+    /// a parameter of the product or the engine is out of its domain
+    InvalidParameter(String),
+    /// the simulation has no paths
+    NoPaths,
+    /// no path yields a payoff
+    NoPayoff,
+    /// the number of paths without a (finite) payoff, which the evaluation policy rejects
+    InvalidPayoffs(usize),
+    /// the estimate is NaN or infinite, e.g. for exploding paths
+    NonFiniteValue(f64),
+    /// the market lacks the spot, curve or volatility of the product
+    MissingMarketData(String),
+    /// the maturity of the product lies beyond the last pillar of the curve
+    CurveHorizonExceeded {
+        curve: String,
+        maturity: f64,
+        horizon: f64,
+    },
+    /// the engine does not price the product
+    UnsupportedProduct(String),
+}
+
+impl PricingError {
+    /// An invalid parameter error unless the condition on the parameter holds.
+    pub(crate) fn check(condition: bool, parameter: &str, value: f64) -> Result<(), Self> {
+        if condition {
+            Ok(())
+        } else {
+            Err(Self::InvalidParameter(format!("{parameter} = {value}")))
+        }
+    }
+}
+
+impl fmt::Display for PricingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PricingError::InvalidParameter(message) => write!(f, "invalid parameter {message}"),
+            PricingError::NoPaths => write!(f, "no paths to simulate"),
+            PricingError::NoPayoff => write!(f, "no path yields a payoff"),
+            PricingError::InvalidPayoffs(nr_paths) => {
+                write!(f, "{nr_paths} paths without a finite payoff")
+            }
+            PricingError::NonFiniteValue(value) => write!(f, "non-finite estimate {value}"),
+            PricingError::MissingMarketData(message) => {
+                write!(f, "missing market data of {message}")
+            }
+            PricingError::CurveHorizonExceeded {
+                curve,
+                maturity,
+                horizon,
+            } => write!(
+                f,
+                "maturity {maturity} beyond the horizon {horizon} of the curve {curve}"
+            ),
+            PricingError::UnsupportedProduct(message) => write!(f, "no pricing of {message}"),
+        }
+    }
+}
+
+impl std::error::Error for PricingError {}
+
+#[cfg(feature = "risk-integration")]
+impl From<risk::RiskError> for PricingError {
+    fn from(error: risk::RiskError) -> Self {
+        PricingError::InvalidParameter(error.to_string())
+    }
+}
+
+impl fmt::Display for PriceResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.6}", self.value)?;
+        if let Some(std_error) = self.std_error {
+            write!(f, " ± {std_error:.6}")?;
+        }
+        if let Some((lower, upper)) = self.confidence_interval {
+            write!(f, " (95% CI [{lower:.6}, {upper:.6}])")?;
+        }
+        if let Some(nr_samples) = self.nr_samples {
+            write!(f, ", {nr_samples} samples")?;
+        }
+        if let Some(runtime) = self.runtime {
+            write!(f, ", {runtime:.3?}")?;
+        }
+        for warning in &self.warnings {
+            write!(f, "; warning: {warning}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The value and the greeks of a product; greeks an engine does not compute are None.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GreeksResult {
+    pub value: f64,
+    pub delta: Option<f64>,
+    pub gamma: Option<f64>,
+    pub vega: Option<f64>,
+    pub theta: Option<f64>,
+    pub rho: Option<f64>,
+}
+
+#[cfg(feature = "mc")]
+impl From<SpotGreeks> for GreeksResult {
+    fn from(greeks: SpotGreeks) -> Self {
+        Self {
+            value: greeks.value,
+            delta: Some(greeks.delta),
+            gamma: Some(greeks.gamma),
+            ..Default::default()
+        }
+    }
+}
+
+impl fmt::Display for GreeksResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "value {:.6}", self.value)?;
+        for (name, greek) in [
+            ("delta", self.delta),
+            ("gamma", self.gamma),
+            ("vega", self.vega),
+            ("theta", self.theta),
+            ("rho", self.rho),
+        ] {
+            if let Some(greek) = greek {
+                write!(f, ", {name} {greek:.6}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The value at risk and the expected shortfall of P&L scenarios at the confidence level.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VarResult {
+    pub level: f64,
+    pub value_at_risk: f64,
+    pub expected_shortfall: f64,
+    pub nr_scenarios: usize,
+}
+
+impl VarResult {
+    /// The empirical VaR (the level quantile of the losses) and ES (the mean of the worst
+    /// $n (1 - level)$ losses, where the boundary loss enters with its fractional weight) of the P&L
+    /// scenarios, as by the loss distributions of the risk crate.
+    pub fn from_pnl(pnl: &[f64], level: f64) -> Option<Self> {
+        if pnl.is_empty() || !(0.0 < level && level < 1.0) || pnl.iter().any(|x| x.is_nan()) {
+            return None;
+        }
+        let mut losses: Vec<f64> = pnl.iter().map(|x| -x).collect();
+        losses.sort_by(f64::total_cmp);
+        let n = losses.len();
+        let idx = ((level * n as f64).ceil() as usize).clamp(1, n) - 1;
+        let tail_size = (1.0 - level) * n as f64;
+        let mut remaining = tail_size;
+        let mut tail_sum = 0.0;
+        for loss in losses.iter().rev() {
+            if remaining <= 0.0 {
+                break;
+            }
+            let weight = remaining.min(1.0);
+            tail_sum += weight * loss;
+            remaining -= weight;
+        }
+        Some(Self {
+            level,
+            value_at_risk: losses[idx],
+            expected_shortfall: tail_sum / tail_size,
+            nr_scenarios: n,
+        })
+    }
+}
+
+#[cfg(feature = "risk-integration")]
+impl VarResult {
+    /// The VaR and the ES of the loss distribution of the risk crate, the same as `from_pnl`.
+    pub fn from_losses(
+        losses: &risk::value_at_risk::EmpiricalLosses,
+        level: f64,
+    ) -> Result<Self, PricingError> {
+        Ok(Self {
+            level,
+            value_at_risk: losses.value_at_risk(level)?,
+            expected_shortfall: losses.expected_shortfall(level)?,
+            nr_scenarios: losses.len(),
+        })
+    }
+}
+
+impl fmt::Display for VarResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "VaR({}) {:.6}, ES {:.6}, {} scenarios",
+            self.level, self.value_at_risk, self.expected_shortfall, self.nr_scenarios
+        )
+    }
+}
+
+/// The values of products (rows) under named scenarios (columns), with the error message of failed cells.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScenarioGridResult {
+    pub products: Vec<String>,
+    pub scenarios: Vec<String>,
+    pub values: Vec<Vec<Result<f64, String>>>,
+}
+
+#[cfg(feature = "mc")]
+impl ScenarioGridResult {
+    pub fn new(products: &[&str], scenarios: &[MarketScenario], results: &ScenarioResults) -> Self {
+        assert_eq!(products.len(), results.cells.len());
+        Self {
+            products: products.iter().map(|p| p.to_string()).collect(),
+            scenarios: scenarios.iter().map(|s| s.name.clone()).collect(),
+            values: results
+                .cells
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|cell| cell.clone().map_err(|err| err.to_string()))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for ScenarioGridResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:<16}", "")?;
+        for scenario in &self.scenarios {
+            write!(f, " {scenario:>14}")?;
+        }
+        writeln!(f)?;
+        for (product, row) in self.products.iter().zip(&self.values) {
+            write!(f, "{product:<16}")?;
+            for cell in row {
+                match cell {
+                    Ok(value) => write!(f, " {value:>14.6}")?,
+                    Err(_) => write!(f, " {:>14}", "error")?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "mc"))]
+mod tests {
+    use super::*;
+    use crate::simulation::scenarios::ScenarioError;
+
+    #[test]
+    fn price_and_risk_results() {
+        let samples: Vec<f64> = (0..100).map(|i| i as f64).collect();
+        let price = PriceResult::from_samples(&samples)
+            .unwrap()
+            .with_runtime(Duration::from_millis(12));
+        assert_eq!(price.value, 49.5);
+        let std_error = price.std_error.unwrap();
+        assert!((std_error - (841.666_666_666_666_6_f64 / 100.0).sqrt()).abs() < 1e-12);
+        let (lower, upper) = price.confidence_interval.unwrap();
+        assert!((upper - lower - 2.0 * Z_95 * std_error).abs() < 1e-12);
+        assert!(price
+            .to_string()
+            .starts_with("49.500000 ± 2.901149 (95% CI ["));
+        assert!(price.to_string().ends_with(", 100 samples, 12.000ms"));
+        assert_eq!(PriceResult::exact(1.5).to_string(), "1.500000");
+        assert!(
+            PriceResult::timed(|| Ok::<_, PricingError>(PriceResult::exact(1.0)))
+                .unwrap()
+                .runtime
+                .is_some()
+        );
+
+        let payoffs = [Some(2.0), None, Some(4.0), None];
+        assert_eq!(PriceResult::from_payoffs(&payoffs).unwrap().value, 1.5);
+        assert_eq!(
+            PriceResult::from_payoffs(&[None, None]),
+            Err(PricingError::NoPayoff)
+        );
+        assert_eq!(PriceResult::from_samples(&[]), Err(PricingError::NoPaths));
+        assert!(matches!(
+            PriceResult::from_samples(&[1.0, f64::NAN]),
+            Err(PricingError::NonFiniteValue(_))
+        ));
+        let note = price.clone().affine(100.0, -1.0);
+        assert_eq!(note.value, 50.5);
+        assert_eq!(note.std_error, price.std_error);
+        assert_eq!(note.confidence_interval.unwrap().0, 100.0 - upper);
+
+        let greeks: GreeksResult = SpotGreeks {
+            value: 10.0,
+            delta: 0.5,
+            gamma: 0.02,
+        }
+        .into();
+        assert_eq!(
+            greeks.to_string(),
+            "value 10.000000, delta 0.500000, gamma 0.020000"
+        );
+
+        let pnl: Vec<f64> = (1..=100).map(|i| 50.0 - i as f64).collect();
+        let var = VarResult::from_pnl(&pnl, 0.95).unwrap();
+        assert_eq!((var.value_at_risk, var.expected_shortfall), (45.0, 48.0));
+        // the boundary loss 45 enters the ES of the worst 5.5 losses with half its weight
+        let var = VarResult::from_pnl(&pnl, 0.945).unwrap();
+        assert!((var.expected_shortfall - (240.0 + 0.5 * 45.0) / 5.5).abs() < 1e-12);
+        assert!(VarResult::from_pnl(&pnl, 1.0).is_none());
+    }
+
+    #[test]
+    fn joint_estimate_of_shifted_samples() {
+        let x: Vec<f64> = (0..100).map(|i| i as f64).collect();
+        let y: Vec<f64> = x.iter().map(|x| x + 1.0).collect();
+        let joint = JointEstimate::from_samples(&[x.clone(), y]).unwrap();
+        let std_error = PriceResult::from_samples(&x).unwrap().std_error.unwrap();
+        assert!((joint.std_errors()[1] - std_error).abs() < 1e-12);
+        assert!((joint.correlation()[[0, 1]] - 1.0).abs() < 1e-12);
+        // the spread of perfectly correlated estimates is exact
+        let spread = joint.linear_combination(&[1.0, -1.0]).unwrap();
+        assert!((spread.value + 1.0).abs() < 1e-12 && spread.std_error.unwrap() < 1e-6);
+        let ratio = joint.ratio(1, 0).unwrap();
+        assert!((ratio.value - 50.5 / 49.5).abs() < 1e-12);
+        // d(1 + 1/m) = -dm / m^2
+        assert!((ratio.std_error.unwrap() - std_error / (49.5 * 49.5)).abs() < 1e-9);
+        assert_eq!(JointEstimate::from_samples(&[x]).unwrap().nr_samples, 100);
+        assert!(JointEstimate::from_samples(&[vec![1.0, 2.0], vec![1.0]]).is_err());
+        assert!(joint.linear_combination(&[1.0]).is_err());
+        assert!(joint.ratio(0, 2).is_err());
+        // the ratio to a zero price is undefined
+        let zero = JointEstimate::from_samples(&[vec![1.0, 2.0], vec![-1.0, 1.0]]).unwrap();
+        assert!(matches!(
+            zero.ratio(0, 1),
+            Err(PricingError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "risk-integration")]
+    fn var_of_risk_losses() {
+        let pnl: Vec<f64> = (1..=100).map(|i| 50.0 - i as f64).collect();
+        let losses = risk::value_at_risk::EmpiricalLosses::from_pnl(&pnl).unwrap();
+        let var = VarResult::from_losses(&losses, 0.95).unwrap();
+        for level in [0.95, 0.945] {
+            let empirical = VarResult::from_pnl(&pnl, level).unwrap();
+            assert_eq!(VarResult::from_losses(&losses, level).unwrap(), empirical);
+        }
+        assert_eq!((var.expected_shortfall, var.nr_scenarios), (48.0, 100));
+        assert!(matches!(
+            VarResult::from_losses(&losses, 1.5),
+            Err(PricingError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn scenario_grid() {
+        let scenarios = vec![
+            MarketScenario::new("base", 100.0, 0.2, 0.01),
+            MarketScenario::new("down", 90.0, 0.2, 0.01),
+        ];
+        let results = ScenarioResults {
+            cells: vec![vec![Ok(10.0), Err(ScenarioError::NoValue)]],
+        };
+        let grid = ScenarioGridResult::new(&["call"], &scenarios, &results);
+        assert_eq!(
+            grid.values[0][1],
+            Err("no path yields a payoff".to_string())
+        );
+        let table = grid.to_string();
+        assert!(table.contains("base") && table.contains("10.000000") && table.contains("error"));
+    }
+}
@@ -0,0 +1,142 @@
+use ndarray::Array2;
+use proptest::prelude::*;
+
+use crate::common::models::{DerivativeParameter, ExerciseType};
+use crate::simulation::sde::gbm::GeometricBrownianMotion;
+
+/// Valid option parameters: strikes within a factor 2 of the spot, maturities up to 10 years,
+/// slightly negative to high rates and volatilities between 5% and 100%.
+pub fn derivative_parameter() -> impl Strategy<Value = DerivativeParameter> {
+    (1.0..1000.0, 0.5..2.0, 0.01..10.0, -0.02..0.1, 0.05..1.0).prop_map(
+        |(asset_price, moneyness, time_to_expiration, rfr, vola): (f64, f64, f64, f64, f64)| {
+            DerivativeParameter::new(
+                asset_price,
+                asset_price * moneyness,
+                time_to_expiration,
+                rfr,
+                vola,
+            )
+        },
+    )
+}
+
+impl Arbitrary for DerivativeParameter {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        derivative_parameter().boxed()
+    }
+}
+
+/// GBM dynamics with valid drift, volatility and daily to yearly time steps.
+pub fn gbm() -> impl Strategy<Value = GeometricBrownianMotion> {
+    (1.0..1000.0, -0.05..0.15, 0.05..1.0, 1.0 / 365.0..1.0).prop_map(
+        |(initial_value, drift, vola, dt): (f64, f64, f64, f64)| {
+            GeometricBrownianMotion::new(initial_value, drift, vola, dt)
+        },
+    )
+}
+
+/// Correlation matrices of the given dimension, i.e. symmetric positive definite with unit diagonal,
+/// normalized from $A A^T + \epsilon I$ for a random matrix A.
+pub fn correlation_matrix(dim: usize) -> impl Strategy<Value = Array2<f64>> {
+    proptest::collection::vec(-1.0..1.0, dim * dim).prop_map(move |entries: Vec<f64>| {
+        let a = Array2::from_shape_vec((dim, dim), entries).unwrap();
+        let covariance = a.dot(&a.t()) + 1e-3 * Array2::<f64>::eye(dim);
+        Array2::from_shape_fn((dim, dim), |(i, j)| {
+            covariance[[i, j]] / (covariance[[i, i]] * covariance[[j, j]]).sqrt()
+        })
+    })
+}
+
+/// Checks the model-free bounds $max(S - K e^{-rT}, 0) \le C \le S$ and
+/// $max(K e^{-rT} - S, 0) \le P \le K e^{-rT}$ up to the tolerance.
+pub fn check_price_bounds(
+    dp: &DerivativeParameter,
+    call: f64,
+    put: f64,
+    tolerance: f64,
+) -> Result<(), String> {
+    let discounted_strike = dp.strike * (-dp.rfr * dp.time_to_expiration).exp();
+    let call_bounds = (
+        ExerciseType::Call.payoff(dp.asset_price, discounted_strike),
+        dp.asset_price,
+    );
+    let put_bounds = (
+        ExerciseType::Put.payoff(dp.asset_price, discounted_strike),
+        discounted_strike,
+    );
+    for (name, price, (lower, upper)) in [("call", call, call_bounds), ("put", put, put_bounds)] {
+        if price < lower - tolerance || price > upper + tolerance {
+            return Err(format!("{name} price {price} not in [{lower}, {upper}]"));
+        }
+    }
+    Ok(())
+}
+
+/// Checks the put-call parity $C - P = S - K e^{-rT}$ up to the tolerance.
+pub fn check_put_call_parity(
+    dp: &DerivativeParameter,
+    call: f64,
+    put: f64,
+    tolerance: f64,
+) -> Result<(), String> {
+    let forward_value = dp.asset_price - dp.strike * (-dp.rfr * dp.time_to_expiration).exp();
+    let violation = call - put - forward_value;
+    if violation.abs() > tolerance {
+        return Err(format!("put-call parity violated by {violation}"));
+    }
+    Ok(())
+}
+
+/// Checks that the prices for increasing strikes are decreasing (calls) or increasing (puts).
+pub fn check_monotone_in_strike(
+    prices: &[f64],
+    is_call: bool,
+    tolerance: f64,
+) -> Result<(), String> {
+    let sign = if is_call { 1.0 } else { -1.0 };
+    match prices
+        .windows(2)
+        .position(|w| sign * (w[1] - w[0]) > tolerance)
+    {
+        Some(idx) => Err(format!("prices not monotone at strike index {idx}")),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::{BlackScholesMerton, OptionPrice};
+
+    proptest! {
+        #[test]
+        fn black_scholes_invariants(dp in any::<DerivativeParameter>()) {
+            let (call, put) = (BlackScholesMerton::call(&dp), BlackScholesMerton::put(&dp));
+            prop_assert!(check_price_bounds(&dp, call, put, 1e-8).is_ok());
+            prop_assert!(check_put_call_parity(&dp, call, put, 1e-8 * dp.asset_price).is_ok());
+
+            let calls: Vec<f64> = [0.8, 0.9, 1.0, 1.1, 1.2]
+                .iter()
+                .map(|m| {
+                    let strike = dp.strike * m;
+                    BlackScholesMerton::call(&DerivativeParameter { strike, ..dp })
+                })
+                .collect();
+            prop_assert!(check_monotone_in_strike(&calls, true, 1e-10).is_ok());
+        }
+
+        #[test]
+        fn valid_correlation_matrices(corr in correlation_matrix(4)) {
+            for i in 0..4 {
+                prop_assert!((corr[[i, i]] - 1.0).abs() < 1e-12);
+                for j in 0..4 {
+                    prop_assert!((corr[[i, j]] - corr[[j, i]]).abs() < 1e-12);
+                    prop_assert!(corr[[i, j]].abs() <= 1.0 + 1e-12);
+                }
+            }
+        }
+    }
+}
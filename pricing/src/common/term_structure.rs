@@ -0,0 +1,72 @@
+use ndarray::{Array1, Array2};
+
+/// Piecewise-constant term structure per coordinate (e.g. per asset of a basket),
+/// where `values[[i, k]]` applies to coordinate `i` on the time bucket $[t_{k-1}, t_k)$ with $t_{-1} = 0$,
+/// for the bucket end times $t_k$ given in `times`. The last bucket is extrapolated flat.
+#[derive(Clone, Debug)]
+pub struct PiecewiseConstantTermStructure {
+    times: Vec<f64>,
+    values: Array2<f64>,
+}
+
+impl PiecewiseConstantTermStructure {
+    pub fn new(times: Vec<f64>, values: Array2<f64>) -> Self {
+        assert!(!times.is_empty());
+        assert_eq!(values.ncols(), times.len());
+        assert!(times.windows(2).all(|w| w[0] < w[1]));
+        Self { times, values }
+    }
+
+    /// Constant values over time for each coordinate.
+    pub fn flat(values: Array1<f64>) -> Self {
+        let dim = values.len();
+        Self::new(vec![f64::INFINITY], values.into_shape((dim, 1)).unwrap())
+    }
+
+    pub fn dim(&self) -> usize {
+        self.values.nrows()
+    }
+
+    fn bucket(&self, t: f64) -> usize {
+        self.times
+            .iter()
+            .position(|&t_k| t < t_k)
+            .unwrap_or(self.times.len() - 1)
+    }
+
+    /// The values of all coordinates at time t.
+    pub fn values_at(&self, t: f64) -> Array1<f64> {
+        self.values.column(self.bucket(t)).to_owned()
+    }
+
+    /// The values on the time grid $t_0 + j dt$ for $j = 0, ..., nr_steps - 1$ as (dim x nr_steps) matrix,
+    /// i.e. column $j$ applies to the step from $t_0 + j dt$ to $t_0 + (j+1) dt$.
+    pub fn on_grid(&self, t0: f64, dt: f64, nr_steps: usize) -> Array2<f64> {
+        let mut grid_values = Array2::zeros((self.dim(), nr_steps));
+        for (j, mut col) in grid_values.columns_mut().into_iter().enumerate() {
+            col.assign(&self.values.column(self.bucket(t0 + j as f64 * dt)));
+        }
+        grid_values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::{arr1, arr2};
+
+    #[test]
+    fn piecewise_constant_values() {
+        let term_structure =
+            PiecewiseConstantTermStructure::new(vec![0.5, 1.0], arr2(&[[0.1, 0.2], [0.3, 0.4]]));
+        assert_eq!(term_structure.values_at(0.0), arr1(&[0.1, 0.3]));
+        assert_eq!(term_structure.values_at(0.5), arr1(&[0.2, 0.4]));
+        assert_eq!(term_structure.values_at(2.0), arr1(&[0.2, 0.4]));
+
+        let grid_values = term_structure.on_grid(0.25, 0.25, 3);
+        assert_eq!(grid_values, arr2(&[[0.1, 0.2, 0.2], [0.3, 0.4, 0.4]]));
+
+        let flat = PiecewiseConstantTermStructure::flat(arr1(&[0.1, 0.3]));
+        assert_eq!(flat.values_at(100.0), arr1(&[0.1, 0.3]));
+    }
+}
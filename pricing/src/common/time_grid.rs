@@ -0,0 +1,100 @@
+use crate::common::results::PricingError;
+
+/// Increasing simulation times (in years) starting at 0, including event times such as
+/// ex-dividend or observation dates as grid points.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeGrid {
+    times: Vec<f64>,
+}
+
+impl TimeGrid {
+    /// Times closer than this are considered the same grid point.
+    const TIME_TOLERANCE: f64 = 1e-10;
+
+    pub fn new(mut times: Vec<f64>) -> Self {
+        times.sort_by(|a, b| a.total_cmp(b));
+        times.dedup_by(|a, b| (*a - *b).abs() < Self::TIME_TOLERANCE);
+        assert!(times.first() == Some(&0.0) && times.len() > 1);
+        Self { times }
+    }
+
+    /// The grid of `nr_steps` equal steps until the maturity.
+    pub fn uniform(maturity: f64, nr_steps: usize) -> Self {
+        assert!(maturity > 0.0 && nr_steps > 0);
+        let dt = maturity / nr_steps as f64;
+        Self::new((0..=nr_steps).map(|j| j as f64 * dt).collect())
+    }
+
+    /// The uniform grid, or the invalid maturity or number of steps, see `step_size`.
+    pub fn try_uniform(maturity: f64, nr_steps: usize) -> Result<Self, PricingError> {
+        Self::step_size(maturity, nr_steps)?;
+        Ok(Self::uniform(maturity, nr_steps))
+    }
+
+    /// The length of `nr_steps` equal steps until the maturity, which requires a positive finite
+    /// maturity and at least one step.
+    pub fn step_size(maturity: f64, nr_steps: usize) -> Result<f64, PricingError> {
+        PricingError::check(maturity > 0.0 && maturity.is_finite(), "maturity", maturity)?;
+        PricingError::check(nr_steps > 0, "nr steps", nr_steps as f64)?;
+        Ok(maturity / nr_steps as f64)
+    }
+
+    /// The grid with the event times within the grid's horizon added as grid points.
+    pub fn with_event_times(self, event_times: &[f64]) -> Self {
+        let horizon = self.maturity();
+        let mut times = self.times;
+        times.extend(event_times.iter().filter(|t| **t > 0.0 && **t <= horizon));
+        Self::new(times)
+    }
+
+    pub fn times(&self) -> &[f64] {
+        &self.times
+    }
+
+    pub fn maturity(&self) -> f64 {
+        *self.times.last().unwrap()
+    }
+
+    pub fn nr_steps(&self) -> usize {
+        self.times.len() - 1
+    }
+
+    /// The lengths of the steps.
+    pub fn dts(&self) -> Vec<f64> {
+        self.times.windows(2).map(|w| w[1] - w[0]).collect()
+    }
+
+    /// The index of the grid point at time t.
+    pub fn index_of(&self, t: f64) -> Option<usize> {
+        self.times
+            .iter()
+            .position(|t_k| (t_k - t).abs() < Self::TIME_TOLERANCE)
+    }
+
+    /// The index of the first grid point at or after time t, or None beyond the maturity.
+    pub fn index_at_or_after(&self, t: f64) -> Option<usize> {
+        self.times
+            .iter()
+            .position(|t_k| *t_k > t - Self::TIME_TOLERANCE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_with_event_times() {
+        let grid = TimeGrid::uniform(1.0, 4).with_event_times(&[0.3, 0.5, 2.0]);
+        assert_eq!(grid.times(), &[0.0, 0.25, 0.3, 0.5, 0.75, 1.0]);
+        assert_eq!(grid.nr_steps(), 5);
+        assert_eq!(grid.index_of(0.3), Some(2));
+        assert_eq!(grid.index_of(0.4), None);
+        assert!((grid.dts()[1] - 0.05).abs() < 1e-15);
+
+        assert_eq!(TimeGrid::step_size(1.0, 4), Ok(0.25));
+        assert!(TimeGrid::step_size(0.0, 4).is_err());
+        assert!(TimeGrid::step_size(f64::NAN, 4).is_err());
+        assert!(TimeGrid::try_uniform(1.0, 0).is_err());
+    }
+}
@@ -0,0 +1,334 @@
+use std::fmt;
+
+use crate::common::market_data::MarketDataSet;
+
+/// Whether the trade is bought or sold.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Long,
+    Short,
+}
+
+impl Direction {
+    pub fn sign(&self) -> f64 {
+        match self {
+            Direction::Long => 1.0,
+            Direction::Short => -1.0,
+        }
+    }
+}
+
+/// An event in the life of a trade at the time (in years).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TradeEvent {
+    /// reduces the outstanding notional by the given amount
+    PartialUnwind { time: f64, notional: f64 },
+    /// early or final exercise of the remaining notional
+    Exercise { time: f64 },
+    /// expiry of the remaining notional at maturity
+    Expiry { time: f64 },
+}
+
+impl TradeEvent {
+    pub fn time(&self) -> f64 {
+        match self {
+            TradeEvent::PartialUnwind { time, .. }
+            | TradeEvent::Exercise { time }
+            | TradeEvent::Expiry { time } => *time,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TradeStatus {
+    Live,
+    /// the whole notional has been unwound
+    Closed,
+    Exercised,
+    Expired,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TradeError {
+    /// the event lies before the last event, the trade date or after the maturity
+    InvalidEventTime(f64),
+    /// the unwound notional exceeds the outstanding notional
+    ExcessiveUnwind { notional: f64, outstanding: f64 },
+    /// the trade is no longer live
+    Terminated(TradeStatus),
+}
+
+impl fmt::Display for TradeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TradeError::InvalidEventTime(time) => write!(f, "invalid event time {time}"),
+            TradeError::ExcessiveUnwind {
+                notional,
+                outstanding,
+            } => write!(
+                f,
+                "unwind of {notional} exceeds the outstanding notional {outstanding}"
+            ),
+            TradeError::Terminated(status) => write!(f, "trade is {status:?}"),
+        }
+    }
+}
+
+impl std::error::Error for TradeError {}
+
+/// A trade in a product, e.g. option parameters or a pricer struct, with its notional, direction and
+/// lifecycle events. Values are the unit values of the product times the signed outstanding notional.
+#[derive(Clone, Debug)]
+pub struct Trade<Product> {
+    pub id: String,
+    pub product: Product,
+    pub notional: f64,
+    pub direction: Direction,
+    /// (in years) on the time axis of the market data
+    pub trade_date: f64,
+    pub maturity: f64,
+    events: Vec<TradeEvent>,
+}
+
+impl<Product> Trade<Product> {
+    pub fn new(
+        id: &str,
+        product: Product,
+        notional: f64,
+        direction: Direction,
+        trade_date: f64,
+        maturity: f64,
+    ) -> Self {
+        assert!(notional > 0.0);
+        assert!(trade_date <= maturity);
+        Self {
+            id: id.to_string(),
+            product,
+            notional,
+            direction,
+            trade_date,
+            maturity,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn events(&self) -> &[TradeEvent] {
+        &self.events
+    }
+
+    /// Books the event, which must not precede the previous events.
+    pub fn apply(&mut self, event: TradeEvent) -> Result<(), TradeError> {
+        let time = event.time();
+        let last_time = self.events.last().map_or(self.trade_date, |e| e.time());
+        if time < last_time || time > self.maturity {
+            return Err(TradeError::InvalidEventTime(time));
+        }
+        if let Some(status) = self.event_status(time) {
+            return Err(TradeError::Terminated(status));
+        }
+        if let TradeEvent::PartialUnwind { notional, .. } = event {
+            let outstanding = self.outstanding_notional(time);
+            if notional <= 0.0 || notional > outstanding {
+                return Err(TradeError::ExcessiveUnwind {
+                    notional,
+                    outstanding,
+                });
+            }
+        }
+        if let TradeEvent::Expiry { time } = event {
+            if time < self.maturity {
+                return Err(TradeError::InvalidEventTime(time));
+            }
+        }
+        self.events.push(event);
+        Ok(())
+    }
+
+    /// The terminal status by the events up to (including) the time, if any.
+    fn event_status(&self, time: f64) -> Option<TradeStatus> {
+        let mut outstanding = self.notional;
+        for event in self.events.iter().take_while(|e| e.time() <= time) {
+            match event {
+                TradeEvent::PartialUnwind { notional, .. } => outstanding -= notional,
+                TradeEvent::Exercise { .. } => return Some(TradeStatus::Exercised),
+                TradeEvent::Expiry { .. } => return Some(TradeStatus::Expired),
+            }
+        }
+        (outstanding <= 0.0).then_some(TradeStatus::Closed)
+    }
+
+    /// The status after the events up to (including) the time, where the trade expires at maturity.
+    pub fn status(&self, time: f64) -> TradeStatus {
+        match self.event_status(time) {
+            Some(status) => status,
+            None if time >= self.maturity => TradeStatus::Expired,
+            None => TradeStatus::Live,
+        }
+    }
+
+    pub fn is_live(&self, time: f64) -> bool {
+        self.trade_date <= time && self.status(time) == TradeStatus::Live
+    }
+
+    /// The notional after the unwinds up to the time, zero if the trade is not live.
+    pub fn outstanding_notional(&self, time: f64) -> f64 {
+        if !self.is_live(time) {
+            return 0.0;
+        }
+        self.notional
+            - self
+                .events
+                .iter()
+                .take_while(|e| e.time() <= time)
+                .map(|e| match e {
+                    TradeEvent::PartialUnwind { notional, .. } => *notional,
+                    _ => 0.0,
+                })
+                .sum::<f64>()
+    }
+
+    /// The outstanding notional with the sign of the direction.
+    pub fn signed_notional(&self, time: f64) -> f64 {
+        self.direction.sign() * self.outstanding_notional(time)
+    }
+
+    /// The value of the trade for the market data, where `unit_pricer` values one unit of the product;
+    /// e.g. as pricer of the `PnlExplainEngine`.
+    pub fn value(
+        &self,
+        market: &MarketDataSet,
+        unit_pricer: impl Fn(&Product, &MarketDataSet) -> f64,
+    ) -> f64 {
+        let signed_notional = self.signed_notional(market.time);
+        if signed_notional == 0.0 {
+            return 0.0;
+        }
+        signed_notional * unit_pricer(&self.product, market)
+    }
+}
+
+/// The trades in the same product, e.g. a book's position in an option series.
+#[derive(Clone, Debug)]
+pub struct Position<Product> {
+    pub trades: Vec<Trade<Product>>,
+}
+
+impl<Product> Position<Product> {
+    pub fn new(trades: Vec<Trade<Product>>) -> Self {
+        Self { trades }
+    }
+
+    pub fn net_notional(&self, time: f64) -> f64 {
+        self.trades.iter().map(|t| t.signed_notional(time)).sum()
+    }
+
+    pub fn live_trades(&self, time: f64) -> impl Iterator<Item = &Trade<Product>> {
+        self.trades.iter().filter(move |t| t.is_live(time))
+    }
+
+    pub fn value(
+        &self,
+        market: &MarketDataSet,
+        unit_pricer: impl Fn(&Product, &MarketDataSet) -> f64,
+    ) -> f64 {
+        self.trades
+            .iter()
+            .map(|t| t.value(market, &unit_pricer))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::BsmComputation;
+    use crate::analytic::pnl_explain::{AttributionMethod, PnlExplainEngine};
+    use crate::common::models::DerivativeParameter;
+    use assert_approx_eq::assert_approx_eq;
+
+    /// A call with the strike maturing at the time (in years).
+    fn call_price(product: &(f64, f64), market: &MarketDataSet) -> f64 {
+        let (strike, maturity) = *product;
+        let dp = DerivativeParameter::new(
+            market.spot,
+            strike,
+            maturity - market.time,
+            market.rfr,
+            market.vola,
+        );
+        BsmComputation::new(&dp).call()
+    }
+
+    #[test]
+    fn lifecycle() {
+        let mut trade = Trade::new("t1", (100.0, 1.0), 1_000.0, Direction::Short, 0.0, 1.0);
+        assert_eq!(trade.signed_notional(0.1), -1_000.0);
+
+        trade
+            .apply(TradeEvent::PartialUnwind {
+                time: 0.25,
+                notional: 400.0,
+            })
+            .unwrap();
+        assert_eq!(trade.outstanding_notional(0.2), 1_000.0);
+        assert_eq!(trade.outstanding_notional(0.25), 600.0);
+        assert!(matches!(
+            trade.apply(TradeEvent::PartialUnwind {
+                time: 0.3,
+                notional: 700.0
+            }),
+            Err(TradeError::ExcessiveUnwind { .. })
+        ));
+        assert_eq!(
+            trade.apply(TradeEvent::Exercise { time: 0.1 }),
+            Err(TradeError::InvalidEventTime(0.1))
+        );
+
+        let mut at_maturity = trade.clone();
+        at_maturity
+            .apply(TradeEvent::Exercise { time: 1.0 })
+            .unwrap();
+        assert_eq!(at_maturity.status(1.0), TradeStatus::Exercised);
+
+        trade.apply(TradeEvent::Exercise { time: 0.5 }).unwrap();
+        assert_eq!(trade.status(0.4), TradeStatus::Live);
+        assert_eq!(trade.status(0.5), TradeStatus::Exercised);
+        assert_eq!(trade.outstanding_notional(0.6), 0.0);
+        assert_eq!(
+            trade.apply(TradeEvent::Expiry { time: 1.0 }),
+            Err(TradeError::Terminated(TradeStatus::Exercised))
+        );
+
+        // without events, the trade expires at maturity
+        let trade = Trade::new("t2", (100.0, 1.0), 1.0, Direction::Long, 0.5, 1.0);
+        assert!(!trade.is_live(0.4));
+        assert_eq!(trade.status(1.0), TradeStatus::Expired);
+    }
+
+    #[test]
+    fn position_pnl_explain() {
+        let mut unwound = Trade::new("long", (100.0, 1.0), 10.0, Direction::Long, 0.0, 1.0);
+        unwound
+            .apply(TradeEvent::PartialUnwind {
+                time: 0.5,
+                notional: 5.0,
+            })
+            .unwrap();
+        let short = Trade::new("short", (100.0, 1.0), 2.0, Direction::Short, 0.0, 1.0);
+        let position = Position::new(vec![unwound, short]);
+        assert_eq!(position.net_notional(0.1), 8.0);
+        assert_eq!(position.net_notional(0.5), 3.0);
+        assert_eq!(position.live_trades(0.5).count(), 2);
+
+        let t0 = MarketDataSet::new(0.1, 100.0, 0.2, 0.02);
+        let unit = call_price(&(100.0, 1.0), &t0);
+        assert_approx_eq!(position.value(&t0, call_price), 8.0 * unit, 1e-10);
+
+        let engine =
+            PnlExplainEngine::new(|market: &MarketDataSet| position.value(market, call_price));
+        let t1 = MarketDataSet::new(0.11, 101.0, 0.21, 0.02);
+        let explain = engine.explain(&t0, &t1, AttributionMethod::Sequential);
+        assert!(explain.delta > 0.0);
+        assert_approx_eq!(explain.residual, 0.0, 1e-10);
+    }
+}
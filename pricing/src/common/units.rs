@@ -0,0 +1,234 @@
+use std::ops::{Add, Mul, Neg, Sub};
+
+use crate::common::results::PricingError;
+
+/// An annualized, continuously compounded interest rate (or yield), stored as decimal (0.05 for 5%).
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Default)]
+pub struct Rate(f64);
+
+impl Rate {
+    pub fn from_decimal(rate: f64) -> Self {
+        Self(rate)
+    }
+
+    pub fn from_percent(rate: f64) -> Self {
+        Self(rate / 100.0)
+    }
+
+    pub fn from_basis_points(rate: f64) -> Self {
+        Self(rate / 10_000.0)
+    }
+
+    pub fn decimal(&self) -> f64 {
+        self.0
+    }
+
+    pub fn percent(&self) -> f64 {
+        100.0 * self.0
+    }
+
+    /// The discount factor $e^{-r t}$ for the time t in years.
+    pub fn discount_factor(&self, t: f64) -> DiscountFactor {
+        DiscountFactor((-self.0 * t).exp())
+    }
+}
+
+impl Add for Rate {
+    type Output = Rate;
+
+    fn add(self, other: Rate) -> Rate {
+        Rate(self.0 + other.0)
+    }
+}
+
+impl Sub for Rate {
+    type Output = Rate;
+
+    fn sub(self, other: Rate) -> Rate {
+        Rate(self.0 - other.0)
+    }
+}
+
+impl Neg for Rate {
+    type Output = Rate;
+
+    fn neg(self) -> Rate {
+        Rate(-self.0)
+    }
+}
+
+impl Mul<f64> for Rate {
+    type Output = Rate;
+
+    fn mul(self, factor: f64) -> Rate {
+        Rate(self.0 * factor)
+    }
+}
+
+impl From<f64> for Rate {
+    /// The rate of the decimal.
+    fn from(rate: f64) -> Self {
+        Self::from_decimal(rate)
+    }
+}
+
+impl From<Rate> for f64 {
+    /// The decimal of the rate, e.g. for the fields of `DerivativeParameter`.
+    fn from(rate: Rate) -> Self {
+        rate.0
+    }
+}
+
+/// An annualized volatility, stored as decimal (0.2 for 20%).
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Default)]
+pub struct Volatility(f64);
+
+impl Volatility {
+    /// The volatility of the non-negative decimal, which panics otherwise; see `try_from` for
+    /// decimals which are not known to be valid.
+    pub fn from_decimal(vola: f64) -> Self {
+        assert!(vola >= 0.0);
+        Self(vola)
+    }
+
+    pub fn from_percent(vola: f64) -> Self {
+        Self::from_decimal(vola / 100.0)
+    }
+
+    pub fn decimal(&self) -> f64 {
+        self.0
+    }
+
+    pub fn percent(&self) -> f64 {
+        100.0 * self.0
+    }
+
+    /// The total variance $\sigma^2 t$ for the time t in years.
+    pub fn variance(&self, t: f64) -> f64 {
+        self.0 * self.0 * t
+    }
+
+    /// The difference of the volatilities, or None if it is negative.
+    pub fn checked_sub(self, other: Volatility) -> Option<Volatility> {
+        Volatility::try_from(self.0 - other.0).ok()
+    }
+
+    /// The volatility scaled by the factor, or None if the factor is negative or not finite.
+    pub fn checked_mul(self, factor: f64) -> Option<Volatility> {
+        Volatility::try_from(self.0 * factor).ok()
+    }
+}
+
+impl Add for Volatility {
+    type Output = Volatility;
+
+    fn add(self, other: Volatility) -> Volatility {
+        Volatility(self.0 + other.0)
+    }
+}
+
+impl TryFrom<f64> for Volatility {
+    type Error = PricingError;
+
+    /// The volatility of the decimal, which has to be non-negative and finite.
+    fn try_from(vola: f64) -> Result<Self, PricingError> {
+        PricingError::check(vola >= 0.0 && vola.is_finite(), "vola", vola)?;
+        Ok(Self(vola))
+    }
+}
+
+impl From<Volatility> for f64 {
+    /// The decimal of the volatility, e.g. for the fields of `DerivativeParameter`.
+    fn from(vola: Volatility) -> Self {
+        vola.0
+    }
+}
+
+/// A discount factor in (0, 1] for positive rates.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct DiscountFactor(f64);
+
+impl DiscountFactor {
+    pub fn new(discount_factor: f64) -> Self {
+        assert!(discount_factor > 0.0);
+        Self(discount_factor)
+    }
+
+    pub fn value(&self) -> f64 {
+        self.0
+    }
+
+    /// The continuously compounded rate $-ln(P) / t$ for the time t in years.
+    pub fn rate(&self, t: f64) -> Rate {
+        Rate(-self.0.ln() / t)
+    }
+}
+
+/// Composes the discount factors of consecutive periods.
+impl Mul for DiscountFactor {
+    type Output = DiscountFactor;
+
+    fn mul(self, other: DiscountFactor) -> DiscountFactor {
+        DiscountFactor(self.0 * other.0)
+    }
+}
+
+/// Discounts an amount.
+impl Mul<f64> for DiscountFactor {
+    type Output = f64;
+
+    fn mul(self, amount: f64) -> f64 {
+        self.0 * amount
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::models::DerivativeParameter;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn units() {
+        assert_eq!(Rate::from_percent(5.0), Rate::from_decimal(0.05));
+        assert_approx_eq!(Rate::from_basis_points(25.0).percent(), 0.25, 1e-15);
+        assert_approx_eq!(
+            (Rate::from_percent(3.0) - Rate::from_percent(1.0)).decimal(),
+            0.02,
+            1e-15
+        );
+        assert_approx_eq!(Volatility::from_percent(20.0).variance(2.0), 0.08, 1e-15);
+
+        let df = Rate::from_decimal(0.03).discount_factor(1.0)
+            * Rate::from_decimal(0.05).discount_factor(1.0);
+        assert_approx_eq!(df.rate(2.0).decimal(), 0.04, 1e-15);
+        assert_approx_eq!(df * 100.0, 100.0 * (-0.08_f64).exp(), 1e-12);
+
+        let dp = DerivativeParameter::with_units(
+            100.0,
+            95.0,
+            1.0,
+            Rate::from_percent(5.0),
+            Volatility::from_percent(20.0),
+        );
+        assert_eq!(dp.rfr, 0.05);
+        assert_eq!(dp.volatility(), Ok(Volatility::from_decimal(0.2)));
+        assert_eq!(dp.rate(), Rate::from_decimal(0.05));
+        assert!(DerivativeParameter { vola: -0.1, ..dp }
+            .volatility()
+            .is_err());
+
+        // the volatility stays non-negative without panics
+        let (high, low) = (
+            Volatility::from_percent(30.0),
+            Volatility::from_percent(20.0),
+        );
+        assert_approx_eq!(high.checked_sub(low).unwrap().decimal(), 0.1, 1e-15);
+        assert_eq!(low.checked_sub(high), None);
+        assert_eq!(low.checked_mul(2.0), Some(Volatility::from_decimal(0.4)));
+        assert_eq!(low.checked_mul(-1.0), None);
+        assert!(Volatility::try_from(f64::NAN).is_err());
+        assert_eq!(f64::from(Rate::from(0.03)), 0.03);
+        assert_eq!(f64::from(low), 0.2);
+    }
+}
@@ -0,0 +1,161 @@
+use ndarray::Array2;
+
+use crate::common::results::PricingWarning;
+
+/// Volatility surface with nodes `vols[[k, l]]` for the time bucket $[t_{k-1}, t_k)$ (with $t_{-1} = 0$
+/// and the last bucket extrapolated flat) and the strike $K_l$, interpolated linearly in the strike
+/// and extrapolated flat beyond the first and last strike.
+#[derive(Clone, Debug)]
+pub struct VolatilitySurface {
+    times: Vec<f64>,
+    strikes: Vec<f64>,
+    vols: Array2<f64>,
+}
+
+impl VolatilitySurface {
+    pub fn new(times: Vec<f64>, strikes: Vec<f64>, vols: Array2<f64>) -> Self {
+        assert!(!times.is_empty() && !strikes.is_empty());
+        assert_eq!(vols.shape(), &[times.len(), strikes.len()]);
+        assert!(times.windows(2).all(|w| w[0] < w[1]));
+        assert!(strikes.windows(2).all(|w| w[0] < w[1]));
+        Self {
+            times,
+            strikes,
+            vols,
+        }
+    }
+
+    pub fn flat(vola: f64) -> Self {
+        Self::new(
+            vec![f64::INFINITY],
+            vec![1.0],
+            Array2::from_elem((1, 1), vola),
+        )
+    }
+
+    pub fn times(&self) -> &[f64] {
+        &self.times
+    }
+
+    pub fn strikes(&self) -> &[f64] {
+        &self.strikes
+    }
+
+    pub fn vols(&self) -> &Array2<f64> {
+        &self.vols
+    }
+
+    /// The index of the time bucket containing t.
+    pub fn bucket(&self, t: f64) -> usize {
+        self.times
+            .iter()
+            .position(|end| t < *end)
+            .unwrap_or(self.times.len() - 1)
+    }
+
+    /// The strike nodes with their linear interpolation weights for the strike.
+    pub fn strike_weights(&self, strike: f64) -> Vec<(usize, f64)> {
+        let idx = self.strikes.partition_point(|k| *k <= strike);
+        if idx == 0 {
+            return vec![(0, 1.0)];
+        }
+        if idx == self.strikes.len() {
+            return vec![(idx - 1, 1.0)];
+        }
+        let weight = (strike - self.strikes[idx - 1]) / (self.strikes[idx] - self.strikes[idx - 1]);
+        vec![(idx - 1, 1.0 - weight), (idx, weight)]
+    }
+
+    /// The volatility of the time bucket k at the strike.
+    pub fn vola(&self, bucket: usize, strike: f64) -> f64 {
+        self.strike_weights(strike)
+            .iter()
+            .map(|(l, weight)| weight * self.vols[[bucket, *l]])
+            .sum()
+    }
+
+    /// The warning if the volatility until t at the strike is extrapolated, i.e. t is beyond the last
+    /// time or the strike outside of the strikes of a surface with several strikes.
+    pub fn extrapolation_warning(&self, t: f64, strike: f64) -> Option<PricingWarning> {
+        let beyond_times = t > self.times[self.times.len() - 1];
+        let outside_strikes = self.strikes.len() > 1
+            && (strike < self.strikes[0] || strike > self.strikes[self.strikes.len() - 1]);
+        (beyond_times || outside_strikes)
+            .then_some(PricingWarning::VolatilityExtrapolated { time: t, strike })
+    }
+
+    /// The length of the overlap of each time bucket with $[0, t]$.
+    pub fn bucket_lengths(&self, t: f64) -> Vec<f64> {
+        let mut start = 0.0;
+        self.times
+            .iter()
+            .enumerate()
+            .map(|(k, end)| {
+                let end = if k == self.times.len() - 1 {
+                    f64::INFINITY
+                } else {
+                    *end
+                };
+                let length = (t.min(end) - start).max(0.0);
+                start = end;
+                length
+            })
+            .collect()
+    }
+
+    /// The volatility $\sqrt{\frac{1}{t}\int_0^t \sigma^2(s, K) ds}$ for the strike until t.
+    pub fn effective_vola(&self, t: f64, strike: f64) -> f64 {
+        let integrated_variance: f64 = self
+            .bucket_lengths(t)
+            .iter()
+            .enumerate()
+            .map(|(k, length)| self.vola(k, strike).powi(2) * length)
+            .sum();
+        (integrated_variance / t).sqrt()
+    }
+
+    /// The surface with the node (k, l) shifted by `shift`.
+    pub fn bumped(&self, bucket: usize, strike_idx: usize, shift: f64) -> Self {
+        let mut bumped = self.clone();
+        bumped.vols[[bucket, strike_idx]] += shift;
+        bumped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use ndarray::arr2;
+
+    #[test]
+    fn surface_interpolation() {
+        let surface = VolatilitySurface::new(
+            vec![0.5, 1.0],
+            vec![90.0, 110.0],
+            arr2(&[[0.3, 0.2], [0.25, 0.15]]),
+        );
+        assert_approx_eq!(surface.vola(0, 100.0), 0.25, 1e-15);
+        assert_eq!(surface.vola(1, 80.0), 0.25);
+        assert_eq!(surface.vola(1, 120.0), 0.15);
+
+        assert_eq!(surface.bucket_lengths(0.75), vec![0.5, 0.25]);
+        assert_eq!(surface.bucket_lengths(2.0), vec![0.5, 1.5]);
+        let expected = ((0.25_f64.powi(2) * 0.5 + 0.2_f64.powi(2) * 0.5) / 1.0).sqrt();
+        assert_approx_eq!(surface.effective_vola(1.0, 100.0), expected, 1e-15);
+
+        assert_eq!(surface.extrapolation_warning(1.0, 100.0), None);
+        assert_eq!(
+            surface.extrapolation_warning(0.75, 120.0),
+            Some(PricingWarning::VolatilityExtrapolated {
+                time: 0.75,
+                strike: 120.0
+            })
+        );
+        assert!(surface.extrapolation_warning(2.0, 100.0).is_some());
+        assert_eq!(
+            VolatilitySurface::flat(0.2).extrapolation_warning(30.0, 500.0),
+            None
+        );
+    }
+}
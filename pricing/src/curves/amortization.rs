@@ -0,0 +1,296 @@
+use crate::common::results::PricingError;
+use crate::curves::cashflows::{payment_schedule, Cashflow, Leg};
+
+/// The scheduled repayment of the notional over the periods.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Amortization {
+    /// the notional is repaid at maturity
+    Bullet,
+    /// equal principal repayments per period
+    Linear,
+    /// constant payments of interest and principal at the (annual) rate, as for a mortgage
+    Annuity { rate: f64 },
+    /// the scheduled (positive) outstanding notionals at the starts of the periods, repaid at maturity
+    Custom(Vec<f64>),
+}
+
+/// The constant prepayment rate (CPR), the annualized fraction of the outstanding notional prepaid on top
+/// of the scheduled principal, i.e. the single monthly mortality $1 - (1 - CPR)^{1/f}$ per period of
+/// frequency f.
+/// See https://en.wikipedia.org/wiki/Prepayment_of_loan#Prepayment_models
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConstantPrepaymentRate {
+    pub cpr: f64,
+}
+
+impl ConstantPrepaymentRate {
+    pub fn new(cpr: f64) -> Result<Self, PricingError> {
+        PricingError::check((0.0..=1.0).contains(&cpr), "CPR", cpr)?;
+        Ok(Self { cpr })
+    }
+
+    /// The fraction of the outstanding notional prepaid over the year fraction.
+    pub fn period_rate(&self, year_fraction: f64) -> f64 {
+        1.0 - (1.0 - self.cpr).powf(year_fraction)
+    }
+}
+
+/// A period of an amortizing schedule with its outstanding notional and the principal repaid at its end.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AmortizationPeriod {
+    pub accrual_start: f64,
+    pub accrual_end: f64,
+    pub notional: f64,
+    pub scheduled_principal: f64,
+    pub prepaid_principal: f64,
+}
+
+impl AmortizationPeriod {
+    pub fn principal(&self) -> f64 {
+        self.scheduled_principal + self.prepaid_principal
+    }
+}
+
+/// The notional schedule of an amortizing loan or swap paying with the frequency (per year) until the
+/// maturity, where the scheduled principal of each period is recomputed on the notional outstanding
+/// after prepayments.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AmortizingSchedule {
+    periods: Vec<AmortizationPeriod>,
+}
+
+impl AmortizingSchedule {
+    pub fn new(
+        notional: f64,
+        maturity: f64,
+        frequency: usize,
+        amortization: &Amortization,
+    ) -> Result<Self, PricingError> {
+        Self::with_prepayment(notional, maturity, frequency, amortization, None)
+    }
+
+    pub fn with_prepayment(
+        notional: f64,
+        maturity: f64,
+        frequency: usize,
+        amortization: &Amortization,
+        prepayment: Option<ConstantPrepaymentRate>,
+    ) -> Result<Self, PricingError> {
+        PricingError::check(maturity > 0.0, "maturity", maturity)?;
+        PricingError::check(frequency > 0, "frequency", frequency as f64)?;
+        let payment_times = payment_schedule(maturity, frequency);
+        let nr_periods = payment_times.len();
+        if let Amortization::Custom(notionals) = amortization {
+            PricingError::check(
+                notionals.len() == nr_periods,
+                "nr custom notionals",
+                notionals.len() as f64,
+            )?;
+            // the scheduled fractions are ratios of consecutive notionals
+            for custom_notional in notionals {
+                PricingError::check(
+                    *custom_notional > 0.0 && custom_notional.is_finite(),
+                    "custom notional",
+                    *custom_notional,
+                )?;
+            }
+        }
+
+        let mut periods = Vec::with_capacity(nr_periods);
+        let (mut accrual_start, mut outstanding) = (0.0, notional);
+        for (k, accrual_end) in payment_times.into_iter().enumerate() {
+            let tau = accrual_end - accrual_start;
+            let remaining_periods = (nr_periods - k) as f64;
+            let scheduled_principal = if k + 1 == nr_periods {
+                outstanding
+            } else {
+                match amortization {
+                    Amortization::Bullet => 0.0,
+                    Amortization::Linear => outstanding / remaining_periods,
+                    Amortization::Annuity { rate } if *rate * tau == 0.0 => {
+                        outstanding / remaining_periods
+                    }
+                    Amortization::Annuity { rate } => {
+                        let period_rate = rate * tau;
+                        let payment = outstanding * period_rate
+                            / (1.0 - (1.0 + period_rate).powf(-remaining_periods));
+                        payment - outstanding * period_rate
+                    }
+                    // the scheduled fraction of the notional, applied to the prepaid notional
+                    Amortization::Custom(notionals) => {
+                        outstanding * (1.0 - notionals[k + 1] / notionals[k])
+                    }
+                }
+            };
+            let prepaid_principal = prepayment.map_or(0.0, |prepayment| {
+                prepayment.period_rate(tau) * (outstanding - scheduled_principal)
+            });
+            periods.push(AmortizationPeriod {
+                accrual_start,
+                accrual_end,
+                notional: outstanding,
+                scheduled_principal,
+                prepaid_principal,
+            });
+            outstanding -= scheduled_principal + prepaid_principal;
+            accrual_start = accrual_end;
+        }
+        Ok(Self { periods })
+    }
+
+    pub fn periods(&self) -> &[AmortizationPeriod] {
+        &self.periods
+    }
+
+    /// The weighted average life $\sum_k t_k P_k / \sum_k P_k$ of the principal repayments $P_k$,
+    /// which is also the time integral of the outstanding notional per unit of the initial notional.
+    /// See https://en.wikipedia.org/wiki/Weighted-average_life
+    pub fn weighted_average_life(&self) -> f64 {
+        let principal: f64 = self.periods.iter().map(AmortizationPeriod::principal).sum();
+        self.periods
+            .iter()
+            .map(|period| period.accrual_end * period.principal())
+            .sum::<f64>()
+            / principal
+    }
+
+    fn coupons(&self, coupon: impl Fn(&AmortizationPeriod) -> Cashflow) -> Vec<Cashflow> {
+        self.periods.iter().map(coupon).collect()
+    }
+
+    /// The coupons of the fixed rate on the outstanding notionals, e.g. the fixed leg of an amortizing swap.
+    pub fn fixed_leg(&self, rate: f64) -> Leg {
+        Leg::new(self.coupons(|period| Cashflow::FixedCoupon {
+            notional: period.notional,
+            rate,
+            accrual_start: period.accrual_start,
+            accrual_end: period.accrual_end,
+        }))
+    }
+
+    /// The coupons of the floating rate plus the spread on the outstanding notionals.
+    pub fn floating_leg(&self, spread: f64) -> Leg {
+        Leg::new(self.coupons(|period| Cashflow::FloatingCoupon {
+            notional: period.notional,
+            spread,
+            accrual_start: period.accrual_start,
+            accrual_end: period.accrual_end,
+        }))
+    }
+
+    /// The principal repayments at the ends of the periods.
+    pub fn principal_leg(&self) -> Leg {
+        Leg::new(
+            self.periods
+                .iter()
+                .map(|period| Cashflow::Fixed {
+                    payment_time: period.accrual_end,
+                    amount: period.principal(),
+                })
+                .collect(),
+        )
+    }
+
+    /// The cashflows of the loan paying the fixed rate: the coupons and the principal repayments.
+    pub fn fixed_rate_loan(&self, rate: f64) -> Leg {
+        let mut cashflows = self.fixed_leg(rate).cashflows().to_vec();
+        cashflows.extend_from_slice(self.principal_leg().cashflows());
+        cashflows.sort_by(|a, b| a.payment_time().total_cmp(&b.payment_time()));
+        Leg::new(cashflows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curves::cashflows::Swap;
+    use crate::curves::yield_curve::{FlatCurve, YieldCurve};
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn amortization_profiles() {
+        let linear = AmortizingSchedule::new(100.0, 5.0, 1, &Amortization::Linear).unwrap();
+        assert!(linear
+            .periods()
+            .iter()
+            .all(|period| (period.principal() - 20.0).abs() < 1e-12));
+        assert_approx_eq!(linear.weighted_average_life(), 3.0, 1e-12);
+
+        let bullet = AmortizingSchedule::new(100.0, 5.0, 1, &Amortization::Bullet).unwrap();
+        assert_approx_eq!(bullet.weighted_average_life(), 5.0, 1e-12);
+
+        // the annuity pays constant installments and is at par when discounted at its rate
+        let rate: f64 = 0.05;
+        let annuity =
+            AmortizingSchedule::new(100.0, 10.0, 1, &Amortization::Annuity { rate }).unwrap();
+        let loan = annuity.fixed_rate_loan(rate);
+        let installment = 100.0 * rate / (1.0 - (1.0 + rate).powi(-10));
+        for period in annuity.periods() {
+            assert_approx_eq!(
+                period.principal() + period.notional * rate,
+                installment,
+                1e-10
+            );
+        }
+        let curve = FlatCurve::new(rate.ln_1p());
+        assert_approx_eq!(loan.present_value(&curve, &curve), 100.0, 1e-10);
+        let zero_rate =
+            AmortizingSchedule::new(100.0, 5.0, 1, &Amortization::Annuity { rate: 0.0 }).unwrap();
+        assert_eq!(zero_rate, linear);
+
+        let custom = AmortizingSchedule::new(
+            100.0,
+            2.0,
+            2,
+            &Amortization::Custom(vec![100.0, 100.0, 50.0, 50.0]),
+        )
+        .unwrap();
+        let principal: Vec<f64> = custom.periods().iter().map(|p| p.principal()).collect();
+        assert_eq!(principal, vec![0.0, 50.0, 0.0, 50.0]);
+        assert!(
+            AmortizingSchedule::new(100.0, 2.0, 2, &Amortization::Custom(vec![100.0])).is_err()
+        );
+        let fully_repaid = Amortization::Custom(vec![100.0, 0.0, 50.0, 50.0]);
+        assert!(matches!(
+            AmortizingSchedule::new(100.0, 2.0, 2, &fully_repaid),
+            Err(PricingError::InvalidParameter(_))
+        ));
+        assert!(ConstantPrepaymentRate::new(1.5).is_err());
+    }
+
+    #[test]
+    fn prepayments_shorten_the_life() {
+        let rate: f64 = 0.04;
+        let annuity = Amortization::Annuity { rate };
+        let scheduled = AmortizingSchedule::new(100.0, 30.0, 12, &annuity).unwrap();
+        let prepaid = AmortizingSchedule::with_prepayment(
+            100.0,
+            30.0,
+            12,
+            &annuity,
+            Some(ConstantPrepaymentRate::new(0.06).unwrap()),
+        )
+        .unwrap();
+        assert!(prepaid.weighted_average_life() < scheduled.weighted_average_life() - 5.0);
+        let total: f64 = prepaid.periods().iter().map(|p| p.principal()).sum();
+        assert_approx_eq!(total, 100.0, 1e-10);
+        // a month prepays the single monthly mortality of the unscheduled notional
+        let first = prepaid.periods()[0];
+        let smm = 1.0 - 0.94_f64.powf(1.0 / 12.0);
+        assert_approx_eq!(
+            first.prepaid_principal,
+            smm * (100.0 - first.scheduled_principal),
+            1e-12
+        );
+
+        // prepayments at par leave the loan at par, and the amortizing swap at its par rate is worth zero
+        let curve = FlatCurve::new(12.0 * (rate / 12.0).ln_1p());
+        let loan = prepaid.fixed_rate_loan(rate);
+        assert_approx_eq!(loan.present_value(&curve, &curve), 100.0, 1e-9);
+        let par_rate = prepaid.floating_leg(0.0).present_value(&curve, &curve)
+            / prepaid.fixed_leg(1.0).present_value(&curve, &curve);
+        let swap = Swap::new(prepaid.floating_leg(0.0), prepaid.fixed_leg(par_rate));
+        assert_approx_eq!(swap.value(&curve, &curve), 0.0, 1e-10);
+        assert!(curve.discount_factor(1.0) < 1.0);
+    }
+}
@@ -0,0 +1,270 @@
+use crate::analytic::dual_greeks::AnalyticFormula;
+use crate::common::models::{DerivativeParameter, ExerciseType};
+use crate::common::results::PricingError;
+use crate::curves::yield_curve::YieldCurve;
+use crate::numerics::solvers::{brent, SolverOptions};
+
+/// The price of the caplet (call) or floorlet (put) on the simple forward rate
+/// $F = (P(0, T) / P(0, T + \tau) - 1) / \tau$ fixing at T and paying $\tau (F - K)^+$ at $T + \tau$,
+/// i.e. $\tau P(0, T + \tau)$ times the undiscounted Black76 (with shift) or Bachelier price.
+/// See https://en.wikipedia.org/wiki/Interest_rate_cap_and_floor
+pub fn caplet_price(
+    curve: &(impl YieldCurve + ?Sized),
+    formula: &AnalyticFormula,
+    fixing_time: f64,
+    tenor: f64,
+    strike: f64,
+    vola: f64,
+    exercise_type: &ExerciseType,
+) -> f64 {
+    let payment_discount_factor = curve.discount_factor(fixing_time + tenor);
+    let forward = (curve.discount_factor(fixing_time) / payment_discount_factor - 1.0) / tenor;
+    let undiscounted = formula.price([forward, strike, fixing_time, 0.0, vola], exercise_type);
+    tenor * payment_discount_factor * undiscounted
+}
+
+/// The fixing times $\tau, 2 \tau, ...$ of the caplets of a spot-starting cap until the maturity, where
+/// the first period is excluded since its rate is already fixed.
+fn fixing_times(maturity: f64, tenor: f64) -> Result<Vec<f64>, PricingError> {
+    let nr_periods = (maturity / tenor).round();
+    PricingError::check(
+        nr_periods >= 2.0 && (nr_periods * tenor - maturity).abs() < 1e-9,
+        "cap maturity",
+        maturity,
+    )?;
+    Ok((1..nr_periods as usize).map(|i| i as f64 * tenor).collect())
+}
+
+/// The market quote of a cap by its flat volatility, i.e. the volatility of all its caplets.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CapQuote {
+    pub maturity: f64,
+    pub strike: f64,
+    pub flat_vola: f64,
+}
+
+/// Caplet volatilities stripped from cap quotes, constant for the caplets fixing between consecutive cap
+/// maturities, e.g. to price caplets and off-market caps consistently with the quoted caps.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CapletVolatilities {
+    pub formula: AnalyticFormula,
+    /// the accrual period of the caplets
+    pub tenor: f64,
+    /// the cap maturities, which end the periods of constant caplet volatility
+    maturities: Vec<f64>,
+    vols: Vec<f64>,
+}
+
+impl CapletVolatilities {
+    /// Bootstraps the caplet volatilities from the quotes of increasing maturities: the caplets of the
+    /// periods until the previous maturity keep their stripped volatilities, and the volatility of the
+    /// new caplets reprices the cap at its flat volatility. Quotes of different strikes, e.g. of ATM
+    /// caps, are stripped into a single term structure, disregarding the smile between the strikes.
+    pub fn strip(
+        curve: &(impl YieldCurve + ?Sized),
+        quotes: &[CapQuote],
+        tenor: f64,
+        formula: AnalyticFormula,
+    ) -> Result<Self, PricingError> {
+        PricingError::check(tenor > 0.0, "tenor", tenor)?;
+        PricingError::check(!quotes.is_empty(), "nr cap quotes", 0.0)?;
+        let mut stripped = Self {
+            formula,
+            tenor,
+            maturities: Vec::with_capacity(quotes.len()),
+            vols: Vec::with_capacity(quotes.len()),
+        };
+        for quote in quotes {
+            let fixings = fixing_times(quote.maturity, tenor)?;
+            let previous_maturity = stripped.maturities.last().copied().unwrap_or(0.0);
+            PricingError::check(
+                quote.maturity > previous_maturity,
+                "cap maturity",
+                quote.maturity,
+            )?;
+            formula.validate(&DerivativeParameter::new(
+                quote.strike,
+                quote.strike,
+                tenor,
+                0.0,
+                quote.flat_vola,
+            ))?;
+
+            let caplet = |fixing_time: f64, vola: f64| {
+                caplet_price(
+                    curve,
+                    &formula,
+                    fixing_time,
+                    tenor,
+                    quote.strike,
+                    vola,
+                    &ExerciseType::Call,
+                )
+            };
+            let (stripped_fixings, new_fixings): (Vec<f64>, Vec<f64>) = fixings
+                .iter()
+                .partition(|fixing_time| **fixing_time < previous_maturity);
+            let cap_price: f64 = fixings.iter().map(|t| caplet(*t, quote.flat_vola)).sum();
+            let stripped_price: f64 = stripped_fixings
+                .iter()
+                .map(|t| caplet(*t, stripped.vola(*t)))
+                .sum();
+            let price_error = |vola: f64| {
+                stripped_price + new_fixings.iter().map(|t| caplet(*t, vola)).sum::<f64>()
+                    - cap_price
+            };
+            // the previous caplets are too expensive or cheap for any volatility of the new caplets
+            let vola = brent(
+                price_error,
+                1e-4 * quote.flat_vola,
+                10.0 * quote.flat_vola,
+                &SolverOptions::default(),
+            )
+            .and_then(|solution| solution.converged())
+            .ok_or_else(|| {
+                PricingError::InvalidParameter(format!(
+                    "flat vola = {} of the cap maturing at {}",
+                    quote.flat_vola, quote.maturity
+                ))
+            })?;
+            stripped.maturities.push(quote.maturity);
+            stripped.vols.push(vola);
+        }
+        Ok(stripped)
+    }
+
+    /// The cap maturities, one per stripped quote.
+    pub fn maturities(&self) -> &[f64] {
+        &self.maturities
+    }
+
+    /// The caplet volatilities of the periods ending at the cap maturities.
+    pub fn vols(&self) -> &[f64] {
+        &self.vols
+    }
+
+    /// The volatility of the caplet fixing at the time, extrapolated flat beyond the last cap.
+    pub fn vola(&self, fixing_time: f64) -> f64 {
+        let period = self
+            .maturities
+            .iter()
+            .position(|maturity| fixing_time < *maturity)
+            .unwrap_or(self.maturities.len() - 1);
+        self.vols[period]
+    }
+
+    /// The price of the caplet or floorlet fixing at the time with its stripped volatility.
+    pub fn caplet_price(
+        &self,
+        curve: &(impl YieldCurve + ?Sized),
+        fixing_time: f64,
+        strike: f64,
+        exercise_type: &ExerciseType,
+    ) -> f64 {
+        caplet_price(
+            curve,
+            &self.formula,
+            fixing_time,
+            self.tenor,
+            strike,
+            self.vola(fixing_time),
+            exercise_type,
+        )
+    }
+
+    /// The price of the spot-starting cap or floor as the sum of its caplets or floorlets.
+    pub fn cap_price(
+        &self,
+        curve: &(impl YieldCurve + ?Sized),
+        maturity: f64,
+        strike: f64,
+        exercise_type: &ExerciseType,
+    ) -> Result<f64, PricingError> {
+        Ok(fixing_times(maturity, self.tenor)?
+            .iter()
+            .map(|t| self.caplet_price(curve, *t, strike, exercise_type))
+            .sum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curves::yield_curve::FlatCurve;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn caplet_stripping() {
+        let curve = FlatCurve::new(0.03);
+        let quotes: Vec<CapQuote> = [(1.0, 0.22), (2.0, 0.21), (5.0, 0.19), (10.0, 0.18)]
+            .iter()
+            .map(|(maturity, flat_vola)| CapQuote {
+                maturity: *maturity,
+                strike: 0.03,
+                flat_vola: *flat_vola,
+            })
+            .collect();
+        let formula = AnalyticFormula::Black76 { shift: 0.01 };
+        let stripped = CapletVolatilities::strip(&curve, &quotes, 0.25, formula).unwrap();
+        // the three caplets of the first cap have its flat volatility
+        assert_approx_eq!(stripped.vols()[0], 0.22, 1e-10);
+        // the decreasing flat volatilities are averages of faster decreasing caplet volatilities
+        assert!(stripped.vols().windows(2).all(|pair| pair[1] < pair[0]));
+        assert!(stripped.vols()[3] < 0.18);
+        assert_eq!(stripped.vola(4.75), stripped.vols()[2]);
+        assert_eq!(stripped.vola(20.0), stripped.vols()[3]);
+        for quote in &quotes {
+            let flat = fixing_times(quote.maturity, 0.25)
+                .unwrap()
+                .iter()
+                .map(|t| {
+                    caplet_price(
+                        &curve,
+                        &formula,
+                        *t,
+                        0.25,
+                        quote.strike,
+                        quote.flat_vola,
+                        &ExerciseType::Call,
+                    )
+                })
+                .sum::<f64>();
+            let cap = stripped
+                .cap_price(&curve, quote.maturity, quote.strike, &ExerciseType::Call)
+                .unwrap();
+            assert_approx_eq!(cap, flat, 1e-10);
+        }
+        // the put-call parity of the caplet and the floorlet at the forward rate
+        let forward = ((0.03_f64 * 0.25).exp() - 1.0) / 0.25;
+        let floorlet = stripped.caplet_price(&curve, 3.0, forward, &ExerciseType::Put);
+        assert_approx_eq!(
+            stripped.caplet_price(&curve, 3.0, forward, &ExerciseType::Call),
+            floorlet,
+            1e-12
+        );
+
+        // normal volatilities of rates in absolute terms
+        let normal: Vec<CapQuote> = quotes
+            .iter()
+            .map(|quote| CapQuote {
+                flat_vola: quote.flat_vola * 0.04,
+                ..*quote
+            })
+            .collect();
+        let bachelier =
+            CapletVolatilities::strip(&curve, &normal, 0.25, AnalyticFormula::Bachelier).unwrap();
+        assert!(bachelier
+            .vols()
+            .iter()
+            .all(|vola| *vola > 0.0 && *vola < 0.01));
+        assert!(CapletVolatilities::strip(&curve, &quotes, 0.3, formula).is_err());
+        let unattainable = [
+            quotes[0],
+            CapQuote {
+                flat_vola: 0.002,
+                ..quotes[3]
+            },
+        ];
+        assert!(CapletVolatilities::strip(&curve, &unattainable, 0.25, formula).is_err());
+    }
+}
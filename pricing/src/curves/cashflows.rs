@@ -0,0 +1,339 @@
+use crate::curves::overnight::projected_compounded_rate;
+use crate::curves::yield_curve::YieldCurve;
+
+/// The payment times (in years) of the periods with the frequency (per year) until the maturity,
+/// with a short first period if the maturity is not a multiple of the period.
+pub fn payment_schedule(maturity: f64, frequency: usize) -> Vec<f64> {
+    assert!(maturity > 0.0 && frequency > 0);
+    let period = 1.0 / frequency as f64;
+    let nr_periods = (maturity / period - 1e-9).ceil() as usize;
+    (0..nr_periods)
+        .rev()
+        .map(|k| maturity - k as f64 * period)
+        .collect()
+}
+
+/// A cashflow of a leg, paid at the end of its accrual period $[t_s, t_e]$ (in years).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cashflow {
+    /// a known amount, e.g. a notional exchange or a fee
+    Fixed { payment_time: f64, amount: f64 },
+    /// the interest $N c (t_e - t_s)$ of the fixed rate c
+    FixedCoupon {
+        notional: f64,
+        rate: f64,
+        accrual_start: f64,
+        accrual_end: f64,
+    },
+    /// the interest $N (F(t_s, t_e) + s) (t_e - t_s)$ of the simple forward rate F of the projection curve
+    /// plus the spread s
+    FloatingCoupon {
+        notional: f64,
+        spread: f64,
+        accrual_start: f64,
+        accrual_end: f64,
+    },
+}
+
+impl Cashflow {
+    pub fn payment_time(&self) -> f64 {
+        match self {
+            Cashflow::Fixed { payment_time, .. } => *payment_time,
+            Cashflow::FixedCoupon { accrual_end, .. }
+            | Cashflow::FloatingCoupon { accrual_end, .. } => *accrual_end,
+        }
+    }
+
+    /// The year fraction of the accrual period, which is 0 for fixed amounts.
+    pub fn year_fraction(&self) -> f64 {
+        match self {
+            Cashflow::Fixed { .. } => 0.0,
+            Cashflow::FixedCoupon {
+                accrual_start,
+                accrual_end,
+                ..
+            }
+            | Cashflow::FloatingCoupon {
+                accrual_start,
+                accrual_end,
+                ..
+            } => accrual_end - accrual_start,
+        }
+    }
+
+    /// The amount projected with the forward rates of the curve.
+    pub fn amount(&self, projection: &impl YieldCurve) -> f64 {
+        match *self {
+            Cashflow::Fixed { amount, .. } => amount,
+            Cashflow::FixedCoupon { notional, rate, .. } => notional * rate * self.year_fraction(),
+            Cashflow::FloatingCoupon {
+                notional,
+                spread,
+                accrual_start,
+                accrual_end,
+            } => {
+                let tau = self.year_fraction();
+                let forward =
+                    projected_compounded_rate(projection, accrual_start, accrual_end, tau);
+                notional * (forward + spread) * tau
+            }
+        }
+    }
+}
+
+/// A sequence of cashflows, e.g. the coupons and the redemption of a bond or one side of a swap.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Leg {
+    cashflows: Vec<Cashflow>,
+}
+
+impl Leg {
+    pub fn new(cashflows: Vec<Cashflow>) -> Self {
+        Self { cashflows }
+    }
+
+    /// The coupons of the fixed rate paid with the frequency (per year) until the maturity.
+    pub fn fixed(notional: f64, rate: f64, maturity: f64, frequency: usize) -> Self {
+        Self::fixed_on_schedule(notional, rate, &payment_schedule(maturity, frequency))
+    }
+
+    /// The coupons of the fixed rate paid at the increasing payment times, accruing from 0.
+    pub fn fixed_on_schedule(notional: f64, rate: f64, payment_times: &[f64]) -> Self {
+        Self::from_schedule(payment_times, |accrual_start, accrual_end| {
+            Cashflow::FixedCoupon {
+                notional,
+                rate,
+                accrual_start,
+                accrual_end,
+            }
+        })
+    }
+
+    /// The coupons of the floating rate plus the spread paid with the frequency (per year) until the maturity.
+    pub fn floating(notional: f64, spread: f64, maturity: f64, frequency: usize) -> Self {
+        Self::from_schedule(
+            &payment_schedule(maturity, frequency),
+            |accrual_start, accrual_end| Cashflow::FloatingCoupon {
+                notional,
+                spread,
+                accrual_start,
+                accrual_end,
+            },
+        )
+    }
+
+    fn from_schedule(payment_times: &[f64], coupon: impl Fn(f64, f64) -> Cashflow) -> Self {
+        assert!(payment_times.windows(2).all(|w| w[0] < w[1]));
+        let mut accrual_start = 0.0;
+        let cashflows = payment_times
+            .iter()
+            .map(|accrual_end| {
+                let cashflow = coupon(accrual_start, *accrual_end);
+                accrual_start = *accrual_end;
+                cashflow
+            })
+            .collect();
+        Self { cashflows }
+    }
+
+    /// The leg with the amount paid at the time, e.g. the redemption of a bond.
+    pub fn with_payment(mut self, payment_time: f64, amount: f64) -> Self {
+        self.cashflows.push(Cashflow::Fixed {
+            payment_time,
+            amount,
+        });
+        self.cashflows
+            .sort_by(|a, b| a.payment_time().total_cmp(&b.payment_time()));
+        self
+    }
+
+    /// The leg with the notional paid at the start and received back at the end of the last period,
+    /// where a negative notional reverses the exchange.
+    pub fn with_notional_exchange(self, notional: f64) -> Self {
+        let maturity = self.maturity();
+        self.with_payment(0.0, -notional)
+            .with_payment(maturity, notional)
+    }
+
+    pub fn cashflows(&self) -> &[Cashflow] {
+        &self.cashflows
+    }
+
+    /// The last payment time, or 0 for an empty leg.
+    pub fn maturity(&self) -> f64 {
+        self.cashflows
+            .iter()
+            .map(Cashflow::payment_time)
+            .fold(0.0, f64::max)
+    }
+
+    /// The payment times with the amounts projected with the forward rates of the curve.
+    pub fn projected_cashflows(&self, projection: &impl YieldCurve) -> Vec<(f64, f64)> {
+        self.cashflows
+            .iter()
+            .map(|cashflow| (cashflow.payment_time(), cashflow.amount(projection)))
+            .collect()
+    }
+
+    /// The present value of the projected cashflows paid after the time `after`, each scaled by the
+    /// adjustment of its payment time, e.g. an indexation or a survival probability.
+    pub fn adjusted_value(
+        &self,
+        projection: &impl YieldCurve,
+        discount: &impl YieldCurve,
+        after: f64,
+        adjustment: impl Fn(f64) -> f64,
+    ) -> f64 {
+        self.projected_cashflows(projection)
+            .into_iter()
+            .filter(|(t, _)| *t > after)
+            .map(|(t, amount)| amount * adjustment(t) * discount.discount_factor(t))
+            .sum()
+    }
+
+    /// The present value of the cashflows not paid yet, with the floating coupons projected on the
+    /// projection curve and discounted on the discount curve.
+    pub fn present_value(&self, projection: &impl YieldCurve, discount: &impl YieldCurve) -> f64 {
+        self.adjusted_value(projection, discount, 0.0, |_| 1.0)
+    }
+
+    /// The present value of a unit coupon rate on the notionals of the coupons, i.e. the change of the
+    /// value per unit change of the fixed rate or the spread.
+    pub fn annuity(&self, discount: &impl YieldCurve) -> f64 {
+        self.cashflows
+            .iter()
+            .map(|cashflow| match cashflow {
+                Cashflow::Fixed { .. } => 0.0,
+                Cashflow::FixedCoupon { notional, .. }
+                | Cashflow::FloatingCoupon { notional, .. } => {
+                    notional
+                        * cashflow.year_fraction()
+                        * discount.discount_factor(cashflow.payment_time())
+                }
+            })
+            .sum()
+    }
+}
+
+/// Interest rate swap receiving the cashflows of one leg against paying those of the other leg.
+/// See https://en.wikipedia.org/wiki/Interest_rate_swap
+#[derive(Clone, Debug, PartialEq)]
+pub struct Swap {
+    pub receive_leg: Leg,
+    pub pay_leg: Leg,
+}
+
+impl Swap {
+    pub fn new(receive_leg: Leg, pay_leg: Leg) -> Self {
+        Self {
+            receive_leg,
+            pay_leg,
+        }
+    }
+
+    /// The vanilla swap paying the fixed rate against receiving the floating rate flat.
+    pub fn payer(
+        notional: f64,
+        fixed_rate: f64,
+        maturity: f64,
+        fixed_frequency: usize,
+        floating_frequency: usize,
+    ) -> Self {
+        Self::new(
+            Leg::floating(notional, 0.0, maturity, floating_frequency),
+            Leg::fixed(notional, fixed_rate, maturity, fixed_frequency),
+        )
+    }
+
+    pub fn value(&self, projection: &impl YieldCurve, discount: &impl YieldCurve) -> f64 {
+        self.receive_leg.present_value(projection, discount)
+            - self.pay_leg.present_value(projection, discount)
+    }
+
+    /// The fixed rate of the pay leg of zero value, assuming its coupons are the only fixed coupons.
+    pub fn par_rate(&self, projection: &impl YieldCurve, discount: &impl YieldCurve) -> f64 {
+        let fixed_rate_free = Leg::new(
+            self.pay_leg
+                .cashflows()
+                .iter()
+                .filter(|cashflow| !matches!(cashflow, Cashflow::FixedCoupon { .. }))
+                .copied()
+                .collect(),
+        );
+        (self.receive_leg.present_value(projection, discount)
+            - fixed_rate_free.present_value(projection, discount))
+            / self.pay_leg.annuity(discount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curves::yield_curve::{FlatCurve, InterpolatedCurve};
+    use crate::numerics::interpolation::InterpolationMethod;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn schedules_and_bonds() {
+        assert_eq!(payment_schedule(2.0, 2), vec![0.5, 1.0, 1.5, 2.0]);
+        let short_first = payment_schedule(1.25, 2);
+        assert_eq!(short_first.len(), 3);
+        assert_approx_eq!(short_first[0], 0.25, 1e-15);
+
+        // a floating rate note with its notional is at par on its projection curve
+        let curve = InterpolatedCurve::new(
+            vec![1.0, 2.0, 5.0],
+            vec![0.01, 0.02, 0.03],
+            InterpolationMethod::LogLinear,
+        );
+        let frn = Leg::floating(100.0, 0.0, 5.0, 4).with_payment(5.0, 100.0);
+        assert_approx_eq!(frn.present_value(&curve, &curve), 100.0, 1e-10);
+        assert_eq!(frn.maturity(), 5.0);
+
+        // the fixed coupons of the par yield
+        let flat = FlatCurve::new(0.03);
+        let bond = Leg::fixed(100.0, 0.0, 3.0, 1).with_payment(3.0, 100.0);
+        assert_approx_eq!(
+            bond.present_value(&flat, &flat),
+            100.0 * (-0.09_f64).exp(),
+            1e-10
+        );
+        let par_coupon = (1.0 - (-0.09_f64).exp()) / Leg::fixed(1.0, 0.0, 3.0, 1).annuity(&flat);
+        let par_bond = Leg::fixed(100.0, par_coupon, 3.0, 1).with_payment(3.0, 100.0);
+        assert_approx_eq!(par_bond.present_value(&flat, &flat), 100.0, 1e-10);
+        // the notional exchange at the start is paid
+        let exchanged = Leg::fixed(100.0, par_coupon, 3.0, 1).with_notional_exchange(100.0);
+        assert_approx_eq!(exchanged.present_value(&flat, &flat), 100.0, 1e-10);
+        assert_approx_eq!(
+            exchanged.adjusted_value(&flat, &flat, -1.0, |_| 1.0),
+            0.0,
+            1e-10
+        );
+    }
+
+    #[test]
+    fn swap_par_rate() {
+        let projection = InterpolatedCurve::new(
+            vec![1.0, 2.0, 5.0],
+            vec![0.02, 0.025, 0.03],
+            InterpolationMethod::LogLinear,
+        );
+        let discount = FlatCurve::new(0.02);
+        let swap = Swap::payer(1e6, 0.03, 5.0, 1, 4);
+        let par_rate = swap.par_rate(&projection, &discount);
+        let par_swap = Swap::payer(1e6, par_rate, 5.0, 1, 4);
+        assert_approx_eq!(par_swap.value(&projection, &discount), 0.0, 1e-6);
+        // the payer gains from rising rates
+        let higher = FlatCurve::new(0.04);
+        assert!(par_swap.value(&higher, &discount) > 0.0);
+
+        // single curve: the floating leg is worth $1 - P(T)$
+        let single = Swap::payer(1.0, 0.0, 5.0, 1, 4);
+        let annuity = Leg::fixed(1.0, 0.0, 5.0, 1).annuity(&discount);
+        assert_approx_eq!(
+            single.par_rate(&discount, &discount),
+            (1.0 - discount.discount_factor(5.0)) / annuity,
+            1e-12
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use crate::common::results::PricingError;
+use crate::common::time_grid::TimeGrid;
+use crate::curves::yield_curve::YieldCurve;
+
+/// Discount curve with piecewise constant forward rates, where the log discount factors and the
+/// forward rates of the segments are precomputed, such that a discount factor costs a binary search
+/// and an exponential. Sorted batches of times are evaluated in a single sweep over the pillars.
+#[derive(Clone, Debug)]
+pub struct LogLinearDiscountCurve {
+    /// the pillars including t = 0
+    times: Vec<f64>,
+    log_discount_factors: Vec<f64>,
+    /// the forward rate of the segment $[t_i, t_{i+1}]$, the last one extrapolated
+    forward_rates: Vec<f64>,
+}
+
+impl LogLinearDiscountCurve {
+    /// The curve, or the invalid parameter: no pillars, a number of discount factors other than of
+    /// pillars, non-positive or not strictly increasing pillars, or a non-positive discount factor.
+    pub fn try_new(times: Vec<f64>, discount_factors: Vec<f64>) -> Result<Self, PricingError> {
+        PricingError::check(!times.is_empty(), "number of pillars", 0.0)?;
+        PricingError::check(
+            times.len() == discount_factors.len(),
+            "number of discount factors",
+            discount_factors.len() as f64,
+        )?;
+        PricingError::check(
+            times[0] > 0.0 && times[0].is_finite(),
+            "first pillar",
+            times[0],
+        )?;
+        if let Some(w) = times
+            .windows(2)
+            .find(|w| !(w[0] < w[1] && w[1].is_finite()))
+        {
+            return Err(PricingError::InvalidParameter(format!(
+                "pillar {} after {}",
+                w[1], w[0]
+            )));
+        }
+        for p in &discount_factors {
+            PricingError::check(*p > 0.0 && p.is_finite(), "discount factor", *p)?;
+        }
+        let times: Vec<f64> = std::iter::once(0.0).chain(times).collect();
+        let log_discount_factors: Vec<f64> = std::iter::once(0.0)
+            .chain(discount_factors.iter().map(|p| p.ln()))
+            .collect();
+        let forward_rates = times
+            .windows(2)
+            .zip(log_discount_factors.windows(2))
+            .map(|(t, p)| -(p[1] - p[0]) / (t[1] - t[0]))
+            .collect();
+        Ok(Self {
+            times,
+            log_discount_factors,
+            forward_rates,
+        })
+    }
+
+    /// The curve matching the discount factors of the curve at the pillars, which fails for invalid
+    /// pillars as [`Self::try_new`].
+    pub fn from_curve(curve: &impl YieldCurve, pillars: Vec<f64>) -> Result<Self, PricingError> {
+        let discount_factors = curve.discount_factors(&pillars);
+        Self::try_new(pillars, discount_factors)
+    }
+
+    /// The discount factor on the segment starting at the pillar i.
+    fn segment_discount_factor(&self, i: usize, t: f64) -> f64 {
+        (self.log_discount_factors[i] - self.forward_rates[i] * (t - self.times[i])).exp()
+    }
+
+    fn segment(&self, t: f64) -> usize {
+        self.times
+            .partition_point(|t_i| *t_i <= t)
+            .clamp(1, self.forward_rates.len())
+            - 1
+    }
+}
+
+impl YieldCurve for LogLinearDiscountCurve {
+    fn zero_rate(&self, t: f64) -> f64 {
+        if t <= 0.0 {
+            return self.forward_rates[0];
+        }
+        -self.discount_factor(t).ln() / t
+    }
+
+    fn discount_factor(&self, t: f64) -> f64 {
+        self.segment_discount_factor(self.segment(t), t)
+    }
+
+    fn discount_factors(&self, times: &[f64]) -> Vec<f64> {
+        if !times.windows(2).all(|w| w[0] <= w[1]) {
+            return times.iter().map(|t| self.discount_factor(*t)).collect();
+        }
+        let mut i = 0;
+        times
+            .iter()
+            .map(|t| {
+                while i + 1 < self.forward_rates.len() && self.times[i + 1] <= *t {
+                    i += 1;
+                }
+                self.segment_discount_factor(i, *t)
+            })
+            .collect()
+    }
+
+    fn horizon(&self) -> Option<f64> {
+        self.times.last().copied()
+    }
+
+    fn pillars(&self) -> Vec<f64> {
+        self.times[1..].to_vec()
+    }
+}
+
+/// The discount factors of a curve memoized on the times of a simulation grid, for per-step discounting
+/// of Monte Carlo paths without evaluating the curve.
+#[derive(Clone, Debug)]
+pub struct DiscountGrid {
+    times: Vec<f64>,
+    discount_factors: Vec<f64>,
+}
+
+impl DiscountGrid {
+    pub fn new(curve: &impl YieldCurve, grid: &TimeGrid) -> Self {
+        let times = grid.times().to_vec();
+        let discount_factors = curve.discount_factors(&times);
+        Self {
+            times,
+            discount_factors,
+        }
+    }
+
+    pub fn times(&self) -> &[f64] {
+        &self.times
+    }
+
+    /// The discount factors $P(0, t_k)$ of the grid points.
+    pub fn discount_factors(&self) -> &[f64] {
+        &self.discount_factors
+    }
+
+    pub fn discount_factor(&self, step: usize) -> f64 {
+        self.discount_factors[step]
+    }
+
+    /// The forward discount factor $P(t_k, t_{k+1})$ of the step k.
+    pub fn step_discount_factor(&self, step: usize) -> f64 {
+        self.discount_factors[step + 1] / self.discount_factors[step]
+    }
+
+    /// The continuously compounded forward rate of the step k.
+    pub fn forward_rate(&self, step: usize) -> f64 {
+        -self.step_discount_factor(step).ln() / (self.times[step + 1] - self.times[step])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curves::yield_curve::InterpolatedCurve;
+    use crate::numerics::interpolation::InterpolationMethod;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn precomputed_log_linear_curve() {
+        let times = vec![1.0, 2.0, 5.0];
+        let rates = vec![0.01, 0.02, 0.03];
+        let reference =
+            InterpolatedCurve::new(times.clone(), rates, InterpolationMethod::LogLinear);
+        let curve = LogLinearDiscountCurve::from_curve(&reference, times).unwrap();
+
+        let queries = [0.0, 0.3, 1.0, 1.7, 2.0, 4.2, 5.0, 7.5];
+        let batched = curve.discount_factors(&queries);
+        let reversed: Vec<f64> = queries.iter().rev().copied().collect();
+        let batched_reversed = curve.discount_factors(&reversed);
+        for (k, t) in queries.iter().enumerate() {
+            assert_approx_eq!(
+                curve.discount_factor(*t),
+                reference.discount_factor(*t),
+                1e-14
+            );
+            assert_eq!(batched[k], curve.discount_factor(*t));
+            assert_eq!(batched_reversed[queries.len() - 1 - k], batched[k]);
+        }
+        assert_approx_eq!(curve.zero_rate(0.0), 0.01, 1e-14);
+        assert_approx_eq!(curve.zero_rate(2.0), 0.02, 1e-14);
+    }
+
+    #[test]
+    fn memoized_grid() {
+        let curve = LogLinearDiscountCurve::try_new(vec![1.0, 2.0], vec![0.97, 0.93]).unwrap();
+        let grid = DiscountGrid::new(&curve, &TimeGrid::uniform(2.0, 8));
+        assert_eq!(grid.discount_factor(0), 1.0);
+        assert_approx_eq!(grid.discount_factor(4), 0.97, 1e-14);
+        let compounded: f64 = (0..8).map(|k| grid.step_discount_factor(k)).product();
+        assert_approx_eq!(compounded, 0.93, 1e-14);
+        assert_approx_eq!(grid.forward_rate(5), (0.97_f64 / 0.93).ln(), 1e-12);
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        for (times, discount_factors) in [
+            (vec![], vec![]),
+            (vec![1.0, 2.0], vec![0.97]),
+            (vec![0.0, 2.0], vec![1.0, 0.93]),
+            (vec![2.0, 1.0], vec![0.97, 0.93]),
+            (vec![1.0, f64::NAN], vec![0.97, 0.93]),
+            (vec![1.0, 2.0], vec![0.97, 0.0]),
+            (vec![1.0, 2.0], vec![f64::NAN, 0.93]),
+        ] {
+            assert!(matches!(
+                LogLinearDiscountCurve::try_new(times, discount_factors),
+                Err(PricingError::InvalidParameter(_))
+            ));
+        }
+    }
+}
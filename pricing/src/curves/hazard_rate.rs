@@ -0,0 +1,99 @@
+/// Term structure of default risk, where times are measured in years from today.
+/// See https://en.wikipedia.org/wiki/Survival_function
+pub trait SurvivalCurve {
+    /// The probability $Q(t)$ of no default until t.
+    fn survival_probability(&self, t: f64) -> f64;
+
+    /// The probability of a default in $(t_1, t_2]$.
+    fn default_probability(&self, t1: f64, t2: f64) -> f64 {
+        assert!(t1 <= t2);
+        self.survival_probability(t1) - self.survival_probability(t2)
+    }
+}
+
+/// Piecewise constant hazard rates $\lambda_i$ on $(t_{i-1}, t_i]$ with survival probability
+/// $Q(t) = e^{-\int_0^t \lambda(s) ds}$, where the last hazard rate is extrapolated flat.
+/// See https://en.wikipedia.org/wiki/Failure_rate
+#[derive(Clone, Debug, PartialEq)]
+pub struct PiecewiseHazardCurve {
+    /// the increasing end times of the pieces
+    times: Vec<f64>,
+    hazard_rates: Vec<f64>,
+}
+
+impl PiecewiseHazardCurve {
+    pub fn new(times: Vec<f64>, hazard_rates: Vec<f64>) -> Self {
+        assert!(!times.is_empty());
+        assert_eq!(times.len(), hazard_rates.len());
+        assert!(times[0] > 0.0 && times.windows(2).all(|w| w[0] < w[1]));
+        assert!(hazard_rates.iter().all(|h| *h >= 0.0));
+        Self {
+            times,
+            hazard_rates,
+        }
+    }
+
+    pub fn flat(hazard_rate: f64) -> Self {
+        Self::new(vec![1.0], vec![hazard_rate])
+    }
+
+    pub fn times(&self) -> &[f64] {
+        &self.times
+    }
+
+    pub fn hazard_rates(&self) -> &[f64] {
+        &self.hazard_rates
+    }
+
+    /// The hazard rate $\lambda(t)$, right-continuous at the pillars.
+    pub fn hazard_rate(&self, t: f64) -> f64 {
+        let idx = self.times.partition_point(|end| *end <= t);
+        self.hazard_rates[idx.min(self.hazard_rates.len() - 1)]
+    }
+
+    /// The cumulative hazard $\int_0^t \lambda(s) ds$.
+    pub fn cumulative_hazard(&self, t: f64) -> f64 {
+        let mut cumulative = 0.0;
+        let mut start = 0.0;
+        for (idx, (end, rate)) in self.times.iter().zip(&self.hazard_rates).enumerate() {
+            let is_last = idx == self.times.len() - 1;
+            let end = if is_last { t.max(*end) } else { *end };
+            cumulative += rate * (t.min(end) - start).max(0.0);
+            if t <= end {
+                break;
+            }
+            start = end;
+        }
+        cumulative
+    }
+}
+
+impl SurvivalCurve for PiecewiseHazardCurve {
+    fn survival_probability(&self, t: f64) -> f64 {
+        (-self.cumulative_hazard(t)).exp()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn piecewise_survival() {
+        let curve = PiecewiseHazardCurve::new(vec![1.0, 3.0], vec![0.01, 0.02]);
+        assert_eq!(curve.survival_probability(0.0), 1.0);
+        assert_approx_eq!(curve.survival_probability(0.5), (-0.005_f64).exp(), 1e-15);
+        assert_approx_eq!(curve.survival_probability(2.0), (-0.03_f64).exp(), 1e-15);
+        // flat extrapolation of the last hazard rate
+        assert_approx_eq!(curve.survival_probability(5.0), (-0.09_f64).exp(), 1e-15);
+
+        assert_eq!(curve.hazard_rate(1.0), 0.02);
+        assert_eq!(curve.hazard_rate(10.0), 0.02);
+        assert_approx_eq!(
+            curve.default_probability(1.0, 2.0),
+            (-0.01_f64).exp() - (-0.03_f64).exp(),
+            1e-15
+        );
+    }
+}
@@ -0,0 +1,282 @@
+use ndarray::{Array1, Array2};
+
+use crate::common::results::PricingError;
+use crate::curves::cashflows::Leg;
+use crate::curves::yield_curve::YieldCurve;
+use crate::numerics::least_squares::solve_linear_system;
+use crate::numerics::linear_programming::simplex_maximize;
+
+/// The present value with the Fisher-Weil duration $D = \sum_i t_i V_i / V$ and convexity
+/// $C = \sum_i t_i^2 V_i / V$ of the discounted cashflows $V_i$, i.e. the sensitivities to parallel
+/// shifts of the continuously compounded zero rates, $dV / V = -D\, dr + C\, dr^2 / 2$.
+/// See https://en.wikipedia.org/wiki/Bond_duration#Fisher%E2%80%93Weil_duration
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateSensitivities {
+    pub present_value: f64,
+    pub duration: f64,
+    pub convexity: f64,
+}
+
+impl RateSensitivities {
+    /// The sensitivities of the cashflows not paid yet, projected and discounted on the curve.
+    pub fn of_leg(leg: &Leg, curve: &impl YieldCurve) -> Self {
+        let (mut present_value, mut dollar_duration, mut dollar_convexity) = (0.0, 0.0, 0.0);
+        for (t, amount) in leg.projected_cashflows(curve) {
+            if t > 0.0 {
+                let value = amount * curve.discount_factor(t);
+                present_value += value;
+                dollar_duration += t * value;
+                dollar_convexity += t * t * value;
+            }
+        }
+        Self {
+            present_value,
+            duration: dollar_duration / present_value,
+            convexity: dollar_convexity / present_value,
+        }
+    }
+}
+
+/// The holdings (units) of the bonds of a universe immunizing liabilities, with their cost.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImmunizedPortfolio {
+    pub holdings: Vec<f64>,
+    pub cost: f64,
+}
+
+impl ImmunizedPortfolio {
+    /// The cashflows of the holdings of the bonds.
+    pub fn cashflows(&self, bonds: &[Leg], curve: &impl YieldCurve) -> Vec<(f64, f64)> {
+        let mut cashflows: Vec<(f64, f64)> = bonds
+            .iter()
+            .zip(&self.holdings)
+            .flat_map(|(bond, units)| {
+                bond.projected_cashflows(curve)
+                    .into_iter()
+                    .map(move |(t, amount)| (t, units * amount))
+            })
+            .collect();
+        cashflows.sort_by(|a, b| a.0.total_cmp(&b.0));
+        cashflows
+    }
+}
+
+/// The cheapest long-only portfolio of the bonds (per unit at the prices) whose cashflows cover each
+/// liability cashflow, where surpluses are reinvested until the next liability date at the
+/// (annually compounded) reinvestment rate. This is the linear program
+/// '''math
+/// \min_{x \ge 0} p^T x \quad \text{s.t.} \quad \sum_{t_i \le t_k} (1 + r)^{t_k - t_i} (C_{ij} x_j - L_i) \ge 0
+/// '''
+/// for all liability dates $t_k$, which is solved by its dual.
+/// See https://en.wikipedia.org/wiki/Immunization_(finance)#Cash_flow_matching
+pub fn cashflow_matching(
+    liabilities: &Leg,
+    bonds: &[Leg],
+    prices: &[f64],
+    curve: &impl YieldCurve,
+    reinvestment_rate: f64,
+) -> Result<ImmunizedPortfolio, PricingError> {
+    PricingError::check(
+        prices.len() == bonds.len(),
+        "nr bond prices",
+        prices.len() as f64,
+    )?;
+    for price in prices {
+        PricingError::check(*price > 0.0, "bond price", *price)?;
+    }
+    let liability_cashflows: Vec<(f64, f64)> = liabilities
+        .projected_cashflows(curve)
+        .into_iter()
+        .filter(|(t, _)| *t > 0.0)
+        .collect();
+    let mut dates: Vec<f64> = liability_cashflows.iter().map(|(t, _)| *t).collect();
+    dates.sort_by(f64::total_cmp);
+    dates.dedup();
+
+    let accumulated = |cashflows: &[(f64, f64)], date: f64| -> f64 {
+        cashflows
+            .iter()
+            .filter(|(t, _)| *t > 0.0 && *t <= date)
+            .map(|(t, amount)| amount * (1.0 + reinvestment_rate).powf(date - t))
+            .sum()
+    };
+    let bond_cashflows: Vec<Vec<(f64, f64)>> = bonds
+        .iter()
+        .map(|bond| bond.projected_cashflows(curve))
+        .collect();
+    let coverage = Array2::from_shape_fn((dates.len(), bonds.len()), |(k, j)| {
+        accumulated(&bond_cashflows[j], dates[k])
+    });
+    let required: Array1<f64> = dates
+        .iter()
+        .map(|date| accumulated(&liability_cashflows, *date))
+        .collect();
+
+    // the dual max L^T y s.t. C^T y <= p, y >= 0 is unbounded if the bonds cannot cover the liabilities
+    let dual = simplex_maximize(
+        &required,
+        &coverage.t().to_owned(),
+        &Array1::from(prices.to_vec()),
+    )
+    .ok_or_else(|| {
+        PricingError::InvalidParameter("the bonds cannot cover the liabilities".to_string())
+    })?;
+    Ok(ImmunizedPortfolio {
+        holdings: dual.duals.to_vec(),
+        cost: dual.objective,
+    })
+}
+
+/// The portfolio of the bonds, priced on the curve, matching the present value and the duration of the
+/// liabilities, and their convexity if `match_convexity`, with the minimum sum of squared market values
+/// of the holdings. Holdings may be short. Redington's condition of a surplus that increases under parallel
+/// shifts holds if the convexity of the portfolio exceeds the one of the liabilities.
+/// See https://en.wikipedia.org/wiki/Immunization_(finance)
+pub fn duration_matching(
+    liabilities: &Leg,
+    bonds: &[Leg],
+    curve: &impl YieldCurve,
+    match_convexity: bool,
+) -> Result<ImmunizedPortfolio, PricingError> {
+    let target = RateSensitivities::of_leg(liabilities, curve);
+    let sensitivities: Vec<RateSensitivities> = bonds
+        .iter()
+        .map(|bond| RateSensitivities::of_leg(bond, curve))
+        .collect();
+    for bond in &sensitivities {
+        PricingError::check(bond.present_value > 0.0, "bond value", bond.present_value)?;
+    }
+    let nr_constraints = if match_convexity { 3 } else { 2 };
+    PricingError::check(
+        bonds.len() >= nr_constraints,
+        "nr bonds",
+        bonds.len() as f64,
+    )?;
+
+    // the constraints M w = m on the market values w: the sum, the duration and the convexity
+    let constraints = Array2::from_shape_fn((nr_constraints, bonds.len()), |(k, j)| match k {
+        0 => 1.0,
+        1 => sensitivities[j].duration,
+        _ => sensitivities[j].convexity,
+    });
+    let moments = [1.0, target.duration, target.convexity];
+    let targets: Array1<f64> = moments[..nr_constraints]
+        .iter()
+        .map(|moment| moment * target.present_value)
+        .collect();
+    // the minimum-norm solution $w = M^T (M M^T)^{-1} m$
+    let gram = constraints.dot(&constraints.t());
+    let multipliers = solve_linear_system(&gram, &targets).ok_or_else(|| {
+        PricingError::InvalidParameter("the bonds do not span the constraints".to_string())
+    })?;
+    let market_values = constraints.t().dot(&multipliers);
+    Ok(ImmunizedPortfolio {
+        holdings: market_values
+            .iter()
+            .zip(&sensitivities)
+            .map(|(value, bond)| value / bond.present_value)
+            .collect(),
+        cost: market_values.sum(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curves::yield_curve::FlatCurve;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn bond(coupon: f64, maturity: f64) -> Leg {
+        Leg::fixed(100.0, coupon, maturity, 1).with_payment(maturity, 100.0)
+    }
+
+    #[test]
+    fn cashflow_matching_covers_liabilities() {
+        let curve = FlatCurve::new(0.03);
+        let liabilities = Leg::default()
+            .with_payment(1.0, 100.0)
+            .with_payment(2.0, 200.0)
+            .with_payment(3.0, 150.0);
+        let bonds = vec![bond(0.0, 1.0), bond(0.05, 2.0), bond(0.04, 3.0)];
+        let prices: Vec<f64> = bonds
+            .iter()
+            .map(|b| b.present_value(&curve, &curve))
+            .collect();
+        let portfolio = cashflow_matching(&liabilities, &bonds, &prices, &curve, 0.0).unwrap();
+
+        // without reinvestment the exact match is cheapest: 150 / 104 of the 3y bond, ...
+        let x3 = 150.0 / 104.0;
+        let x2 = (200.0 - 4.0 * x3) / 105.0;
+        let x1 = (100.0 - 4.0 * x3 - 5.0 * x2) / 100.0;
+        assert_approx_eq!(portfolio.holdings[0], x1, 1e-10);
+        assert_approx_eq!(portfolio.holdings[1], x2, 1e-10);
+        assert_approx_eq!(portfolio.holdings[2], x3, 1e-10);
+        let cost: f64 = prices
+            .iter()
+            .zip(&portfolio.holdings)
+            .map(|(p, x)| p * x)
+            .sum();
+        assert_approx_eq!(portfolio.cost, cost, 1e-8);
+        assert_approx_eq!(
+            portfolio.cost,
+            liabilities.present_value(&curve, &curve),
+            1e-8
+        );
+        assert_eq!(portfolio.cashflows(&bonds, &curve).len(), 9);
+
+        // a liability before the first bond cashflow cannot be covered
+        let early = liabilities.clone().with_payment(0.5, 10.0);
+        assert!(cashflow_matching(&early, &bonds, &prices, &curve, 0.0).is_err());
+        // a liability after the last bond is covered by reinvested surpluses, more cheaply at a higher rate
+        let later = liabilities.with_payment(5.0, 10.0);
+        let carried = cashflow_matching(&later, &bonds, &prices, &curve, 0.0).unwrap();
+        let reinvested = cashflow_matching(&later, &bonds, &prices, &curve, 0.02).unwrap();
+        assert!(reinvested.holdings.iter().all(|x| *x >= 0.0));
+        assert!(reinvested.cost < carried.cost);
+        assert!(carried.cost > portfolio.cost);
+    }
+
+    #[test]
+    fn duration_and_convexity_immunization() {
+        let curve = FlatCurve::new(0.04);
+        let liabilities = Leg::default()
+            .with_payment(5.0, 1000.0)
+            .with_payment(8.0, 1000.0);
+        let bonds = vec![bond(0.03, 2.0), bond(0.04, 6.0), bond(0.05, 15.0)];
+        let target = RateSensitivities::of_leg(&liabilities, &curve);
+
+        for match_convexity in [false, true] {
+            let portfolio =
+                duration_matching(&liabilities, &bonds, &curve, match_convexity).unwrap();
+            assert_approx_eq!(portfolio.cost, target.present_value, 1e-8);
+            let assets = Leg::new(
+                bonds
+                    .iter()
+                    .zip(&portfolio.holdings)
+                    .flat_map(|(bond, units)| {
+                        bond.projected_cashflows(&curve)
+                            .into_iter()
+                            .map(
+                                move |(t, amount)| crate::curves::cashflows::Cashflow::Fixed {
+                                    payment_time: t,
+                                    amount: units * amount,
+                                },
+                            )
+                    })
+                    .collect(),
+            );
+            let matched = RateSensitivities::of_leg(&assets, &curve);
+            assert_approx_eq!(matched.present_value, target.present_value, 1e-8);
+            assert_approx_eq!(matched.duration, target.duration, 1e-10);
+            if match_convexity {
+                assert_approx_eq!(matched.convexity, target.convexity, 1e-10);
+            }
+            // the surplus is immune to small parallel shifts
+            let shifted = FlatCurve::new(0.0401);
+            let surplus = assets.present_value(&shifted, &shifted)
+                - liabilities.present_value(&shifted, &shifted);
+            assert!(surplus.abs() < 1e-3);
+        }
+        assert!(duration_matching(&liabilities, &bonds[..2], &curve, true).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+use crate::common::results::PricingError;
+use crate::curves::cashflows::{payment_schedule, Leg};
+use crate::curves::yield_curve::YieldCurve;
+use crate::numerics::interpolation::{Extrapolation, InterpolationMethod, Interpolator1D};
+
+/// Projection of a price index (e.g. CPI) from the base index $I_0$ today, where the trend of the index
+/// ratios $I(T) / I_0 = (1 + z(T))^T$ is implied by zero-coupon inflation swap rates $z(T)$ and
+/// interpolated log-linearly, i.e. with piecewise constant forward inflation rates,
+/// and a monthly seasonality is applied within the years.
+/// See https://en.wikipedia.org/wiki/Inflation_swap
+#[derive(Clone, Debug)]
+pub struct InflationCurve {
+    base_index: f64,
+    /// the trend of the index ratios $I(T) / I_0$
+    index_ratios: Interpolator1D,
+    /// the cumulative log seasonal adjustments from the start of a year to each month
+    seasonality: [f64; 12],
+}
+
+impl InflationCurve {
+    /// The curve of the zero-coupon inflation swap rates (annually compounded) of the increasing
+    /// maturities (in years), one rate per maturity.
+    pub fn from_zc_swap_rates(
+        base_index: f64,
+        maturities: Vec<f64>,
+        zc_rates: Vec<f64>,
+    ) -> Result<Self, PricingError> {
+        PricingError::check(
+            base_index > 0.0 && base_index.is_finite(),
+            "base index",
+            base_index,
+        )?;
+        if maturities.is_empty() || maturities.len() != zc_rates.len() {
+            return Err(PricingError::InvalidParameter(format!(
+                "{} maturities and {} zero-coupon inflation swap rates",
+                maturities.len(),
+                zc_rates.len()
+            )));
+        }
+        let mut previous = 0.0;
+        for (t, z) in maturities.iter().zip(&zc_rates) {
+            PricingError::check(*t > previous && t.is_finite(), "maturity", *t)?;
+            PricingError::check(*z > -1.0 && z.is_finite(), "zero-coupon inflation rate", *z)?;
+            previous = *t;
+        }
+        let ratios = std::iter::once(1.0)
+            .chain(
+                maturities
+                    .iter()
+                    .zip(&zc_rates)
+                    .map(|(t, z)| (1.0 + z).powf(*t)),
+            )
+            .collect();
+        let times = std::iter::once(0.0).chain(maturities).collect();
+        Ok(Self {
+            base_index,
+            index_ratios: Interpolator1D::new(times, ratios, InterpolationMethod::LogLinear)
+                .with_extrapolation(Extrapolation::Linear),
+            seasonality: [0.0; 12],
+        })
+    }
+
+    /// The monthly log seasonal adjustments of the months of the year (from the base month), which are
+    /// normalized to sum to zero, such that the index of whole years is unaffected.
+    pub fn with_seasonality(self, monthly_adjustments: [f64; 12]) -> Self {
+        let mean = monthly_adjustments.iter().sum::<f64>() / 12.0;
+        let mut seasonality = [0.0; 12];
+        for month in 1..12 {
+            seasonality[month] = seasonality[month - 1] + monthly_adjustments[month - 1] - mean;
+        }
+        Self {
+            seasonality,
+            ..self
+        }
+    }
+
+    pub fn base_index(&self) -> f64 {
+        self.base_index
+    }
+
+    /// The projected index $I(t)$ at the non-negative time t (in years).
+    pub fn index(&self, t: f64) -> Result<f64, PricingError> {
+        PricingError::check(t >= 0.0 && t.is_finite(), "time", t)?;
+        let month = ((12.0 * t.fract()).floor() as usize).min(11);
+        let index_ratio = self.index_ratios.value(t).ok_or_else(|| {
+            PricingError::InvalidParameter(format!("inflation index ratio at time {t}"))
+        })?;
+        Ok(self.base_index * index_ratio * self.seasonality[month].exp())
+    }
+
+    /// The annually compounded zero-coupon inflation rate $(I(t) / I_0)^{1/t} - 1$.
+    pub fn zc_rate(&self, t: f64) -> Result<f64, PricingError> {
+        PricingError::check(t > 0.0, "time", t)?;
+        Ok((self.index(t)? / self.base_index).powf(1.0 / t) - 1.0)
+    }
+}
+
+/// Zero-coupon inflation swap, which exchanges the inflation $N (I(T) / I_0 - 1)$ against the fixed
+/// amount $N ((1 + K)^T - 1)$ at maturity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ZeroCouponInflationSwap {
+    pub notional: f64,
+    pub maturity: f64,
+    pub fixed_rate: f64,
+}
+
+impl ZeroCouponInflationSwap {
+    pub fn new(notional: f64, maturity: f64, fixed_rate: f64) -> Self {
+        Self {
+            notional,
+            maturity,
+            fixed_rate,
+        }
+    }
+
+    /// The value for the receiver of the inflation leg.
+    pub fn value(
+        &self,
+        inflation: &InflationCurve,
+        discount: &impl YieldCurve,
+    ) -> Result<f64, PricingError> {
+        let inflation_ratio = inflation.index(self.maturity)? / inflation.base_index();
+        let fixed_ratio = (1.0 + self.fixed_rate).powf(self.maturity);
+        Ok(self.notional
+            * discount.discount_factor(self.maturity)
+            * (inflation_ratio - fixed_ratio))
+    }
+
+    /// The fixed rate of zero value.
+    pub fn par_rate(&self, inflation: &InflationCurve) -> Result<f64, PricingError> {
+        inflation.zc_rate(self.maturity)
+    }
+}
+
+/// Inflation-linked bond paying the real coupons and the notional indexed by the ratio of the projected
+/// index to the base index at issuance (without indexation lag and deflation floor).
+#[derive(Clone, Debug, PartialEq)]
+pub struct InflationLinkedBond {
+    pub notional: f64,
+    /// the annual real coupon rate
+    pub real_coupon: f64,
+    /// the coupon times (in years), where the last one is the maturity
+    pub coupon_times: Vec<f64>,
+    /// the index at issuance
+    pub base_index: f64,
+}
+
+impl InflationLinkedBond {
+    /// The bond with the coupons paid with the frequency (per year) until the maturity.
+    pub fn new(
+        notional: f64,
+        real_coupon: f64,
+        maturity: f64,
+        frequency: usize,
+        base_index: f64,
+    ) -> Self {
+        let coupon_times = payment_schedule(maturity, frequency);
+        Self {
+            notional,
+            real_coupon,
+            coupon_times,
+            base_index,
+        }
+    }
+
+    /// The present value of the indexed coupons and notional.
+    pub fn dirty_price(
+        &self,
+        inflation: &InflationCurve,
+        discount: &impl YieldCurve,
+    ) -> Result<f64, PricingError> {
+        let maturity = *self.coupon_times.last().unwrap();
+        let real_cashflows =
+            Leg::fixed_on_schedule(self.notional, self.real_coupon, &self.coupon_times)
+                .with_payment(maturity, self.notional);
+        real_cashflows
+            .projected_cashflows(discount)
+            .into_iter()
+            .filter(|(t, _)| *t > 0.0)
+            .map(|(t, amount)| {
+                let indexation = inflation.index(t)? / self.base_index;
+                Ok(amount * indexation * discount.discount_factor(t))
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curves::yield_curve::FlatCurve;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn inflation_curve() -> InflationCurve {
+        InflationCurve::from_zc_swap_rates(300.0, vec![1.0, 2.0, 5.0], vec![0.03, 0.025, 0.022])
+            .unwrap()
+    }
+
+    #[test]
+    fn index_projection_with_seasonality() {
+        let curve = inflation_curve();
+        assert_eq!(curve.index(0.0).unwrap(), 300.0);
+        assert_approx_eq!(curve.index(2.0).unwrap(), 300.0 * 1.025_f64.powi(2), 1e-10);
+        assert_approx_eq!(curve.zc_rate(5.0).unwrap(), 0.022, 1e-12);
+        // constant forward inflation between the pillars
+        assert_approx_eq!(
+            curve.index(1.5).unwrap(),
+            (curve.index(1.0).unwrap() * curve.index(2.0).unwrap()).sqrt(),
+            1e-10
+        );
+
+        let mut adjustments = [0.0; 12];
+        adjustments[0] = 0.004;
+        adjustments[6] = -0.004;
+        let seasonal = inflation_curve().with_seasonality(adjustments);
+        // whole years are unaffected, whereas the index rises faster in the first months
+        assert_approx_eq!(
+            seasonal.index(2.0).unwrap(),
+            curve.index(2.0).unwrap(),
+            1e-10
+        );
+        assert!(seasonal.index(1.25).unwrap() > curve.index(1.25).unwrap());
+        assert_approx_eq!(
+            seasonal.index(1.25).unwrap(),
+            curve.index(1.25).unwrap() * 0.004_f64.exp(),
+            1e-10
+        );
+        assert_approx_eq!(
+            seasonal.index(1.75).unwrap(),
+            curve.index(1.75).unwrap(),
+            1e-10
+        );
+    }
+
+    #[test]
+    fn zc_swap_and_linker() {
+        let inflation = inflation_curve();
+        let discount = FlatCurve::new(0.04);
+
+        let swap = ZeroCouponInflationSwap::new(1e6, 2.0, 0.025);
+        assert_approx_eq!(swap.value(&inflation, &discount).unwrap(), 0.0, 1e-6);
+        assert_approx_eq!(swap.par_rate(&inflation).unwrap(), 0.025, 1e-12);
+        let payer = ZeroCouponInflationSwap::new(1e6, 2.0, 0.03);
+        assert!(payer.value(&inflation, &discount).unwrap() < 0.0);
+
+        // a linker with the real yield equal to the nominal rate less inflation is at par
+        let flat_inflation =
+            InflationCurve::from_zc_swap_rates(300.0, vec![1.0, 10.0], vec![0.02; 2]).unwrap();
+        let real_rate = 1.04_f64.ln() - 1.02_f64.ln();
+        let bond = InflationLinkedBond::new(100.0, real_rate.exp() - 1.0, 3.0, 1, 300.0);
+        assert_eq!(bond.coupon_times, vec![1.0, 2.0, 3.0]);
+        let price = bond
+            .dirty_price(&flat_inflation, &FlatCurve::new(1.04_f64.ln()))
+            .unwrap();
+        assert_approx_eq!(price, 100.0, 1e-10);
+    }
+
+    #[test]
+    fn invalid_curves() {
+        let curve = |maturities: Vec<f64>, zc_rates: Vec<f64>| {
+            InflationCurve::from_zc_swap_rates(300.0, maturities, zc_rates)
+        };
+        assert!(curve(vec![1.0, 2.0], vec![0.02]).is_err());
+        assert!(curve(vec![], vec![]).is_err());
+        assert!(curve(vec![2.0, 1.0], vec![0.02, 0.02]).is_err());
+        assert!(curve(vec![1.0], vec![f64::NAN]).is_err());
+        assert!(inflation_curve().index(-1.0).is_err());
+        assert!(inflation_curve().zc_rate(0.0).is_err());
+    }
+}
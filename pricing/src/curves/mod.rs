@@ -0,0 +1,14 @@
+pub mod amortization;
+pub mod caplets;
+pub mod cashflows;
+pub mod discount_cache;
+pub mod hazard_rate;
+pub mod immunization;
+pub mod inflation;
+pub mod nelson_siegel;
+pub mod overnight;
+pub mod roll_down;
+pub mod yield_curve;
+
+pub use hazard_rate::SurvivalCurve;
+pub use yield_curve::YieldCurve;
@@ -0,0 +1,193 @@
+use ndarray::{Array1, Array2};
+
+use crate::curves::yield_curve::YieldCurve;
+use crate::numerics::least_squares::least_squares;
+
+/// The loading $(1 - e^{-t/\tau}) / (t/\tau)$ of the slope factor, with limit 1 at t = 0.
+fn slope_loading(t: f64, tau: f64) -> f64 {
+    let x = t / tau;
+    if x < 1e-10 {
+        return 1.0 - x / 2.0;
+    }
+    (1.0 - (-x).exp()) / x
+}
+
+/// The loading of the curvature factor, vanishing at t = 0.
+fn curvature_loading(t: f64, tau: f64) -> f64 {
+    slope_loading(t, tau) - (-t / tau).exp()
+}
+
+/// Candidate decay parameters (in years) of the grid search.
+fn tau_grid() -> Vec<f64> {
+    (0..40)
+        .map(|i| 0.1 * (300.0_f64).powf(i as f64 / 39.0))
+        .collect()
+}
+
+/// Least squares fit of the linear factors for the fixed decays, returning the betas and the squared error.
+fn fit_betas(maturities: &[f64], yields: &[f64], taus: &[f64]) -> Option<(Array1<f64>, f64)> {
+    let nr_factors = 2 + taus.len();
+    let design = Array2::from_shape_fn((maturities.len(), nr_factors), |(i, j)| match j {
+        0 => 1.0,
+        1 => slope_loading(maturities[i], taus[0]),
+        _ => curvature_loading(maturities[i], taus[j - 2]),
+    });
+    let y = Array1::from(yields.to_vec());
+    let betas = least_squares(&design, &y)?;
+    let residuals = design.dot(&betas) - &y;
+    Some((betas, residuals.dot(&residuals)))
+}
+
+/// The Nelson-Siegel yield curve
+/// '''math
+/// y(t) = \beta_0 + \beta_1 \frac{1 - e^{-t/\tau}}{t/\tau} + \beta_2 (\frac{1 - e^{-t/\tau}}{t/\tau} - e^{-t/\tau})
+/// '''
+/// with level, slope and curvature factors. See https://en.wikipedia.org/wiki/Fixed-income_attribution#Modeling_the_yield_curve
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NelsonSiegel {
+    pub beta0: f64,
+    pub beta1: f64,
+    pub beta2: f64,
+    pub tau: f64,
+}
+
+impl NelsonSiegel {
+    pub fn new(beta0: f64, beta1: f64, beta2: f64, tau: f64) -> Self {
+        assert!(tau > 0.0);
+        Self {
+            beta0,
+            beta1,
+            beta2,
+            tau,
+        }
+    }
+
+    /// Least squares fit to the observed (continuously compounded) zero yields, where the betas are
+    /// solved linearly for each decay $\tau$ of a grid search. Returns None for too few observations.
+    pub fn fit(maturities: &[f64], yields: &[f64]) -> Option<Self> {
+        assert_eq!(maturities.len(), yields.len());
+        if maturities.len() < 3 {
+            return None;
+        }
+        tau_grid()
+            .into_iter()
+            .filter_map(|tau| fit_betas(maturities, yields, &[tau]).map(|fit| (tau, fit)))
+            .min_by(|(_, (_, e1)), (_, (_, e2))| e1.total_cmp(e2))
+            .map(|(tau, (betas, _))| Self::new(betas[0], betas[1], betas[2], tau))
+    }
+}
+
+impl YieldCurve for NelsonSiegel {
+    fn zero_rate(&self, t: f64) -> f64 {
+        self.beta0
+            + self.beta1 * slope_loading(t, self.tau)
+            + self.beta2 * curvature_loading(t, self.tau)
+    }
+}
+
+/// The Svensson extension of the Nelson-Siegel curve by a second curvature factor
+/// '''math
+/// \beta_3 (\frac{1 - e^{-t/\tau_2}}{t/\tau_2} - e^{-t/\tau_2})
+/// '''
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Svensson {
+    pub beta0: f64,
+    pub beta1: f64,
+    pub beta2: f64,
+    pub beta3: f64,
+    pub tau1: f64,
+    pub tau2: f64,
+}
+
+impl Svensson {
+    pub fn new(beta0: f64, beta1: f64, beta2: f64, beta3: f64, tau1: f64, tau2: f64) -> Self {
+        assert!(tau1 > 0.0 && tau2 > 0.0);
+        Self {
+            beta0,
+            beta1,
+            beta2,
+            beta3,
+            tau1,
+            tau2,
+        }
+    }
+
+    /// Least squares fit to the observed zero yields, see `NelsonSiegel::fit`, with a grid search
+    /// over both decays $\tau_1 < \tau_2$. Returns None for too few observations.
+    pub fn fit(maturities: &[f64], yields: &[f64]) -> Option<Self> {
+        assert_eq!(maturities.len(), yields.len());
+        if maturities.len() < 4 {
+            return None;
+        }
+        let grid = tau_grid();
+        grid.iter()
+            .enumerate()
+            .flat_map(|(i, tau1)| grid[i + 1..].iter().map(move |tau2| (*tau1, *tau2)))
+            .filter_map(|(tau1, tau2)| {
+                fit_betas(maturities, yields, &[tau1, tau2]).map(|fit| ((tau1, tau2), fit))
+            })
+            .min_by(|(_, (_, e1)), (_, (_, e2))| e1.total_cmp(e2))
+            .map(|((tau1, tau2), (betas, _))| {
+                Self::new(betas[0], betas[1], betas[2], betas[3], tau1, tau2)
+            })
+    }
+}
+
+impl YieldCurve for Svensson {
+    fn zero_rate(&self, t: f64) -> f64 {
+        self.beta0
+            + self.beta1 * slope_loading(t, self.tau1)
+            + self.beta2 * curvature_loading(t, self.tau1)
+            + self.beta3 * curvature_loading(t, self.tau2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    const MATURITIES: [f64; 10] = [0.25, 0.5, 1.0, 2.0, 3.0, 5.0, 7.0, 10.0, 20.0, 30.0];
+
+    #[test]
+    fn nelson_siegel_limits() {
+        let curve = NelsonSiegel::new(0.04, -0.02, 0.01, 2.0);
+        // short rate beta0 + beta1 and long rate beta0
+        assert_approx_eq!(curve.zero_rate(0.0), 0.02, 1e-12);
+        assert_approx_eq!(curve.zero_rate(1e4), 0.04, 1e-3);
+        assert_approx_eq!(
+            curve.discount_factor(2.0),
+            (-2.0 * curve.zero_rate(2.0)).exp(),
+            1e-15
+        );
+    }
+
+    #[test]
+    fn nelson_siegel_fit() {
+        let true_curve = NelsonSiegel::new(0.045, -0.02, 0.015, 1.5);
+        let yields: Vec<f64> = MATURITIES
+            .iter()
+            .map(|t| true_curve.zero_rate(*t))
+            .collect();
+
+        let fitted = NelsonSiegel::fit(&MATURITIES, &yields).unwrap();
+        for t in MATURITIES {
+            assert_approx_eq!(fitted.zero_rate(t), true_curve.zero_rate(t), 1e-4);
+        }
+        assert!(NelsonSiegel::fit(&[1.0, 2.0], &[0.01, 0.02]).is_none());
+    }
+
+    #[test]
+    fn svensson_fit() {
+        let true_curve = Svensson::new(0.04, -0.01, 0.02, -0.015, 1.0, 8.0);
+        let yields: Vec<f64> = MATURITIES
+            .iter()
+            .map(|t| true_curve.zero_rate(*t))
+            .collect();
+
+        let fitted = Svensson::fit(&MATURITIES, &yields).unwrap();
+        for t in MATURITIES {
+            assert_approx_eq!(fitted.zero_rate(t), true_curve.zero_rate(t), 1e-4);
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use crate::common::results::PricingError;
+use crate::curves::yield_curve::YieldCurve;
+
+/// The number of days per year of the Actual/360 and Actual/365 Fixed day count conventions,
+/// e.g. Act/360 for SOFR and ESTR and Act/365F for SONIA.
+/// See https://en.wikipedia.org/wiki/Day_count_convention#Actual_methods
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DayCountBasis {
+    Act360,
+    Act365Fixed,
+}
+
+impl DayCountBasis {
+    pub fn days_per_year(&self) -> f64 {
+        match self {
+            DayCountBasis::Act360 => 360.0,
+            DayCountBasis::Act365Fixed => 365.0,
+        }
+    }
+
+    pub fn year_fraction(&self, nr_days: u32) -> f64 {
+        nr_days as f64 / self.days_per_year()
+    }
+}
+
+/// The observation conventions of a rate compounded in arrears, in business days.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompoundingConvention {
+    pub basis: DayCountBasis,
+    /// the lag of the observed rates, such that the rate is known before the payment
+    pub lookback: usize,
+    /// whether the rates are weighted by the days of the (shifted) observation period
+    /// instead of the interest period
+    pub observation_shift: bool,
+    /// the number of last days of the period which repeat the rate of the day before them
+    pub lockout: usize,
+}
+
+impl CompoundingConvention {
+    /// Plain compounding in arrears without lag.
+    pub fn new(basis: DayCountBasis) -> Self {
+        Self {
+            basis,
+            lookback: 0,
+            observation_shift: false,
+            lockout: 0,
+        }
+    }
+
+    pub fn with_lookback(self, lookback: usize) -> Self {
+        Self { lookback, ..self }
+    }
+
+    /// The lookback applies to the day weights too.
+    pub fn with_observation_shift(self) -> Self {
+        Self {
+            observation_shift: true,
+            ..self
+        }
+    }
+
+    pub fn with_lockout(self, lockout: usize) -> Self {
+        Self { lockout, ..self }
+    }
+}
+
+/// The compounded rate of an accrual period with its growth factor and year fraction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CompoundedAccrual {
+    /// the annualized compounded rate $(\prod_i (1 + r_i d_i / D) - 1) D / \sum_i d_i$, with the days
+    /// $d_i$ of the observation period under the observation shift
+    pub rate: f64,
+    /// the growth factor $\prod_i (1 + r_i d_i / D)$
+    pub growth_factor: f64,
+    /// the year fraction of the interest period, over which the interest accrues
+    pub year_fraction: f64,
+}
+
+impl CompoundedAccrual {
+    /// The interest of the notional for the period.
+    pub fn interest(&self, notional: f64) -> f64 {
+        notional * self.rate * self.year_fraction
+    }
+}
+
+/// The published fixings of an overnight index (e.g. SOFR, ESTR) per business day with the number
+/// of calendar days until the next business day, e.g. 3 for a Friday.
+/// See https://en.wikipedia.org/wiki/Overnight_rate
+#[derive(Clone, Debug, PartialEq)]
+pub struct OvernightFixings {
+    rates: Vec<f64>,
+    day_counts: Vec<u32>,
+}
+
+impl OvernightFixings {
+    pub fn new(rates: Vec<f64>, day_counts: Vec<u32>) -> Result<Self, PricingError> {
+        PricingError::check(
+            rates.len() == day_counts.len(),
+            "number of day counts",
+            day_counts.len() as f64,
+        )?;
+        if let Some(days) = day_counts.iter().find(|days| **days == 0) {
+            return Err(PricingError::InvalidParameter(format!(
+                "day count = {days}"
+            )));
+        }
+        Ok(Self { rates, day_counts })
+    }
+
+    pub fn nr_business_days(&self) -> usize {
+        self.rates.len()
+    }
+
+    /// The compounded rate of the interest period of the business days `start..end`,
+    /// which fails if the observations before `start` required by the lookback are missing.
+    pub fn compound(
+        &self,
+        start: usize,
+        end: usize,
+        convention: &CompoundingConvention,
+    ) -> Result<CompoundedAccrual, PricingError> {
+        PricingError::check(start < end, "period end", end as f64)?;
+        if end > self.nr_business_days() || start < convention.lookback {
+            return Err(PricingError::MissingMarketData(format!(
+                "overnight fixings for business days {}..{end}",
+                start.saturating_sub(convention.lookback)
+            )));
+        }
+        // the last day of the period before the lockout
+        let last_observed = (end - 1).saturating_sub(convention.lockout).max(start);
+        let days_per_year = convention.basis.days_per_year();
+
+        let (mut growth_factor, mut nr_weighted_days, mut nr_interest_days) = (1.0, 0, 0);
+        for day in start..end {
+            let observation = day.min(last_observed) - convention.lookback;
+            let weight = if convention.observation_shift {
+                self.day_counts[day - convention.lookback]
+            } else {
+                self.day_counts[day]
+            };
+            growth_factor *= 1.0 + self.rates[observation] * weight as f64 / days_per_year;
+            nr_weighted_days += weight;
+            nr_interest_days += self.day_counts[day];
+        }
+        Ok(CompoundedAccrual {
+            rate: (growth_factor - 1.0) / convention.basis.year_fraction(nr_weighted_days),
+            growth_factor,
+            year_fraction: convention.basis.year_fraction(nr_interest_days),
+        })
+    }
+}
+
+/// The forward compounded rate $(P(t_s) / P(t_e) - 1) / \tau$ of a future period, which the daily
+/// compounding of the curve's overnight forwards replicates, for the year fraction $\tau$ of the period.
+pub fn projected_compounded_rate(
+    curve: &impl YieldCurve,
+    start_time: f64,
+    end_time: f64,
+    year_fraction: f64,
+) -> f64 {
+    (curve.discount_factor(start_time) / curve.discount_factor(end_time) - 1.0) / year_fraction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curves::yield_curve::FlatCurve;
+    use assert_approx_eq::assert_approx_eq;
+
+    /// Two weeks of fixings from a Monday.
+    fn fixings() -> OvernightFixings {
+        let rates = (0..10).map(|day| 0.05 + 0.001 * day as f64).collect();
+        let day_counts = (0..10)
+            .map(|day| if day % 5 == 4 { 3 } else { 1 })
+            .collect();
+        OvernightFixings::new(rates, day_counts).unwrap()
+    }
+
+    #[test]
+    fn compounded_in_arrears() {
+        let fixings = fixings();
+        let convention = CompoundingConvention::new(DayCountBasis::Act360);
+
+        // Wednesday to Tuesday, over the weekend
+        let accrual = fixings.compound(2, 6, &convention).unwrap();
+        let growth = (1.0 + 0.052 / 360.0)
+            * (1.0 + 0.053 / 360.0)
+            * (1.0 + 0.054 * 3.0 / 360.0)
+            * (1.0 + 0.055 / 360.0);
+        assert_approx_eq!(accrual.growth_factor, growth, 1e-15);
+        assert_approx_eq!(accrual.year_fraction, 6.0 / 360.0, 1e-15);
+        assert_approx_eq!(accrual.rate, (growth - 1.0) * 60.0, 1e-12);
+        assert_approx_eq!(accrual.interest(1e6), 1e6 * (growth - 1.0), 1e-6);
+
+        // a lookback of two days observes Monday to Thursday, weighted by the interest period's days
+        let lookback = fixings
+            .compound(2, 6, &convention.with_lookback(2))
+            .unwrap();
+        let growth = (1.0 + 0.050 / 360.0)
+            * (1.0 + 0.051 / 360.0)
+            * (1.0 + 0.052 * 3.0 / 360.0)
+            * (1.0 + 0.053 / 360.0);
+        assert_approx_eq!(lookback.growth_factor, growth, 1e-15);
+
+        // with the observation shift, the weekend moves with the observed rates
+        let shifted = fixings
+            .compound(4, 8, &convention.with_lookback(2).with_observation_shift())
+            .unwrap();
+        let growth = (1.0 + 0.052 / 360.0)
+            * (1.0 + 0.053 / 360.0)
+            * (1.0 + 0.054 * 3.0 / 360.0)
+            * (1.0 + 0.055 / 360.0);
+        assert_approx_eq!(shifted.growth_factor, growth, 1e-15);
+
+        // Monday to Wednesday observes Thursday to Monday: the rate is annualized by the five days of
+        // the observation period, while the interest accrues over the three days of the interest period
+        let shifted = fixings
+            .compound(5, 8, &convention.with_lookback(2).with_observation_shift())
+            .unwrap();
+        let growth = (1.0 + 0.053 / 360.0) * (1.0 + 0.054 * 3.0 / 360.0) * (1.0 + 0.055 / 360.0);
+        assert_approx_eq!(shifted.growth_factor, growth, 1e-15);
+        assert_approx_eq!(shifted.rate, (growth - 1.0) * 360.0 / 5.0, 1e-12);
+        assert_approx_eq!(shifted.year_fraction, 3.0 / 360.0, 1e-15);
+        assert_approx_eq!(
+            shifted.interest(1e6),
+            1e6 * (growth - 1.0) * 3.0 / 5.0,
+            1e-6
+        );
+
+        // the last two days repeat the rate of the day before the lockout
+        let lockout = fixings.compound(2, 6, &convention.with_lockout(2)).unwrap();
+        let growth = (1.0 + 0.052 / 360.0)
+            * (1.0 + 0.053 / 360.0)
+            * (1.0 + 0.053 * 3.0 / 360.0)
+            * (1.0 + 0.053 / 360.0);
+        assert_approx_eq!(lockout.growth_factor, growth, 1e-15);
+
+        assert!(matches!(
+            fixings.compound(1, 6, &convention.with_lookback(2)),
+            Err(PricingError::MissingMarketData(_))
+        ));
+        assert!(fixings.compound(5, 11, &convention).is_err());
+    }
+
+    #[test]
+    fn projection_matches_daily_compounding() {
+        let rate = 0.04;
+        let curve = FlatCurve::new(rate);
+        // daily compounding of the curve's overnight forwards over 90 days
+        let daily_forward = ((rate / 365.0).exp() - 1.0) * 365.0;
+        let fixings = OvernightFixings::new(vec![daily_forward; 90], vec![1; 90]).unwrap();
+        let realized = fixings
+            .compound(
+                0,
+                90,
+                &CompoundingConvention::new(DayCountBasis::Act365Fixed),
+            )
+            .unwrap();
+        let projected = projected_compounded_rate(&curve, 0.5, 0.5 + 90.0 / 365.0, 90.0 / 365.0);
+        assert_approx_eq!(realized.rate, projected, 1e-12);
+    }
+}
@@ -0,0 +1,204 @@
+use crate::curves::cashflows::{Cashflow, Leg, Swap};
+use crate::curves::yield_curve::YieldCurve;
+
+/// The excess return over the funding of the value over the horizon, assuming the curve is unchanged,
+/// i.e. each zero rate stays the same for its time to maturity as time passes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RollDownReport {
+    /// the coupon income accrued over the horizon less the funding cost of the (dirty) value today
+    pub carry: f64,
+    /// the change of the clean value rolling down the unchanged curve, including repaid principal
+    pub roll_down: f64,
+}
+
+impl RollDownReport {
+    pub fn total(&self) -> f64 {
+        self.carry + self.roll_down
+    }
+}
+
+/// The curve seen from the horizon h in the times from today, $P_h(t) = P(0, t - h)$ for $t > h$.
+struct RolledCurve<'a, C: YieldCurve> {
+    curve: &'a C,
+    horizon: f64,
+}
+
+impl<C: YieldCurve> YieldCurve for RolledCurve<'_, C> {
+    fn zero_rate(&self, t: f64) -> f64 {
+        let time_to_maturity = t - self.horizon;
+        self.curve.zero_rate(time_to_maturity) * time_to_maturity / t
+    }
+}
+
+/// Carry and roll-down of curve instruments over a horizon (one month by default), where the funding
+/// cost is the growth of today's value at the zero rate of the horizon, and coupons fixing before the
+/// horizon are projected on today's curve.
+/// See https://en.wikipedia.org/wiki/Rolldown_return
+#[derive(Clone, Copy, Debug)]
+pub struct RollDown {
+    /// the horizon in years
+    horizon: f64,
+}
+
+impl Default for RollDown {
+    fn default() -> Self {
+        Self {
+            horizon: 1.0 / 12.0,
+        }
+    }
+}
+
+impl RollDown {
+    pub fn new(horizon: f64) -> Self {
+        assert!(horizon > 0.0);
+        Self { horizon }
+    }
+
+    /// The interest of the coupon accrued until the time s.
+    fn accrued(cashflow: &Cashflow, amount: f64, s: f64) -> f64 {
+        match cashflow {
+            Cashflow::Fixed { .. } => 0.0,
+            Cashflow::FixedCoupon {
+                accrual_start,
+                accrual_end,
+                ..
+            }
+            | Cashflow::FloatingCoupon {
+                accrual_start,
+                accrual_end,
+                ..
+            } => {
+                let accrued_time = (s.min(*accrual_end) - accrual_start).max(0.0);
+                amount * accrued_time / (accrual_end - accrual_start)
+            }
+        }
+    }
+
+    /// The carry and roll-down of the leg, e.g. a bond with its redemption.
+    pub fn leg(
+        &self,
+        leg: &Leg,
+        projection: &impl YieldCurve,
+        discount: &impl YieldCurve,
+    ) -> RollDownReport {
+        let h = self.horizon;
+        let rolled_projection = RolledCurve {
+            curve: projection,
+            horizon: h,
+        };
+        let (mut value, mut clean_value, mut clean_value_rolled, mut income) = (0.0, 0.0, 0.0, 0.0);
+        for cashflow in leg.cashflows() {
+            let t = cashflow.payment_time();
+            if t <= 0.0 {
+                continue;
+            }
+            let amount = cashflow.amount(projection);
+            let accrued_today = Self::accrued(cashflow, amount, 0.0);
+            value += amount * discount.discount_factor(t);
+            clean_value += amount * discount.discount_factor(t) - accrued_today;
+            income += Self::accrued(cashflow, amount, h) - accrued_today;
+            if t > h {
+                let rolled_amount = match cashflow {
+                    Cashflow::FloatingCoupon { accrual_start, .. } if *accrual_start >= h => {
+                        cashflow.amount(&rolled_projection)
+                    }
+                    _ => amount,
+                };
+                clean_value_rolled += rolled_amount * discount.discount_factor(t - h)
+                    - Self::accrued(cashflow, rolled_amount, h);
+            } else if let Cashflow::Fixed { .. } = cashflow {
+                // the repaid principal
+                clean_value_rolled += amount;
+            }
+        }
+        let funding = value * (1.0 / discount.discount_factor(h) - 1.0);
+        RollDownReport {
+            carry: income - funding,
+            roll_down: clean_value_rolled - clean_value,
+        }
+    }
+
+    /// The carry and roll-down of the swap, receiving the one of the receive leg less the one of the pay leg.
+    pub fn swap(
+        &self,
+        swap: &Swap,
+        projection: &impl YieldCurve,
+        discount: &impl YieldCurve,
+    ) -> RollDownReport {
+        let receive = self.leg(&swap.receive_leg, projection, discount);
+        let pay = self.leg(&swap.pay_leg, projection, discount);
+        RollDownReport {
+            carry: receive.carry - pay.carry,
+            roll_down: receive.roll_down - pay.roll_down,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curves::yield_curve::{FlatCurve, InterpolatedCurve};
+    use crate::numerics::interpolation::InterpolationMethod;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn upward_curve() -> InterpolatedCurve {
+        InterpolatedCurve::new(
+            vec![0.5, 1.0, 2.0, 5.0, 10.0],
+            vec![0.01, 0.015, 0.02, 0.03, 0.035],
+            InterpolationMethod::LogLinear,
+        )
+    }
+
+    #[test]
+    fn bond_roll_down() {
+        // a zero-coupon bond has no income and rolls down to the discount factor of the shorter maturity
+        let curve = upward_curve();
+        let zero = Leg::default().with_payment(5.0, 100.0);
+        let report = RollDown::new(1.0).leg(&zero, &curve, &curve);
+        let (p1, p4, p5) = (
+            curve.discount_factor(1.0),
+            curve.discount_factor(4.0),
+            curve.discount_factor(5.0),
+        );
+        assert_approx_eq!(report.carry, -100.0 * p5 * (1.0 / p1 - 1.0), 1e-10);
+        assert_approx_eq!(report.roll_down, 100.0 * (p4 - p5), 1e-10);
+        assert!(report.total() > 0.0);
+
+        // on a flat curve the unchanged curve is the forward curve, which earns the funding only
+        let flat = FlatCurve::new(0.03);
+        let bond = Leg::fixed(100.0, 0.05, 5.0, 2).with_payment(5.0, 100.0);
+        let report = RollDown::new(0.25).leg(&bond, &flat, &flat);
+        assert_approx_eq!(report.total(), 0.0, 1e-10);
+        let funding = bond.present_value(&flat, &flat) * ((0.03_f64 * 0.25).exp() - 1.0);
+        assert_approx_eq!(report.carry, 100.0 * 0.05 * 0.25 - funding, 1e-10);
+        assert_eq!(RollDown::default().horizon, 1.0 / 12.0);
+    }
+
+    #[test]
+    fn swap_carry_on_upward_curve() {
+        // a par receiver swap earns the fixed rate above the first fixing and rolls down to lower par rates
+        let curve = upward_curve();
+        let payer = Swap::payer(1_000_000.0, 0.0, 5.0, 1, 4);
+        let par_rate = payer.par_rate(&curve, &curve);
+        let receiver = Swap::new(
+            Leg::fixed(1_000_000.0, par_rate, 5.0, 1),
+            Leg::floating(1_000_000.0, 0.0, 5.0, 4),
+        );
+        let report = RollDown::new(0.25).swap(&receiver, &curve, &curve);
+        let first_fixing = (curve.discount_factor(0.0) / curve.discount_factor(0.25) - 1.0) / 0.25;
+        assert_approx_eq!(
+            report.carry,
+            1_000_000.0 * (par_rate - first_fixing) * 0.25,
+            1e-6
+        );
+        assert!(report.carry > 0.0 && report.roll_down > 0.0);
+
+        // paying the legs reverses the report
+        let payer_report = RollDown::new(0.25).swap(
+            &Swap::new(receiver.pay_leg.clone(), receiver.receive_leg.clone()),
+            &curve,
+            &curve,
+        );
+        assert_approx_eq!(payer_report.total(), -report.total(), 1e-8);
+    }
+}
@@ -0,0 +1,160 @@
+use crate::numerics::interpolation::{Extrapolation, InterpolationMethod, Interpolator1D};
+
+/// Term structure of interest rates with continuously compounded zero rates,
+/// where times are measured in years from today.
+/// See https://en.wikipedia.org/wiki/Yield_curve
+pub trait YieldCurve {
+    /// The continuously compounded zero rate $r(t)$ for the maturity t.
+    fn zero_rate(&self, t: f64) -> f64;
+
+    /// The discount factor $P(0, t) = e^{-r(t) t}$.
+    fn discount_factor(&self, t: f64) -> f64 {
+        (-self.zero_rate(t) * t).exp()
+    }
+
+    /// The discount factors of the times, e.g. the dates of a simulation grid.
+    fn discount_factors(&self, times: &[f64]) -> Vec<f64> {
+        times.iter().map(|t| self.discount_factor(*t)).collect()
+    }
+
+    /// The last maturity the curve is built for, beyond which its rates are extrapolated,
+    /// or None if it covers all maturities, e.g. a parametric curve.
+    fn horizon(&self) -> Option<f64> {
+        None
+    }
+
+    /// The maturities of the pillars the curve is built from, e.g. to record the node values a pricing
+    /// used, or none for a parametric curve.
+    fn pillars(&self) -> Vec<f64> {
+        Vec::new()
+    }
+
+    /// The continuously compounded forward rate between $t_1 < t_2$.
+    fn forward_rate(&self, t1: f64, t2: f64) -> f64 {
+        assert!(t1 < t2);
+        (self.zero_rate(t2) * t2 - self.zero_rate(t1) * t1) / (t2 - t1)
+    }
+}
+
+/// Constant zero rate for all maturities.
+#[derive(Clone, Copy, Debug)]
+pub struct FlatCurve {
+    rate: f64,
+}
+
+impl FlatCurve {
+    pub fn new(rate: f64) -> Self {
+        Self { rate }
+    }
+}
+
+impl YieldCurve for FlatCurve {
+    fn zero_rate(&self, _t: f64) -> f64 {
+        self.rate
+    }
+}
+
+/// Zero rate curve interpolated between pillar maturities. Log-linear interpolation is applied to the
+/// discount factors (piecewise constant forward rates) and extrapolated with the last forward rate,
+/// the other methods interpolate the zero rates with flat extrapolation.
+#[derive(Clone, Debug)]
+pub struct InterpolatedCurve {
+    interpolator: Interpolator1D,
+    method: InterpolationMethod,
+}
+
+impl InterpolatedCurve {
+    pub fn new(times: Vec<f64>, zero_rates: Vec<f64>, method: InterpolationMethod) -> Self {
+        assert!(times.iter().all(|t| *t > 0.0));
+        let interpolator = match method {
+            InterpolationMethod::LogLinear => {
+                // the pillar at t = 0 pins the short end to P(0, 0) = 1
+                let discount_factors = std::iter::once(1.0)
+                    .chain(times.iter().zip(&zero_rates).map(|(t, r)| (-r * t).exp()))
+                    .collect();
+                let times = std::iter::once(0.0).chain(times).collect();
+                Interpolator1D::new(times, discount_factors, method)
+                    .with_extrapolation(Extrapolation::Linear)
+            }
+            _ => Interpolator1D::new(times, zero_rates, method),
+        };
+        Self {
+            interpolator,
+            method,
+        }
+    }
+}
+
+impl YieldCurve for InterpolatedCurve {
+    fn zero_rate(&self, t: f64) -> f64 {
+        match self.method {
+            InterpolationMethod::LogLinear => {
+                // the short rate for t = 0 is the first forward rate
+                let t = t.max(1e-8);
+                -self.discount_factor(t).ln() / t
+            }
+            _ => self.interpolator.value(t).unwrap_or_default(),
+        }
+    }
+
+    fn discount_factor(&self, t: f64) -> f64 {
+        match self.method {
+            InterpolationMethod::LogLinear => self.interpolator.value(t).unwrap_or_default(),
+            _ => (-self.zero_rate(t) * t).exp(),
+        }
+    }
+
+    fn horizon(&self) -> Option<f64> {
+        self.interpolator.xs().last().copied()
+    }
+
+    fn pillars(&self) -> Vec<f64> {
+        // without the pillar at t = 0 of the log-linear interpolation
+        self.interpolator
+            .xs()
+            .iter()
+            .copied()
+            .filter(|t| *t > 0.0)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn flat_curve() {
+        let curve = FlatCurve::new(0.03);
+        assert_eq!(curve.discount_factor(0.0), 1.0);
+        assert_approx_eq!(curve.discount_factor(2.0), (-0.06_f64).exp(), 1e-15);
+        assert_approx_eq!(curve.forward_rate(1.0, 3.0), 0.03, 1e-15);
+    }
+
+    #[test]
+    fn interpolated_curve() {
+        let times = vec![1.0, 2.0, 5.0];
+        let rates = vec![0.01, 0.02, 0.03];
+        let linear =
+            InterpolatedCurve::new(times.clone(), rates.clone(), InterpolationMethod::Linear);
+        assert_approx_eq!(linear.zero_rate(1.5), 0.015, 1e-15);
+        assert_approx_eq!(linear.zero_rate(10.0), 0.03, 1e-15);
+        assert_eq!(linear.horizon(), Some(5.0));
+
+        let log_linear = InterpolatedCurve::new(times, rates, InterpolationMethod::LogLinear);
+        assert_approx_eq!(log_linear.zero_rate(2.0), 0.02, 1e-14);
+        // constant forward rates between the pillars
+        assert_approx_eq!(
+            log_linear.forward_rate(2.5, 3.0),
+            log_linear.forward_rate(3.0, 5.0),
+            1e-12
+        );
+        assert_approx_eq!(
+            log_linear.forward_rate(6.0, 7.0),
+            (0.15 - 0.04) / 3.0,
+            1e-12
+        );
+        assert_approx_eq!(log_linear.zero_rate(0.5), 0.01, 1e-12);
+    }
+}
@@ -0,0 +1,310 @@
+use crate::common::results::PricingError;
+use crate::curves::cashflows::Leg;
+use crate::curves::{SurvivalCurve, YieldCurve};
+use crate::lattice::trinomial_tree::{log_spacing, BranchProbabilities};
+
+/// Convertible bond paying annual coupons of `coupon_rate` on the `face_value` in `coupons_per_year`
+/// instalments, convertible at any time into `conversion_ratio` shares, and optionally callable by
+/// the issuer or puttable by the holder at any time at the given (clean) prices.
+/// See https://en.wikipedia.org/wiki/Convertible_bond
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConvertibleBond {
+    pub face_value: f64,
+    pub coupon_rate: f64,
+    pub coupons_per_year: usize,
+    /// the maturity in years
+    pub maturity: f64,
+    pub conversion_ratio: f64,
+    pub call_price: Option<f64>,
+    pub put_price: Option<f64>,
+}
+
+/// The value of a convertible bond split into the equity component, discounted risk-free,
+/// and the cash (debt) component, discounted with the issuer's credit spread.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConvertibleBondValue {
+    pub equity_component: f64,
+    pub debt_component: f64,
+}
+
+impl ConvertibleBondValue {
+    pub fn value(&self) -> f64 {
+        self.equity_component + self.debt_component
+    }
+}
+
+impl ConvertibleBond {
+    /// The convertible bond, or the invalid parameter: a non-positive or non-finite maturity, no
+    /// coupons per year, or a negative conversion ratio.
+    pub fn try_new(
+        face_value: f64,
+        coupon_rate: f64,
+        coupons_per_year: usize,
+        maturity: f64,
+        conversion_ratio: f64,
+    ) -> Result<Self, PricingError> {
+        PricingError::check(maturity > 0.0 && maturity.is_finite(), "maturity", maturity)?;
+        PricingError::check(
+            coupons_per_year > 0,
+            "coupons per year",
+            coupons_per_year as f64,
+        )?;
+        PricingError::check(
+            conversion_ratio >= 0.0,
+            "conversion ratio",
+            conversion_ratio,
+        )?;
+        Ok(Self {
+            face_value,
+            coupon_rate,
+            coupons_per_year,
+            maturity,
+            conversion_ratio,
+            call_price: None,
+            put_price: None,
+        })
+    }
+
+    pub fn with_call(self, call_price: f64) -> Self {
+        Self {
+            call_price: Some(call_price),
+            ..self
+        }
+    }
+
+    pub fn with_put(self, put_price: f64) -> Self {
+        Self {
+            put_price: Some(put_price),
+            ..self
+        }
+    }
+
+    /// The fixed coupon leg paid on the `face_value` until the maturity.
+    pub fn coupon_leg(&self) -> Leg {
+        Leg::fixed(
+            self.face_value,
+            self.coupon_rate,
+            self.maturity,
+            self.coupons_per_year,
+        )
+    }
+
+    /// Applies the holder's conversion and put and the issuer's call to the holding value at a node.
+    fn exercise(&self, spot: f64, holding: ConvertibleBondValue) -> ConvertibleBondValue {
+        let conversion_value = self.conversion_ratio * spot;
+        let mut value = holding.value();
+        if let Some(call_price) = self.call_price {
+            value = value.min(call_price);
+        }
+        if let Some(put_price) = self.put_price {
+            value = value.max(put_price);
+        }
+
+        if conversion_value >= value {
+            ConvertibleBondValue {
+                equity_component: conversion_value,
+                debt_component: 0.0,
+            }
+        } else if value != holding.value() {
+            // called or put for cash
+            ConvertibleBondValue {
+                equity_component: 0.0,
+                debt_component: value,
+            }
+        } else {
+            holding
+        }
+    }
+
+    /// Prices the convertible bond by the Tsiveriotis-Fernandes approach on a trinomial tree of the
+    /// stock with the forward rates of the `discount_curve`, where the cash component is discounted
+    /// additionally by the credit spread $(1 - R) \lambda(t)$ of the issuer's `survival_curve`.
+    /// Fails for zero steps. See https://doi.org/10.3905/jfi.1998.408240
+    pub fn price(
+        &self,
+        spot: f64,
+        vola: f64,
+        discount_curve: &impl YieldCurve,
+        survival_curve: &impl SurvivalCurve,
+        recovery_rate: f64,
+        nr_steps: usize,
+    ) -> Result<ConvertibleBondValue, PricingError> {
+        PricingError::check(nr_steps > 0, "nr steps", nr_steps as f64)?;
+        let dt = self.maturity / nr_steps as f64;
+        let dx = log_spacing(vola, dt);
+        let spot_at = |step: usize, j: usize| spot * ((j as f64 - step as f64) * dx).exp();
+
+        // the fixed coupons do not depend on the projection curve
+        let coupon_flows = self.coupon_leg().projected_cashflows(discount_curve);
+        let coupons_between = |t1: f64, t2: f64| -> f64 {
+            coupon_flows
+                .iter()
+                .filter(|(t, _)| *t > t1 + 1e-9 && *t <= t2 + 1e-9)
+                .map(|(_, amount)| amount)
+                .sum()
+        };
+
+        let redemption = self.face_value + coupons_between(self.maturity - dt, self.maturity);
+        let mut values: Vec<ConvertibleBondValue> = (0..=2 * nr_steps)
+            .map(|j| {
+                let conversion_value = self.conversion_ratio * spot_at(nr_steps, j);
+                if conversion_value > redemption {
+                    ConvertibleBondValue {
+                        equity_component: conversion_value,
+                        debt_component: 0.0,
+                    }
+                } else {
+                    ConvertibleBondValue {
+                        equity_component: 0.0,
+                        debt_component: redemption,
+                    }
+                }
+            })
+            .collect();
+
+        for step in (0..nr_steps).rev() {
+            let (t, t_next) = (step as f64 * dt, (step + 1) as f64 * dt);
+            let disc_factor =
+                discount_curve.discount_factor(t_next) / discount_curve.discount_factor(t);
+            let survival = survival_curve.survival_probability(t_next)
+                / survival_curve.survival_probability(t);
+            let risky_disc_factor = disc_factor * survival.powf(1.0 - recovery_rate);
+            let probabilities =
+                BranchProbabilities::new(-disc_factor.ln() / dt - vola.powi(2) / 2.0, vola, dt, dx);
+            let coupons = if step > 0 {
+                coupons_between(t - dt, t)
+            } else {
+                0.0
+            };
+
+            let equity: Vec<f64> = values.iter().map(|v| v.equity_component).collect();
+            let debt: Vec<f64> = values.iter().map(|v| v.debt_component).collect();
+            values = (0..=2 * step)
+                .map(|j| {
+                    let holding = ConvertibleBondValue {
+                        equity_component: disc_factor * probabilities.expectation(&equity, j),
+                        debt_component: risky_disc_factor * probabilities.expectation(&debt, j)
+                            + coupons,
+                    };
+                    self.exercise(spot_at(step, j), holding)
+                })
+                .collect();
+        }
+        Ok(values[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::{BlackScholesMerton, OptionPrice};
+    use crate::common::models::DerivativeParameter;
+    use crate::curves::hazard_rate::PiecewiseHazardCurve;
+    use crate::curves::yield_curve::FlatCurve;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn straight_risky_bond() {
+        // without conversion, the bond is a risky coupon bond
+        let bond = ConvertibleBond::try_new(100.0, 0.04, 4, 5.0, 0.0).unwrap();
+        let (rate, hazard_rate, recovery_rate) = (0.03, 0.02, 0.4);
+        let value = bond
+            .price(
+                50.0,
+                0.3,
+                &FlatCurve::new(rate),
+                &PiecewiseHazardCurve::flat(hazard_rate),
+                recovery_rate,
+                200,
+            )
+            .unwrap();
+        assert_eq!(value.equity_component, 0.0);
+
+        let risky_rate = rate + (1.0 - recovery_rate) * hazard_rate;
+        let expected = (1..=20)
+            .map(|k| 1.0 * (-risky_rate * k as f64 / 4.0).exp())
+            .sum::<f64>()
+            + 100.0 * (-risky_rate * 5.0).exp();
+        assert_approx_eq!(value.value(), expected, 1e-9);
+    }
+
+    #[test]
+    fn short_first_coupon_period() {
+        // the coupons follow the payment schedule of the fixed leg, counted back from the maturity
+        let bond = ConvertibleBond::try_new(100.0, 0.04, 1, 1.5, 0.0).unwrap();
+        let curve = FlatCurve::new(0.03);
+        let value = bond
+            .price(
+                50.0,
+                0.3,
+                &curve,
+                &PiecewiseHazardCurve::flat(0.0),
+                0.4,
+                150,
+            )
+            .unwrap();
+        let expected =
+            bond.coupon_leg().present_value(&curve, &curve) + 100.0 * curve.discount_factor(1.5);
+        assert_approx_eq!(value.value(), expected, 1e-9);
+    }
+
+    #[test]
+    fn zero_coupon_convertible_without_credit_risk() {
+        // the convertible is the bond plus a call on the conversion ratio shares,
+        // which is not exercised early without dividends
+        let bond = ConvertibleBond::try_new(100.0, 0.0, 1, 2.0, 1.0).unwrap();
+        let value = bond
+            .price(
+                90.0,
+                0.25,
+                &FlatCurve::new(0.03),
+                &PiecewiseHazardCurve::flat(0.0),
+                0.4,
+                400,
+            )
+            .unwrap();
+        let call =
+            BlackScholesMerton::call(&DerivativeParameter::new(90.0, 100.0, 2.0, 0.03, 0.25));
+        assert_approx_eq!(value.value(), 100.0 * (-0.06_f64).exp() + call, 2e-2);
+    }
+
+    #[test]
+    fn call_and_put_features() {
+        let bond = ConvertibleBond::try_new(100.0, 0.03, 2, 5.0, 1.0).unwrap();
+        let price = |bond: ConvertibleBond| {
+            bond.price(
+                95.0,
+                0.3,
+                &FlatCurve::new(0.03),
+                &PiecewiseHazardCurve::flat(0.03),
+                0.4,
+                200,
+            )
+            .unwrap()
+            .value()
+        };
+        let plain = price(bond);
+        assert!(plain > 95.0);
+        assert!(price(bond.with_call(110.0)) < plain);
+        assert!(price(bond.with_put(100.0)) > plain);
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        assert!(ConvertibleBond::try_new(100.0, 0.03, 2, 0.0, 1.0).is_err());
+        assert!(ConvertibleBond::try_new(100.0, 0.03, 0, 5.0, 1.0).is_err());
+        assert!(ConvertibleBond::try_new(100.0, 0.03, 2, 5.0, -1.0).is_err());
+        let bond = ConvertibleBond::try_new(100.0, 0.03, 2, 5.0, 1.0).unwrap();
+        assert!(matches!(
+            bond.price(
+                95.0,
+                0.3,
+                &FlatCurve::new(0.03),
+                &PiecewiseHazardCurve::flat(0.03),
+                0.4,
+                0
+            ),
+            Err(PricingError::InvalidParameter(_))
+        ));
+    }
+}
@@ -0,0 +1,164 @@
+use crate::common::results::PricingError;
+use crate::lattice::trinomial_tree::{log_spacing, BranchProbabilities};
+
+/// Employee stock option in the Hull-White model: the option can not be exercised during the
+/// `vesting_period`, is exercised voluntarily once the stock reaches `exercise_multiple` times the
+/// strike, and employees leave at the annual `exit_rate`, forfeiting unvested options and exercising
+/// vested options which are in the money.
+/// See https://en.wikipedia.org/wiki/Employee_stock_option#Valuation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EmployeeStockOption {
+    pub strike: f64,
+    /// the maturity in years
+    pub maturity: f64,
+    /// the vesting period in years
+    pub vesting_period: f64,
+    pub exercise_multiple: f64,
+    pub exit_rate: f64,
+}
+
+impl EmployeeStockOption {
+    /// The option, or the invalid parameter: a non-positive or non-finite maturity, a vesting period
+    /// outside of [0, maturity], an exercise multiple below 1 (infinite for no voluntary exercise), or a
+    /// negative or non-finite exit rate.
+    pub fn try_new(
+        strike: f64,
+        maturity: f64,
+        vesting_period: f64,
+        exercise_multiple: f64,
+        exit_rate: f64,
+    ) -> Result<Self, PricingError> {
+        PricingError::check(maturity > 0.0 && maturity.is_finite(), "maturity", maturity)?;
+        PricingError::check(
+            (0.0..=maturity).contains(&vesting_period),
+            "vesting period",
+            vesting_period,
+        )?;
+        PricingError::check(
+            exercise_multiple >= 1.0,
+            "exercise multiple",
+            exercise_multiple,
+        )?;
+        PricingError::check(
+            exit_rate >= 0.0 && exit_rate.is_finite(),
+            "exit rate",
+            exit_rate,
+        )?;
+        Ok(Self {
+            strike,
+            maturity,
+            vesting_period,
+            exercise_multiple,
+            exit_rate,
+        })
+    }
+
+    /// The value of the option on a trinomial tree of the stock with continuous `dividend_yield`, which
+    /// fails for zero steps.
+    pub fn price(
+        &self,
+        spot: f64,
+        rfr: f64,
+        dividend_yield: f64,
+        vola: f64,
+        nr_steps: usize,
+    ) -> Result<f64, PricingError> {
+        PricingError::check(nr_steps > 0, "nr steps", nr_steps as f64)?;
+        let dt = self.maturity / nr_steps as f64;
+        let dx = log_spacing(vola, dt);
+        let probabilities =
+            BranchProbabilities::new(rfr - dividend_yield - vola.powi(2) / 2.0, vola, dt, dx);
+        let disc_factor = (-rfr * dt).exp();
+        let exit_probability = 1.0 - (-self.exit_rate * dt).exp();
+
+        let spot_at = |step: usize, j: usize| spot * ((j as f64 - step as f64) * dx).exp();
+        let intrinsic = |spot: f64| (spot - self.strike).max(0.0);
+
+        let mut values: Vec<f64> = (0..=2 * nr_steps)
+            .map(|j| intrinsic(spot_at(nr_steps, j)))
+            .collect();
+
+        for step in (0..nr_steps).rev() {
+            let is_vested = step as f64 * dt >= self.vesting_period - 1e-12;
+            for j in 0..=2 * step {
+                let spot = spot_at(step, j);
+                let continuation = disc_factor * probabilities.expectation(&values, j);
+                values[j] = if !is_vested {
+                    (1.0 - exit_probability) * continuation
+                } else if spot >= self.exercise_multiple * self.strike {
+                    intrinsic(spot)
+                } else {
+                    (1.0 - exit_probability) * continuation + exit_probability * intrinsic(spot)
+                };
+            }
+            values.truncate(2 * step + 1);
+        }
+        Ok(values[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::{BlackScholesMerton, OptionPrice};
+    use crate::common::models::DerivativeParameter;
+    use assert_approx_eq::assert_approx_eq;
+
+    const NR_STEPS: usize = 300;
+
+    #[test]
+    fn limit_of_american_call() {
+        // without exits and voluntary exercise, the option is an american call,
+        // which is not exercised early without dividends
+        let eso = EmployeeStockOption::try_new(50.0, 5.0, 0.0, f64::INFINITY, 0.0).unwrap();
+        let value = eso.price(50.0, 0.05, 0.0, 0.3, NR_STEPS).unwrap();
+        let call = BlackScholesMerton::call(&DerivativeParameter::new(50.0, 50.0, 5.0, 0.05, 0.3));
+        assert_approx_eq!(value, call, 2e-2);
+    }
+
+    #[test]
+    fn employee_behaviour_reduces_value() {
+        let (spot, rfr, dividend_yield, vola) = (50.0, 0.05, 0.01, 0.3);
+        let price = |eso: EmployeeStockOption| {
+            eso.price(spot, rfr, dividend_yield, vola, NR_STEPS)
+                .unwrap()
+        };
+
+        let american =
+            price(EmployeeStockOption::try_new(50.0, 10.0, 3.0, f64::INFINITY, 0.0).unwrap());
+        let with_multiple = price(EmployeeStockOption::try_new(50.0, 10.0, 3.0, 2.0, 0.0).unwrap());
+        let with_exits = price(EmployeeStockOption::try_new(50.0, 10.0, 3.0, 2.0, 0.05).unwrap());
+        assert!(with_multiple < american);
+        assert!(with_exits < with_multiple);
+
+        // unvested options are forfeited on exits
+        let vested = price(EmployeeStockOption::try_new(50.0, 10.0, 0.0, 2.0, 0.05).unwrap());
+        assert!(with_exits < vested);
+        assert!(with_exits > 0.0);
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        for (maturity, vesting_period, exercise_multiple, exit_rate) in [
+            (0.0, 0.0, 2.0, 0.0),
+            (5.0, 6.0, 2.0, 0.0),
+            (5.0, -1.0, 2.0, 0.0),
+            (5.0, 1.0, 0.5, 0.0),
+            (5.0, 1.0, f64::NAN, 0.0),
+            (5.0, 1.0, 2.0, -0.1),
+        ] {
+            assert!(matches!(
+                EmployeeStockOption::try_new(
+                    50.0,
+                    maturity,
+                    vesting_period,
+                    exercise_multiple,
+                    exit_rate
+                ),
+                Err(PricingError::InvalidParameter(_))
+            ));
+        }
+        let eso = EmployeeStockOption::try_new(50.0, 5.0, 1.0, 2.0, 0.05).unwrap();
+        assert!(eso.price(50.0, 0.05, 0.0, 0.3, 0).is_err());
+    }
+}
@@ -0,0 +1,3 @@
+pub mod convertible_bond;
+pub mod employee_stock_option;
+pub mod trinomial_tree;
@@ -0,0 +1,250 @@
+use crate::common::models::{DerivativeParameter, ExerciseType};
+
+/// A cash dividend of `amount` paid at `time` (in years).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DiscreteDividend {
+    pub time: f64,
+    pub amount: f64,
+}
+
+impl DiscreteDividend {
+    pub fn new(time: f64, amount: f64) -> Self {
+        assert!(time > 0.0 && amount >= 0.0);
+        Self { time, amount }
+    }
+}
+
+/// How the tree accounts for discrete cash dividends, such that the nodes recombine.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DividendTreatment {
+    /// the tree models the spot less the present value of the dividends until expiration,
+    /// which is added back at the nodes
+    Escrowed,
+    /// the tree models the spot including the dividends, and the nodes after a dividend
+    /// are shifted down by the dividend compounded to the node's time
+    ShiftedNodes,
+}
+
+/// The log-price spacing $\sigma \sqrt{3 \Delta t}$ of the trinomial nodes.
+pub(crate) fn log_spacing(vola: f64, dt: f64) -> f64 {
+    vola * (3.0 * dt).sqrt()
+}
+
+/// The probabilities of the down, middle and up branches matching the first two moments
+/// of the log-price increments with drift `nu` over the time step `dt`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct BranchProbabilities {
+    down: f64,
+    mid: f64,
+    up: f64,
+}
+
+impl BranchProbabilities {
+    pub(crate) fn new(nu: f64, vola: f64, dt: f64, dx: f64) -> Self {
+        let second_moment = (vola.powi(2) * dt + (nu * dt).powi(2)) / dx.powi(2);
+        let up = 0.5 * (second_moment + nu * dt / dx);
+        let down = 0.5 * (second_moment - nu * dt / dx);
+        Self {
+            down,
+            mid: 1.0 - up - down,
+            up,
+        }
+    }
+
+    /// The expectation of the values at the successor nodes j, j + 1 and j + 2 of the next step.
+    #[inline]
+    pub(crate) fn expectation(&self, next_values: &[f64], j: usize) -> f64 {
+        self.down * next_values[j] + self.mid * next_values[j + 1] + self.up * next_values[j + 2]
+    }
+}
+
+/// Trinomial tree for European and American options on a stock paying discrete cash dividends.
+/// See https://en.wikipedia.org/wiki/Trinomial_tree
+#[derive(Clone, Debug)]
+pub struct TrinomialTree {
+    nr_steps: usize,
+    dividends: Vec<DiscreteDividend>,
+    dividend_treatment: DividendTreatment,
+}
+
+impl TrinomialTree {
+    pub fn new(nr_steps: usize) -> Self {
+        assert!(nr_steps > 0);
+        Self {
+            nr_steps,
+            dividends: Vec::new(),
+            dividend_treatment: DividendTreatment::Escrowed,
+        }
+    }
+
+    pub fn with_dividends(
+        self,
+        dividends: Vec<DiscreteDividend>,
+        dividend_treatment: DividendTreatment,
+    ) -> Self {
+        Self {
+            dividends,
+            dividend_treatment,
+            ..self
+        }
+    }
+
+    /// The present value at time t of the dividends paid in (t, maturity].
+    fn dividends_value(&self, t: f64, maturity: f64, rfr: f64) -> f64 {
+        self.dividends
+            .iter()
+            .filter(|d| t < d.time && d.time <= maturity)
+            .map(|d| d.amount * (-rfr * (d.time - t)).exp())
+            .sum()
+    }
+
+    /// The value at time t of the dividends paid in (0, t], compounded to t.
+    fn paid_dividends_value(&self, t: f64, rfr: f64) -> f64 {
+        self.dividends
+            .iter()
+            .filter(|d| d.time <= t)
+            .map(|d| d.amount * (rfr * (t - d.time)).exp())
+            .sum()
+    }
+
+    /// The option price, where `is_american` allows for early exercise at each step.
+    pub fn price(
+        &self,
+        dp: &DerivativeParameter,
+        exercise_type: &ExerciseType,
+        is_american: bool,
+    ) -> f64 {
+        if dp.is_expired() {
+            return dp.intrinsic_value(exercise_type);
+        }
+        let maturity = dp.time_to_expiration;
+        let dt = maturity / self.nr_steps as f64;
+        let dx = log_spacing(dp.vola, dt);
+        let probabilities =
+            BranchProbabilities::new(dp.rfr - dp.vola.powi(2) / 2.0, dp.vola, dt, dx);
+        let disc_factor = (-dp.rfr * dt).exp();
+
+        let tree_spot = match self.dividend_treatment {
+            DividendTreatment::Escrowed => {
+                dp.asset_price - self.dividends_value(0.0, maturity, dp.rfr)
+            }
+            DividendTreatment::ShiftedNodes => dp.asset_price,
+        };
+        let spot_at = |step: usize, j: usize| {
+            let t = step as f64 * dt;
+            let tree_price = tree_spot * ((j as f64 - step as f64) * dx).exp();
+            match self.dividend_treatment {
+                DividendTreatment::Escrowed => {
+                    tree_price + self.dividends_value(t, maturity, dp.rfr)
+                }
+                DividendTreatment::ShiftedNodes => {
+                    (tree_price - self.paid_dividends_value(t, dp.rfr)).max(0.0)
+                }
+            }
+        };
+        let payoff = |spot: f64| exercise_type.payoff(spot, dp.strike);
+
+        let mut values: Vec<f64> = (0..=2 * self.nr_steps)
+            .map(|j| payoff(spot_at(self.nr_steps, j)))
+            .collect();
+
+        for step in (0..self.nr_steps).rev() {
+            for j in 0..=2 * step {
+                let continuation = disc_factor * probabilities.expectation(&values, j);
+                values[j] = if is_american {
+                    continuation.max(payoff(spot_at(step, j)))
+                } else {
+                    continuation
+                };
+            }
+            values.truncate(2 * step + 1);
+        }
+        values[0]
+    }
+
+    /// The price by Richardson extrapolation $2 V_{2N} - V_N$ of the prices with N and 2N steps,
+    /// removing the leading $O(1/N)$ error term.
+    /// See https://en.wikipedia.org/wiki/Richardson_extrapolation
+    pub fn price_extrapolated(
+        &self,
+        dp: &DerivativeParameter,
+        exercise_type: &ExerciseType,
+        is_american: bool,
+    ) -> f64 {
+        let fine_tree = Self {
+            nr_steps: 2 * self.nr_steps,
+            ..self.clone()
+        };
+        2.0 * fine_tree.price(dp, exercise_type, is_american)
+            - self.price(dp, exercise_type, is_american)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::{BlackScholesMerton, OptionPrice};
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn european_converges_to_black_scholes() {
+        let dp = DerivativeParameter::new(300.0, 250.0, 1.0, 0.03, 0.15);
+        let tree = TrinomialTree::new(200);
+
+        let call = tree.price(&dp, &ExerciseType::Call, false);
+        assert_approx_eq!(call, BlackScholesMerton::call(&dp), 1e-2);
+        let put = tree.price(&dp, &ExerciseType::Put, false);
+        assert_approx_eq!(put, BlackScholesMerton::put(&dp), 1e-2);
+
+        let dp = DerivativeParameter::new(100.0, 100.0, 1.0, 0.05, 0.2);
+        let error =
+            (tree.price(&dp, &ExerciseType::Put, false) - BlackScholesMerton::put(&dp)).abs();
+        let extrapolated_error = (tree.price_extrapolated(&dp, &ExerciseType::Put, false)
+            - BlackScholesMerton::put(&dp))
+        .abs();
+        assert!(extrapolated_error < error);
+    }
+
+    #[test]
+    fn american_early_exercise() {
+        let dp = DerivativeParameter::new(100.0, 110.0, 1.0, 0.05, 0.2);
+        let tree = TrinomialTree::new(200);
+
+        let american_put = tree.price_extrapolated(&dp, &ExerciseType::Put, true);
+        assert!(american_put > BlackScholesMerton::put(&dp) + 0.1);
+        // reference value of a 4000 steps binomial (CRR) tree
+        assert_approx_eq!(american_put, 11.9728, 1e-2);
+
+        // without dividends, the american call is never exercised early
+        let american_call = tree.price(&dp, &ExerciseType::Call, true);
+        assert_approx_eq!(
+            american_call,
+            tree.price(&dp, &ExerciseType::Call, false),
+            1e-12
+        );
+    }
+
+    #[test]
+    fn discrete_dividends() {
+        let dp = DerivativeParameter::new(100.0, 100.0, 1.0, 0.05, 0.2);
+        let dividend = DiscreteDividend::new(0.5, 3.0);
+        let escrowed =
+            TrinomialTree::new(400).with_dividends(vec![dividend], DividendTreatment::Escrowed);
+
+        // the escrowed european call is the Black-Scholes call on the spot less the dividend's present value
+        let escrowed_dp =
+            DerivativeParameter::new(100.0 - 3.0 * (-0.05_f64 * 0.5).exp(), 100.0, 1.0, 0.05, 0.2);
+        let european_call = escrowed.price(&dp, &ExerciseType::Call, false);
+        assert_approx_eq!(european_call, BlackScholesMerton::call(&escrowed_dp), 1e-2);
+
+        // early exercise before the dividend has value
+        let american_call = escrowed.price(&dp, &ExerciseType::Call, true);
+        assert!(american_call > european_call);
+
+        // both treatments agree up to the different volatility specification
+        let shifted = TrinomialTree::new(400)
+            .with_dividends(vec![dividend], DividendTreatment::ShiftedNodes)
+            .price(&dp, &ExerciseType::Call, true);
+        assert_approx_eq!(shifted, american_call, 0.3);
+    }
+}
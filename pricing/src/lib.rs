@@ -1,5 +1,12 @@
 pub mod analytic;
 pub mod common;
+pub mod curves;
+pub mod lattice;
+pub mod numerics;
+pub mod pde;
+pub mod prelude;
+pub mod real_options;
+#[cfg(feature = "mc")]
 pub mod simulation;
 
 extern crate ndarray;
@@ -0,0 +1,320 @@
+//! Solvers of the sparse linear systems of finite difference schemes: the Thomas algorithm for
+//! tridiagonal matrices, e.g. the implicit steps of one-dimensional schemes and of each direction of
+//! ADI schemes, and the LU factorization of banded matrices, e.g. of higher order stencils.
+//! The factorizations do not pivot, which is stable for diagonally dominant matrices such as the
+//! implicit operators $I - \theta \Delta t A$ of diffusion equations.
+
+use ndarray::Array2;
+
+/// The tridiagonal matrix of the subdiagonal `lower[i] = a_{i, i-1}` (with `lower[0]` unused), the
+/// diagonal and the superdiagonal `upper[i] = a_{i, i+1}` (with `upper[n-1]` unused).
+/// See https://en.wikipedia.org/wiki/Tridiagonal_matrix
+#[derive(Clone, Debug, PartialEq)]
+pub struct TridiagonalMatrix {
+    pub lower: Vec<f64>,
+    pub diagonal: Vec<f64>,
+    pub upper: Vec<f64>,
+}
+
+impl TridiagonalMatrix {
+    pub fn new(lower: Vec<f64>, diagonal: Vec<f64>, upper: Vec<f64>) -> Self {
+        assert!(lower.len() == diagonal.len() && upper.len() == diagonal.len());
+        Self {
+            lower,
+            diagonal,
+            upper,
+        }
+    }
+
+    pub fn identity(n: usize) -> Self {
+        Self::new(vec![0.0; n], vec![1.0; n], vec![0.0; n])
+    }
+
+    pub fn dim(&self) -> usize {
+        self.diagonal.len()
+    }
+
+    /// The matrix $a A + b I$, e.g. the implicit operator $I - \theta \Delta t A$ of a scheme.
+    pub fn scaled_shifted(&self, a: f64, b: f64) -> Self {
+        let scale = |values: &[f64]| values.iter().map(|x| a * x).collect();
+        Self::new(
+            scale(&self.lower),
+            self.diagonal.iter().map(|x| a * x + b).collect(),
+            scale(&self.upper),
+        )
+    }
+
+    /// The product $A x$.
+    pub fn multiply(&self, x: &[f64]) -> Vec<f64> {
+        let n = self.dim();
+        assert_eq!(x.len(), n);
+        (0..n)
+            .map(|i| {
+                let mut value = self.diagonal[i] * x[i];
+                if i > 0 {
+                    value += self.lower[i] * x[i - 1];
+                }
+                if i + 1 < n {
+                    value += self.upper[i] * x[i + 1];
+                }
+                value
+            })
+            .collect()
+    }
+
+    /// The solution x of $A x = b$ by the Thomas algorithm in O(n), or None on a vanishing pivot.
+    /// See https://en.wikipedia.org/wiki/Tridiagonal_matrix_algorithm
+    pub fn solve(&self, rhs: &[f64]) -> Option<Vec<f64>> {
+        let n = self.dim();
+        assert_eq!(rhs.len(), n);
+        if n == 0 {
+            return Some(Vec::new());
+        }
+        let mut diagonal = self.diagonal.clone();
+        let mut x = rhs.to_vec();
+        for i in 1..n {
+            if diagonal[i - 1] == 0.0 {
+                return None;
+            }
+            let factor = self.lower[i] / diagonal[i - 1];
+            diagonal[i] -= factor * self.upper[i - 1];
+            x[i] -= factor * x[i - 1];
+        }
+        if diagonal[n - 1] == 0.0 {
+            return None;
+        }
+        x[n - 1] /= diagonal[n - 1];
+        for i in (0..n - 1).rev() {
+            x[i] = (x[i] - self.upper[i] * x[i + 1]) / diagonal[i];
+        }
+        Some(x)
+    }
+}
+
+/// The square matrix with `nr_lower` subdiagonals and `nr_upper` superdiagonals, stored by rows as
+/// `bands[[i, j - i + nr_lower]]` for $a_{ij}$ within the band.
+/// See https://en.wikipedia.org/wiki/Band_matrix
+#[derive(Clone, Debug, PartialEq)]
+pub struct BandedMatrix {
+    nr_lower: usize,
+    nr_upper: usize,
+    bands: Array2<f64>,
+}
+
+impl BandedMatrix {
+    pub fn zeros(n: usize, nr_lower: usize, nr_upper: usize) -> Self {
+        Self {
+            nr_lower,
+            nr_upper,
+            bands: Array2::zeros((n, nr_lower + nr_upper + 1)),
+        }
+    }
+
+    /// The band of the dense matrix, ignoring its entries outside of the band.
+    pub fn from_dense(matrix: &Array2<f64>, nr_lower: usize, nr_upper: usize) -> Self {
+        let n = matrix.nrows();
+        assert_eq!(matrix.shape(), &[n, n]);
+        let mut banded = Self::zeros(n, nr_lower, nr_upper);
+        for i in 0..n {
+            for j in banded.columns(i) {
+                banded.set(i, j, matrix[[i, j]]);
+            }
+        }
+        banded
+    }
+
+    pub fn dim(&self) -> usize {
+        self.bands.nrows()
+    }
+
+    /// The columns of the band in the row.
+    fn columns(&self, row: usize) -> std::ops::Range<usize> {
+        row.saturating_sub(self.nr_lower)..(row + self.nr_upper + 1).min(self.dim())
+    }
+
+    fn in_band(&self, i: usize, j: usize) -> bool {
+        j + self.nr_lower >= i && j <= i + self.nr_upper
+    }
+
+    /// The entry $a_{ij}$, zero outside of the band.
+    pub fn get(&self, i: usize, j: usize) -> f64 {
+        if !self.in_band(i, j) {
+            return 0.0;
+        }
+        self.bands[[i, j + self.nr_lower - i]]
+    }
+
+    pub fn set(&mut self, i: usize, j: usize, value: f64) {
+        assert!(self.in_band(i, j), "({i}, {j}) outside of the band");
+        self.bands[[i, j + self.nr_lower - i]] = value;
+    }
+
+    /// The product $A x$.
+    pub fn multiply(&self, x: &[f64]) -> Vec<f64> {
+        assert_eq!(x.len(), self.dim());
+        (0..self.dim())
+            .map(|i| self.columns(i).map(|j| self.get(i, j) * x[j]).sum())
+            .collect()
+    }
+
+    /// The LU factorization without pivoting in $O(n p q)$ for p lower and q upper diagonals, whose
+    /// factors keep the band, or None on a vanishing pivot.
+    /// See https://en.wikipedia.org/wiki/LU_decomposition
+    pub fn lu(&self) -> Option<BandedLu> {
+        let n = self.dim();
+        let mut factors = self.clone();
+        for k in 0..n {
+            let pivot = factors.get(k, k);
+            if pivot == 0.0 {
+                return None;
+            }
+            for i in k + 1..(k + self.nr_lower + 1).min(n) {
+                let factor = factors.get(i, k) / pivot;
+                factors.set(i, k, factor);
+                for j in k + 1..(k + self.nr_upper + 1).min(n) {
+                    let value = factors.get(i, j) - factor * factors.get(k, j);
+                    factors.set(i, j, value);
+                }
+            }
+        }
+        Some(BandedLu { factors })
+    }
+
+    /// The solution x of $A x = b$, or None on a vanishing pivot.
+    pub fn solve(&self, rhs: &[f64]) -> Option<Vec<f64>> {
+        Some(self.lu()?.solve(rhs))
+    }
+}
+
+impl From<&TridiagonalMatrix> for BandedMatrix {
+    fn from(matrix: &TridiagonalMatrix) -> Self {
+        let n = matrix.dim();
+        let mut banded = Self::zeros(n, 1, 1);
+        for i in 0..n {
+            banded.set(i, i, matrix.diagonal[i]);
+            if i > 0 {
+                banded.set(i, i - 1, matrix.lower[i]);
+            }
+            if i + 1 < n {
+                banded.set(i, i + 1, matrix.upper[i]);
+            }
+        }
+        banded
+    }
+}
+
+/// The LU factors of a banded matrix in its band: the unit lower triangular L below and the upper
+/// triangular U on and above the diagonal, which solve systems of several right hand sides, e.g. of
+/// the time steps of a scheme with a constant operator.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BandedLu {
+    factors: BandedMatrix,
+}
+
+impl BandedLu {
+    /// The solution x of $L U x = b$ by forward and back substitution.
+    pub fn solve(&self, rhs: &[f64]) -> Vec<f64> {
+        let factors = &self.factors;
+        let n = factors.dim();
+        assert_eq!(rhs.len(), n);
+        let mut x = rhs.to_vec();
+        for i in 0..n {
+            let partial: f64 = (i.saturating_sub(factors.nr_lower)..i)
+                .map(|j| factors.get(i, j) * x[j])
+                .sum();
+            x[i] -= partial;
+        }
+        for i in (0..n).rev() {
+            let partial: f64 = (i + 1..(i + factors.nr_upper + 1).min(n))
+                .map(|j| factors.get(i, j) * x[j])
+                .sum();
+            x[i] = (x[i] - partial) / factors.get(i, i);
+        }
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numerics::least_squares::solve_linear_system;
+    use assert_approx_eq::assert_approx_eq;
+    use ndarray::Array1;
+
+    /// A diagonally dominant matrix with the bandwidths.
+    fn dense_banded(n: usize, nr_lower: usize, nr_upper: usize) -> Array2<f64> {
+        Array2::from_shape_fn((n, n), |(i, j)| {
+            if i == j {
+                10.0 + i as f64
+            } else if j + nr_lower >= i && j <= i + nr_upper {
+                ((i * 7 + j * 3) % 5) as f64 - 2.0
+            } else {
+                0.0
+            }
+        })
+    }
+
+    #[test]
+    fn tridiagonal_solver() {
+        let n = 50;
+        let dense = dense_banded(n, 1, 1);
+        let matrix = TridiagonalMatrix::new(
+            (0..n)
+                .map(|i| if i > 0 { dense[[i, i - 1]] } else { 0.0 })
+                .collect(),
+            (0..n).map(|i| dense[[i, i]]).collect(),
+            (0..n)
+                .map(|i| if i + 1 < n { dense[[i, i + 1]] } else { 0.0 })
+                .collect(),
+        );
+        let rhs: Vec<f64> = (0..n).map(|i| (i as f64).sin()).collect();
+        let x = matrix.solve(&rhs).unwrap();
+        let reference = solve_linear_system(&dense, &Array1::from(rhs.clone())).unwrap();
+        for (xi, ri) in x.iter().zip(&reference) {
+            assert_approx_eq!(xi, ri, 1e-12);
+        }
+        for (ax, b) in matrix.multiply(&x).iter().zip(&rhs) {
+            assert_approx_eq!(ax, b, 1e-12);
+        }
+        let banded = BandedMatrix::from(&matrix);
+        for (xi, yi) in x.iter().zip(banded.solve(&rhs).unwrap()) {
+            assert_approx_eq!(xi, yi, 1e-12);
+        }
+
+        // the implicit operator of the heat equation $u_t = u_{xx}$
+        let laplacian = TridiagonalMatrix::new(vec![1.0; 5], vec![-2.0; 5], vec![1.0; 5]);
+        let implicit = laplacian.scaled_shifted(-0.5, 1.0);
+        assert_eq!(implicit.diagonal, vec![2.0; 5]);
+        assert_eq!(
+            TridiagonalMatrix::identity(3).solve(&[1.0, 2.0, 3.0]),
+            Some(vec![1.0, 2.0, 3.0])
+        );
+        let singular = TridiagonalMatrix::new(vec![0.0; 2], vec![0.0, 1.0], vec![1.0; 2]);
+        assert_eq!(singular.solve(&[1.0, 1.0]), None);
+        assert_eq!(TridiagonalMatrix::identity(0).solve(&[]), Some(vec![]));
+    }
+
+    #[test]
+    fn banded_solver() {
+        for (nr_lower, nr_upper) in [(2, 2), (1, 3), (0, 2), (3, 0)] {
+            let n = 30;
+            let dense = dense_banded(n, nr_lower, nr_upper);
+            let banded = BandedMatrix::from_dense(&dense, nr_lower, nr_upper);
+            assert_eq!(banded.get(0, n - 1), 0.0);
+            let rhs: Vec<f64> = (0..n).map(|i| 1.0 + i as f64).collect();
+            let lu = banded.lu().unwrap();
+            let x = lu.solve(&rhs);
+            let reference = solve_linear_system(&dense, &Array1::from(rhs.clone())).unwrap();
+            for (xi, ri) in x.iter().zip(&reference) {
+                assert_approx_eq!(xi, ri, 1e-12);
+            }
+            for (ax, b) in banded.multiply(&x).iter().zip(&rhs) {
+                assert_approx_eq!(ax, b, 1e-11);
+            }
+        }
+        let mut singular = BandedMatrix::zeros(2, 1, 1);
+        singular.set(0, 1, 1.0);
+        singular.set(1, 0, 1.0);
+        assert!(singular.solve(&[1.0, 1.0]).is_none());
+    }
+}
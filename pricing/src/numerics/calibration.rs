@@ -0,0 +1,614 @@
+use ndarray::{Array1, Array2};
+use rand::Rng;
+use rayon::prelude::*;
+
+use std::hash::Hash;
+
+use crate::common::results::PricingError;
+use crate::numerics::evaluation_cache::{
+    content_hash, CacheStatistics, ContentKey, EvaluationCache,
+};
+use crate::numerics::least_squares::solve_linear_system;
+use crate::numerics::solvers::{nelder_mead, SolverOptions};
+
+/// The box constraint of a model parameter, enforced by a smooth transform from the unconstrained
+/// optimization variable u:
+/// '''math
+/// x = a + e^u, \quad x = b - e^u, \quad x = a + \frac{b - a}{1 + e^{-u}}
+/// '''
+/// for a lower bound a, an upper bound b and both.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bound {
+    Unbounded,
+    Lower(f64),
+    Upper(f64),
+    Interval(f64, f64),
+}
+
+impl Bound {
+    /// The parameter of the unconstrained variable.
+    pub fn to_constrained(&self, u: f64) -> f64 {
+        match *self {
+            Bound::Unbounded => u,
+            Bound::Lower(a) => a + u.exp(),
+            Bound::Upper(b) => b - u.exp(),
+            Bound::Interval(a, b) => a + (b - a) / (1.0 + (-u).exp()),
+        }
+    }
+
+    /// The unconstrained variable of a parameter strictly within the bounds.
+    pub fn to_unconstrained(&self, x: f64) -> f64 {
+        match *self {
+            Bound::Unbounded => x,
+            Bound::Lower(a) => (x - a).ln(),
+            Bound::Upper(b) => (b - x).ln(),
+            Bound::Interval(a, b) => {
+                let p = (x - a) / (b - a);
+                (p / (1.0 - p)).ln()
+            }
+        }
+    }
+
+    /// The parameter at the probability p in (0, 1), i.e. the transform of the logit of p.
+    /// Uniform on an interval, and spreading over the orders of magnitude of half-bounded parameters.
+    fn at_probability(&self, p: f64) -> f64 {
+        self.to_constrained((p / (1.0 - p)).ln())
+    }
+}
+
+/// A local optimum of the objective found from a start point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LocalOptimum {
+    pub parameters: Vec<f64>,
+    pub objective: f64,
+    pub start: Vec<f64>,
+    pub converged: bool,
+    pub function_evaluations: usize,
+}
+
+/// The distinct local optima of a multi-start calibration, sorted by their objective values.
+#[derive(Clone, Debug)]
+pub struct CalibrationResult {
+    pub optima: Vec<LocalOptimum>,
+    pub nr_starts: usize,
+    /// whether the starts stopped before the maximal number as the best objective reached a plateau
+    pub terminated_early: bool,
+    /// the lookups of the cached objective values, None without cache
+    pub cache_statistics: Option<CacheStatistics>,
+}
+
+impl CalibrationResult {
+    pub fn best(&self) -> &LocalOptimum {
+        &self.optima[0]
+    }
+
+    /// Whether all optima with objectives within the tolerance of the best one agree with its parameters
+    /// up to the relative tolerance. Distinct near-optimal parameter sets indicate that the data do not
+    /// identify the parameters, e.g. a trade-off between the vol of vol and the correlation.
+    pub fn is_identifiable(&self, objective_tolerance: f64, parameter_tolerance: f64) -> bool {
+        let best = self.best();
+        self.optima
+            .iter()
+            .filter(|o| o.objective - best.objective <= objective_tolerance)
+            .all(|o| relative_distance(&o.parameters, &best.parameters) <= parameter_tolerance)
+    }
+}
+
+fn relative_distance(x: &[f64], y: &[f64]) -> f64 {
+    x.iter()
+        .zip(y)
+        .map(|(a, b)| (a - b).abs() / a.abs().max(b.abs()).max(1.0))
+        .fold(0.0, f64::max)
+}
+
+/// Latin hypercube sample of n points in the unit cube: each coordinate hits each of the n strata once.
+/// See https://en.wikipedia.org/wiki/Latin_hypercube_sampling
+pub fn latin_hypercube(rng: &mut impl Rng, nr_points: usize, dim: usize) -> Vec<Vec<f64>> {
+    let mut points = vec![vec![0.0; dim]; nr_points];
+    for k in 0..dim {
+        let mut strata: Vec<usize> = (0..nr_points).collect();
+        for i in (1..nr_points).rev() {
+            strata.swap(i, rng.gen_range(0..=i));
+        }
+        for (point, stratum) in points.iter_mut().zip(strata) {
+            point[k] = (stratum as f64 + rng.gen_range(0.0..1.0)) / nr_points as f64;
+        }
+    }
+    points
+}
+
+/// Multi-start calibration of bounded parameters: the starts are Latin hypercube points, which are
+/// optimized in parallel batches by Nelder-Mead in the unconstrained variables of the bounds.
+/// The starts terminate once a batch does not improve the best objective by more than the plateau tolerance.
+#[derive(Clone, Debug)]
+pub struct Calibrator {
+    bounds: Vec<Bound>,
+    nr_starts: usize,
+    batch_size: usize,
+    plateau_tolerance: Option<f64>,
+    seed_nr: u64,
+    step: f64,
+    options: SolverOptions,
+    /// the capacity of the cache with the content hash of the configuration of the evaluations
+    cache: Option<(usize, u64)>,
+}
+
+impl Calibrator {
+    pub fn new(bounds: Vec<Bound>) -> Self {
+        Self {
+            bounds,
+            nr_starts: 16,
+            batch_size: 8,
+            plateau_tolerance: None,
+            seed_nr: 0,
+            step: 0.5,
+            options: SolverOptions::new(1e-8, 1e-12, 2_000),
+            cache: None,
+        }
+    }
+
+    pub fn with_starts(mut self, nr_starts: usize, batch_size: usize) -> Self {
+        assert!(nr_starts > 0 && batch_size > 0);
+        self.nr_starts = nr_starts;
+        self.batch_size = batch_size;
+        self
+    }
+
+    pub fn with_plateau_termination(mut self, tolerance: f64) -> Self {
+        self.plateau_tolerance = Some(tolerance);
+        self
+    }
+
+    pub fn with_seed(mut self, seed_nr: u64) -> Self {
+        self.seed_nr = seed_nr;
+        self
+    }
+
+    /// The options of the local optimizations, with the initial simplex step in the unconstrained variables.
+    pub fn with_options(mut self, options: SolverOptions, step: f64) -> Self {
+        self.options = options;
+        self.step = step;
+        self
+    }
+
+    /// Caches up to the capacity of (least recently used) evaluations by the content of their
+    /// parameters and the configuration of the evaluations, e.g. the time grid, the number of paths and
+    /// the seed of expensive simulated objectives revisited by the local optimizations, and for quotes
+    /// the model values of the quotes at the same parameters.
+    pub fn with_cache(mut self, capacity: usize, config: &impl Hash) -> Result<Self, PricingError> {
+        PricingError::check(capacity > 0, "cache capacity", capacity as f64)?;
+        self.cache = Some((capacity, content_hash(config)));
+        Ok(self)
+    }
+
+    fn evaluation_cache<K: Clone + Eq + Hash, V: Clone>(&self) -> Option<EvaluationCache<K, V>> {
+        self.cache
+            .and_then(|(capacity, _)| EvaluationCache::new(capacity).ok())
+    }
+
+    fn cache_config(&self) -> u64 {
+        self.cache.map_or(0, |(_, config)| config)
+    }
+
+    fn to_constrained(&self, u: &[f64]) -> Vec<f64> {
+        self.bounds
+            .iter()
+            .zip(u)
+            .map(|(b, u)| b.to_constrained(*u))
+            .collect()
+    }
+
+    fn optimize(
+        &self,
+        objective: &(impl Fn(&[f64]) -> f64 + Sync),
+        start: Vec<f64>,
+    ) -> LocalOptimum {
+        let u0: Vec<f64> = self
+            .bounds
+            .iter()
+            .zip(&start)
+            .map(|(b, x)| b.to_unconstrained(*x))
+            .collect();
+        let solution = nelder_mead(
+            |u| objective(&self.to_constrained(u)),
+            &u0,
+            self.step,
+            &self.options,
+        );
+        LocalOptimum {
+            parameters: self.to_constrained(&solution.x),
+            objective: solution.value,
+            start,
+            converged: solution.converged,
+            function_evaluations: solution.function_evaluations,
+        }
+    }
+
+    /// The local optima of the objective, where optima agreeing within the relative
+    /// distance `distinct_tolerance` are merged into the better one.
+    pub fn calibrate<SeedRng>(
+        &self,
+        objective: impl Fn(&[f64]) -> f64 + Sync,
+        distinct_tolerance: f64,
+    ) -> CalibrationResult
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        let cache = self.evaluation_cache();
+        let objective = |x: &[f64]| match &cache {
+            Some(cache) => {
+                cache.get_or_insert_with(ContentKey::new(x, self.cache_config()), || objective(x))
+            }
+            None => objective(x),
+        };
+        let mut rng = SeedRng::seed_from_u64(self.seed_nr);
+        let starts: Vec<Vec<f64>> = latin_hypercube(&mut rng, self.nr_starts, self.bounds.len())
+            .into_iter()
+            .map(|p| {
+                self.bounds
+                    .iter()
+                    .zip(p)
+                    .map(|(b, p)| b.at_probability(p))
+                    .collect()
+            })
+            .collect();
+
+        let mut optima: Vec<LocalOptimum> = Vec::new();
+        let mut nr_starts = 0;
+        let mut terminated_early = false;
+        for batch in starts.chunks(self.batch_size) {
+            let previous_best = optima
+                .iter()
+                .map(|o| o.objective)
+                .fold(f64::INFINITY, f64::min);
+            let batch_optima: Vec<LocalOptimum> = batch
+                .par_iter()
+                .map(|start| self.optimize(&objective, start.clone()))
+                .collect();
+            nr_starts += batch.len();
+            optima.extend(batch_optima);
+
+            let best = optima
+                .iter()
+                .map(|o| o.objective)
+                .fold(f64::INFINITY, f64::min);
+            if let Some(tolerance) = self.plateau_tolerance {
+                if previous_best - best <= tolerance && nr_starts < starts.len() {
+                    terminated_early = true;
+                    break;
+                }
+            }
+        }
+
+        optima.sort_by(|a, b| a.objective.total_cmp(&b.objective));
+        let mut distinct: Vec<LocalOptimum> = Vec::new();
+        for optimum in optima {
+            if distinct
+                .iter()
+                .all(|d| relative_distance(&d.parameters, &optimum.parameters) > distinct_tolerance)
+            {
+                distinct.push(optimum);
+            }
+        }
+        CalibrationResult {
+            optima: distinct,
+            nr_starts,
+            terminated_early,
+            cache_statistics: cache.map(|cache| cache.statistics()),
+        }
+    }
+}
+
+/// A market quote to calibrate to, e.g. an option price or implied vol at the maturity and strike.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CalibrationQuote {
+    pub maturity: f64,
+    pub strike: f64,
+    pub market_value: f64,
+    pub weight: f64,
+}
+
+impl CalibrationQuote {
+    pub fn new(maturity: f64, strike: f64, market_value: f64) -> Self {
+        Self {
+            maturity,
+            strike,
+            market_value,
+            weight: 1.0,
+        }
+    }
+
+    pub fn with_weight(mut self, weight: f64) -> Self {
+        assert!(weight > 0.0);
+        self.weight = weight;
+        self
+    }
+}
+
+/// The fit of the calibrated model to a quote.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuoteFit {
+    pub quote: CalibrationQuote,
+    pub model_value: f64,
+    /// the model minus the market value
+    pub residual: f64,
+    /// the residual times the square root of the weight
+    pub weighted_residual: f64,
+}
+
+/// The calibration to quotes with the diagnostics of the best fit.
+#[derive(Clone, Debug)]
+pub struct CalibrationReport {
+    pub calibration: CalibrationResult,
+    pub quote_fits: Vec<QuoteFit>,
+    /// the root mean squared residual over all quotes
+    pub rmse: f64,
+    /// the root mean squared residuals per maturity, in increasing order of the maturities
+    pub rmse_by_maturity: Vec<(f64, f64)>,
+    /// the asymptotic standard errors of the parameters, None if the fit does not determine them
+    pub parameter_standard_errors: Option<Vec<f64>>,
+    /// the asymptotic covariance of the parameters, None if the fit does not determine them
+    pub parameter_covariance: Option<Array2<f64>>,
+    /// the lookups of the cached model values of the quotes, None without cache
+    pub model_cache_statistics: Option<CacheStatistics>,
+}
+
+impl CalibrationReport {
+    pub fn parameters(&self) -> &[f64] {
+        &self.calibration.best().parameters
+    }
+
+    /// The quote with the largest absolute weighted residual.
+    pub fn worst_fit(&self) -> Option<&QuoteFit> {
+        self.quote_fits.iter().max_by(|a, b| {
+            a.weighted_residual
+                .abs()
+                .total_cmp(&b.weighted_residual.abs())
+        })
+    }
+}
+
+fn root_mean_square<'a>(residuals: impl Iterator<Item = &'a f64>) -> f64 {
+    let (sum, n) = residuals.fold((0.0, 0), |(sum, n), r| (sum + r * r, n + 1));
+    (sum / n as f64).sqrt()
+}
+
+/// The asymptotic covariance of the parameters of nonlinear weighted least squares
+/// '''math
+/// s^2 (J^T J)^{-1}, \quad s^2 = \frac{\sum_i r_i^2}{n - p}
+/// '''
+/// with the Jacobian J of the weighted residuals r by central differences.
+/// See https://en.wikipedia.org/wiki/Non-linear_least_squares#Parameter_errors,_confidence_limits,_residuals_etc.
+fn parameter_covariance(
+    weighted_residuals: impl Fn(&[f64]) -> Vec<f64>,
+    parameters: &[f64],
+) -> Option<Array2<f64>> {
+    let residuals = weighted_residuals(parameters);
+    let (n, p) = (residuals.len(), parameters.len());
+    if n <= p {
+        return None;
+    }
+    let mut jacobian = Array2::zeros((n, p));
+    for k in 0..p {
+        let h = 1e-6 * parameters[k].abs().max(1.0);
+        let mut up = parameters.to_vec();
+        let mut down = parameters.to_vec();
+        up[k] += h;
+        down[k] -= h;
+        for (i, (r_up, r_down)) in weighted_residuals(&up)
+            .into_iter()
+            .zip(weighted_residuals(&down))
+            .enumerate()
+        {
+            jacobian[[i, k]] = (r_up - r_down) / (2.0 * h);
+        }
+    }
+    let information = jacobian.t().dot(&jacobian);
+    let s2 = residuals.iter().map(|r| r * r).sum::<f64>() / (n - p) as f64;
+    let mut covariance = Array2::zeros((p, p));
+    for k in 0..p {
+        let mut unit = Array1::zeros(p);
+        unit[k] = 1.0;
+        let column = solve_linear_system(&information, &unit)?;
+        covariance.column_mut(k).assign(&(s2 * column));
+    }
+    covariance
+        .diag()
+        .iter()
+        .all(|v| *v > 0.0)
+        .then_some(covariance)
+}
+
+impl Calibrator {
+    /// Calibrates the model values of the parameters to the quotes by weighted least squares and
+    /// reports the fit of the best parameters.
+    pub fn calibrate_quotes<SeedRng>(
+        &self,
+        quotes: &[CalibrationQuote],
+        model: impl Fn(&[f64], &CalibrationQuote) -> f64 + Sync,
+        distinct_tolerance: f64,
+    ) -> CalibrationReport
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        let cache = self.evaluation_cache();
+        let model = |x: &[f64], i: usize, q: &CalibrationQuote| match &cache {
+            Some(cache) => cache
+                .get_or_insert_with(ContentKey::new(x, (self.cache_config(), i)), || model(x, q)),
+            None => model(x, q),
+        };
+        let weighted_residuals = |x: &[f64]| -> Vec<f64> {
+            quotes
+                .iter()
+                .enumerate()
+                .map(|(i, q)| q.weight.sqrt() * (model(x, i, q) - q.market_value))
+                .collect()
+        };
+        let calibration = self.calibrate::<SeedRng>(
+            |x| weighted_residuals(x).iter().map(|r| r * r).sum(),
+            distinct_tolerance,
+        );
+
+        let parameters = &calibration.best().parameters;
+        let quote_fits: Vec<QuoteFit> = quotes
+            .iter()
+            .enumerate()
+            .map(|(i, q)| {
+                let model_value = model(parameters, i, q);
+                let residual = model_value - q.market_value;
+                QuoteFit {
+                    quote: *q,
+                    model_value,
+                    residual,
+                    weighted_residual: q.weight.sqrt() * residual,
+                }
+            })
+            .collect();
+
+        let mut maturities: Vec<f64> = quotes.iter().map(|q| q.maturity).collect();
+        maturities.sort_by(f64::total_cmp);
+        maturities.dedup();
+        let rmse_by_maturity = maturities
+            .into_iter()
+            .map(|t| {
+                let rmse = root_mean_square(
+                    quote_fits
+                        .iter()
+                        .filter(|f| f.quote.maturity == t)
+                        .map(|f| &f.residual),
+                );
+                (t, rmse)
+            })
+            .collect();
+
+        let covariance = parameter_covariance(weighted_residuals, parameters);
+        CalibrationReport {
+            rmse: root_mean_square(quote_fits.iter().map(|f| &f.residual)),
+            parameter_standard_errors: covariance
+                .as_ref()
+                .map(|covariance| covariance.diag().mapv(f64::sqrt).to_vec()),
+            parameter_covariance: covariance,
+            calibration,
+            quote_fits,
+            rmse_by_maturity,
+            model_cache_statistics: cache.map(|cache| cache.statistics()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use rand::SeedableRng;
+
+    #[test]
+    fn bound_transforms() {
+        for (bound, x) in [
+            (Bound::Unbounded, -3.0),
+            (Bound::Lower(0.0), 0.04),
+            (Bound::Upper(1.0), -0.5),
+            (Bound::Interval(-1.0, 1.0), 0.7),
+        ] {
+            assert_approx_eq!(bound.to_constrained(bound.to_unconstrained(x)), x, 1e-12);
+        }
+        assert!(Bound::Interval(-1.0, 1.0).to_constrained(50.0) <= 1.0);
+
+        let mut rng = rand_hc::Hc128Rng::seed_from_u64(1);
+        let points = latin_hypercube(&mut rng, 10, 2);
+        for k in 0..2 {
+            let mut strata: Vec<usize> = points.iter().map(|p| (p[k] * 10.0) as usize).collect();
+            strata.sort();
+            assert_eq!(strata, (0..10).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn multi_start_optima() {
+        // a double well with the global minimum at x = 1 and a local one near x = -1
+        let double_well = |x: &[f64]| (x[0] * x[0] - 1.0).powi(2) + 0.1 * (x[0] - 1.0).powi(2);
+        let result = Calibrator::new(vec![Bound::Interval(-2.0, 2.0)])
+            .with_seed(3)
+            .calibrate::<rand_hc::Hc128Rng>(double_well, 1e-3);
+        assert_eq!(result.nr_starts, 16);
+        assert_eq!(result.optima.len(), 2);
+        assert_approx_eq!(result.best().parameters[0], 1.0, 1e-4);
+        assert!(result.optima[1].parameters[0] < -0.9);
+        assert!(result.optima[1].objective > 0.3);
+        assert!(result.is_identifiable(0.1, 1e-3));
+
+        // the bound is active at the optimum
+        let bounded = Calibrator::new(vec![Bound::Lower(0.0), Bound::Unbounded])
+            .with_starts(4, 2)
+            .calibrate::<rand_hc::Hc128Rng>(|x| (x[0] + 1.0).powi(2) + (x[1] - 2.0).powi(2), 1e-2);
+        assert!(bounded.best().parameters[0] > 0.0);
+        assert_approx_eq!(bounded.best().parameters[0], 0.0, 1e-3);
+        assert_approx_eq!(bounded.best().parameters[1], 2.0, 1e-4);
+    }
+
+    #[test]
+    fn plateau_and_identifiability() {
+        // only the product of the parameters is identified
+        let product = |x: &[f64]| (x[0] * x[1] - 1.0).powi(2);
+        let result = Calibrator::new(vec![Bound::Interval(0.1, 10.0); 2])
+            .with_starts(32, 4)
+            .with_plateau_termination(1e-10)
+            .calibrate::<rand_hc::Hc128Rng>(product, 1e-2);
+        assert!(result.terminated_early);
+        assert!(result.nr_starts < 32);
+        assert!(result.best().objective < 1e-10);
+        assert!(!result.is_identifiable(1e-8, 1e-2));
+    }
+
+    #[test]
+    fn quote_report() {
+        // a linear skew a + b (K - 100) / 100 with small perturbations of the market vols
+        let strikes = [80.0, 90.0, 100.0, 110.0, 120.0];
+        let noise = [
+            0.002, -0.001, 0.0, 0.001, -0.002, 0.004, -0.003, 0.001, 0.0, -0.002,
+        ];
+        let quotes: Vec<CalibrationQuote> = [0.5, 1.0]
+            .iter()
+            .flat_map(|t| strikes.iter().map(move |k| (*t, *k)))
+            .zip(noise)
+            .map(|((t, k), e)| CalibrationQuote::new(t, k, 0.2 - 0.1 * (k - 100.0) / 100.0 + e))
+            .collect();
+        let skew = |x: &[f64], q: &CalibrationQuote| x[0] + x[1] * (q.strike - 100.0) / 100.0;
+
+        let report = Calibrator::new(vec![Bound::Lower(0.0), Bound::Unbounded])
+            .with_starts(4, 4)
+            .calibrate_quotes::<rand_hc::Hc128Rng>(&quotes, skew, 1e-3);
+        assert_eq!(report.quote_fits.len(), 10);
+        assert_eq!(report.rmse_by_maturity.len(), 2);
+        assert!(report.rmse_by_maturity[1].1 > report.rmse_by_maturity[0].1);
+        assert_eq!(report.worst_fit().unwrap().quote.maturity, 1.0);
+
+        // ordinary least squares: the intercept is the mean with the standard error s / sqrt(n),
+        // the slope has the standard error s / sqrt(sum (x - mean)^2)
+        let mean_noise = noise.iter().sum::<f64>() / 10.0;
+        assert_approx_eq!(report.parameters()[0], 0.2 + mean_noise, 1e-7);
+        let ssr: f64 = report.quote_fits.iter().map(|f| f.residual.powi(2)).sum();
+        let s = (ssr / 8.0).sqrt();
+        let errors = report.parameter_standard_errors.clone().unwrap();
+        assert_approx_eq!(errors[0], s / 10.0_f64.sqrt(), 1e-6);
+        assert_approx_eq!(errors[1], s / 0.2_f64.sqrt(), 1e-5);
+        assert_approx_eq!(report.rmse, (ssr / 10.0).sqrt(), 1e-12);
+        assert_eq!(report.model_cache_statistics, None);
+
+        // the fits at the best parameters reuse the cached model values
+        let cached = Calibrator::new(vec![Bound::Lower(0.0), Bound::Unbounded])
+            .with_starts(4, 4)
+            .with_cache(1_000, &("quotes", 10))
+            .unwrap()
+            .calibrate_quotes::<rand_hc::Hc128Rng>(&quotes, skew, 1e-3);
+        assert_eq!(cached.parameters(), report.parameters());
+        let statistics = cached.model_cache_statistics.unwrap();
+        assert!(statistics.hits >= 10);
+        assert!(statistics.evictions > 0);
+        assert!(cached.calibration.cache_statistics.is_some());
+        assert!(matches!(
+            Calibrator::new(vec![Bound::Unbounded]).with_cache(0, &()),
+            Err(PricingError::InvalidParameter(_))
+        ));
+    }
+}
@@ -0,0 +1,306 @@
+use ndarray::{s, Array1, Array2, ArrayView1, ArrayView2, Axis};
+
+use crate::common::results::{PricingError, PricingWarning};
+use crate::numerics::linalg::{cholesky, matmul, symmetric_eigen};
+
+/// The smallest eigenvalue of repaired correlation matrices, such that they are positive definite.
+const MIN_EIGENVALUE: f64 = 1e-8;
+
+/// The correlation matrix of a covariance matrix, $D^{-1/2} \Sigma D^{-1/2}$ for its diagonal $D$.
+pub fn covariance_to_correlation(covariance: &Array2<f64>) -> Array2<f64> {
+    let std_devs = covariance.diag().mapv(f64::sqrt);
+    Array2::from_shape_fn(covariance.raw_dim(), |(i, j)| {
+        covariance[[i, j]] / (std_devs[i] * std_devs[j])
+    })
+}
+
+/// Repairs a symmetric matrix with unit diagonal, e.g. a stressed or estimated correlation matrix,
+/// to a positive definite correlation matrix by clipping the eigenvalues at `min_eigenvalue` and
+/// rescaling to unit diagonal (spectral method of Rebonato and Jäckel).
+pub fn repair_correlation(matrix: &Array2<f64>, min_eigenvalue: f64) -> Array2<f64> {
+    let (eigenvalues, eigenvectors) = symmetric_eigen(matrix);
+    let clipped = eigenvalues.mapv(|e| e.max(min_eigenvalue));
+    let scaled = &eigenvectors * &clipped.insert_axis(Axis(0));
+    let repaired = matmul(&scaled, &eigenvectors.t());
+    covariance_to_correlation(&repaired)
+}
+
+/// The matrix itself if it has a Cholesky factor, or else its repaired version.
+pub(crate) fn ensure_positive_definite(correlation: Array2<f64>) -> Array2<f64> {
+    ensure_positive_definite_with_warning(correlation).0
+}
+
+/// The matrix itself if it has a Cholesky factor, or else its repaired version with the warning
+/// of the repair.
+pub(crate) fn ensure_positive_definite_with_warning(
+    correlation: Array2<f64>,
+) -> (Array2<f64>, Option<PricingWarning>) {
+    if cholesky(&correlation).is_some() {
+        return (correlation, None);
+    }
+    let min_eigenvalue = symmetric_eigen(&correlation)
+        .0
+        .fold(f64::INFINITY, |min, e| min.min(*e));
+    (
+        repair_correlation(&correlation, MIN_EIGENVALUE),
+        Some(PricingWarning::CorrelationRepaired { min_eigenvalue }),
+    )
+}
+
+/// Fails for a variance of the returns of an asset which is not positive, e.g. of a price that stays
+/// constant over the window, whose correlations are undefined.
+fn check_variances(variances: ArrayView1<f64>) -> Result<(), PricingError> {
+    match variances.iter().position(|v| !(*v > 0.0 && v.is_finite())) {
+        Some(asset) => Err(PricingError::InvalidParameter(format!(
+            "variance {} of the returns of asset {asset}",
+            variances[asset]
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// The correlation matrix of a covariance matrix of estimated returns, or the asset of zero variance.
+fn estimated_correlation(covariance: &Array2<f64>) -> Result<Array2<f64>, PricingError> {
+    check_variances(covariance.diag())?;
+    Ok(covariance_to_correlation(covariance))
+}
+
+/// The sample correlation matrix of the returns with one row per time and one column per asset.
+/// Fails for an asset without variance in the returns.
+pub fn sample_correlation(returns: ArrayView2<f64>) -> Result<Array2<f64>, PricingError> {
+    let nr_obs = returns.nrows();
+    assert!(nr_obs > 1);
+    let means = returns.mean_axis(Axis(0)).unwrap();
+    let centered = &returns - &means.insert_axis(Axis(0));
+    let covariance = matmul(&centered.t(), &centered) / (nr_obs - 1) as f64;
+    estimated_correlation(&covariance)
+}
+
+/// The correlation matrices of the rolling windows over the returns (one row per time, one column
+/// per asset), i.e. one matrix per time from `window - 1` on, each repaired if not positive definite,
+/// e.g. for windows shorter than the number of assets. Fails for a window in which an asset has no
+/// variance.
+pub fn rolling_correlations(
+    returns: &Array2<f64>,
+    window: usize,
+) -> Result<Vec<Array2<f64>>, PricingError> {
+    assert!(1 < window && window <= returns.nrows());
+    (window..=returns.nrows())
+        .map(|end| {
+            sample_correlation(returns.slice(s![end - window..end, ..]))
+                .map(ensure_positive_definite)
+        })
+        .collect()
+}
+
+/// Exponentially weighted (RiskMetrics) covariance $\Sigma_t = \lambda \Sigma_{t-1} + (1 - \lambda) r_t r_t^T$
+/// of zero-mean returns, started at the sample covariance of the first `warm_up` returns.
+/// See https://en.wikipedia.org/wiki/Exponential_smoothing
+#[derive(Clone, Copy, Debug)]
+pub struct EwmaCorrelation {
+    pub decay: f64,
+    pub warm_up: usize,
+}
+
+impl EwmaCorrelation {
+    pub fn new(decay: f64, warm_up: usize) -> Self {
+        assert!((0.0..1.0).contains(&decay));
+        assert!(warm_up > 0);
+        Self { decay, warm_up }
+    }
+
+    /// The covariances after each return from `warm_up` on.
+    pub fn covariances(&self, returns: &Array2<f64>) -> Vec<Array2<f64>> {
+        assert!(self.warm_up <= returns.nrows());
+        let warm_up = returns.slice(s![..self.warm_up, ..]);
+        let mut covariance = matmul(&warm_up.t(), &warm_up) / self.warm_up as f64;
+        let mut covariances = vec![covariance.clone()];
+        for r in returns.slice(s![self.warm_up.., ..]).rows() {
+            let r = r.insert_axis(Axis(1));
+            covariance = self.decay * covariance + (1.0 - self.decay) * matmul(&r, &r.t());
+            covariances.push(covariance.clone());
+        }
+        covariances
+    }
+
+    /// The correlation matrices after each return from `warm_up` on.
+    /// Fails if an asset has no variance, e.g. for zero returns throughout the warm-up.
+    pub fn correlations(&self, returns: &Array2<f64>) -> Result<Vec<Array2<f64>>, PricingError> {
+        self.covariances(returns)
+            .iter()
+            .map(|covariance| estimated_correlation(covariance).map(ensure_positive_definite))
+            .collect()
+    }
+}
+
+/// Dynamic conditional correlation (Engle, 2002) of the returns standardized by their exponentially
+/// weighted volatilities, with the pseudo-correlation
+/// '''math
+/// Q_t = (1 - \alpha - \beta) \bar{Q} + \alpha \epsilon_{t-1} \epsilon_{t-1}^T + \beta Q_{t-1}
+/// '''
+/// mean-reverting to the sample correlation $\bar{Q}$ of the standardized returns $\epsilon$,
+/// and the correlation $R_t = diag(Q_t)^{-1/2} Q_t diag(Q_t)^{-1/2}$.
+/// See https://en.wikipedia.org/wiki/Autoregressive_conditional_heteroskedasticity
+#[derive(Clone, Copy, Debug)]
+pub struct DccCorrelation {
+    pub alpha: f64,
+    pub beta: f64,
+    /// decay of the exponentially weighted variances standardizing the returns
+    pub volatility_decay: f64,
+}
+
+impl DccCorrelation {
+    pub fn new(alpha: f64, beta: f64) -> Self {
+        assert!(alpha >= 0.0 && beta >= 0.0 && alpha + beta < 1.0);
+        Self {
+            alpha,
+            beta,
+            volatility_decay: 0.94,
+        }
+    }
+
+    pub fn with_volatility_decay(self, volatility_decay: f64) -> Self {
+        assert!((0.0..1.0).contains(&volatility_decay));
+        Self {
+            volatility_decay,
+            ..self
+        }
+    }
+
+    /// The returns divided by their exponentially weighted volatilities known before each return,
+    /// where the first variance is the sample variance. Fails if the variance of an asset vanishes.
+    pub fn standardized_returns(&self, returns: &Array2<f64>) -> Result<Array2<f64>, PricingError> {
+        let mut variances: Array1<f64> = returns.mapv(|r| r * r).mean_axis(Axis(0)).unwrap();
+        let mut standardized = returns.to_owned();
+        for mut row in standardized.rows_mut() {
+            check_variances(variances.view())?;
+            let squared = row.mapv(|r| r * r);
+            row /= &variances.mapv(f64::sqrt);
+            variances =
+                self.volatility_decay * &variances + (1.0 - self.volatility_decay) * squared;
+        }
+        Ok(standardized)
+    }
+
+    /// The conditional correlation matrices for each time, i.e. known before the return of the time,
+    /// and the one after the last return. Fails if the variance of an asset vanishes.
+    pub fn correlations(&self, returns: &Array2<f64>) -> Result<Vec<Array2<f64>>, PricingError> {
+        let standardized = self.standardized_returns(returns)?;
+        let nr_obs = standardized.nrows() as f64;
+        let unconditional = matmul(&standardized.t(), &standardized) / nr_obs;
+
+        let mut q = unconditional.clone();
+        let mut correlations = vec![ensure_positive_definite(covariance_to_correlation(&q))];
+        for eps in standardized.rows() {
+            let eps = eps.insert_axis(Axis(1));
+            q = (1.0 - self.alpha - self.beta) * &unconditional
+                + self.alpha * matmul(&eps, &eps.t())
+                + self.beta * q;
+            correlations.push(ensure_positive_definite(covariance_to_correlation(&q)));
+        }
+        Ok(correlations)
+    }
+}
+
+#[cfg(all(test, feature = "multi-asset"))]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use ndarray::arr2;
+    use ndarray_rand::RandomExt;
+    use rand::SeedableRng;
+
+    /// Returns of two assets whose correlation switches from `rho_1` to `rho_2` halfway.
+    fn regime_returns(rho_1: f64, rho_2: f64, nr_obs: usize) -> Array2<f64> {
+        let mut rng = rand_hc::Hc128Rng::seed_from_u64(3);
+        let normals: Array2<f64> = Array2::random_using(
+            (nr_obs, 2),
+            ndarray_rand::rand_distr::StandardNormal,
+            &mut rng,
+        );
+        Array2::from_shape_fn((nr_obs, 2), |(t, i)| {
+            let rho: f64 = if t < nr_obs / 2 { rho_1 } else { rho_2 };
+            let z = normals[[t, 0]] * rho + normals[[t, 1]] * (1.0 - rho * rho).sqrt();
+            0.01 * if i == 0 { normals[[t, 0]] } else { z }
+        })
+    }
+
+    #[test]
+    fn repair() {
+        // not positive semi-definite
+        let stressed = arr2(&[[1.0, 0.9, 0.2], [0.9, 1.0, -0.6], [0.2, -0.6, 1.0]]);
+        assert!(cholesky(&stressed).is_none());
+        let repaired = repair_correlation(&stressed, 1e-4);
+        assert!(cholesky(&repaired).is_some());
+        for i in 0..3 {
+            assert_approx_eq!(repaired[[i, i]], 1.0, 1e-12);
+        }
+        assert_approx_eq!(repaired[[0, 1]], repaired[[1, 0]], 1e-12);
+        assert!((&repaired - &stressed).iter().all(|d| d.abs() < 0.3));
+
+        // a positive definite matrix remains (nearly) unchanged
+        let valid = arr2(&[[1.0, 0.5], [0.5, 1.0]]);
+        assert!((&repair_correlation(&valid, 1e-8) - &valid)
+            .iter()
+            .all(|d| d.abs() < 1e-12));
+    }
+
+    #[test]
+    fn rolling_and_ewma() {
+        let returns = regime_returns(0.0, 0.8, 1000);
+        let rolling = rolling_correlations(&returns, 250).unwrap();
+        assert_eq!(rolling.len(), 751);
+        assert_approx_eq!(rolling[0][[0, 1]], 0.0, 0.15);
+        assert_approx_eq!(rolling.last().unwrap()[[0, 1]], 0.8, 0.1);
+
+        // windows shorter than the number of assets are singular and repaired
+        let short = rolling_correlations(&regime_returns(0.5, 0.5, 10), 2).unwrap();
+        assert!(short.iter().all(|c| cholesky(c).is_some()));
+
+        let ewma = EwmaCorrelation::new(0.97, 50)
+            .correlations(&returns)
+            .unwrap();
+        assert_eq!(ewma.len(), 951);
+        assert_approx_eq!(ewma[400][[0, 1]], 0.0, 0.3);
+        assert_approx_eq!(ewma.last().unwrap()[[0, 1]], 0.8, 0.15);
+    }
+
+    #[test]
+    fn dcc_tracks_correlation_regimes() {
+        let returns = regime_returns(0.0, 0.8, 1000);
+        let dcc = DccCorrelation::new(0.05, 0.93);
+        let correlations = dcc.correlations(&returns).unwrap();
+        assert_eq!(correlations.len(), 1001);
+        for correlation in &correlations {
+            assert_approx_eq!(correlation[[0, 0]], 1.0, 1e-12);
+        }
+        let average = |range: std::ops::Range<usize>| {
+            range.clone().map(|t| correlations[t][[0, 1]]).sum::<f64>() / range.len() as f64
+        };
+        assert_approx_eq!(average(300..500), 0.0, 0.15);
+        assert_approx_eq!(average(800..1000), 0.8, 0.15);
+
+        let standardized = dcc.standardized_returns(&returns).unwrap();
+        let variance = standardized.column(0).mapv(|e| e * e).mean().unwrap();
+        assert_approx_eq!(variance, 1.0, 0.1);
+    }
+
+    #[test]
+    fn zero_variance_is_reported() {
+        // the second asset does not move in the first 20 returns
+        let mut returns = regime_returns(0.5, 0.5, 100);
+        returns.slice_mut(s![..20, 1]).fill(0.0);
+        let zero_variance = |result: Result<Vec<Array2<f64>>, PricingError>| matches!(result, Err(PricingError::InvalidParameter(message)) if message.contains("asset 1"));
+        assert!(zero_variance(rolling_correlations(&returns, 10)));
+        assert!(rolling_correlations(&returns, 30).is_ok());
+        assert!(zero_variance(
+            EwmaCorrelation::new(0.97, 10).correlations(&returns)
+        ));
+        assert!(EwmaCorrelation::new(0.97, 30)
+            .correlations(&returns)
+            .is_ok());
+        assert!(zero_variance(
+            DccCorrelation::new(0.05, 0.93).correlations(&returns.slice(s![..20, ..]).to_owned())
+        ));
+    }
+}
@@ -0,0 +1,194 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::numerics::normal::{cdf, pdf};
+
+/// The real numbers the analytic formulas are generic over: `f64` and (nested) dual numbers, which
+/// carry the exact derivatives through the arithmetic (forward mode automatic differentiation).
+pub trait Scalar:
+    Copy
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    /// The constant, whose derivatives are 0.
+    fn constant(x: f64) -> Self;
+
+    /// The value without the derivatives.
+    fn real(&self) -> f64;
+
+    fn exp(self) -> Self;
+
+    fn ln(self) -> Self;
+
+    fn sqrt(self) -> Self;
+
+    /// The standard normal cumulative distribution function.
+    fn norm_cdf(self) -> Self;
+
+    /// The standard normal density.
+    fn norm_pdf(self) -> Self;
+}
+
+impl Scalar for f64 {
+    fn constant(x: f64) -> Self {
+        x
+    }
+
+    fn real(&self) -> f64 {
+        *self
+    }
+
+    fn exp(self) -> Self {
+        f64::exp(self)
+    }
+
+    fn ln(self) -> Self {
+        f64::ln(self)
+    }
+
+    fn sqrt(self) -> Self {
+        f64::sqrt(self)
+    }
+
+    fn norm_cdf(self) -> Self {
+        cdf(self)
+    }
+
+    fn norm_pdf(self) -> Self {
+        pdf(self)
+    }
+}
+
+/// The dual number $a + b \epsilon$ with $\epsilon^2 = 0$, such that $f(a + \epsilon) = f(a) + f'(a) \epsilon$.
+/// Nesting dual numbers, e.g. `Dual<Dual<f64>>`, yields derivatives of higher orders.
+/// See https://en.wikipedia.org/wiki/Automatic_differentiation#Automatic_differentiation_using_dual_numbers
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dual<T> {
+    pub value: T,
+    pub derivative: T,
+}
+
+impl<T: Scalar> Dual<T> {
+    pub fn new(value: T, derivative: T) -> Self {
+        Self { value, derivative }
+    }
+
+    /// The variable of the differentiation, with unit derivative.
+    pub fn variable(value: T) -> Self {
+        Self::new(value, T::constant(1.0))
+    }
+
+    /// The chain rule $f(a + b \epsilon) = f(a) + f'(a) b \epsilon$.
+    fn chain(self, value: T, derivative: T) -> Self {
+        Self::new(value, derivative * self.derivative)
+    }
+}
+
+impl<T: Scalar> Add for Dual<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.value + rhs.value, self.derivative + rhs.derivative)
+    }
+}
+
+impl<T: Scalar> Sub for Dual<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.value - rhs.value, self.derivative - rhs.derivative)
+    }
+}
+
+impl<T: Scalar> Mul for Dual<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.value * rhs.value,
+            self.derivative * rhs.value + self.value * rhs.derivative,
+        )
+    }
+}
+
+impl<T: Scalar> Div for Dual<T> {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        Self::new(
+            self.value / rhs.value,
+            (self.derivative * rhs.value - self.value * rhs.derivative) / (rhs.value * rhs.value),
+        )
+    }
+}
+
+impl<T: Scalar> Neg for Dual<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.value, -self.derivative)
+    }
+}
+
+impl<T: Scalar> Scalar for Dual<T> {
+    fn constant(x: f64) -> Self {
+        Self::new(T::constant(x), T::constant(0.0))
+    }
+
+    fn real(&self) -> f64 {
+        self.value.real()
+    }
+
+    fn exp(self) -> Self {
+        let exp = self.value.exp();
+        self.chain(exp, exp)
+    }
+
+    fn ln(self) -> Self {
+        self.chain(self.value.ln(), T::constant(1.0) / self.value)
+    }
+
+    fn sqrt(self) -> Self {
+        let sqrt = self.value.sqrt();
+        self.chain(sqrt, T::constant(0.5) / sqrt)
+    }
+
+    fn norm_cdf(self) -> Self {
+        self.chain(self.value.norm_cdf(), self.value.norm_pdf())
+    }
+
+    fn norm_pdf(self) -> Self {
+        let pdf = self.value.norm_pdf();
+        self.chain(pdf, -self.value * pdf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn derivatives_of_elementary_functions() {
+        let f = |x: Dual<f64>| (x * x).exp() / x.sqrt() + x.ln();
+        let x = 0.7_f64;
+        let y = f(Dual::variable(x));
+        let exact = (x * x).exp() * (2.0 * x.sqrt() - 0.5 / (x * x.sqrt())) + 1.0 / x;
+        assert_approx_eq!(y.value, (x * x).exp() / x.sqrt() + x.ln(), 1e-15);
+        assert_approx_eq!(y.derivative, exact, 1e-13);
+
+        // the nested dual numbers of the variable yield the second derivative
+        let z = Dual::new(Dual::variable(0.3), Dual::new(1.0, 0.0));
+        let pdf = z.norm_cdf();
+        assert_approx_eq!(pdf.derivative.value, pdf_of(0.3), 1e-15);
+        assert_approx_eq!(pdf.derivative.derivative, -0.3 * pdf_of(0.3), 1e-15);
+        assert_approx_eq!(pdf.value.derivative, pdf_of(0.3), 1e-15);
+        assert_eq!(Dual::<f64>::constant(2.0).derivative, 0.0);
+    }
+
+    fn pdf_of(x: f64) -> f64 {
+        (-0.5 * x * x).exp() / (2.0 * std::f64::consts::PI).sqrt()
+    }
+}
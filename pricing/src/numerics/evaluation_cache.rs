@@ -0,0 +1,233 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::common::results::PricingError;
+
+/// The content hash of a configuration of the computation, e.g. the time grid, the number of paths
+/// and the seed of a simulation.
+pub fn content_hash(config: &impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    config.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The content of the model parameters, by their bit patterns, and of the configuration of the
+/// computation, which is compared in full when the hashes of two keys collide.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ContentKey<C> {
+    parameters: Vec<u64>,
+    config: C,
+}
+
+impl<C> ContentKey<C> {
+    pub fn new(parameters: &[f64], config: C) -> Self {
+        Self {
+            parameters: parameters.iter().map(|p| p.to_bits()).collect(),
+            config,
+        }
+    }
+}
+
+/// The lookups of a cache for tuning its capacity.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStatistics {
+    pub hits: usize,
+    pub misses: usize,
+    pub evictions: usize,
+}
+
+impl CacheStatistics {
+    pub fn lookups(&self) -> usize {
+        self.hits + self.misses
+    }
+
+    /// The fraction of the lookups found in the cache, 0 without lookups.
+    pub fn hit_rate(&self) -> f64 {
+        if self.lookups() == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / self.lookups() as f64
+    }
+}
+
+#[derive(Debug)]
+struct CacheState<K, V> {
+    /// the values with the time of their last use
+    entries: HashMap<K, (V, u64)>,
+    /// the keys by the time of their last use
+    recency: BTreeMap<u64, K>,
+    clock: u64,
+    statistics: CacheStatistics,
+}
+
+impl<K: Clone + Eq + Hash, V: Clone> CacheState<K, V> {
+    fn touch(&mut self, key: &K) -> Option<V> {
+        self.clock += 1;
+        let clock = self.clock;
+        let (value, last_use) = self.entries.get_mut(key)?;
+        self.recency.remove(last_use);
+        self.recency.insert(clock, key.clone());
+        *last_use = clock;
+        Some(value.clone())
+    }
+}
+
+/// A content-addressed cache of evaluations, e.g. of the model values of a calibration, keyed by the
+/// `ContentKey` of their inputs, which evicts the least recently used value beyond its capacity.
+/// The cache is shared by parallel evaluations, which compute missing values outside of its lock,
+/// such that concurrent misses of the same key may compute the value more than once.
+#[derive(Debug)]
+pub struct EvaluationCache<K, V> {
+    capacity: usize,
+    state: Mutex<CacheState<K, V>>,
+}
+
+impl<K: Clone + Eq + Hash, V: Clone> EvaluationCache<K, V> {
+    pub fn new(capacity: usize) -> Result<Self, PricingError> {
+        PricingError::check(capacity > 0, "cache capacity", capacity as f64)?;
+        Ok(Self {
+            capacity,
+            state: Mutex::new(CacheState {
+                entries: HashMap::new(),
+                recency: BTreeMap::new(),
+                clock: 0,
+                statistics: CacheStatistics::default(),
+            }),
+        })
+    }
+
+    /// The state, which stays consistent if an evaluation panics, as values are computed outside of
+    /// the lock.
+    fn state(&self) -> MutexGuard<'_, CacheState<K, V>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.state().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn statistics(&self) -> CacheStatistics {
+        self.state().statistics
+    }
+
+    /// The cached value of the key, counted as a hit or a miss.
+    pub fn get(&self, key: &K) -> Option<V> {
+        let mut state = self.state();
+        let value = state.touch(key);
+        match value {
+            Some(_) => state.statistics.hits += 1,
+            None => state.statistics.misses += 1,
+        }
+        value
+    }
+
+    /// Caches the value of the key, evicting the least recently used value if the cache is full.
+    pub fn insert(&self, key: K, value: V) {
+        let mut state = self.state();
+        state.clock += 1;
+        let clock = state.clock;
+        if let Some((_, last_use)) = state.entries.remove(&key) {
+            state.recency.remove(&last_use);
+        } else if state.entries.len() == self.capacity {
+            if let Some((_, oldest)) = state.recency.pop_first() {
+                state.entries.remove(&oldest);
+                state.statistics.evictions += 1;
+            }
+        }
+        state.recency.insert(clock, key.clone());
+        state.entries.insert(key, (value, clock));
+    }
+
+    /// The cached value of the key, or the evaluation of f, which is cached.
+    pub fn get_or_insert_with(&self, key: K, f: impl FnOnce() -> V) -> V {
+        if let Some(value) = self.get(&key) {
+            return value;
+        }
+        let value = f();
+        self.insert(key, value.clone());
+        value
+    }
+
+    /// Removes the values, keeping the statistics.
+    pub fn clear(&mut self) {
+        let state = self.state.get_mut().unwrap_or_else(PoisonError::into_inner);
+        state.entries.clear();
+        state.recency.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn least_recently_used_eviction() {
+        let cache = EvaluationCache::new(2).unwrap();
+        let key = |x: f64| ContentKey::new(&[x], ("grid", 100));
+        assert_ne!(key(0.0), ContentKey::new(&[0.0], ("grid", 200)));
+
+        assert_eq!(cache.get_or_insert_with(key(1.0), || 1.0), 1.0);
+        assert_eq!(cache.get_or_insert_with(key(2.0), || 2.0), 2.0);
+        // the hit makes 2.0 the least recently used value
+        assert_eq!(cache.get_or_insert_with(key(1.0), || panic!("cached")), 1.0);
+        cache.get_or_insert_with(key(3.0), || 3.0);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&key(2.0)), None);
+        assert_eq!(cache.get(&key(1.0)), Some(1.0));
+
+        let statistics = cache.statistics();
+        assert_eq!(
+            statistics,
+            CacheStatistics {
+                hits: 2,
+                misses: 4,
+                evictions: 1
+            }
+        );
+        assert!((statistics.hit_rate() - 1.0 / 3.0).abs() < 1e-15);
+    }
+
+    #[test]
+    fn colliding_hashes_and_poisoned_lock() {
+        // a configuration hashing equally for all values: the keys are told apart by their content
+        #[derive(Clone, PartialEq, Eq)]
+        struct Config(u32);
+        impl Hash for Config {
+            fn hash<H: Hasher>(&self, _state: &mut H) {}
+        }
+
+        let cache = EvaluationCache::new(4).unwrap();
+        cache.insert(ContentKey::new(&[1.0], Config(1)), 1.0);
+        cache.insert(ContentKey::new(&[1.0], Config(2)), 2.0);
+        assert_eq!(cache.get(&ContentKey::new(&[1.0], Config(1))), Some(1.0));
+        assert_eq!(cache.get(&ContentKey::new(&[1.0], Config(2))), Some(2.0));
+        assert_eq!(cache.get(&ContentKey::new(&[1.0], Config(3))), None);
+
+        // a panic while holding the lock does not disable the cache
+        let poisoned = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _state = cache.state();
+            panic!("evaluation failed");
+        }));
+        assert!(poisoned.is_err());
+        assert_eq!(
+            cache.get_or_insert_with(ContentKey::new(&[2.0], Config(1)), || 3.0),
+            3.0
+        );
+        assert_eq!(cache.len(), 3);
+
+        assert!(matches!(
+            EvaluationCache::<u64, f64>::new(0),
+            Err(PricingError::InvalidParameter(_))
+        ));
+    }
+}
@@ -0,0 +1,208 @@
+use ndarray::{concatenate, s, Array1, Array2, Axis};
+
+use crate::common::results::PricingError;
+use crate::numerics::least_squares::least_squares;
+
+/// The variance of a return split into the part explained by the factors and the specific
+/// (idiosyncratic) part.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RiskDecomposition {
+    pub factor_variance: f64,
+    pub specific_variance: f64,
+}
+
+impl RiskDecomposition {
+    pub fn total_variance(&self) -> f64 {
+        self.factor_variance + self.specific_variance
+    }
+
+    pub fn volatility(&self) -> f64 {
+        self.total_variance().sqrt()
+    }
+
+    /// The fraction of the variance explained by the factors, i.e. the $R^2$.
+    pub fn factor_share(&self) -> f64 {
+        self.factor_variance / self.total_variance()
+    }
+}
+
+/// The factor risk of a portfolio with the contributions $x_k (F x)_k$ of the factors to the factor
+/// variance for the portfolio exposures $x = B^T w$, which sum to the factor variance.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortfolioFactorRisk {
+    pub exposures: Array1<f64>,
+    pub factor_contributions: Array1<f64>,
+    pub decomposition: RiskDecomposition,
+}
+
+/// A linear factor model $r = B f + \epsilon$ of the asset returns by the exposures B (assets x factors)
+/// to the factor returns f of covariance F and uncorrelated specific returns ε, such that the asset
+/// covariance is $B F B^T + diag(s^2)$.
+/// See https://en.wikipedia.org/wiki/Arbitrage_pricing_theory
+#[derive(Clone, Debug, PartialEq)]
+pub struct FactorModel {
+    exposures: Array2<f64>,
+    factor_covariance: Array2<f64>,
+    specific_variances: Array1<f64>,
+}
+
+impl FactorModel {
+    pub fn new(
+        exposures: Array2<f64>,
+        factor_covariance: Array2<f64>,
+        specific_variances: Array1<f64>,
+    ) -> Result<Self, PricingError> {
+        let (nr_assets, nr_factors) = exposures.dim();
+        PricingError::check(
+            factor_covariance.shape() == [nr_factors, nr_factors],
+            "nr factors",
+            nr_factors as f64,
+        )?;
+        PricingError::check(
+            specific_variances.len() == nr_assets,
+            "nr assets",
+            nr_assets as f64,
+        )?;
+        if let Some(variance) = specific_variances.iter().find(|v| **v < 0.0) {
+            return Err(PricingError::InvalidParameter(format!(
+                "specific variance = {variance}"
+            )));
+        }
+        Ok(Self {
+            exposures,
+            factor_covariance,
+            specific_variances,
+        })
+    }
+
+    /// Estimates the exposures by regressing the returns of each asset on the factor returns with an
+    /// intercept (one row per time), the factor covariance by the sample covariance of the factor
+    /// returns, and the specific variances by the (unbiased) variances of the residuals.
+    pub fn estimate(
+        asset_returns: &Array2<f64>,
+        factor_returns: &Array2<f64>,
+    ) -> Result<Self, PricingError> {
+        let (nr_obs, nr_factors) = factor_returns.dim();
+        PricingError::check(
+            asset_returns.nrows() == nr_obs,
+            "nr observations",
+            asset_returns.nrows() as f64,
+        )?;
+        PricingError::check(nr_obs > nr_factors + 1, "nr observations", nr_obs as f64)?;
+        let design = concatenate![Axis(1), Array2::ones((nr_obs, 1)), factor_returns.view()];
+
+        let nr_assets = asset_returns.ncols();
+        let mut exposures = Array2::zeros((nr_assets, nr_factors));
+        let mut specific_variances = Array1::zeros(nr_assets);
+        for (asset, returns) in asset_returns.axis_iter(Axis(1)).enumerate() {
+            let returns = returns.to_owned();
+            let coefficients = least_squares(&design, &returns).ok_or_else(|| {
+                PricingError::InvalidParameter("collinear factor returns".to_string())
+            })?;
+            let residuals = &returns - &design.dot(&coefficients);
+            exposures
+                .row_mut(asset)
+                .assign(&coefficients.slice(s![1..]));
+            specific_variances[asset] =
+                residuals.dot(&residuals) / (nr_obs - nr_factors - 1) as f64;
+        }
+
+        let means = factor_returns.mean_axis(Axis(0)).unwrap();
+        let centered = factor_returns - &means.insert_axis(Axis(0));
+        let factor_covariance = centered.t().dot(&centered) / (nr_obs - 1) as f64;
+        Self::new(exposures, factor_covariance, specific_variances)
+    }
+
+    pub fn exposures(&self) -> &Array2<f64> {
+        &self.exposures
+    }
+
+    pub fn factor_covariance(&self) -> &Array2<f64> {
+        &self.factor_covariance
+    }
+
+    pub fn specific_variances(&self) -> &Array1<f64> {
+        &self.specific_variances
+    }
+
+    /// The covariance $B F B^T + diag(s^2)$ of the asset returns.
+    pub fn covariance(&self) -> Array2<f64> {
+        let b = &self.exposures;
+        b.dot(&self.factor_covariance).dot(&b.t()) + Array2::from_diag(&self.specific_variances)
+    }
+
+    /// The factor and specific variance of each asset.
+    pub fn asset_risk(&self) -> Vec<RiskDecomposition> {
+        self.exposures
+            .axis_iter(Axis(0))
+            .zip(&self.specific_variances)
+            .map(|(exposures, specific_variance)| RiskDecomposition {
+                factor_variance: exposures.dot(&self.factor_covariance.dot(&exposures)),
+                specific_variance: *specific_variance,
+            })
+            .collect()
+    }
+
+    /// The factor risk of the portfolio of the weights.
+    pub fn portfolio_risk(&self, weights: &Array1<f64>) -> PortfolioFactorRisk {
+        assert_eq!(weights.len(), self.exposures.nrows());
+        let exposures = self.exposures.t().dot(weights);
+        let factor_contributions = &exposures * &self.factor_covariance.dot(&exposures);
+        let specific_variance = (weights * weights).dot(&self.specific_variances);
+        PortfolioFactorRisk {
+            decomposition: RiskDecomposition {
+                factor_variance: factor_contributions.sum(),
+                specific_variance,
+            },
+            exposures,
+            factor_contributions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::{arr1, arr2};
+
+    #[test]
+    fn decompose_and_estimate() {
+        let model = FactorModel::new(
+            arr2(&[[1.2, 0.3], [0.8, -0.5], [1.0, 0.0]]),
+            arr2(&[[0.04, 0.01], [0.01, 0.02]]),
+            arr1(&[0.01, 0.02, 0.005]),
+        )
+        .unwrap();
+        let weights = arr1(&[0.5, 0.3, 0.2]);
+        let risk = model.portfolio_risk(&weights);
+        let total = weights.dot(&model.covariance().dot(&weights));
+        assert!((risk.decomposition.total_variance() - total).abs() < 1e-14);
+        assert!((risk.exposures[0] - 1.04).abs() < 1e-14);
+        assert!(risk.decomposition.factor_share() > 0.9);
+        // diversification leaves mostly factor risk, unlike for a single asset
+        assert!(model.asset_risk()[1].factor_share() < risk.decomposition.factor_share());
+
+        // the exposures of returns of the factors plus orthogonal noise
+        let nr_obs = 400;
+        let factor_returns = Array2::from_shape_fn((nr_obs, 2), |(t, k)| {
+            0.01 * ((t * (k + 2)) as f64 * 0.7).sin()
+        });
+        let noise = Array2::from_shape_fn((nr_obs, 3), |(t, i)| {
+            0.005 * ((t * (i + 7)) as f64 * 1.3 + 0.4).cos()
+        });
+        let asset_returns = factor_returns.dot(&model.exposures().t()) + &noise + 0.001;
+        let estimated = FactorModel::estimate(&asset_returns, &factor_returns).unwrap();
+        for (b, e) in estimated.exposures().iter().zip(model.exposures()) {
+            assert!((b - e).abs() < 0.05, "{b} != {e}");
+        }
+        assert!(estimated
+            .specific_variances()
+            .iter()
+            .all(|v| (v.sqrt() - 0.005 / 2.0_f64.sqrt()).abs() < 5e-4));
+        assert!(FactorModel::estimate(
+            &asset_returns.slice(s![..3, ..]).to_owned(),
+            &factor_returns
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,387 @@
+use ndarray::{Array1, Array2};
+
+use crate::numerics::banded::TridiagonalMatrix;
+
+/// The behaviour outside of the first and last node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Extrapolation {
+    /// the value at the nearest node
+    Flat,
+    /// the tangent of the interpolant at the nearest node
+    Linear,
+    /// no value outside of the nodes
+    Forbidden,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InterpolationMethod {
+    Linear,
+    /// linear in the logarithm of the (positive) values, e.g. of discount factors
+    LogLinear,
+    /// the cubic spline with vanishing second derivatives at the end nodes
+    NaturalCubic,
+    /// the natural cubic spline with the Hyman filter on the node slopes, which preserves monotone data
+    MonotoneCubic,
+}
+
+/// One dimensional interpolation of the values `ys` on the increasing nodes `xs`, where the cubic methods
+/// are represented as piecewise cubic Hermite polynomials by their node slopes.
+/// See https://en.wikipedia.org/wiki/Spline_interpolation
+#[derive(Clone, Debug)]
+pub struct Interpolator1D {
+    xs: Vec<f64>,
+    /// the interpolated values, i.e. the logarithms for log-linear interpolation
+    ys: Vec<f64>,
+    method: InterpolationMethod,
+    extrapolation: Extrapolation,
+    /// the node slopes of the cubic methods
+    slopes: Vec<f64>,
+}
+
+impl Interpolator1D {
+    pub fn new(xs: Vec<f64>, ys: Vec<f64>, method: InterpolationMethod) -> Self {
+        assert!(xs.len() >= 2 && xs.len() == ys.len());
+        assert!(xs.windows(2).all(|w| w[0] < w[1]));
+        let ys = match method {
+            InterpolationMethod::LogLinear => {
+                assert!(ys.iter().all(|y| *y > 0.0));
+                ys.iter().map(|y| y.ln()).collect()
+            }
+            _ => ys,
+        };
+        let slopes = match method {
+            InterpolationMethod::Linear | InterpolationMethod::LogLinear => Vec::new(),
+            InterpolationMethod::NaturalCubic => natural_spline_slopes(&xs, &ys),
+            InterpolationMethod::MonotoneCubic => {
+                hyman_filter(&xs, &ys, natural_spline_slopes(&xs, &ys))
+            }
+        };
+        Self {
+            xs,
+            ys,
+            method,
+            extrapolation: Extrapolation::Flat,
+            slopes,
+        }
+    }
+
+    pub fn with_extrapolation(self, extrapolation: Extrapolation) -> Self {
+        Self {
+            extrapolation,
+            ..self
+        }
+    }
+
+    pub fn xs(&self) -> &[f64] {
+        &self.xs
+    }
+
+    /// The value at x, or None outside of the nodes for forbidden extrapolation.
+    pub fn value(&self, x: f64) -> Option<f64> {
+        let n = self.xs.len();
+        let (x0, xn) = (self.xs[0], self.xs[n - 1]);
+        let y = if x < x0 || x > xn {
+            let (node, idx) = if x < x0 { (x0, 0) } else { (xn, n - 1) };
+            match self.extrapolation {
+                Extrapolation::Flat => self.ys[idx],
+                Extrapolation::Linear => self.ys[idx] + self.slope_at_node(idx) * (x - node),
+                Extrapolation::Forbidden => return None,
+            }
+        } else {
+            let i = self.xs.partition_point(|xi| *xi <= x).clamp(1, n - 1) - 1;
+            self.segment_value(i, x)
+        };
+        Some(match self.method {
+            InterpolationMethod::LogLinear => y.exp(),
+            _ => y,
+        })
+    }
+
+    fn secant(&self, i: usize) -> f64 {
+        (self.ys[i + 1] - self.ys[i]) / (self.xs[i + 1] - self.xs[i])
+    }
+
+    fn slope_at_node(&self, idx: usize) -> f64 {
+        if self.slopes.is_empty() {
+            self.secant(idx.min(self.xs.len() - 2))
+        } else {
+            self.slopes[idx]
+        }
+    }
+
+    /// The interpolant on the segment $[x_i, x_{i+1}]$.
+    fn segment_value(&self, i: usize, x: f64) -> f64 {
+        let h = self.xs[i + 1] - self.xs[i];
+        let t = (x - self.xs[i]) / h;
+        if self.slopes.is_empty() {
+            return self.ys[i] + t * (self.ys[i + 1] - self.ys[i]);
+        }
+        let (t2, t3) = (t * t, t * t * t);
+        (2.0 * t3 - 3.0 * t2 + 1.0) * self.ys[i]
+            + (t3 - 2.0 * t2 + t) * h * self.slopes[i]
+            + (-2.0 * t3 + 3.0 * t2) * self.ys[i + 1]
+            + (t3 - t2) * h * self.slopes[i + 1]
+    }
+}
+
+/// The node slopes of the natural cubic spline by solving the tridiagonal system of the continuity
+/// of the second derivatives with the Thomas algorithm.
+fn natural_spline_slopes(xs: &[f64], ys: &[f64]) -> Vec<f64> {
+    let n = xs.len();
+    let h: Vec<f64> = xs.windows(2).map(|w| w[1] - w[0]).collect();
+    let secants: Vec<f64> = (0..n - 1).map(|i| (ys[i + 1] - ys[i]) / h[i]).collect();
+
+    // equations for the slopes d_i: natural end conditions 2 d_0 + d_1 = 3 s_0 and d_{n-2} + 2 d_{n-1} = 3 s_{n-2}
+    let mut lower = vec![0.0; n];
+    let mut diagonal = vec![0.0; n];
+    let mut upper = vec![0.0; n];
+    let mut rhs = vec![0.0; n];
+    diagonal[0] = 2.0;
+    upper[0] = 1.0;
+    rhs[0] = 3.0 * secants[0];
+    for i in 1..n - 1 {
+        lower[i] = h[i];
+        diagonal[i] = 2.0 * (h[i - 1] + h[i]);
+        upper[i] = h[i - 1];
+        rhs[i] = 3.0 * (h[i] * secants[i - 1] + h[i - 1] * secants[i]);
+    }
+    lower[n - 1] = 1.0;
+    diagonal[n - 1] = 2.0;
+    rhs[n - 1] = 3.0 * secants[n - 2];
+
+    // the system is diagonally dominant for increasing nodes
+    TridiagonalMatrix::new(lower, diagonal, upper)
+        .solve(&rhs)
+        .expect("nonsingular spline system")
+}
+
+/// Hyman's filter: limits the slopes to three times the adjacent secants (and to zero at local extrema),
+/// such that the cubic Hermite interpolant is monotone on monotone data.
+/// See Hyman, Accurate monotonicity preserving cubic interpolation (1983).
+fn hyman_filter(xs: &[f64], ys: &[f64], mut slopes: Vec<f64>) -> Vec<f64> {
+    let n = xs.len();
+    let secants: Vec<f64> = (0..n - 1)
+        .map(|i| (ys[i + 1] - ys[i]) / (xs[i + 1] - xs[i]))
+        .collect();
+    for (i, slope) in slopes.iter_mut().enumerate() {
+        let (left, right) = match i {
+            0 => (secants[0], secants[0]),
+            _ if i == n - 1 => (secants[n - 2], secants[n - 2]),
+            _ => (secants[i - 1], secants[i]),
+        };
+        *slope = if left * right <= 0.0 || *slope * left <= 0.0 {
+            0.0
+        } else {
+            let bound = 3.0 * left.abs().min(right.abs());
+            left.signum() * slope.abs().min(bound)
+        };
+    }
+    slopes
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InterpolationMethod2D {
+    Bilinear,
+    /// natural cubic splines in both dimensions
+    Bicubic,
+}
+
+/// Two dimensional interpolation of `values[[i, j]]` on the grid of the nodes `xs[i]` and `ys[j]`,
+/// extrapolated flat beyond the grid. The bicubic method is the tensor product of natural cubic splines,
+/// represented per cell as bicubic Hermite polynomials by the partial derivatives at the nodes.
+#[derive(Clone, Debug)]
+pub struct Interpolator2D {
+    xs: Vec<f64>,
+    ys: Vec<f64>,
+    values: Array2<f64>,
+    /// the partial derivatives $f_x$, $f_y$ and $f_{xy}$ at the nodes of the bicubic method
+    derivatives: Option<[Array2<f64>; 3]>,
+}
+
+impl Interpolator2D {
+    pub fn new(
+        xs: Vec<f64>,
+        ys: Vec<f64>,
+        values: &Array2<f64>,
+        method: InterpolationMethod2D,
+    ) -> Self {
+        assert!(xs.len() >= 2 && ys.len() >= 2);
+        assert!(xs.windows(2).all(|w| w[0] < w[1]) && ys.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(values.shape(), &[xs.len(), ys.len()]);
+        let derivatives = match method {
+            InterpolationMethod2D::Bilinear => None,
+            InterpolationMethod2D::Bicubic => {
+                // the spline slopes are linear in the values, so the cross derivatives are the
+                // slopes along x of the slopes along y
+                let along_x = |values: &Array2<f64>| {
+                    let mut slopes = Array2::zeros(values.dim());
+                    for (j, column) in values.columns().into_iter().enumerate() {
+                        let column_slopes = natural_spline_slopes(&xs, &column.to_vec());
+                        slopes.column_mut(j).assign(&Array1::from(column_slopes));
+                    }
+                    slopes
+                };
+                let mut dy = Array2::zeros(values.dim());
+                for (i, row) in values.rows().into_iter().enumerate() {
+                    let row_slopes = natural_spline_slopes(&ys, &row.to_vec());
+                    dy.row_mut(i).assign(&Array1::from(row_slopes));
+                }
+                Some([along_x(values), along_x(&dy), dy])
+            }
+        };
+        Self {
+            xs,
+            ys,
+            values: values.to_owned(),
+            derivatives,
+        }
+    }
+
+    pub fn value(&self, x: f64, y: f64) -> f64 {
+        let (i, x_weights) = Self::node_weights(&self.xs, x, self.derivatives.is_some());
+        let (j, y_weights) = Self::node_weights(&self.ys, y, self.derivatives.is_some());
+        let mut value = 0.0;
+        for (a, (x_value, x_slope)) in x_weights.iter().enumerate() {
+            for (b, (y_value, y_slope)) in y_weights.iter().enumerate() {
+                let node = [i + a, j + b];
+                value += x_value * y_value * self.values[node];
+                if let Some([dx, dxy, dy]) = &self.derivatives {
+                    value += x_slope * y_value * dx[node]
+                        + x_value * y_slope * dy[node]
+                        + x_slope * y_slope * dxy[node];
+                }
+            }
+        }
+        value
+    }
+
+    /// The segment of the (clamped) point with the weights of the values and slopes at its two nodes,
+    /// of the cubic Hermite polynomial or of the linear interpolant.
+    fn node_weights(nodes: &[f64], point: f64, cubic: bool) -> (usize, [(f64, f64); 2]) {
+        let n = nodes.len();
+        let point = point.clamp(nodes[0], nodes[n - 1]);
+        let i = nodes.partition_point(|node| *node <= point).clamp(1, n - 1) - 1;
+        let h = nodes[i + 1] - nodes[i];
+        let t = (point - nodes[i]) / h;
+        if !cubic {
+            return (i, [(1.0 - t, 0.0), (t, 0.0)]);
+        }
+        let (t2, t3) = (t * t, t * t * t);
+        (
+            i,
+            [
+                (2.0 * t3 - 3.0 * t2 + 1.0, (t3 - 2.0 * t2 + t) * h),
+                (-2.0 * t3 + 3.0 * t2, (t3 - t2) * h),
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use ndarray::arr2;
+
+    #[test]
+    fn one_dimensional_methods() {
+        let xs = vec![0.0, 1.0, 2.0, 4.0];
+        let linear = Interpolator1D::new(
+            xs.clone(),
+            vec![1.0, 3.0, 2.0, 2.0],
+            InterpolationMethod::Linear,
+        );
+        assert_eq!(linear.value(0.5), Some(2.0));
+        assert_eq!(linear.value(-1.0), Some(1.0));
+        let linear = linear.with_extrapolation(Extrapolation::Linear);
+        assert_eq!(linear.value(-1.0), Some(-1.0));
+        let linear = linear.with_extrapolation(Extrapolation::Forbidden);
+        assert_eq!(linear.value(5.0), None);
+
+        let discount_factors = vec![1.0, 0.97, 0.94, 0.88];
+        let log_linear =
+            Interpolator1D::new(xs.clone(), discount_factors, InterpolationMethod::LogLinear);
+        assert_approx_eq!(
+            log_linear.value(3.0).unwrap(),
+            (0.94_f64 * 0.88).sqrt(),
+            1e-15
+        );
+
+        // cubic splines reproduce smooth data away from the natural end conditions closely,
+        // and the nodes exactly
+        let cubic_xs: Vec<f64> = (0..11).map(|i| i as f64).collect();
+        let cubic_ys: Vec<f64> = cubic_xs.iter().map(|x| x.sin()).collect();
+        let cubic = Interpolator1D::new(cubic_xs, cubic_ys, InterpolationMethod::NaturalCubic);
+        assert_approx_eq!(cubic.value(3.0).unwrap(), 3.0_f64.sin(), 1e-15);
+        assert_approx_eq!(cubic.value(4.5).unwrap(), 4.5_f64.sin(), 1e-2);
+    }
+
+    #[test]
+    fn monotone_cubic_preserves_monotonicity() {
+        let xs = vec![0.0, 1.0, 2.0, 3.0, 4.0];
+        let ys = vec![0.0, 0.0, 0.1, 1.0, 1.0];
+        let natural =
+            Interpolator1D::new(xs.clone(), ys.clone(), InterpolationMethod::NaturalCubic);
+        let monotone = Interpolator1D::new(xs, ys, InterpolationMethod::MonotoneCubic);
+
+        let grid: Vec<f64> = (0..=400).map(|i| i as f64 / 100.0).collect();
+        let overshoots = grid
+            .windows(2)
+            .any(|w| natural.value(w[1]).unwrap() < natural.value(w[0]).unwrap() - 1e-12);
+        assert!(overshoots);
+        assert!(grid
+            .windows(2)
+            .all(|w| monotone.value(w[1]).unwrap() >= monotone.value(w[0]).unwrap() - 1e-12));
+    }
+
+    #[test]
+    fn two_dimensional_methods() {
+        let values = arr2(&[[1.0, 2.0, 3.0], [2.0, 4.0, 6.0]]);
+        let bilinear = Interpolator2D::new(
+            vec![0.0, 1.0],
+            vec![0.0, 1.0, 2.0],
+            &values,
+            InterpolationMethod2D::Bilinear,
+        );
+        assert_approx_eq!(bilinear.value(0.5, 0.5), 2.25, 1e-15);
+        assert_approx_eq!(bilinear.value(2.0, 3.0), 6.0, 1e-15);
+
+        // bicubic splines reproduce the bilinear function x * y
+        let xs = vec![0.0, 1.0, 2.0, 3.0];
+        let values = Array2::from_shape_fn((4, 4), |(i, j)| (i * j) as f64);
+        let bicubic = Interpolator2D::new(xs.clone(), xs, &values, InterpolationMethod2D::Bicubic);
+        assert_approx_eq!(bicubic.value(1.5, 2.5), 3.75, 1e-12);
+    }
+
+    #[test]
+    fn bicubic_is_the_tensor_product_of_natural_splines() {
+        let xs: Vec<f64> = vec![0.0, 0.5, 1.5, 3.0, 3.5];
+        let ys: Vec<f64> = vec![-1.0, 0.0, 2.0, 2.5];
+        let values = Array2::from_shape_fn((5, 4), |(i, j)| (xs[i] * ys[j]).sin() + xs[i].exp());
+        let bicubic = Interpolator2D::new(
+            xs.clone(),
+            ys.clone(),
+            &values,
+            InterpolationMethod2D::Bicubic,
+        );
+
+        // the splines along y per x node, interpolated by a spline along x
+        let nested = |x: f64, y: f64| {
+            let column: Vec<f64> = values
+                .rows()
+                .into_iter()
+                .map(|row| {
+                    Interpolator1D::new(ys.clone(), row.to_vec(), InterpolationMethod::NaturalCubic)
+                        .value(y)
+                        .unwrap()
+                })
+                .collect();
+            Interpolator1D::new(xs.clone(), column, InterpolationMethod::NaturalCubic)
+                .value(x)
+                .unwrap()
+        };
+        for (x, y) in [(0.2, -0.5), (1.0, 1.0), (3.2, 2.4), (-1.0, 3.0), (1.5, 0.0)] {
+            assert_approx_eq!(bicubic.value(x, y), nested(x, y), 1e-12);
+        }
+    }
+}
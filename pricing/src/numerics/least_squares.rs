@@ -0,0 +1,89 @@
+use ndarray::{Array1, Array2};
+
+/// Solves the linear system $A x = b$ by Gaussian elimination with partial pivoting.
+/// Returns None if the matrix is (numerically) singular.
+pub fn solve_linear_system(a: &Array2<f64>, b: &Array1<f64>) -> Option<Array1<f64>> {
+    let n = b.len();
+    assert_eq!(a.shape(), &[n, n]);
+
+    let mut m = a.to_owned();
+    let mut x = b.to_owned();
+
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| m[[i, col]].abs().total_cmp(&m[[j, col]].abs()))?;
+        if m[[pivot, col]].abs() < 1e-14 {
+            return None;
+        }
+        if pivot != col {
+            for k in 0..n {
+                m.swap([col, k], [pivot, k]);
+            }
+            x.swap(col, pivot);
+        }
+        for row in col + 1..n {
+            let factor = m[[row, col]] / m[[col, col]];
+            for k in col..n {
+                m[[row, k]] -= factor * m[[col, k]];
+            }
+            x[row] -= factor * x[col];
+        }
+    }
+
+    for row in (0..n).rev() {
+        let tail: f64 = (row + 1..n).map(|k| m[[row, k]] * x[k]).sum();
+        x[row] = (x[row] - tail) / m[[row, row]];
+    }
+    Some(x)
+}
+
+/// Ordinary least squares estimate $\beta$ minimizing $||X \beta - y||^2$ via the normal equations,
+/// where the rows of the design matrix $X$ are the observations.
+/// See https://en.wikipedia.org/wiki/Ordinary_least_squares
+pub fn least_squares(design: &Array2<f64>, y: &Array1<f64>) -> Option<Array1<f64>> {
+    assert_eq!(design.nrows(), y.len());
+    let xt = design.t();
+    solve_linear_system(&xt.dot(design), &xt.dot(y))
+}
+
+/// Design matrix with the columns $1, x, x^2, ..., x^degree$.
+pub fn polynomial_basis(x: &[f64], degree: usize) -> Array2<f64> {
+    Array2::from_shape_fn((x.len(), degree + 1), |(i, j)| x[i].powi(j as i32))
+}
+
+/// Evaluates the polynomial $\sum_j c_j x^j$ by Horner's scheme.
+pub fn polynomial_value(coefficients: &Array1<f64>, x: f64) -> f64 {
+    coefficients.iter().rev().fold(0.0, |acc, c| acc * x + c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use ndarray::{arr1, arr2};
+
+    #[test]
+    fn linear_system() {
+        // requires pivoting as the first diagonal entry vanishes
+        let a = arr2(&[[0.0, 2.0, 1.0], [1.0, 1.0, 0.0], [2.0, 0.0, 3.0]]);
+        let b = arr1(&[7.0, 3.0, 11.0]);
+        let x = solve_linear_system(&a, &b).unwrap();
+        for (xi, expected) in x.iter().zip([1.0, 2.0, 3.0]) {
+            assert_approx_eq!(xi, expected, 1e-12);
+        }
+
+        let singular = arr2(&[[1.0, 2.0], [2.0, 4.0]]);
+        assert!(solve_linear_system(&singular, &arr1(&[1.0, 2.0])).is_none());
+    }
+
+    #[test]
+    fn polynomial_fit() {
+        let x: Vec<f64> = (0..20).map(|i| i as f64 / 10.0).collect();
+        let y: Array1<f64> = x.iter().map(|x| 1.0 - 2.0 * x + 0.5 * x * x).collect();
+        let coefficients = least_squares(&polynomial_basis(&x, 2), &y).unwrap();
+
+        for (c, expected) in coefficients.iter().zip([1.0, -2.0, 0.5]) {
+            assert_approx_eq!(c, expected, 1e-10);
+        }
+        assert_approx_eq!(polynomial_value(&coefficients, 3.0), 1.0 - 6.0 + 4.5, 1e-10);
+    }
+}
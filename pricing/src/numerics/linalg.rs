@@ -0,0 +1,168 @@
+//! Dense linear algebra of the matrix-heavy operations (correlation transforms, factorizations, PCA).
+//! With the `blas` feature the factorizations are LAPACK's by ndarray-linalg and the matrix products of
+//! ndarray's `dot` are BLAS's, otherwise all of them are pure Rust.
+
+#[cfg(feature = "blas")]
+use ndarray::s;
+#[cfg(not(feature = "blas"))]
+use ndarray::Axis;
+use ndarray::{Array1, Array2, ArrayBase, Data, Ix2};
+#[cfg(feature = "blas")]
+use ndarray_linalg::{Cholesky, Eigh, UPLO};
+
+/// The matrix product $A B$, by BLAS's `dgemm` with the `blas` feature.
+pub fn matmul<S, T>(a: &ArrayBase<S, Ix2>, b: &ArrayBase<T, Ix2>) -> Array2<f64>
+where
+    S: Data<Elem = f64>,
+    T: Data<Elem = f64>,
+{
+    a.dot(b)
+}
+
+/// The lower triangular Cholesky factor L with $L L^T = A$ of a symmetric positive definite matrix,
+/// or None if the matrix is not positive definite. Only the lower triangle of the matrix is read.
+/// See https://en.wikipedia.org/wiki/Cholesky_decomposition
+pub fn cholesky(matrix: &Array2<f64>) -> Option<Array2<f64>> {
+    let n = matrix.nrows();
+    assert_eq!(matrix.shape(), &[n, n]);
+    #[cfg(feature = "blas")]
+    return matrix.cholesky(UPLO::Lower).ok();
+    #[cfg(not(feature = "blas"))]
+    cholesky_rust(matrix)
+}
+
+#[cfg(not(feature = "blas"))]
+fn cholesky_rust(matrix: &Array2<f64>) -> Option<Array2<f64>> {
+    let n = matrix.nrows();
+    let mut factor = Array2::<f64>::zeros((n, n));
+    for j in 0..n {
+        let diagonal = matrix[[j, j]] - (0..j).map(|k| factor[[j, k]].powi(2)).sum::<f64>();
+        if diagonal <= 0.0 {
+            return None;
+        }
+        factor[[j, j]] = diagonal.sqrt();
+        for i in j + 1..n {
+            let off_diagonal =
+                matrix[[i, j]] - (0..j).map(|k| factor[[i, k]] * factor[[j, k]]).sum::<f64>();
+            factor[[i, j]] = off_diagonal / factor[[j, j]];
+        }
+    }
+    Some(factor)
+}
+
+/// Eigenvalues and eigenvectors (as columns) of a symmetric matrix, sorted by decreasing eigenvalue,
+/// by LAPACK's `dsyevd` with the `blas` feature and otherwise by the cyclic Jacobi method.
+/// See https://en.wikipedia.org/wiki/Jacobi_eigenvalue_algorithm
+pub fn symmetric_eigen(matrix: &Array2<f64>) -> (Array1<f64>, Array2<f64>) {
+    let n = matrix.nrows();
+    assert_eq!(matrix.shape(), &[n, n]);
+    #[cfg(feature = "blas")]
+    {
+        // LAPACK sorts the eigenvalues increasingly, and fails only if the iteration does not converge
+        let (eigenvalues, eigenvectors) = matrix
+            .eigh(UPLO::Lower)
+            .expect("the eigendecomposition of a symmetric matrix converges");
+        (
+            eigenvalues.slice(s![..;-1]).to_owned(),
+            eigenvectors.slice(s![.., ..;-1]).to_owned(),
+        )
+    }
+    #[cfg(not(feature = "blas"))]
+    jacobi_eigen(matrix)
+}
+
+#[cfg(not(feature = "blas"))]
+fn jacobi_eigen(matrix: &Array2<f64>) -> (Array1<f64>, Array2<f64>) {
+    let n = matrix.nrows();
+
+    let mut a = matrix.to_owned();
+    let mut v = Array2::<f64>::eye(n);
+    let scale = a.iter().map(|x| x * x).sum::<f64>().max(f64::MIN_POSITIVE);
+
+    for _sweep in 0..100 {
+        let off_diagonal: f64 = (0..n)
+            .flat_map(|p| (p + 1..n).map(move |q| (p, q)))
+            .map(|(p, q)| a[[p, q]].powi(2))
+            .sum();
+        if off_diagonal <= 1e-30 * scale {
+            break;
+        }
+
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[[p, q]] == 0.0 {
+                    continue;
+                }
+                // the rotation angle annihilating a[p, q]
+                let theta = (a[[q, q]] - a[[p, p]]) / (2.0 * a[[p, q]]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+
+                for k in 0..n {
+                    let (akp, akq) = (a[[k, p]], a[[k, q]]);
+                    a[[k, p]] = c * akp - s * akq;
+                    a[[k, q]] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let (apk, aqk) = (a[[p, k]], a[[q, k]]);
+                    a[[p, k]] = c * apk - s * aqk;
+                    a[[q, k]] = s * apk + c * aqk;
+                }
+                for k in 0..n {
+                    let (vkp, vkq) = (v[[k, p]], v[[k, q]]);
+                    v[[k, p]] = c * vkp - s * vkq;
+                    v[[k, q]] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|i, j| a[[*j, *j]].total_cmp(&a[[*i, *i]]));
+
+    let eigenvalues = Array1::from_iter(order.iter().map(|i| a[[*i, *i]]));
+    let eigenvectors = v.select(Axis(1), &order);
+    (eigenvalues, eigenvectors)
+}
+
+/// The solution x of $L L^T x = b$ for the Cholesky factor L by forward and back substitution.
+pub fn cholesky_solve(factor: &Array2<f64>, b: &Array1<f64>) -> Array1<f64> {
+    let n = factor.nrows();
+    assert_eq!(b.len(), n);
+    let mut y = Array1::<f64>::zeros(n);
+    for i in 0..n {
+        let partial: f64 = (0..i).map(|k| factor[[i, k]] * y[k]).sum();
+        y[i] = (b[i] - partial) / factor[[i, i]];
+    }
+    let mut x = Array1::<f64>::zeros(n);
+    for i in (0..n).rev() {
+        let partial: f64 = (i + 1..n).map(|k| factor[[k, i]] * x[k]).sum();
+        x[i] = (y[i] - partial) / factor[[i, i]];
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::arr2;
+
+    #[test]
+    fn cholesky_factorization() {
+        let covariance = arr2(&[[4.0, 2.0, 0.4], [2.0, 2.0, 0.5], [0.4, 0.5, 1.0]]);
+        let factor = cholesky(&covariance).unwrap();
+        assert_eq!(factor[[0, 1]], 0.0);
+        let reconstructed = matmul(&factor, &factor.t());
+        for (x, y) in reconstructed.iter().zip(covariance.iter()) {
+            assert!((x - y).abs() < 1e-14);
+        }
+        assert!(cholesky(&arr2(&[[1.0, 2.0], [2.0, 1.0]])).is_none());
+
+        let b = ndarray::arr1(&[1.0, -2.0, 0.5]);
+        let x = cholesky_solve(&factor, &b);
+        for (x, y) in covariance.dot(&x).iter().zip(b.iter()) {
+            assert!((x - y).abs() < 1e-14);
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use ndarray::{Array1, Array2};
+
+/// The optimum of a linear program with the dual values (shadow prices) of its constraints.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinearProgramSolution {
+    pub x: Array1<f64>,
+    pub duals: Array1<f64>,
+    pub objective: f64,
+}
+
+/// Maximizes $c^T x$ subject to $A x \le b$ and $x \ge 0$ for $b \ge 0$ by the simplex method with
+/// Bland's rule, which cannot cycle. Returns None if b has negative entries, i.e. the origin is infeasible,
+/// or if the objective is unbounded.
+/// The program $\min b^T y$ subject to $A^T y \ge c$, $y \ge 0$ is solved by the duals.
+/// See https://en.wikipedia.org/wiki/Simplex_algorithm
+pub fn simplex_maximize(
+    c: &Array1<f64>,
+    a: &Array2<f64>,
+    b: &Array1<f64>,
+) -> Option<LinearProgramSolution> {
+    let (m, n) = a.dim();
+    assert_eq!(c.len(), n);
+    assert_eq!(b.len(), m);
+    if b.iter().any(|bi| *bi < 0.0) {
+        return None;
+    }
+    let eps = 1e-12;
+
+    // the constraint rows [A | I | b] and the objective row [-c | 0 | 0]
+    let mut tableau = Array2::<f64>::zeros((m + 1, n + m + 1));
+    for i in 0..m {
+        for j in 0..n {
+            tableau[[i, j]] = a[[i, j]];
+        }
+        tableau[[i, n + i]] = 1.0;
+        tableau[[i, n + m]] = b[i];
+    }
+    for j in 0..n {
+        tableau[[m, j]] = -c[j];
+    }
+    let mut basis: Vec<usize> = (n..n + m).collect();
+
+    // enter by the lowest index with a negative reduced cost, leave by the minimum ratio
+    while let Some(entering) = (0..n + m).find(|j| tableau[[m, *j]] < -eps) {
+        let leaving = (0..m)
+            .filter(|i| tableau[[*i, entering]] > eps)
+            .min_by(|i, k| {
+                let ratio_i = tableau[[*i, n + m]] / tableau[[*i, entering]];
+                let ratio_k = tableau[[*k, n + m]] / tableau[[*k, entering]];
+                ratio_i.total_cmp(&ratio_k).then(basis[*i].cmp(&basis[*k]))
+            })?;
+
+        let pivot = tableau[[leaving, entering]];
+        tableau.row_mut(leaving).mapv_inplace(|v| v / pivot);
+        let pivot_row = tableau.row(leaving).to_owned();
+        for i in 0..=m {
+            if i != leaving {
+                let factor = tableau[[i, entering]];
+                if factor != 0.0 {
+                    tableau.row_mut(i).scaled_add(-factor, &pivot_row);
+                }
+            }
+        }
+        basis[leaving] = entering;
+    }
+
+    let mut x = Array1::zeros(n);
+    for (i, j) in basis.iter().enumerate() {
+        if *j < n {
+            x[*j] = tableau[[i, n + m]];
+        }
+    }
+    Some(LinearProgramSolution {
+        x,
+        duals: (0..m).map(|i| tableau[[m, n + i]]).collect(),
+        objective: tableau[[m, n + m]],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use ndarray::{arr1, arr2};
+
+    #[test]
+    fn textbook_program() {
+        // max 3x + 5y s.t. x <= 4, 2y <= 12, 3x + 2y <= 18 has the optimum 36 at (2, 6)
+        let c = arr1(&[3.0, 5.0]);
+        let a = arr2(&[[1.0, 0.0], [0.0, 2.0], [3.0, 2.0]]);
+        let b = arr1(&[4.0, 12.0, 18.0]);
+        let solution = simplex_maximize(&c, &a, &b).unwrap();
+        assert_approx_eq!(solution.objective, 36.0, 1e-12);
+        assert_approx_eq!(solution.x[0], 2.0, 1e-12);
+        assert_approx_eq!(solution.x[1], 6.0, 1e-12);
+        // strong duality
+        assert_approx_eq!(solution.duals.dot(&b), 36.0, 1e-12);
+        assert_approx_eq!(solution.duals[0], 0.0, 1e-12);
+        assert_approx_eq!(solution.duals[1], 1.5, 1e-12);
+        assert_approx_eq!(solution.duals[2], 1.0, 1e-12);
+
+        let unbounded = arr2(&[[1.0, -1.0]]);
+        assert!(simplex_maximize(&c, &unbounded, &arr1(&[1.0])).is_none());
+        assert!(simplex_maximize(&c, &a, &arr1(&[4.0, -1.0, 18.0])).is_none());
+    }
+}
@@ -0,0 +1,19 @@
+pub mod banded;
+#[cfg(feature = "mc")]
+pub mod calibration;
+pub mod correlation;
+pub mod dual;
+pub mod evaluation_cache;
+pub mod factor_risk;
+pub mod interpolation;
+pub mod least_squares;
+pub mod linalg;
+pub mod linear_programming;
+pub mod normal;
+#[cfg(feature = "mc")]
+pub mod parameter_uncertainty;
+pub mod pca;
+pub mod quadrature;
+pub mod solvers;
+#[cfg(feature = "parallel")]
+pub mod summation;
@@ -0,0 +1,34 @@
+use std::sync::OnceLock;
+
+use probability::distribution::{Continuous, Distribution, Gaussian};
+
+/// The standard normal distribution, constructed once.
+fn standard_normal() -> &'static Gaussian {
+    static STANDARD_NORMAL: OnceLock<Gaussian> = OnceLock::new();
+    STANDARD_NORMAL.get_or_init(|| Gaussian::new(0.0, 1.0))
+}
+
+/// The standard normal cumulative distribution function.
+pub(crate) fn cdf(d: f64) -> f64 {
+    standard_normal().distribution(d)
+}
+
+/// The standard normal density.
+pub(crate) fn pdf(d: f64) -> f64 {
+    standard_normal().density(d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn normal_cdf() {
+        let center_value = cdf(0.0);
+        assert_eq!(center_value, 0.5);
+
+        let sigma_top = cdf(1.0); // mu + 1 sigma
+        assert_approx_eq!(sigma_top, 0.8413, 0.0001); // table value for 1.0
+    }
+}
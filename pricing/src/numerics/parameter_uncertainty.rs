@@ -0,0 +1,232 @@
+use ndarray::{Array1, Array2};
+use rand::Rng;
+use rand_distr::StandardNormal;
+use rayon::prelude::*;
+
+use crate::common::results::PricingError;
+use crate::numerics::calibration::CalibrationReport;
+use crate::numerics::linalg::cholesky;
+
+/// The estimation uncertainty of model parameters.
+#[derive(Clone, Debug)]
+pub enum ParameterUncertainty {
+    /// the asymptotic normal distribution $N(\hat\theta, \Sigma)$ of the estimate, e.g. of a calibration,
+    /// with the Cholesky factor of the covariance
+    Gaussian {
+        estimate: Array1<f64>,
+        cholesky_factor: Array2<f64>,
+    },
+    /// parameter sets re-estimated on resampled data, e.g. a bootstrap of the history
+    Resampled(Vec<Vec<f64>>),
+}
+
+impl ParameterUncertainty {
+    /// The normal distribution of the estimate with the covariance, which must be positive definite.
+    pub fn gaussian(estimate: Vec<f64>, covariance: &Array2<f64>) -> Result<Self, PricingError> {
+        PricingError::check(
+            covariance.dim() == (estimate.len(), estimate.len()),
+            "covariance dimension",
+            covariance.nrows() as f64,
+        )?;
+        let cholesky_factor = cholesky(covariance).ok_or_else(|| {
+            PricingError::InvalidParameter("covariance is not positive definite".to_string())
+        })?;
+        Ok(Self::Gaussian {
+            estimate: Array1::from(estimate),
+            cholesky_factor,
+        })
+    }
+
+    /// The asymptotic distribution of the calibrated parameters, or None if the fit does not determine
+    /// their covariance.
+    pub fn from_calibration(report: &CalibrationReport) -> Option<Self> {
+        let covariance = report.parameter_covariance.as_ref()?;
+        Self::gaussian(report.parameters().to_vec(), covariance).ok()
+    }
+
+    /// The parameters estimated on `nr_resamples` bootstrap samples of the observations drawn with
+    /// replacement, e.g. the volatility of historical returns.
+    /// See https://en.wikipedia.org/wiki/Bootstrapping_(statistics)
+    pub fn bootstrap<SeedRng, T>(
+        observations: &[T],
+        nr_resamples: usize,
+        seed_nr: u64,
+        estimator: impl Fn(&[T]) -> Vec<f64>,
+    ) -> Self
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+        T: Clone,
+    {
+        assert!(!observations.is_empty());
+        let mut rng = SeedRng::seed_from_u64(seed_nr);
+        let samples = (0..nr_resamples)
+            .map(|_| {
+                let resample: Vec<T> = (0..observations.len())
+                    .map(|_| observations[rng.gen_range(0..observations.len())].clone())
+                    .collect();
+                estimator(&resample)
+            })
+            .collect();
+        Self::Resampled(samples)
+    }
+
+    /// The parameter sets: draws of the normal distribution or all resampled estimates.
+    pub fn samples<SeedRng>(&self, nr_samples: usize, seed_nr: u64) -> Vec<Vec<f64>>
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        match self {
+            ParameterUncertainty::Gaussian {
+                estimate,
+                cholesky_factor,
+            } => {
+                let mut rng = SeedRng::seed_from_u64(seed_nr);
+                (0..nr_samples)
+                    .map(|_| {
+                        let normals: Array1<f64> = (0..estimate.len())
+                            .map(|_| rng.sample::<f64, _>(StandardNormal))
+                            .collect();
+                        (estimate + &cholesky_factor.dot(&normals)).to_vec()
+                    })
+                    .collect()
+            }
+            ParameterUncertainty::Resampled(samples) => samples.clone(),
+        }
+    }
+
+    /// The prices of the parameter samples, priced in parallel. Parameter sets the pricer rejects,
+    /// e.g. a negative volatility drawn from the normal distribution, are counted as failures.
+    pub fn propagate<SeedRng>(
+        &self,
+        nr_samples: usize,
+        seed_nr: u64,
+        price: impl Fn(&[f64]) -> Result<f64, PricingError> + Sync,
+    ) -> Result<PriceDistribution, PricingError>
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        let results: Vec<Result<f64, PricingError>> = self
+            .samples::<SeedRng>(nr_samples, seed_nr)
+            .par_iter()
+            .map(|parameters| price(parameters))
+            .collect();
+        let nr_failures = results.iter().filter(|result| result.is_err()).count();
+        let mut prices: Vec<f64> = results.into_iter().filter_map(Result::ok).collect();
+        if prices.is_empty() {
+            return Err(PricingError::NoPaths);
+        }
+        prices.sort_by(f64::total_cmp);
+        Ok(PriceDistribution {
+            prices,
+            nr_failures,
+        })
+    }
+}
+
+/// The distribution of the prices of the parameter samples.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PriceDistribution {
+    /// the prices in increasing order
+    pub prices: Vec<f64>,
+    /// the number of parameter samples the pricer rejected
+    pub nr_failures: usize,
+}
+
+impl PriceDistribution {
+    pub fn mean(&self) -> f64 {
+        self.prices.iter().sum::<f64>() / self.prices.len() as f64
+    }
+
+    pub fn std_dev(&self) -> f64 {
+        let n = self.prices.len();
+        if n < 2 {
+            return 0.0;
+        }
+        let mean = self.mean();
+        (self.prices.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / (n - 1) as f64).sqrt()
+    }
+
+    /// The empirical quantile of the prices at the level in [0, 1], interpolated linearly.
+    pub fn quantile(&self, level: f64) -> f64 {
+        let position = level.clamp(0.0, 1.0) * (self.prices.len() - 1) as f64;
+        let (lower, upper) = (position.floor() as usize, position.ceil() as usize);
+        let weight = position - lower as f64;
+        (1.0 - weight) * self.prices[lower] + weight * self.prices[upper]
+    }
+
+    /// The central band of the prices with the confidence level, e.g. 0.9 for the 5% and 95% quantiles.
+    pub fn confidence_band(&self, confidence_level: f64) -> (f64, f64) {
+        let tail = 0.5 * (1.0 - confidence_level);
+        (self.quantile(tail), self.quantile(1.0 - tail))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::{BlackScholesMerton, OptionPrice};
+    use crate::common::models::DerivativeParameter;
+    use assert_approx_eq::assert_approx_eq;
+    use ndarray::arr2;
+    use rand::SeedableRng;
+
+    #[test]
+    fn gaussian_propagation() {
+        // a linear pricer maps the parameter covariance to the price variance $w^T \Sigma w$
+        let covariance = arr2(&[[0.04, 0.01], [0.01, 0.09]]);
+        let uncertainty = ParameterUncertainty::gaussian(vec![1.0, 2.0], &covariance).unwrap();
+        let distribution = uncertainty
+            .propagate::<rand_hc::Hc128Rng>(20_000, 42, |x| Ok(3.0 * x[0] - x[1]))
+            .unwrap();
+        assert_eq!(distribution.nr_failures, 0);
+        assert_approx_eq!(distribution.mean(), 1.0, 0.02);
+        assert_approx_eq!(
+            distribution.std_dev(),
+            (9.0 * 0.04 - 6.0 * 0.01 + 0.09_f64).sqrt(),
+            0.01
+        );
+        let (lower, upper) = distribution.confidence_band(0.9);
+        assert_approx_eq!(upper - 1.0, 1.0 - lower, 0.03);
+        assert_approx_eq!(upper - 1.0, 1.645 * 0.39_f64.sqrt(), 0.03);
+
+        // rejected parameter sets are counted
+        let positive = uncertainty
+            .propagate::<rand_hc::Hc128Rng>(1_000, 42, |x| {
+                PricingError::check(x[0] > 1.0, "parameter", x[0]).map(|_| x[0])
+            })
+            .unwrap();
+        assert!(positive.nr_failures > 400 && positive.nr_failures < 600);
+        assert!(ParameterUncertainty::gaussian(vec![1.0], &covariance).is_err());
+    }
+
+    #[test]
+    fn bootstrap_volatility_band() {
+        // a year of daily returns with 20% volatility, annualized with 252 days
+        let mut rng = rand_hc::Hc128Rng::seed_from_u64(3);
+        let returns: Vec<f64> = (0..252)
+            .map(|_| 0.2 / 252.0_f64.sqrt() * rng.sample::<f64, _>(StandardNormal))
+            .collect();
+        let volatility = |returns: &[f64]| {
+            let n = returns.len() as f64;
+            let mean = returns.iter().sum::<f64>() / n;
+            let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+            vec![(252.0 * variance).sqrt()]
+        };
+        let uncertainty =
+            ParameterUncertainty::bootstrap::<rand_hc::Hc128Rng, _>(&returns, 500, 7, volatility);
+        let call = |x: &[f64]| {
+            PricingError::check(x[0] > 0.0, "volatility", x[0])?;
+            Ok(BlackScholesMerton::call(&DerivativeParameter::new(
+                100.0, 100.0, 1.0, 0.02, x[0],
+            )))
+        };
+        let distribution = uncertainty
+            .propagate::<rand_hc::Hc128Rng>(0, 0, call)
+            .unwrap();
+        assert_eq!(distribution.prices.len(), 500);
+        let estimate = call(&volatility(&returns)).unwrap();
+        let (lower, upper) = distribution.confidence_band(0.95);
+        assert!(lower < estimate && estimate < upper);
+        assert!(upper - lower > 0.1);
+    }
+}
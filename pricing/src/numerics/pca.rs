@@ -0,0 +1,197 @@
+use ndarray::{s, Array1, Array2, Axis};
+
+#[cfg(feature = "mc")]
+use crate::simulation::distributions::CorrelationStructure;
+
+pub use crate::numerics::linalg::symmetric_eigen;
+
+/// Principal component analysis, e.g. of yield curve changes into level, slope and curvature factors.
+/// See https://en.wikipedia.org/wiki/Principal_component_analysis
+#[derive(Clone, Debug)]
+pub struct PrincipalComponents {
+    means: Array1<f64>,
+    /// the variances of the components in decreasing order
+    eigenvalues: Array1<f64>,
+    /// the (unit) eigenvectors as columns
+    loadings: Array2<f64>,
+}
+
+impl PrincipalComponents {
+    /// PCA of a symmetric covariance matrix, assuming zero means.
+    pub fn from_covariance(covariance: &Array2<f64>) -> Self {
+        let (eigenvalues, loadings) = symmetric_eigen(covariance);
+        Self {
+            means: Array1::zeros(covariance.nrows()),
+            // negative eigenvalues stem from rounding or a non positive semi-definite input
+            eigenvalues: eigenvalues.mapv(|e| e.max(0.0)),
+            loadings,
+        }
+    }
+
+    /// PCA of the sample covariance of the observations, where each row is an observation
+    /// (e.g. daily returns or curve shifts) and each column a variable (e.g. a tenor).
+    /// Returns None for less than two observations.
+    pub fn from_observations(observations: &Array2<f64>) -> Option<Self> {
+        let nr_observations = observations.nrows();
+        if nr_observations < 2 {
+            return None;
+        }
+        let means = observations.mean_axis(Axis(0))?;
+        let centered = observations - &means;
+        let covariance = centered.t().dot(&centered) / (nr_observations - 1) as f64;
+
+        Some(Self {
+            means,
+            ..Self::from_covariance(&covariance)
+        })
+    }
+
+    pub fn dim(&self) -> usize {
+        self.means.len()
+    }
+
+    pub fn means(&self) -> &Array1<f64> {
+        &self.means
+    }
+
+    /// The variances of the components in decreasing order.
+    pub fn explained_variance(&self) -> &Array1<f64> {
+        &self.eigenvalues
+    }
+
+    /// The proportions of the total variance explained by the components.
+    pub fn explained_variance_ratio(&self) -> Array1<f64> {
+        let total = self.eigenvalues.sum();
+        if total == 0.0 {
+            return Array1::zeros(self.dim());
+        }
+        &self.eigenvalues / total
+    }
+
+    /// The smallest number of components explaining at least the `threshold` (e.g. 0.99) of the variance.
+    pub fn nr_components_for(&self, threshold: f64) -> usize {
+        let mut cumulative = 0.0;
+        for (idx, ratio) in self.explained_variance_ratio().iter().enumerate() {
+            cumulative += ratio;
+            if cumulative >= threshold {
+                return idx + 1;
+            }
+        }
+        self.dim()
+    }
+
+    /// The loadings (unit eigenvectors as columns) of the first `nr_components` components.
+    pub fn loadings(&self, nr_components: usize) -> Array2<f64> {
+        assert!(nr_components <= self.dim());
+        self.loadings.slice(s![.., ..nr_components]).to_owned()
+    }
+
+    /// The scores of an observation on the first `nr_components` components.
+    pub fn project(&self, observation: &Array1<f64>, nr_components: usize) -> Array1<f64> {
+        self.loadings(nr_components)
+            .t()
+            .dot(&(observation - &self.means))
+    }
+
+    /// The observation given by the scores on the leading components, e.g. for a rate scenario
+    /// of a parallel shift by the level factor.
+    pub fn reconstruct(&self, scores: &Array1<f64>) -> Array1<f64> {
+        &self.means + &self.loadings(scores.len()).dot(scores)
+    }
+
+    /// Compresses the covariance into a factor model with the first `nr_factors` components
+    /// (scaled by their volatility) and the residual variances as idiosyncratic part,
+    /// for the factor model Monte Carlo simulation.
+    #[cfg(feature = "mc")]
+    pub fn factor_model(&self, nr_factors: usize) -> CorrelationStructure {
+        let factor_volas = self.eigenvalues.slice(s![..nr_factors]).mapv(f64::sqrt);
+        let loadings = self.loadings(nr_factors) * &factor_volas;
+
+        let variances = self
+            .loadings
+            .dot(&Array2::from_diag(&self.eigenvalues))
+            .dot(&self.loadings.t())
+            .into_diag();
+        let idiosyncratic = (variances - loadings.mapv(|b| b * b).sum_axis(Axis(1)))
+            .mapv(|residual| residual.max(0.0).sqrt());
+
+        CorrelationStructure::factor_model(loadings, idiosyncratic)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use ndarray::{arr1, arr2};
+
+    #[test]
+    fn eigen_decomposition() {
+        let matrix = arr2(&[[4.0, 1.0, 0.5], [1.0, 3.0, 0.2], [0.5, 0.2, 1.0]]);
+        let (eigenvalues, eigenvectors) = symmetric_eigen(&matrix);
+
+        assert!(eigenvalues[0] >= eigenvalues[1] && eigenvalues[1] >= eigenvalues[2]);
+        assert_approx_eq!(eigenvalues.sum(), 8.0, 1e-12);
+        let reconstructed = eigenvectors
+            .dot(&Array2::from_diag(&eigenvalues))
+            .dot(&eigenvectors.t());
+        for (x, y) in reconstructed.iter().zip(matrix.iter()) {
+            assert_approx_eq!(x, y, 1e-12);
+        }
+    }
+
+    #[test]
+    fn curve_shift_factors() {
+        // shifts of 4 tenors driven by a level and a (smaller) slope factor
+        let level = arr1(&[1.0, 1.0, 1.0, 1.0]);
+        let slope = arr1(&[-1.5, -0.5, 0.5, 1.5]);
+        let mut observations = Array2::zeros((40, 4));
+        for (i, mut row) in observations.rows_mut().into_iter().enumerate() {
+            let x = i as f64;
+            let shift = &level * (x * 0.7).sin() * 3.0 + &slope * (x * 1.3).cos() * 0.5;
+            row.assign(&shift);
+        }
+
+        let pca = PrincipalComponents::from_observations(&observations).unwrap();
+        assert_eq!(pca.nr_components_for(0.999), 2);
+        assert_approx_eq!(pca.explained_variance_ratio().sum(), 1.0, 1e-12);
+        // the first component is the parallel shift
+        let first = pca.loadings(1);
+        for value in first.iter() {
+            assert_approx_eq!(value.abs(), 0.5, 1e-2);
+        }
+
+        let observation = observations.row(7).to_owned();
+        let reconstructed = pca.reconstruct(&pca.project(&observation, 2));
+        for (x, y) in reconstructed.iter().zip(observation.iter()) {
+            assert_approx_eq!(x, y, 1e-10);
+        }
+
+        assert!(PrincipalComponents::from_observations(&Array2::zeros((1, 4))).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "mc")]
+    fn compressed_factor_model() {
+        let covariance = arr2(&[[1.0, 0.8, 0.6], [0.8, 1.0, 0.7], [0.6, 0.7, 1.0]]);
+        let pca = PrincipalComponents::from_covariance(&covariance);
+
+        let factor_model = pca.factor_model(1);
+        assert_eq!(factor_model.dim(), 3);
+        assert_eq!(factor_model.nr_factors(), 4);
+        if let CorrelationStructure::Factor {
+            loadings,
+            idiosyncratic,
+        } = factor_model
+        {
+            // the factor model preserves the variances
+            let variances =
+                loadings.mapv(|b| b * b).sum_axis(Axis(1)) + idiosyncratic.mapv(|d| d * d);
+            for variance in variances.iter() {
+                assert_approx_eq!(variance, 1.0, 1e-12);
+            }
+        } else {
+            panic!("expected a factor model");
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use ndarray::Array2;
+
+use crate::numerics::pca::symmetric_eigen;
+
+/// Gaussian quadrature rule $\int f(x) w(x) dx \approx \sum_i w_i f(x_i)$ with n nodes, exact for
+/// polynomials of degree up to 2n - 1. The nodes and weights are computed by the Golub-Welsch algorithm
+/// from the Jacobi matrix of the orthogonal polynomials' three-term recurrence.
+/// See https://en.wikipedia.org/wiki/Gaussian_quadrature
+#[derive(Clone, Debug)]
+pub struct GaussQuadrature {
+    pub nodes: Vec<f64>,
+    pub weights: Vec<f64>,
+}
+
+impl GaussQuadrature {
+    /// The rule of the Jacobi matrix with the `diagonal` and `off_diagonal` recurrence coefficients
+    /// and the total mass `mu0` of the weight function.
+    fn golub_welsch(diagonal: &[f64], off_diagonal: &[f64], mu0: f64) -> Self {
+        let n = diagonal.len();
+        let jacobi_matrix = Array2::from_shape_fn((n, n), |(i, j)| match i.abs_diff(j) {
+            0 => diagonal[i],
+            1 => off_diagonal[i.min(j)],
+            _ => 0.0,
+        });
+        let (eigenvalues, eigenvectors) = symmetric_eigen(&jacobi_matrix);
+
+        let mut rule: Vec<(f64, f64)> = (0..n)
+            .map(|k| (eigenvalues[k], mu0 * eigenvectors[[0, k]].powi(2)))
+            .collect();
+        rule.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let (nodes, weights) = rule.into_iter().unzip();
+        Self { nodes, weights }
+    }
+
+    /// Gauss-Legendre rule for $\int_{-1}^1 f(x) dx$.
+    pub fn legendre(n: usize) -> Self {
+        let off_diagonal: Vec<f64> = (1..n)
+            .map(|k| k as f64 / ((4 * k * k - 1) as f64).sqrt())
+            .collect();
+        Self::golub_welsch(&vec![0.0; n], &off_diagonal, 2.0)
+    }
+
+    /// Gauss-Hermite rule for $\int_{-\infty}^\infty f(x) e^{-x^2} dx$.
+    pub fn hermite(n: usize) -> Self {
+        let off_diagonal: Vec<f64> = (1..n).map(|k| (k as f64 / 2.0).sqrt()).collect();
+        Self::golub_welsch(&vec![0.0; n], &off_diagonal, std::f64::consts::PI.sqrt())
+    }
+
+    /// Gauss-Laguerre rule for $\int_0^\infty f(x) e^{-x} dx$.
+    pub fn laguerre(n: usize) -> Self {
+        let diagonal: Vec<f64> = (0..n).map(|k| (2 * k + 1) as f64).collect();
+        let off_diagonal: Vec<f64> = (1..n).map(|k| k as f64).collect();
+        Self::golub_welsch(&diagonal, &off_diagonal, 1.0)
+    }
+
+    /// The quadrature sum $\sum_i w_i f(x_i)$.
+    pub fn integrate(&self, f: impl Fn(f64) -> f64) -> f64 {
+        self.nodes
+            .iter()
+            .zip(&self.weights)
+            .map(|(x, w)| w * f(*x))
+            .sum()
+    }
+
+    /// The integral $\int_a^b f(x) dx$ for a Legendre rule, mapped linearly from $[-1, 1]$.
+    pub fn integrate_on(&self, f: impl Fn(f64) -> f64, a: f64, b: f64) -> f64 {
+        let (center, half_width) = (0.5 * (a + b), 0.5 * (b - a));
+        half_width * self.integrate(|x| f(center + half_width * x))
+    }
+
+    /// The expectation $E[f(Z)]$ for a standard normal Z for a Hermite rule.
+    pub fn normal_expectation(&self, f: impl Fn(f64) -> f64) -> f64 {
+        self.integrate(|x| f(std::f64::consts::SQRT_2 * x)) / std::f64::consts::PI.sqrt()
+    }
+}
+
+/// The integral $\int_a^b f(x) dx$ by adaptive Simpson's rule, refining the intervals until the
+/// Richardson error estimate is below the tolerance or the maximal recursion depth is reached.
+/// See https://en.wikipedia.org/wiki/Adaptive_Simpson%27s_method
+pub fn adaptive_simpson(
+    f: impl Fn(f64) -> f64,
+    a: f64,
+    b: f64,
+    tolerance: f64,
+    max_depth: usize,
+) -> f64 {
+    struct Interval {
+        a: f64,
+        b: f64,
+        fa: f64,
+        fm: f64,
+        fb: f64,
+        whole: f64,
+    }
+
+    fn simpson(a: f64, b: f64, fa: f64, fm: f64, fb: f64) -> f64 {
+        (b - a) / 6.0 * (fa + 4.0 * fm + fb)
+    }
+
+    fn refine(f: &impl Fn(f64) -> f64, interval: Interval, tolerance: f64, depth: usize) -> f64 {
+        let Interval {
+            a,
+            b,
+            fa,
+            fm,
+            fb,
+            whole,
+        } = interval;
+        let m = 0.5 * (a + b);
+        let (lm, rm) = (0.5 * (a + m), 0.5 * (m + b));
+        let (flm, frm) = (f(lm), f(rm));
+        let left = simpson(a, m, fa, flm, fm);
+        let right = simpson(m, b, fm, frm, fb);
+        let error = left + right - whole;
+        if depth == 0 || error.abs() <= 15.0 * tolerance {
+            return left + right + error / 15.0;
+        }
+        refine(
+            f,
+            Interval {
+                a,
+                b: m,
+                fa,
+                fm: flm,
+                fb: fm,
+                whole: left,
+            },
+            0.5 * tolerance,
+            depth - 1,
+        ) + refine(
+            f,
+            Interval {
+                a: m,
+                b,
+                fa: fm,
+                fm: frm,
+                fb,
+                whole: right,
+            },
+            0.5 * tolerance,
+            depth - 1,
+        )
+    }
+
+    let (fa, fm, fb) = (f(a), f(0.5 * (a + b)), f(b));
+    let whole = simpson(a, b, fa, fm, fb);
+    refine(
+        &f,
+        Interval {
+            a,
+            b,
+            fa,
+            fm,
+            fb,
+            whole,
+        },
+        tolerance,
+        max_depth,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::BsmComputation;
+    use crate::common::models::DerivativeParameter;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn gauss_rules() {
+        let legendre = GaussQuadrature::legendre(5);
+        // exact up to degree 9
+        assert_approx_eq!(
+            legendre.integrate(|x| x.powi(8) + x.powi(3)),
+            2.0 / 9.0,
+            1e-13
+        );
+        assert_approx_eq!(legendre.integrate_on(|x| x * x, 0.0, 3.0), 9.0, 1e-12);
+        assert_approx_eq!(legendre.weights.iter().sum::<f64>(), 2.0, 1e-13);
+
+        let laguerre = GaussQuadrature::laguerre(8);
+        assert_approx_eq!(laguerre.integrate(|x| x.powi(3)), 6.0, 1e-10);
+
+        let hermite = GaussQuadrature::hermite(10);
+        assert_approx_eq!(hermite.normal_expectation(|z| z.powi(4)), 3.0, 1e-10);
+        assert_approx_eq!(hermite.normal_expectation(|z| z.powi(2)), 1.0, 1e-12);
+    }
+
+    #[test]
+    fn normal_expectation_of_call_payoff() {
+        let (spot, strike, t, rfr, vola): (f64, f64, f64, f64, f64) =
+            (100.0, 105.0, 1.0, 0.03, 0.2);
+        let payoff = |z: f64| {
+            let terminal = spot * ((rfr - 0.5 * vola * vola) * t + vola * t.sqrt() * z).exp();
+            (terminal - strike).max(0.0)
+        };
+        let expected = BsmComputation::new(&DerivativeParameter::new(spot, strike, t, rfr, vola))
+            .call()
+            * (rfr * t).exp();
+
+        // the kink of the payoff limits the accuracy of the Gauss-Hermite rule
+        let hermite = GaussQuadrature::hermite(40);
+        assert_approx_eq!(hermite.normal_expectation(payoff), expected, 0.1);
+        let forward = hermite.normal_expectation(|z| {
+            spot * ((rfr - 0.5 * vola * vola) * t + vola * t.sqrt() * z).exp()
+        });
+        assert_approx_eq!(forward, spot * (rfr * t).exp(), 1e-8);
+
+        let density = |z: f64| (-0.5 * z * z).exp() / (2.0 * std::f64::consts::PI).sqrt();
+        let simpson = adaptive_simpson(|z| payoff(z) * density(z), -10.0, 10.0, 1e-10, 30);
+        assert_approx_eq!(simpson, expected, 1e-8);
+    }
+
+    #[test]
+    fn adaptive_simpson_integrals() {
+        let integral = adaptive_simpson(f64::sin, 0.0, std::f64::consts::PI, 1e-12, 50);
+        assert_approx_eq!(integral, 2.0, 1e-11);
+        assert_approx_eq!(
+            adaptive_simpson(|x| x.sqrt(), 0.0, 1.0, 1e-10, 50),
+            2.0 / 3.0,
+            1e-8
+        );
+    }
+}
@@ -0,0 +1,425 @@
+/// The stopping criteria of the iterative solvers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SolverOptions {
+    /// the tolerance on the argument, e.g. the width of the bracket or simplex
+    pub x_tolerance: f64,
+    /// the tolerance on the function value, e.g. the residual of a root
+    pub f_tolerance: f64,
+    pub max_iterations: usize,
+}
+
+impl Default for SolverOptions {
+    fn default() -> Self {
+        Self {
+            x_tolerance: 1e-12,
+            f_tolerance: 1e-12,
+            max_iterations: 100,
+        }
+    }
+}
+
+impl SolverOptions {
+    pub fn new(x_tolerance: f64, f_tolerance: f64, max_iterations: usize) -> Self {
+        Self {
+            x_tolerance,
+            f_tolerance,
+            max_iterations,
+        }
+    }
+}
+
+/// The result of a solver with its convergence diagnostics.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Solution<X> {
+    /// the root or the minimizer
+    pub x: X,
+    /// the function value at x
+    pub value: f64,
+    pub iterations: usize,
+    pub function_evaluations: usize,
+    /// false if the maximal number of iterations was reached before the tolerances
+    pub converged: bool,
+}
+
+impl<X> Solution<X> {
+    /// The solution if the solver converged.
+    pub fn converged(self) -> Option<X> {
+        self.converged.then_some(self.x)
+    }
+}
+
+/// Counts the evaluations of a function.
+struct Counted<F> {
+    f: F,
+    evaluations: usize,
+}
+
+impl<F> Counted<F> {
+    fn new(f: F) -> Self {
+        Self { f, evaluations: 0 }
+    }
+
+    fn call<X: ?Sized, Y>(&mut self, x: &X) -> Y
+    where
+        F: Fn(&X) -> Y,
+    {
+        self.evaluations += 1;
+        (self.f)(x)
+    }
+}
+
+/// The root of f in the bracket [lower, upper] by Brent's method, combining bisection, secant
+/// and inverse quadratic interpolation steps. Returns None if f(lower) and f(upper) have the same sign.
+/// See https://en.wikipedia.org/wiki/Brent%27s_method
+pub fn brent(
+    f: impl Fn(f64) -> f64,
+    lower: f64,
+    upper: f64,
+    options: &SolverOptions,
+) -> Option<Solution<f64>> {
+    let mut f = Counted::new(|x: &f64| f(*x));
+    let (mut a, mut b) = (lower, upper);
+    let (mut fa, mut fb) = (f.call(&a), f.call(&b));
+    if fa.signum() == fb.signum() && fa != 0.0 && fb != 0.0 {
+        return None;
+    }
+    let (mut c, mut fc) = (a, fa);
+    let (mut d, mut e) = (b - a, b - a);
+
+    for iteration in 0..=options.max_iterations {
+        if fb.signum() == fc.signum() && fb != 0.0 {
+            c = a;
+            fc = fa;
+            d = b - a;
+            e = d;
+        }
+        if fc.abs() < fb.abs() {
+            a = b;
+            b = c;
+            c = a;
+            fa = fb;
+            fb = fc;
+            fc = fa;
+        }
+        let tolerance = 2.0 * f64::EPSILON * b.abs() + 0.5 * options.x_tolerance;
+        let half_width = 0.5 * (c - b);
+        if fb.abs() <= options.f_tolerance || half_width.abs() <= tolerance {
+            return Some(Solution {
+                x: b,
+                value: fb,
+                iterations: iteration,
+                function_evaluations: f.evaluations,
+                converged: true,
+            });
+        }
+        if iteration == options.max_iterations {
+            break;
+        }
+
+        if e.abs() >= tolerance && fa.abs() > fb.abs() {
+            let s = fb / fa;
+            let (mut p, mut q) = if a == c {
+                // secant step
+                (2.0 * half_width * s, 1.0 - s)
+            } else {
+                // inverse quadratic interpolation
+                let q = fa / fc;
+                let r = fb / fc;
+                (
+                    s * (2.0 * half_width * q * (q - r) - (b - a) * (r - 1.0)),
+                    (q - 1.0) * (r - 1.0) * (s - 1.0),
+                )
+            };
+            if p > 0.0 {
+                q = -q;
+            } else {
+                p = -p;
+            }
+            if 2.0 * p < (3.0 * half_width * q - (tolerance * q).abs()).min((e * q).abs()) {
+                e = d;
+                d = p / q;
+            } else {
+                d = half_width;
+                e = d;
+            }
+        } else {
+            d = half_width;
+            e = d;
+        }
+        a = b;
+        fa = fb;
+        b += if d.abs() > tolerance {
+            d
+        } else {
+            tolerance.copysign(half_width)
+        };
+        fb = f.call(&b);
+    }
+    Some(Solution {
+        x: b,
+        value: fb,
+        iterations: options.max_iterations,
+        function_evaluations: f.evaluations,
+        converged: false,
+    })
+}
+
+/// The root of f in the bracket [lower, upper] by Newton's method, where `f_df` returns the value and
+/// the derivative. Steps leaving the (shrinking) bracket or with a vanishing derivative fall back to
+/// bisection, such that the iteration converges for any continuous f.
+/// Returns None if f(lower) and f(upper) have the same sign.
+/// See https://en.wikipedia.org/wiki/Newton%27s_method
+pub fn newton(
+    f_df: impl Fn(f64) -> (f64, f64),
+    initial_guess: f64,
+    lower: f64,
+    upper: f64,
+    options: &SolverOptions,
+) -> Option<Solution<f64>> {
+    let mut f_df = Counted::new(|x: &f64| f_df(*x));
+    let (f_lower, f_upper) = (f_df.call(&lower).0, f_df.call(&upper).0);
+    if f_lower.signum() == f_upper.signum() && f_lower != 0.0 && f_upper != 0.0 {
+        return None;
+    }
+    // orient the bracket such that f(low) <= 0 <= f(high)
+    let (mut low, mut high) = if f_lower <= 0.0 {
+        (lower, upper)
+    } else {
+        (upper, lower)
+    };
+
+    let mut x = initial_guess.clamp(lower.min(upper), lower.max(upper));
+    for iteration in 0..=options.max_iterations {
+        let (value, derivative) = f_df.call(&x);
+        if value.abs() <= options.f_tolerance || (high - low).abs() <= options.x_tolerance {
+            return Some(Solution {
+                x,
+                value,
+                iterations: iteration,
+                function_evaluations: f_df.evaluations,
+                converged: true,
+            });
+        }
+        if iteration == options.max_iterations {
+            return Some(Solution {
+                x,
+                value,
+                iterations: iteration,
+                function_evaluations: f_df.evaluations,
+                converged: false,
+            });
+        }
+        if value < 0.0 {
+            low = x;
+        } else {
+            high = x;
+        }
+        let step = x - value / derivative;
+        x = if step.is_finite() && (step - low) * (step - high) < 0.0 {
+            step
+        } else {
+            0.5 * (low + high)
+        };
+    }
+    unreachable!()
+}
+
+/// The minimizer of a unimodal f on [lower, upper] by golden-section search.
+/// See https://en.wikipedia.org/wiki/Golden-section_search
+pub fn golden_section(
+    f: impl Fn(f64) -> f64,
+    lower: f64,
+    upper: f64,
+    options: &SolverOptions,
+) -> Solution<f64> {
+    let inv_phi = (5.0_f64.sqrt() - 1.0) / 2.0;
+    let mut f = Counted::new(|x: &f64| f(*x));
+    let (mut a, mut b) = (lower, upper);
+    let mut x1 = b - inv_phi * (b - a);
+    let mut x2 = a + inv_phi * (b - a);
+    let (mut f1, mut f2) = (f.call(&x1), f.call(&x2));
+
+    let mut iterations = 0;
+    while (b - a).abs() > options.x_tolerance && iterations < options.max_iterations {
+        iterations += 1;
+        if f1 < f2 {
+            b = x2;
+            x2 = x1;
+            f2 = f1;
+            x1 = b - inv_phi * (b - a);
+            f1 = f.call(&x1);
+        } else {
+            a = x1;
+            x1 = x2;
+            f1 = f2;
+            x2 = a + inv_phi * (b - a);
+            f2 = f.call(&x2);
+        }
+    }
+    let (x, value) = if f1 < f2 { (x1, f1) } else { (x2, f2) };
+    Solution {
+        x,
+        value,
+        iterations,
+        function_evaluations: f.evaluations,
+        converged: (b - a).abs() <= options.x_tolerance,
+    }
+}
+
+/// The (local) minimizer of f by the Nelder-Mead simplex method, starting from the simplex of the
+/// initial guess and its shifts by `step` in each coordinate. Converges when the function values
+/// of the simplex agree within the f tolerance and its vertices within the x tolerance.
+/// See https://en.wikipedia.org/wiki/Nelder%E2%80%93Mead_method
+pub fn nelder_mead(
+    f: impl Fn(&[f64]) -> f64,
+    initial_guess: &[f64],
+    step: f64,
+    options: &SolverOptions,
+) -> Solution<Vec<f64>> {
+    const REFLECTION: f64 = 1.0;
+    const EXPANSION: f64 = 2.0;
+    const CONTRACTION: f64 = 0.5;
+    const SHRINKAGE: f64 = 0.5;
+
+    let mut f = Counted::new(f);
+    let n = initial_guess.len();
+    let mut simplex: Vec<Vec<f64>> = (0..=n)
+        .map(|i| {
+            let mut vertex = initial_guess.to_vec();
+            if i > 0 {
+                vertex[i - 1] += step;
+            }
+            vertex
+        })
+        .collect();
+    let mut values: Vec<f64> = simplex.iter().map(|v| f.call(v.as_slice())).collect();
+    // the point x0 + t (x - x0)
+    let towards = |x0: &[f64], x: &[f64], t: f64| -> Vec<f64> {
+        x0.iter().zip(x).map(|(a, b)| a + t * (b - a)).collect()
+    };
+
+    let mut iterations = 0;
+    let mut converged = false;
+    while iterations < options.max_iterations {
+        let mut order: Vec<usize> = (0..=n).collect();
+        order.sort_by(|i, j| values[*i].total_cmp(&values[*j]));
+        simplex = order.iter().map(|i| simplex[*i].clone()).collect();
+        values = order.iter().map(|i| values[*i]).collect();
+
+        let f_spread = values[n] - values[0];
+        let x_spread = simplex[1..]
+            .iter()
+            .flat_map(|v| v.iter().zip(&simplex[0]).map(|(a, b)| (a - b).abs()))
+            .fold(0.0, f64::max);
+        if f_spread <= options.f_tolerance && x_spread <= options.x_tolerance {
+            converged = true;
+            break;
+        }
+        iterations += 1;
+
+        let centroid: Vec<f64> = (0..n)
+            .map(|k| simplex[..n].iter().map(|v| v[k]).sum::<f64>() / n as f64)
+            .collect();
+        let reflected = towards(&centroid, &simplex[n], -REFLECTION);
+        let f_reflected = f.call(reflected.as_slice());
+
+        if f_reflected < values[0] {
+            let expanded = towards(&centroid, &simplex[n], -EXPANSION);
+            let f_expanded = f.call(expanded.as_slice());
+            if f_expanded < f_reflected {
+                simplex[n] = expanded;
+                values[n] = f_expanded;
+            } else {
+                simplex[n] = reflected;
+                values[n] = f_reflected;
+            }
+        } else if f_reflected < values[n - 1] {
+            simplex[n] = reflected;
+            values[n] = f_reflected;
+        } else {
+            let (contracted, f_contracted) = if f_reflected < values[n] {
+                let outside = towards(&centroid, &reflected, CONTRACTION);
+                let f_outside = f.call(outside.as_slice());
+                (outside, f_outside)
+            } else {
+                let inside = towards(&centroid, &simplex[n], CONTRACTION);
+                let f_inside = f.call(inside.as_slice());
+                (inside, f_inside)
+            };
+            if f_contracted < values[n].min(f_reflected) {
+                simplex[n] = contracted;
+                values[n] = f_contracted;
+            } else {
+                for i in 1..=n {
+                    simplex[i] = towards(&simplex[0], &simplex[i], SHRINKAGE);
+                    values[i] = f.call(simplex[i].as_slice());
+                }
+            }
+        }
+    }
+
+    let best = (0..=n)
+        .min_by(|i, j| values[*i].total_cmp(&values[*j]))
+        .unwrap_or(0);
+    Solution {
+        x: simplex[best].clone(),
+        value: values[best],
+        iterations,
+        function_evaluations: f.evaluations,
+        converged,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn root_finding() {
+        let f = |x: f64| x.powi(3) - 2.0 * x - 5.0;
+        let options = SolverOptions::default();
+
+        let solution = brent(f, 2.0, 3.0, &options).unwrap();
+        assert!(solution.converged);
+        assert_approx_eq!(solution.x, 2.0945514815423265, 1e-12);
+        assert!(solution.iterations < 15);
+        assert!(brent(f, 3.0, 4.0, &options).is_none());
+
+        let solution = newton(|x| (f(x), 3.0 * x * x - 2.0), 2.5, 2.0, 3.0, &options).unwrap();
+        assert!(solution.converged);
+        assert_approx_eq!(solution.x, 2.0945514815423265, 1e-12);
+
+        // the safeguard keeps Newton in the bracket despite the flat derivative at the initial guess
+        let solution = newton(
+            |x| (x.atan(), 1.0 / (1.0 + x * x)),
+            10.0,
+            -20.0,
+            15.0,
+            &options,
+        )
+        .unwrap();
+        assert_approx_eq!(solution.x, 0.0, 1e-12);
+
+        let capped = SolverOptions::new(0.0, 0.0, 3);
+        let solution = brent(f, 2.0, 3.0, &capped).unwrap();
+        assert!(!solution.converged && solution.iterations == 3);
+        assert!(solution.converged().is_none());
+    }
+
+    #[test]
+    fn minimization() {
+        let options = SolverOptions::new(1e-8, 1e-14, 500);
+        let solution = golden_section(|x| (x - 1.5).powi(2) + 0.5, 0.0, 4.0, &options);
+        assert!(solution.converged);
+        assert_approx_eq!(solution.x, 1.5, 1e-7);
+        assert_approx_eq!(solution.value, 0.5, 1e-14);
+
+        let rosenbrock = |x: &[f64]| (1.0 - x[0]).powi(2) + 100.0 * (x[1] - x[0] * x[0]).powi(2);
+        let solution = nelder_mead(rosenbrock, &[-1.2, 1.0], 0.5, &options);
+        assert!(solution.converged);
+        assert_approx_eq!(solution.x[0], 1.0, 1e-6);
+        assert_approx_eq!(solution.x[1], 1.0, 1e-6);
+        assert!(solution.function_evaluations > solution.iterations);
+    }
+}
@@ -0,0 +1,94 @@
+use rayon::prelude::*;
+
+/// The number of values below which the pairwise summation adds sequentially.
+const PAIRWISE_BLOCK_SIZE: usize = 1024;
+
+/// The trade-off between speed and bit-stability of floating-point sums, which depend on the summation order.
+/// All modes but `Fastest` yield bit-identical results for the same values in the same order,
+/// independent of the number of threads.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Reproducibility {
+    /// sequential summation in the order of the values
+    #[default]
+    Sequential,
+    /// sequential Kahan-Babuska (Neumaier) compensated summation, which bounds the rounding error
+    /// independently of the number of values.
+    /// See https://en.wikipedia.org/wiki/Kahan_summation_algorithm#Further_enhancements
+    Compensated,
+    /// pairwise summation over a fixed binary tree of blocks, whose halves are added in parallel
+    /// See https://en.wikipedia.org/wiki/Pairwise_summation
+    Pairwise,
+    /// parallel reduction, whose summation order depends on the scheduling of the threads
+    Fastest,
+}
+
+impl Reproducibility {
+    /// Whether repeated sums of the same values are bit-identical.
+    pub fn is_bit_stable(&self) -> bool {
+        !matches!(self, Self::Fastest)
+    }
+
+    pub fn sum(&self, values: &[f64]) -> f64 {
+        match self {
+            Self::Sequential => values.iter().sum(),
+            Self::Compensated => compensated_sum(values),
+            Self::Pairwise => pairwise_sum(values),
+            Self::Fastest => values.par_iter().sum(),
+        }
+    }
+}
+
+fn compensated_sum(values: &[f64]) -> f64 {
+    let mut sum = 0.0;
+    let mut compensation = 0.0;
+    for value in values {
+        let t = sum + value;
+        if f64::abs(sum) >= value.abs() {
+            compensation += (sum - t) + value;
+        } else {
+            compensation += (value - t) + sum;
+        }
+        sum = t;
+    }
+    sum + compensation
+}
+
+fn pairwise_sum(values: &[f64]) -> f64 {
+    if values.len() <= PAIRWISE_BLOCK_SIZE {
+        return values.iter().sum();
+    }
+    let (left, right) = values.split_at(values.len() / 2);
+    let (left, right) = rayon::join(|| pairwise_sum(left), || pairwise_sum(right));
+    left + right
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summation_modes() {
+        // the small values are lost by the sequential summation
+        let mut values = vec![1.0];
+        values.extend(std::iter::repeat_n(1e-16, 10_000));
+        let exact = 1.0 + 1e-12;
+
+        assert_eq!(Reproducibility::Sequential.sum(&values), 1.0);
+        assert!((Reproducibility::Compensated.sum(&values) - exact).abs() < 1e-15);
+        // only the block with the large value loses its small values
+        assert!((Reproducibility::Pairwise.sum(&values) - exact).abs() < 1e-13);
+        // sums of integers are exact in any order
+        let integers: Vec<f64> = (1..=10_000).map(f64::from).collect();
+        assert_eq!(Reproducibility::Fastest.sum(&integers), 50_005_000.0);
+
+        let pairwise = Reproducibility::Pairwise.sum(&values);
+        for _ in 0..10 {
+            assert_eq!(
+                Reproducibility::Pairwise.sum(&values).to_bits(),
+                pairwise.to_bits()
+            );
+        }
+        assert!(!Reproducibility::Fastest.is_bit_stable());
+        assert_eq!(Reproducibility::Compensated.sum(&[]), 0.0);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::common::results::PricingError;
+use crate::numerics::solvers::{brent, Solution, SolverOptions};
+
+/// The n equidistant points from start to end (inclusive), e.g. the maturities to sample a curve at.
+pub fn uniform_grid(start: f64, end: f64, n: usize) -> Vec<f64> {
+    assert!(n > 1 && start < end);
+    let step = (end - start) / (n - 1) as f64;
+    (0..n).map(|i| start + i as f64 * step).collect()
+}
+
+/// The n nodes from lower to upper concentrated around the points, e.g. the strike and the barrier,
+/// where the mesh width is smallest. The nodes are uniform in $\xi(x) = \sum_k \sinh^{-1}((x - p_k)
+/// / c)$ for the concentration width c, i.e. the sinh grid $x = p + c \sinh(\xi)$ of a single point,
+/// which becomes uniform for c large relative to the range. Without points, the grid is uniform.
+/// Fails for fewer than two nodes, an empty range or a non-positive concentration width.
+/// See in 't Hout and Foulon, ADI finite difference schemes for option pricing in the Heston model with
+/// correlation, International Journal of Numerical Analysis and Modeling 7 (2010).
+pub fn sinh_grid(
+    lower: f64,
+    upper: f64,
+    n: usize,
+    points: &[f64],
+    concentration: f64,
+) -> Result<Vec<f64>, PricingError> {
+    PricingError::check(n > 1, "nr nodes", n as f64)?;
+    PricingError::check(lower < upper, "upper", upper)?;
+    PricingError::check(concentration > 0.0, "concentration", concentration)?;
+    if points.is_empty() {
+        return Ok(uniform_grid(lower, upper, n));
+    }
+    let xi = |x: f64| {
+        points
+            .iter()
+            .map(|point| ((x - point) / concentration).asinh())
+            .sum::<f64>()
+    };
+    let (xi_lower, xi_upper) = (xi(lower), xi(upper));
+    let options = SolverOptions::new(1e-12 * (upper - lower), 0.0, 200);
+    let targets = uniform_grid(xi_lower, xi_upper, n);
+    let interior = targets[1..n - 1]
+        .iter()
+        .map(|target| {
+            brent(|x| xi(x) - target, lower, upper, &options)
+                .and_then(Solution::converged)
+                .ok_or_else(|| {
+                    PricingError::InvalidParameter(format!(
+                        "sinh grid node not found in [{lower}, {upper}]"
+                    ))
+                })
+        })
+        .collect::<Result<Vec<f64>, PricingError>>()?;
+    // the boundaries exactly, which may not be bracketed by the rounded targets
+    Ok(std::iter::once(lower)
+        .chain(interior)
+        .chain(std::iter::once(upper))
+        .collect())
+}
+
+/// The nodes with the interior node closest to the point moved onto the point, e.g. such that the kink
+/// or jump of a payoff at the strike lies on the grid. The order of the nodes is preserved since no
+/// other node lies between the closest node and the point.
+pub fn with_node_at(mut nodes: Vec<f64>, point: f64) -> Vec<f64> {
+    let n = nodes.len();
+    if n < 3 || point <= nodes[0] || point >= nodes[n - 1] {
+        return nodes;
+    }
+    let closest = (1..n - 1)
+        .min_by(|&i, &k| {
+            (nodes[i] - point)
+                .abs()
+                .total_cmp(&(nodes[k] - point).abs())
+        })
+        .unwrap();
+    nodes[closest] = point;
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn stretched_grids() {
+        let grid = sinh_grid(0.0, 400.0, 101, &[100.0], 20.0).unwrap();
+        assert_eq!((grid[0], grid[100]), (0.0, 400.0));
+        assert!(grid.windows(2).all(|pair| pair[0] < pair[1]));
+        // the sinh grid of a single point
+        let (xi_lower, xi_upper) = ((-5.0_f64).asinh(), 15.0_f64.asinh());
+        let xi = xi_lower + 0.3 * (xi_upper - xi_lower);
+        assert_approx_eq!(grid[30], 100.0 + 20.0 * xi.sinh(), 1e-9);
+        let width = |grid: &[f64], x: f64| {
+            let i = grid.iter().position(|node| *node > x).unwrap();
+            grid[i] - grid[i - 1]
+        };
+        assert!(width(&grid, 100.0) < 0.5 * 4.0);
+        assert!(width(&grid, 350.0) > 4.0);
+
+        // concentrated at the strike and the barrier
+        let grid = sinh_grid(80.0, 400.0, 101, &[80.0, 100.0], 10.0).unwrap();
+        assert!(width(&grid, 80.0) < width(&grid, 90.0));
+        assert!(width(&grid, 100.0) < width(&grid, 90.0));
+        assert_eq!(sinh_grid(0.0, 1.0, 3, &[], 1.0), Ok(vec![0.0, 0.5, 1.0]));
+        for (lower, n, concentration) in [(1.0, 11, 1.0), (0.0, 1, 1.0), (0.0, 11, 0.0)] {
+            assert!(matches!(
+                sinh_grid(lower, 1.0, n, &[0.5], concentration),
+                Err(PricingError::InvalidParameter(_))
+            ));
+        }
+
+        let grid = with_node_at(uniform_grid(0.0, 1.0, 11), 0.33);
+        assert!(grid.contains(&0.33) && !grid.contains(&0.3));
+        assert_eq!(with_node_at(grid.clone(), 1.5), grid);
+    }
+}
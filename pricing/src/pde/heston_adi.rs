@@ -0,0 +1,782 @@
+use ndarray::Array2;
+
+use crate::common::models::{ExerciseType, HestonParameters};
+use crate::common::results::{GreeksResult, PricingError};
+use crate::numerics::banded::TridiagonalMatrix;
+use crate::numerics::interpolation::{InterpolationMethod2D, Interpolator2D};
+use crate::pde::grid::{sinh_grid, uniform_grid, with_node_at};
+
+/// The ADI splitting of the time steps: the explicit step of the full operator is corrected by implicit
+/// steps in the spot and in the variance direction, where Craig-Sneyd also corrects the mixed derivative.
+/// See in 't Hout and Foulon, ADI finite difference schemes for option pricing in the Heston model with
+/// correlation, International Journal of Numerical Analysis and Modeling 7 (2010).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AdiScheme {
+    /// first order in time with the mixed derivative explicit
+    Douglas,
+    /// second order in time
+    #[default]
+    CraigSneyd,
+}
+
+/// The knock-out barrier of an option, which is worthless once the spot reaches the barrier.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KnockOut {
+    Down(f64),
+    Up(f64),
+}
+
+/// The payoff at maturity of the options priced on the grid.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Payoff {
+    /// $(s - K)^+$ of a call or $(K - s)^+$ of a put
+    #[default]
+    Vanilla,
+    /// one unit of cash if the option ends in the money
+    CashOrNothing,
+}
+
+/// The finite difference weights of the first and second derivative at a node from its neighbours at
+/// the distances $h_-$ and $h_+$, second order on nonuniform grids.
+fn central_weights(h_minus: f64, h_plus: f64) -> ([f64; 3], [f64; 3]) {
+    let first = [
+        -h_plus / (h_minus * (h_minus + h_plus)),
+        (h_plus - h_minus) / (h_minus * h_plus),
+        h_minus / (h_plus * (h_minus + h_plus)),
+    ];
+    let second = [
+        2.0 / (h_minus * (h_minus + h_plus)),
+        -2.0 / (h_minus * h_plus),
+        2.0 / (h_plus * (h_minus + h_plus)),
+    ];
+    (first, second)
+}
+
+/// The Heston option price $u(\tau, s, v)$ for the time to maturity τ on the grid of spots and variances.
+struct HestonGrid<'a> {
+    spots: &'a [f64],
+    variances: &'a [f64],
+    rate: f64,
+    parameters: &'a HestonParameters,
+}
+
+impl HestonGrid<'_> {
+    /// The operators $A_1$ of the spot direction per variance node, $\frac{1}{2} s^2 v u_{ss} + r s u_s
+    /// - \frac{1}{2} r u$, vanishing on the spot boundaries, where the values are given.
+    fn spot_operators(&self) -> Vec<TridiagonalMatrix> {
+        let (m, s) = (self.spots.len(), self.spots);
+        self.variances
+            .iter()
+            .map(|v| {
+                let mut operator = TridiagonalMatrix::new(vec![0.0; m], vec![0.0; m], vec![0.0; m]);
+                for i in 1..m - 1 {
+                    let (first, second) = central_weights(s[i] - s[i - 1], s[i + 1] - s[i]);
+                    let (diffusion, convection) = (0.5 * s[i] * s[i] * v, self.rate * s[i]);
+                    let weight = |k: usize| diffusion * second[k] + convection * first[k];
+                    operator.lower[i] = weight(0);
+                    operator.diagonal[i] = weight(1) - 0.5 * self.rate;
+                    operator.upper[i] = weight(2);
+                }
+                operator
+            })
+            .collect()
+    }
+
+    /// The operators $A_2$ of the variance direction per spot node, $\frac{1}{2} \sigma^2 v u_{vv} +
+    /// \kappa (\theta - v) u_v - \frac{1}{2} r u$, with the degenerate equation at $v = 0$ and
+    /// $u_v = 0$ at the largest variance, vanishing on the spot boundaries.
+    fn variance_operators(&self) -> Vec<TridiagonalMatrix> {
+        let (n, v) = (self.variances.len(), self.variances);
+        let p = self.parameters;
+        (0..self.spots.len())
+            .map(|i| {
+                let mut operator = TridiagonalMatrix::new(vec![0.0; n], vec![0.0; n], vec![0.0; n]);
+                if i == 0 || i == self.spots.len() - 1 {
+                    return operator;
+                }
+                // the upwind difference of the drift $\kappa \theta$ into the domain
+                let drift = p.mean_reversion * p.long_term_variance / (v[1] - v[0]);
+                operator.diagonal[0] = -drift - 0.5 * self.rate;
+                operator.upper[0] = drift;
+                for j in 1..n - 1 {
+                    let (first, second) = central_weights(v[j] - v[j - 1], v[j + 1] - v[j]);
+                    let diffusion = 0.5 * p.vol_of_vol.powi(2) * v[j];
+                    let convection = p.mean_reversion * (p.long_term_variance - v[j]);
+                    let weight = |k: usize| diffusion * second[k] + convection * first[k];
+                    operator.lower[j] = weight(0);
+                    operator.diagonal[j] = weight(1) - 0.5 * self.rate;
+                    operator.upper[j] = weight(2);
+                }
+                // the reflection $u_{n} = u_{n-2}$ of $u_v = 0$
+                let h = v[n - 1] - v[n - 2];
+                let diffusion = p.vol_of_vol.powi(2) * v[n - 1] / (h * h);
+                operator.lower[n - 1] = diffusion;
+                operator.diagonal[n - 1] = -diffusion - 0.5 * self.rate;
+                operator
+            })
+            .collect()
+    }
+
+    /// The mixed derivative term $A_0 u = \rho \sigma s v u_{sv}$ at the interior nodes.
+    fn apply_mixed(&self, u: &Array2<f64>) -> Array2<f64> {
+        let (s, v) = (self.spots, self.variances);
+        let (m, n) = (s.len(), v.len());
+        let coefficient = self.parameters.correlation * self.parameters.vol_of_vol;
+        let mut result = Array2::zeros((m, n));
+        for i in 1..m - 1 {
+            let (spot_weights, _) = central_weights(s[i] - s[i - 1], s[i + 1] - s[i]);
+            for j in 1..n - 1 {
+                let (variance_weights, _) = central_weights(v[j] - v[j - 1], v[j + 1] - v[j]);
+                let mut u_sv = 0.0;
+                for (k, spot_weight) in spot_weights.iter().enumerate() {
+                    for (l, variance_weight) in variance_weights.iter().enumerate() {
+                        u_sv += spot_weight * variance_weight * u[[i + k - 1, j + l - 1]];
+                    }
+                }
+                result[[i, j]] = coefficient * s[i] * v[j] * u_sv;
+            }
+        }
+        result
+    }
+}
+
+fn apply_spot(operators: &[TridiagonalMatrix], u: &Array2<f64>) -> Array2<f64> {
+    let mut result = Array2::zeros(u.raw_dim());
+    for (j, operator) in operators.iter().enumerate() {
+        let values = operator.multiply(&u.column(j).to_vec());
+        result.column_mut(j).assign(&ndarray::Array1::from(values));
+    }
+    result
+}
+
+fn apply_variance(operators: &[TridiagonalMatrix], u: &Array2<f64>) -> Array2<f64> {
+    let mut result = Array2::zeros(u.raw_dim());
+    for (i, operator) in operators.iter().enumerate() {
+        let values = operator.multiply(&u.row(i).to_vec());
+        result.row_mut(i).assign(&ndarray::Array1::from(values));
+    }
+    result
+}
+
+/// Solves $(I - \theta \Delta\tau A_1) y = rhs$ per variance node with the boundary values of the spots.
+fn solve_spot(
+    operators: &[TridiagonalMatrix],
+    step: f64,
+    mut rhs: Array2<f64>,
+    boundary: (f64, f64),
+) -> Array2<f64> {
+    let m = rhs.nrows();
+    for (j, operator) in operators.iter().enumerate() {
+        let mut column = rhs.column(j).to_vec();
+        (column[0], column[m - 1]) = boundary;
+        let solution = operator
+            .scaled_shifted(-step, 1.0)
+            .solve(&column)
+            .expect("diagonally dominant ADI system");
+        rhs.column_mut(j).assign(&ndarray::Array1::from(solution));
+    }
+    rhs
+}
+
+/// Solves $(I - \theta \Delta\tau A_2) y = rhs$ per spot node.
+fn solve_variance(operators: &[TridiagonalMatrix], step: f64, mut rhs: Array2<f64>) -> Array2<f64> {
+    for (i, operator) in operators.iter().enumerate() {
+        let solution = operator
+            .scaled_shifted(-step, 1.0)
+            .solve(&rhs.row(i).to_vec())
+            .expect("diagonally dominant ADI system");
+        rhs.row_mut(i).assign(&ndarray::Array1::from(solution));
+    }
+    rhs
+}
+
+/// Finite difference prices of European and knock-out options in the Heston model by solving
+/// '''math
+/// u_\tau = \frac{1}{2} s^2 v u_{ss} + \rho \sigma s v u_{sv} + \frac{1}{2} \sigma^2 v u_{vv}
+///     + r s u_s + \kappa (\theta - v) u_v - r u
+/// ''' backwards from the payoff with an ADI scheme on a grid of spots and variances, e.g. to
+/// cross-check the Heston Monte Carlo prices without simulation noise. The spot grid is concentrated
+/// at the strike and the barrier, the variance grid at 0, and the first steps are damped by implicit
+/// half steps against the oscillations of the non-smooth payoff (Rannacher start-up).
+/// See https://en.wikipedia.org/wiki/Alternating-direction_implicit_method
+#[derive(Clone, Debug, PartialEq)]
+pub struct HestonAdi {
+    rate: f64,
+    parameters: HestonParameters,
+    nr_spot_nodes: usize,
+    nr_variance_nodes: usize,
+    nr_time_steps: usize,
+    /// the largest spot relative to the larger of the spot and the strike
+    spot_multiple: f64,
+    max_variance: f64,
+    /// the sinh concentration width of the spot grid relative to the strike, uniform if None
+    spot_concentration: Option<f64>,
+    /// the sinh concentration width of the variance grid at 0 relative to the largest variance
+    variance_concentration: Option<f64>,
+    /// the number of first time steps replaced by two implicit Euler half steps each
+    damping_steps: usize,
+    /// the exponent γ of the time steps $\tau_k = T (k / N)^\gamma$, refined close to maturity for γ > 1
+    time_grading: f64,
+    scheme: AdiScheme,
+}
+
+impl HestonAdi {
+    pub fn new(rate: f64, parameters: HestonParameters) -> Self {
+        Self {
+            rate,
+            parameters,
+            nr_spot_nodes: 200,
+            nr_variance_nodes: 100,
+            nr_time_steps: 100,
+            spot_multiple: 4.0,
+            max_variance: 1.0,
+            spot_concentration: Some(0.1),
+            variance_concentration: Some(0.01),
+            damping_steps: 2,
+            time_grading: 1.0,
+            scheme: AdiScheme::default(),
+        }
+    }
+
+    pub fn with_grid(
+        self,
+        nr_spot_nodes: usize,
+        nr_variance_nodes: usize,
+        nr_time_steps: usize,
+    ) -> Self {
+        Self {
+            nr_spot_nodes,
+            nr_variance_nodes,
+            nr_time_steps,
+            ..self
+        }
+    }
+
+    /// The truncation of the grid at `spot_multiple` times the larger of the spot and the strike (or at
+    /// an up barrier) and at the variance.
+    pub fn with_bounds(self, spot_multiple: f64, max_variance: f64) -> Self {
+        Self {
+            spot_multiple,
+            max_variance,
+            ..self
+        }
+    }
+
+    /// The sinh stretching of the spot grid around the strike and the barrier with the concentration
+    /// width `spot_concentration` times the strike and of the variance grid at 0 with the width
+    /// `variance_concentration` times the largest variance, see `grid::sinh_grid`, or uniform grids
+    /// for None. Smaller widths concentrate more nodes.
+    pub fn with_stretching(
+        self,
+        spot_concentration: Option<f64>,
+        variance_concentration: Option<f64>,
+    ) -> Self {
+        Self {
+            spot_concentration,
+            variance_concentration,
+            ..self
+        }
+    }
+
+    /// The Rannacher start-up of `damping_steps` implicit steps, which are halved, and the grading
+    /// exponent of the time steps, where 1 is uniform.
+    /// See Rannacher, Finite element solution of diffusion problems with irregular data, Numerische
+    /// Mathematik 43 (1984).
+    pub fn with_time_stepping(self, damping_steps: usize, time_grading: f64) -> Self {
+        Self {
+            damping_steps,
+            time_grading,
+            ..self
+        }
+    }
+
+    pub fn with_scheme(self, scheme: AdiScheme) -> Self {
+        Self { scheme, ..self }
+    }
+
+    fn validate(
+        &self,
+        spot: f64,
+        strike: f64,
+        maturity: f64,
+        knock_out: Option<KnockOut>,
+    ) -> Result<(), PricingError> {
+        self.parameters.validate()?;
+        PricingError::check(self.rate.is_finite(), "rate", self.rate)?;
+        PricingError::check(spot > 0.0, "spot", spot)?;
+        PricingError::check(strike > 0.0, "strike", strike)?;
+        if let Some(KnockOut::Down(barrier) | KnockOut::Up(barrier)) = knock_out {
+            PricingError::check(barrier > 0.0 && barrier.is_finite(), "barrier", barrier)?;
+        }
+        PricingError::check(
+            maturity >= 0.0 && maturity.is_finite(),
+            "maturity",
+            maturity,
+        )?;
+        PricingError::check(
+            self.nr_spot_nodes >= 3,
+            "nr spot nodes",
+            self.nr_spot_nodes as f64,
+        )?;
+        PricingError::check(
+            self.nr_variance_nodes >= 3,
+            "nr variance nodes",
+            self.nr_variance_nodes as f64,
+        )?;
+        PricingError::check(
+            self.nr_time_steps > 0,
+            "nr time steps",
+            self.nr_time_steps as f64,
+        )?;
+        PricingError::check(
+            self.damping_steps <= self.nr_time_steps,
+            "damping steps",
+            self.damping_steps as f64,
+        )?;
+        PricingError::check(self.time_grading >= 1.0, "time grading", self.time_grading)?;
+        for concentration in [self.spot_concentration, self.variance_concentration]
+            .into_iter()
+            .flatten()
+        {
+            PricingError::check(concentration > 0.0, "concentration", concentration)?;
+        }
+        PricingError::check(
+            self.spot_multiple > 1.0,
+            "spot multiple",
+            self.spot_multiple,
+        )?;
+        PricingError::check(
+            self.max_variance > self.parameters.initial_variance,
+            "max variance",
+            self.max_variance,
+        )
+    }
+
+    /// The nodes of the spot and the variance grid, with a node at the strike if stretched.
+    fn grid(
+        &self,
+        spot: f64,
+        strike: f64,
+        knock_out: Option<KnockOut>,
+    ) -> Result<(Vec<f64>, Vec<f64>), PricingError> {
+        let (lower, upper, barrier) = match knock_out {
+            Some(KnockOut::Down(barrier)) => (
+                barrier,
+                self.spot_multiple * spot.max(strike),
+                Some(barrier),
+            ),
+            Some(KnockOut::Up(barrier)) => (0.0, barrier, Some(barrier)),
+            None => (0.0, self.spot_multiple * spot.max(strike), None),
+        };
+        let spots = match self.spot_concentration {
+            Some(concentration) => {
+                let points: Vec<f64> = [Some(strike), barrier]
+                    .into_iter()
+                    .flatten()
+                    .filter(|point| (lower..=upper).contains(point))
+                    .collect();
+                let nodes = sinh_grid(
+                    lower,
+                    upper,
+                    self.nr_spot_nodes,
+                    &points,
+                    concentration * strike,
+                )?;
+                with_node_at(nodes, strike)
+            }
+            None => uniform_grid(lower, upper, self.nr_spot_nodes),
+        };
+        let variances = match self.variance_concentration {
+            Some(concentration) => sinh_grid(
+                0.0,
+                self.max_variance,
+                self.nr_variance_nodes,
+                &[0.0],
+                concentration * self.max_variance,
+            )?,
+            None => uniform_grid(0.0, self.max_variance, self.nr_variance_nodes),
+        };
+        Ok((spots, variances))
+    }
+
+    /// The times to maturity of the time steps.
+    fn times(&self, maturity: f64) -> Vec<f64> {
+        let n = self.nr_time_steps as f64;
+        (0..=self.nr_time_steps)
+            .map(|k| maturity * (k as f64 / n).powf(self.time_grading))
+            .collect()
+    }
+
+    /// The price of the European call or put on the spot, knocked out at the barrier if any.
+    pub fn price(
+        &self,
+        spot: f64,
+        strike: f64,
+        maturity: f64,
+        exercise_type: &ExerciseType,
+        knock_out: Option<KnockOut>,
+    ) -> Result<f64, PricingError> {
+        self.greeks(
+            spot,
+            strike,
+            maturity,
+            exercise_type,
+            knock_out,
+            Payoff::Vanilla,
+        )
+        .map(|greeks| greeks.value)
+    }
+
+    /// The price of the cash-or-nothing call or put paying one unit, knocked out at the barrier if any.
+    pub fn digital_price(
+        &self,
+        spot: f64,
+        strike: f64,
+        maturity: f64,
+        exercise_type: &ExerciseType,
+        knock_out: Option<KnockOut>,
+    ) -> Result<f64, PricingError> {
+        self.greeks(
+            spot,
+            strike,
+            maturity,
+            exercise_type,
+            knock_out,
+            Payoff::CashOrNothing,
+        )
+        .map(|greeks| greeks.value)
+    }
+
+    /// The price with the delta and gamma of the grid at the spot, i.e. of the parabola through the
+    /// values of the closest spot nodes, e.g. of a barrier option close to the barrier, where bumping
+    /// the spot is unstable. The greeks are unavailable at maturity.
+    pub fn greeks(
+        &self,
+        spot: f64,
+        strike: f64,
+        maturity: f64,
+        exercise_type: &ExerciseType,
+        knock_out: Option<KnockOut>,
+        payoff: Payoff,
+    ) -> Result<GreeksResult, PricingError> {
+        self.validate(spot, strike, maturity, knock_out)?;
+        let payoff_at = |s: f64| {
+            let moneyness = match exercise_type {
+                ExerciseType::Call => s - strike,
+                ExerciseType::Put => strike - s,
+            };
+            match payoff {
+                Payoff::Vanilla => moneyness.max(0.0),
+                // the average over the cell of a node at the strike
+                Payoff::CashOrNothing if moneyness == 0.0 => 0.5,
+                Payoff::CashOrNothing => (moneyness > 0.0) as u8 as f64,
+            }
+        };
+        let knocked_out = GreeksResult {
+            value: 0.0,
+            delta: Some(0.0),
+            gamma: Some(0.0),
+            ..Default::default()
+        };
+        match knock_out {
+            Some(KnockOut::Down(barrier)) if spot <= barrier => return Ok(knocked_out),
+            Some(KnockOut::Up(barrier)) if spot >= barrier => return Ok(knocked_out),
+            _ if maturity == 0.0 => {
+                return Ok(GreeksResult {
+                    value: payoff_at(spot),
+                    ..Default::default()
+                })
+            }
+            _ => {}
+        }
+
+        let (spots, variances) = self.grid(spot, strike, knock_out)?;
+        let heston_grid = HestonGrid {
+            spots: &spots,
+            variances: &variances,
+            rate: self.rate,
+            parameters: &self.parameters,
+        };
+        let (spot_operators, variance_operators) = (
+            heston_grid.spot_operators(),
+            heston_grid.variance_operators(),
+        );
+        let spot_max = spots[spots.len() - 1];
+        // the values on the spot boundaries at the time to maturity
+        let boundary = |tau: f64| {
+            let discount_factor = (-self.rate * tau).exp();
+            let in_the_money = match payoff {
+                Payoff::Vanilla => (
+                    strike * discount_factor,
+                    spot_max - strike * discount_factor,
+                ),
+                Payoff::CashOrNothing => (discount_factor, discount_factor),
+            };
+            let lower = match (knock_out, exercise_type) {
+                (Some(KnockOut::Down(_)), _) | (_, ExerciseType::Call) => 0.0,
+                (_, ExerciseType::Put) => in_the_money.0,
+            };
+            let upper = match (knock_out, exercise_type) {
+                (Some(KnockOut::Up(_)), _) | (_, ExerciseType::Put) => 0.0,
+                (_, ExerciseType::Call) => in_the_money.1,
+            };
+            (lower, upper)
+        };
+
+        let mut u =
+            Array2::from_shape_fn((spots.len(), variances.len()), |(i, _)| payoff_at(spots[i]));
+        let (first, last) = boundary(0.0);
+        u.row_mut(0).fill(first);
+        u.row_mut(spots.len() - 1).fill(last);
+
+        let step = |u: &Array2<f64>, tau: f64, dt: f64, theta: f64, scheme: AdiScheme| {
+            let mixed = heston_grid.apply_mixed(u);
+            let spot_part = apply_spot(&spot_operators, u);
+            let variance_part = apply_variance(&variance_operators, u);
+            let y0 = u + &((&mixed + &spot_part + &variance_part) * dt);
+            let implicit_steps = |y: &Array2<f64>| {
+                let y1 = solve_spot(
+                    &spot_operators,
+                    theta * dt,
+                    y - &(&spot_part * (theta * dt)),
+                    boundary(tau + dt),
+                );
+                solve_variance(
+                    &variance_operators,
+                    theta * dt,
+                    y1 - &(&variance_part * (theta * dt)),
+                )
+            };
+            let y2 = implicit_steps(&y0);
+            match scheme {
+                AdiScheme::Douglas => y2,
+                AdiScheme::CraigSneyd => {
+                    let corrected = y0 + &((heston_grid.apply_mixed(&y2) - &mixed) * (0.5 * dt));
+                    implicit_steps(&corrected)
+                }
+            }
+        };
+        for (k, taus) in self.times(maturity).windows(2).enumerate() {
+            let (tau, dt) = (taus[0], taus[1] - taus[0]);
+            if k < self.damping_steps {
+                u = step(&u, tau, 0.5 * dt, 1.0, AdiScheme::Douglas);
+                u = step(&u, tau + 0.5 * dt, 0.5 * dt, 1.0, AdiScheme::Douglas);
+            } else {
+                u = step(&u, tau, dt, 0.5, self.scheme);
+            }
+        }
+
+        let interpolator =
+            Interpolator2D::new(spots.clone(), variances, &u, InterpolationMethod2D::Bicubic);
+        let value_at = |s: f64| interpolator.value(s, self.parameters.initial_variance);
+        let i = (1..spots.len() - 1)
+            .min_by(|&i, &k| (spots[i] - spot).abs().total_cmp(&(spots[k] - spot).abs()))
+            .unwrap();
+        let (first, second) = central_weights(spots[i] - spots[i - 1], spots[i + 1] - spots[i]);
+        let values = [
+            value_at(spots[i - 1]),
+            value_at(spots[i]),
+            value_at(spots[i + 1]),
+        ];
+        let derivative = |weights: [f64; 3]| weights.iter().zip(values).map(|(w, u)| w * u).sum();
+        let gamma: f64 = derivative(second);
+        Ok(GreeksResult {
+            value: value_at(spot),
+            delta: Some(derivative(first) + gamma * (spot - spots[i])),
+            gamma: Some(gamma),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::BsmComputation;
+    use crate::common::models::DerivativeParameter;
+    use crate::numerics::normal::{cdf, pdf};
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn heston_adi_prices() {
+        // the reference of Broadie and Kaya (2006) by Fourier inversion, see simulation::references
+        let parameters = HestonParameters {
+            initial_variance: 0.010201,
+            mean_reversion: 6.21,
+            long_term_variance: 0.019,
+            vol_of_vol: 0.61,
+            correlation: -0.7,
+        };
+        let adi = HestonAdi::new(0.0319, parameters).with_bounds(4.0, 0.5);
+        let call = |adi: &HestonAdi| {
+            adi.price(100.0, 100.0, 1.0, &ExerciseType::Call, None)
+                .unwrap()
+        };
+        assert_approx_eq!(call(&adi), 6.8061, 0.003);
+        // the uniform grids converge much slower
+        let uniform = adi.clone().with_stretching(None, None);
+        assert!((call(&uniform) - 6.8061).abs() > 0.01);
+
+        // the schemes, the put-call parity and the knock-outs on a coarse grid
+        let coarse = adi.with_grid(100, 50, 50);
+        let coarse_call = call(&coarse);
+        let douglas = call(&coarse.clone().with_scheme(AdiScheme::Douglas));
+        assert_approx_eq!(douglas, coarse_call, 0.005);
+        let put = coarse
+            .price(100.0, 100.0, 1.0, &ExerciseType::Put, None)
+            .unwrap();
+        assert_approx_eq!(coarse_call - put, 100.0 - 100.0 * (-0.0319_f64).exp(), 0.01);
+        let down_and_out = coarse
+            .price(
+                100.0,
+                100.0,
+                1.0,
+                &ExerciseType::Call,
+                Some(KnockOut::Down(90.0)),
+            )
+            .unwrap();
+        assert!(0.0 < down_and_out && down_and_out < coarse_call);
+        let far_barrier = coarse
+            .price(
+                100.0,
+                100.0,
+                1.0,
+                &ExerciseType::Put,
+                Some(KnockOut::Up(400.0)),
+            )
+            .unwrap();
+        assert_approx_eq!(far_barrier, put, 0.01);
+        assert_eq!(
+            coarse.price(
+                80.0,
+                100.0,
+                1.0,
+                &ExerciseType::Call,
+                Some(KnockOut::Down(90.0))
+            ),
+            Ok(0.0)
+        );
+        assert!(coarse
+            .clone()
+            .with_grid(2, 10, 10)
+            .price(100.0, 100.0, 1.0, &ExerciseType::Call, None)
+            .is_err());
+        for barrier in [
+            KnockOut::Down(f64::NAN),
+            KnockOut::Up(-10.0),
+            KnockOut::Up(f64::INFINITY),
+        ] {
+            assert!(matches!(
+                coarse.price(100.0, 100.0, 1.0, &ExerciseType::Call, Some(barrier)),
+                Err(PricingError::InvalidParameter(_))
+            ));
+        }
+        assert!(matches!(
+            HestonAdi::new(f64::NAN, parameters).price(
+                100.0,
+                100.0,
+                1.0,
+                &ExerciseType::Call,
+                None
+            ),
+            Err(PricingError::InvalidParameter(_))
+        ));
+
+        // without vol of vol and with the variance at its long-term mean, the Black-Scholes price
+        let flat = HestonParameters {
+            initial_variance: 0.04,
+            long_term_variance: 0.04,
+            vol_of_vol: 1e-4,
+            ..parameters
+        };
+        let black_scholes =
+            BsmComputation::new(&DerivativeParameter::new(100.0, 110.0, 0.5, 0.02, 0.2));
+        assert_approx_eq!(
+            HestonAdi::new(0.02, flat)
+                .with_bounds(4.0, 0.5)
+                .with_grid(100, 50, 50)
+                .price(100.0, 110.0, 0.5, &ExerciseType::Put, None)
+                .unwrap(),
+            black_scholes.put(),
+            0.05
+        );
+    }
+
+    #[test]
+    fn digital_and_barrier_greeks() {
+        // the Black-Scholes limit with a volatility of 0.2
+        let parameters = HestonParameters {
+            initial_variance: 0.04,
+            mean_reversion: 2.0,
+            long_term_variance: 0.04,
+            vol_of_vol: 1e-4,
+            correlation: 0.0,
+        };
+        let adi = HestonAdi::new(0.02, parameters)
+            .with_bounds(4.0, 0.5)
+            .with_grid(100, 50, 50);
+        let black_scholes = |spot: f64, strike: f64| {
+            BsmComputation::new(&DerivativeParameter::new(spot, strike, 0.5, 0.02, 0.2))
+        };
+
+        let digital = black_scholes(100.0, 110.0);
+        let greeks = adi
+            .greeks(
+                100.0,
+                110.0,
+                0.5,
+                &ExerciseType::Call,
+                None,
+                Payoff::CashOrNothing,
+            )
+            .unwrap();
+        assert_approx_eq!(greeks.value, digital.disc_factor * cdf(digital.d2), 0.002);
+        assert_approx_eq!(
+            greeks.delta.unwrap(),
+            digital.disc_factor * pdf(digital.d2) / (0.2 * 0.5_f64.sqrt() * 100.0),
+            2e-4
+        );
+        let uniform = adi
+            .clone()
+            .with_stretching(None, None)
+            .digital_price(100.0, 110.0, 0.5, &ExerciseType::Call, None)
+            .unwrap();
+        assert!((uniform - greeks.value).abs() > 0.01);
+        let digital_put = adi
+            .clone()
+            .with_time_stepping(4, 2.0)
+            .digital_price(100.0, 110.0, 0.5, &ExerciseType::Put, None)
+            .unwrap();
+        assert_approx_eq!(greeks.value + digital_put, digital.disc_factor, 0.002);
+
+        // the down-and-out call $C(S) - (B / S)^{2 r / \sigma^2 - 1} C(B^2 / S)$ close to the barrier
+        let (barrier, strike) = (90.0, 100.0);
+        let down_and_out = |spot: f64| {
+            black_scholes(spot, strike).call()
+                - (barrier / spot).powf(2.0 * 0.02 / 0.04 - 1.0)
+                    * black_scholes(barrier * barrier / spot, strike).call()
+        };
+        let greeks = adi
+            .greeks(
+                92.0,
+                strike,
+                0.5,
+                &ExerciseType::Call,
+                Some(KnockOut::Down(barrier)),
+                Payoff::Vanilla,
+            )
+            .unwrap();
+        assert_approx_eq!(greeks.value, down_and_out(92.0), 0.005);
+        let h = 1e-3;
+        let delta = (down_and_out(92.0 + h) - down_and_out(92.0 - h)) / (2.0 * h);
+        let gamma =
+            (down_and_out(92.0 + h) - 2.0 * down_and_out(92.0) + down_and_out(92.0 - h)) / (h * h);
+        assert_approx_eq!(greeks.delta.unwrap(), delta, 0.002);
+        assert_approx_eq!(greeks.gamma.unwrap(), gamma, 0.002);
+        assert!(adi
+            .clone()
+            .with_time_stepping(60, 1.0)
+            .price(100.0, 100.0, 1.0, &ExerciseType::Call, None)
+            .is_err());
+    }
+}
@@ -0,0 +1,4 @@
+//! Finite difference solvers of pricing PDEs on grids, backwards from the payoff.
+
+pub mod grid;
+pub mod heston_adi;
@@ -0,0 +1,36 @@
+//! The commonly used types of the crate in one import, `use pricing::prelude::*;`, independent of the
+//! module layout: results and errors, market parameters, curves, analytic formulas, the Monte Carlo
+//! simulator with its SDEs and products.
+
+pub use crate::analytic::black_scholes::{
+    Bachelier, Black76, BlackScholesMerton, BsmComputation, OptionPrice, ShiftedBlack76,
+};
+pub use crate::analytic::dual_greeks::AnalyticFormula;
+pub use crate::common::market_context::{MarketContext, MarketSnapshot};
+pub use crate::common::models::{DerivativeParameter, ExerciseType, Greek};
+pub use crate::common::pricer::{Pricer, Product, VanillaOption};
+pub use crate::common::results::{GreeksResult, PriceResult, PricingError};
+pub use crate::common::time_grid::TimeGrid;
+pub use crate::common::vol_surface::VolatilitySurface;
+pub use crate::curves::cashflows::{Leg, Swap};
+pub use crate::curves::hazard_rate::{PiecewiseHazardCurve, SurvivalCurve};
+pub use crate::curves::yield_curve::{FlatCurve, InterpolatedCurve, YieldCurve};
+pub use crate::lattice::trinomial_tree::TrinomialTree;
+#[cfg(feature = "mc")]
+pub use crate::simulation::monte_carlo::{MonteCarloPathSimulator, PathEvaluator, PathGenerator};
+#[cfg(feature = "mc")]
+pub use crate::simulation::noise::NoiseSource;
+#[cfg(feature = "multi-asset")]
+pub use crate::simulation::products::basket_option::MonteCarloEuropeanBasketOption;
+#[cfg(feature = "mc")]
+pub use crate::simulation::products::european_option::MonteCarloEuropeanOption;
+#[cfg(feature = "multi-asset")]
+pub use crate::simulation::products::reverse_convertible::WorstOfReverseConvertible;
+#[cfg(feature = "mc")]
+pub use crate::simulation::sde::gbm::GeometricBrownianMotion;
+#[cfg(feature = "multi-asset")]
+pub use crate::simulation::sde::multivariate_gbm::MultivariateGeometricBrownianMotion;
+#[cfg(feature = "multi-asset")]
+pub use crate::simulation::sde::multivariate_jump_diffusion::MultivariateJumpDiffusion;
+#[cfg(feature = "mc")]
+pub use crate::simulation::sde::shifted_lognormal::ShiftedLognormalForward;
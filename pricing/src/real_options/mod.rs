@@ -0,0 +1,2 @@
+pub mod optimal_stopping;
+pub mod perpetual_option;
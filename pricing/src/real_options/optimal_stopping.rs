@@ -0,0 +1,153 @@
+/// The value function of an infinite-horizon optimal stopping problem on a grid of spots.
+#[derive(Clone, Debug)]
+pub struct OptimalStoppingSolution {
+    spots: Vec<f64>,
+    values: Vec<f64>,
+    payoffs: Vec<f64>,
+}
+
+impl OptimalStoppingSolution {
+    pub fn spots(&self) -> &[f64] {
+        &self.spots
+    }
+
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    /// The value at the spot by linear interpolation in the log spot, or None outside of the grid.
+    pub fn value(&self, spot: f64) -> Option<f64> {
+        let idx = self.spots.partition_point(|s| *s < spot);
+        if idx == 0 {
+            return (spot == self.spots[0]).then(|| self.values[0]);
+        }
+        if idx == self.spots.len() {
+            return None;
+        }
+        let (lower, upper) = (self.spots[idx - 1].ln(), self.spots[idx].ln());
+        let weight = (spot.ln() - lower) / (upper - lower);
+        Some((1.0 - weight) * self.values[idx - 1] + weight * self.values[idx])
+    }
+
+    /// The spots of the grid at which stopping is optimal, i.e. the value equals the (positive) payoff.
+    pub fn exercise_spots(&self) -> Vec<f64> {
+        self.spots
+            .iter()
+            .zip(self.values.iter().zip(&self.payoffs))
+            .filter(|(_, (value, payoff))| **payoff > 0.0 && *value <= *payoff)
+            .map(|(spot, _)| *spot)
+            .collect()
+    }
+}
+
+/// Solves the optimal stopping problem $V(S) = sup_\tau E[e^{-r\tau} g(S_\tau)]$ for a GBM with
+/// continuous dividend yield and a perpetual payoff g, i.e. the linear complementarity problem
+/// '''math
+/// \min(rV - (r - q) S V' - \frac{1}{2}\sigma^2 S^2 V'', V - g) = 0
+/// '''
+/// by projected successive over-relaxation on a log-spot grid.
+/// See https://en.wikipedia.org/wiki/Optimal_stopping
+#[derive(Clone, Copy, Debug)]
+pub struct OptimalStoppingSolver {
+    pub rfr: f64,
+    pub dividend_yield: f64,
+    pub vola: f64,
+    pub nr_nodes: usize,
+    /// the grid covers the spots $S_c e^{\pm range}$ around the center spot $S_c$
+    pub log_range: f64,
+}
+
+impl OptimalStoppingSolver {
+    const RELAXATION: f64 = 1.9;
+    const TOLERANCE: f64 = 1e-10;
+    const MAX_ITERATIONS: usize = 200_000;
+
+    pub fn new(rfr: f64, dividend_yield: f64, vola: f64) -> Self {
+        assert!(rfr > 0.0 && vola > 0.0);
+        Self {
+            rfr,
+            dividend_yield,
+            vola,
+            nr_nodes: 401,
+            log_range: 4.0,
+        }
+    }
+
+    pub fn with_grid(self, nr_nodes: usize, log_range: f64) -> Self {
+        assert!(nr_nodes > 2 && log_range > 0.0);
+        Self {
+            nr_nodes,
+            log_range,
+            ..self
+        }
+    }
+
+    /// The value function on the grid around `center_spot`, where the values at the grid boundaries
+    /// are the payoffs. Returns None if the iteration does not converge.
+    pub fn solve(
+        &self,
+        center_spot: f64,
+        payoff: impl Fn(f64) -> f64,
+    ) -> Option<OptimalStoppingSolution> {
+        let dx = 2.0 * self.log_range / (self.nr_nodes - 1) as f64;
+        let spots: Vec<f64> = (0..self.nr_nodes)
+            .map(|i| center_spot * (-self.log_range + i as f64 * dx).exp())
+            .collect();
+        let payoffs: Vec<f64> = spots.iter().map(|s| payoff(*s)).collect();
+
+        let diffusion = self.vola.powi(2) / (2.0 * dx.powi(2));
+        let drift = (self.rfr - self.dividend_yield - self.vola.powi(2) / 2.0) / (2.0 * dx);
+        let (lower, upper) = (diffusion - drift, diffusion + drift);
+        let diagonal = 2.0 * diffusion + self.rfr;
+
+        let mut values = payoffs.clone();
+        for _ in 0..Self::MAX_ITERATIONS {
+            let mut max_change: f64 = 0.0;
+            for i in 1..self.nr_nodes - 1 {
+                let gauss_seidel = (lower * values[i - 1] + upper * values[i + 1]) / diagonal;
+                let relaxed = values[i] + Self::RELAXATION * (gauss_seidel - values[i]);
+                let updated = relaxed.max(payoffs[i]);
+                max_change = max_change.max((updated - values[i]).abs());
+                values[i] = updated;
+            }
+            if max_change < Self::TOLERANCE {
+                return Some(OptimalStoppingSolution {
+                    spots,
+                    values,
+                    payoffs,
+                });
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::real_options::perpetual_option::PerpetualAmericanOption;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn perpetual_options() {
+        let (strike, rfr, dividend_yield, vola) = (100.0, 0.05, 0.03, 0.25);
+        let closed_form = PerpetualAmericanOption::new(strike, rfr, dividend_yield, vola);
+        let solver = OptimalStoppingSolver::new(rfr, dividend_yield, vola);
+
+        let put = solver.solve(strike, |s| (strike - s).max(0.0)).unwrap();
+        for spot in [60.0, 90.0, 100.0, 150.0] {
+            assert_approx_eq!(put.value(spot).unwrap(), closed_form.put(spot), 5e-2);
+        }
+        let put_boundary = put.exercise_spots().into_iter().fold(0.0, f64::max);
+        assert_approx_eq!(put_boundary, closed_form.put_threshold().unwrap(), 2.0);
+
+        let call = solver.solve(strike, |s| (s - strike).max(0.0)).unwrap();
+        for spot in [60.0, 100.0, 150.0] {
+            assert_approx_eq!(call.value(spot).unwrap(), closed_form.call(spot), 5e-2);
+        }
+        let call_boundary = call.exercise_spots().into_iter().fold(f64::MAX, f64::min);
+        assert_approx_eq!(call_boundary, closed_form.call_threshold().unwrap(), 5.0);
+
+        assert!(put.value(1e6).is_none());
+    }
+}
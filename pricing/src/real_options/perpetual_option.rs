@@ -0,0 +1,107 @@
+/// Perpetual American call and put options on an asset with continuous `dividend_yield`
+/// (for real options e.g. the convenience yield or the opportunity cost of waiting),
+/// which are exercised once the asset crosses a constant threshold.
+/// See https://en.wikipedia.org/wiki/Real_options_valuation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PerpetualAmericanOption {
+    pub strike: f64,
+    pub rfr: f64,
+    pub dividend_yield: f64,
+    pub vola: f64,
+}
+
+impl PerpetualAmericanOption {
+    pub fn new(strike: f64, rfr: f64, dividend_yield: f64, vola: f64) -> Self {
+        assert!(strike > 0.0 && vola > 0.0);
+        Self {
+            strike,
+            rfr,
+            dividend_yield,
+            vola,
+        }
+    }
+
+    /// The roots $\beta_{1,2}$ of $\frac{1}{2}\sigma^2 \beta (\beta - 1) + (r - q) \beta - r = 0$.
+    fn characteristic_roots(&self) -> (f64, f64) {
+        let sigma_sq = self.vola.powi(2);
+        let a = 0.5 - (self.rfr - self.dividend_yield) / sigma_sq;
+        let root = (a.powi(2) + 2.0 * self.rfr / sigma_sq).sqrt();
+        (a + root, a - root)
+    }
+
+    /// The spot at and above which the call is exercised, or None if it is never exercised
+    /// (for a non-positive dividend yield).
+    pub fn call_threshold(&self) -> Option<f64> {
+        if self.dividend_yield <= 0.0 {
+            return None;
+        }
+        let (beta, _) = self.characteristic_roots();
+        Some(beta / (beta - 1.0) * self.strike)
+    }
+
+    /// The spot at and below which the put is exercised, or None if it is never exercised
+    /// (for a non-positive interest rate).
+    pub fn put_threshold(&self) -> Option<f64> {
+        if self.rfr <= 0.0 {
+            return None;
+        }
+        let (_, beta) = self.characteristic_roots();
+        Some(beta / (beta - 1.0) * self.strike)
+    }
+
+    pub fn call(&self, spot: f64) -> f64 {
+        match self.call_threshold() {
+            // the value converges to the spot for ever later exercise
+            None => spot,
+            Some(threshold) if spot >= threshold => spot - self.strike,
+            Some(threshold) => {
+                let (beta, _) = self.characteristic_roots();
+                (threshold - self.strike) * (spot / threshold).powf(beta)
+            }
+        }
+    }
+
+    pub fn put(&self, spot: f64) -> f64 {
+        match self.put_threshold() {
+            None => self.strike,
+            Some(threshold) if spot <= threshold => self.strike - spot,
+            Some(threshold) => {
+                let (_, beta) = self.characteristic_roots();
+                (self.strike - threshold) * (spot / threshold).powf(beta)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn smooth_pasting() {
+        let option = PerpetualAmericanOption::new(100.0, 0.05, 0.03, 0.25);
+        let h = 1e-6;
+
+        let call_threshold = option.call_threshold().unwrap();
+        assert!(call_threshold > 100.0);
+        assert_approx_eq!(option.call(call_threshold), call_threshold - 100.0, 1e-10);
+        let call_delta = (option.call(call_threshold) - option.call(call_threshold - h)) / h;
+        assert_approx_eq!(call_delta, 1.0, 1e-4);
+
+        let put_threshold = option.put_threshold().unwrap();
+        assert!(put_threshold < 100.0);
+        let put_delta = (option.put(put_threshold + h) - option.put(put_threshold)) / h;
+        assert_approx_eq!(put_delta, -1.0, 1e-4);
+    }
+
+    #[test]
+    fn no_early_exercise() {
+        let option = PerpetualAmericanOption::new(100.0, 0.05, 0.0, 0.25);
+        assert!(option.call_threshold().is_none());
+        assert_eq!(option.call(90.0), 90.0);
+        // the put value decreases in the spot and is bounded by the strike
+        assert!(option.put(80.0) > option.put(120.0));
+        assert!(option.put(1.0) <= 100.0);
+    }
+}
@@ -0,0 +1,290 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Running sums of the samples of an estimator.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PartialSums {
+    pub sum: f64,
+    pub sum_sq: f64,
+    pub count: usize,
+}
+
+impl PartialSums {
+    pub fn add(&mut self, value: f64) {
+        self.sum += value;
+        self.sum_sq += value * value;
+        self.count += 1;
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        Some(self.sum / self.count as f64)
+    }
+
+    /// The (unbiased) sample variance.
+    pub fn variance(&self) -> Option<f64> {
+        if self.count < 2 {
+            return None;
+        }
+        let mean = self.sum / self.count as f64;
+        Some((self.sum_sq - self.count as f64 * mean * mean) / (self.count - 1) as f64)
+    }
+}
+
+/// The state of a level of a checkpointed simulation.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LevelState {
+    /// the number of completed batches, which determines the random number generator of the next batch
+    pub nr_completed_batches: usize,
+    pub sums: PartialSums,
+}
+
+/// A snapshot of a (multi-level) simulation, from which the simulation can be resumed.
+/// The random number generator of each batch is seeded by the seed number, the level and the batch index,
+/// so the seed number and the completed batches fully determine the random number state.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Checkpoint {
+    pub seed_nr: u64,
+    pub levels: Vec<LevelState>,
+}
+
+impl Checkpoint {
+    pub fn new(seed_nr: u64, nr_levels: usize) -> Self {
+        Self {
+            seed_nr,
+            levels: vec![LevelState::default(); nr_levels],
+        }
+    }
+
+    /// The multi-level estimate, i.e. the sum of the level means (the mean for a single level).
+    pub fn estimate(&self) -> Option<f64> {
+        self.levels.iter().map(|level| level.sums.mean()).sum()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|err: ParseCheckpointError| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+/// One line per level after the seed, where the floats are written in their (exactly round-tripping)
+/// shortest representation.
+impl fmt::Display for Checkpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "seed_nr {}", self.seed_nr)?;
+        for level in self.levels.iter() {
+            writeln!(
+                f,
+                "level {} {} {} {}",
+                level.nr_completed_batches, level.sums.count, level.sums.sum, level.sums.sum_sq
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseCheckpointError(String);
+
+impl fmt::Display for ParseCheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid checkpoint: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseCheckpointError {}
+
+impl FromStr for Checkpoint {
+    type Err = ParseCheckpointError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn field<T: FromStr>(token: Option<&str>, line: &str) -> Result<T, ParseCheckpointError> {
+            token
+                .and_then(|t| t.parse().ok())
+                .ok_or_else(|| ParseCheckpointError(line.to_string()))
+        }
+
+        let mut lines = s.lines();
+        let first = lines.next().unwrap_or_default();
+        let seed_nr = field(first.strip_prefix("seed_nr "), first)?;
+
+        let levels = lines
+            .map(|line| {
+                let mut tokens = line.strip_prefix("level ").unwrap_or_default().split(' ');
+                Ok(LevelState {
+                    nr_completed_batches: field(tokens.next(), line)?,
+                    sums: PartialSums {
+                        count: field(tokens.next(), line)?,
+                        sum: field(tokens.next(), line)?,
+                        sum_sq: field(tokens.next(), line)?,
+                    },
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { seed_nr, levels })
+    }
+}
+
+/// A simulation of `nr_batches[l]` batches of `batch_size` samples per level l, which writes a checkpoint
+/// after every `checkpoint_interval` batches and can be resumed from any checkpoint with identical final
+/// results to an uninterrupted run. For a single level, this is a plain Monte Carlo simulation,
+/// for multiple levels the level samples are the corrections of a multi-level Monte Carlo estimator.
+pub struct CheckpointedSimulation<SeedRng>
+where
+    SeedRng: rand::SeedableRng + rand::RngCore,
+{
+    nr_batches: Vec<usize>,
+    batch_size: usize,
+    checkpoint_interval: usize,
+    _phantom_rng: PhantomData<SeedRng>,
+}
+
+impl<SeedRng> CheckpointedSimulation<SeedRng>
+where
+    SeedRng: rand::SeedableRng + rand::RngCore,
+{
+    pub fn new(nr_batches: Vec<usize>, batch_size: usize, checkpoint_interval: usize) -> Self {
+        assert!(checkpoint_interval > 0);
+        Self {
+            nr_batches,
+            batch_size,
+            checkpoint_interval,
+            _phantom_rng: PhantomData::<SeedRng>,
+        }
+    }
+
+    pub fn start(&self, seed_nr: u64) -> Checkpoint {
+        Checkpoint::new(seed_nr, self.nr_batches.len())
+    }
+
+    pub fn is_complete(&self, checkpoint: &Checkpoint) -> bool {
+        checkpoint
+            .levels
+            .iter()
+            .zip(&self.nr_batches)
+            .all(|(level, nr_batches)| level.nr_completed_batches >= *nr_batches)
+    }
+
+    fn batch_rng(seed_nr: u64, level: usize, batch: usize) -> SeedRng {
+        SeedRng::seed_from_u64(
+            seed_nr
+                .wrapping_add((level as u64) << 32)
+                .wrapping_add(batch as u64),
+        )
+    }
+
+    /// Runs the remaining batches from the checkpoint, where `sampler` draws a sample of the given level
+    /// (samples with None are skipped). After every `checkpoint_interval` batches, `on_checkpoint` is called
+    /// with the current state, e.g. to persist it, and the run is interrupted if it returns false.
+    /// Returns the state at completion or interruption.
+    pub fn resume(
+        &self,
+        mut checkpoint: Checkpoint,
+        sampler: impl Fn(usize, &mut SeedRng) -> Option<f64>,
+        mut on_checkpoint: impl FnMut(&Checkpoint) -> bool,
+    ) -> Checkpoint {
+        assert_eq!(checkpoint.levels.len(), self.nr_batches.len());
+        let mut batches_since_checkpoint = 0;
+        for (l, nr_batches) in self.nr_batches.iter().enumerate() {
+            while checkpoint.levels[l].nr_completed_batches < *nr_batches {
+                let level = &mut checkpoint.levels[l];
+                let mut rn_generator =
+                    Self::batch_rng(checkpoint.seed_nr, l, level.nr_completed_batches);
+                for _ in 0..self.batch_size {
+                    if let Some(sample) = sampler(l, &mut rn_generator) {
+                        level.sums.add(sample);
+                    }
+                }
+                level.nr_completed_batches += 1;
+
+                batches_since_checkpoint += 1;
+                if batches_since_checkpoint == self.checkpoint_interval {
+                    batches_since_checkpoint = 0;
+                    if !self.is_complete(&checkpoint) && !on_checkpoint(&checkpoint) {
+                        return checkpoint;
+                    }
+                }
+            }
+        }
+        checkpoint
+    }
+
+    /// Runs the simulation from the start without interruption.
+    pub fn run(
+        &self,
+        seed_nr: u64,
+        sampler: impl Fn(usize, &mut SeedRng) -> Option<f64>,
+    ) -> Checkpoint {
+        self.resume(self.start(seed_nr), sampler, |_| true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand_distr::StandardNormal;
+
+    fn sampler(level: usize, rng: &mut rand_hc::Hc128Rng) -> Option<f64> {
+        let z: f64 = rng.sample(StandardNormal);
+        match level {
+            0 => Some(1.0 + z),
+            _ => (z > -1.0).then_some(0.1 * z),
+        }
+    }
+
+    #[test]
+    fn resume_matches_uninterrupted_run() {
+        let simulation: CheckpointedSimulation<rand_hc::Hc128Rng> =
+            CheckpointedSimulation::new(vec![10, 6], 1_000, 2);
+        let uninterrupted = simulation.run(42, sampler);
+        assert!(simulation.is_complete(&uninterrupted));
+        assert_eq!(uninterrupted.levels[0].sums.count, 10_000);
+
+        // interrupt after the third checkpoint and resume from the persisted state
+        let mut nr_checkpoints = 0;
+        let interrupted = simulation.resume(simulation.start(42), sampler, |_| {
+            nr_checkpoints += 1;
+            nr_checkpoints < 3
+        });
+        assert!(!simulation.is_complete(&interrupted));
+        assert_eq!(interrupted.levels[0].nr_completed_batches, 6);
+
+        // unique per process and run, such that concurrent test runs do not share the file
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let file = std::env::temp_dir().join(format!(
+            "math_finance_checkpoint_{}_{nanos}.txt",
+            std::process::id()
+        ));
+        interrupted.save(&file).unwrap();
+        let restored = Checkpoint::load(&file).unwrap();
+        fs::remove_file(&file).unwrap();
+        assert_eq!(restored, interrupted);
+
+        let resumed = simulation.resume(restored, sampler, |_| true);
+        assert_eq!(resumed, uninterrupted);
+        assert_eq!(resumed.estimate(), uninterrupted.estimate());
+    }
+
+    #[test]
+    fn invalid_checkpoint() {
+        assert!("seed_nr 1\nlevel 2 x 0 0".parse::<Checkpoint>().is_err());
+        assert!("".parse::<Checkpoint>().is_err());
+        let checkpoint = Checkpoint::new(7, 2);
+        assert_eq!(checkpoint.to_string().parse(), Ok(checkpoint));
+    }
+}
@@ -0,0 +1,141 @@
+use std::marker::PhantomData;
+
+use crate::simulation::monte_carlo::PathGenerator;
+
+/// Common random numbers for custom sensitivities: each of several (bumped) pricings gets a fresh
+/// random number generator in the same initial state, and thus the identical random inputs,
+/// such that the differences of the prices are due to the bumps and not to the sampling noise.
+/// No generator state is shared between the pricings, so a pricing which draws more or fewer
+/// random numbers does not change the others.
+/// See Glasserman, Monte Carlo Methods in Financial Engineering (2003), section 7.1
+#[derive(Clone, Debug)]
+pub struct CommonRandomNumbers<SeedRng>
+where
+    SeedRng: rand::SeedableRng + rand::RngCore,
+{
+    nr_paths: usize,
+    nr_steps: usize,
+    seed_nr: u64,
+    _phantom_rng: PhantomData<SeedRng>,
+}
+
+impl<SeedRng> CommonRandomNumbers<SeedRng>
+where
+    SeedRng: rand::SeedableRng + rand::RngCore,
+{
+    pub fn new(nr_paths: usize, nr_steps: usize, seed_nr: u64) -> Self {
+        Self {
+            nr_paths,
+            nr_steps,
+            seed_nr,
+            _phantom_rng: PhantomData::<SeedRng>,
+        }
+    }
+
+    pub fn nr_paths(&self) -> usize {
+        self.nr_paths
+    }
+
+    pub fn nr_steps(&self) -> usize {
+        self.nr_steps
+    }
+
+    /// A fresh generator in the common initial state.
+    pub fn rn_generator(&self) -> SeedRng {
+        SeedRng::seed_from_u64(self.seed_nr)
+    }
+
+    /// The paths of the path generator from the common random numbers, which are identical to
+    /// the paths of a `MonteCarloPathSimulator` with the same seed.
+    pub fn simulate_paths<PathGen, Path>(&self, path_generator: &PathGen) -> Vec<Path>
+    where
+        PathGen: PathGenerator<Path>,
+    {
+        let mut rn_generator = self.rn_generator();
+        (0..self.nr_paths)
+            .map(|_| path_generator.sample_path(&mut rn_generator, self.nr_steps))
+            .collect()
+    }
+
+    /// Prices each of the (bumped) path generators on its paths from the common random numbers.
+    pub fn price_bumped<PathGen, Path, T>(
+        &self,
+        path_generators: &[PathGen],
+        pricing: impl Fn(&[Path]) -> T,
+    ) -> Vec<T>
+    where
+        PathGen: PathGenerator<Path>,
+    {
+        path_generators
+            .iter()
+            .map(|path_generator| pricing(&self.simulate_paths(path_generator)))
+            .collect()
+    }
+
+    /// Runs the closure for each of the (bumped) scenarios with a fresh generator in the common
+    /// initial state, for pricings which draw the random numbers themselves.
+    pub fn run<Scenario, T>(
+        &self,
+        scenarios: &[Scenario],
+        pricing: impl Fn(&Scenario, &mut SeedRng) -> T,
+    ) -> Vec<T> {
+        scenarios
+            .iter()
+            .map(|scenario| pricing(scenario, &mut self.rn_generator()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::BsmComputation;
+    use crate::common::models::{DerivativeParameter, ExerciseType};
+    use crate::simulation::monte_carlo::{MonteCarloPathSimulator, PathEvaluator};
+    use crate::simulation::sde::gbm::GeometricBrownianMotion;
+    use rand::Rng;
+
+    #[test]
+    fn bumped_delta_with_common_random_numbers() {
+        let (spot, strike, rfr, vola, t, nr_steps) = (100.0, 100.0, 0.03, 0.2, 1.0, 10);
+        let bump = 0.01 * spot;
+        let gbm = |s0: f64| GeometricBrownianMotion::new(s0, rfr, vola, t / nr_steps as f64);
+        let call = |paths: &[Vec<f64>]| {
+            let payoff = |path: &Vec<f64>| path.last().map(|s| (s - strike).max(0.0));
+            (-rfr * t).exp() * PathEvaluator::new(paths).evaluate_average(payoff).unwrap()
+        };
+
+        let crn: CommonRandomNumbers<rand_hc::Hc128Rng> =
+            CommonRandomNumbers::new(10_000, nr_steps, 42);
+        let prices = crn.price_bumped(&[gbm(spot + bump), gbm(spot - bump)], call);
+        let delta = (prices[0] - prices[1]) / (2.0 * bump);
+        let reference = BsmComputation::new(&DerivativeParameter::new(spot, strike, t, rfr, vola));
+        assert!(
+            (delta - reference.delta(&ExerciseType::Call)).abs() < 0.01,
+            "{delta}"
+        );
+
+        // the paths are the ones of the simulator with the same seed
+        let simulator: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, Vec<f64>> =
+            MonteCarloPathSimulator::new(gbm(spot), Some(42));
+        assert_eq!(
+            crn.simulate_paths(&gbm(spot)),
+            simulator.simulate_paths(10_000, nr_steps)
+        );
+    }
+
+    #[test]
+    fn no_state_leakage_between_pricings() {
+        let crn: CommonRandomNumbers<rand_hc::Hc128Rng> = CommonRandomNumbers::new(1, 1, 7);
+        let draws = |nr_draws: &usize, rn_generator: &mut rand_hc::Hc128Rng| {
+            (0..*nr_draws)
+                .map(|_| rn_generator.gen::<f64>())
+                .collect::<Vec<_>>()
+        };
+        let results = crn.run(&[3, 1, 5], draws);
+        // every pricing starts from the same state, irrespective of the draws of the others
+        assert_eq!(results[1][0], results[0][0]);
+        assert_eq!(results[2][..3], results[0][..]);
+        assert_eq!(crn.run(&[5], draws)[0], results[2]);
+    }
+}
@@ -0,0 +1,260 @@
+use ndarray::{Array1, Array2, Axis};
+use probability::distribution::{Beta, Distribution as _, Inverse};
+use rand::Rng;
+use rand_distr::{ChiSquared, Distribution, StandardNormal};
+
+use crate::common::results::PricingError;
+use crate::numerics::correlation::ensure_positive_definite;
+use crate::numerics::linalg::cholesky;
+use crate::numerics::solvers::{golden_section, SolverOptions};
+
+/// The degrees of freedom between which the calibration searches, beyond which the copula is Gaussian.
+const DEGREES_OF_FREEDOM_RANGE: (f64, f64) = (1.0, 200.0);
+
+/// The logarithm of the gamma function by the Lanczos approximation (g = 7), accurate to about 1e-15.
+/// See https://en.wikipedia.org/wiki/Lanczos_approximation
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // the reflection formula
+        return (std::f64::consts::PI / (std::f64::consts::PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |sum, (k, c)| {
+            sum + c / (x + k as f64 + 1.0)
+        });
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+/// The distribution function of Student's t distribution with ν degrees of freedom,
+/// $1 - \frac{1}{2} I_{\nu / (\nu + x^2)}(\frac{\nu}{2}, \frac{1}{2})$ for $x \ge 0$.
+/// See https://en.wikipedia.org/wiki/Student%27s_t-distribution
+pub fn student_t_cdf(x: f64, degrees_of_freedom: f64) -> f64 {
+    let nu = degrees_of_freedom;
+    let tail = 0.5 * Beta::new(0.5 * nu, 0.5, 0.0, 1.0).distribution(nu / (nu + x * x));
+    if x >= 0.0 {
+        1.0 - tail
+    } else {
+        tail
+    }
+}
+
+/// The quantile function of Student's t distribution with ν degrees of freedom.
+pub fn student_t_quantile(p: f64, degrees_of_freedom: f64) -> f64 {
+    if p < 0.5 {
+        return -student_t_quantile(1.0 - p, degrees_of_freedom);
+    }
+    let nu = degrees_of_freedom;
+    let z = Beta::new(0.5 * nu, 0.5, 0.0, 1.0).inverse(2.0 * (1.0 - p));
+    (nu * (1.0 - z) / z).sqrt()
+}
+
+/// Kendall's rank correlation $\tau = (n_c - n_d) / \binom{n}{2}$ of the concordant and discordant pairs.
+/// See https://en.wikipedia.org/wiki/Kendall_rank_correlation_coefficient
+pub fn kendall_tau(x: &[f64], y: &[f64]) -> f64 {
+    assert_eq!(x.len(), y.len());
+    let n = x.len();
+    let mut concordance = 0.0;
+    for i in 0..n {
+        for j in i + 1..n {
+            concordance += ((x[i] - x[j]) * (y[i] - y[j])).signum();
+        }
+    }
+    concordance / (n * (n - 1) / 2) as f64
+}
+
+/// The pseudo-observations $r / (n + 1)$ of the ranks r of each column of the observations.
+pub fn pseudo_observations(observations: &Array2<f64>) -> Array2<f64> {
+    let n = observations.nrows();
+    let mut uniforms = Array2::zeros(observations.raw_dim());
+    for (column, mut ranks) in observations
+        .axis_iter(Axis(1))
+        .zip(uniforms.axis_iter_mut(Axis(1)))
+    {
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|i, j| column[*i].total_cmp(&column[*j]));
+        for (rank, idx) in order.into_iter().enumerate() {
+            ranks[idx] = (rank + 1) as f64 / (n + 1) as f64;
+        }
+    }
+    uniforms
+}
+
+/// The Student t copula of the correlation matrix and the degrees of freedom ν, the dependence of
+/// $t_\nu(X)$ for $X = Z / \sqrt{W / \nu}$ with correlated normals Z and an independent $W \sim \chi^2_\nu$.
+/// Unlike the Gaussian copula, it has symmetric tail dependence, i.e. joint extreme moves.
+/// See https://en.wikipedia.org/wiki/Copula_(probability_theory)#Elliptical_copulas
+#[derive(Clone, Debug)]
+pub struct StudentTCopula {
+    correlation: Array2<f64>,
+    degrees_of_freedom: f64,
+    cholesky_factor: Array2<f64>,
+}
+
+impl StudentTCopula {
+    pub fn new(correlation: Array2<f64>, degrees_of_freedom: f64) -> Result<Self, PricingError> {
+        PricingError::check(
+            degrees_of_freedom > 0.0,
+            "degrees of freedom",
+            degrees_of_freedom,
+        )?;
+        let cholesky_factor = cholesky(&correlation)
+            .ok_or_else(|| PricingError::InvalidParameter("correlation".to_string()))?;
+        Ok(Self {
+            correlation,
+            degrees_of_freedom,
+            cholesky_factor,
+        })
+    }
+
+    /// Calibrates the copula to the historical returns (one row per time, one column per asset):
+    /// the correlation by inverting Kendall's tau, $\rho = \sin(\pi \tau / 2)$, repaired if it is not
+    /// positive definite, and the degrees of freedom by maximizing the likelihood of the
+    /// pseudo-observations for this correlation.
+    pub fn calibrate(returns: &Array2<f64>) -> Result<Self, PricingError> {
+        let (nr_obs, dim) = returns.dim();
+        PricingError::check(nr_obs > dim, "nr observations", nr_obs as f64)?;
+        let mut correlation = Array2::eye(dim);
+        for i in 0..dim {
+            for j in 0..i {
+                let tau = kendall_tau(&returns.column(i).to_vec(), &returns.column(j).to_vec());
+                let rho = (0.5 * std::f64::consts::PI * tau).sin();
+                correlation[[i, j]] = rho;
+                correlation[[j, i]] = rho;
+            }
+        }
+        let correlation = ensure_positive_definite(correlation);
+        let uniforms = pseudo_observations(returns);
+
+        let (lower, upper) = DEGREES_OF_FREEDOM_RANGE;
+        let negative_log_likelihood = |ln_nu: f64| {
+            Self::new(correlation.clone(), ln_nu.exp())
+                .map_or(f64::INFINITY, |copula| -copula.log_likelihood(&uniforms))
+        };
+        let solution = golden_section(
+            negative_log_likelihood,
+            lower.ln(),
+            upper.ln(),
+            &SolverOptions::new(1e-4, 0.0, 200),
+        );
+        Self::new(correlation, solution.x.exp())
+    }
+
+    pub fn correlation(&self) -> &Array2<f64> {
+        &self.correlation
+    }
+
+    pub fn degrees_of_freedom(&self) -> f64 {
+        self.degrees_of_freedom
+    }
+
+    pub fn dim(&self) -> usize {
+        self.correlation.nrows()
+    }
+
+    /// The coefficient $\lambda = 2 t_{\nu + 1}(-\sqrt{(\nu + 1)(1 - \rho) / (1 + \rho)})$ of the (lower and
+    /// upper) tail dependence of the assets, the limit of $P(U_i \le q | U_j \le q)$ as $q \to 0$.
+    /// See https://en.wikipedia.org/wiki/Tail_dependence
+    pub fn tail_dependence(&self, i: usize, j: usize) -> f64 {
+        let (nu, rho) = (self.degrees_of_freedom, self.correlation[[i, j]]);
+        2.0 * student_t_cdf(-((nu + 1.0) * (1.0 - rho) / (1.0 + rho)).sqrt(), nu + 1.0)
+    }
+
+    /// The log-likelihood of the uniforms (one row per observation) under the copula density
+    /// $c(u) = f_{\nu, R}(x) / \prod_i f_\nu(x_i)$ for $x_i = t_\nu^{-1}(u_i)$.
+    pub fn log_likelihood(&self, uniforms: &Array2<f64>) -> f64 {
+        let (nu, d) = (self.degrees_of_freedom, self.dim() as f64);
+        let ln_det = 2.0 * self.cholesky_factor.diag().mapv(f64::ln).sum();
+        let constant = ln_gamma(0.5 * (nu + d)) + (d - 1.0) * ln_gamma(0.5 * nu)
+            - d * ln_gamma(0.5 * (nu + 1.0))
+            - 0.5 * ln_det;
+        uniforms
+            .axis_iter(Axis(0))
+            .map(|u| {
+                let x: Array1<f64> = u.mapv(|u| student_t_quantile(u, nu));
+                // the quadratic form of the inverse correlation by forward substitution
+                let mut y = Array1::<f64>::zeros(x.len());
+                for i in 0..x.len() {
+                    let partial: f64 = (0..i).map(|k| self.cholesky_factor[[i, k]] * y[k]).sum();
+                    y[i] = (x[i] - partial) / self.cholesky_factor[[i, i]];
+                }
+                constant - 0.5 * (nu + d) * (y.dot(&y) / nu).ln_1p()
+                    + 0.5 * (nu + 1.0) * x.iter().map(|x| (x * x / nu).ln_1p()).sum::<f64>()
+            })
+            .sum()
+    }
+
+    /// Samples of the uniforms of the copula, one row per sample.
+    pub fn sample<R: Rng>(&self, rn_generator: &mut R, nr_samples: usize) -> Array2<f64> {
+        let chi_squared = ChiSquared::new(self.degrees_of_freedom).unwrap();
+        let mut uniforms = Array2::zeros((nr_samples, self.dim()));
+        for mut row in uniforms.axis_iter_mut(Axis(0)) {
+            let normals: Array1<f64> = (0..self.dim())
+                .map(|_| rn_generator.sample(StandardNormal))
+                .collect();
+            let scale = (chi_squared.sample(rn_generator) / self.degrees_of_freedom).sqrt();
+            let x = self.cholesky_factor.dot(&normals) / scale;
+            row.assign(&x.mapv(|x| student_t_cdf(x, self.degrees_of_freedom)));
+        }
+        uniforms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use ndarray::arr2;
+    use rand::SeedableRng;
+
+    #[test]
+    fn student_t_distribution() {
+        assert_approx_eq!(student_t_quantile(0.975, 10.0), 2.228_138_851_986_5, 1e-9);
+        assert_approx_eq!(student_t_cdf(-2.228_138_851_986_5, 10.0), 0.025, 1e-12);
+        assert_eq!(student_t_quantile(0.5, 3.0), 0.0);
+        // one degree of freedom is the Cauchy distribution
+        assert_approx_eq!(student_t_cdf(1.0, 1.0), 0.75, 1e-12);
+        assert_approx_eq!(ln_gamma(5.0), 24.0_f64.ln(), 1e-13);
+        assert_approx_eq!(ln_gamma(0.5), 0.5 * std::f64::consts::PI.ln(), 1e-13);
+    }
+
+    #[test]
+    fn calibrate_to_simulated_returns() {
+        let correlation = arr2(&[[1.0, 0.6, 0.3], [0.6, 1.0, 0.4], [0.3, 0.4, 1.0]]);
+        let copula = StudentTCopula::new(correlation.clone(), 4.0).unwrap();
+        let mut rng = rand_hc::Hc128Rng::seed_from_u64(5);
+        let uniforms = copula.sample(&mut rng, 2_000);
+        // returns with other marginals have the same copula
+        let returns = uniforms.mapv(|u| 0.01 * student_t_quantile(u, 3.0));
+
+        let calibrated = StudentTCopula::calibrate(&returns).unwrap();
+        for i in 0..3 {
+            for j in 0..3 {
+                assert_approx_eq!(calibrated.correlation()[[i, j]], correlation[[i, j]], 0.05);
+            }
+        }
+        let nu = calibrated.degrees_of_freedom();
+        assert!(nu > 2.5 && nu < 7.0, "{nu}");
+        assert!(calibrated.tail_dependence(0, 1) > 0.1);
+        // the Kendall tau of the t copula is $2 \arcsin(\rho) / \pi$
+        let tau = kendall_tau(&uniforms.column(0).to_vec(), &uniforms.column(1).to_vec());
+        assert_approx_eq!(tau, 2.0 * 0.6_f64.asin() / std::f64::consts::PI, 0.03);
+        assert!(
+            StudentTCopula::calibrate(&returns.slice(ndarray::s![..3, ..]).to_owned()).is_err()
+        );
+    }
+}
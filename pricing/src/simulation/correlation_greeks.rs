@@ -0,0 +1,167 @@
+use ndarray::Array2;
+
+use crate::common::results::PricingError;
+use crate::numerics::correlation::{covariance_to_correlation, repair_correlation};
+use crate::numerics::linalg::{cholesky, matmul};
+
+/// The smallest eigenvalue of the repaired bumped correlation matrices.
+const MIN_EIGENVALUE: f64 = 1e-8;
+
+/// The shift of the correlations of the cega.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CorrelationBump {
+    /// all correlations shifted by the bump size
+    Parallel,
+    /// the correlation of the pair of assets shifted by the bump size
+    Pair(usize, usize),
+}
+
+impl CorrelationBump {
+    /// Whether the bump shifts the correlation of the assets i and j.
+    fn shifts(&self, i: usize, j: usize) -> bool {
+        match *self {
+            CorrelationBump::Parallel => i != j,
+            CorrelationBump::Pair(k, l) => (i, j) == (k, l) || (i, j) == (l, k),
+        }
+    }
+}
+
+/// The Cholesky factor of the covariance $D (C + \Delta) D$, where the covariance $L L^T = D C D$ of the
+/// Cholesky factor L splits into the volatilities D and the correlations C, and the correlations shifted
+/// by $\Delta$ are capped to [-1, 1] and repaired to a positive definite matrix if necessary.
+pub fn bumped_cholesky_factor(
+    cholesky_factor: &Array2<f64>,
+    bump: CorrelationBump,
+    bump_size: f64,
+) -> Result<Array2<f64>, PricingError> {
+    let covariance = matmul(cholesky_factor, &cholesky_factor.t());
+    let volas = covariance.diag().mapv(f64::sqrt);
+    let mut correlation = covariance_to_correlation(&covariance);
+    let dim = correlation.nrows();
+    for i in 0..dim {
+        for j in 0..dim {
+            if bump.shifts(i, j) {
+                correlation[[i, j]] = (correlation[[i, j]] + bump_size).clamp(-1.0, 1.0);
+            }
+        }
+    }
+    let correlation = match cholesky(&correlation) {
+        Some(_) => correlation,
+        None => repair_correlation(&correlation, MIN_EIGENVALUE),
+    };
+    let covariance = Array2::from_shape_fn((dim, dim), |(i, j)| {
+        volas[i] * volas[j] * correlation[[i, j]]
+    });
+    cholesky(&covariance)
+        .ok_or_else(|| PricingError::InvalidParameter(format!("correlation bump = {bump_size}")))
+}
+
+/// Whether the bump by the size moves a shifted correlation beyond [-1, 1], where it is capped.
+fn is_capped(cholesky_factor: &Array2<f64>, bump: CorrelationBump, bump_size: f64) -> bool {
+    let correlation = covariance_to_correlation(&matmul(cholesky_factor, &cholesky_factor.t()));
+    correlation
+        .indexed_iter()
+        .any(|((i, j), rho)| bump.shifts(i, j) && (rho + bump_size).abs() > 1.0)
+}
+
+/// The cega $\partial V / \partial \rho$ by the central difference of the prices of the bumped Cholesky
+/// factors, or by the one-sided difference away from a correlation within the bump size of -1 or 1,
+/// where the capped bump would understate the shift. The pricer must simulate with common random
+/// numbers (e.g. a fixed seed), such that the prices differ by the bump and not by the sampling noise.
+pub fn cega(
+    cholesky_factor: &Array2<f64>,
+    bump: CorrelationBump,
+    bump_size: f64,
+    price: impl Fn(&Array2<f64>) -> Result<f64, PricingError>,
+) -> Result<f64, PricingError> {
+    PricingError::check(bump_size > 0.0, "correlation bump", bump_size)?;
+    let bumped_price = |size: f64| price(&bumped_cholesky_factor(cholesky_factor, bump, size)?);
+    match (
+        is_capped(cholesky_factor, bump, bump_size),
+        is_capped(cholesky_factor, bump, -bump_size),
+    ) {
+        (false, false) => {
+            Ok((bumped_price(bump_size)? - bumped_price(-bump_size)?) / (2.0 * bump_size))
+        }
+        (true, false) => Ok((bumped_price(0.0)? - bumped_price(-bump_size)?) / bump_size),
+        (false, true) => Ok((bumped_price(bump_size)? - bumped_price(0.0)?) / bump_size),
+        (true, true) => Err(PricingError::InvalidParameter(format!(
+            "correlation bump = {bump_size} beyond both -1 and 1"
+        ))),
+    }
+}
+
+/// The cegas of all pairs of assets as a symmetric matrix with zero diagonal.
+pub fn pairwise_cega(
+    cholesky_factor: &Array2<f64>,
+    bump_size: f64,
+    price: impl Fn(&Array2<f64>) -> Result<f64, PricingError>,
+) -> Result<Array2<f64>, PricingError> {
+    let dim = cholesky_factor.nrows();
+    let mut cegas = Array2::zeros((dim, dim));
+    for i in 0..dim {
+        for j in i + 1..dim {
+            let pair_cega = cega(
+                cholesky_factor,
+                CorrelationBump::Pair(i, j),
+                bump_size,
+                &price,
+            )?;
+            cegas[[i, j]] = pair_cega;
+            cegas[[j, i]] = pair_cega;
+        }
+    }
+    Ok(cegas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use ndarray::arr2;
+
+    #[test]
+    fn bumped_factors() {
+        let factor = cholesky(&arr2(&[
+            [0.04, 0.015, 0.0],
+            [0.015, 0.09, 0.0],
+            [0.0, 0.0, 0.01],
+        ]))
+        .unwrap();
+        let bumped = bumped_cholesky_factor(&factor, CorrelationBump::Pair(0, 1), 0.1).unwrap();
+        let covariance = matmul(&bumped, &bumped.t());
+        assert_approx_eq!(covariance[[0, 0]], 0.04, 1e-15);
+        assert_approx_eq!(covariance[[0, 1]], 0.06 * 0.35, 1e-15);
+        assert_approx_eq!(covariance[[1, 2]], 0.0, 1e-15);
+
+        // the parallel bump beyond the positive definite correlations is repaired
+        let perfect = bumped_cholesky_factor(&factor, CorrelationBump::Parallel, 1.0).unwrap();
+        let correlation = covariance_to_correlation(&matmul(&perfect, &perfect.t()));
+        assert!(correlation[[0, 1]] > 0.99 && correlation[[0, 1]] <= 1.0);
+    }
+
+    #[test]
+    fn cega_of_covariance_function() {
+        // the variance of the sum of two assets grows with $2 \sigma_1 \sigma_2 \rho$
+        let factor = cholesky(&arr2(&[[0.04, 0.0], [0.0, 0.09]])).unwrap();
+        let basket_variance = |factor: &Array2<f64>| Ok(matmul(factor, &factor.t()).sum());
+        let parallel = cega(&factor, CorrelationBump::Parallel, 0.01, basket_variance).unwrap();
+        assert_approx_eq!(parallel, 2.0 * 0.2 * 0.3, 1e-12);
+        let pairwise = pairwise_cega(&factor, 0.01, basket_variance).unwrap();
+        assert_approx_eq!(pairwise[[1, 0]], parallel, 1e-12);
+        assert_eq!(pairwise[[0, 0]], 0.0);
+        assert!(cega(&factor, CorrelationBump::Parallel, 0.0, basket_variance).is_err());
+
+        // close to perfect correlation the difference is one-sided and stays exact
+        let correlated = cholesky(&arr2(&[[0.04, 0.0594], [0.0594, 0.09]])).unwrap();
+        let one_sided = cega(
+            &correlated,
+            CorrelationBump::Parallel,
+            0.02,
+            basket_variance,
+        )
+        .unwrap();
+        assert_approx_eq!(one_sided, 2.0 * 0.2 * 0.3, 1e-9);
+        assert!(cega(&correlated, CorrelationBump::Parallel, 2.5, basket_variance).is_err());
+    }
+}
@@ -1,6 +1,7 @@
+use crate::numerics::linalg::{cholesky, matmul};
 use crate::simulation::monte_carlo::PathGenerator;
 
-use ndarray::{arr1, Array1, Array2};
+use ndarray::{arr1, s, Array1, Array2, Axis};
 use ndarray_rand::RandomExt;
 use rand::Rng;
 use rand_distr::{Distribution, StandardNormal};
@@ -32,13 +33,109 @@ impl PathGenerator<Vec<f64>> for rand_distr::Normal<f64> {
     }
 }
 
+/// Correlation structure of multivariate normal random variables.
+#[derive(Clone, Debug)]
+pub enum CorrelationStructure {
+    /// dense cholesky_factor $C$ which is lower triangular and satisfies $C C^T = \Sigma$
+    /// for the covariance matrix $\Sigma$; the transformation costs $O(n^2)$
+    Cholesky(Array2<f64>),
+    /// factor model $\Sigma = B B^T + diag(d^2)$ with loadings $B$ (n x k) for $k << n$ common factors
+    /// and idiosyncratic volatilities $d$; the transformation costs $O(nk)$
+    Factor {
+        loadings: Array2<f64>,
+        idiosyncratic: Array1<f64>,
+    },
+}
+
+impl CorrelationStructure {
+    /// The Cholesky structure of the covariance matrix, or None if it is not positive definite.
+    pub fn from_covariance(covariance: &Array2<f64>) -> Option<Self> {
+        cholesky(covariance).map(Self::Cholesky)
+    }
+
+    pub fn factor_model(loadings: Array2<f64>, idiosyncratic: Array1<f64>) -> Self {
+        assert_eq!(loadings.nrows(), idiosyncratic.len());
+        Self::Factor {
+            loadings,
+            idiosyncratic,
+        }
+    }
+
+    /// The dimension of the correlated random variables.
+    pub fn dim(&self) -> usize {
+        match self {
+            Self::Cholesky(cholesky_factor) => cholesky_factor.nrows(),
+            Self::Factor { loadings, .. } => loadings.nrows(),
+        }
+    }
+
+    /// The number of independent standard normals required per sample.
+    pub fn nr_factors(&self) -> usize {
+        match self {
+            Self::Cholesky(cholesky_factor) => cholesky_factor.ncols(),
+            Self::Factor { loadings, .. } => loadings.ncols() + loadings.nrows(),
+        }
+    }
+
+    /// The covariance matrix $\Sigma$ of the correlated normals, e.g. $C C^T$ for the cholesky factor $C$.
+    pub fn covariance(&self) -> Array2<f64> {
+        match self {
+            Self::Cholesky(cholesky_factor) => matmul(cholesky_factor, &cholesky_factor.t()),
+            Self::Factor {
+                loadings,
+                idiosyncratic,
+            } => {
+                matmul(loadings, &loadings.t()) + Array2::from_diag(&idiosyncratic.mapv(|d| d * d))
+            }
+        }
+    }
+
+    /// Transforms independent standard normals into correlated normals.
+    pub fn correlate(&self, standard_normals: &Array1<f64>) -> Array1<f64> {
+        match self {
+            Self::Cholesky(cholesky_factor) => cholesky_factor.dot(standard_normals),
+            Self::Factor {
+                loadings,
+                idiosyncratic,
+            } => {
+                let k = loadings.ncols();
+                loadings.dot(&standard_normals.slice(s![..k]))
+                    + idiosyncratic * &standard_normals.slice(s![k..])
+            }
+        }
+    }
+
+    /// Transforms independent standard normals into correlated normals, where each column is a sample.
+    pub fn correlate_matrix(&self, standard_normals_matrix: &Array2<f64>) -> Array2<f64> {
+        match self {
+            Self::Cholesky(cholesky_factor) => matmul(cholesky_factor, standard_normals_matrix),
+            Self::Factor {
+                loadings,
+                idiosyncratic,
+            } => {
+                let k = loadings.ncols();
+                let mut correlated = matmul(loadings, &standard_normals_matrix.slice(s![..k, ..]));
+                let idiosyncratic_normals = standard_normals_matrix.slice(s![k.., ..]);
+                for (mut row, (z, d)) in correlated.rows_mut().into_iter().zip(
+                    idiosyncratic_normals
+                        .rows()
+                        .into_iter()
+                        .zip(idiosyncratic.iter()),
+                ) {
+                    row.scaled_add(*d, &z);
+                }
+                correlated
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct MultivariateNormalDistribution {
     /// expected values (as by coordinate)
     mu: Array1<f64>,
-    /// correlation structure via the cholesky_factor $C$ which is upper triangular and satisfies
-    /// $C^T*C = \Sigma$ for the covariance matrix $\Sigma$
-    cholesky_factor: Array2<f64>,
+    /// correlation structure, e.g. via the cholesky_factor or a factor model
+    correlation: CorrelationStructure,
 }
 
 /// https://en.wikipedia.org/wiki/Multivariate_normal_distribution
@@ -59,7 +156,21 @@ impl MultivariateNormalDistribution {
 
         Self {
             mu,
-            cholesky_factor,
+            correlation: CorrelationStructure::Cholesky(cholesky_factor),
+        }
+    }
+
+    /// Multivariate normal distribution with covariance $B B^T + diag(d^2)$ given by a factor model,
+    /// suitable for high dimensions.
+    pub fn with_factor_model(
+        mu: Array1<f64>,
+        loadings: Array2<f64>,
+        idiosyncratic: Array1<f64>,
+    ) -> Self {
+        assert_eq!(loadings.nrows(), mu.len());
+        Self {
+            mu,
+            correlation: CorrelationStructure::factor_model(loadings, idiosyncratic),
         }
     }
 
@@ -67,12 +178,47 @@ impl MultivariateNormalDistribution {
         self.mu.shape()[0]
     }
 
+    pub fn mean(&self) -> &Array1<f64> {
+        &self.mu
+    }
+
+    /// The covariance matrix implied by the correlation structure, e.g. $C C^T$ for the cholesky factor $C$.
+    pub fn covariance(&self) -> Array2<f64> {
+        self.correlation.covariance()
+    }
+
+    /// Compares the empirical mean and (unbiased) covariance of the samples, one sample per column
+    /// as generated by `sample_path`, to the mean and covariance of the distribution.
+    /// This verifies that e.g. a cholesky factor produces the intended correlations.
+    pub fn match_moments(&self, samples: &Array2<f64>, tolerance: f64) -> MomentMatch {
+        assert_eq!(samples.nrows(), self.dim());
+        assert!(samples.ncols() > 1);
+        let nr_samples = samples.ncols() as f64;
+
+        let empirical_mean = samples.sum_axis(Axis(1)) / nr_samples;
+        let centered = samples - &empirical_mean.view().insert_axis(Axis(1));
+        let empirical_covariance = matmul(&centered, &centered.t()) / (nr_samples - 1.0);
+
+        let max_abs = |a: f64, b: f64| a.max(b.abs());
+        let mean_error = (&empirical_mean - &self.mu).fold(0.0, |acc, d| max_abs(acc, *d));
+        let covariance_error =
+            (&empirical_covariance - &self.covariance()).fold(0.0, |acc, d| max_abs(acc, *d));
+        MomentMatch {
+            empirical_mean,
+            empirical_covariance,
+            mean_error,
+            covariance_error,
+            tolerance,
+        }
+    }
+
     pub(crate) fn transform_sample(&self, standard_normals: &Array1<f64>) -> Array1<f64> {
-        &self.mu + self.cholesky_factor.dot(standard_normals)
+        &self.mu + self.correlation.correlate(standard_normals)
     }
 
     pub(crate) fn transform_path(&self, standard_normals_matrix: &Array2<f64>) -> Array2<f64> {
-        let mut corr_standard_normals_path = self.cholesky_factor.dot(standard_normals_matrix);
+        let mut corr_standard_normals_path =
+            self.correlation.correlate_matrix(standard_normals_matrix);
 
         for mut col in corr_standard_normals_path.columns_mut() {
             let rdn = &self.mu + &col;
@@ -82,10 +228,31 @@ impl MultivariateNormalDistribution {
     }
 }
 
+/// The empirical first two moments of multivariate samples and their maximal absolute deviations
+/// from the theoretical mean and covariance.
+#[derive(Clone, Debug)]
+pub struct MomentMatch {
+    pub empirical_mean: Array1<f64>,
+    pub empirical_covariance: Array2<f64>,
+    pub mean_error: f64,
+    pub covariance_error: f64,
+    pub tolerance: f64,
+}
+
+impl MomentMatch {
+    /// Whether mean and covariance entries all deviate by at most the tolerance.
+    pub fn passes(&self) -> bool {
+        self.mean_error <= self.tolerance && self.covariance_error <= self.tolerance
+    }
+}
+
 impl Distribution<Array1<f64>> for MultivariateNormalDistribution {
     #[inline]
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Array1<f64> {
-        let standard_normals: Vec<f64> = rng.sample_iter(StandardNormal).take(self.dim()).collect();
+        let standard_normals: Vec<f64> = rng
+            .sample_iter(StandardNormal)
+            .take(self.correlation.nr_factors())
+            .collect();
         self.transform_sample(&Array1::from(standard_normals))
     }
 }
@@ -98,9 +265,10 @@ impl PathGenerator<Array2<f64>> for MultivariateNormalDistribution {
         rn_generator: &mut SeedRng,
         nr_samples: usize,
     ) -> Array2<f64> {
-        let dim = self.dim();
+        let nr_factors = self.correlation.nr_factors();
         let distr = ndarray_rand::rand_distr::StandardNormal;
-        let sample_matrix = ndarray::Array::random_using((dim, nr_samples), distr, rn_generator);
+        let sample_matrix =
+            ndarray::Array::random_using((nr_factors, nr_samples), distr, rn_generator);
         self.transform_path(&sample_matrix)
     }
 }
@@ -110,7 +278,7 @@ impl PathGenerator<Vec<Array1<f64>>> for MultivariateNormalDistribution {
     /// Optimized version of
     /// ''' rn_generator.sample_iter(self).take(nr_samples).collect()'''
     #[inline]
-    fn sample_path<SeedRng: rand::SeedableRng + rand::RngCore>(
+    fn sample_path<SeedRng>(
         &self,
         rn_generator: &mut SeedRng,
         nr_samples: usize,
@@ -118,12 +286,13 @@ impl PathGenerator<Vec<Array1<f64>>> for MultivariateNormalDistribution {
     where
         SeedRng: rand::SeedableRng + rand::RngCore,
     {
-        let dim = self.dim();
-        let standard_normals: Vec<f64> = StandardNormal.sample_path(rn_generator, nr_samples * dim);
+        let nr_factors = self.correlation.nr_factors();
+        let standard_normals: Vec<f64> =
+            StandardNormal.sample_path(rn_generator, nr_samples * nr_factors);
 
         let mut path: Vec<Array1<f64>> = Vec::with_capacity(nr_samples);
-        for (idx, _) in standard_normals.iter().enumerate().step_by(dim) {
-            let slice = &standard_normals[idx..idx + dim];
+        for (idx, _) in standard_normals.iter().enumerate().step_by(nr_factors) {
+            let slice = &standard_normals[idx..idx + nr_factors];
             path.push(self.transform_sample(&arr1(slice)))
         }
 
@@ -134,6 +303,7 @@ impl PathGenerator<Vec<Array1<f64>>> for MultivariateNormalDistribution {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use assert_approx_eq::assert_approx_eq;
     use ndarray::{arr1, arr2};
     use rand::SeedableRng;
 
@@ -191,4 +361,73 @@ mod tests {
             arr1(&[0.09734041097783784, 0.20242533842636964, 0.3057350243384335])
         );
     }
+
+    #[test]
+    fn moment_matching() {
+        let mut rn_generator = rand_hc::Hc128Rng::seed_from_u64(7);
+        let covariance = arr2(&[
+            [0.04, 0.018, -0.01],
+            [0.018, 0.09, 0.0],
+            [-0.01, 0.0, 0.0625],
+        ]);
+        let mu = arr1(&[0.1, 0.2, 0.3]);
+        let cholesky_factor = cholesky(&covariance).unwrap();
+        let mv_normal = MultivariateNormalDistribution::new(mu.clone(), cholesky_factor.clone());
+        assert!((&mv_normal.covariance() - &covariance)
+            .iter()
+            .all(|d| d.abs() < 1e-14));
+
+        let samples: Array2<_> = mv_normal.sample_path(&mut rn_generator, 50_000);
+        let moment_match = mv_normal.match_moments(&samples, 5e-3);
+        assert!(moment_match.passes());
+        assert_approx_eq!(moment_match.empirical_covariance[[0, 1]], 0.018, 5e-3);
+
+        // the transposed (upper triangular) factor implies a different covariance
+        let transposed = MultivariateNormalDistribution::new(mu, cholesky_factor.t().to_owned());
+        assert!(!transposed.match_moments(&samples, 5e-3).passes());
+    }
+
+    #[test]
+    fn factor_model_transform() {
+        let loadings = arr2(&[[1.0, 0.5], [0.0, 0.6], [0.2, 0.3]]);
+        let idiosyncratic = arr1(&[0.1, 0.2, 0.3]);
+        let factor_model = CorrelationStructure::factor_model(loadings, idiosyncratic);
+        assert_eq!(factor_model.dim(), 3);
+        assert_eq!(factor_model.nr_factors(), 5);
+
+        let standard_normals = arr1(&[1.0, -1.0, 0.5, 1.0, -2.0]);
+        let correlated = factor_model.correlate(&standard_normals);
+        let expected = arr1(&[0.5 + 0.05, -0.6 + 0.2, -0.1 - 0.6]);
+        for (c, e) in correlated.iter().zip(expected.iter()) {
+            assert_approx_eq!(c, e, 1e-12);
+        }
+
+        let matrix = arr2(&[[1.0, 0.0], [-1.0, 0.0], [0.5, 0.0], [1.0, 0.0], [-2.0, 1.0]]);
+        let correlated_matrix = factor_model.correlate_matrix(&matrix);
+        assert_eq!(correlated_matrix.column(0), correlated);
+        assert_eq!(correlated_matrix.column(1), arr1(&[0.0, 0.0, 0.3]));
+    }
+
+    #[test]
+    fn factor_model_samples_cov() {
+        let mut rn_generator = rand_hc::Hc128Rng::seed_from_u64(13241114);
+        let nr_samples = 100_000;
+
+        let loadings = arr2(&[[0.3], [0.2], [-0.1], [0.4]]);
+        let idiosyncratic = arr1(&[0.1, 0.2, 0.1, 0.3]);
+        let mv_normal = MultivariateNormalDistribution::with_factor_model(
+            Array1::zeros(4),
+            loadings,
+            idiosyncratic,
+        );
+        let samples: Array2<_> = mv_normal.sample_path(&mut rn_generator, nr_samples);
+        assert_eq!(samples.shape(), &[4, nr_samples]);
+
+        // covariance B B^T + diag(d^2)
+        let cov = samples.dot(&samples.t()) / nr_samples as f64;
+        assert_approx_eq!(cov[[0, 0]], 0.09 + 0.01, 5e-3);
+        assert_approx_eq!(cov[[0, 1]], 0.06, 5e-3);
+        assert_approx_eq!(cov[[1, 2]], -0.02, 5e-3);
+        assert_approx_eq!(cov[[3, 3]], 0.16 + 0.09, 5e-3);
+    }
 }
@@ -0,0 +1,299 @@
+use std::fmt::Write;
+
+use ndarray::{Array1, Array2};
+use rand_distr::StandardNormal;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::results::PricingError;
+use crate::numerics::linalg::cholesky;
+use crate::simulation::monte_carlo::PathGenerator;
+use crate::simulation::sde::stochastic_dividend::MeanRevertingSpread;
+
+/// The spacing of the scenario dates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScenarioFrequency {
+    Annual,
+    Quarterly,
+}
+
+impl ScenarioFrequency {
+    pub fn periods_per_year(&self) -> usize {
+        match self {
+            ScenarioFrequency::Annual => 1,
+            ScenarioFrequency::Quarterly => 4,
+        }
+    }
+}
+
+/// The economy of a scenario at a scenario date.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EconomicState {
+    /// time in years
+    pub time: f64,
+    pub equity_index: f64,
+    /// the continuously compounded short rate
+    pub short_rate: f64,
+    /// the continuously compounded inflation rate
+    pub inflation_rate: f64,
+    /// the price index, e.g. the CPI
+    pub price_index: f64,
+}
+
+/// The states per scenario (outer) and scenario date (inner), starting with the initial state.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EconomicScenarioSet {
+    pub scenarios: Vec<Vec<EconomicState>>,
+}
+
+impl EconomicScenarioSet {
+    /// The states of all scenarios at the scenario date.
+    pub fn at_date(&self, date_idx: usize) -> Vec<EconomicState> {
+        self.scenarios
+            .iter()
+            .map(|scenario| scenario[date_idx])
+            .collect()
+    }
+
+    /// The scenarios as CSV with a header and one line per scenario and date.
+    pub fn to_csv(&self) -> String {
+        let mut csv =
+            String::from("scenario,time,equity_index,short_rate,inflation_rate,price_index\n");
+        for (idx, scenario) in self.scenarios.iter().enumerate() {
+            for state in scenario {
+                writeln!(
+                    csv,
+                    "{},{},{},{},{},{}",
+                    idx,
+                    state.time,
+                    state.equity_index,
+                    state.short_rate,
+                    state.inflation_rate,
+                    state.price_index
+                )
+                .unwrap();
+            }
+        }
+        csv
+    }
+}
+
+/// Real-world economic scenarios over long horizons for asset-liability analyses, coupling the equity index,
+/// the short rate and the inflation rate by the correlation of their shocks (in this order):
+/// '''math
+/// d \ln S = (r + \lambda - \sigma^2 / 2) dt + \sigma dW^S, \quad
+/// dr = \kappa_r (\theta_r - r) dt + \sigma_r dW^r, \quad
+/// d\pi = \kappa_\pi (\theta_\pi - \pi) dt + \sigma_\pi dW^\pi, \quad
+/// d \ln I = \pi dt
+/// '''
+/// with the equity risk premium $\lambda$ and the price index $I$. The rates are stepped exactly, the equity
+/// and the price index with the rates at the start of each step.
+#[derive(Clone, Debug)]
+pub struct EconomicScenarioGenerator {
+    equity_index: f64,
+    equity_risk_premium: f64,
+    equity_vola: f64,
+    short_rate: MeanRevertingSpread,
+    inflation: MeanRevertingSpread,
+    cholesky_factor: Array2<f64>,
+    base_index: f64,
+    frequency: ScenarioFrequency,
+    steps_per_period: usize,
+}
+
+impl EconomicScenarioGenerator {
+    /// The generator with the 3x3 correlation matrix of the equity, short rate and inflation shocks,
+    /// or an error if it is not positive definite.
+    pub fn new(
+        equity_index: f64,
+        equity_risk_premium: f64,
+        equity_vola: f64,
+        short_rate: MeanRevertingSpread,
+        inflation: MeanRevertingSpread,
+        correlation: &Array2<f64>,
+    ) -> Result<Self, PricingError> {
+        PricingError::check(equity_index > 0.0, "equity index", equity_index)?;
+        PricingError::check(equity_vola >= 0.0, "equity vola", equity_vola)?;
+        PricingError::check(
+            correlation.shape() == [3, 3],
+            "correlation dimension",
+            correlation.nrows() as f64,
+        )?;
+        let cholesky_factor = cholesky(correlation).ok_or_else(|| {
+            PricingError::InvalidParameter("correlation is not positive definite".to_string())
+        })?;
+        Ok(Self {
+            equity_index,
+            equity_risk_premium,
+            equity_vola,
+            short_rate,
+            inflation,
+            cholesky_factor,
+            base_index: 100.0,
+            frequency: ScenarioFrequency::Annual,
+            steps_per_period: 1,
+        })
+    }
+
+    /// The initial price index, 100 by default.
+    pub fn with_base_index(self, base_index: f64) -> Self {
+        Self { base_index, ..self }
+    }
+
+    pub fn with_frequency(self, frequency: ScenarioFrequency) -> Self {
+        Self { frequency, ..self }
+    }
+
+    /// The number of simulation steps between two scenario dates, 1 by default.
+    pub fn with_steps_per_period(self, steps_per_period: usize) -> Self {
+        Self {
+            steps_per_period: steps_per_period.max(1),
+            ..self
+        }
+    }
+
+    fn initial_state(&self) -> EconomicState {
+        EconomicState {
+            time: 0.0,
+            equity_index: self.equity_index,
+            short_rate: self.short_rate.initial_value,
+            inflation_rate: self.inflation.initial_value,
+            price_index: self.base_index,
+        }
+    }
+
+    fn step(&self, state: &EconomicState, dt: f64, normals: &[f64]) -> EconomicState {
+        let shocks = self
+            .cholesky_factor
+            .dot(&Array1::from_vec(normals.to_vec()));
+        let log_return = (state.short_rate + self.equity_risk_premium
+            - 0.5 * self.equity_vola * self.equity_vola)
+            * dt
+            + self.equity_vola * dt.sqrt() * shocks[0];
+        EconomicState {
+            time: state.time + dt,
+            equity_index: state.equity_index * log_return.exp(),
+            short_rate: self.short_rate.step(state.short_rate, dt, shocks[1]),
+            inflation_rate: self.inflation.step(state.inflation_rate, dt, shocks[2]),
+            price_index: state.price_index * (state.inflation_rate * dt).exp(),
+        }
+    }
+
+    /// The scenarios over the horizon (in whole years) at the generator's frequency.
+    pub fn generate<SeedRng>(
+        &self,
+        nr_scenarios: usize,
+        horizon_years: usize,
+        seed_nr: u64,
+    ) -> EconomicScenarioSet
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        let mut rn_generator = SeedRng::seed_from_u64(seed_nr);
+        let nr_periods = horizon_years * self.frequency.periods_per_year();
+        let dt = 1.0 / (self.frequency.periods_per_year() * self.steps_per_period) as f64;
+        let scenarios = (0..nr_scenarios)
+            .map(|_| {
+                let mut state = self.initial_state();
+                let mut scenario = Vec::with_capacity(nr_periods + 1);
+                scenario.push(state);
+                for _ in 0..nr_periods {
+                    for _ in 0..self.steps_per_period {
+                        let normals = StandardNormal.sample_path(&mut rn_generator, 3);
+                        state = self.step(&state, dt, &normals);
+                    }
+                    scenario.push(state);
+                }
+                scenario
+            })
+            .collect();
+        EconomicScenarioSet { scenarios }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use ndarray::arr2;
+
+    fn generator() -> EconomicScenarioGenerator {
+        let correlation = arr2(&[[1.0, -0.2, -0.1], [-0.2, 1.0, 0.5], [-0.1, 0.5, 1.0]]);
+        EconomicScenarioGenerator::new(
+            1000.0,
+            0.04,
+            0.18,
+            MeanRevertingSpread::new(0.01, 0.2, 0.03, 0.01),
+            MeanRevertingSpread::new(0.04, 0.5, 0.02, 0.008),
+            &correlation,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn long_horizon_scenarios() {
+        let esg = generator().with_frequency(ScenarioFrequency::Quarterly);
+        let set = esg.generate::<rand_hc::Hc128Rng>(5_000, 30, 42);
+        assert_eq!(set.scenarios.len(), 5_000);
+        assert_eq!(set.scenarios[0].len(), 30 * 4 + 1);
+
+        // the rates revert to their exact means
+        let horizon = set.at_date(120);
+        assert_approx_eq!(horizon[0].time, 30.0, 1e-10);
+        let mean = |f: fn(&EconomicState) -> f64| {
+            horizon.iter().map(f).sum::<f64>() / horizon.len() as f64
+        };
+        let short_rate = MeanRevertingSpread::new(0.01, 0.2, 0.03, 0.01);
+        assert_approx_eq!(mean(|s| s.short_rate), short_rate.mean(30.0), 1e-3);
+        assert_approx_eq!(mean(|s| s.inflation_rate), 0.02, 1e-3);
+
+        // the shocks of the rates and inflation over the first quarter are correlated by 0.5
+        let changes: Vec<(f64, f64)> = set
+            .scenarios
+            .iter()
+            .map(|s| {
+                (
+                    s[1].short_rate - s[0].short_rate,
+                    s[1].inflation_rate - s[0].inflation_rate,
+                )
+            })
+            .collect();
+        let n = changes.len() as f64;
+        let (mx, my) = changes
+            .iter()
+            .fold((0.0, 0.0), |(x, y), (a, b)| (x + a / n, y + b / n));
+        let (cov, vx, vy) = changes.iter().fold((0.0, 0.0, 0.0), |(c, vx, vy), (a, b)| {
+            (
+                c + (a - mx) * (b - my),
+                vx + (a - mx).powi(2),
+                vy + (b - my).powi(2),
+            )
+        });
+        assert_approx_eq!(cov / (vx * vy).sqrt(), 0.5, 0.05);
+    }
+
+    #[test]
+    fn scenario_csv_and_validation() {
+        let set = generator()
+            .with_steps_per_period(12)
+            .generate::<rand_hc::Hc128Rng>(2, 3, 7);
+        let csv = set.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 1 + 2 * 4);
+        assert!(lines[0].starts_with("scenario,time,equity_index"));
+        assert!(lines[1].starts_with("0,0,1000,0.01,0.04,100"));
+
+        let invalid = arr2(&[[1.0, 0.9, 0.9], [0.9, 1.0, -0.9], [0.9, -0.9, 1.0]]);
+        assert!(EconomicScenarioGenerator::new(
+            1000.0,
+            0.04,
+            0.18,
+            MeanRevertingSpread::new(0.01, 0.2, 0.03, 0.01),
+            MeanRevertingSpread::new(0.04, 0.5, 0.02, 0.008),
+            &invalid,
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,170 @@
+use rayon::prelude::*;
+
+use crate::numerics::summation::Reproducibility;
+
+/// The estimate of a single seed of an ensemble.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SeedEstimate {
+    pub seed_nr: u64,
+    pub mean: f64,
+    /// the sample variance of the seed's samples
+    pub sample_variance: f64,
+    pub nr_samples: usize,
+}
+
+impl SeedEstimate {
+    fn from_samples(seed_nr: u64, samples: &[f64], reproducibility: Reproducibility) -> Self {
+        let n = samples.len() as f64;
+        let mean = reproducibility.sum(samples) / n;
+        let squared_deviations: Vec<f64> = samples.iter().map(|x| (x - mean).powi(2)).collect();
+        let sample_variance = reproducibility.sum(&squared_deviations) / (n - 1.0);
+        Self {
+            seed_nr,
+            mean,
+            sample_variance,
+            nr_samples: samples.len(),
+        }
+    }
+
+    /// The variance of the seed's mean estimated from its own samples.
+    pub fn estimator_variance(&self) -> f64 {
+        self.sample_variance / self.nr_samples as f64
+    }
+}
+
+/// The seed estimates of an ensemble with the decomposition of their variance.
+/// For independent seeds the between-seed variance of the means matches the within-seed estimator
+/// variance; a ratio far from one indicates correlated seeds or a misestimated standard error,
+/// whereas the deviation of the ensemble mean from a reference value beyond its standard error is bias.
+#[derive(Clone, Debug)]
+pub struct EnsembleReport {
+    pub seed_estimates: Vec<SeedEstimate>,
+}
+
+impl EnsembleReport {
+    pub fn mean(&self) -> f64 {
+        self.seed_estimates.iter().map(|e| e.mean).sum::<f64>() / self.seed_estimates.len() as f64
+    }
+
+    /// The sample variance of the seeds' means.
+    pub fn between_seed_variance(&self) -> f64 {
+        let mean = self.mean();
+        self.seed_estimates
+            .iter()
+            .map(|e| (e.mean - mean).powi(2))
+            .sum::<f64>()
+            / (self.seed_estimates.len() - 1) as f64
+    }
+
+    /// The average over the seeds of the variance of a seed's mean estimated from its samples.
+    pub fn within_seed_variance(&self) -> f64 {
+        self.seed_estimates
+            .iter()
+            .map(|e| e.estimator_variance())
+            .sum::<f64>()
+            / self.seed_estimates.len() as f64
+    }
+
+    /// The ratio of the between-seed to the within-seed variance, close to one for independent seeds.
+    pub fn variance_ratio(&self) -> f64 {
+        self.between_seed_variance() / self.within_seed_variance()
+    }
+
+    /// The standard error of the ensemble mean from the between-seed variance.
+    pub fn standard_error(&self) -> f64 {
+        (self.between_seed_variance() / self.seed_estimates.len() as f64).sqrt()
+    }
+
+    /// The deviation from the reference value in units of the standard error, e.g. of an analytic price
+    /// with the same discretization; large values indicate discretization bias rather than noise.
+    pub fn bias_z_score(&self, reference: f64) -> f64 {
+        (self.mean() - reference) / self.standard_error()
+    }
+}
+
+/// Runs the same pricing job for several seeds in parallel.
+pub struct EnsembleRunner {
+    seeds: Vec<u64>,
+    reproducibility: Reproducibility,
+}
+
+impl EnsembleRunner {
+    pub fn new(seeds: Vec<u64>) -> Self {
+        assert!(seeds.len() > 1);
+        Self {
+            seeds,
+            reproducibility: Reproducibility::default(),
+        }
+    }
+
+    /// The consecutive seeds starting at `base_seed`, wrapping around beyond `u64::MAX`.
+    pub fn consecutive(base_seed: u64, nr_seeds: usize) -> Self {
+        Self::new(
+            (0..nr_seeds as u64)
+                .map(|k| base_seed.wrapping_add(k))
+                .collect(),
+        )
+    }
+
+    /// The summation of the seeds' samples, by default sequential.
+    pub fn with_reproducibility(self, reproducibility: Reproducibility) -> Self {
+        Self {
+            reproducibility,
+            ..self
+        }
+    }
+
+    /// Runs the job, which returns the (discounted) payoff samples for a seed, once per seed.
+    pub fn run(&self, job: impl Fn(u64) -> Vec<f64> + Sync) -> EnsembleReport {
+        let seed_estimates = self
+            .seeds
+            .par_iter()
+            .map(|seed_nr| {
+                SeedEstimate::from_samples(*seed_nr, &job(*seed_nr), self.reproducibility)
+            })
+            .collect();
+        EnsembleReport { seed_estimates }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::BsmComputation;
+    use crate::common::models::DerivativeParameter;
+    use crate::simulation::greeks::SharedNormals;
+    use crate::simulation::sde::gbm::GeometricBrownianMotion;
+
+    #[test]
+    fn ensemble_of_call_prices() {
+        let (spot, strike, rfr, vola, t) = (100.0, 100.0, 0.03, 0.2, 1.0);
+        let nr_steps = 20;
+        let job = |seed_nr: u64| -> Vec<f64> {
+            let gbm = GeometricBrownianMotion::new(spot, rfr, vola, t / nr_steps as f64);
+            let normals = SharedNormals::new::<rand_hc::Hc128Rng>(2_000, nr_steps, seed_nr);
+            normals
+                .paths()
+                .iter()
+                .map(|z| {
+                    let path = gbm.generate_path(spot, z);
+                    (-rfr * t).exp() * (path.last().unwrap() - strike).max(0.0)
+                })
+                .collect()
+        };
+        let runner = EnsembleRunner::consecutive(100, 16);
+        let reference =
+            BsmComputation::new(&DerivativeParameter::new(spot, strike, t, rfr, vola)).call();
+
+        let report = runner.run(job);
+        assert_eq!(report.seed_estimates.len(), 16);
+        assert!(report.variance_ratio() > 0.3 && report.variance_ratio() < 3.0);
+        assert!(report.bias_z_score(reference).abs() < 3.0);
+        // a bias of the size of a few standard errors is detected
+        assert!(report.bias_z_score(reference + 0.5).abs() > 3.0);
+
+        assert_eq!(
+            EnsembleRunner::consecutive(u64::MAX, 3).seeds,
+            vec![u64::MAX, 0, 1]
+        );
+    }
+}
@@ -0,0 +1,729 @@
+use ndarray::{Array1, Array2, Axis};
+use probability::distribution::{Gaussian, Inverse};
+
+use crate::common::results::PricingError;
+use crate::curves::{SurvivalCurve, YieldCurve};
+use crate::numerics::least_squares::{least_squares, polynomial_basis, polynomial_value};
+use crate::numerics::solvers::{brent, Solution, SolverOptions};
+
+/// The collateral terms of a (two-way) credit support annex: collateral is called for the part of the
+/// netted value beyond the threshold, transfers below the minimum transfer amount are skipped, and
+/// the collateral lags the value by the margin period of risk, i.e. the time from the last successful
+/// margin call until the close-out after a default.
+/// See https://en.wikipedia.org/wiki/Credit_support_annex
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CollateralAgreement {
+    pub threshold: f64,
+    pub minimum_transfer_amount: f64,
+    /// the margin period of risk in years
+    pub margin_period_of_risk: f64,
+}
+
+impl CollateralAgreement {
+    pub fn new(threshold: f64, minimum_transfer_amount: f64, margin_period_of_risk: f64) -> Self {
+        assert!(threshold >= 0.0 && minimum_transfer_amount >= 0.0);
+        assert!(margin_period_of_risk >= 0.0);
+        Self {
+            threshold,
+            minimum_transfer_amount,
+            margin_period_of_risk,
+        }
+    }
+
+    /// The collateral called for the netted value.
+    fn required_collateral(&self, value: f64) -> f64 {
+        (value - self.threshold).max(0.0) - (-value - self.threshold).max(0.0)
+    }
+}
+
+/// Dynamic initial margin, posted by both parties and held segregated, as the quantile of the value
+/// change over the margin period of risk conditional on the netted value: the conditional variance
+/// is the regression of the squared value changes on a polynomial in the netted value per exposure time.
+/// The initial margin lags the value by the margin period of risk like the variation margin.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InitialMarginModel {
+    /// e.g. 0.99
+    pub confidence_level: f64,
+    /// the margin period of risk in years
+    pub margin_period_of_risk: f64,
+    /// the degree of the polynomial of the conditional variance
+    pub degree: usize,
+}
+
+impl InitialMarginModel {
+    pub fn new(confidence_level: f64, margin_period_of_risk: f64, degree: usize) -> Self {
+        assert!(0.5 < confidence_level && confidence_level < 1.0);
+        assert!(margin_period_of_risk > 0.0);
+        Self {
+            confidence_level,
+            margin_period_of_risk,
+            degree,
+        }
+    }
+}
+
+/// The trades (indices into the simulated trade values) whose values are netted at a default,
+/// optionally collateralized by variation margin and initial margin.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NettingSet {
+    pub trades: Vec<usize>,
+    pub collateral: Option<CollateralAgreement>,
+    pub initial_margin: Option<InitialMarginModel>,
+}
+
+impl NettingSet {
+    pub fn new(trades: Vec<usize>) -> Self {
+        Self {
+            trades,
+            collateral: None,
+            initial_margin: None,
+        }
+    }
+
+    /// The netting set of the trades without variation and initial margin.
+    pub fn uncollateralized(&self) -> Self {
+        Self::new(self.trades.clone())
+    }
+
+    pub fn with_initial_margin(self, initial_margin: InitialMarginModel) -> Self {
+        Self {
+            initial_margin: Some(initial_margin),
+            ..self
+        }
+    }
+
+    pub fn with_collateral(self, collateral: CollateralAgreement) -> Self {
+        Self {
+            collateral: Some(collateral),
+            ..self
+        }
+    }
+}
+
+/// The exposure statistics per exposure time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExposureProfile {
+    pub times: Vec<f64>,
+    /// the expected exposure $E[max(V_t - C_t, 0)]$
+    pub expected_exposure: Vec<f64>,
+    /// the expected negative exposure $E[max(C_t - V_t, 0)]$, i.e. the exposure of the counterparty
+    pub expected_negative_exposure: Vec<f64>,
+    /// the quantile of the exposure at the confidence level
+    pub potential_future_exposure: Vec<f64>,
+    /// the expected initial margin held, which is 0 without an initial margin model
+    pub expected_initial_margin: Vec<f64>,
+}
+
+impl ExposureProfile {
+    /// The maximum of the potential future exposures over time.
+    pub fn peak_exposure(&self) -> f64 {
+        self.potential_future_exposure
+            .iter()
+            .fold(0.0, |peak, pfe| peak.max(*pfe))
+    }
+
+    /// The credit valuation adjustment $(1 - R) \sum_i P(t_i) EE(t_i) PD(t_{i-1}, t_i)$ for the default
+    /// probabilities of the counterparty in the periods between the exposure times, starting from 0.
+    /// See https://en.wikipedia.org/wiki/Credit_valuation_adjustment
+    pub fn cva(
+        &self,
+        discount: &impl YieldCurve,
+        counterparty: &impl SurvivalCurve,
+        recovery_rate: f64,
+    ) -> f64 {
+        let mut prev = 0.0;
+        let mut cva = 0.0;
+        for (t, exposure) in self.times.iter().zip(&self.expected_exposure) {
+            cva += discount.discount_factor(*t)
+                * exposure
+                * counterparty.default_probability(prev, *t);
+            prev = *t;
+        }
+        (1.0 - recovery_rate) * cva
+    }
+
+    /// The margin valuation adjustment $\sum_i s P(t_i) E[IM(t_i)] (t_i - t_{i-1})$ of funding the posted
+    /// initial margin at the funding spread s, assuming the posted margin equals the one received.
+    pub fn mva(&self, discount: &impl YieldCurve, funding_spread: f64) -> f64 {
+        let mut prev = 0.0;
+        let mut mva = 0.0;
+        for (t, initial_margin) in self.times.iter().zip(&self.expected_initial_margin) {
+            mva += discount.discount_factor(*t) * initial_margin * (t - prev);
+            prev = *t;
+        }
+        funding_spread * mva
+    }
+}
+
+/// The simulated values of trades on a common set of scenarios and exposure times, e.g. the future
+/// values of nested or regression-based simulations, from which the exposures are aggregated per
+/// netting set.
+#[derive(Clone, Debug)]
+pub struct ExposureSimulation {
+    times: Vec<f64>,
+    /// the values (scenarios x times) per trade, in units of the time of the exposure
+    trade_values: Vec<Array2<f64>>,
+}
+
+impl ExposureSimulation {
+    pub fn new(times: Vec<f64>, trade_values: Vec<Array2<f64>>) -> Result<Self, PricingError> {
+        PricingError::check(
+            times.windows(2).all(|w| w[0] < w[1]) && times.first().is_some_and(|t| *t > 0.0),
+            "first exposure time",
+            times.first().copied().unwrap_or(f64::NAN),
+        )?;
+        let shape = trade_values
+            .first()
+            .map(|values| values.dim())
+            .ok_or(PricingError::NoPaths)?;
+        for values in &trade_values {
+            PricingError::check(
+                values.dim() == shape && values.ncols() == times.len(),
+                "number of exposure times",
+                values.ncols() as f64,
+            )?;
+        }
+        Ok(Self {
+            times,
+            trade_values,
+        })
+    }
+
+    pub fn times(&self) -> &[f64] {
+        &self.times
+    }
+
+    pub fn nr_scenarios(&self) -> usize {
+        self.trade_values[0].nrows()
+    }
+
+    /// The sum of the values of the netting set's trades per scenario and time.
+    pub fn netted_values(&self, netting_set: &NettingSet) -> Result<Array2<f64>, PricingError> {
+        let mut netted = Array2::zeros(self.trade_values[0].dim());
+        for trade in &netting_set.trades {
+            let values = self
+                .trade_values
+                .get(*trade)
+                .ok_or_else(|| PricingError::InvalidParameter(format!("trade index = {trade}")))?;
+            netted += values;
+        }
+        Ok(netted)
+    }
+
+    /// The index of the last exposure time at or before $t - MPoR$ of each exposure time, whose margin
+    /// call determines the collateral at the close-out, or None before the first exposure time.
+    fn margin_call_indices(&self, margin_period_of_risk: f64) -> Vec<Option<usize>> {
+        self.times
+            .iter()
+            .map(|t| {
+                self.times
+                    .partition_point(|s| *s <= t - margin_period_of_risk + 1e-12)
+                    .checked_sub(1)
+            })
+            .collect()
+    }
+
+    /// The collateral balances per scenario and time, which follow the required collateral of the
+    /// margin calls at the exposure times unless the transfer is below the minimum transfer amount.
+    /// No collateral is posted before the first exposure time.
+    pub fn collateral_balances(
+        netted_values: &Array2<f64>,
+        agreement: &CollateralAgreement,
+    ) -> Array2<f64> {
+        let mut balances = Array2::zeros(netted_values.dim());
+        for (values, mut balance) in netted_values
+            .axis_iter(Axis(0))
+            .zip(balances.axis_iter_mut(Axis(0)))
+        {
+            let mut held = 0.0;
+            for (value, balance) in values.iter().zip(balance.iter_mut()) {
+                let required = agreement.required_collateral(*value);
+                if (required - held).abs() >= agreement.minimum_transfer_amount {
+                    held = required;
+                }
+                *balance = held;
+            }
+        }
+        balances
+    }
+
+    /// The initial margin per scenario and time called for the netted values, which is 0 at the exposure
+    /// times without a later exposure time at the end of the margin period of risk.
+    pub fn initial_margins(
+        &self,
+        netted_values: &Array2<f64>,
+        model: &InitialMarginModel,
+    ) -> Result<Array2<f64>, PricingError> {
+        let quantile = Gaussian::new(0.0, 1.0).inverse(model.confidence_level);
+        let mut margins = Array2::zeros(netted_values.dim());
+        for (j, t) in self.times.iter().enumerate() {
+            let k = self
+                .times
+                .partition_point(|s| *s < t + model.margin_period_of_risk - 1e-12);
+            if k == self.times.len() {
+                continue;
+            }
+            let values = netted_values.column(j);
+            let squared_changes: Array1<f64> = netted_values
+                .column(k)
+                .iter()
+                .zip(&values)
+                .map(|(later, value)| (later - value).powi(2))
+                .collect();
+
+            // standardize the regressor for a well conditioned polynomial basis
+            let n = values.len() as f64;
+            let mean = values.sum() / n;
+            let std = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt();
+            let degree = if std > 0.0 { model.degree } else { 0 };
+            let standardized: Vec<f64> = values
+                .iter()
+                .map(|v| (v - mean) / std.max(f64::MIN_POSITIVE))
+                .collect();
+            let coefficients =
+                least_squares(&polynomial_basis(&standardized, degree), &squared_changes)
+                    .ok_or_else(|| {
+                        PricingError::InvalidParameter(format!(
+                            "initial margin regression degree = {degree}"
+                        ))
+                    })?;
+            for (margin, x) in margins.column_mut(j).iter_mut().zip(&standardized) {
+                *margin = quantile * polynomial_value(&coefficients, *x).max(0.0).sqrt();
+            }
+        }
+        Ok(margins)
+    }
+
+    /// The collateral held at the close-out per scenario and time, i.e. the balances of the last margin
+    /// calls before the margin period of risk.
+    fn lagged(&self, balances: &Array2<f64>, margin_period_of_risk: f64) -> Array2<f64> {
+        let mut lagged = Array2::zeros(balances.dim());
+        let margin_calls = self.margin_call_indices(margin_period_of_risk);
+        for (j, margin_call) in margin_calls.into_iter().enumerate() {
+            if let Some(k) = margin_call {
+                lagged.column_mut(j).assign(&balances.column(k));
+            }
+        }
+        lagged
+    }
+
+    /// The netted values less the variation margin held at the close-out per scenario and time,
+    /// whose positive part is the exposure to the counterparty without initial margin.
+    pub fn collateralized_values(
+        &self,
+        netting_set: &NettingSet,
+    ) -> Result<Array2<f64>, PricingError> {
+        let values = self.netted_values(netting_set)?;
+        Ok(match &netting_set.collateral {
+            Some(agreement) => {
+                let balances = Self::collateral_balances(&values, agreement);
+                &values - &self.lagged(&balances, agreement.margin_period_of_risk)
+            }
+            None => values,
+        })
+    }
+
+    /// The values less the variation margin and the initial margin held at the close-out.
+    fn margined_values(
+        &self,
+        netting_set: &NettingSet,
+    ) -> Result<(Array2<f64>, Array2<f64>), PricingError> {
+        let values = self.collateralized_values(netting_set)?;
+        let initial_margins = match &netting_set.initial_margin {
+            Some(model) => {
+                let margins = self.initial_margins(&self.netted_values(netting_set)?, model)?;
+                self.lagged(&margins, model.margin_period_of_risk)
+            }
+            None => Array2::zeros(values.dim()),
+        };
+        Ok((values, initial_margins))
+    }
+
+    /// The exposures per scenario and time after variation and initial margin.
+    pub fn exposures(&self, netting_set: &NettingSet) -> Result<Array2<f64>, PricingError> {
+        let (values, initial_margins) = self.margined_values(netting_set)?;
+        Ok((values - initial_margins).mapv(|exposure| exposure.max(0.0)))
+    }
+
+    /// The exposure profile of the netting set with the potential future exposure at the confidence
+    /// level, e.g. 0.95. The initial margin received reduces the exposure and the one posted the
+    /// negative exposure.
+    pub fn profile(
+        &self,
+        netting_set: &NettingSet,
+        confidence_level: f64,
+    ) -> Result<ExposureProfile, PricingError> {
+        PricingError::check(
+            0.0 < confidence_level && confidence_level < 1.0,
+            "confidence level",
+            confidence_level,
+        )?;
+        let (values, initial_margins) = self.margined_values(netting_set)?;
+        let n = self.nr_scenarios() as f64;
+        let mut profile = ExposureProfile {
+            times: self.times.clone(),
+            expected_exposure: Vec::with_capacity(self.times.len()),
+            expected_negative_exposure: Vec::with_capacity(self.times.len()),
+            potential_future_exposure: Vec::with_capacity(self.times.len()),
+            expected_initial_margin: Vec::with_capacity(self.times.len()),
+        };
+        for (column, margins) in values
+            .axis_iter(Axis(1))
+            .zip(initial_margins.axis_iter(Axis(1)))
+        {
+            let mut exposures: Vec<f64> = column
+                .iter()
+                .zip(&margins)
+                .map(|(v, im)| (v - im).max(0.0))
+                .collect();
+            profile
+                .expected_exposure
+                .push(exposures.iter().sum::<f64>() / n);
+            profile.expected_negative_exposure.push(
+                column
+                    .iter()
+                    .zip(&margins)
+                    .map(|(v, im)| (-v - im).max(0.0))
+                    .sum::<f64>()
+                    / n,
+            );
+            profile.expected_initial_margin.push(margins.sum() / n);
+            exposures.sort_by(f64::total_cmp);
+            let idx = ((confidence_level * n).ceil() as usize).clamp(1, exposures.len()) - 1;
+            profile.potential_future_exposure.push(exposures[idx]);
+        }
+        Ok(profile)
+    }
+
+    /// The profiles of the netting set without and with its variation and initial margin.
+    pub fn profiles(
+        &self,
+        netting_set: &NettingSet,
+        confidence_level: f64,
+    ) -> Result<(ExposureProfile, ExposureProfile), PricingError> {
+        Ok((
+            self.profile(&netting_set.uncollateralized(), confidence_level)?,
+            self.profile(netting_set, confidence_level)?,
+        ))
+    }
+
+    /// The CVA of each netting set with the same counterparty, whose sum is the counterparty's CVA.
+    pub fn cva(
+        &self,
+        netting_sets: &[NettingSet],
+        discount: &impl YieldCurve,
+        counterparty: &impl SurvivalCurve,
+        recovery_rate: f64,
+    ) -> Result<Vec<f64>, PricingError> {
+        netting_sets
+            .iter()
+            .map(|netting_set| {
+                self.profile(netting_set, 0.5)
+                    .map(|profile| profile.cva(discount, counterparty, recovery_rate))
+            })
+            .collect()
+    }
+
+    /// The CVA $(1 - R) E[\sum_i P(t_i) E(t_i) (Q(t_{i-1}) - Q(t_i))]$ for the default intensities per
+    /// scenario on the periods $(t_{i-1}, t_i]$ between the exposure times, where the pathwise survival
+    /// probabilities are $Q(t_i) = e^{-\sum_{k \le i} \lambda_k (t_k - t_{k-1})}$. Intensities depending on
+    /// the simulated paths capture wrong-way (or right-way) risk.
+    pub fn cva_with_intensities(
+        &self,
+        netting_set: &NettingSet,
+        discount: &impl YieldCurve,
+        intensities: &Array2<f64>,
+        recovery_rate: f64,
+    ) -> Result<f64, PricingError> {
+        let exposures = self.exposures(netting_set)?;
+        PricingError::check(
+            intensities.dim() == exposures.dim(),
+            "number of intensity scenarios",
+            intensities.nrows() as f64,
+        )?;
+        let discount_factors = discount.discount_factors(&self.times);
+        let mut cva = 0.0;
+        for (exposures, intensities) in exposures
+            .axis_iter(Axis(0))
+            .zip(intensities.axis_iter(Axis(0)))
+        {
+            let (mut prev, mut survival) = (0.0, 1.0);
+            for (j, t) in self.times.iter().enumerate() {
+                let next_survival = survival * (-intensities[j] * (t - prev)).exp();
+                cva += discount_factors[j] * exposures[j] * (survival - next_survival);
+                (prev, survival) = (*t, next_survival);
+            }
+        }
+        Ok((1.0 - recovery_rate) * cva / self.nr_scenarios() as f64)
+    }
+
+    /// The intensities $\lambda_i = e^{a_i + b x_i}$ of the Hull-White wrong-way risk model per scenario
+    /// for the driver x per scenario and time (e.g. the netted value or the underlying), where the
+    /// $a_i$ are calibrated such that the expected pathwise survival probabilities match the curve.
+    /// A positive sensitivity b raises the default risk with the driver (wrong-way risk),
+    /// b = 0 yields the deterministic intensities of the curve.
+    /// See Hull, White: CVA and wrong-way risk, Financial Analysts Journal 68 (2012)
+    pub fn hull_white_intensities(
+        &self,
+        driver: &Array2<f64>,
+        counterparty: &impl SurvivalCurve,
+        sensitivity: f64,
+    ) -> Result<Array2<f64>, PricingError> {
+        PricingError::check(
+            driver.ncols() == self.times.len(),
+            "number of driver times",
+            driver.ncols() as f64,
+        )?;
+        let mut intensities = Array2::zeros(driver.dim());
+        let mut log_survivals = Array1::<f64>::zeros(driver.nrows());
+        let mut prev = 0.0;
+        for (j, t) in self.times.iter().enumerate() {
+            let dt = t - prev;
+            let target = counterparty.survival_probability(*t);
+            if counterparty.default_probability(prev, *t) > 0.0 {
+                let column = driver.column(j);
+                let expected_survival = |a: f64| {
+                    log_survivals
+                        .iter()
+                        .zip(&column)
+                        .map(|(log_survival, x)| {
+                            (log_survival - (a + sensitivity * x).exp() * dt).exp()
+                        })
+                        .sum::<f64>()
+                        / column.len() as f64
+                        - target
+                };
+                let a = brent(expected_survival, -50.0, 20.0, &SolverOptions::default())
+                    .and_then(Solution::converged)
+                    .ok_or_else(|| {
+                        PricingError::InvalidParameter(format!(
+                            "wrong-way risk sensitivity = {sensitivity}"
+                        ))
+                    })?;
+                for (intensity, x) in intensities.column_mut(j).iter_mut().zip(&column) {
+                    *intensity = (a + sensitivity * x).exp();
+                }
+            }
+            log_survivals
+                .iter_mut()
+                .zip(intensities.column(j))
+                .for_each(|(log_survival, intensity)| *log_survival -= intensity * dt);
+            prev = *t;
+        }
+        Ok(intensities)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curves::hazard_rate::PiecewiseHazardCurve;
+    use crate::curves::yield_curve::FlatCurve;
+    use assert_approx_eq::assert_approx_eq;
+    use ndarray::arr2;
+    use rand::{Rng, SeedableRng};
+    use std::f64::consts::PI;
+
+    /// Two scenarios of a trade gaining or losing 10 per period and its offsetting trade.
+    fn simulation() -> ExposureSimulation {
+        let trade = arr2(&[[10.0, 20.0, 30.0, 40.0], [-10.0, -20.0, -30.0, -40.0]]);
+        let hedge = arr2(&[[-5.0, -15.0, -25.0, -35.0], [5.0, 15.0, 25.0, 35.0]]);
+        ExposureSimulation::new(vec![0.25, 0.5, 0.75, 1.0], vec![trade, hedge]).unwrap()
+    }
+
+    #[test]
+    fn netting_set_exposures() {
+        let simulation = simulation();
+        let trade = simulation.profile(&NettingSet::new(vec![0]), 0.95).unwrap();
+        assert_eq!(trade.expected_exposure, vec![5.0, 10.0, 15.0, 20.0]);
+        assert_eq!(
+            trade.expected_negative_exposure,
+            vec![5.0, 10.0, 15.0, 20.0]
+        );
+        assert_eq!(
+            trade.potential_future_exposure,
+            vec![10.0, 20.0, 30.0, 40.0]
+        );
+        assert_eq!(trade.peak_exposure(), 40.0);
+
+        // netting the hedge leaves the exposure of the net position of 5 only
+        let netted = simulation
+            .profile(&NettingSet::new(vec![0, 1]), 0.95)
+            .unwrap();
+        assert_eq!(netted.expected_exposure, vec![2.5; 4]);
+        let hedge = simulation.profile(&NettingSet::new(vec![1]), 0.95).unwrap();
+        assert!(
+            netted.expected_exposure[3] < trade.expected_exposure[3] + hedge.expected_exposure[3]
+        );
+
+        assert!(simulation.profile(&NettingSet::new(vec![2]), 0.95).is_err());
+        assert!(ExposureSimulation::new(vec![0.5], vec![arr2(&[[1.0, 2.0]])]).is_err());
+    }
+
+    #[test]
+    fn collateralized_exposures() {
+        let simulation = simulation();
+        // full daily margining without lag removes the exposure
+        let margined =
+            NettingSet::new(vec![0]).with_collateral(CollateralAgreement::new(0.0, 0.0, 0.0));
+        let profile = simulation.profile(&margined, 0.95).unwrap();
+        assert_eq!(profile.expected_exposure, vec![0.0; 4]);
+
+        // the threshold caps the exposure without lag
+        let threshold =
+            NettingSet::new(vec![0]).with_collateral(CollateralAgreement::new(15.0, 0.0, 0.0));
+        let profile = simulation.profile(&threshold, 0.95).unwrap();
+        assert_eq!(
+            profile.potential_future_exposure,
+            vec![10.0, 15.0, 15.0, 15.0]
+        );
+
+        // the margin period of risk exposes the value change since the last margin call
+        let lagged =
+            NettingSet::new(vec![0]).with_collateral(CollateralAgreement::new(0.0, 0.0, 0.25));
+        let profile = simulation.profile(&lagged, 0.95).unwrap();
+        assert_eq!(profile.potential_future_exposure, vec![10.0; 4]);
+
+        // transfers below the minimum transfer amount are skipped
+        let balances = ExposureSimulation::collateral_balances(
+            &arr2(&[[4.0, 8.0, 12.0, 30.0]]),
+            &CollateralAgreement::new(0.0, 5.0, 0.0),
+        );
+        assert_eq!(balances, arr2(&[[0.0, 8.0, 8.0, 30.0]]));
+    }
+
+    #[test]
+    fn initial_margin_paths() {
+        // a random walk with normal increments of standard deviation 10 per period
+        let nr_scenarios = 20_000;
+        let mut rng = rand_hc::Hc128Rng::seed_from_u64(7);
+        let normal = rand_distr::StandardNormal;
+        let mut values = Array2::zeros((nr_scenarios, 8));
+        for mut row in values.rows_mut() {
+            let mut value = 0.0;
+            for v in row.iter_mut() {
+                let z: f64 = rng.sample(normal);
+                value += 10.0 * z;
+                *v = value;
+            }
+        }
+        let times: Vec<f64> = (1..=8).map(|k| k as f64 * 0.125).collect();
+        let simulation = ExposureSimulation::new(times, vec![values]).unwrap();
+        let model = InitialMarginModel::new(0.99, 0.125, 2);
+        let margins = simulation
+            .initial_margins(
+                &simulation.netted_values(&NettingSet::new(vec![0])).unwrap(),
+                &model,
+            )
+            .unwrap();
+        // the value changes are independent of the value
+        let quantile = Gaussian::new(0.0, 1.0).inverse(0.99);
+        assert_approx_eq!(margins[[0, 3]], 10.0 * quantile, 1.0);
+        assert_approx_eq!(margins[[17, 3]], 10.0 * quantile, 1.0);
+        assert_eq!(margins[[0, 7]], 0.0);
+
+        let netting_set = NettingSet::new(vec![0])
+            .with_collateral(CollateralAgreement::new(0.0, 0.0, 0.125))
+            .with_initial_margin(model);
+        let (uncollateralized, collateralized) = simulation.profiles(&netting_set, 0.95).unwrap();
+        assert!(uncollateralized
+            .expected_initial_margin
+            .iter()
+            .all(|im| *im == 0.0));
+        // variation margin leaves the exposure of one period, which initial margin covers mostly
+        let vm_only = simulation
+            .profile(
+                &netting_set
+                    .uncollateralized()
+                    .with_collateral(CollateralAgreement::new(0.0, 0.0, 0.125)),
+                0.95,
+            )
+            .unwrap();
+        assert_approx_eq!(vm_only.expected_exposure[4], 10.0 / (2.0 * PI).sqrt(), 0.2);
+        assert!(collateralized.expected_exposure[4] < 0.1);
+        assert!(collateralized.expected_exposure[4] < vm_only.expected_exposure[4]);
+        assert!(uncollateralized.expected_exposure[4] > vm_only.expected_exposure[4]);
+
+        let mva = collateralized.mva(&FlatCurve::new(0.0), 0.01);
+        let expected: f64 =
+            collateralized.expected_initial_margin.iter().sum::<f64>() * 0.125 * 0.01;
+        assert_approx_eq!(mva, expected, 1e-12);
+        assert!(mva > 0.0);
+    }
+
+    #[test]
+    fn cva_of_netting_sets() {
+        let simulation = simulation();
+        let discount = FlatCurve::new(0.02);
+        let counterparty = PiecewiseHazardCurve::flat(0.03);
+        let netting_sets = [NettingSet::new(vec![0]), NettingSet::new(vec![0, 1])];
+        let cva = simulation
+            .cva(&netting_sets, &discount, &counterparty, 0.4)
+            .unwrap();
+
+        let expected: f64 = [(0.25_f64, 5.0), (0.5, 10.0), (0.75, 15.0), (1.0, 20.0)]
+            .iter()
+            .map(|(t, exposure)| {
+                0.6 * (-0.02 * t).exp() * exposure * counterparty.default_probability(t - 0.25, *t)
+            })
+            .sum();
+        assert_approx_eq!(cva[0], expected, 1e-12);
+        assert!(cva[1] < cva[0]);
+    }
+
+    #[test]
+    fn wrong_way_risk() {
+        // a trade whose value grows by 10 per period on the first half of the scenarios and falls on the other
+        let trade = Array2::from_shape_fn((100, 4), |(i, j)| {
+            let sign = if i < 50 { 1.0 } else { -1.0 };
+            sign * 10.0 * (j + 1) as f64
+        });
+        let simulation =
+            ExposureSimulation::new(vec![0.25, 0.5, 0.75, 1.0], vec![trade.clone()]).unwrap();
+        let netting_set = NettingSet::new(vec![0]);
+        let discount = FlatCurve::new(0.02);
+        let counterparty = PiecewiseHazardCurve::flat(0.05);
+
+        // independent intensities reproduce the CVA of the expected exposures
+        let independent = simulation
+            .hull_white_intensities(&trade, &counterparty, 0.0)
+            .unwrap();
+        assert_approx_eq!(independent[[3, 2]], 0.05, 1e-10);
+        let cva = simulation
+            .cva_with_intensities(&netting_set, &discount, &independent, 0.4)
+            .unwrap();
+        let reference = simulation
+            .cva(
+                std::slice::from_ref(&netting_set),
+                &discount,
+                &counterparty,
+                0.4,
+            )
+            .unwrap()[0];
+        assert_approx_eq!(cva, reference, 1e-12);
+
+        // the calibrated intensities keep the expected survival, but default where the exposure is high
+        let wrong_way = simulation
+            .hull_white_intensities(&trade, &counterparty, 0.05)
+            .unwrap();
+        let survival: f64 = wrong_way
+            .rows()
+            .into_iter()
+            .map(|row| (-row.sum() * 0.25).exp())
+            .sum::<f64>()
+            / 100.0;
+        assert_approx_eq!(survival, counterparty.survival_probability(1.0), 1e-10);
+        assert!(wrong_way[[0, 3]] > wrong_way[[99, 3]]);
+        let wrong_way_cva = simulation
+            .cva_with_intensities(&netting_set, &discount, &wrong_way, 0.4)
+            .unwrap();
+        let right_way = simulation
+            .hull_white_intensities(&trade, &counterparty, -0.05)
+            .unwrap();
+        let right_way_cva = simulation
+            .cva_with_intensities(&netting_set, &discount, &right_way, 0.4)
+            .unwrap();
+        assert!(wrong_way_cva > 1.2 * cva);
+        assert!(right_way_cva < cva);
+    }
+}
@@ -0,0 +1,245 @@
+use rand::Rng;
+use rand_distr::StandardNormal;
+
+use crate::analytic::black_scholes::BsmComputation;
+use crate::common::models::{DerivativeParameter, ExerciseType};
+use crate::common::results::PricingError;
+
+/// When the delta hedge of the option is rebalanced during the simulation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RehedgeTrigger {
+    /// after every given number of simulation steps, e.g. hourly
+    Interval(usize),
+    /// whenever the spot moved by the relative threshold since the last rehedge
+    SpotMove(f64),
+}
+
+/// The P&L of a delta-hedged long option over the holding period of a path.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScalpingPnl {
+    /// the mark-to-market P&L of the option, the hedge and the financing
+    pub total: f64,
+    /// the gamma rent $\sum_k \frac{1}{2} \Gamma_k S_k^2 \sigma_i^2 \Delta t$ at the implied volatility
+    pub theta_paid: f64,
+    /// the gains from rehedging the realized moves, i.e. the total P&L plus the theta paid
+    pub scalping: f64,
+    pub nr_rehedges: usize,
+}
+
+/// The distribution of the P&Ls over the simulated paths.
+#[derive(Clone, Debug)]
+pub struct GammaScalpingReport {
+    pub paths: Vec<ScalpingPnl>,
+}
+
+impl GammaScalpingReport {
+    /// The mean of the metric, e.g. `|pnl| pnl.total`.
+    pub fn mean(&self, metric: impl Fn(&ScalpingPnl) -> f64) -> f64 {
+        self.paths.iter().map(metric).sum::<f64>() / self.paths.len() as f64
+    }
+
+    pub fn std_dev(&self, metric: impl Fn(&ScalpingPnl) -> f64) -> f64 {
+        let values: Vec<f64> = self.paths.iter().map(metric).collect();
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64).sqrt()
+    }
+
+    /// The empirical quantile of the metric at the level.
+    pub fn quantile(&self, metric: impl Fn(&ScalpingPnl) -> f64, level: f64) -> f64 {
+        let mut values: Vec<f64> = self.paths.iter().map(metric).collect();
+        values.sort_by(f64::total_cmp);
+        let idx = ((level * values.len() as f64).ceil() as usize).clamp(1, values.len()) - 1;
+        values[idx]
+    }
+}
+
+/// Intraday gamma scalping of a long option bought at the implied volatility of the parameters,
+/// delta-hedged at the Black-Scholes delta of the implied volatility while the spot follows a GBM of the
+/// realized volatility. Over the holding period the hedged option earns about
+/// $\sum_k \frac{1}{2} \Gamma_k S_k^2 ((\Delta S_k / S_k)^2 - \sigma_i^2 \Delta t)$,
+/// i.e. the scalping gains of the realized moves against the theta paid.
+/// See https://en.wikipedia.org/wiki/Gamma_scalping
+#[derive(Clone, Copy, Debug)]
+pub struct GammaScalpingSimulation {
+    dp: DerivativeParameter,
+    exercise_type: ExerciseType,
+    realized_vola: f64,
+    /// the drift of the spot, the rate by default
+    drift: f64,
+    nr_days: usize,
+    steps_per_day: usize,
+    trading_days_per_year: f64,
+    trigger: RehedgeTrigger,
+}
+
+impl GammaScalpingSimulation {
+    /// One trading day of 5-minute steps (78 per 6.5 hours) with a rehedge every hour by default.
+    pub fn new(dp: DerivativeParameter, exercise_type: ExerciseType, realized_vola: f64) -> Self {
+        Self {
+            dp,
+            exercise_type,
+            realized_vola,
+            drift: dp.rfr,
+            nr_days: 1,
+            steps_per_day: 78,
+            trading_days_per_year: 252.0,
+            trigger: RehedgeTrigger::Interval(12),
+        }
+    }
+
+    pub fn with_drift(self, drift: f64) -> Self {
+        Self { drift, ..self }
+    }
+
+    /// The holding period in trading days.
+    pub fn with_nr_days(self, nr_days: usize) -> Self {
+        Self { nr_days, ..self }
+    }
+
+    pub fn with_steps_per_day(self, steps_per_day: usize) -> Self {
+        Self {
+            steps_per_day,
+            ..self
+        }
+    }
+
+    pub fn with_trigger(self, trigger: RehedgeTrigger) -> Self {
+        Self { trigger, ..self }
+    }
+
+    fn bsm(&self, spot: f64, time_to_expiration: f64) -> BsmComputation {
+        BsmComputation::new(&DerivativeParameter {
+            asset_price: spot,
+            time_to_expiration,
+            ..self.dp
+        })
+    }
+
+    fn simulate_path(&self, rn_generator: &mut impl Rng) -> ScalpingPnl {
+        let dt = 1.0 / (self.trading_days_per_year * self.steps_per_day as f64);
+        let nr_steps = self.nr_days * self.steps_per_day;
+        let (sigma_r, sigma_i) = (self.realized_vola, self.dp.vola);
+
+        let mut spot = self.dp.asset_price;
+        let mut time_to_expiration = self.dp.time_to_expiration;
+        let initial = self.bsm(spot, time_to_expiration);
+        let initial_value = initial.price(&self.exercise_type);
+        let mut delta = initial.delta(&self.exercise_type);
+        // the short delta finances the option
+        let mut cash = delta * spot - initial_value;
+        let (mut hedged_spot, mut theta_paid, mut nr_rehedges) = (spot, 0.0, 0);
+
+        for k in 0..nr_steps {
+            let bsm = self.bsm(spot, time_to_expiration);
+            theta_paid += 0.5 * bsm.gamma() * spot * spot * sigma_i * sigma_i * dt;
+
+            let z: f64 = rn_generator.sample(StandardNormal);
+            spot *= ((self.drift - 0.5 * sigma_r * sigma_r) * dt + sigma_r * dt.sqrt() * z).exp();
+            time_to_expiration -= dt;
+            cash *= (self.dp.rfr * dt).exp();
+
+            let rehedge = match self.trigger {
+                RehedgeTrigger::Interval(interval) => (k + 1) % interval.max(1) == 0,
+                RehedgeTrigger::SpotMove(threshold) => {
+                    (spot / hedged_spot - 1.0).abs() >= threshold
+                }
+            };
+            if rehedge {
+                let new_delta = self
+                    .bsm(spot, time_to_expiration)
+                    .delta(&self.exercise_type);
+                cash += (new_delta - delta) * spot;
+                delta = new_delta;
+                hedged_spot = spot;
+                nr_rehedges += 1;
+            }
+        }
+        let option_value = self
+            .bsm(spot, time_to_expiration)
+            .price(&self.exercise_type);
+        let total = option_value - delta * spot + cash;
+        ScalpingPnl {
+            total,
+            theta_paid,
+            scalping: total + theta_paid,
+            nr_rehedges,
+        }
+    }
+
+    /// The P&Ls of the paths, or an error if the holding period does not end before the expiration.
+    pub fn simulate<SeedRng>(
+        &self,
+        nr_paths: usize,
+        seed_nr: u64,
+    ) -> Result<GammaScalpingReport, PricingError>
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        PricingError::check(nr_paths > 1, "nr paths", nr_paths as f64)?;
+        PricingError::check(self.steps_per_day > 0, "steps per day", 0.0)?;
+        let holding_period = self.nr_days as f64 / self.trading_days_per_year;
+        PricingError::check(
+            holding_period < self.dp.time_to_expiration,
+            "holding period",
+            holding_period,
+        )?;
+        let mut rn_generator = SeedRng::seed_from_u64(seed_nr);
+        Ok(GammaScalpingReport {
+            paths: (0..nr_paths)
+                .map(|_| self.simulate_path(&mut rn_generator))
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn at_the_money() -> DerivativeParameter {
+        DerivativeParameter::new(100.0, 100.0, 0.25, 0.02, 0.2)
+    }
+
+    #[test]
+    fn scalping_pays_theta_at_implied_vol() {
+        let simulation = GammaScalpingSimulation::new(at_the_money(), ExerciseType::Call, 0.2)
+            .with_nr_days(5)
+            .with_trigger(RehedgeTrigger::Interval(1));
+        let report = simulation.simulate::<rand_hc::Hc128Rng>(2_000, 42).unwrap();
+        let theta = report.mean(|pnl| pnl.theta_paid);
+        // about the Black-Scholes theta of the hedged option over a week
+        let bsm = BsmComputation::new(&at_the_money());
+        let hedged_theta = -(bsm.theta(&ExerciseType::Call)
+            + 0.02 * (bsm.delta(&ExerciseType::Call) * 100.0 - bsm.call()));
+        assert_approx_eq!(theta, hedged_theta * 5.0 / 252.0, 0.01);
+        assert_approx_eq!(report.mean(|pnl| pnl.scalping), theta, 0.05);
+        assert!(report.mean(|pnl| pnl.total).abs() < 0.05);
+        assert_eq!(report.paths[0].nr_rehedges, 5 * 78);
+    }
+
+    #[test]
+    fn realized_above_implied_earns() {
+        let simulation = GammaScalpingSimulation::new(at_the_money(), ExerciseType::Put, 0.3)
+            .with_nr_days(5)
+            .with_trigger(RehedgeTrigger::SpotMove(0.005));
+        let report = simulation.simulate::<rand_hc::Hc128Rng>(2_000, 7).unwrap();
+        let bsm = BsmComputation::new(&at_the_money());
+        // the variance gap earns $\frac{1}{2} \Gamma S^2 (\sigma_r^2 - \sigma_i^2) T_h$
+        let expected = 0.5 * bsm.gamma() * 100.0 * 100.0 * (0.09 - 0.04) * 5.0 / 252.0;
+        assert_approx_eq!(
+            report.mean(|pnl| pnl.total),
+            expected,
+            0.1 * expected + 0.05
+        );
+        assert!(report.mean(|pnl| pnl.scalping) > report.mean(|pnl| pnl.theta_paid));
+        assert!(report.quantile(|pnl| pnl.total, 0.05) < report.quantile(|pnl| pnl.total, 0.95));
+        assert!(report.std_dev(|pnl| pnl.total) > 0.0);
+        let rehedges = report.mean(|pnl| pnl.nr_rehedges as f64);
+        assert!(rehedges > 5.0 && rehedges < 100.0);
+
+        let too_long =
+            GammaScalpingSimulation::new(at_the_money(), ExerciseType::Put, 0.3).with_nr_days(100);
+        assert!(too_long.simulate::<rand_hc::Hc128Rng>(10, 7).is_err());
+    }
+}
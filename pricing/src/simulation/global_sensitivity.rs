@@ -0,0 +1,225 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use rand::Rng;
+
+use crate::common::results::PricingError;
+
+/// An input of a pricer, e.g. the spot, a volatility, a rate or a correlation, sampled uniformly
+/// from its range of plausible values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SensitivityInput {
+    pub name: String,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl SensitivityInput {
+    pub fn new(name: &str, lower: f64, upper: f64) -> Self {
+        assert!(lower <= upper);
+        Self {
+            name: name.to_string(),
+            lower,
+            upper,
+        }
+    }
+
+    fn sample(&self, uniform: f64) -> f64 {
+        self.lower + uniform * (self.upper - self.lower)
+    }
+}
+
+/// The first order and total Sobol' indices of the inputs, i.e. the fractions of the variance of the
+/// output explained by each input alone and by each input including its interactions with the others.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SobolIndices {
+    pub names: Vec<String>,
+    pub first_order: Vec<f64>,
+    pub total: Vec<f64>,
+    /// the variance of the output over the input ranges
+    pub variance: f64,
+    pub nr_evaluations: usize,
+}
+
+impl SobolIndices {
+    /// The inputs by decreasing total index, i.e. by their priority for the input quality.
+    pub fn ranking(&self) -> Vec<(&str, f64)> {
+        let mut ranking: Vec<(&str, f64)> = self
+            .names
+            .iter()
+            .map(String::as_str)
+            .zip(self.total.iter().copied())
+            .collect();
+        ranking.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranking
+    }
+}
+
+impl fmt::Display for SobolIndices {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<16} {:>12} {:>12}", "input", "first order", "total")?;
+        for ((name, first_order), total) in
+            self.names.iter().zip(&self.first_order).zip(&self.total)
+        {
+            writeln!(f, "{name:<16} {first_order:>12.4} {total:>12.4}")?;
+        }
+        write!(
+            f,
+            "variance {:.6} of {} evaluations",
+            self.variance, self.nr_evaluations
+        )
+    }
+}
+
+/// Global sensitivity analysis of a pricer over the ranges of its inputs by the Sobol' indices,
+/// estimated with the estimators of Saltelli et al. (2010) from two independent samples A and B of
+/// the inputs and the samples $A_B^{(i)}$ of A with the i-th input taken from B, which costs
+/// `nr_samples * (nr_inputs + 2)` evaluations. Unlike local greeks, the indices capture the
+/// nonlinearity of the pricer and the interactions of the inputs over their whole ranges.
+/// See https://en.wikipedia.org/wiki/Variance-based_sensitivity_analysis
+pub struct SobolAnalysis<SeedRng>
+where
+    SeedRng: rand::SeedableRng + rand::RngCore,
+{
+    inputs: Vec<SensitivityInput>,
+    nr_samples: usize,
+    seed_nr: u64,
+    _phantom_rng: PhantomData<SeedRng>,
+}
+
+impl<SeedRng> SobolAnalysis<SeedRng>
+where
+    SeedRng: rand::SeedableRng + rand::RngCore,
+{
+    pub fn new(inputs: Vec<SensitivityInput>, nr_samples: usize, seed_nr: u64) -> Self {
+        Self {
+            inputs,
+            nr_samples,
+            seed_nr,
+            _phantom_rng: PhantomData::<SeedRng>,
+        }
+    }
+
+    fn sample_inputs(&self, rng: &mut SeedRng) -> Vec<Vec<f64>> {
+        (0..self.nr_samples)
+            .map(|_| {
+                self.inputs
+                    .iter()
+                    .map(|input| input.sample(rng.gen::<f64>()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The Sobol' indices of the pricer, which maps the inputs in the order of the analysis to the
+    /// price, or the first error of the pricer.
+    pub fn analyze(
+        &self,
+        pricer: impl Fn(&[f64]) -> Result<f64, PricingError>,
+    ) -> Result<SobolIndices, PricingError> {
+        PricingError::check(self.nr_samples > 1, "nr samples", self.nr_samples as f64)?;
+        PricingError::check(
+            !self.inputs.is_empty(),
+            "nr inputs",
+            self.inputs.len() as f64,
+        )?;
+        let mut rng = SeedRng::seed_from_u64(self.seed_nr);
+        let a = self.sample_inputs(&mut rng);
+        let b = self.sample_inputs(&mut rng);
+        let evaluate = |samples: &[Vec<f64>]| -> Result<Vec<f64>, PricingError> {
+            samples.iter().map(|x| pricer(x)).collect()
+        };
+        let f_a = evaluate(&a)?;
+        let f_b = evaluate(&b)?;
+
+        let n = self.nr_samples as f64;
+        let mean = (f_a.iter().sum::<f64>() + f_b.iter().sum::<f64>()) / (2.0 * n);
+        let variance = f_a
+            .iter()
+            .chain(&f_b)
+            .map(|y| (y - mean).powi(2))
+            .sum::<f64>()
+            / (2.0 * n - 1.0);
+        PricingError::check(variance > 0.0, "variance", variance)?;
+
+        let (mut first_order, mut total) = (Vec::new(), Vec::new());
+        for i in 0..self.inputs.len() {
+            let a_b: Vec<Vec<f64>> = a
+                .iter()
+                .zip(&b)
+                .map(|(x_a, x_b)| {
+                    let mut x = x_a.clone();
+                    x[i] = x_b[i];
+                    x
+                })
+                .collect();
+            let f_ab = evaluate(&a_b)?;
+            let (mut first, mut tot) = (0.0, 0.0);
+            for ((y_a, y_b), y_ab) in f_a.iter().zip(&f_b).zip(&f_ab) {
+                first += y_b * (y_ab - y_a);
+                tot += (y_a - y_ab).powi(2);
+            }
+            first_order.push(first / n / variance);
+            total.push(0.5 * tot / n / variance);
+        }
+        Ok(SobolIndices {
+            names: self.inputs.iter().map(|input| input.name.clone()).collect(),
+            first_order,
+            total,
+            variance,
+            nr_evaluations: self.nr_samples * (self.inputs.len() + 2),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::BsmComputation;
+    use crate::common::models::DerivativeParameter;
+    use assert_approx_eq::assert_approx_eq;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn sobol_indices() {
+        // the Ishigami function $\sin x_1 + 7 \sin^2 x_2 + 0.1 x_3^4 \sin x_1$ with known indices
+        let inputs = ["x1", "x2", "x3"]
+            .iter()
+            .map(|name| SensitivityInput::new(name, -PI, PI))
+            .collect();
+        let analysis: SobolAnalysis<rand_hc::Hc128Rng> = SobolAnalysis::new(inputs, 20_000, 42);
+        let indices = analysis
+            .analyze(|x| {
+                Ok(x[0].sin() + 7.0 * x[1].sin().powi(2) + 0.1 * x[2].powi(4) * x[0].sin())
+            })
+            .unwrap();
+        assert_eq!(indices.nr_evaluations, 100_000);
+        for (estimate, exact) in indices.first_order.iter().zip([0.3139, 0.4424, 0.0]) {
+            assert_approx_eq!(estimate, exact, 0.03);
+        }
+        for (estimate, exact) in indices.total.iter().zip([0.5576, 0.4424, 0.2437]) {
+            assert_approx_eq!(estimate, exact, 0.03);
+        }
+        assert_eq!(indices.ranking()[0].0, "x1");
+
+        // an at-the-money call is driven by the spot and the volatility, hardly by the rate
+        let inputs = vec![
+            SensitivityInput::new("spot", 90.0, 110.0),
+            SensitivityInput::new("vola", 0.15, 0.25),
+            SensitivityInput::new("rate", 0.0, 0.02),
+        ];
+        let analysis: SobolAnalysis<rand_hc::Hc128Rng> = SobolAnalysis::new(inputs, 2_000, 7);
+        let indices = analysis
+            .analyze(|x| {
+                let dp = DerivativeParameter::new(x[0], 100.0, 0.5, x[2], x[1]);
+                Ok(BsmComputation::new(&dp).call())
+            })
+            .unwrap();
+        let ranking: Vec<&str> = indices.ranking().iter().map(|(name, _)| *name).collect();
+        assert_eq!(ranking, vec!["spot", "vola", "rate"]);
+        assert!(indices.total[2] < 0.05);
+        assert!(analysis
+            .analyze(|_| Err(PricingError::NoPaths))
+            .is_err_and(|error| error == PricingError::NoPaths));
+    }
+}
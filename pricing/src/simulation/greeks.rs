@@ -0,0 +1,646 @@
+use std::marker::PhantomData;
+
+use ndarray::Array2;
+use rand::Rng;
+use rand_distr::StandardNormal;
+
+use crate::analytic::vega_buckets::{VegaBucket, VegaReport};
+use crate::common::results::GreeksResult;
+use crate::common::vol_surface::VolatilitySurface;
+use crate::simulation::monte_carlo::PathGenerator;
+use crate::simulation::payoff_smoothing::{ConditionalPayoff, PayoffSmoothing, SmoothablePayoff};
+use crate::simulation::scenarios::MarketScenario;
+use crate::simulation::sde::gbm::GeometricBrownianMotion;
+
+/// Standard normals sampled once and shared by all valuations of a greeks computation
+/// (common random numbers), such that bumped prices differ by the bump and not by the sampling noise.
+#[derive(Clone, Debug)]
+pub struct SharedNormals {
+    paths: Vec<Vec<f64>>,
+}
+
+impl SharedNormals {
+    pub fn new<SeedRng>(nr_paths: usize, nr_steps: usize, seed_nr: u64) -> Self
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        let mut rn_generator = SeedRng::seed_from_u64(seed_nr);
+        let paths = (0..nr_paths)
+            .map(|_| StandardNormal.sample_path(&mut rn_generator, nr_steps))
+            .collect();
+        Self { paths }
+    }
+
+    pub fn nr_paths(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// The standard normals per path.
+    pub fn paths(&self) -> &[Vec<f64>] {
+        &self.paths
+    }
+}
+
+/// The bump sizes of the finite differences: relative for the spot and absolute for the volatility.
+#[derive(Clone, Copy, Debug)]
+pub struct BumpSizes {
+    pub spot: f64,
+    pub vola: f64,
+}
+
+impl Default for BumpSizes {
+    fn default() -> Self {
+        Self {
+            spot: 0.01,
+            vola: 0.01,
+        }
+    }
+}
+
+/// The estimator of the spot greeks of discontinuous payoffs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GreekStrategy {
+    /// central finite differences of the (smoothed) payoff
+    BumpAndReprice,
+    /// finite differences of the analytic expectation of the last step conditional on the path before
+    ConditionalExpectation,
+    /// pathwise up to the last step and likelihood ratio over `nr_samples` antithetic samples of the last step,
+    /// see Giles, Vibrato Monte Carlo sensitivities (2009)
+    Vibrato { nr_samples: usize },
+    /// likelihood ratio with the Malliavin weights of the exact terminal value, which needs no bumps nor smoothing,
+    /// see Fournié et al., Applications of Malliavin calculus to Monte Carlo methods in finance (1999)
+    Malliavin,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpotGreeks {
+    pub value: f64,
+    pub delta: f64,
+    pub gamma: f64,
+}
+
+/// Values and greeks on a grid of spots (rows) and volatilities (columns).
+#[derive(Clone, Debug)]
+pub struct GreeksSurface {
+    pub spots: Vec<f64>,
+    pub volas: Vec<f64>,
+    pub value: Array2<f64>,
+    pub delta: Array2<f64>,
+    pub gamma: Array2<f64>,
+    pub vega: Array2<f64>,
+}
+
+/// The greeks of a payoff in the (stressed) market state of a scenario.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScenarioGreeks {
+    pub scenario: String,
+    pub greeks: GreeksResult,
+}
+
+/// Bump-and-reprice greeks of payoffs on GBM paths under the risk neutral measure,
+/// where all valuations use the same standard normals.
+pub struct GbmGreeksEngine<SeedRng>
+where
+    SeedRng: rand::SeedableRng + rand::RngCore,
+{
+    rfr: f64,
+    time_to_expiration: f64,
+    nr_steps: usize,
+    shared_normals: SharedNormals,
+    bump_sizes: BumpSizes,
+    smoothing: PayoffSmoothing,
+    seed_nr: u64,
+    _phantom_rng: PhantomData<SeedRng>,
+}
+
+impl<SeedRng> GbmGreeksEngine<SeedRng>
+where
+    SeedRng: rand::SeedableRng + rand::RngCore,
+{
+    pub fn new(
+        rfr: f64,
+        time_to_expiration: f64,
+        nr_paths: usize,
+        nr_steps: usize,
+        seed_nr: u64,
+    ) -> Self {
+        Self {
+            rfr,
+            time_to_expiration,
+            nr_steps,
+            shared_normals: SharedNormals::new::<SeedRng>(nr_paths, nr_steps, seed_nr),
+            bump_sizes: BumpSizes::default(),
+            smoothing: PayoffSmoothing::default(),
+            seed_nr,
+            _phantom_rng: PhantomData::<SeedRng>,
+        }
+    }
+
+    pub fn with_bump_sizes(self, bump_sizes: BumpSizes) -> Self {
+        Self { bump_sizes, ..self }
+    }
+
+    pub fn with_smoothing(self, smoothing: PayoffSmoothing) -> Self {
+        Self { smoothing, ..self }
+    }
+
+    /// The payoff of the product with the engine's smoothing of its discontinuities.
+    pub fn smoothed_payoff<'a>(
+        &'a self,
+        product: &'a impl SmoothablePayoff,
+    ) -> impl Fn(&[f64]) -> f64 + 'a {
+        move |path| product.payoff(path, &self.smoothing)
+    }
+
+    /// The discounted average of the (undiscounted) `payoff` at expiration over the shared paths.
+    pub fn value(&self, spot: f64, vola: f64, payoff: &impl Fn(&[f64]) -> f64) -> f64 {
+        self.value_at_rate(spot, vola, self.rfr, payoff)
+    }
+
+    fn value_at_rate(
+        &self,
+        spot: f64,
+        vola: f64,
+        rfr: f64,
+        payoff: &impl Fn(&[f64]) -> f64,
+    ) -> f64 {
+        let dt = self.time_to_expiration / self.nr_steps as f64;
+        let gbm = GeometricBrownianMotion::new(spot, rfr, vola, dt);
+        let sum: f64 = self
+            .shared_normals
+            .paths
+            .iter()
+            .map(|normals| payoff(&gbm.generate_path(spot, normals)))
+            .sum();
+        (-rfr * self.time_to_expiration).exp() * sum / self.shared_normals.nr_paths() as f64
+    }
+
+    /// The value, delta, gamma, vega and rho conditional on each scenario's market state, e.g. the delta
+    /// after a spot move of -20% and a vola shift of +10pts, where the scenario's rate replaces the engine's.
+    /// All scenarios and bumps use the shared normals, such that stressed and base greeks differ by the
+    /// market state only. The rho is by a central difference of one basis point.
+    pub fn scenario_greeks(
+        &self,
+        scenarios: &[MarketScenario],
+        payoff: impl Fn(&[f64]) -> f64,
+    ) -> Vec<ScenarioGreeks> {
+        let rate_bump = 1e-4;
+        scenarios
+            .iter()
+            .map(|scenario| {
+                let value = |spot, vola, rfr| self.value_at_rate(spot, vola, rfr, &payoff);
+                let (spot, vola, rfr) = (scenario.spot, scenario.vola, scenario.rfr);
+                let spot_greeks = self.finite_differences(spot, |s| value(s, vola, rfr));
+                let vola_bump = self.bump_sizes.vola;
+                let vega = (value(spot, vola + vola_bump, rfr)
+                    - value(spot, vola - vola_bump, rfr))
+                    / (2.0 * vola_bump);
+                let rho = (value(spot, vola, rfr + rate_bump) - value(spot, vola, rfr - rate_bump))
+                    / (2.0 * rate_bump);
+                ScenarioGreeks {
+                    scenario: scenario.name.clone(),
+                    greeks: GreeksResult {
+                        value: spot_greeks.value,
+                        delta: Some(spot_greeks.delta),
+                        gamma: Some(spot_greeks.gamma),
+                        vega: Some(vega),
+                        rho: Some(rho),
+                        ..Default::default()
+                    },
+                }
+            })
+            .collect()
+    }
+
+    /// The discounted average payoff, where the volatility of each step is the one of the surface
+    /// at the `strike` for the step's time bucket.
+    pub fn value_with_surface(
+        &self,
+        spot: f64,
+        surface: &VolatilitySurface,
+        strike: f64,
+        payoff: &impl Fn(&[f64]) -> f64,
+    ) -> f64 {
+        let dt = self.time_to_expiration / self.nr_steps as f64;
+        let step_volas: Vec<f64> = (0..self.nr_steps)
+            .map(|j| surface.vola(surface.bucket(j as f64 * dt), strike))
+            .collect();
+
+        let sum: f64 = self
+            .shared_normals
+            .paths
+            .iter()
+            .map(|normals| {
+                let mut path = Vec::with_capacity(self.nr_steps + 1);
+                let mut curr_p = spot;
+                path.push(curr_p);
+                for (z, vola) in normals.iter().zip(&step_volas) {
+                    curr_p =
+                        GeometricBrownianMotion::new(curr_p, self.rfr, *vola, dt).step(curr_p, *z);
+                    path.push(curr_p);
+                }
+                payoff(&path)
+            })
+            .sum();
+        (-self.rfr * self.time_to_expiration).exp() * sum / self.shared_normals.nr_paths() as f64
+    }
+
+    /// The bucketed vega per node of the volatility surface by central finite differences,
+    /// where the paths use the surface's volatilities at the `strike`.
+    pub fn surface_vega(
+        &self,
+        spot: f64,
+        surface: &VolatilitySurface,
+        strike: f64,
+        payoff: impl Fn(&[f64]) -> f64,
+    ) -> VegaReport {
+        let mut report = VegaReport::default();
+        let bump = self.bump_sizes.vola;
+        for (k, length) in surface
+            .bucket_lengths(self.time_to_expiration)
+            .iter()
+            .enumerate()
+        {
+            if *length == 0.0 {
+                continue;
+            }
+            for (l, _) in surface.strike_weights(strike) {
+                let value_up =
+                    self.value_with_surface(spot, &surface.bumped(k, l, bump), strike, &payoff);
+                let value_down =
+                    self.value_with_surface(spot, &surface.bumped(k, l, -bump), strike, &payoff);
+                report.add(
+                    VegaBucket {
+                        maturity_bucket: k,
+                        strike_bucket: Some(l),
+                    },
+                    (value_up - value_down) / (2.0 * bump),
+                );
+            }
+        }
+        report
+    }
+
+    /// The value, delta and gamma of a product whose payoff depends on the terminal value,
+    /// where the discontinuity is handled by the given strategy.
+    pub fn spot_greeks<P>(
+        &self,
+        spot: f64,
+        vola: f64,
+        product: &P,
+        strategy: GreekStrategy,
+    ) -> SpotGreeks
+    where
+        P: SmoothablePayoff + ConditionalPayoff,
+    {
+        match strategy {
+            GreekStrategy::BumpAndReprice => self.finite_differences(spot, |s| {
+                self.value(s, vola, &self.smoothed_payoff(product))
+            }),
+            GreekStrategy::ConditionalExpectation => {
+                self.finite_differences(spot, |s| self.conditional_value(s, vola, product))
+            }
+            GreekStrategy::Vibrato { nr_samples } => {
+                self.vibrato(spot, vola, &self.smoothed_payoff(product), nr_samples)
+            }
+            GreekStrategy::Malliavin => {
+                let (value, delta, gamma, _) = self.malliavin_weighted(spot, vola, &|path| {
+                    product.payoff(path, &PayoffSmoothing::default())
+                });
+                SpotGreeks {
+                    value,
+                    delta,
+                    gamma,
+                }
+            }
+        }
+    }
+
+    fn finite_differences(&self, spot: f64, value: impl Fn(f64) -> f64) -> SpotGreeks {
+        let spot_bump = spot * self.bump_sizes.spot;
+        let (value_up, value_mid, value_down) = (
+            value(spot + spot_bump),
+            value(spot),
+            value(spot - spot_bump),
+        );
+        SpotGreeks {
+            value: value_mid,
+            delta: (value_up - value_down) / (2.0 * spot_bump),
+            gamma: (value_up - 2.0 * value_mid + value_down) / spot_bump.powi(2),
+        }
+    }
+
+    /// The paths up to the penultimate step with the mean and standard deviation of the log-normal last step.
+    fn last_step_conditions(&self, spot: f64, vola: f64) -> Vec<(Vec<f64>, f64, f64)> {
+        let dt = self.time_to_expiration / self.nr_steps as f64;
+        let gbm = GeometricBrownianMotion::new(spot, self.rfr, vola, dt);
+        self.shared_normals
+            .paths
+            .iter()
+            .map(|normals| {
+                let path = gbm.generate_path(spot, &normals[..self.nr_steps - 1]);
+                let log_mean = path.last().unwrap().ln() + (self.rfr - 0.5 * vola * vola) * dt;
+                (path, log_mean, vola * dt.sqrt())
+            })
+            .collect()
+    }
+
+    /// The discounted average of the analytic conditional expectations of the last step.
+    pub fn conditional_value(&self, spot: f64, vola: f64, product: &impl ConditionalPayoff) -> f64 {
+        let conditions = self.last_step_conditions(spot, vola);
+        let sum: f64 = conditions
+            .iter()
+            .map(|(path, log_mean, log_std)| {
+                product.conditional_expectation(path, *log_mean, *log_std)
+            })
+            .sum();
+        (-self.rfr * self.time_to_expiration).exp() * sum / conditions.len() as f64
+    }
+
+    /// With $X = ln S_T \sim N(m, s^2)$ conditional on the path before and $m = ln S_0 + const$,
+    /// $\partial_m E[f] = E[f (X - m)] / s^2$ and $\partial_m^2 E[f] = E[f ((X - m)^2 - s^2)] / s^4$.
+    fn vibrato(
+        &self,
+        spot: f64,
+        vola: f64,
+        payoff: &impl Fn(&[f64]) -> f64,
+        nr_samples: usize,
+    ) -> SpotGreeks {
+        let mut rn_generator = SeedRng::seed_from_u64(self.seed_nr.wrapping_add(1));
+        let conditions = self.last_step_conditions(spot, vola);
+        let (mut value, mut d_m, mut d_mm) = (0.0, 0.0, 0.0);
+        for (mut path, log_mean, log_std) in conditions.iter().cloned() {
+            path.push(log_mean.exp());
+            let payoff_mean = payoff(&path);
+            for _ in 0..nr_samples {
+                let z: f64 = rn_generator.sample(StandardNormal);
+                *path.last_mut().unwrap() = (log_mean + log_std * z).exp();
+                let payoff_up = payoff(&path);
+                *path.last_mut().unwrap() = (log_mean - log_std * z).exp();
+                let payoff_down = payoff(&path);
+
+                let average = 0.5 * (payoff_up + payoff_down);
+                value += average;
+                d_m += 0.5 * (payoff_up - payoff_down) * z / log_std;
+                // the payoff at the mean is a control variate, since E[z^2 - 1] = 0
+                d_mm += (average - payoff_mean) * (z * z - 1.0) / log_std.powi(2);
+            }
+        }
+        let scale =
+            (-self.rfr * self.time_to_expiration).exp() / (conditions.len() * nr_samples) as f64;
+        let (d_m, d_mm) = (d_m * scale, d_mm * scale);
+        SpotGreeks {
+            value: value * scale,
+            delta: d_m / spot,
+            gamma: (d_mm - d_m) / spot.powi(2),
+        }
+    }
+
+    /// The value, delta, gamma and vega of a payoff of the terminal value by Malliavin weights, where the
+    /// payoff receives the paths $[S_0, S_T]$ of the exact terminal values $S_T$ of the shared normals.
+    pub fn malliavin_greeks(
+        &self,
+        spot: f64,
+        vola: f64,
+        payoff: &impl Fn(&[f64]) -> f64,
+    ) -> GreeksResult {
+        let (value, delta, gamma, vega) = self.malliavin_weighted(spot, vola, payoff);
+        GreeksResult {
+            value,
+            delta: Some(delta),
+            gamma: Some(gamma),
+            vega: Some(vega),
+            ..Default::default()
+        }
+    }
+
+    /// With $W = W_T$ the greeks are the discounted expectations of the payoff times the weights
+    /// '''math
+    /// \Delta: \frac{W}{S_0 \sigma T}, \quad
+    /// \mathcal{V}: \frac{W^2}{\sigma T} - W - \frac{1}{\sigma}, \quad
+    /// \Gamma: \frac{\mathcal{V}}{S_0^2 \sigma T}
+    /// '''
+    fn malliavin_weighted(
+        &self,
+        spot: f64,
+        vola: f64,
+        payoff: &impl Fn(&[f64]) -> f64,
+    ) -> (f64, f64, f64, f64) {
+        let t = self.time_to_expiration;
+        let dt = t / self.nr_steps as f64;
+        let drift = (self.rfr - 0.5 * vola * vola) * t;
+        let (mut value, mut delta, mut vega) = (0.0, 0.0, 0.0);
+        for normals in &self.shared_normals.paths {
+            let w = dt.sqrt() * normals.iter().sum::<f64>();
+            let f = payoff(&[spot, spot * (drift + vola * w).exp()]);
+            value += f;
+            delta += f * w / (spot * vola * t);
+            vega += f * (w * w / (vola * t) - w - 1.0 / vola);
+        }
+        let scale = (-self.rfr * t).exp() / self.shared_normals.nr_paths() as f64;
+        let vega = vega * scale;
+        (
+            value * scale,
+            delta * scale,
+            vega / (spot * spot * vola * t),
+            vega,
+        )
+    }
+
+    /// The values and the central finite difference delta, gamma and vega for each grid node.
+    pub fn greeks_surface(
+        &self,
+        spots: &[f64],
+        volas: &[f64],
+        payoff: impl Fn(&[f64]) -> f64,
+    ) -> GreeksSurface {
+        let shape = (spots.len(), volas.len());
+        let mut surface = GreeksSurface {
+            spots: spots.to_vec(),
+            volas: volas.to_vec(),
+            value: Array2::zeros(shape),
+            delta: Array2::zeros(shape),
+            gamma: Array2::zeros(shape),
+            vega: Array2::zeros(shape),
+        };
+
+        for (i, spot) in spots.iter().enumerate() {
+            let spot_bump = spot * self.bump_sizes.spot;
+            for (j, vola) in volas.iter().enumerate() {
+                let value = self.value(*spot, *vola, &payoff);
+                let spot_up = self.value(spot + spot_bump, *vola, &payoff);
+                let spot_down = self.value(spot - spot_bump, *vola, &payoff);
+                let vola_up = self.value(*spot, vola + self.bump_sizes.vola, &payoff);
+                let vola_down = self.value(*spot, vola - self.bump_sizes.vola, &payoff);
+
+                surface.value[[i, j]] = value;
+                surface.delta[[i, j]] = (spot_up - spot_down) / (2.0 * spot_bump);
+                surface.gamma[[i, j]] = (spot_up - 2.0 * value + spot_down) / spot_bump.powi(2);
+                surface.vega[[i, j]] = (vola_up - vola_down) / (2.0 * self.bump_sizes.vola);
+            }
+        }
+        surface
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::BsmComputation;
+    use crate::analytic::vega_buckets::surface_vega;
+    use crate::common::models::{DerivativeParameter, ExerciseType};
+    use crate::numerics::normal::{cdf, pdf};
+    use crate::simulation::payoff_smoothing::DigitalOption;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn call_greeks_surface() {
+        let (strike, rfr, t) = (100.0, 0.03, 1.0);
+        let engine: GbmGreeksEngine<rand_hc::Hc128Rng> =
+            GbmGreeksEngine::new(rfr, t, 20_000, 20, 42);
+        let spots = [90.0, 100.0, 110.0];
+        let volas = [0.2, 0.3];
+        let surface = engine.greeks_surface(&spots, &volas, |path| {
+            (path.last().unwrap() - strike).max(0.0)
+        });
+        assert_eq!(surface.delta.shape(), &[3, 2]);
+
+        for (i, spot) in spots.iter().enumerate() {
+            for (j, vola) in volas.iter().enumerate() {
+                let bsm =
+                    BsmComputation::new(&DerivativeParameter::new(*spot, strike, t, rfr, *vola));
+                assert_approx_eq!(surface.value[[i, j]], bsm.call(), 0.5);
+                assert_approx_eq!(surface.delta[[i, j]], bsm.delta(&ExerciseType::Call), 0.02);
+                assert_approx_eq!(surface.gamma[[i, j]], bsm.gamma(), 0.005);
+                assert_approx_eq!(surface.vega[[i, j]], bsm.vega(), 1.5);
+            }
+        }
+    }
+
+    #[test]
+    fn stressed_call_greeks() {
+        let (strike, t) = (100.0, 1.0);
+        let engine: GbmGreeksEngine<rand_hc::Hc128Rng> =
+            GbmGreeksEngine::new(0.03, t, 20_000, 20, 7);
+        let base = MarketScenario::new("base", 100.0, 0.2, 0.03);
+        let scenarios = [
+            base.clone(),
+            base.shifted("crash", -0.2, 0.1, 0.0),
+            base.shifted("rates up", 0.0, 0.0, 0.02),
+        ];
+        let results =
+            engine.scenario_greeks(&scenarios, |path| (path.last().unwrap() - strike).max(0.0));
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[1].scenario, "crash");
+
+        for (scenario, result) in scenarios.iter().zip(&results) {
+            let dp =
+                DerivativeParameter::new(scenario.spot, strike, t, scenario.rfr, scenario.vola);
+            let bsm = BsmComputation::new(&dp);
+            let greeks = &result.greeks;
+            assert_approx_eq!(greeks.value, bsm.call(), 0.5);
+            assert_approx_eq!(greeks.delta.unwrap(), bsm.delta(&ExerciseType::Call), 0.02);
+            assert_approx_eq!(greeks.gamma.unwrap(), bsm.gamma(), 0.005);
+            assert_approx_eq!(greeks.vega.unwrap(), bsm.vega(), 1.5);
+            assert_approx_eq!(greeks.rho.unwrap(), bsm.rho(&ExerciseType::Call), 2.0);
+        }
+        // the base scenario reproduces the engine's own valuation
+        let payoff = |path: &[f64]| (path.last().unwrap() - strike).max(0.0);
+        assert_eq!(results[0].greeks.value, engine.value(100.0, 0.2, &payoff));
+    }
+
+    #[test]
+    fn bucketed_surface_vega() {
+        let (spot, strike, rfr, t) = (100.0, 105.0, 0.03, 1.0);
+        let vola_surface = VolatilitySurface::new(
+            vec![0.25, 0.5, 2.0],
+            vec![100.0, 110.0],
+            ndarray::arr2(&[[0.3, 0.25], [0.25, 0.2], [0.2, 0.18]]),
+        );
+        let engine: GbmGreeksEngine<rand_hc::Hc128Rng> =
+            GbmGreeksEngine::new(rfr, t, 20_000, 20, 7);
+        let report = engine.surface_vega(spot, &vola_surface, strike, |path| {
+            (path.last().unwrap() - strike).max(0.0)
+        });
+        let analytic = surface_vega(&vola_surface, spot, strike, t, rfr);
+
+        assert_eq!(report.vegas.len(), 6);
+        for (bucket, vega) in analytic.vegas.iter() {
+            assert_approx_eq!(report.get(bucket), *vega, 1.0);
+        }
+        assert_approx_eq!(report.total(), analytic.total(), 1.5);
+    }
+
+    #[test]
+    fn digital_greek_strategies() {
+        let (spot, strike, rfr, vola, t): (f64, f64, f64, f64, f64) =
+            (100.0, 105.0, 0.02, 0.2, 1.0);
+        let d2 = ((spot / strike).ln() + (rfr - 0.5 * vola * vola) * t) / (vola * t.sqrt());
+        let discount = (-rfr * t).exp();
+        let delta = discount * pdf(d2) / (spot * vola * t.sqrt());
+        let gamma = -discount * pdf(d2) * (d2 + vola * t.sqrt()) / (spot * vola * t.sqrt()).powi(2);
+
+        let digital = DigitalOption {
+            strike,
+            cash: 1.0,
+            exercise_type: ExerciseType::Call,
+        };
+        let engine: GbmGreeksEngine<rand_hc::Hc128Rng> =
+            GbmGreeksEngine::new(rfr, t, 10_000, 1, 11);
+
+        let conditional =
+            engine.spot_greeks(spot, vola, &digital, GreekStrategy::ConditionalExpectation);
+        assert_approx_eq!(conditional.value, discount * cdf(d2), 1e-12);
+        assert_approx_eq!(conditional.delta, delta, 1e-4);
+        assert_approx_eq!(conditional.gamma, gamma, 1e-5);
+
+        let vibrato = engine.spot_greeks(
+            spot,
+            vola,
+            &digital,
+            GreekStrategy::Vibrato { nr_samples: 10 },
+        );
+        assert_approx_eq!(vibrato.value, discount * cdf(d2), 5e-3);
+        assert_approx_eq!(vibrato.delta, delta, 5e-4);
+        assert_approx_eq!(vibrato.gamma, gamma, 5e-5);
+    }
+
+    #[test]
+    fn malliavin_greeks() {
+        let (spot, strike, rfr, vola, t): (f64, f64, f64, f64, f64) =
+            (100.0, 105.0, 0.02, 0.2, 1.0);
+        let engine: GbmGreeksEngine<rand_hc::Hc128Rng> =
+            GbmGreeksEngine::new(rfr, t, 100_000, 1, 5);
+
+        // the discontinuous digital needs neither bumps nor smoothing
+        let d1 = ((spot / strike).ln() + (rfr + 0.5 * vola * vola) * t) / (vola * t.sqrt());
+        let d2 = d1 - vola * t.sqrt();
+        let discount = (-rfr * t).exp();
+        let digital = DigitalOption {
+            strike,
+            cash: 1.0,
+            exercise_type: ExerciseType::Call,
+        };
+        let greeks = engine.spot_greeks(spot, vola, &digital, GreekStrategy::Malliavin);
+        assert_approx_eq!(greeks.value, discount * cdf(d2), 5e-3);
+        assert_approx_eq!(
+            greeks.delta,
+            discount * pdf(d2) / (spot * vola * t.sqrt()),
+            5e-4
+        );
+        assert_approx_eq!(
+            greeks.gamma,
+            -discount * pdf(d2) * d1 / (spot * vola * t.sqrt()).powi(2),
+            5e-5
+        );
+
+        let call = engine.malliavin_greeks(spot, vola, &|path: &[f64]| {
+            (path.last().unwrap() - strike).max(0.0)
+        });
+        let bsm = BsmComputation::new(&DerivativeParameter::new(spot, strike, t, rfr, vola));
+        assert_approx_eq!(call.value, bsm.call(), 0.1);
+        assert_approx_eq!(call.delta.unwrap(), bsm.delta(&ExerciseType::Call), 0.01);
+        assert_approx_eq!(call.gamma.unwrap(), bsm.gamma(), 5e-4);
+        assert_approx_eq!(call.vega.unwrap(), bsm.vega(), 1.0);
+    }
+}
@@ -0,0 +1,194 @@
+use rand::Rng;
+use rand_distr::{Distribution, Standard};
+
+use crate::analytic::black_scholes::BsmComputation;
+use crate::common::models::DerivativeParameter;
+use crate::common::vol_surface::VolatilitySurface;
+
+/// The risk-neutral distribution of the terminal asset price implied by the volatility smile at the
+/// expiration, by the Breeden-Litzenberger relation for the call prices $C(K)$
+/// '''math
+/// P(S_T \le K) = 1 + e^{rT} \frac{\partial C}{\partial K}
+/// '''
+/// evaluated on a log-spaced strike grid. Terminal values are sampled by inverting the cdf,
+/// which prices European payoffs consistently with the smile and without simulating paths.
+/// See https://en.wikipedia.org/wiki/Breeden-Litzenberger_formula
+#[derive(Clone, Debug)]
+pub struct ImpliedDistribution {
+    /// the (midpoint) strikes of the grid
+    strikes: Vec<f64>,
+    /// the non-decreasing cdf at the strikes
+    cdf: Vec<f64>,
+    disc_factor: f64,
+}
+
+impl ImpliedDistribution {
+    /// The implied distribution for the strike grid spanning `nr_std_devs` standard deviations
+    /// (of the at-the-money forward volatility) around the forward with `nr_strikes` strikes.
+    pub fn new(
+        surface: &VolatilitySurface,
+        spot: f64,
+        rfr: f64,
+        time_to_expiration: f64,
+        nr_strikes: usize,
+        nr_std_devs: f64,
+    ) -> Self {
+        assert!(nr_strikes > 2);
+        let forward = spot * (rfr * time_to_expiration).exp();
+        let std_dev =
+            surface.effective_vola(time_to_expiration, forward) * time_to_expiration.sqrt();
+        let log_width = 2.0 * nr_std_devs * std_dev / (nr_strikes - 1) as f64;
+
+        let grid: Vec<f64> = (0..nr_strikes)
+            .map(|i| forward * (-nr_std_devs * std_dev + i as f64 * log_width).exp())
+            .collect();
+        let call_prices: Vec<f64> = grid
+            .iter()
+            .map(|strike| {
+                let vola = surface.effective_vola(time_to_expiration, *strike);
+                BsmComputation::new(&DerivativeParameter::new(
+                    spot,
+                    *strike,
+                    time_to_expiration,
+                    rfr,
+                    vola,
+                ))
+                .call()
+            })
+            .collect();
+
+        let disc_factor = (-rfr * time_to_expiration).exp();
+        let mut max_cdf: f64 = 0.0;
+        let (strikes, cdf) = grid
+            .windows(2)
+            .zip(call_prices.windows(2))
+            .map(|(k, c)| {
+                let slope = (c[1] - c[0]) / (k[1] - k[0]);
+                // enforce a valid cdf for smiles with (numerical) arbitrage
+                max_cdf = max_cdf.max((1.0 + slope / disc_factor).clamp(0.0, 1.0));
+                (0.5 * (k[0] + k[1]), max_cdf)
+            })
+            .unzip();
+
+        Self {
+            strikes,
+            cdf,
+            disc_factor,
+        }
+    }
+
+    /// The implied probability $P(S_T \le K)$, interpolated linearly between the grid strikes.
+    pub fn cdf(&self, strike: f64) -> f64 {
+        let idx = self.strikes.partition_point(|k| *k <= strike);
+        if idx == 0 {
+            return 0.0;
+        }
+        if idx == self.strikes.len() {
+            return 1.0;
+        }
+        let weight = (strike - self.strikes[idx - 1]) / (self.strikes[idx] - self.strikes[idx - 1]);
+        (1.0 - weight) * self.cdf[idx - 1] + weight * self.cdf[idx]
+    }
+
+    /// The terminal asset price of the probability level u in (0, 1), where the mass beyond the grid
+    /// is put on the first and last strike.
+    pub fn inverse_cdf(&self, u: f64) -> f64 {
+        let idx = self.cdf.partition_point(|p| *p < u);
+        if idx == 0 {
+            return self.strikes[0];
+        }
+        if idx == self.cdf.len() {
+            return self.strikes[idx - 1];
+        }
+        let (p0, p1) = (self.cdf[idx - 1], self.cdf[idx]);
+        let weight = if p1 > p0 { (u - p0) / (p1 - p0) } else { 1.0 };
+        (1.0 - weight) * self.strikes[idx - 1] + weight * self.strikes[idx]
+    }
+
+    /// The discounted average of the European `payoff` over `nr_samples` sampled terminal values.
+    pub fn present_value<SeedRng>(
+        &self,
+        payoff: impl Fn(f64) -> f64,
+        nr_samples: usize,
+        seed_nr: u64,
+    ) -> Option<f64>
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        if nr_samples == 0 {
+            return None;
+        }
+        let mut rn_generator = SeedRng::seed_from_u64(seed_nr);
+        let sum: f64 = (0..nr_samples)
+            .map(|_| payoff(rn_generator.sample(self)))
+            .sum();
+        Some(self.disc_factor * sum / nr_samples as f64)
+    }
+}
+
+impl Distribution<f64> for ImpliedDistribution {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        let u: f64 = rng.sample(Standard);
+        self.inverse_cdf(u)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use ndarray::arr2;
+
+    fn smile() -> VolatilitySurface {
+        VolatilitySurface::new(
+            vec![2.0],
+            vec![40.0, 100.0, 160.0],
+            arr2(&[[0.3, 0.22, 0.18]]),
+        )
+    }
+
+    #[test]
+    fn flat_vol_is_lognormal() {
+        let (spot, rfr, t, vola) = (100.0, 0.03, 1.0, 0.2);
+        let distribution =
+            ImpliedDistribution::new(&VolatilitySurface::flat(vola), spot, rfr, t, 2001, 8.0);
+
+        // P(S_T <= K) = N(-d2)
+        let dp = DerivativeParameter::new(spot, 110.0, t, rfr, vola);
+        let d2 = BsmComputation::new(&dp).d2;
+        assert_approx_eq!(
+            distribution.cdf(110.0),
+            1.0 - crate::numerics::normal::cdf(d2),
+            1e-4
+        );
+        assert_approx_eq!(distribution.inverse_cdf(distribution.cdf(95.0)), 95.0, 1e-6);
+
+        let price = distribution
+            .present_value::<rand_hc::Hc128Rng>(|s| (s - 110.0).max(0.0), 200_000, 42)
+            .unwrap();
+        assert_approx_eq!(price, BsmComputation::new(&dp).call(), 0.1);
+    }
+
+    #[test]
+    fn prices_consistent_with_smile() {
+        let (spot, rfr, t) = (100.0, 0.02, 1.0);
+        let surface = smile();
+        let distribution = ImpliedDistribution::new(&surface, spot, rfr, t, 2001, 8.0);
+
+        for strike in [80.0, 100.0, 115.0] {
+            let vola = surface.effective_vola(t, strike);
+            let expected =
+                BsmComputation::new(&DerivativeParameter::new(spot, strike, t, rfr, vola)).put();
+            let price = distribution
+                .present_value::<rand_hc::Hc128Rng>(|s| (strike - s).max(0.0), 200_000, 7)
+                .unwrap();
+            assert_approx_eq!(price, expected, 0.1);
+        }
+
+        // the forward is preserved
+        let forward = distribution
+            .present_value::<rand_hc::Hc128Rng>(|s| s, 200_000, 7)
+            .unwrap();
+        assert_approx_eq!(forward, spot, 0.2);
+    }
+}
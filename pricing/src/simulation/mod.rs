@@ -1,6 +1,38 @@
+pub mod checkpoint;
+pub mod common_random_numbers;
+#[cfg(feature = "multi-asset")]
+pub mod copula;
+#[cfg(feature = "multi-asset")]
+pub mod correlation_greeks;
 pub mod distributions;
+pub mod economic_scenarios;
+pub mod ensemble;
+pub mod exposure;
+pub mod gamma_scalping;
+pub mod global_sensitivity;
+pub mod greeks;
+pub mod implied_distribution;
+pub mod model_risk;
 pub mod monte_carlo;
+pub mod nested;
+pub mod noise;
+pub mod numeraire;
+pub mod path_construction;
+pub mod path_diagnostics;
+#[cfg(feature = "multi-asset")]
+pub mod path_layout;
+pub mod path_statistics;
+pub mod payoff_portfolio;
+pub mod payoff_script;
+pub mod payoff_smoothing;
+pub mod pipeline;
 pub mod products;
+pub mod references;
+pub mod rng_streams;
+pub mod scenarios;
 pub mod sde;
+pub mod state_space;
+pub mod verification;
 
 pub use monte_carlo::{PathEvaluator, PathGenerator};
+pub use pipeline::PathStage;
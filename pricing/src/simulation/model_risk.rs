@@ -0,0 +1,298 @@
+use std::fmt;
+
+use crate::common::results::{PriceResult, PricingError};
+use crate::simulation::monte_carlo::{MonteCarloPathSimulator, PathGenerator};
+use crate::simulation::PathEvaluator;
+
+/// The payoff of a path, e.g. at expiration or path-dependent, or None if the path has none.
+pub type PathPayoff<'a> = Box<dyn Fn(&[f64]) -> Option<f64> + 'a>;
+
+/// A vanilla the models are calibrated to, by its path payoff (at expiration) and market price.
+pub struct CalibrationInstrument<'a> {
+    pub name: String,
+    pub payoff: PathPayoff<'a>,
+    pub market_price: f64,
+}
+
+/// The paths of a calibrated model with the discount factor of the payoffs.
+struct SimulatedModel {
+    name: String,
+    paths: Vec<Vec<f64>>,
+    discount_factor: f64,
+}
+
+impl SimulatedModel {
+    fn price(&self, payoff: &dyn Fn(&[f64]) -> Option<f64>) -> Result<PriceResult, PricingError> {
+        PathEvaluator::new(&self.paths).price(|path| payoff(path).map(|p| p * self.discount_factor))
+    }
+}
+
+/// The price of the payoff under one model with the model's fit to the calibration instruments.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModelPrice {
+    pub model: String,
+    pub price: Result<PriceResult, PricingError>,
+    /// the model minus the market prices of the calibration instruments
+    pub calibration_errors: Vec<f64>,
+}
+
+impl ModelPrice {
+    /// The largest absolute error of the calibration instruments, which should be within the
+    /// sampling error for models matching the same vanilla surface.
+    pub fn max_calibration_error(&self) -> f64 {
+        self.calibration_errors
+            .iter()
+            .fold(0.0, |acc, e| acc.max(e.abs()))
+    }
+}
+
+/// The prices of a payoff under several models, whose dispersion quantifies the model risk.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModelRiskReport {
+    pub prices: Vec<ModelPrice>,
+}
+
+impl ModelRiskReport {
+    fn values(&self) -> Vec<f64> {
+        self.prices
+            .iter()
+            .filter_map(|p| p.price.as_ref().ok().map(|price| price.value))
+            .collect()
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        let values = self.values();
+        (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+    }
+
+    /// The difference of the highest and the lowest price, a common model reserve.
+    pub fn range(&self) -> Option<f64> {
+        let values = self.values();
+        let min = values.iter().copied().reduce(f64::min)?;
+        let max = values.iter().copied().reduce(f64::max)?;
+        Some(max - min)
+    }
+
+    /// The sample standard deviation of the prices across the models.
+    pub fn std_dev(&self) -> Option<f64> {
+        let values = self.values();
+        if values.len() < 2 {
+            return None;
+        }
+        let mean = self.mean()?;
+        let variance =
+            values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64;
+        Some(variance.sqrt())
+    }
+
+    pub fn price(&self, model: &str) -> Option<&ModelPrice> {
+        self.prices.iter().find(|p| p.model == model)
+    }
+}
+
+impl fmt::Display for ModelRiskReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for model_price in &self.prices {
+            let price = match &model_price.price {
+                Ok(price) => price.to_string(),
+                Err(error) => error.to_string(),
+            };
+            writeln!(
+                f,
+                "{:<24} {} (max calibration error {:.6})",
+                model_price.model,
+                price,
+                model_price.max_calibration_error()
+            )?;
+        }
+        if let (Some(mean), Some(range)) = (self.mean(), self.range()) {
+            writeln!(f, "mean {mean:.6}, range {range:.6}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Prices a payoff under several models calibrated to the same vanilla surface, e.g. Black-Scholes,
+/// stochastic or local volatility and jump diffusion dynamics, and reports the dispersion of the prices
+/// together with each model's fit to the calibration instruments.
+#[derive(Default)]
+pub struct ModelRiskComparison<'a> {
+    models: Vec<SimulatedModel>,
+    instruments: Vec<CalibrationInstrument<'a>>,
+}
+
+impl<'a> ModelRiskComparison<'a> {
+    pub fn new() -> Self {
+        Self {
+            models: Vec::new(),
+            instruments: Vec::new(),
+        }
+    }
+
+    /// A vanilla to check the calibration of the models against.
+    pub fn with_instrument(
+        mut self,
+        name: impl Into<String>,
+        payoff: impl Fn(&[f64]) -> Option<f64> + 'a,
+        market_price: f64,
+    ) -> Self {
+        self.instruments.push(CalibrationInstrument {
+            name: name.into(),
+            payoff: Box::new(payoff),
+            market_price,
+        });
+        self
+    }
+
+    /// A model by its simulated paths and the discount factor of the payoffs.
+    pub fn with_paths(
+        mut self,
+        name: impl Into<String>,
+        paths: Vec<Vec<f64>>,
+        discount_factor: f64,
+    ) -> Self {
+        self.models.push(SimulatedModel {
+            name: name.into(),
+            paths,
+            discount_factor,
+        });
+        self
+    }
+
+    /// A model by its calibrated path generator, simulated with the seed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_model<PathGen, SeedRng>(
+        self,
+        name: impl Into<String>,
+        generator: PathGen,
+        nr_paths: usize,
+        nr_steps: usize,
+        seed_nr: u64,
+        discount_factor: f64,
+    ) -> Self
+    where
+        PathGen: PathGenerator<Vec<f64>>,
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        let simulator: MonteCarloPathSimulator<PathGen, SeedRng, Vec<f64>> =
+            MonteCarloPathSimulator::new(generator, Some(seed_nr));
+        let paths = simulator.simulate_paths(nr_paths, nr_steps);
+        self.with_paths(name, paths, discount_factor)
+    }
+
+    pub fn instruments(&self) -> &[CalibrationInstrument<'a>] {
+        &self.instruments
+    }
+
+    /// The prices of the payoff under all models.
+    pub fn compare(&self, payoff: impl Fn(&[f64]) -> Option<f64>) -> ModelRiskReport {
+        let prices = self
+            .models
+            .iter()
+            .map(|model| ModelPrice {
+                model: model.name.clone(),
+                price: model.price(&payoff),
+                calibration_errors: self
+                    .instruments
+                    .iter()
+                    .map(|instrument| {
+                        model
+                            .price(&*instrument.payoff)
+                            .map_or(f64::NAN, |price| price.value - instrument.market_price)
+                    })
+                    .collect(),
+            })
+            .collect();
+        ModelRiskReport { prices }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::{BlackScholesMerton, OptionPrice, ShiftedBlack76};
+    use crate::common::models::{DerivativeParameter, ExerciseType};
+    use crate::numerics::solvers::{brent, SolverOptions};
+    use crate::simulation::sde::gbm::GeometricBrownianMotion;
+    use crate::simulation::sde::shifted_lognormal::ShiftedLognormalForward;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn digital_under_skewed_models() {
+        let (spot, maturity, vola, nr_steps) = (100.0, 1.0, 0.2, 50);
+        let dt = maturity / nr_steps as f64;
+        let atm_call =
+            BlackScholesMerton::call(&DerivativeParameter::new(spot, spot, maturity, 0.0, vola));
+        // the displaced diffusion with the vola matching the at-the-money call has a negative skew
+        let shift = 100.0;
+        let shifted_vola = brent(
+            |sigma| {
+                ShiftedBlack76::new(shift)
+                    .price(
+                        &DerivativeParameter::new(spot, spot, maturity, 0.0, sigma),
+                        &ExerciseType::Call,
+                    )
+                    .unwrap()
+                    - atm_call
+            },
+            0.01,
+            1.0,
+            &SolverOptions::default(),
+        )
+        .unwrap()
+        .x;
+
+        let call = |path: &[f64]| path.last().map(|s| (s - spot).max(0.0));
+        let comparison = ModelRiskComparison::new()
+            .with_instrument("atm call", call, atm_call)
+            .with_model::<_, rand_hc::Hc128Rng>(
+                "black-scholes",
+                GeometricBrownianMotion::new(spot, 0.0, vola, dt),
+                20_000,
+                nr_steps,
+                42,
+                1.0,
+            )
+            .with_model::<_, rand_hc::Hc128Rng>(
+                "displaced diffusion",
+                ShiftedLognormalForward::new(spot, shift, shifted_vola, dt).unwrap(),
+                20_000,
+                nr_steps,
+                42,
+                1.0,
+            );
+        assert_eq!(comparison.instruments().len(), 1);
+
+        // the digital call above the forward is dearer under the negative skew, as the call spread
+        // gains from the higher implied vola at the lower strike
+        let report = comparison.compare(|path| path.last().map(|s| (*s > 110.0) as u8 as f64));
+        for model_price in &report.prices {
+            assert!(model_price.max_calibration_error() < 0.2);
+        }
+        let black_scholes = report
+            .price("black-scholes")
+            .unwrap()
+            .price
+            .clone()
+            .unwrap();
+        let displaced = report
+            .price("displaced diffusion")
+            .unwrap()
+            .price
+            .clone()
+            .unwrap();
+        assert!(displaced.value > black_scholes.value + 0.005);
+        assert_approx_eq!(
+            report.range().unwrap(),
+            displaced.value - black_scholes.value,
+            1e-15
+        );
+        assert!(report.std_dev().unwrap() > 0.0);
+        assert!(report.to_string().contains("displaced diffusion"));
+
+        assert!(ModelRiskComparison::new()
+            .compare(|_| Some(1.0))
+            .range()
+            .is_none());
+    }
+}
@@ -1,6 +1,16 @@
 use rand::Rng;
+use std::cell::{Cell, RefCell};
 use std::marker::PhantomData;
 
+use crate::common::results::{NonFinitePolicy, PriceResult, PricingError};
+use crate::curves::yield_curve::YieldCurve;
+use crate::numerics::summation::Reproducibility;
+use crate::simulation::numeraire::{Numeraire, NumeraireConvention};
+use crate::simulation::path_diagnostics::{
+    DiagnosticsBuffer, DiagnosticsSampling, PathDiagnostics,
+};
+use crate::simulation::pipeline::{Identity, PathPipeline};
+
 // TODO: not yet used / required for later
 /// Models the dynamics of the asset(s) price.
 /// RandomPath represents the underlying random distribution,
@@ -9,11 +19,66 @@ pub trait Dynamics<Input, RandomPath, Path> {
     fn transform(&self, input: Input, rnd_path: RandomPath) -> Path;
 }
 
+/// Samples paths of `nr_samples` time steps. The SDE generators (e.g. GBM) include the initial value
+/// by default, i.e. their paths have `nr_samples + 1` values and index i is the value after i steps;
+/// use `with_initial_value_in_path(false)` for paths of the `nr_samples` values after each step.
 pub trait PathGenerator<Path> {
     fn sample_path<SeedRng>(&self, rn_generator: &mut SeedRng, nr_samples: usize) -> Path
     where
         SeedRng: rand::SeedableRng + rand::RngCore;
 }
+/// Whether the generation of a path continues after a step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepControl {
+    Continue,
+    /// the rest of the path is irrelevant, e.g. after a knock-out
+    Stop,
+}
+
+/// Decides after each step (counted from 1) on the value of the path whether to continue.
+pub trait StepController<Value> {
+    fn after_step(&self, step: usize, value: &Value) -> StepControl;
+}
+
+impl<Value, F> StepController<Value> for F
+where
+    F: Fn(usize, &Value) -> StepControl,
+{
+    fn after_step(&self, step: usize, value: &Value) -> StepControl {
+        self(step, value)
+    }
+}
+
+/// Path generators which stop a path early, such that the path ends with the value of the stopping step.
+/// The random numbers of the skipped steps are not drawn, hence the subsequent paths differ from the
+/// ones of `sample_path`, yet have the same distribution.
+pub trait ControlledPathGenerator<Path>: PathGenerator<Path> {
+    /// The value of the path after a step, e.g. the spot.
+    type StepValue;
+
+    fn sample_path_controlled<SeedRng>(
+        &self,
+        rn_generator: &mut SeedRng,
+        nr_samples: usize,
+        controller: &impl StepController<Self::StepValue>,
+    ) -> Path
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore;
+}
+
+/// Path generators which can be restarted from an intermediate state of the simulation,
+/// e.g. for nested simulations or re-pricing mid-life trades.
+pub trait WarmStart: Sized {
+    /// The state of the dynamics at an intermediate time, e.g. spot, vol and rate values.
+    type State;
+
+    /// The (constant) time step of the generated paths.
+    fn dt(&self) -> f64;
+
+    /// A generator of the same dynamics, started from the given state at time `t0` (in years).
+    fn warm_start(&self, t0: f64, state: &Self::State) -> Self;
+}
+
 /// Implementations for seedable_rng are for instance:
 /// rand_hc::Hc128Rng
 /// rand_isaac::Isaac64Rng
@@ -26,6 +91,8 @@ where
 {
     path_generator: PathGen,
     seed_nr: Option<u64>,
+    /// the number of 32-bit words discarded after seeding
+    warm_up: u64,
     _phantom_path: PhantomData<Path>,
     _phantom_rng: PhantomData<SeedRng>,
 }
@@ -39,20 +106,74 @@ where
         Self {
             path_generator,
             seed_nr,
+            warm_up: 0,
             _phantom_path: PhantomData::<Path>,
             _phantom_rng: PhantomData::<SeedRng>,
         }
     }
 
-    fn rn_generator(&self) -> SeedRng {
-        match self.seed_nr {
+    /// The simulator of the generator's steps up to the horizon, e.g. the time to expiration, or the
+    /// invalid parameter: a non-positive or non-finite horizon or step, or a step beyond the horizon.
+    pub fn try_new(
+        path_generator: PathGen,
+        seed_nr: Option<u64>,
+        horizon: f64,
+    ) -> Result<Self, PricingError>
+    where
+        PathGen: WarmStart,
+    {
+        PricingError::check(horizon > 0.0 && horizon.is_finite(), "horizon", horizon)?;
+        let dt = path_generator.dt();
+        PricingError::check(dt > 0.0 && dt <= horizon, "dt", dt)?;
+        Ok(Self::new(path_generator, seed_nr))
+    }
+
+    /// Discards the number of 32-bit words of the generator after seeding.
+    pub fn with_warm_up(self, nr_words: u64) -> Self {
+        Self {
+            warm_up: nr_words,
+            ..self
+        }
+    }
+
+    pub(crate) fn path_generator(&self) -> &PathGen {
+        &self.path_generator
+    }
+
+    pub(crate) fn rn_generator(&self) -> SeedRng {
+        let mut rn_generator = match self.seed_nr {
             Some(seed_nr) => SeedRng::seed_from_u64(seed_nr),
             None => {
                 let random_seed =
                     rand::thread_rng().sample(rand_distr::Uniform::new(0u64, 100_000));
                 SeedRng::seed_from_u64(random_seed)
             }
+        };
+        for _ in 0..self.warm_up {
+            rn_generator.next_u32();
         }
+        rn_generator
+    }
+
+    /// The generator at the start of the simulation after the warm-up, if seeded. Its state is exported
+    /// and imported by serde for the generators implementing it, e.g. ChaCha and Isaac with their
+    /// `serde1` features, such that a simulation continues the same stream in another process.
+    pub fn rng_state(&self) -> Option<SeedRng> {
+        self.seed_nr.map(|_| self.rn_generator())
+    }
+
+    /// Samples the paths continuing the random number stream of the generator, which is advanced past
+    /// the paths, such that a simulation split into several calls, e.g. across process restarts with the
+    /// serialized generator, samples the same paths as a single call.
+    pub fn simulate_paths_continued(
+        &self,
+        generator: &mut SeedRng,
+        nr_paths: usize,
+        nr_steps: usize,
+    ) -> Vec<Path> {
+        (0..nr_paths)
+            .map(|_| self.path_generator.sample_path(generator, nr_steps))
+            .collect()
     }
 
     pub fn simulate_paths(&self, nr_paths: usize, nr_steps: usize) -> Vec<Path> {
@@ -66,21 +187,34 @@ where
         paths
     }
 
+    /// Samples the paths, each until the controller stops it or for `nr_steps` steps.
+    pub fn simulate_paths_controlled(
+        &self,
+        nr_paths: usize,
+        nr_steps: usize,
+        controller: &impl StepController<PathGen::StepValue>,
+    ) -> Vec<Path>
+    where
+        PathGen: ControlledPathGenerator<Path>,
+    {
+        let mut generator = self.rn_generator();
+        (0..nr_paths)
+            .map(|_| {
+                self.path_generator
+                    .sample_path_controlled(&mut generator, nr_steps, controller)
+            })
+            .collect()
+    }
+
     pub fn simulate_paths_with(
         &self,
         nr_paths: usize,
         nr_steps: usize,
         path_fn: impl Fn(&Path) -> Path,
     ) -> Vec<Path> {
-        let mut paths = Vec::with_capacity(nr_paths);
-        let mut generator = self.rn_generator();
-
-        for _ in 0..nr_paths {
-            let path = self.path_generator.sample_path(&mut generator, nr_steps);
-            let v = path_fn(&path);
-            paths.push(v);
-        }
-        paths
+        self.pipe()
+            .then(|path: Path| path_fn(&path))
+            .simulate(nr_paths, nr_steps)
     }
 
     pub fn simulate_paths_apply_in_place(
@@ -89,45 +223,285 @@ where
         nr_steps: usize,
         apply_in_place_fn: impl Fn(&mut Path),
     ) -> Vec<Path> {
-        let mut paths = Vec::with_capacity(nr_paths);
-        let mut generator = self.rn_generator();
+        self.pipe()
+            .then(|mut path: Path| {
+                apply_in_place_fn(&mut path);
+                path
+            })
+            .simulate(nr_paths, nr_steps)
+    }
 
-        for _ in 0..nr_paths {
-            let mut path = self.path_generator.sample_path(&mut generator, nr_steps);
-            apply_in_place_fn(&mut path);
-            paths.push(path);
-        }
-        paths
+    /// Simulate paths started from `state` at time `t0` (in years) until `maturity`,
+    /// keeping the time step of the path generator, or the invalid parameter: a negative or non-finite
+    /// `t0`, a non-finite maturity not after `t0`, or a remaining time shorter than half a step.
+    pub fn simulate_paths_from(
+        &self,
+        t0: f64,
+        state: &PathGen::State,
+        nr_paths: usize,
+        maturity: f64,
+    ) -> Result<Vec<Path>, PricingError>
+    where
+        PathGen: WarmStart,
+    {
+        PricingError::check(t0 >= 0.0 && t0.is_finite(), "t0", t0)?;
+        PricingError::check(maturity > t0 && maturity.is_finite(), "maturity", maturity)?;
+        let nr_steps = ((maturity - t0) / self.path_generator.dt()).round();
+        PricingError::check(nr_steps >= 1.0, "nr steps", nr_steps)?;
+
+        let warm_simulator: MonteCarloPathSimulator<PathGen, SeedRng, Path> =
+            MonteCarloPathSimulator::new(self.path_generator.warm_start(t0, state), self.seed_nr)
+                .with_warm_up(self.warm_up);
+        Ok(warm_simulator.simulate_paths(nr_paths, nr_steps as usize))
+    }
+
+    /// Start a pipeline of composable stages on the sampled paths, see `PathPipeline`.
+    pub fn pipe(&self) -> PathPipeline<'_, PathGen, SeedRng, Path, Identity> {
+        PathPipeline::new(self, Identity)
+    }
+}
+
+/// The handling of paths without a payoff (None) in the evaluation. NaN or infinite payoffs are
+/// handled by the `NonFinitePolicy` of the evaluator.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MissingPayoffPolicy {
+    /// the paths contribute 0 to the average over all paths, e.g. for a knocked-out barrier
+    #[default]
+    TreatAsZero,
+    /// the average is over the paths with a payoff only
+    SkipAndRenormalize,
+    /// any such path fails the evaluation
+    Error,
+}
+
+/// The average of the path values with the number of paths whose value is missing, NaN or infinite.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PathAverage {
+    pub value: f64,
+    pub nr_paths: usize,
+    /// the number of paths without a value
+    pub nr_missing: usize,
+    /// the number of paths with a NaN or infinite value, quarantined from the average
+    pub nr_nan: usize,
+}
+
+impl PathAverage {
+    /// The number of paths without a valid value.
+    pub fn nr_invalid(&self) -> usize {
+        self.nr_missing + self.nr_nan
+    }
+}
+
+/// The cashflows paid along a path, e.g. coupons and an autocall redemption, each discounted with the
+/// curve's discount factor to its own payment time.
+pub struct PathCashflows<'c, C: YieldCurve> {
+    curve: &'c C,
+    value: f64,
+}
+
+impl<'c, C: YieldCurve> PathCashflows<'c, C> {
+    pub fn new(curve: &'c C) -> Self {
+        Self { curve, value: 0.0 }
+    }
+
+    /// Pays the amount at the time (in years).
+    pub fn pay(&mut self, amount: f64, payment_time: f64) {
+        self.value += amount * self.curve.discount_factor(payment_time);
+    }
+
+    /// The present value of the cashflows paid so far.
+    pub fn value(&self) -> f64 {
+        self.value
     }
 }
 
 pub struct PathEvaluator<'a, Path> {
     paths: &'a [Path],
+    reproducibility: Reproducibility,
+    missing_payoff_policy: MissingPayoffPolicy,
+    non_finite_policy: NonFinitePolicy,
 }
 
 impl<'a, Path> PathEvaluator<'a, Path> {
     pub fn new(paths: &'a [Path]) -> Self {
-        Self { paths }
+        Self {
+            paths,
+            reproducibility: Reproducibility::default(),
+            missing_payoff_policy: MissingPayoffPolicy::default(),
+            non_finite_policy: NonFinitePolicy::default(),
+        }
+    }
+
+    /// The summation of the path values, by default sequential in the order of the paths.
+    pub fn with_reproducibility(self, reproducibility: Reproducibility) -> Self {
+        Self {
+            reproducibility,
+            ..self
+        }
+    }
+
+    /// The handling of missing path values, by default as zeros.
+    pub fn with_missing_payoff_policy(self, missing_payoff_policy: MissingPayoffPolicy) -> Self {
+        Self {
+            missing_payoff_policy,
+            ..self
+        }
+    }
+
+    /// The handling of NaN or infinite path values, which by default fail the evaluation.
+    pub fn with_non_finite_policy(self, non_finite_policy: NonFinitePolicy) -> Self {
+        Self {
+            non_finite_policy,
+            ..self
+        }
     }
 
     pub fn apply(&self, path_fn: impl Fn(&Path) -> Option<f64>) -> Vec<Option<f64>> {
         self.paths.iter().map(path_fn).collect()
     }
 
-    pub fn evaluate_average(&self, path_fn: impl Fn(&Path) -> Option<f64>) -> Option<f64> {
+    /// The price of the path values with its standard error and confidence interval, where the missing
+    /// payoff policy decides on paths without a value and the non-finite policy on NaN or infinite
+    /// values, see `PriceResult::from_samples_with_policy`.
+    pub fn price(
+        &self,
+        path_fn: impl Fn(&Path) -> Option<f64>,
+    ) -> Result<PriceResult, PricingError> {
         if self.paths.is_empty() {
-            return None;
+            return Err(PricingError::NoPaths);
         }
-        if let Some(total) = self.paths.iter().fold(None, |acc, path| {
-            if let Some(path_value) = path_fn(path) {
-                Some(acc.unwrap_or(0.0) + path_value)
-            } else {
-                acc
+        let payoffs = self.apply(path_fn);
+        let nr_missing = payoffs.iter().filter(|payoff| payoff.is_none()).count();
+        if nr_missing == payoffs.len() {
+            return Err(PricingError::NoPayoff);
+        }
+        let samples: Vec<f64> = match self.missing_payoff_policy {
+            MissingPayoffPolicy::Error if nr_missing > 0 => {
+                return Err(PricingError::InvalidPayoffs(nr_missing))
             }
-        }) {
-            return Some(total / self.paths.len() as f64);
+            MissingPayoffPolicy::SkipAndRenormalize => payoffs.into_iter().flatten().collect(),
+            _ => payoffs.iter().map(|p| p.unwrap_or_default()).collect(),
         };
-        None
+        PriceResult::from_samples_reproducible(
+            &samples,
+            self.non_finite_policy,
+            self.reproducibility,
+        )
+    }
+
+    /// The average of the path values according to the missing payoff and the non-finite policies,
+    /// which fails if there are no paths or no path has a valid value. Quarantined NaN or infinite
+    /// values are excluded from the average like skipped paths.
+    pub fn evaluate(
+        &self,
+        path_fn: impl Fn(&Path) -> Option<f64>,
+    ) -> Result<PathAverage, PricingError> {
+        if self.paths.is_empty() {
+            return Err(PricingError::NoPaths);
+        }
+        let (mut nr_missing, mut nr_nan) = (0, 0);
+        let mut first_non_finite = None;
+        let path_values: Vec<f64> = self
+            .paths
+            .iter()
+            .filter_map(|path| match path_fn(path) {
+                None => {
+                    nr_missing += 1;
+                    None
+                }
+                Some(value) if !value.is_finite() => {
+                    nr_nan += 1;
+                    first_non_finite.get_or_insert(value);
+                    None
+                }
+                value => value,
+            })
+            .collect();
+        if let Some(value) = first_non_finite {
+            match self.non_finite_policy {
+                NonFinitePolicy::Fail => return Err(PricingError::NonFiniteValue(value)),
+                NonFinitePolicy::Quarantine { max_fraction }
+                    if nr_nan as f64 > max_fraction * self.paths.len() as f64 =>
+                {
+                    return Err(PricingError::InvalidPayoffs(nr_nan))
+                }
+                NonFinitePolicy::Quarantine { .. } => {}
+            }
+        }
+        if path_values.is_empty() {
+            return Err(PricingError::NoPayoff);
+        }
+        let nr_averaged = match self.missing_payoff_policy {
+            MissingPayoffPolicy::Error if nr_missing > 0 => {
+                return Err(PricingError::InvalidPayoffs(nr_missing))
+            }
+            MissingPayoffPolicy::SkipAndRenormalize => path_values.len(),
+            _ => self.paths.len() - nr_nan,
+        };
+        Ok(PathAverage {
+            value: self.reproducibility.sum(&path_values) / nr_averaged as f64,
+            nr_paths: self.paths.len(),
+            nr_missing,
+            nr_nan,
+        })
+    }
+
+    /// The average of the path values according to the policies, or None if it fails or quarantines
+    /// NaN or infinite values, whose number only `evaluate` reports.
+    pub fn evaluate_average(&self, path_fn: impl Fn(&Path) -> Option<f64>) -> Option<f64> {
+        self.evaluate(path_fn)
+            .ok()
+            .filter(|average| average.nr_nan == 0)
+            .map(|average| average.value)
+    }
+
+    /// The average of the path values as by `evaluate`, where `path_fn` records intermediate quantities
+    /// of the sampled paths via `PathDiagnostics`, e.g. the breach step of a barrier. The diagnostics
+    /// are returned also if the evaluation fails, e.g. to inspect why no path has a payoff.
+    pub fn evaluate_with_diagnostics(
+        &self,
+        sampling: &DiagnosticsSampling,
+        path_fn: impl Fn(&Path, &mut PathDiagnostics<'_>) -> Option<f64>,
+    ) -> (Result<PathAverage, PricingError>, DiagnosticsBuffer) {
+        let buffer = RefCell::new(DiagnosticsBuffer::default());
+        let path_index = Cell::new(0);
+        let average = self.evaluate(|path| {
+            let index = path_index.replace(path_index.get() + 1);
+            let sampled = sampling.is_sampled(index);
+            let mut buffer = buffer.borrow_mut();
+            buffer.nr_sampled_paths += usize::from(sampled);
+            let mut diagnostics =
+                PathDiagnostics::new(index, sampled.then_some(&mut buffer.records));
+            path_fn(path, &mut diagnostics)
+        });
+        (average, buffer.into_inner())
+    }
+
+    /// The present value of the (undiscounted) payoffs paid at `payment_time`,
+    /// converted by the numeraire and payoff currency of the convention.
+    pub fn evaluate_present_value<N: Numeraire>(
+        &self,
+        convention: &NumeraireConvention<N>,
+        payment_time: f64,
+        path_fn: impl Fn(&Path) -> Option<f64>,
+    ) -> Option<f64> {
+        let payoff_factor = convention.payoff_factor(payment_time);
+        self.evaluate_average(|path| path_fn(path).map(|payoff| payoff * payoff_factor))
+    }
+
+    /// The average present value of the cashflows paid along the paths by `cashflow_fn` via
+    /// `PathCashflows::pay`, discounted on the curve to their payment times. A path paying nothing is worth 0.
+    /// Cheap discount factors for many payment times are given by a `LogLinearDiscountCurve`.
+    pub fn evaluate_cashflows<C: YieldCurve>(
+        &self,
+        curve: &C,
+        cashflow_fn: impl Fn(&Path, &mut PathCashflows<'_, C>),
+    ) -> Result<PathAverage, PricingError> {
+        self.evaluate(|path| {
+            let mut cashflows = PathCashflows::new(curve);
+            cashflow_fn(path, &mut cashflows);
+            Some(cashflows.value())
+        })
     }
 }
 
@@ -136,7 +510,8 @@ mod tests {
     use std::vec;
 
     use super::*;
-    use crate::simulation::sde::gbm::GeometricBrownianMotion;
+    use crate::simulation::numeraire::MoneyMarketAccount;
+    use crate::simulation::sde::gbm::{GbmState, GeometricBrownianMotion};
     use rand_distr::{Normal, StandardNormal};
 
     use assert_approx_eq::assert_approx_eq;
@@ -144,6 +519,60 @@ mod tests {
     /// NOTE: the tolerance will depend on the number of samples paths and other params like steps and the volatility
     const TOLERANCE: f64 = 1e-1;
 
+    #[test]
+    fn cashflows_discounted_to_payment_dates() {
+        use crate::common::time_grid::TimeGrid;
+        use crate::curves::yield_curve::InterpolatedCurve;
+        use crate::numerics::interpolation::InterpolationMethod;
+        use crate::numerics::normal::cdf;
+
+        let curve = InterpolatedCurve::new(
+            vec![1.0, 2.0, 3.0],
+            vec![0.01, 0.03, 0.05],
+            InterpolationMethod::LogLinear,
+        );
+        let (s0, vola, barrier) = (100.0, 0.2, 95.0);
+        let grid = TimeGrid::uniform(3.0, 36);
+        let gbm = GeometricBrownianMotion::new(s0, 0.0, vola, 1.0 / 12.0);
+        let mc_simulator: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, Vec<f64>> =
+            MonteCarloPathSimulator::new(StandardNormal, Some(42));
+        let paths = mc_simulator.simulate_paths_with(20_000, 36, |normals| {
+            gbm.generate_path_on_curve(s0, &grid, &curve, normals)
+        });
+        let evaluator = PathEvaluator::new(&paths);
+
+        // the discounted spot is a martingale on each payment date
+        let spot_strip = evaluator
+            .evaluate_cashflows(&curve, |path, cashflows| {
+                for year in 1..=3 {
+                    cashflows.pay(path[12 * year], year as f64);
+                }
+            })
+            .unwrap();
+        assert_approx_eq!(spot_strip.value, 3.0 * s0, 3.0);
+
+        // annual digital coupons of 5 above the barrier
+        let coupons = evaluator
+            .evaluate_cashflows(&curve, |path, cashflows| {
+                for year in 1..=3 {
+                    if path[12 * year] > barrier {
+                        cashflows.pay(5.0, year as f64);
+                    }
+                }
+            })
+            .unwrap();
+        let exact: f64 = (1..=3)
+            .map(|year| {
+                let t = year as f64;
+                let df = curve.discount_factor(t);
+                let d2 =
+                    ((s0 / barrier).ln() - df.ln() - 0.5 * vola * vola * t) / (vola * t.sqrt());
+                5.0 * df * cdf(d2)
+            })
+            .sum();
+        assert_approx_eq!(coupons.value, exact, 0.05);
+    }
+
     #[test]
     fn normal_path_simulation() {
         let sampler: Normal<f64> = Normal::new(0.5, 1.0).unwrap();
@@ -216,6 +645,160 @@ mod tests {
         assert_approx_eq!(avg_delta.unwrap(), exp_delta, TOLERANCE);
     }
 
+    #[test]
+    fn warm_started_stock_price_simulation() {
+        let (s0, drift, vola, dt) = (100.0, 0.05, 0.3, 0.01);
+        let stock_gbm = GeometricBrownianMotion::new(s0, drift, vola, dt);
+        let mc_simulator: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, Vec<f64>> =
+            MonteCarloPathSimulator::new(stock_gbm, Some(42));
+
+        let state = GbmState::new(120.0, None, Some(0.2));
+        let paths = mc_simulator
+            .simulate_paths_from(0.5, &state, 10, 1.0)
+            .unwrap();
+        assert_eq!(paths.len(), 10);
+        assert_eq!(paths[0].len(), 51);
+        assert_eq!(paths[0][0], 120.0);
+
+        // equals a fresh simulation of the restarted dynamics over the remaining time
+        let restarted_gbm = GeometricBrownianMotion::new(120.0, drift, 0.2, dt);
+        let fresh_simulator: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, Vec<f64>> =
+            MonteCarloPathSimulator::new(restarted_gbm, Some(42));
+        assert_eq!(paths, fresh_simulator.simulate_paths(10, 50));
+
+        // the warm-up of the generator carries over to the warm-started paths
+        let warmed_up = MonteCarloPathSimulator::<_, rand_hc::Hc128Rng, Vec<f64>>::new(
+            GeometricBrownianMotion::new(s0, drift, vola, dt),
+            Some(42),
+        )
+        .with_warm_up(7);
+        let from_start = warmed_up
+            .simulate_paths_from(0.0, &GbmState::new(s0, None, None), 10, 1.0)
+            .unwrap();
+        assert_eq!(from_start, warmed_up.simulate_paths(10, 100));
+        assert_ne!(from_start, mc_simulator.simulate_paths(10, 100));
+
+        // invalid start times and remaining times shorter than half a step
+        for (t0, maturity) in [
+            (-0.1, 1.0),
+            (f64::NAN, 1.0),
+            (0.5, 0.4),
+            (0.5, f64::INFINITY),
+        ] {
+            assert!(matches!(
+                mc_simulator.simulate_paths_from(t0, &state, 10, maturity),
+                Err(PricingError::InvalidParameter(_))
+            ));
+        }
+        assert!(mc_simulator
+            .simulate_paths_from(0.5, &state, 10, 0.504)
+            .is_err());
+        assert_eq!(
+            mc_simulator
+                .simulate_paths_from(0.5, &state, 10, 0.506)
+                .unwrap()[0]
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn validated_simulator() {
+        let simulator = |dt: f64, horizon: f64| {
+            MonteCarloPathSimulator::<_, rand_hc::Hc128Rng, Vec<f64>>::try_new(
+                GeometricBrownianMotion::new(100.0, 0.02, 0.3, dt),
+                Some(1),
+                horizon,
+            )
+        };
+        assert!(simulator(0.01, 1.0).is_ok());
+        assert!(simulator(1.0, 1.0).is_ok());
+        for (dt, horizon) in [(2.0, 1.0), (0.0, 1.0), (f64::NAN, 1.0), (0.01, 0.0)] {
+            assert!(matches!(
+                simulator(dt, horizon),
+                Err(PricingError::InvalidParameter(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn simulation_continued_from_exported_rng_state() {
+        let gbm = GeometricBrownianMotion::new(100.0, 0.02, 0.3, 0.01);
+        let mc_simulator: MonteCarloPathSimulator<_, rand_chacha::ChaCha20Rng, Vec<f64>> =
+            MonteCarloPathSimulator::new(gbm.clone(), Some(7)).with_warm_up(1_000);
+        let paths = mc_simulator.simulate_paths(30, 20);
+
+        // split across a restart which persists the generator as JSON
+        let mut generator = mc_simulator.rng_state().unwrap();
+        let mut split = mc_simulator.simulate_paths_continued(&mut generator, 12, 20);
+        let json = serde_json::to_string(&generator).unwrap();
+        let mut restored: rand_chacha::ChaCha20Rng = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, generator);
+        split.extend(mc_simulator.simulate_paths_continued(&mut restored, 18, 20));
+        assert_eq!(split, paths);
+
+        let cold: MonteCarloPathSimulator<_, rand_chacha::ChaCha20Rng, Vec<f64>> =
+            MonteCarloPathSimulator::new(gbm.clone(), Some(7));
+        assert_ne!(cold.simulate_paths(1, 20), paths[..1]);
+        let unseeded: MonteCarloPathSimulator<_, rand_chacha::ChaCha20Rng, Vec<f64>> =
+            MonteCarloPathSimulator::new(gbm, None);
+        assert!(unseeded.rng_state().is_none());
+    }
+
+    #[test]
+    fn path_eval_with_diagnostics() {
+        // a down-and-out call recording the breach step and the terminal spot
+        let paths: Vec<Vec<f64>> = (0..100)
+            .map(|i| vec![100.0, 95.0 + (i % 10) as f64, 90.0 + (i % 20) as f64])
+            .collect();
+        let knock_out_call = |path: &Vec<f64>, diagnostics: &mut PathDiagnostics<'_>| {
+            if let Some(step) = path.iter().position(|s| *s < 97.0) {
+                diagnostics.record_at("breach", step, path[step]);
+                return None;
+            }
+            diagnostics.record("terminal", path[2]);
+            Some((path[2] - 100.0).max(0.0))
+        };
+        let evaluator = PathEvaluator::new(&paths);
+        let (average, buffer) =
+            evaluator.evaluate_with_diagnostics(&DiagnosticsSampling::new(7, 10), knock_out_call);
+        assert_eq!(
+            average.unwrap(),
+            evaluator
+                .evaluate(|path| knock_out_call(path, &mut PathDiagnostics::new(0, None)))
+                .unwrap()
+        );
+        assert_eq!(buffer.nr_sampled_paths, 10);
+        assert_eq!(buffer.records.len(), 10);
+        assert!(buffer
+            .records
+            .iter()
+            .all(|r| r.path % 7 == 0 && r.path < 70));
+        assert!(buffer.with_label("breach").all(|r| r.step.is_some()));
+        assert_eq!(buffer.of_path(14).next().unwrap().label, "terminal");
+        assert!(buffer.to_string().contains("breach"));
+
+        // the diagnostics show why no path pays
+        let (average, buffer) = evaluator
+            .evaluate_with_diagnostics(&DiagnosticsSampling::new(1, 100), |path, diagnostics| {
+                knock_out_call(path, diagnostics).filter(|_| false)
+            });
+        assert!(average.is_err());
+        assert_eq!(
+            buffer.values("breach").len() + buffer.values("terminal").len(),
+            100
+        );
+    }
+
+    #[test]
+    fn path_eval_present_value() {
+        let paths = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
+        let path_eval = PathEvaluator::new(&paths);
+        let convention = NumeraireConvention::domestic(MoneyMarketAccount::new(0.05));
+        let pv = path_eval.evaluate_present_value(&convention, 2.0, |path| path.last().cloned());
+        assert_approx_eq!(pv.unwrap(), 3.0 * (-0.1_f64).exp(), 1e-12);
+    }
+
     #[test]
     fn path_eval() {
         let paths = vec![vec![1.0, 2.0], vec![3.0, 4.0], vec![]];
@@ -229,4 +812,98 @@ mod tests {
         let avg = path_eval.evaluate_average(|path| path.last().cloned());
         assert_eq!(avg.unwrap(), (2.0 + 4.0) / 3.0);
     }
+
+    #[test]
+    fn path_eval_missing_and_nan_payoffs() {
+        let paths = vec![vec![1.0, 2.0], vec![3.0, f64::NAN], vec![], vec![5.0, 6.0]];
+        let last = |path: &Vec<f64>| path.last().cloned();
+
+        // the NaN value fails the evaluation by default
+        assert!(matches!(
+            PathEvaluator::new(&paths).evaluate(last),
+            Err(PricingError::NonFiniteValue(value)) if value.is_nan()
+        ));
+        assert!(PathEvaluator::new(&paths).price(last).is_err());
+
+        let quarantining = PathEvaluator::new(&paths)
+            .with_non_finite_policy(NonFinitePolicy::Quarantine { max_fraction: 0.25 });
+        let average = quarantining.evaluate(last).unwrap();
+        assert_eq!(average.value, (2.0 + 6.0) / 3.0);
+        assert_eq!((average.nr_missing, average.nr_nan), (1, 1));
+        assert_eq!(average.nr_invalid(), 2);
+        // the average alone would hide the quarantined value
+        assert_eq!(quarantining.evaluate_average(last), None);
+        let price = quarantining.price(last).unwrap();
+        assert_eq!(price.value, average.value);
+        assert_eq!(price.quarantine.unwrap().first_index, 1);
+        assert_eq!(
+            quarantining
+                .with_non_finite_policy(NonFinitePolicy::Quarantine { max_fraction: 0.1 })
+                .evaluate(last),
+            Err(PricingError::InvalidPayoffs(1))
+        );
+
+        let renormalized = PathEvaluator::new(&paths)
+            .with_non_finite_policy(NonFinitePolicy::Quarantine { max_fraction: 0.25 })
+            .with_missing_payoff_policy(MissingPayoffPolicy::SkipAndRenormalize)
+            .evaluate(last)
+            .unwrap();
+        assert_eq!(renormalized.value, (2.0 + 6.0) / 2.0);
+
+        let strict =
+            PathEvaluator::new(&paths).with_missing_payoff_policy(MissingPayoffPolicy::Error);
+        assert_eq!(
+            strict.evaluate(|path| path.first().cloned()),
+            Err(PricingError::InvalidPayoffs(1))
+        );
+        assert_eq!(strict.evaluate_average(|path| path.first().cloned()), None);
+        assert_eq!(
+            strict.price(|path| path.first().cloned()),
+            Err(PricingError::InvalidPayoffs(1))
+        );
+        assert_eq!(
+            PathEvaluator::new(&paths)
+                .price(|path| path.first().cloned())
+                .unwrap()
+                .value,
+            (1.0 + 3.0 + 5.0) / 4.0
+        );
+
+        assert_eq!(
+            PathEvaluator::new(&paths).evaluate(|_| None),
+            Err(PricingError::NoPayoff)
+        );
+        let no_paths: Vec<Vec<f64>> = vec![];
+        assert_eq!(
+            PathEvaluator::new(&no_paths).evaluate(last),
+            Err(PricingError::NoPaths)
+        );
+    }
+
+    #[test]
+    fn path_eval_reproducibility() {
+        let gbm = GeometricBrownianMotion::new(100.0, 0.02, 0.3, 0.01);
+        let simulator: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, Vec<f64>> =
+            MonteCarloPathSimulator::new(gbm, Some(7));
+        let paths = simulator.simulate_paths(5_000, 10);
+        let average = |reproducibility| {
+            PathEvaluator::new(&paths)
+                .with_reproducibility(reproducibility)
+                .evaluate_average(|path| path.last().cloned())
+                .unwrap()
+        };
+        let sequential = average(Reproducibility::Sequential);
+        assert_eq!(
+            sequential,
+            PathEvaluator::new(&paths)
+                .evaluate_average(|path| path.last().cloned())
+                .unwrap()
+        );
+        for reproducibility in [Reproducibility::Compensated, Reproducibility::Pairwise] {
+            let value = average(reproducibility);
+            assert_eq!(value.to_bits(), average(reproducibility).to_bits());
+            assert_approx_eq!(value, sequential, 1e-9);
+        }
+        assert_approx_eq!(average(Reproducibility::Fastest), sequential, 1e-9);
+    }
 }
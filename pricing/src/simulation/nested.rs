@@ -0,0 +1,275 @@
+use std::marker::PhantomData;
+
+use crate::common::results::PricingError;
+use crate::numerics::least_squares::{least_squares, polynomial_basis, polynomial_value};
+use crate::simulation::monte_carlo::{MonteCarloPathSimulator, PathGenerator, WarmStart};
+use crate::simulation::PathEvaluator;
+use ndarray::Array1;
+
+/// Replaces the inner simulations by a regression: inner simulations are run for the first
+/// `nr_training_paths` outer scenarios only, and the future values of all scenarios are
+/// estimated by a polynomial of the given degree in a (scalar) regressor of the outer paths.
+#[derive(Clone, Copy, Debug)]
+pub struct RegressionProxy {
+    pub nr_training_paths: usize,
+    pub degree: usize,
+}
+
+impl RegressionProxy {
+    /// The proxy, or the invalid parameter: no more training paths than the degree, too few to fit
+    /// the polynomial.
+    pub fn new(nr_training_paths: usize, degree: usize) -> Result<Self, PricingError> {
+        PricingError::check(
+            nr_training_paths > degree,
+            "number of training paths",
+            nr_training_paths as f64,
+        )?;
+        Ok(Self {
+            nr_training_paths,
+            degree,
+        })
+    }
+}
+
+/// Nested Monte Carlo simulation: outer (real-world) scenarios until the `horizon`,
+/// and inner (risk-neutral) pricing simulations from each outer scenario until `maturity`.
+/// The result is the distribution of future values at the horizon, e.g. for PFE or capital figures.
+pub struct NestedMonteCarloSimulator<OuterGen, InnerGen, SeedRng, Path>
+where
+    OuterGen: PathGenerator<Path> + WarmStart,
+    InnerGen: PathGenerator<Path> + WarmStart,
+    SeedRng: rand::SeedableRng + rand::RngCore,
+{
+    outer_generator: OuterGen,
+    inner_generator: InnerGen,
+    /// the future date (in years) at which the values are simulated
+    horizon: f64,
+    /// the maturity (in years) of the product priced in the inner simulations
+    maturity: f64,
+    nr_outer_paths: usize,
+    nr_inner_paths: usize,
+    seed_nr: u64,
+    _phantom_path: PhantomData<Path>,
+    _phantom_rng: PhantomData<SeedRng>,
+}
+
+impl<OuterGen, InnerGen, SeedRng, Path> NestedMonteCarloSimulator<OuterGen, InnerGen, SeedRng, Path>
+where
+    OuterGen: PathGenerator<Path> + WarmStart + Clone,
+    InnerGen: PathGenerator<Path> + WarmStart,
+    SeedRng: rand::SeedableRng + rand::RngCore,
+{
+    /// The nested simulator, or the invalid parameter: a negative or non-finite horizon, or a
+    /// non-finite maturity before the horizon.
+    pub fn new(
+        outer_generator: OuterGen,
+        inner_generator: InnerGen,
+        horizon: f64,
+        maturity: f64,
+        nr_outer_paths: usize,
+        nr_inner_paths: usize,
+        seed_nr: u64,
+    ) -> Result<Self, PricingError> {
+        PricingError::check(horizon >= 0.0 && horizon.is_finite(), "horizon", horizon)?;
+        PricingError::check(
+            maturity >= horizon && maturity.is_finite(),
+            "maturity",
+            maturity,
+        )?;
+        Ok(Self {
+            outer_generator,
+            inner_generator,
+            horizon,
+            maturity,
+            nr_outer_paths,
+            nr_inner_paths,
+            seed_nr,
+            _phantom_path: PhantomData::<Path>,
+            _phantom_rng: PhantomData::<SeedRng>,
+        })
+    }
+
+    /// The outer scenarios until the horizon.
+    pub fn outer_paths(&self) -> Vec<Path> {
+        let nr_steps = (self.horizon / self.outer_generator.dt()).round() as usize;
+        let simulator: MonteCarloPathSimulator<OuterGen, SeedRng, Path> =
+            MonteCarloPathSimulator::new(self.outer_generator.clone(), Some(self.seed_nr));
+        simulator.simulate_paths(self.nr_outer_paths, nr_steps)
+    }
+
+    /// The inner (risk-neutral) value at the horizon for the state of an outer scenario.
+    /// Each scenario uses its own seed, so values do not depend on the evaluation order.
+    fn inner_value(
+        &self,
+        scenario_idx: usize,
+        state: &InnerGen::State,
+        payoff: &impl Fn(&Path) -> Option<f64>,
+    ) -> Option<f64> {
+        let nr_steps =
+            ((self.maturity - self.horizon) / self.inner_generator.dt()).round() as usize;
+        let seed_nr = self.seed_nr.wrapping_add(1 + scenario_idx as u64);
+        let simulator: MonteCarloPathSimulator<InnerGen, SeedRng, Path> =
+            MonteCarloPathSimulator::new(
+                self.inner_generator.warm_start(self.horizon, state),
+                Some(seed_nr),
+            );
+        let paths = simulator.simulate_paths(self.nr_inner_paths, nr_steps);
+        PathEvaluator::new(&paths).evaluate_average(payoff)
+    }
+
+    /// The future values at the horizon per outer scenario, where `state_fn` maps an outer path to the
+    /// state the inner simulation starts from and `payoff` is discounted to the horizon.
+    pub fn future_values(
+        &self,
+        state_fn: impl Fn(&Path) -> InnerGen::State,
+        payoff: impl Fn(&Path) -> Option<f64>,
+    ) -> Vec<Option<f64>> {
+        self.outer_paths()
+            .iter()
+            .enumerate()
+            .map(|(idx, outer_path)| self.inner_value(idx, &state_fn(outer_path), &payoff))
+            .collect()
+    }
+
+    /// The future values at the horizon per outer scenario, estimated by the regression `proxy`
+    /// on the `regressor` (e.g. the spot at the horizon) of the outer paths.
+    /// Returns None if the regression can not be solved.
+    pub fn future_values_with_proxy(
+        &self,
+        state_fn: impl Fn(&Path) -> InnerGen::State,
+        regressor: impl Fn(&Path) -> f64,
+        payoff: impl Fn(&Path) -> Option<f64>,
+        proxy: &RegressionProxy,
+    ) -> Option<Vec<f64>> {
+        let outer_paths = self.outer_paths();
+        let regressors: Vec<f64> = outer_paths.iter().map(&regressor).collect();
+
+        let (training_x, training_y): (Vec<f64>, Vec<f64>) = outer_paths
+            .iter()
+            .enumerate()
+            .take(proxy.nr_training_paths)
+            .filter_map(|(idx, outer_path)| {
+                self.inner_value(idx, &state_fn(outer_path), &payoff)
+                    .map(|value| (regressors[idx], value))
+            })
+            .unzip();
+
+        // standardize the regressor for a well conditioned polynomial basis
+        let n = training_x.len() as f64;
+        let mean = training_x.iter().sum::<f64>() / n;
+        let std = (training_x.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n).sqrt();
+        let scale = if std > 0.0 { std } else { 1.0 };
+        let standardize = |x: &f64| (x - mean) / scale;
+
+        let standardized: Vec<f64> = training_x.iter().map(standardize).collect();
+        let coefficients = least_squares(
+            &polynomial_basis(&standardized, proxy.degree),
+            &Array1::from(training_y),
+        )?;
+
+        Some(
+            regressors
+                .iter()
+                .map(|x| polynomial_value(&coefficients, standardize(x)))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::{BlackScholesMerton, OptionPrice};
+    use crate::common::models::DerivativeParameter;
+    use crate::simulation::sde::gbm::{GbmState, GeometricBrownianMotion};
+    use assert_approx_eq::assert_approx_eq;
+
+    const TOLERANCE: f64 = 0.5;
+
+    fn nested_simulator() -> NestedMonteCarloSimulator<
+        GeometricBrownianMotion,
+        GeometricBrownianMotion,
+        rand_hc::Hc128Rng,
+        Vec<f64>,
+    > {
+        let real_world_gbm = GeometricBrownianMotion::new(100.0, 0.08, 0.2, 0.05);
+        let risk_neutral_gbm = GeometricBrownianMotion::new(100.0, 0.03, 0.2, 0.05);
+        NestedMonteCarloSimulator::new(real_world_gbm, risk_neutral_gbm, 0.5, 1.0, 20, 5_000, 42)
+            .unwrap()
+    }
+
+    fn analytic_call(spot: f64) -> f64 {
+        BlackScholesMerton::call(&DerivativeParameter::new(spot, 100.0, 0.5, 0.03, 0.2))
+    }
+
+    #[test]
+    fn nested_future_values() {
+        let simulator = nested_simulator();
+        let disc_factor = (-0.03_f64 * 0.5).exp();
+        let values = simulator.future_values(
+            |path| GbmState::new(*path.last().unwrap(), None, None),
+            |path| path.last().map(|s| (s - 100.0).max(0.0) * disc_factor),
+        );
+        assert_eq!(values.len(), 20);
+
+        for (outer_path, value) in simulator.outer_paths().iter().zip(values) {
+            let spot = *outer_path.last().unwrap();
+            assert_approx_eq!(value.unwrap(), analytic_call(spot), TOLERANCE);
+        }
+    }
+
+    #[test]
+    fn invalid_times() {
+        let gbm = GeometricBrownianMotion::new(100.0, 0.03, 0.2, 0.05);
+        for (horizon, maturity) in [
+            (-0.5, 1.0),
+            (f64::NAN, 1.0),
+            (0.5, 0.4),
+            (0.5, f64::INFINITY),
+        ] {
+            assert!(matches!(
+                NestedMonteCarloSimulator::<_, _, rand_hc::Hc128Rng, Vec<f64>>::new(
+                    gbm.clone(),
+                    gbm.clone(),
+                    horizon,
+                    maturity,
+                    20,
+                    100,
+                    42
+                ),
+                Err(PricingError::InvalidParameter(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn invalid_regression_proxy() {
+        assert!(RegressionProxy::new(4, 3).is_ok());
+        for nr_training_paths in [0, 2, 3] {
+            assert!(matches!(
+                RegressionProxy::new(nr_training_paths, 3),
+                Err(PricingError::InvalidParameter(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn nested_future_values_with_proxy() {
+        let simulator = nested_simulator();
+        let disc_factor = (-0.03_f64 * 0.5).exp();
+        let values = simulator
+            .future_values_with_proxy(
+                |path| GbmState::new(*path.last().unwrap(), None, None),
+                |path| *path.last().unwrap(),
+                |path| path.last().map(|s| (s - 100.0).max(0.0) * disc_factor),
+                &RegressionProxy::new(10, 3).unwrap(),
+            )
+            .unwrap();
+        assert_eq!(values.len(), 20);
+
+        for (outer_path, value) in simulator.outer_paths().iter().zip(values) {
+            let spot = *outer_path.last().unwrap();
+            assert_approx_eq!(value, analytic_call(spot), 2.0 * TOLERANCE);
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::cell::{Cell, RefCell};
+
+use rand_distr::StandardNormal;
+
+use crate::common::results::PricingError;
+use crate::simulation::monte_carlo::PathGenerator;
+
+/// The standard normals driving a simulation: drawn from the simulator's seeded random number generator,
+/// or supplied externally, e.g. recorded in a production run or generated by another library, to replay
+/// runs and to regress against other implementations. As path generator it samples `nr_samples` normals
+/// per path, which are transformed by the stages of a pipeline, e.g.
+/// ''' MonteCarloPathSimulator::new(NoiseSource::from_slice(&normals), None).pipe().then(gbm) '''
+pub enum NoiseSource<'a> {
+    /// drawn from the random number generator of the simulator
+    Generated,
+    /// the normals of the slice in order, path after path
+    FromSlice {
+        normals: &'a [f64],
+        consumed: Cell<usize>,
+    },
+    /// the normals of the iterator in order, path after path
+    FromIter(RefCell<Box<dyn Iterator<Item = f64> + 'a>>),
+}
+
+impl<'a> NoiseSource<'a> {
+    pub fn from_slice(normals: &'a [f64]) -> Self {
+        NoiseSource::FromSlice {
+            normals,
+            consumed: Cell::new(0),
+        }
+    }
+
+    pub fn from_iterator(normals: impl IntoIterator<Item = f64> + 'a) -> Self {
+        NoiseSource::FromIter(RefCell::new(Box::new(normals.into_iter())))
+    }
+
+    /// The number of normals left to sample, known for slices only.
+    pub fn remaining(&self) -> Option<usize> {
+        match self {
+            NoiseSource::FromSlice { normals, consumed } => Some(normals.len() - consumed.get()),
+            _ => None,
+        }
+    }
+
+    /// Checks that a slice holds the normals of `nr_paths` paths of `nr_steps` steps.
+    pub fn check_available(&self, nr_paths: usize, nr_steps: usize) -> Result<(), PricingError> {
+        match self.remaining() {
+            Some(remaining) if remaining < nr_paths * nr_steps => {
+                Err(PricingError::InvalidParameter(format!(
+                    "{remaining} normals for {nr_paths} paths of {nr_steps} steps"
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The paths of supplied normals are shorter than `nr_samples` once the normals are exhausted.
+impl PathGenerator<Vec<f64>> for NoiseSource<'_> {
+    fn sample_path<SeedRng>(&self, rn_generator: &mut SeedRng, nr_samples: usize) -> Vec<f64>
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        match self {
+            NoiseSource::Generated => StandardNormal.sample_path(rn_generator, nr_samples),
+            NoiseSource::FromSlice { normals, consumed } => {
+                let start = consumed.get();
+                let end = (start + nr_samples).min(normals.len());
+                consumed.set(end);
+                normals[start..end].to_vec()
+            }
+            NoiseSource::FromIter(normals) => {
+                normals.borrow_mut().by_ref().take(nr_samples).collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::monte_carlo::MonteCarloPathSimulator;
+    use crate::simulation::sde::gbm::GeometricBrownianMotion;
+
+    #[test]
+    fn replayed_normals() {
+        let gbm = GeometricBrownianMotion::new(100.0, 0.02, 0.2, 0.1);
+        let generated: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, _> =
+            MonteCarloPathSimulator::new(NoiseSource::Generated, Some(7));
+        let normals = generated.simulate_paths(3, 10).concat();
+        let paths = generated.pipe().then(gbm.clone()).simulate(3, 10);
+
+        // the recorded normals reproduce the paths, as slice or iterator
+        let replayed: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, _> =
+            MonteCarloPathSimulator::new(NoiseSource::from_slice(&normals), None);
+        assert_eq!(replayed.pipe().then(gbm.clone()).simulate(3, 10), paths);
+        let iterated: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, _> =
+            MonteCarloPathSimulator::new(NoiseSource::from_iterator(normals.clone()), None);
+        assert_eq!(iterated.pipe().then(gbm).simulate(3, 10), paths);
+
+        let source = NoiseSource::from_slice(&normals);
+        assert!(source.check_available(3, 10).is_ok());
+        assert!(source.check_available(4, 10).is_err());
+        let exhausted: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, _> =
+            MonteCarloPathSimulator::new(source, None);
+        let paths = exhausted.simulate_paths(4, 8);
+        assert_eq!(paths[3].len(), 30 - 3 * 8);
+        assert_eq!(NoiseSource::Generated.remaining(), None);
+    }
+}
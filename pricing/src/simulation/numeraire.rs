@@ -0,0 +1,143 @@
+/// The numeraire $N$ of the pricing measure, such that the price of a payoff $X$ paid at $T$ is
+/// '''math
+/// V_0 = N_0 E^N[X / N_T]
+/// '''
+/// See https://en.wikipedia.org/wiki/Num%C3%A9raire
+pub trait Numeraire {
+    /// The value of the numeraire at time t (in years).
+    fn value(&self, t: f64) -> f64;
+
+    /// The factor $N_0 / N_T$ converting payoffs paid at $T$ into today's value.
+    fn deflator(&self, payment_time: f64) -> f64 {
+        self.value(0.0) / self.value(payment_time)
+    }
+}
+
+/// The money market (bank) account $B_t = e^{rt}$ of the risk neutral measure.
+#[derive(Clone, Copy, Debug)]
+pub struct MoneyMarketAccount {
+    rate: f64,
+}
+
+impl MoneyMarketAccount {
+    pub fn new(rate: f64) -> Self {
+        Self { rate }
+    }
+}
+
+impl Numeraire for MoneyMarketAccount {
+    fn value(&self, t: f64) -> f64 {
+        (self.rate * t).exp()
+    }
+
+    fn deflator(&self, payment_time: f64) -> f64 {
+        (-self.rate * payment_time).exp()
+    }
+}
+
+/// The zero-coupon bond $P(t, T) = e^{-r(T-t)}$ maturing at $T$, the numeraire of the T-forward measure,
+/// e.g. for caplets paying at $T$.
+#[derive(Clone, Copy, Debug)]
+pub struct ZeroCouponBond {
+    rate: f64,
+    maturity: f64,
+}
+
+impl ZeroCouponBond {
+    pub fn new(rate: f64, maturity: f64) -> Self {
+        Self { rate, maturity }
+    }
+}
+
+impl Numeraire for ZeroCouponBond {
+    fn value(&self, t: f64) -> f64 {
+        (-self.rate * (self.maturity - t)).exp()
+    }
+}
+
+/// The currency of the payoffs relative to the (domestic) currency of the numeraire.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PayoffCurrency {
+    /// the payoff is paid in the currency of the numeraire
+    Domestic,
+    /// the payoff in units of the foreign currency is paid in domestic currency at the fixed `fx_rate`
+    /// (domestic per foreign), as for quantos; the quanto drift adjustment belongs to the dynamics
+    Quanto { fx_rate: f64 },
+    /// the payoff is paid in foreign currency and converted at today's `fx_spot` (domestic per foreign);
+    /// the numeraire is then expected to be the one of the foreign currency
+    Foreign { fx_spot: f64 },
+}
+
+impl PayoffCurrency {
+    /// The factor converting the value in payoff units to the domestic currency.
+    pub fn conversion_factor(&self) -> f64 {
+        match self {
+            PayoffCurrency::Domestic => 1.0,
+            PayoffCurrency::Quanto { fx_rate } => *fx_rate,
+            PayoffCurrency::Foreign { fx_spot } => *fx_spot,
+        }
+    }
+}
+
+/// Prices payoffs paid at `payment_time` consistently with the given numeraire and payoff currency.
+#[derive(Clone, Debug)]
+pub struct NumeraireConvention<N: Numeraire> {
+    numeraire: N,
+    payoff_currency: PayoffCurrency,
+}
+
+impl<N: Numeraire> NumeraireConvention<N> {
+    pub fn new(numeraire: N, payoff_currency: PayoffCurrency) -> Self {
+        Self {
+            numeraire,
+            payoff_currency,
+        }
+    }
+
+    pub fn domestic(numeraire: N) -> Self {
+        Self::new(numeraire, PayoffCurrency::Domestic)
+    }
+
+    /// The factor converting an (undiscounted) payoff paid at `payment_time` into today's domestic value.
+    pub fn payoff_factor(&self, payment_time: f64) -> f64 {
+        self.numeraire.deflator(payment_time) * self.payoff_currency.conversion_factor()
+    }
+
+    /// Today's value from the expectation of the payoff under the numeraire's measure.
+    pub fn present_value(&self, expected_payoff: f64, payment_time: f64) -> f64 {
+        expected_payoff * self.payoff_factor(payment_time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn deflators() {
+        let bank_account = MoneyMarketAccount::new(0.05);
+        assert_eq!(bank_account.value(0.0), 1.0);
+        assert_approx_eq!(bank_account.deflator(2.0), (-0.1_f64).exp(), 1e-15);
+
+        // under the T-forward measure, the deflator of a payment at T is the discount factor P(0, T)
+        let bond = ZeroCouponBond::new(0.05, 2.0);
+        assert_eq!(bond.value(2.0), 1.0);
+        assert_approx_eq!(bond.deflator(2.0), bank_account.deflator(2.0), 1e-15);
+    }
+
+    #[test]
+    fn quanto_conversion() {
+        let convention = NumeraireConvention::new(
+            MoneyMarketAccount::new(0.02),
+            PayoffCurrency::Quanto { fx_rate: 1.1 },
+        );
+        assert_approx_eq!(
+            convention.present_value(10.0, 1.0),
+            10.0 * 1.1 * (-0.02_f64).exp(),
+            1e-12
+        );
+        let domestic = NumeraireConvention::domestic(MoneyMarketAccount::new(0.02));
+        assert_eq!(domestic.payoff_factor(0.0), 1.0);
+    }
+}
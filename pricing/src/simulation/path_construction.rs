@@ -0,0 +1,143 @@
+use ndarray::{Array1, Array2};
+use rand_distr::StandardNormal;
+
+use crate::numerics::pca::symmetric_eigen;
+use crate::simulation::monte_carlo::PathGenerator;
+
+/// Principal component construction of Brownian paths on an equidistant time grid:
+/// with the eigen-decomposition $C = V \Lambda V^T$ of the covariance $C_{ij} = \min(t_i, t_j)$
+/// of the Brownian motion at the grid points $t_i = i$ (in units of the time step),
+/// '''math
+/// W = V \Lambda^{1/2} z
+/// '''
+/// for i.i.d. standard normals z, where the first normals carry most of the variance.
+/// The increments of W are again i.i.d. standard normals, such that the construction can replace the
+/// normals of any path generator, and it reduces the effective dimension for (quasi) Monte Carlo.
+/// See Glasserman, Monte Carlo Methods in Financial Engineering, section 3.1.
+#[derive(Clone, Debug)]
+pub struct PcaPathConstruction {
+    /// the eigenvalues in decreasing order
+    eigenvalues: Array1<f64>,
+    /// the columns $\sqrt{\lambda_k} v_k$
+    factors: Array2<f64>,
+}
+
+impl PcaPathConstruction {
+    pub fn new(nr_steps: usize) -> Self {
+        let covariance =
+            Array2::from_shape_fn((nr_steps, nr_steps), |(i, j)| (i.min(j) + 1) as f64);
+        let (eigenvalues, eigenvectors) = symmetric_eigen(&covariance);
+        let eigenvalues = eigenvalues.mapv(|e| e.max(0.0));
+        let factors = &eigenvectors * &eigenvalues.mapv(f64::sqrt);
+        Self {
+            eigenvalues,
+            factors,
+        }
+    }
+
+    pub fn nr_steps(&self) -> usize {
+        self.eigenvalues.len()
+    }
+
+    /// The share of the total variance explained by each principal component.
+    pub fn explained_variance_ratio(&self) -> Array1<f64> {
+        &self.eigenvalues / self.eigenvalues.sum()
+    }
+
+    /// The (standardized) Brownian increments constructed from the standard normals.
+    pub fn increments(&self, standard_normals: &[f64]) -> Vec<f64> {
+        assert_eq!(standard_normals.len(), self.nr_steps());
+        let levels = self.factors.dot(&Array1::from(standard_normals.to_vec()));
+        let mut prev = 0.0;
+        levels
+            .iter()
+            .map(|w| {
+                let increment = w - prev;
+                prev = *w;
+                increment
+            })
+            .collect()
+    }
+
+    /// The construction applied to each row of a matrix of standard normals, e.g. the factors (rows)
+    /// by time steps (columns) of multivariate paths, before they are correlated.
+    pub fn increments_matrix(&self, standard_normals: &Array2<f64>) -> Array2<f64> {
+        let mut increments = standard_normals.to_owned();
+        for mut row in increments.rows_mut() {
+            let constructed = self.increments(&row.to_vec());
+            row.assign(&Array1::from(constructed));
+        }
+        increments
+    }
+}
+
+impl PathGenerator<Vec<f64>> for PcaPathConstruction {
+    /// The PCA constructed increments of `nr_samples` (equal to the number of steps) standard normals.
+    fn sample_path<SeedRng>(&self, rn_generator: &mut SeedRng, nr_samples: usize) -> Vec<f64>
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        self.increments(&StandardNormal.sample_path(rn_generator, nr_samples))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::BsmComputation;
+    use crate::common::models::DerivativeParameter;
+    use crate::simulation::sde::gbm::GeometricBrownianMotion;
+    use assert_approx_eq::assert_approx_eq;
+    use rand::SeedableRng;
+
+    #[test]
+    fn reproduces_brownian_covariance() {
+        let construction = PcaPathConstruction::new(16);
+        let covariance = construction.factors.dot(&construction.factors.t());
+        for i in 0..16 {
+            for j in 0..16 {
+                assert_approx_eq!(covariance[[i, j]], (i.min(j) + 1) as f64, 1e-9);
+            }
+        }
+        // the first component dominates, roughly 81% for fine grids
+        let ratios = construction.explained_variance_ratio();
+        assert!(ratios[0] > 0.8 && ratios[0] > 8.0 * ratios[1]);
+
+        // the unit vectors map to the (scaled) eigenvectors
+        let mut z = vec![0.0; 16];
+        z[0] = 1.0;
+        let increments = construction.increments(&z);
+        assert_approx_eq!(
+            increments.iter().sum::<f64>(),
+            construction.factors[[15, 0]],
+            1e-12
+        );
+
+        let matrix =
+            Array2::from_shape_fn((2, 16), |(i, j)| if i == 0 && j == 0 { 1.0 } else { 0.0 });
+        let rows = construction.increments_matrix(&matrix);
+        assert_eq!(rows.row(0).to_vec(), increments);
+        assert!(rows.row(1).iter().all(|x| *x == 0.0));
+    }
+
+    #[test]
+    fn gbm_call_with_pca_increments() {
+        let (spot, strike, t, rfr, vola, nr_steps) = (100.0, 105.0, 1.0, 0.03, 0.25, 32);
+        let construction = PcaPathConstruction::new(nr_steps);
+        let gbm = GeometricBrownianMotion::new(spot, rfr, vola, t / nr_steps as f64);
+        let mut rn_generator = rand_hc::Hc128Rng::seed_from_u64(42);
+
+        let nr_paths = 20_000;
+        let sum: f64 = (0..nr_paths)
+            .map(|_| {
+                let normals = construction.sample_path(&mut rn_generator, nr_steps);
+                let path = gbm.generate_path(spot, &normals);
+                (path.last().unwrap() - strike).max(0.0)
+            })
+            .sum();
+        let price = (-rfr * t).exp() * sum / nr_paths as f64;
+
+        let dp = DerivativeParameter::new(spot, strike, t, rfr, vola);
+        assert_approx_eq!(price, BsmComputation::new(&dp).call(), 0.3);
+    }
+}
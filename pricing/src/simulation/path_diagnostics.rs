@@ -0,0 +1,119 @@
+use std::fmt;
+
+/// An intermediate quantity recorded by a payoff on a path, e.g. the step of a barrier breach,
+/// an exercise decision or the running average of an Asian option.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiagnosticRecord {
+    /// the index of the path
+    pub path: usize,
+    pub label: &'static str,
+    /// the step of the path the quantity refers to, if any
+    pub step: Option<usize>,
+    pub value: f64,
+}
+
+/// Which paths record their diagnostics: every nth path, up to a maximal number of paths.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DiagnosticsSampling {
+    pub every_nth: usize,
+    pub max_nr_paths: usize,
+}
+
+impl DiagnosticsSampling {
+    pub fn new(every_nth: usize, max_nr_paths: usize) -> Self {
+        assert!(every_nth > 0);
+        Self {
+            every_nth,
+            max_nr_paths,
+        }
+    }
+
+    /// Whether the path of the index records its diagnostics.
+    pub fn is_sampled(&self, path: usize) -> bool {
+        path.is_multiple_of(self.every_nth) && path / self.every_nth < self.max_nr_paths
+    }
+}
+
+/// The recorder of the diagnostics of a path handed to the payoff, which ignores the records of
+/// paths that are not sampled, such that the payoff records unconditionally at little cost.
+pub struct PathDiagnostics<'b> {
+    path: usize,
+    records: Option<&'b mut Vec<DiagnosticRecord>>,
+}
+
+impl<'b> PathDiagnostics<'b> {
+    /// The recorder of the path into the records, or ignoring the records without, e.g. to evaluate
+    /// a payoff with diagnostics outside of `PathEvaluator::evaluate_with_diagnostics`.
+    pub fn new(path: usize, records: Option<&'b mut Vec<DiagnosticRecord>>) -> Self {
+        Self { path, records }
+    }
+
+    /// Whether the path is sampled, e.g. to skip computing expensive diagnostics otherwise.
+    pub fn is_recording(&self) -> bool {
+        self.records.is_some()
+    }
+
+    pub fn record(&mut self, label: &'static str, value: f64) {
+        self.push(label, None, value);
+    }
+
+    /// Records the quantity at the step of the path.
+    pub fn record_at(&mut self, label: &'static str, step: usize, value: f64) {
+        self.push(label, Some(step), value);
+    }
+
+    fn push(&mut self, label: &'static str, step: Option<usize>, value: f64) {
+        if let Some(records) = self.records.as_mut() {
+            records.push(DiagnosticRecord {
+                path: self.path,
+                label,
+                step,
+                value,
+            });
+        }
+    }
+}
+
+/// The diagnostics recorded on the sampled paths, in the order of the paths and the records.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DiagnosticsBuffer {
+    pub records: Vec<DiagnosticRecord>,
+    pub nr_sampled_paths: usize,
+}
+
+impl DiagnosticsBuffer {
+    pub fn with_label<'a>(
+        &'a self,
+        label: &'a str,
+    ) -> impl Iterator<Item = &'a DiagnosticRecord> + 'a {
+        self.records.iter().filter(move |r| r.label == label)
+    }
+
+    pub fn of_path(&self, path: usize) -> impl Iterator<Item = &DiagnosticRecord> {
+        self.records.iter().filter(move |r| r.path == path)
+    }
+
+    /// The values of the label, e.g. to histogram the breach steps.
+    pub fn values(&self, label: &str) -> Vec<f64> {
+        self.with_label(label).map(|r| r.value).collect()
+    }
+}
+
+impl fmt::Display for DiagnosticsBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>8} {:<24} {:>6} {:>14}",
+            "path", "label", "step", "value"
+        )?;
+        for record in &self.records {
+            let step = record.step.map_or("-".to_string(), |step| step.to_string());
+            writeln!(
+                f,
+                "{:>8} {:<24} {:>6} {:>14.6}",
+                record.path, record.label, step, record.value
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,261 @@
+use ndarray::{Array1, Array2, ArrayView1, Axis};
+
+use crate::common::models::ExerciseType;
+use crate::simulation::distributions::MultivariateNormalDistribution;
+use crate::simulation::monte_carlo::{PathEvaluator, PathGenerator};
+use crate::simulation::sde::multivariate_gbm::MultivariateGeometricBrownianMotion;
+
+/// Multivariate path with one row per asset and one column per time, i.e. (dim x nr_times),
+/// as generated by `transform_path`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssetMajorPath(Array2<f64>);
+
+/// Multivariate path with one row per time and one column per asset, i.e. (nr_times x dim).
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeMajorPath(Array2<f64>);
+
+impl AssetMajorPath {
+    pub fn new(values: Array2<f64>) -> Self {
+        Self(values)
+    }
+
+    pub fn values(&self) -> &Array2<f64> {
+        &self.0
+    }
+
+    pub fn nr_assets(&self) -> usize {
+        self.0.nrows()
+    }
+
+    pub fn nr_times(&self) -> usize {
+        self.0.ncols()
+    }
+
+    /// The values of the asset over time.
+    pub fn asset(&self, asset_idx: usize) -> ArrayView1<'_, f64> {
+        self.0.row(asset_idx)
+    }
+
+    /// The values of all assets at the time index.
+    pub fn at(&self, time_idx: usize) -> ArrayView1<'_, f64> {
+        self.0.column(time_idx)
+    }
+
+    pub fn terminal(&self) -> ArrayView1<'_, f64> {
+        self.at(self.nr_times() - 1)
+    }
+
+    pub fn to_time_major(&self) -> TimeMajorPath {
+        TimeMajorPath(self.0.t().to_owned())
+    }
+
+    /// The time average $\frac{1}{n} \sum_t \sum_i w_i S_i(t)$ of the weighted basket over the times
+    /// from the time index on, in one pass over the contiguous rows (the weighted sum of the assets' sums).
+    pub fn basket_time_average(&self, weights: &Array1<f64>, from_time_idx: usize) -> f64 {
+        assert_eq!(weights.len(), self.nr_assets());
+        assert!(from_time_idx < self.nr_times());
+        let total: f64 = self
+            .0
+            .outer_iter()
+            .zip(weights)
+            .map(|(asset, w)| w * asset.iter().skip(from_time_idx).sum::<f64>())
+            .sum();
+        total / (self.nr_times() - from_time_idx) as f64
+    }
+
+    /// The performances $S_i(t) / S_i(0)$ of the worst asset at each time.
+    pub fn worst_performances(&self) -> Array1<f64> {
+        let mut worst = Array1::from_elem(self.nr_times(), f64::INFINITY);
+        for asset in self.0.outer_iter() {
+            let initial = asset[0];
+            worst.zip_mut_with(&asset, |w, s| *w = w.min(s / initial));
+        }
+        worst
+    }
+
+    /// The running minimum over the times of the worst performance, i.e. $min_{s \le t} min_i S_i(s) / S_i(0)$.
+    pub fn worst_of_running_minimum(&self) -> Array1<f64> {
+        let mut minimum = self.worst_performances();
+        minimum.accumulate_axis_inplace(Axis(0), |previous, current| {
+            *current = current.min(*previous)
+        });
+        minimum
+    }
+
+    /// The minimum of the performances of all assets at all times, e.g. to monitor a worst-of barrier.
+    pub fn worst_of_minimum(&self) -> f64 {
+        self.0
+            .outer_iter()
+            .map(|asset| {
+                let initial = asset[0];
+                asset.fold(f64::INFINITY, |acc, s| acc.min(s / initial))
+            })
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
+impl PathEvaluator<'_, AssetMajorPath> {
+    /// The discounted payoffs of the Asian option on the time average of the weighted basket,
+    /// averaged over the times after the initial one.
+    pub fn asian_basket_payoffs(
+        &self,
+        weights: &Array1<f64>,
+        strike: f64,
+        exercise_type: &ExerciseType,
+        disc_factor: f64,
+    ) -> Vec<Option<f64>> {
+        self.apply(|path| {
+            let average = path.basket_time_average(weights, 1.min(path.nr_times() - 1));
+            Some(disc_factor * exercise_type.payoff(average, strike))
+        })
+    }
+
+    /// The minimum worst-of performance of each path.
+    pub fn worst_of_minima(&self) -> Vec<f64> {
+        self.apply(|path| Some(path.worst_of_minimum()))
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+}
+
+impl TimeMajorPath {
+    pub fn new(values: Array2<f64>) -> Self {
+        Self(values)
+    }
+
+    /// The path of the samples of all assets per time.
+    pub fn from_samples(samples: &[Array1<f64>]) -> Self {
+        let views: Vec<ArrayView1<f64>> = samples.iter().map(|s| s.view()).collect();
+        Self(ndarray::stack(Axis(0), &views).unwrap())
+    }
+
+    pub fn values(&self) -> &Array2<f64> {
+        &self.0
+    }
+
+    pub fn nr_assets(&self) -> usize {
+        self.0.ncols()
+    }
+
+    pub fn nr_times(&self) -> usize {
+        self.0.nrows()
+    }
+
+    /// The values of the asset over time.
+    pub fn asset(&self, asset_idx: usize) -> ArrayView1<'_, f64> {
+        self.0.column(asset_idx)
+    }
+
+    /// The values of all assets at the time index.
+    pub fn at(&self, time_idx: usize) -> ArrayView1<'_, f64> {
+        self.0.row(time_idx)
+    }
+
+    pub fn terminal(&self) -> ArrayView1<'_, f64> {
+        self.at(self.nr_times() - 1)
+    }
+
+    pub fn to_asset_major(&self) -> AssetMajorPath {
+        AssetMajorPath(self.0.t().to_owned())
+    }
+}
+
+impl PathGenerator<AssetMajorPath> for MultivariateGeometricBrownianMotion {
+    fn sample_path<SeedRng>(&self, rn_generator: &mut SeedRng, nr_samples: usize) -> AssetMajorPath
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        AssetMajorPath(PathGenerator::<Array2<f64>>::sample_path(
+            self,
+            rn_generator,
+            nr_samples,
+        ))
+    }
+}
+
+impl PathGenerator<TimeMajorPath> for MultivariateGeometricBrownianMotion {
+    fn sample_path<SeedRng>(&self, rn_generator: &mut SeedRng, nr_samples: usize) -> TimeMajorPath
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        let samples: Vec<Array1<f64>> = self.sample_path(rn_generator, nr_samples);
+        TimeMajorPath::from_samples(&samples)
+    }
+}
+
+impl PathGenerator<AssetMajorPath> for MultivariateNormalDistribution {
+    fn sample_path<SeedRng>(&self, rn_generator: &mut SeedRng, nr_samples: usize) -> AssetMajorPath
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        AssetMajorPath(PathGenerator::<Array2<f64>>::sample_path(
+            self,
+            rn_generator,
+            nr_samples,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::{arr1, arr2};
+    use rand::SeedableRng;
+
+    #[test]
+    fn layouts_agree() {
+        let mv_gbm = MultivariateGeometricBrownianMotion::new(
+            arr1(&[100.0, 50.0, 10.0]),
+            arr1(&[0.01, 0.02, 0.03]),
+            arr2(&[[0.2, 0.0, 0.0], [0.1, 0.2, 0.0], [0.0, 0.1, 0.3]]),
+            0.1,
+        );
+        let asset_major: AssetMajorPath =
+            mv_gbm.sample_path(&mut rand_hc::Hc128Rng::seed_from_u64(1), 5);
+        assert_eq!(asset_major.nr_assets(), 3);
+        assert_eq!(asset_major.nr_times(), 6);
+        assert_eq!(asset_major.asset(1)[0], 50.0);
+        assert_eq!(asset_major.at(0), arr1(&[100.0, 50.0, 10.0]));
+
+        let time_major = asset_major.to_time_major();
+        assert_eq!(time_major.terminal(), asset_major.terminal());
+        assert_eq!(time_major.asset(2), asset_major.asset(2));
+        assert_eq!(time_major.to_asset_major(), asset_major);
+
+        let time_major: TimeMajorPath =
+            mv_gbm.sample_path(&mut rand_hc::Hc128Rng::seed_from_u64(1), 5);
+        assert_eq!(time_major.nr_assets(), 3);
+        assert_eq!(time_major.at(0), arr1(&[100.0, 50.0, 10.0]));
+
+        let mv_gbm = mv_gbm.with_initial_value_in_path(false);
+        let steps: AssetMajorPath = mv_gbm.sample_path(&mut rand_hc::Hc128Rng::seed_from_u64(1), 5);
+        assert_eq!(steps.nr_times(), 5);
+        assert_eq!(steps.terminal(), asset_major.terminal());
+        let steps: TimeMajorPath = mv_gbm.sample_path(&mut rand_hc::Hc128Rng::seed_from_u64(1), 5);
+        assert_eq!(steps.nr_times(), 5);
+    }
+
+    #[test]
+    fn multi_asset_functionals() {
+        let path = AssetMajorPath::new(arr2(&[
+            [100.0, 110.0, 90.0, 105.0],
+            [50.0, 45.0, 55.0, 40.0],
+        ]));
+        let weights = arr1(&[0.5, 0.5]);
+        let naive: f64 = (1..4).map(|t| path.at(t).dot(&weights)).sum::<f64>() / 3.0;
+        assert!((path.basket_time_average(&weights, 1) - naive).abs() < 1e-12);
+        assert_eq!(path.basket_time_average(&weights, 0), 74.375);
+
+        assert_eq!(path.worst_performances(), arr1(&[1.0, 0.9, 0.9, 0.8]));
+        assert_eq!(path.worst_of_running_minimum(), arr1(&[1.0, 0.9, 0.9, 0.8]));
+        assert_eq!(path.worst_of_minimum(), 0.8);
+
+        let paths = vec![path.clone(), AssetMajorPath::new(path.values() * 2.0)];
+        let evaluator = PathEvaluator::new(&paths);
+        assert_eq!(evaluator.worst_of_minima(), vec![0.8, 0.8]);
+        let payoffs = evaluator.asian_basket_payoffs(&weights, 80.0, &ExerciseType::Call, 0.5);
+        assert!((payoffs[0].unwrap() - 0.5 * (naive - 80.0).max(0.0)).abs() < 1e-12);
+        assert!((payoffs[1].unwrap() - 0.5 * (2.0 * naive - 80.0)).abs() < 1e-12);
+    }
+}
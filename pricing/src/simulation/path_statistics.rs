@@ -0,0 +1,265 @@
+use crate::simulation::monte_carlo::{PathGenerator, WarmStart};
+
+/// Running statistics of a univariate path, updated step by step during the path generation,
+/// e.g. for barrier, Asian or lookback payoffs without a second pass over the path.
+/// The extrema include the initial value, whereas the average is taken over the simulated values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RunningStatistics {
+    pub max: f64,
+    pub min: f64,
+    sum: f64,
+    /// the sum of the squared log returns
+    sum_squared_log_returns: f64,
+    nr_steps: usize,
+}
+
+impl RunningStatistics {
+    pub fn new(initial_value: f64) -> Self {
+        Self {
+            max: initial_value,
+            min: initial_value,
+            sum: 0.0,
+            sum_squared_log_returns: 0.0,
+            nr_steps: 0,
+        }
+    }
+
+    /// Statistics of an existing path, where the first value is the initial value.
+    pub fn from_path(path: &[f64]) -> Option<Self> {
+        let (initial_value, tail) = path.split_first()?;
+        let mut statistics = Self::new(*initial_value);
+        let mut prev = *initial_value;
+        for curr in tail {
+            statistics.update(prev, *curr);
+            prev = *curr;
+        }
+        Some(statistics)
+    }
+
+    #[inline]
+    pub fn update(&mut self, prev: f64, curr: f64) {
+        self.max = self.max.max(curr);
+        self.min = self.min.min(curr);
+        self.sum += curr;
+        self.sum_squared_log_returns += (curr / prev).ln().powi(2);
+        self.nr_steps += 1;
+    }
+
+    pub fn nr_steps(&self) -> usize {
+        self.nr_steps
+    }
+
+    /// The arithmetic average of the simulated values.
+    pub fn average(&self) -> Option<f64> {
+        if self.nr_steps == 0 {
+            return None;
+        }
+        Some(self.sum / self.nr_steps as f64)
+    }
+
+    /// The realized variance $\sum_i ln(S_{i+1} / S_i)^2$ (not annualized).
+    pub fn realized_variance(&self) -> f64 {
+        self.sum_squared_log_returns
+    }
+
+    /// The annualized realized volatility for the time step `dt` (in years).
+    pub fn realized_volatility(&self, dt: f64) -> Option<f64> {
+        if self.nr_steps == 0 {
+            return None;
+        }
+        Some((self.sum_squared_log_returns / (self.nr_steps as f64 * dt)).sqrt())
+    }
+}
+
+/// The running variance of a univariate path accrued only on the steps starting with the value inside
+/// the corridor $[L, U]$, e.g. for corridor and conditional variance swaps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CorridorVariance {
+    pub lower: f64,
+    pub upper: f64,
+    /// the sum of the squared log returns of the steps inside the corridor
+    sum_squared_log_returns: f64,
+    nr_steps_inside: usize,
+    nr_steps: usize,
+}
+
+impl CorridorVariance {
+    pub fn new(lower: f64, upper: f64) -> Self {
+        assert!(lower < upper);
+        Self {
+            lower,
+            upper,
+            sum_squared_log_returns: 0.0,
+            nr_steps_inside: 0,
+            nr_steps: 0,
+        }
+    }
+
+    /// The corridor variance of an existing path, where the first value is the initial value.
+    pub fn from_path(lower: f64, upper: f64, path: &[f64]) -> Self {
+        let mut corridor_variance = Self::new(lower, upper);
+        for step in path.windows(2) {
+            corridor_variance.update(step[0], step[1]);
+        }
+        corridor_variance
+    }
+
+    #[inline]
+    pub fn update(&mut self, prev: f64, curr: f64) {
+        if self.lower <= prev && prev <= self.upper {
+            self.sum_squared_log_returns += (curr / prev).ln().powi(2);
+            self.nr_steps_inside += 1;
+        }
+        self.nr_steps += 1;
+    }
+
+    pub fn nr_steps(&self) -> usize {
+        self.nr_steps
+    }
+
+    /// The fraction of the steps starting inside the corridor.
+    pub fn fraction_inside(&self) -> Option<f64> {
+        if self.nr_steps == 0 {
+            return None;
+        }
+        Some(self.nr_steps_inside as f64 / self.nr_steps as f64)
+    }
+
+    /// The realized variance $\sum_i 1_{L \le S_i \le U} ln(S_{i+1} / S_i)^2$ (not annualized).
+    pub fn realized_variance(&self) -> f64 {
+        self.sum_squared_log_returns
+    }
+}
+
+/// A path together with its running statistics.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathWithStatistics {
+    pub path: Vec<f64>,
+    pub statistics: RunningStatistics,
+}
+
+/// Path generation computing the running statistics fused into the step loop.
+pub trait StatisticsPathGenerator {
+    fn sample_path_with_statistics<SeedRng>(
+        &self,
+        rn_generator: &mut SeedRng,
+        nr_samples: usize,
+    ) -> PathWithStatistics
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore;
+}
+
+/// Wraps a path generator, such that a `MonteCarloPathSimulator` samples `PathWithStatistics`.
+#[derive(Clone, Debug)]
+pub struct WithRunningStatistics<PathGen> {
+    path_generator: PathGen,
+}
+
+impl<PathGen> WithRunningStatistics<PathGen> {
+    pub fn new(path_generator: PathGen) -> Self {
+        Self { path_generator }
+    }
+}
+
+impl<PathGen> PathGenerator<PathWithStatistics> for WithRunningStatistics<PathGen>
+where
+    PathGen: StatisticsPathGenerator,
+{
+    #[inline]
+    fn sample_path<SeedRng>(
+        &self,
+        rn_generator: &mut SeedRng,
+        nr_samples: usize,
+    ) -> PathWithStatistics
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        self.path_generator
+            .sample_path_with_statistics(rn_generator, nr_samples)
+    }
+}
+
+impl<PathGen> WarmStart for WithRunningStatistics<PathGen>
+where
+    PathGen: WarmStart,
+{
+    type State = PathGen::State;
+
+    fn dt(&self) -> f64 {
+        self.path_generator.dt()
+    }
+
+    fn warm_start(&self, t0: f64, state: &Self::State) -> Self {
+        Self::new(self.path_generator.warm_start(t0, state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::monte_carlo::MonteCarloPathSimulator;
+    use crate::simulation::sde::gbm::GeometricBrownianMotion;
+    use crate::simulation::PathEvaluator;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn statistics_from_path() {
+        let statistics = RunningStatistics::from_path(&[100.0, 110.0, 90.0, 100.0]).unwrap();
+        assert_eq!(statistics.max, 110.0);
+        assert_eq!(statistics.min, 90.0);
+        assert_eq!(statistics.average(), Some(100.0));
+        assert_eq!(statistics.nr_steps(), 3);
+
+        let expected = (1.1_f64).ln().powi(2)
+            + (90.0_f64 / 110.0).ln().powi(2)
+            + (100.0_f64 / 90.0).ln().powi(2);
+        assert_approx_eq!(statistics.realized_variance(), expected, 1e-15);
+
+        assert!(RunningStatistics::from_path(&[]).is_none());
+        assert_eq!(
+            RunningStatistics::from_path(&[1.0]).unwrap().average(),
+            None
+        );
+    }
+
+    #[test]
+    fn corridor_variance() {
+        let path = [100.0, 110.0, 90.0, 100.0];
+        let plain = RunningStatistics::from_path(&path).unwrap();
+        let inside = CorridorVariance::from_path(95.0, 105.0, &path);
+        assert_eq!(inside.fraction_inside(), Some(1.0 / 3.0));
+        assert_approx_eq!(inside.realized_variance(), (1.1_f64).ln().powi(2), 1e-15);
+        // the accrual below and above a level adds up to the realized variance
+        let below = CorridorVariance::from_path(0.0, 100.0, &path);
+        let above = CorridorVariance::from_path(100.0 + 1e-9, f64::INFINITY, &path);
+        assert_approx_eq!(
+            below.realized_variance() + above.realized_variance(),
+            plain.realized_variance(),
+            1e-15
+        );
+        assert_eq!(CorridorVariance::new(0.0, 1.0).fraction_inside(), None);
+    }
+
+    #[test]
+    fn fused_gbm_statistics() {
+        let vola = 0.3;
+        let dt = 1.0 / 250.0;
+        let gbm = GeometricBrownianMotion::new(100.0, 0.05, vola, dt);
+        let mc_simulator: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, PathWithStatistics> =
+            MonteCarloPathSimulator::new(WithRunningStatistics::new(gbm), Some(42));
+        let paths = mc_simulator.simulate_paths(200, 250);
+
+        for path in paths.iter() {
+            assert_eq!(path.path.len(), 251);
+            assert_eq!(
+                Some(path.statistics),
+                RunningStatistics::from_path(&path.path)
+            );
+        }
+
+        let path_eval = PathEvaluator::new(&paths);
+        let avg_realized_vola =
+            path_eval.evaluate_average(|p| p.statistics.realized_volatility(dt));
+        assert_approx_eq!(avg_realized_vola.unwrap(), vola, 0.01);
+    }
+}
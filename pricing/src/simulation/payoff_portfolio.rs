@@ -0,0 +1,217 @@
+use crate::common::results::{JointEstimate, PriceResult, PricingError};
+
+type PathPayoff<'a, Path> = Box<dyn Fn(&Path) -> Option<f64> + 'a>;
+
+/// A leg of a payoff portfolio: the quantity held of a (undiscounted) payoff.
+struct PayoffLeg<'a, Path> {
+    name: String,
+    quantity: f64,
+    payoff: PathPayoff<'a, Path>,
+}
+
+/// The prices of the legs of a payoff portfolio per unit, and of the portfolio of their quantities,
+/// estimated on the same paths.
+#[derive(Clone, Debug)]
+pub struct PortfolioPrice {
+    pub names: Vec<String>,
+    pub quantities: Vec<f64>,
+    /// the price per unit of each leg
+    pub legs: Vec<PriceResult>,
+    /// the price of the portfolio, whose standard error accounts for the correlation of the legs
+    pub total: PriceResult,
+    /// the joint estimate of the leg prices per unit with the covariance of their errors
+    pub joint: JointEstimate,
+}
+
+impl PortfolioPrice {
+    /// The price per unit of the leg of the name.
+    pub fn leg(&self, name: &str) -> Option<&PriceResult> {
+        self.index(name).map(|idx| &self.legs[idx])
+    }
+
+    fn index(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|leg_name| leg_name == name)
+    }
+
+    /// The price per unit of the first leg less the second, whose standard error accounts for their
+    /// correlation.
+    pub fn spread(&self, long: &str, short: &str) -> Option<PriceResult> {
+        let (i, j) = (self.index(long)?, self.index(short)?);
+        let mut weights = vec![0.0; self.names.len()];
+        weights[i] += 1.0;
+        weights[j] -= 1.0;
+        self.joint.linear_combination(&weights).ok()
+    }
+
+    /// The units of the hedge leg per unit of the leg of equal value, with its delta-method standard error,
+    /// or None for an unknown leg or a hedge leg of zero price.
+    pub fn hedge_ratio(&self, name: &str, hedge: &str) -> Option<PriceResult> {
+        self.joint.ratio(self.index(name)?, self.index(hedge)?).ok()
+    }
+}
+
+/// Several payoffs, e.g. a call spread plus a digital hedge or the legs of a structured note, priced in
+/// one simulation on identical paths, such that the legs are consistent with each other and with the total.
+pub struct PayoffPortfolio<'a, Path> {
+    legs: Vec<PayoffLeg<'a, Path>>,
+    discount_factor: f64,
+}
+
+impl<Path> Default for PayoffPortfolio<'_, Path> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, Path> PayoffPortfolio<'a, Path> {
+    pub fn new() -> Self {
+        Self {
+            legs: Vec::new(),
+            discount_factor: 1.0,
+        }
+    }
+
+    /// Adds the quantity of the payoff, where paths without a payoff contribute 0.
+    pub fn with_payoff(
+        mut self,
+        name: impl Into<String>,
+        quantity: f64,
+        payoff: impl Fn(&Path) -> Option<f64> + 'a,
+    ) -> Self {
+        self.legs.push(PayoffLeg {
+            name: name.into(),
+            quantity,
+            payoff: Box::new(payoff),
+        });
+        self
+    }
+
+    /// The discount factor of the payment time of the payoffs, 1 by default.
+    pub fn with_discount_factor(self, discount_factor: f64) -> Self {
+        Self {
+            discount_factor,
+            ..self
+        }
+    }
+
+    /// The prices of the legs and the portfolio on the paths, evaluating every payoff once per path.
+    pub fn price(&self, paths: &[Path]) -> Result<PortfolioPrice, PricingError> {
+        if self.legs.is_empty() {
+            return Err(PricingError::NoPayoff);
+        }
+        if paths.len() < 2 {
+            return Err(PricingError::NoPaths);
+        }
+        let samples: Vec<Vec<f64>> = self
+            .legs
+            .iter()
+            .map(|leg| {
+                paths
+                    .iter()
+                    .map(|path| self.discount_factor * (leg.payoff)(path).unwrap_or_default())
+                    .collect()
+            })
+            .collect();
+        let totals: Vec<f64> = (0..paths.len())
+            .map(|k| {
+                self.legs
+                    .iter()
+                    .zip(&samples)
+                    .map(|(leg, leg_samples)| leg.quantity * leg_samples[k])
+                    .sum()
+            })
+            .collect();
+
+        Ok(PortfolioPrice {
+            names: self.legs.iter().map(|leg| leg.name.clone()).collect(),
+            quantities: self.legs.iter().map(|leg| leg.quantity).collect(),
+            legs: samples
+                .iter()
+                .map(|leg_samples| PriceResult::from_samples(leg_samples))
+                .collect::<Result<_, _>>()?,
+            total: PriceResult::from_samples(&totals)?,
+            joint: JointEstimate::from_samples(&samples)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::BsmComputation;
+    use crate::common::models::DerivativeParameter;
+    use crate::numerics::normal::cdf;
+    use crate::simulation::monte_carlo::MonteCarloPathSimulator;
+    use crate::simulation::sde::gbm::GeometricBrownianMotion;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn call_spread_with_digital_hedge() {
+        let (s0, rfr, vola, t) = (100.0, 0.02, 0.25, 1.0);
+        let gbm = GeometricBrownianMotion::new(s0, rfr, vola, t / 50.0);
+        let simulator: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, Vec<f64>> =
+            MonteCarloPathSimulator::new(gbm, Some(11));
+        let paths = simulator.simulate_paths(50_000, 50);
+        let terminal = |path: &Vec<f64>| *path.last().unwrap();
+
+        let portfolio = PayoffPortfolio::new()
+            .with_payoff("long call", 1.0, move |path| {
+                Some((terminal(path) - 100.0).max(0.0))
+            })
+            .with_payoff("short call", -1.0, move |path| {
+                Some((terminal(path) - 110.0).max(0.0))
+            })
+            .with_payoff("digital", 2.0, move |path| {
+                (terminal(path) > 110.0).then_some(1.0)
+            })
+            .with_discount_factor((-rfr * t).exp());
+        let price = portfolio.price(&paths).unwrap();
+
+        let call =
+            |strike| BsmComputation::new(&DerivativeParameter::new(s0, strike, t, rfr, vola));
+        let digital = (-rfr * t).exp() * cdf(call(110.0).d2);
+        let exact = call(100.0).call() - call(110.0).call() + 2.0 * digital;
+        assert_approx_eq!(
+            price.leg("long call").unwrap().value,
+            call(100.0).call(),
+            0.5
+        );
+        assert_approx_eq!(price.leg("digital").unwrap().value, digital, 0.01);
+        assert_approx_eq!(
+            price.total.value,
+            exact,
+            4.0 * price.total.std_error.unwrap()
+        );
+
+        // the total is the quantity-weighted sum of the legs on the same paths
+        let legs_total: f64 = price
+            .legs
+            .iter()
+            .zip(&price.quantities)
+            .map(|(leg, quantity)| quantity * leg.value)
+            .sum();
+        assert_approx_eq!(price.total.value, legs_total, 1e-10);
+        // the variance of the total is the quadratic form of the quantities with the covariance
+        let variance: f64 = (0..3)
+            .flat_map(|i| (0..3).map(move |j| (i, j)))
+            .map(|(i, j)| {
+                price.quantities[i] * price.quantities[j] * price.joint.covariance[[i, j]]
+            })
+            .sum();
+        assert_approx_eq!(variance.sqrt(), price.total.std_error.unwrap(), 1e-10);
+        assert!(price.joint.correlation()[[0, 1]] > 0.9);
+        // the correlated errors of the calls largely cancel in the spread
+        let spread = price.spread("long call", "short call").unwrap();
+        assert_approx_eq!(
+            spread.value,
+            call(100.0).call() - call(110.0).call(),
+            4.0 * spread.std_error.unwrap()
+        );
+        let se = price.joint.std_errors();
+        assert!(spread.std_error.unwrap() < 0.5 * (se[0] * se[0] + se[1] * se[1]).sqrt());
+        let ratio = price.hedge_ratio("digital", "long call").unwrap();
+        assert_approx_eq!(ratio.value, digital / call(100.0).call(), 0.01);
+        assert!(price.spread("long call", "put").is_none());
+        assert!(PayoffPortfolio::<Vec<f64>>::new().price(&paths).is_err());
+    }
+}
@@ -0,0 +1,567 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Errors of parsing and compiling payoff scripts, with the byte positions in the source.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScriptError {
+    UnexpectedCharacter {
+        position: usize,
+        found: char,
+    },
+    UnexpectedToken {
+        position: usize,
+        found: String,
+    },
+    UnexpectedEnd,
+    UnknownIdentifier(String),
+    UnknownFunction(String),
+    WrongArity {
+        function: String,
+        found: usize,
+    },
+    /// a path where a number is expected or vice versa
+    TypeMismatch(String),
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::UnexpectedCharacter { position, found } => {
+                write!(f, "unexpected character '{found}' at {position}")
+            }
+            ScriptError::UnexpectedToken { position, found } => {
+                write!(f, "unexpected '{found}' at {position}")
+            }
+            ScriptError::UnexpectedEnd => write!(f, "unexpected end of the script"),
+            ScriptError::UnknownIdentifier(name) => write!(f, "unknown identifier '{name}'"),
+            ScriptError::UnknownFunction(name) => write!(f, "unknown function '{name}'"),
+            ScriptError::WrongArity { function, found } => {
+                write!(f, "wrong number of arguments ({found}) for '{function}'")
+            }
+            ScriptError::TypeMismatch(message) => write!(f, "type mismatch: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Identifier(String),
+    Symbol(&'static str),
+}
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ScriptError> {
+    const SYMBOLS: [&str; 12] = [">=", "<=", "+", "-", "*", "/", "^", "(", ")", ",", ">", "<"];
+    let mut tokens = Vec::new();
+    let mut position = 0;
+    while position < source.len() {
+        let rest = &source[position..];
+        let c = rest.chars().next().unwrap();
+        if c.is_whitespace() {
+            position += c.len_utf8();
+        } else if c.is_ascii_digit() || c == '.' {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+            let number = rest[..len]
+                .parse()
+                .map_err(|_| ScriptError::UnexpectedToken {
+                    position,
+                    found: rest[..len].to_string(),
+                })?;
+            tokens.push((position, Token::Number(number)));
+            position += len;
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push((position, Token::Identifier(rest[..len].to_string())));
+            position += len;
+        } else if let Some(symbol) = SYMBOLS.iter().find(|s| rest.starts_with(**s)) {
+            tokens.push((position, Token::Symbol(symbol)));
+            position += symbol.len();
+        } else {
+            return Err(ScriptError::UnexpectedCharacter { position, found: c });
+        }
+    }
+    Ok(tokens)
+}
+
+/// The syntax tree of a script.
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Number(f64),
+    Identifier(String),
+    Negate(Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+/// Recursive descent parser of
+/// '''
+/// comparison := sum (('>' | '<' | '>=' | '<=') sum)?
+/// sum := product (('+' | '-') product)*
+/// product := power (('*' | '/') power)*
+/// power := unary ('^' power)?
+/// unary := '-' power | number | identifier | identifier '(' arguments ')' | '(' comparison ')'
+/// '''
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    idx: usize,
+}
+
+impl Parser {
+    fn peek_symbol(&self, symbols: &[&'static str]) -> Option<&'static str> {
+        match self.tokens.get(self.idx) {
+            Some((_, Token::Symbol(s))) if symbols.contains(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn expect_symbol(&mut self, symbol: &'static str) -> Result<(), ScriptError> {
+        match self.tokens.get(self.idx) {
+            Some((_, Token::Symbol(s))) if *s == symbol => {
+                self.idx += 1;
+                Ok(())
+            }
+            Some((position, token)) => Err(ScriptError::UnexpectedToken {
+                position: *position,
+                found: format!("{token:?}"),
+            }),
+            None => Err(ScriptError::UnexpectedEnd),
+        }
+    }
+
+    fn parse(mut self) -> Result<Expr, ScriptError> {
+        let expr = self.comparison()?;
+        match self.tokens.get(self.idx) {
+            None => Ok(expr),
+            Some((position, token)) => Err(ScriptError::UnexpectedToken {
+                position: *position,
+                found: format!("{token:?}"),
+            }),
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Expr, ScriptError> {
+        let lhs = self.sum()?;
+        match self.peek_symbol(&[">", "<", ">=", "<="]) {
+            Some(op) => {
+                self.idx += 1;
+                Ok(Expr::Binary(op, Box::new(lhs), Box::new(self.sum()?)))
+            }
+            None => Ok(lhs),
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, ScriptError> {
+        let mut lhs = self.product()?;
+        while let Some(op) = self.peek_symbol(&["+", "-"]) {
+            self.idx += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.product()?));
+        }
+        Ok(lhs)
+    }
+
+    fn product(&mut self) -> Result<Expr, ScriptError> {
+        let mut lhs = self.power()?;
+        while let Some(op) = self.peek_symbol(&["*", "/"]) {
+            self.idx += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.power()?));
+        }
+        Ok(lhs)
+    }
+
+    fn power(&mut self) -> Result<Expr, ScriptError> {
+        let base = self.unary()?;
+        if self.peek_symbol(&["^"]).is_some() {
+            self.idx += 1;
+            return Ok(Expr::Binary("^", Box::new(base), Box::new(self.power()?)));
+        }
+        Ok(base)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ScriptError> {
+        let (position, token) = self
+            .tokens
+            .get(self.idx)
+            .cloned()
+            .ok_or(ScriptError::UnexpectedEnd)?;
+        self.idx += 1;
+        match token {
+            Token::Number(x) => Ok(Expr::Number(x)),
+            Token::Symbol("-") => Ok(Expr::Negate(Box::new(self.power()?))),
+            Token::Symbol("(") => {
+                let expr = self.comparison()?;
+                self.expect_symbol(")")?;
+                Ok(expr)
+            }
+            Token::Identifier(name) if self.peek_symbol(&["("]).is_some() => {
+                self.idx += 1;
+                let mut arguments = vec![self.comparison()?];
+                while self.peek_symbol(&[","]).is_some() {
+                    self.idx += 1;
+                    arguments.push(self.comparison()?);
+                }
+                self.expect_symbol(")")?;
+                Ok(Expr::Call(name, arguments))
+            }
+            Token::Identifier(name) => Ok(Expr::Identifier(name)),
+            Token::Symbol(symbol) => Err(ScriptError::UnexpectedToken {
+                position,
+                found: symbol.to_string(),
+            }),
+        }
+    }
+}
+
+/// A compiled node evaluating a number on the paths of the assets.
+type Node = Box<dyn Fn(&[&[f64]]) -> f64 + Send + Sync>;
+
+/// A payoff on the paths of one or several assets, compiled from a `PayoffScript`.
+pub struct CompiledPayoff {
+    node: Node,
+    nr_assets: usize,
+}
+
+impl CompiledPayoff {
+    /// The number of asset paths the payoff refers to, i.e. the highest index of `S1`, `S2`, ...
+    pub fn nr_assets(&self) -> usize {
+        self.nr_assets
+    }
+
+    /// The payoff on the paths, where `paths[i]` is the path of the asset `S{i + 1}`.
+    pub fn value(&self, paths: &[&[f64]]) -> f64 {
+        assert!(paths.len() >= self.nr_assets);
+        (self.node)(paths)
+    }
+
+    /// The payoff of a single asset script.
+    pub fn value_on_path(&self, path: &[f64]) -> f64 {
+        self.value(&[path])
+    }
+}
+
+impl fmt::Debug for CompiledPayoff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompiledPayoff")
+            .field("nr_assets", &self.nr_assets)
+            .finish()
+    }
+}
+
+/// A payoff defined at runtime by an expression, e.g. `max(avg(S1) - K, 0) * df(T)`, over the
+/// asset paths `S1`, `S2`, ... (`S` for `S1`) and named constants. Comparisons yield 1 or 0.
+///
+/// Functions on numbers: `max(..)`, `min(..)`, `abs`, `exp`, `ln`, `sqrt`, `if(condition, a, b)`
+/// and the discount factor `df(t)` with the constant rate `r`.
+/// Functions on paths: `avg`, `first`, `last`, `high`, `low`, `at(S, step)` and the barrier indicators
+/// `hit_up(S, barrier)` and `hit_down(S, barrier)`.
+#[derive(Clone, Debug)]
+pub struct PayoffScript {
+    source: String,
+    constants: BTreeMap<String, f64>,
+}
+
+impl PayoffScript {
+    pub fn new(source: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            constants: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_constant(mut self, name: &str, value: f64) -> Self {
+        self.constants.insert(name.to_string(), value);
+        self
+    }
+
+    pub fn compile(&self) -> Result<CompiledPayoff, ScriptError> {
+        let expr = Parser {
+            tokens: tokenize(&self.source)?,
+            idx: 0,
+        }
+        .parse()?;
+        let mut nr_assets = 0;
+        let node = self.compile_number(&expr, &mut nr_assets)?;
+        Ok(CompiledPayoff { node, nr_assets })
+    }
+
+    /// The index of the asset path of `S`, `S1`, `S2`, ...
+    fn asset_index(name: &str) -> Option<usize> {
+        match name {
+            "S" => Some(0),
+            _ => name
+                .strip_prefix('S')?
+                .parse::<usize>()
+                .ok()
+                .filter(|i| *i > 0)
+                .map(|i| i - 1),
+        }
+    }
+
+    fn compile_path(&self, expr: &Expr, nr_assets: &mut usize) -> Result<usize, ScriptError> {
+        match expr {
+            Expr::Identifier(name) => {
+                let idx = Self::asset_index(name)
+                    .ok_or_else(|| ScriptError::TypeMismatch(format!("'{name}' is not a path")))?;
+                *nr_assets = (*nr_assets).max(idx + 1);
+                Ok(idx)
+            }
+            _ => Err(ScriptError::TypeMismatch(format!("{expr:?} is not a path"))),
+        }
+    }
+
+    fn compile_number(&self, expr: &Expr, nr_assets: &mut usize) -> Result<Node, ScriptError> {
+        match expr {
+            Expr::Number(x) => {
+                let x = *x;
+                Ok(Box::new(move |_| x))
+            }
+            Expr::Identifier(name) => match self.constants.get(name) {
+                Some(value) => {
+                    let value = *value;
+                    Ok(Box::new(move |_| value))
+                }
+                None if Self::asset_index(name).is_some() => Err(ScriptError::TypeMismatch(
+                    format!("path '{name}' used as a number, e.g. use last({name})"),
+                )),
+                None => Err(ScriptError::UnknownIdentifier(name.to_string())),
+            },
+            Expr::Negate(inner) => {
+                let inner = self.compile_number(inner, nr_assets)?;
+                Ok(Box::new(move |paths| -inner(paths)))
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = self.compile_number(lhs, nr_assets)?;
+                let rhs = self.compile_number(rhs, nr_assets)?;
+                let indicator = |b: bool| if b { 1.0 } else { 0.0 };
+                Ok(match *op {
+                    "+" => Box::new(move |p| lhs(p) + rhs(p)),
+                    "-" => Box::new(move |p| lhs(p) - rhs(p)),
+                    "*" => Box::new(move |p| lhs(p) * rhs(p)),
+                    "/" => Box::new(move |p| lhs(p) / rhs(p)),
+                    "^" => Box::new(move |p| lhs(p).powf(rhs(p))),
+                    ">" => Box::new(move |p| indicator(lhs(p) > rhs(p))),
+                    "<" => Box::new(move |p| indicator(lhs(p) < rhs(p))),
+                    ">=" => Box::new(move |p| indicator(lhs(p) >= rhs(p))),
+                    _ => Box::new(move |p| indicator(lhs(p) <= rhs(p))),
+                })
+            }
+            Expr::Call(function, arguments) => self.compile_call(function, arguments, nr_assets),
+        }
+    }
+
+    fn compile_call(
+        &self,
+        function: &str,
+        arguments: &[Expr],
+        nr_assets: &mut usize,
+    ) -> Result<Node, ScriptError> {
+        let arity = |expected: usize| {
+            if arguments.len() == expected {
+                Ok(())
+            } else {
+                Err(ScriptError::WrongArity {
+                    function: function.to_string(),
+                    found: arguments.len(),
+                })
+            }
+        };
+
+        match function {
+            "max" | "min" => {
+                let nodes = arguments
+                    .iter()
+                    .map(|a| self.compile_number(a, nr_assets))
+                    .collect::<Result<Vec<Node>, ScriptError>>()?;
+                let fold: fn(f64, f64) -> f64 = if function == "max" {
+                    f64::max
+                } else {
+                    f64::min
+                };
+                let init = if function == "max" {
+                    f64::NEG_INFINITY
+                } else {
+                    f64::INFINITY
+                };
+                Ok(Box::new(move |p| {
+                    nodes.iter().map(|n| n(p)).fold(init, fold)
+                }))
+            }
+            "abs" | "exp" | "ln" | "sqrt" => {
+                arity(1)?;
+                let inner = self.compile_number(&arguments[0], nr_assets)?;
+                let f: fn(f64) -> f64 = match function {
+                    "abs" => f64::abs,
+                    "exp" => f64::exp,
+                    "ln" => f64::ln,
+                    _ => f64::sqrt,
+                };
+                Ok(Box::new(move |p| f(inner(p))))
+            }
+            "if" => {
+                arity(3)?;
+                let condition = self.compile_number(&arguments[0], nr_assets)?;
+                let then = self.compile_number(&arguments[1], nr_assets)?;
+                let otherwise = self.compile_number(&arguments[2], nr_assets)?;
+                Ok(Box::new(move |p| {
+                    if condition(p) != 0.0 {
+                        then(p)
+                    } else {
+                        otherwise(p)
+                    }
+                }))
+            }
+            "df" => {
+                arity(1)?;
+                let rate = *self
+                    .constants
+                    .get("r")
+                    .ok_or_else(|| ScriptError::UnknownIdentifier("r".to_string()))?;
+                let time = self.compile_number(&arguments[0], nr_assets)?;
+                Ok(Box::new(move |p| (-rate * time(p)).exp()))
+            }
+            "avg" | "first" | "last" | "high" | "low" => {
+                arity(1)?;
+                let idx = self.compile_path(&arguments[0], nr_assets)?;
+                let aggregate: fn(&[f64]) -> f64 = match function {
+                    "avg" => |path| path.iter().sum::<f64>() / path.len() as f64,
+                    "first" => |path| path.first().copied().unwrap_or(f64::NAN),
+                    "last" => |path| path.last().copied().unwrap_or(f64::NAN),
+                    "high" => |path| path.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                    _ => |path| path.iter().copied().fold(f64::INFINITY, f64::min),
+                };
+                Ok(Box::new(move |p| aggregate(p[idx])))
+            }
+            "at" => {
+                arity(2)?;
+                let idx = self.compile_path(&arguments[0], nr_assets)?;
+                let step = self.compile_number(&arguments[1], nr_assets)?;
+                Ok(Box::new(move |p| {
+                    p[idx]
+                        .get(step(p).round() as usize)
+                        .copied()
+                        .unwrap_or(f64::NAN)
+                }))
+            }
+            "hit_up" | "hit_down" => {
+                arity(2)?;
+                let idx = self.compile_path(&arguments[0], nr_assets)?;
+                let barrier = self.compile_number(&arguments[1], nr_assets)?;
+                let up = function == "hit_up";
+                Ok(Box::new(move |p| {
+                    let b = barrier(p);
+                    let hit = if up {
+                        p[idx].iter().any(|s| *s >= b)
+                    } else {
+                        p[idx].iter().any(|s| *s <= b)
+                    };
+                    if hit {
+                        1.0
+                    } else {
+                        0.0
+                    }
+                }))
+            }
+            _ => Err(ScriptError::UnknownFunction(function.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::monte_carlo::{MonteCarloPathSimulator, PathEvaluator};
+    use crate::simulation::products::european_option::MonteCarloEuropeanOption;
+    use crate::simulation::sde::gbm::GeometricBrownianMotion;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn evaluate_scripts() {
+        let path = [100.0, 110.0, 90.0, 120.0];
+        let script = |source: &str| {
+            PayoffScript::new(source)
+                .with_constant("K", 100.0)
+                .with_constant("T", 2.0)
+                .with_constant("r", 0.05)
+                .compile()
+                .unwrap()
+        };
+
+        assert_approx_eq!(
+            script("max(avg(S1) - K, 0) * df(T)").value_on_path(&path),
+            5.0 * (-0.1_f64).exp(),
+            1e-12
+        );
+        assert_eq!(script("-2 ^ 2 + 3 * (1 + 1)").value_on_path(&path), 2.0);
+        assert_eq!(script("high(S) - low(S)").value_on_path(&path), 30.0);
+        assert_eq!(script("at(S, 1) / first(S)").value_on_path(&path), 1.1);
+        assert_eq!(
+            script("(1 - hit_down(S, 95)) * max(last(S) - K, 0)").value_on_path(&path),
+            0.0
+        );
+        assert_eq!(
+            script("if(last(S) >= 120, 1, 0) + (high(S) > 150)").value_on_path(&path),
+            1.0
+        );
+
+        let spread = script("max(last(S2) - last(S1), 0)");
+        assert_eq!(spread.nr_assets(), 2);
+        assert_eq!(spread.value(&[&path, &[100.0, 130.0]]), 10.0);
+    }
+
+    #[test]
+    fn script_errors() {
+        let compile = |source: &str| PayoffScript::new(source).compile().unwrap_err();
+        assert_eq!(
+            compile("last(S) - K"),
+            ScriptError::UnknownIdentifier("K".to_string())
+        );
+        assert!(matches!(compile("S1 + 1"), ScriptError::TypeMismatch(_)));
+        assert!(matches!(compile("avg(2)"), ScriptError::TypeMismatch(_)));
+        assert!(matches!(compile("foo(S)"), ScriptError::UnknownFunction(_)));
+        assert!(matches!(
+            compile("exp(1, 2)"),
+            ScriptError::WrongArity { .. }
+        ));
+        assert_eq!(compile("max(1, "), ScriptError::UnexpectedEnd);
+        assert!(matches!(
+            compile("1 + 2)"),
+            ScriptError::UnexpectedToken { position: 5, .. }
+        ));
+        assert!(matches!(
+            compile("1 # 2"),
+            ScriptError::UnexpectedCharacter { found: '#', .. }
+        ));
+        assert!(matches!(
+            compile("df(1)"),
+            ScriptError::UnknownIdentifier(_)
+        ));
+    }
+
+    #[test]
+    fn scripted_call_matches_product() {
+        let (spot, strike, rfr, vola, t, nr_steps) = (100.0, 105.0, 0.03, 0.2, 1.0, 50);
+        let payoff = PayoffScript::new("max(last(S) - K, 0) * df(T)")
+            .with_constant("K", strike)
+            .with_constant("T", t)
+            .with_constant("r", rfr)
+            .compile()
+            .unwrap();
+
+        let gbm = GeometricBrownianMotion::new(spot, rfr, vola, t / nr_steps as f64);
+        let simulator: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, Vec<f64>> =
+            MonteCarloPathSimulator::new(gbm, Some(7));
+        let paths = simulator.simulate_paths(10_000, nr_steps);
+        let scripted = PathEvaluator::new(&paths)
+            .evaluate_average(|path| Some(payoff.value_on_path(path)))
+            .unwrap();
+
+        let product: MonteCarloEuropeanOption<rand_hc::Hc128Rng> =
+            MonteCarloEuropeanOption::new(spot, strike, t, rfr, vola, 10_000, nr_steps, 7);
+        assert_approx_eq!(scripted, product.try_call().unwrap().value, 1e-8);
+    }
+}
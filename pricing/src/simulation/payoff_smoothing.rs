@@ -0,0 +1,277 @@
+use crate::common::models::ExerciseType;
+use crate::numerics::normal::cdf;
+use crate::simulation::monte_carlo::StepControl;
+
+/// Replacement of the cash-or-nothing step at the strike.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DigitalSmoothing {
+    None,
+    /// the call spread of the given (absolute) width centered at the strike
+    CallSpread {
+        width: f64,
+    },
+}
+
+/// Replacement of the knock-out indicator at the barrier.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BarrierSmoothing {
+    None,
+    /// the barrier multiplied by $e^{shift}$, e.g. the continuity correction $e^{\pm 0.5826 \sigma \sqrt{dt}}$
+    Shift {
+        shift: f64,
+    },
+    /// a survival weight decreasing linearly on the band $[B (1 - width), B (1 + width)]$
+    Band {
+        width: f64,
+    },
+}
+
+/// Smoothing of discontinuous payoffs such that bump-and-reprice greeks are not dominated by the
+/// few paths crossing the discontinuity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PayoffSmoothing {
+    pub digital: DigitalSmoothing,
+    pub barrier: BarrierSmoothing,
+}
+
+impl Default for PayoffSmoothing {
+    fn default() -> Self {
+        Self {
+            digital: DigitalSmoothing::None,
+            barrier: BarrierSmoothing::None,
+        }
+    }
+}
+
+/// Payoffs on a path whose discontinuities can be smoothed.
+pub trait SmoothablePayoff {
+    fn payoff(&self, path: &[f64], smoothing: &PayoffSmoothing) -> f64;
+}
+
+/// Payoffs with an analytic expectation over the log-normal last step of the path.
+pub trait ConditionalPayoff {
+    /// The expectation conditional on the path before the last step, where the logarithm of the
+    /// terminal value is normal with mean `log_mean` and standard deviation `log_std`.
+    fn conditional_expectation(&self, path: &[f64], log_mean: f64, log_std: f64) -> f64;
+}
+
+/// Cash-or-nothing digital option paying `cash` if the terminal price ends in the money.
+/// See https://en.wikipedia.org/wiki/Binary_option
+pub struct DigitalOption {
+    pub strike: f64,
+    pub cash: f64,
+    pub exercise_type: ExerciseType,
+}
+
+impl SmoothablePayoff for DigitalOption {
+    fn payoff(&self, path: &[f64], smoothing: &PayoffSmoothing) -> f64 {
+        let terminal = *path.last().unwrap();
+        let moneyness = match self.exercise_type {
+            ExerciseType::Call => terminal - self.strike,
+            ExerciseType::Put => self.strike - terminal,
+        };
+        let probability = match smoothing.digital {
+            DigitalSmoothing::None => (moneyness > 0.0) as u8 as f64,
+            DigitalSmoothing::CallSpread { width } => (moneyness / width + 0.5).clamp(0.0, 1.0),
+        };
+        self.cash * probability
+    }
+}
+
+impl ConditionalPayoff for DigitalOption {
+    fn conditional_expectation(&self, _path: &[f64], log_mean: f64, log_std: f64) -> f64 {
+        let d = (log_mean - self.strike.ln()) / log_std;
+        match self.exercise_type {
+            ExerciseType::Call => self.cash * cdf(d),
+            ExerciseType::Put => self.cash * cdf(-d),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BarrierType {
+    UpAndOut,
+    DownAndOut,
+}
+
+/// Knock-out vanilla option, discretely monitored at the path's time steps.
+/// See https://en.wikipedia.org/wiki/Barrier_option
+pub struct BarrierOption {
+    pub strike: f64,
+    pub barrier: f64,
+    pub barrier_type: BarrierType,
+    pub exercise_type: ExerciseType,
+}
+
+impl BarrierOption {
+    /// Stops the paths at the knock-out, after which the (unsmoothed) payoff is 0.
+    pub fn step_controller(&self) -> impl Fn(usize, &f64) -> StepControl + '_ {
+        move |_, spot| {
+            let knocked_out = match self.barrier_type {
+                BarrierType::UpAndOut => *spot >= self.barrier,
+                BarrierType::DownAndOut => *spot <= self.barrier,
+            };
+            if knocked_out {
+                StepControl::Stop
+            } else {
+                StepControl::Continue
+            }
+        }
+    }
+
+    /// The survival weight of the path in [0, 1], which is the knock-out indicator without smoothing.
+    fn survival(&self, path: &[f64], smoothing: &BarrierSmoothing) -> f64 {
+        let barrier = match smoothing {
+            BarrierSmoothing::Shift { shift } => self.barrier * shift.exp(),
+            _ => self.barrier,
+        };
+        // the signed distance of the path's extremum into the surviving region
+        let (distance, scale) = match self.barrier_type {
+            BarrierType::UpAndOut => (
+                barrier - path.iter().cloned().fold(f64::MIN, f64::max),
+                barrier,
+            ),
+            BarrierType::DownAndOut => (
+                path.iter().cloned().fold(f64::MAX, f64::min) - barrier,
+                barrier,
+            ),
+        };
+        match smoothing {
+            BarrierSmoothing::Band { width } => {
+                (distance / (2.0 * width * scale) + 0.5).clamp(0.0, 1.0)
+            }
+            _ => (distance > 0.0) as u8 as f64,
+        }
+    }
+}
+
+impl SmoothablePayoff for BarrierOption {
+    fn payoff(&self, path: &[f64], smoothing: &PayoffSmoothing) -> f64 {
+        let terminal = *path.last().unwrap();
+        let vanilla = self.exercise_type.payoff(terminal, self.strike);
+        vanilla * self.survival(path, &smoothing.barrier)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numerics::normal::pdf;
+    use crate::simulation::greeks::{BumpSizes, GbmGreeksEngine};
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn smoothed_payoffs() {
+        let digital = DigitalOption {
+            strike: 100.0,
+            cash: 10.0,
+            exercise_type: ExerciseType::Call,
+        };
+        let spread = PayoffSmoothing {
+            digital: DigitalSmoothing::CallSpread { width: 4.0 },
+            ..PayoffSmoothing::default()
+        };
+        assert_eq!(
+            digital.payoff(&[100.0, 101.0], &PayoffSmoothing::default()),
+            10.0
+        );
+        assert_approx_eq!(digital.payoff(&[100.0, 101.0], &spread), 7.5, 1e-12);
+        assert_eq!(digital.payoff(&[100.0, 103.0], &spread), 10.0);
+
+        let barrier = BarrierOption {
+            strike: 100.0,
+            barrier: 120.0,
+            barrier_type: BarrierType::UpAndOut,
+            exercise_type: ExerciseType::Call,
+        };
+        let path = [100.0, 119.0, 110.0];
+        assert_eq!(barrier.payoff(&path, &PayoffSmoothing::default()), 10.0);
+        let shifted = PayoffSmoothing {
+            barrier: BarrierSmoothing::Shift { shift: -0.01 },
+            ..PayoffSmoothing::default()
+        };
+        assert_eq!(barrier.payoff(&path, &shifted), 0.0);
+        let band = PayoffSmoothing {
+            barrier: BarrierSmoothing::Band { width: 0.025 },
+            ..PayoffSmoothing::default()
+        };
+        assert_approx_eq!(
+            barrier.payoff(&path, &band),
+            10.0 * (1.0 / 6.0 + 0.5),
+            1e-12
+        );
+    }
+
+    #[test]
+    fn knocked_out_paths_stop_early() {
+        use crate::common::results::PriceResult;
+        use crate::simulation::monte_carlo::MonteCarloPathSimulator;
+        use crate::simulation::sde::gbm::GeometricBrownianMotion;
+        use crate::simulation::verification::assert_within_std_errors;
+
+        let (spot, rfr, vola, nr_steps, nr_paths) = (100.0, 0.02, 0.25, 100, 5_000);
+        let barrier = BarrierOption {
+            strike: 100.0,
+            barrier: 115.0,
+            barrier_type: BarrierType::UpAndOut,
+            exercise_type: ExerciseType::Call,
+        };
+        let gbm = GeometricBrownianMotion::new(spot, rfr, vola, 1.0 / nr_steps as f64);
+        let simulator: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, Vec<f64>> =
+            MonteCarloPathSimulator::new(gbm, Some(42));
+        let price = |paths: &[Vec<f64>]| {
+            let payoffs: Vec<f64> = paths
+                .iter()
+                .map(|path| barrier.payoff(path, &PayoffSmoothing::default()))
+                .collect();
+            PriceResult::from_samples(&payoffs).unwrap()
+        };
+
+        let full_paths = simulator.simulate_paths(nr_paths, nr_steps);
+        let stopped_paths =
+            simulator.simulate_paths_controlled(nr_paths, nr_steps, &barrier.step_controller());
+        let nr_computed_steps: usize = stopped_paths.iter().map(|path| path.len() - 1).sum();
+        assert!(
+            nr_computed_steps < nr_paths * nr_steps * 3 / 4,
+            "{nr_computed_steps}"
+        );
+        assert!(stopped_paths
+            .iter()
+            .all(|path| path.len() == nr_steps + 1 || *path.last().unwrap() >= 115.0));
+        // the stopped paths differ from the full ones after the first knock-out, but not in distribution
+        assert_within_std_errors(&price(&stopped_paths), price(&full_paths).value, 4.0);
+    }
+
+    #[test]
+    fn smoothed_digital_delta() {
+        let (spot, strike, rfr, vola, t): (f64, f64, f64, f64, f64) =
+            (100.0, 100.0, 0.02, 0.2, 1.0);
+        let d2 = ((spot / strike).ln() + (rfr - 0.5 * vola * vola) * t) / (vola * t.sqrt());
+        let delta = (-rfr * t).exp() * pdf(d2) / (spot * vola * t.sqrt());
+
+        let digital = DigitalOption {
+            strike,
+            cash: 1.0,
+            exercise_type: ExerciseType::Call,
+        };
+        let bump_sizes = BumpSizes {
+            spot: 0.001,
+            vola: 0.01,
+        };
+        let delta_error = |smoothing: PayoffSmoothing| {
+            let engine: GbmGreeksEngine<rand_hc::Hc128Rng> =
+                GbmGreeksEngine::new(rfr, t, 20_000, 1, 3)
+                    .with_bump_sizes(bump_sizes)
+                    .with_smoothing(smoothing);
+            let surface = engine.greeks_surface(&[spot], &[vola], engine.smoothed_payoff(&digital));
+            (surface.delta[[0, 0]] - delta).abs()
+        };
+
+        let smoothed_error = delta_error(PayoffSmoothing {
+            digital: DigitalSmoothing::CallSpread { width: 2.0 },
+            ..PayoffSmoothing::default()
+        });
+        assert!(smoothed_error < 1e-3);
+        assert!(smoothed_error < delta_error(PayoffSmoothing::default()));
+    }
+}
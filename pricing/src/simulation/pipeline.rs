@@ -0,0 +1,175 @@
+use crate::simulation::monte_carlo::{MonteCarloPathSimulator, PathGenerator};
+
+/// A single transformation stage applied to a simulated path,
+/// e.g. noise -> asset price path -> discounted payoff.
+/// Stages consume their input, so in-place transformations can mutate and return it.
+pub trait PathStage<Input> {
+    type Output;
+    fn apply(&self, input: Input) -> Self::Output;
+}
+
+impl<Input, Output, F> PathStage<Input> for F
+where
+    F: Fn(Input) -> Output,
+{
+    type Output = Output;
+
+    #[inline]
+    fn apply(&self, input: Input) -> Output {
+        self(input)
+    }
+}
+
+impl<Input, Output> PathStage<Input> for Box<dyn PathStage<Input, Output = Output>> {
+    type Output = Output;
+
+    #[inline]
+    fn apply(&self, input: Input) -> Output {
+        self.as_ref().apply(input)
+    }
+}
+
+/// The stage passing the path through unchanged.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Identity;
+
+impl<Input> PathStage<Input> for Identity {
+    type Output = Input;
+
+    #[inline]
+    fn apply(&self, input: Input) -> Input {
+        input
+    }
+}
+
+/// Composition of two stages, applying `first` and then `second`.
+#[derive(Clone, Copy, Debug)]
+pub struct Chain<First, Second> {
+    first: First,
+    second: Second,
+}
+
+impl<First, Second> Chain<First, Second> {
+    pub fn new(first: First, second: Second) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<Input, First, Second> PathStage<Input> for Chain<First, Second>
+where
+    First: PathStage<Input>,
+    Second: PathStage<First::Output>,
+{
+    type Output = Second::Output;
+
+    #[inline]
+    fn apply(&self, input: Input) -> Self::Output {
+        self.second.apply(self.first.apply(input))
+    }
+}
+
+/// Composable pipeline on top of the paths sampled by a `MonteCarloPathSimulator`, e.g.
+/// ''' simulator.pipe().then(gbm).then(payoff).simulate(nr_paths, nr_steps) '''
+/// Each path is pushed through all stages before the next one is sampled.
+pub struct PathPipeline<'a, PathGen, SeedRng, Path, Stage>
+where
+    PathGen: PathGenerator<Path>,
+    SeedRng: rand::SeedableRng + rand::RngCore,
+{
+    simulator: &'a MonteCarloPathSimulator<PathGen, SeedRng, Path>,
+    stage: Stage,
+}
+
+impl<'a, PathGen, SeedRng, Path, Stage> PathPipeline<'a, PathGen, SeedRng, Path, Stage>
+where
+    PathGen: PathGenerator<Path>,
+    SeedRng: rand::SeedableRng + rand::RngCore,
+    Stage: PathStage<Path>,
+{
+    pub(crate) fn new(
+        simulator: &'a MonteCarloPathSimulator<PathGen, SeedRng, Path>,
+        stage: Stage,
+    ) -> Self {
+        Self { simulator, stage }
+    }
+
+    /// Append a stage operating on the output of the current pipeline.
+    pub fn then<Next>(
+        self,
+        next: Next,
+    ) -> PathPipeline<'a, PathGen, SeedRng, Path, Chain<Stage, Next>>
+    where
+        Next: PathStage<Stage::Output>,
+    {
+        PathPipeline {
+            simulator: self.simulator,
+            stage: Chain::new(self.stage, next),
+        }
+    }
+
+    /// Sample `nr_paths` paths and transform each of them by the pipeline's stages.
+    pub fn simulate(&self, nr_paths: usize, nr_steps: usize) -> Vec<Stage::Output> {
+        let mut outputs = Vec::with_capacity(nr_paths);
+        let mut generator = self.simulator.rn_generator();
+
+        for _ in 0..nr_paths {
+            let path = self
+                .simulator
+                .path_generator()
+                .sample_path(&mut generator, nr_steps);
+            outputs.push(self.stage.apply(path));
+        }
+        outputs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::sde::gbm::GeometricBrownianMotion;
+    use crate::simulation::PathEvaluator;
+    use rand_distr::StandardNormal;
+
+    #[test]
+    fn chained_stages() {
+        let add_one = |x: f64| x + 1.0;
+        let double = |x: f64| 2.0 * x;
+        assert_eq!(Chain::new(add_one, double).apply(1.0), 4.0);
+        assert_eq!(Chain::new(double, add_one).apply(1.0), 3.0);
+        assert_eq!(Chain::new(Identity, add_one).apply(1.0), 2.0);
+    }
+
+    #[test]
+    fn boxed_stages() {
+        let stages: Vec<Box<dyn PathStage<f64, Output = f64>>> =
+            vec![Box::new(|x: f64| x + 1.0), Box::new(Identity)];
+        assert_eq!(stages[0].apply(1.0), 2.0);
+        assert_eq!(stages[1].apply(1.0), 1.0);
+    }
+
+    #[test]
+    fn pipeline_equals_simulate_paths_with() {
+        let s0 = 100.0;
+        let gbm = GeometricBrownianMotion::new(s0, 0.02, 0.2, 0.01);
+        let mc_simulator: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, Vec<f64>> =
+            MonteCarloPathSimulator::new(StandardNormal, Some(42));
+
+        let paths = mc_simulator.simulate_paths_with(100, 50, |z| gbm.generate_path(s0, z));
+        let piped_paths = mc_simulator
+            .pipe()
+            .then(|z: Vec<f64>| gbm.generate_path(s0, &z))
+            .simulate(100, 50);
+        assert_eq!(paths, piped_paths);
+
+        let path_eval = PathEvaluator::new(&paths);
+        let avg_payoff = path_eval.evaluate_average(|p| p.last().map(|s| (s - s0).max(0.0)));
+
+        let payoffs = mc_simulator
+            .pipe()
+            .then(gbm)
+            .then(|p: Vec<f64>| p.last().map(|s| (s - s0).max(0.0)))
+            .simulate(100, 50);
+        let avg_piped_payoff = payoffs.iter().flatten().sum::<f64>() / payoffs.len() as f64;
+        assert_eq!(avg_payoff.unwrap(), avg_piped_payoff);
+    }
+}
@@ -3,10 +3,20 @@ use std::marker::PhantomData;
 use ndarray::prelude::*;
 use ndarray::Array2;
 
-use crate::simulation::monte_carlo::MonteCarloPathSimulator;
+use crate::common::models::ExerciseType;
+use crate::common::results::{PriceResult, PricingError, PricingWarning};
+use crate::common::time_grid::TimeGrid;
+use crate::numerics::correlation::ensure_positive_definite_with_warning;
+use crate::numerics::linalg::cholesky;
+use crate::simulation::correlation_greeks::{cega, pairwise_cega, CorrelationBump};
+use crate::simulation::numeraire::{MoneyMarketAccount, NumeraireConvention};
+use crate::simulation::path_layout::AssetMajorPath;
 use crate::simulation::sde::multivariate_gbm::MultivariateGeometricBrownianMotion;
 use crate::simulation::PathEvaluator;
 
+/// The deviation of the sum of the weights from 1 up to which the weights are normalized.
+const WEIGHT_TOLERANCE: f64 = 1e-6;
+
 // https://backtick.se/blog/options-mc-2/
 // https://jbhender.github.io/Stats506/F18/GP/Group21.html
 /// Indices of cholesky matrix must be aligned with the indices in weights, asset_proces, rf_rates
@@ -27,6 +37,8 @@ where
     seed_nr: u64,
     nr_paths: usize,
     nr_steps: usize,
+    /// the adjustments of the inputs, reported with each price
+    warnings: Vec<PricingWarning>,
     _phantom_rng: PhantomData<SeedRng>,
 }
 
@@ -34,6 +46,7 @@ impl<SeedRng> MonteCarloEuropeanBasketOption<SeedRng>
 where
     SeedRng: rand::SeedableRng + rand::RngCore,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         // underlying_map: HashMap<Underlying, usize>,
         weights: Array1<f64>,
@@ -42,13 +55,18 @@ where
         cholesky_factor: Array2<f64>,
         strike: f64,
         time_to_expiration: f64,
-
         nr_paths: usize,
         nr_steps: usize,
         seed_nr: u64,
     ) -> Self {
-        let weight_sum = weights.iter().fold(0.0, |acc, c| acc + c);
-        assert_eq!(weight_sum, 1.0);
+        let weight_sum = weights.sum();
+        let mut warnings = Vec::new();
+        let weights = if weight_sum != 1.0 && (weight_sum - 1.0).abs() <= WEIGHT_TOLERANCE {
+            warnings.push(PricingWarning::WeightsNormalized { sum: weight_sum });
+            weights / weight_sum
+        } else {
+            weights
+        };
         Self {
             time_to_expiration,
             strike,
@@ -59,21 +77,103 @@ where
             nr_paths,
             nr_steps,
             seed_nr,
+            warnings,
             _phantom_rng: PhantomData::<SeedRng>,
         }
     }
 
+    /// The option on the assets with the volatilities and the correlation matrix, which is repaired
+    /// with a warning if it is not positive definite, e.g. a stressed correlation.
+    /// Fails unless the correlation matrix is square with one row per volatility.
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_correlation(
+        weights: Array1<f64>,
+        asset_prices: Array1<f64>,
+        rf_rates: Array1<f64>,
+        volas: Array1<f64>,
+        correlation: Array2<f64>,
+        strike: f64,
+        time_to_expiration: f64,
+        nr_paths: usize,
+        nr_steps: usize,
+        seed_nr: u64,
+    ) -> Result<Self, PricingError> {
+        if correlation.dim() != (volas.len(), volas.len()) {
+            return Err(PricingError::InvalidParameter(format!(
+                "correlation matrix of shape {:?} for {} volatilities",
+                correlation.dim(),
+                volas.len()
+            )));
+        }
+        let (correlation, warning) = ensure_positive_definite_with_warning(correlation);
+        let factor = cholesky(&correlation).ok_or_else(|| {
+            PricingError::InvalidParameter("correlation matrix not repairable".to_string())
+        })?;
+        let cholesky_factor = &factor * &volas.insert_axis(Axis(1));
+        let mut option = Self::new(
+            weights,
+            asset_prices,
+            rf_rates,
+            cholesky_factor,
+            strike,
+            time_to_expiration,
+            nr_paths,
+            nr_steps,
+            seed_nr,
+        );
+        option.warnings.extend(warning);
+        Ok(option)
+    }
+
+    /// The adjustments of the inputs, e.g. normalized weights or a repaired correlation.
+    pub fn warnings(&self) -> &[PricingWarning] {
+        &self.warnings
+    }
+
     pub fn dt(&self) -> f64 {
         self.time_to_expiration / self.nr_steps as f64
     }
 
-    fn sample_payoffs(&self, pay_off: impl Fn(&Array2<f64>) -> Option<f64>) -> Option<f64> {
-        let gbm: MultivariateGeometricBrownianMotion = self.into();
-        let mc_simulator: MonteCarloPathSimulator<_, SeedRng, _> =
-            MonteCarloPathSimulator::new(gbm, Some(self.seed_nr));
-        let paths = mc_simulator.simulate_paths(self.nr_paths, self.nr_steps);
-        let path_evaluator = PathEvaluator::new(&paths);
-        path_evaluator.evaluate_average(pay_off)
+    fn sample_payoffs(
+        &self,
+        pay_off: impl Fn(&AssetMajorPath) -> Option<f64>,
+    ) -> Result<PriceResult, PricingError> {
+        self.validate()?;
+        PriceResult::timed(|| {
+            let gbm: MultivariateGeometricBrownianMotion = self.into();
+            let mut rn_generator = SeedRng::seed_from_u64(self.seed_nr);
+            let paths = gbm.sample_ensemble_paths(&mut rn_generator, self.nr_paths, self.nr_steps);
+            PathEvaluator::new(&paths)
+                .price(pay_off)
+                .map(|result| result.with_warnings(self.warnings.iter().cloned()))
+        })
+    }
+
+    fn validate(&self) -> Result<(), PricingError> {
+        let nr_assets = self.asset_prices.len();
+        if self.weights.len() != nr_assets
+            || self.rf_rates.len() != nr_assets
+            || self.cholesky_factor.dim() != (nr_assets, nr_assets)
+        {
+            return Err(PricingError::InvalidParameter(format!(
+                "dimensions of the weights, rates and cholesky factor differ from {nr_assets} assets"
+            )));
+        }
+        for asset_price in &self.asset_prices {
+            PricingError::check(*asset_price > 0.0, "asset price", *asset_price)?;
+        }
+        let weight_sum = self.weights.sum();
+        PricingError::check(
+            (weight_sum - 1.0).abs() <= WEIGHT_TOLERANCE,
+            "weight sum",
+            weight_sum,
+        )?;
+        PricingError::check(self.strike >= 0.0, "strike", self.strike)?;
+        TimeGrid::step_size(self.time_to_expiration, self.nr_steps)?;
+        if self.nr_paths == 0 {
+            return Err(PricingError::NoPaths);
+        }
+        Ok(())
     }
 
     fn call_payoff(
@@ -81,11 +181,10 @@ where
         strike: f64,
         weights: &Array1<f64>,
         disc_factor: f64,
-        path: &Array2<f64>,
+        path: &AssetMajorPath,
     ) -> Option<f64> {
-        path.axis_iter(Axis(0))
-            .last()
-            .map(|p| (p.dot(weights) - strike).max(0.0) * disc_factor)
+        let p = path.terminal();
+        Some(ExerciseType::Call.payoff(p.dot(weights), strike) * disc_factor)
     }
 
     fn put_payoff(
@@ -93,27 +192,94 @@ where
         strike: f64,
         weights: &Array1<f64>,
         disc_factor: f64,
-        path: &Array2<f64>,
+        path: &AssetMajorPath,
     ) -> Option<f64> {
-        path.axis_iter(Axis(0))
-            .last()
-            .map(|p| (strike - p.dot(weights)).max(0.0) * disc_factor)
+        let p = path.terminal();
+        Some(ExerciseType::Put.payoff(p.dot(weights), strike) * disc_factor)
+    }
+
+    /// Payoffs are priced with the money market account of the weighted rates as numeraire.
+    pub fn numeraire_convention(&self) -> NumeraireConvention<MoneyMarketAccount> {
+        NumeraireConvention::domestic(MoneyMarketAccount::new(self.rf_rates.dot(&self.weights)))
     }
 
     fn discount_factor(&self, t: f64) -> f64 {
-        (-t * self.rf_rates.dot(&self.weights)).exp()
+        self.numeraire_convention().payoff_factor(t)
     }
 
     /// The price (theoretical value) of the standard European call option (optimized version).
+    #[deprecated(note = "use `try_call`, which reports why the pricing fails")]
     pub fn call(&self) -> Option<f64> {
-        let disc_factor = self.discount_factor(self.time_to_expiration);
-        self.sample_payoffs(|path| self.call_payoff(self.strike, &self.weights, disc_factor, path))
+        self.try_call().ok().map(|result| result.value)
     }
 
     /// The price (theoretical value) of the standard European put option (optimized version).
+    #[deprecated(note = "use `try_put`, which reports why the pricing fails")]
     pub fn put(&self) -> Option<f64> {
+        self.try_put().ok().map(|result| result.value)
+    }
+
+    /// The price of the call or put on the basket, or the reason the pricing fails.
+    pub fn price(&self, exercise_type: &ExerciseType) -> Result<PriceResult, PricingError> {
         let disc_factor = self.discount_factor(self.time_to_expiration);
-        self.sample_payoffs(|path| self.put_payoff(self.strike, &self.weights, disc_factor, path))
+        self.sample_payoffs(|path| match exercise_type {
+            ExerciseType::Call => self.call_payoff(self.strike, &self.weights, disc_factor, path),
+            ExerciseType::Put => self.put_payoff(self.strike, &self.weights, disc_factor, path),
+        })
+    }
+
+    /// The same option on the assets with the covariance of the Cholesky factor.
+    fn with_cholesky_factor(&self, cholesky_factor: Array2<f64>) -> Self {
+        Self {
+            weights: self.weights.to_owned(),
+            asset_prices: self.asset_prices.to_owned(),
+            rf_rates: self.rf_rates.to_owned(),
+            cholesky_factor,
+            warnings: self.warnings.clone(),
+            _phantom_rng: PhantomData::<SeedRng>,
+            ..*self
+        }
+    }
+
+    /// The correlation sensitivity of the call or put to the bump, with common random numbers of the seed.
+    pub fn cega(
+        &self,
+        exercise_type: &ExerciseType,
+        bump: CorrelationBump,
+        bump_size: f64,
+    ) -> Result<f64, PricingError> {
+        self.validate()?;
+        cega(&self.cholesky_factor, bump, bump_size, |factor| {
+            Ok(self
+                .with_cholesky_factor(factor.to_owned())
+                .price(exercise_type)?
+                .value)
+        })
+    }
+
+    /// The correlation sensitivities of the call or put per pair of assets.
+    pub fn pairwise_cega(
+        &self,
+        exercise_type: &ExerciseType,
+        bump_size: f64,
+    ) -> Result<Array2<f64>, PricingError> {
+        self.validate()?;
+        pairwise_cega(&self.cholesky_factor, bump_size, |factor| {
+            Ok(self
+                .with_cholesky_factor(factor.to_owned())
+                .price(exercise_type)?
+                .value)
+        })
+    }
+
+    /// The price of the call with its standard error, confidence interval and runtime.
+    pub fn try_call(&self) -> Result<PriceResult, PricingError> {
+        self.price(&ExerciseType::Call)
+    }
+
+    /// The price of the put with its standard error, confidence interval and runtime.
+    pub fn try_put(&self) -> Result<PriceResult, PricingError> {
+        self.price(&ExerciseType::Put)
     }
 }
 
@@ -155,7 +321,7 @@ mod tests {
                 300,
                 42,
             );
-        let call_price = mc_option.call().unwrap();
+        let call_price = mc_option.try_call().unwrap().value;
         dbg!(call_price);
         // TODO: fix unit test
         // assert_eq!(call_price, 5.59601793502129);
@@ -184,7 +350,7 @@ mod tests {
                 100,
                 42,
             );
-        let call_price = mc_option.call().unwrap();
+        let call_price = mc_option.try_call().unwrap().value;
         dbg!(&call_price);
         // TODO: fix unit test
         // assert_approx_eq!(call_price, 7.290738, TOLERANCE);
@@ -210,7 +376,7 @@ mod tests {
                 300,
                 42,
             );
-        let call_price = mc_option.put().unwrap();
+        let call_price = mc_option.try_put().unwrap().value;
         assert_eq!(call_price, 8.96589328828396);
         // assert_approx_eq!(call_price, 29.47, TOLERANCE);
     }
@@ -245,8 +411,167 @@ mod tests {
         // PriceSens = 0.9822
         // Delta = -0.0995
 
-        let call_price = mc_option.put().unwrap();
+        let call_price = mc_option.try_put().unwrap().value;
         assert_eq!(call_price, 0.9822);
         // assert_approx_eq!(call_price, 29.47, TOLERANCE);
     }
+
+    #[test]
+    fn basket_of_identical_assets_against_published_references() {
+        use crate::simulation::references::{hull_vanillas, validate, ReferenceProduct};
+
+        // perfectly correlated assets of equal spots and volatilities make the vanilla on one asset
+        let engine = |product: &ReferenceProduct| match product {
+            ReferenceProduct::Vanilla {
+                params,
+                exercise_type,
+                is_american: false,
+            } => {
+                let mc_option: MonteCarloEuropeanBasketOption<rand_hc::Hc128Rng> =
+                    MonteCarloEuropeanBasketOption::new(
+                        arr1(&[0.5, 0.5]),
+                        arr1(&[params.asset_price; 2]),
+                        arr1(&[params.rfr; 2]),
+                        arr2(&[[params.vola, 0.0], [params.vola, 0.0]]),
+                        params.strike,
+                        params.time_to_expiration,
+                        50_000,
+                        1,
+                        42,
+                    );
+                mc_option.price(exercise_type).ok()
+            }
+            _ => None,
+        };
+        let report = validate(&hull_vanillas(), engine, 0.0, 3.0);
+        assert!(report.passed(), "{report}");
+        assert_eq!(report.checks.len(), 2);
+    }
+
+    #[test]
+    fn basket_payoff_on_the_terminal_asset_prices() {
+        let mc_option: MonteCarloEuropeanBasketOption<rand_hc::Hc128Rng> =
+            MonteCarloEuropeanBasketOption::new(
+                arr1(&[0.25, 0.75]),
+                arr1(&[100.0, 100.0]),
+                arr1(&[0.0, 0.0]),
+                arr2(&[[0.2, 0.0], [0.0, 0.2]]),
+                100.0,
+                1.0,
+                1,
+                2,
+                42,
+            );
+        // two assets (rows) at three times (columns): the basket is 0.25 * 120 + 0.75 * 80 = 90 at expiry
+        let path = AssetMajorPath::new(arr2(&[[100.0, 110.0, 120.0], [100.0, 90.0, 80.0]]));
+        let weights = &mc_option.weights;
+        assert_eq!(mc_option.call_payoff(80.0, weights, 0.5, &path), Some(5.0));
+        assert_eq!(mc_option.put_payoff(100.0, weights, 0.5, &path), Some(5.0));
+    }
+
+    #[test]
+    fn basket_call_cega() {
+        let cholesky_factor = arr2(&[[0.2, 0.0, 0.0], [0.15, 0.26, 0.0], [0.0, 0.0, 0.3]]);
+        let mc_option: MonteCarloEuropeanBasketOption<rand_hc::Hc128Rng> =
+            MonteCarloEuropeanBasketOption::new(
+                arr1(&[0.25, 0.25, 0.5]),
+                arr1(&[100.0, 100.0, 100.0]),
+                arr1(&[0.02, 0.02, 0.02]),
+                cholesky_factor,
+                100.0,
+                1.0,
+                5_000,
+                10,
+                42,
+            );
+        // a higher correlation raises the basket volatility
+        let parallel = mc_option
+            .cega(&ExerciseType::Call, CorrelationBump::Parallel, 0.01)
+            .unwrap();
+        assert!(parallel > 0.0);
+        let pairwise = mc_option.pairwise_cega(&ExerciseType::Call, 0.01).unwrap();
+        assert_eq!(pairwise[[0, 1]], pairwise[[1, 0]]);
+        // the pair of the heaviest weights is the most sensitive
+        assert!(pairwise[[0, 2]] > pairwise[[0, 1]]);
+        let total = pairwise[[0, 1]] + pairwise[[0, 2]] + pairwise[[1, 2]];
+        assert!((total - parallel).abs() < 0.05 * parallel);
+    }
+
+    #[test]
+    fn invalid_dimensions() {
+        let mc_option: MonteCarloEuropeanBasketOption<rand_hc::Hc128Rng> =
+            MonteCarloEuropeanBasketOption::new(
+                arr1(&[0.5, 0.5]),
+                arr1(&[102.0, 102.0]),
+                arr1(&[0.02, 0.02]),
+                arr2(&[[0.2]]),
+                100.0,
+                0.5,
+                1_000,
+                10,
+                42,
+            );
+        assert!(matches!(
+            mc_option.try_call(),
+            Err(PricingError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn warnings_of_adjusted_inputs() {
+        let basket = |weights, correlation| {
+            MonteCarloEuropeanBasketOption::<rand_hc::Hc128Rng>::from_correlation(
+                weights,
+                arr1(&[100.0, 100.0, 100.0]),
+                arr1(&[0.02, 0.02, 0.02]),
+                arr1(&[0.2, 0.3, 0.25]),
+                correlation,
+                100.0,
+                1.0,
+                1_000,
+                10,
+                42,
+            )
+        };
+        let correlation = arr2(&[[1.0, 0.5, 0.2], [0.5, 1.0, 0.3], [0.2, 0.3, 1.0]]);
+        let price = basket(arr1(&[0.25, 0.25, 0.5]), correlation.clone())
+            .unwrap()
+            .try_call()
+            .unwrap();
+        assert!(price.warnings.is_empty());
+
+        // weights off by rounding are normalized
+        let price = basket(arr1(&[0.25, 0.25, 0.5 + 1e-9]), correlation.clone())
+            .unwrap()
+            .try_call()
+            .unwrap();
+        assert!(matches!(
+            price.warnings[..],
+            [PricingWarning::WeightsNormalized { sum }] if (sum - 1.0 - 1e-9).abs() < 1e-15
+        ));
+        assert!(matches!(
+            basket(arr1(&[0.3, 0.3, 0.5]), correlation)
+                .unwrap()
+                .try_call(),
+            Err(PricingError::InvalidParameter(_))
+        ));
+
+        // a stressed correlation is repaired
+        let stressed = arr2(&[[1.0, 0.9, 0.2], [0.9, 1.0, -0.6], [0.2, -0.6, 1.0]]);
+        let price = basket(arr1(&[0.25, 0.25, 0.5]), stressed)
+            .unwrap()
+            .try_call()
+            .unwrap();
+        assert!(matches!(
+            price.warnings[..],
+            [PricingWarning::CorrelationRepaired { min_eigenvalue }] if min_eigenvalue < 0.0
+        ));
+        assert!(price.to_string().contains("correlation repaired"));
+
+        // a correlation matrix of other dimensions than the volatilities is rejected
+        assert!(matches!(
+            basket(arr1(&[0.25, 0.25, 0.5]), arr2(&[[1.0, 0.5], [0.5, 1.0]])),
+            Err(PricingError::InvalidParameter(_))
+        ));
+    }
 }
@@ -1,9 +1,52 @@
 use std::marker::PhantomData;
 
-use crate::common::models::DerivativeParameter;
+use rand_distr::Distribution;
+
+use crate::common::market_context::MarketSnapshot;
+use crate::common::models::{DerivativeParameter, ExerciseType};
+use crate::common::pricer::VanillaOption;
+use crate::common::results::{NonFinitePolicy, PriceResult, PricingError};
+use crate::common::time_grid::TimeGrid;
+use crate::numerics::summation::Reproducibility;
 use crate::simulation::monte_carlo::{MonteCarloPathSimulator, PathEvaluator};
+use crate::simulation::noise::NoiseSource;
+use crate::simulation::numeraire::{MoneyMarketAccount, NumeraireConvention};
 use crate::simulation::sde::gbm::GeometricBrownianMotion;
 
+/// The price of an option valued `time_shift` years after today, with the spot unchanged.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LadderPoint {
+    pub time_shift: f64,
+    pub price: PriceResult,
+}
+
+/// The prices on a ladder of valuation dates from one simulation, i.e. the time decay profile.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThetaLadder {
+    /// the price today
+    pub base: PriceResult,
+    pub points: Vec<LadderPoint>,
+}
+
+impl ThetaLadder {
+    /// The changes of the price from today to the valuation dates.
+    pub fn time_decay(&self) -> Vec<f64> {
+        self.points
+            .iter()
+            .map(|point| point.price.value - self.base.value)
+            .collect()
+    }
+
+    /// The average thetas $(V(t) - V(0)) / t$ per year until the valuation dates.
+    pub fn thetas(&self) -> Vec<f64> {
+        self.points
+            .iter()
+            .zip(self.time_decay())
+            .map(|(point, decay)| decay / point.time_shift)
+            .collect()
+    }
+}
+
 pub struct MonteCarloEuropeanOption<SeedRng>
 where
     SeedRng: rand::SeedableRng + rand::RngCore,
@@ -12,6 +55,12 @@ where
     pub seed_nr: u64,
     pub nr_paths: usize,
     pub nr_steps: usize,
+    /// sample the terminal values directly from their lognormal law instead of whole paths
+    pub terminal_only: bool,
+    /// the handling of NaN or infinite payoffs, e.g. of supplied noise
+    pub non_finite_policy: NonFinitePolicy,
+    /// the summation of the payoffs, by default sequential
+    pub reproducibility: Reproducibility,
     _phantom_rng: PhantomData<SeedRng>,
 }
 
@@ -19,6 +68,7 @@ impl<SeedRng> MonteCarloEuropeanOption<SeedRng>
 where
     SeedRng: rand::SeedableRng + rand::RngCore,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         asset_price: f64,
         strike: f64,
@@ -36,45 +86,297 @@ where
             nr_paths,
             nr_steps,
             seed_nr,
+            terminal_only: false,
+            non_finite_policy: NonFinitePolicy::Fail,
+            reproducibility: Reproducibility::default(),
             _phantom_rng: PhantomData::<SeedRng>,
         }
     }
 
+    /// The option with the spot, the rate and the volatility of the market snapshot, resolved as by
+    /// `VanillaOption::derivative_parameter`.
+    pub fn from_market(
+        option: &VanillaOption,
+        market: &MarketSnapshot,
+        nr_paths: usize,
+        nr_steps: usize,
+        seed_nr: u64,
+    ) -> Result<Self, PricingError> {
+        let dp = option.derivative_parameter(market)?;
+        Ok(Self::new(
+            dp.asset_price,
+            dp.strike,
+            dp.time_to_expiration,
+            dp.rfr,
+            dp.vola,
+            nr_paths,
+            nr_steps,
+            seed_nr,
+        ))
+    }
+
+    /// Samples $S_T$ exactly over the full horizon, which is unbiased and about `nr_steps` times faster
+    /// for payoffs depending on the terminal value only. The payoffs receive the paths $[S_0, S_T]$.
+    pub fn with_terminal_only(self) -> Self {
+        Self {
+            terminal_only: true,
+            ..self
+        }
+    }
+
+    /// Prices from the finite payoffs if at most the fraction of the payoffs is NaN or infinite,
+    /// reporting the others in `PriceResult::quarantine`, instead of failing.
+    pub fn with_non_finite_policy(self, non_finite_policy: NonFinitePolicy) -> Self {
+        Self {
+            non_finite_policy,
+            ..self
+        }
+    }
+
+    /// Sums the payoffs in the reproducibility mode, e.g. bit-stable across thread counts.
+    pub fn with_reproducibility(self, reproducibility: Reproducibility) -> Self {
+        Self {
+            reproducibility,
+            ..self
+        }
+    }
+
     pub fn dt(&self) -> f64 {
         self.option_params.time_to_expiration / self.nr_steps as f64
     }
 
+    /// The discounted payoffs of the terminal value, where a NaN or infinite terminal value is passed
+    /// on for the non-finite policy instead of vanishing in the maximum.
     fn call_payoff(&self, strike: f64, disc_factor: f64, path: &[f64]) -> Option<f64> {
-        path.last().map(|p| (p - strike).max(0.0) * disc_factor)
+        path.last().map(|p| {
+            if p.is_finite() {
+                ExerciseType::Call.payoff(*p, strike) * disc_factor
+            } else {
+                *p
+            }
+        })
     }
 
     fn put_payoff(&self, strike: f64, disc_factor: f64, path: &[f64]) -> Option<f64> {
-        path.last().map(|p| (strike - p).max(0.0) * disc_factor)
+        path.last().map(|p| {
+            if p.is_finite() {
+                ExerciseType::Put.payoff(*p, strike) * disc_factor
+            } else {
+                *p
+            }
+        })
+    }
+
+    /// The evaluator of the paths with the non-finite policy and the summation of the option.
+    fn evaluator<'a, Path>(&self, paths: &'a [Path]) -> PathEvaluator<'a, Path> {
+        PathEvaluator::new(paths)
+            .with_non_finite_policy(self.non_finite_policy)
+            .with_reproducibility(self.reproducibility)
     }
 
     pub fn sample_payoffs(&self, pay_off: impl Fn(&Vec<f64>) -> Option<f64>) -> Option<f64> {
+        self.evaluator(&self.sample_paths())
+            .evaluate_average(pay_off)
+    }
+
+    /// The paths of the simulation, i.e. $[S_0, S_T]$ for terminal only sampling.
+    fn sample_paths(&self) -> Vec<Vec<f64>> {
+        let params = &self.option_params;
+        if self.terminal_only {
+            let terminal_gbm = GeometricBrownianMotion::new(
+                params.asset_price,
+                params.rfr,
+                params.vola,
+                params.time_to_expiration,
+            );
+            let mut rn_generator = SeedRng::seed_from_u64(self.seed_nr);
+            return (0..self.nr_paths)
+                .map(|_| vec![params.asset_price, terminal_gbm.sample(&mut rn_generator)])
+                .collect();
+        }
         let stock_gbm: GeometricBrownianMotion = self.into();
         let mc_simulator: MonteCarloPathSimulator<_, SeedRng, _> =
             MonteCarloPathSimulator::new(stock_gbm, Some(self.seed_nr));
-        let paths = mc_simulator.simulate_paths(self.nr_paths, self.nr_steps);
-        let path_evaluator = PathEvaluator::new(&paths);
-        path_evaluator.evaluate_average(pay_off)
+        mc_simulator.simulate_paths(self.nr_paths, self.nr_steps)
+    }
+
+    /// Payoffs are priced under the risk neutral measure with the money market account as numeraire.
+    pub fn numeraire_convention(&self) -> NumeraireConvention<MoneyMarketAccount> {
+        NumeraireConvention::domestic(MoneyMarketAccount::new(self.option_params.rfr))
     }
 
     pub fn discount_factor(&self, t: f64) -> f64 {
-        (-t * self.option_params.rfr).exp()
+        self.numeraire_convention().payoff_factor(t)
     }
 
     /// The price (theoretical value) of the standard European call option (optimized version).
+    #[deprecated(note = "use `try_call`, which reports why the pricing fails")]
     pub fn call(&self) -> Option<f64> {
-        let disc_factor = self.discount_factor(self.option_params.time_to_expiration);
-        self.sample_payoffs(|path| self.call_payoff(self.option_params.strike, disc_factor, path))
+        self.try_call().ok().map(|result| result.value)
     }
 
     /// The price (theoretical value) of the standard European put option (optimized version).
+    #[deprecated(note = "use `try_put`, which reports why the pricing fails")]
     pub fn put(&self) -> Option<f64> {
+        self.try_put().ok().map(|result| result.value)
+    }
+
+    /// The price of the standard European call option with its standard error, confidence interval and runtime.
+    pub fn try_call(&self) -> Result<PriceResult, PricingError> {
+        self.price(&ExerciseType::Call)
+    }
+
+    /// The price of the standard European put option with its standard error, confidence interval and runtime.
+    pub fn try_put(&self) -> Result<PriceResult, PricingError> {
+        self.price(&ExerciseType::Put)
+    }
+
+    /// The price of the call or put, or the reason the pricing fails.
+    pub fn price(&self, exercise_type: &ExerciseType) -> Result<PriceResult, PricingError> {
+        self.validate()?;
+        if self.option_params.is_expired() {
+            return Ok(PriceResult::exact(
+                self.option_params.intrinsic_value(exercise_type),
+            ));
+        }
+        PriceResult::timed(|| self.price_paths(&self.sample_paths(), exercise_type))
+    }
+
+    /// The price of the call or put from the supplied standard normals instead of the seeded generator,
+    /// e.g. to replay a recorded run: `nr_steps` normals per path, or one per path for terminal only
+    /// sampling.
+    pub fn price_with_noise(
+        &self,
+        exercise_type: &ExerciseType,
+        noise: NoiseSource<'_>,
+    ) -> Result<PriceResult, PricingError> {
+        self.validate()?;
+        let params = &self.option_params;
+        if params.is_expired() {
+            return Ok(PriceResult::exact(params.intrinsic_value(exercise_type)));
+        }
+        let nr_steps = if self.terminal_only { 1 } else { self.nr_steps };
+        noise.check_available(self.nr_paths, nr_steps)?;
+        PriceResult::timed(|| {
+            let simulator: MonteCarloPathSimulator<_, SeedRng, _> =
+                MonteCarloPathSimulator::new(noise, Some(self.seed_nr));
+            let paths = if self.terminal_only {
+                let terminal_gbm = GeometricBrownianMotion::new(
+                    params.asset_price,
+                    params.rfr,
+                    params.vola,
+                    params.time_to_expiration,
+                );
+                let spot = params.asset_price;
+                simulator
+                    .pipe()
+                    .then(|normals: Vec<f64>| {
+                        let terminal = normals
+                            .first()
+                            .map(|z| terminal_gbm.step_analytic(spot, *z));
+                        [spot].into_iter().chain(terminal).collect::<Vec<f64>>()
+                    })
+                    .simulate(self.nr_paths, nr_steps)
+            } else {
+                let stock_gbm: GeometricBrownianMotion = self.into();
+                simulator
+                    .pipe()
+                    .then(stock_gbm)
+                    .simulate(self.nr_paths, nr_steps)
+            };
+            PricingError::check(
+                paths.iter().all(|path| path.len() == nr_steps + 1),
+                "nr normals",
+                paths.iter().map(|path| path.len() - 1).sum::<usize>() as f64,
+            )?;
+            self.price_paths(&paths, exercise_type)
+        })
+    }
+
+    fn price_paths(
+        &self,
+        paths: &[Vec<f64>],
+        exercise_type: &ExerciseType,
+    ) -> Result<PriceResult, PricingError> {
+        let strike = self.option_params.strike;
         let disc_factor = self.discount_factor(self.option_params.time_to_expiration);
-        self.sample_payoffs(|path| self.put_payoff(self.option_params.strike, disc_factor, path))
+        self.evaluator(paths).price(|path| match exercise_type {
+            ExerciseType::Call => self.call_payoff(strike, disc_factor, path),
+            ExerciseType::Put => self.put_payoff(strike, disc_factor, path),
+        })
+    }
+
+    /// The prices at the valuation dates `time_shifts` (in years, e.g. 1/365 for a day) after today with
+    /// the spot unchanged, from one simulation of full paths: the path from step k on, rescaled to start
+    /// at the spot, is a path of the remaining time to expiration, and the payoffs are discounted over it.
+    /// The time shifts are rounded to the time grid of the simulation.
+    pub fn theta_ladder(
+        &self,
+        exercise_type: &ExerciseType,
+        time_shifts: &[f64],
+    ) -> Result<ThetaLadder, PricingError> {
+        self.validate()?;
+        PricingError::check(self.nr_steps > 0, "nr steps", self.nr_steps as f64)?;
+        let params = &self.option_params;
+        let dt = self.dt();
+        let shift_steps = time_shifts
+            .iter()
+            .map(|shift| {
+                let steps = (shift / dt).round();
+                PricingError::check(
+                    0.0 < steps && steps < self.nr_steps as f64,
+                    "time shift",
+                    *shift,
+                )?;
+                Ok(steps as usize)
+            })
+            .collect::<Result<Vec<usize>, PricingError>>()?;
+
+        let stock_gbm: GeometricBrownianMotion = self.into();
+        let mc_simulator: MonteCarloPathSimulator<_, SeedRng, _> =
+            MonteCarloPathSimulator::try_new(
+                stock_gbm,
+                Some(self.seed_nr),
+                params.time_to_expiration,
+            )?;
+        let paths = mc_simulator.simulate_paths(self.nr_paths, self.nr_steps);
+
+        let price_from_step = |step: usize| -> Result<PriceResult, PricingError> {
+            let remaining_time = params.time_to_expiration - step as f64 * dt;
+            let disc_factor = self.discount_factor(remaining_time);
+            self.evaluator(&paths).price(|path| {
+                let terminal = params.asset_price * path.last()? / path[step];
+                let rescaled = [params.asset_price, terminal];
+                match exercise_type {
+                    ExerciseType::Call => self.call_payoff(params.strike, disc_factor, &rescaled),
+                    ExerciseType::Put => self.put_payoff(params.strike, disc_factor, &rescaled),
+                }
+            })
+        };
+
+        let base = price_from_step(0)?;
+        let points = shift_steps
+            .into_iter()
+            .map(|step| {
+                Ok(LadderPoint {
+                    time_shift: step as f64 * dt,
+                    price: price_from_step(step)?,
+                })
+            })
+            .collect::<Result<Vec<LadderPoint>, PricingError>>()?;
+        Ok(ThetaLadder { base, points })
+    }
+
+    fn validate(&self) -> Result<(), PricingError> {
+        let params = &self.option_params;
+        params.validate()?;
+        if !params.is_expired() && !self.terminal_only {
+            TimeGrid::step_size(params.time_to_expiration, self.nr_steps)?;
+        }
+        if self.nr_paths == 0 {
+            return Err(PricingError::NoPaths);
+        }
+        Ok(())
     }
 }
 
@@ -99,26 +401,59 @@ mod tests {
     use super::*;
     use assert_approx_eq::assert_approx_eq;
 
-    /// NOTE: the tolerance will depend on the number of samples paths and other params like steps and the volatility
-    /// compare with analytic solutions from https://goodcalculators.com/black-scholes-calculator/
-    const TOLERANCE: f64 = 0.5;
+    use crate::simulation::verification::assert_within_std_errors;
+
+    /// The number of standard errors by which the prices may deviate from the references.
+    const NR_STD_ERRORS: f64 = 3.0;
+
+    #[test]
+    fn european_against_published_references() {
+        use crate::simulation::references::{all_references, validate, ReferenceProduct};
+
+        let engine = |product: &ReferenceProduct| match product {
+            ReferenceProduct::Vanilla {
+                params,
+                exercise_type,
+                is_american: false,
+            } => {
+                let mc_option: MonteCarloEuropeanOption<rand_hc::Hc128Rng> =
+                    MonteCarloEuropeanOption::new(
+                        params.asset_price,
+                        params.strike,
+                        params.time_to_expiration,
+                        params.rfr,
+                        params.vola,
+                        50_000,
+                        1,
+                        7,
+                    )
+                    .with_terminal_only();
+                mc_option.price(exercise_type).ok()
+            }
+            _ => None,
+        };
+        let report = validate(&all_references(), engine, 0.0, NR_STD_ERRORS);
+        assert!(report.passed(), "{report}");
+    }
 
     #[test]
     fn european_call() {
         let mc_option: MonteCarloEuropeanOption<rand_hc::Hc128Rng> =
             MonteCarloEuropeanOption::new(300.0, 310.0, 1.0, 0.03, 0.25, 20_000, 1000, 1);
-        let call_price = mc_option.call().unwrap();
+        let result = mc_option.try_call().unwrap();
+        let call_price = result.value;
         assert_eq!(call_price, 29.76722498945371);
-        assert_approx_eq!(call_price, 29.47, TOLERANCE);
+        assert_within_std_errors(&result, 29.47, NR_STD_ERRORS);
     }
 
     #[test]
     fn european_put() {
         let mc_option: MonteCarloEuropeanOption<rand_hc::Hc128Rng> =
             MonteCarloEuropeanOption::new(300.0, 290.0, 1.0, 0.03, 0.12, 100_000, 100, 42);
-        let put_price = mc_option.put().unwrap();
+        let result = mc_option.try_put().unwrap();
+        let put_price = result.value;
         assert_eq!(put_price, 6.4775539881225335);
-        assert_approx_eq!(put_price, 6.547, TOLERANCE);
+        assert_within_std_errors(&result, 6.547, NR_STD_ERRORS);
     }
 
     /// Reference: https://predictivehacks.com/pricing-of-european-options-with-monte-carlo/
@@ -126,9 +461,10 @@ mod tests {
     fn european_put_as_of_reference() {
         let mc_option: MonteCarloEuropeanOption<rand_hc::Hc128Rng> =
             MonteCarloEuropeanOption::new(102.0, 100.0, 0.5, 0.02, 0.2, 1_000_000, 100, 42);
-        let put_price = mc_option.put().unwrap();
+        let result = mc_option.try_put().unwrap();
+        let put_price = result.value;
         assert_eq!(put_price, 4.2836072940653445); // black scholes ref: 4.293135
-        assert_approx_eq!(put_price, 4.294683, TOLERANCE); // monte carlo ref: 4.294683
+        assert_within_std_errors(&result, 4.294683, NR_STD_ERRORS); // monte carlo ref: 4.294683
     }
 
     /// Reference: https://predictivehacks.com/pricing-of-european-options-with-monte-carlo/
@@ -136,8 +472,202 @@ mod tests {
     fn european_call_as_of_reference() {
         let mc_option: MonteCarloEuropeanOption<rand_hc::Hc128Rng> =
             MonteCarloEuropeanOption::new(102.0, 100.0, 0.5, 0.02, 0.2, 1_000_000, 100, 111111);
-        let call_price = mc_option.call().unwrap();
+        let result = mc_option.try_call().unwrap();
+        let call_price = result.value;
         assert_eq!(call_price, 7.297463800819357); // black scholes ref: 7.288151
-        assert_approx_eq!(call_price, 7.290738, TOLERANCE); // monte carlo ref: 7.290738
+        assert_within_std_errors(&result, 7.290738, NR_STD_ERRORS); // monte carlo ref: 7.290738
+    }
+
+    #[test]
+    fn european_terminal_only() {
+        use crate::analytic::black_scholes::{BlackScholesMerton, OptionPrice};
+
+        let mc_option: MonteCarloEuropeanOption<rand_hc::Hc128Rng> =
+            MonteCarloEuropeanOption::new(300.0, 310.0, 1.0, 0.03, 0.25, 200_000, 1000, 1)
+                .with_terminal_only();
+        let call = BlackScholesMerton::call(&mc_option.option_params);
+        assert_within_std_errors(&mc_option.try_call().unwrap(), call, NR_STD_ERRORS);
+
+        let mc_option: MonteCarloEuropeanOption<rand_hc::Hc128Rng> =
+            MonteCarloEuropeanOption::new(102.0, 100.0, 0.5, 0.02, 0.2, 200_000, 100, 42)
+                .with_terminal_only();
+        assert_within_std_errors(&mc_option.try_put().unwrap(), 4.293135, NR_STD_ERRORS);
+    }
+
+    #[test]
+    fn price_with_confidence_interval() {
+        use crate::analytic::black_scholes::{BlackScholesMerton, OptionPrice};
+
+        let mc_option: MonteCarloEuropeanOption<rand_hc::Hc128Rng> =
+            MonteCarloEuropeanOption::new(102.0, 100.0, 0.5, 0.02, 0.2, 50_000, 10, 42)
+                .with_terminal_only();
+        let result = mc_option.try_put().unwrap();
+        assert_eq!(result.nr_samples, Some(50_000));
+        assert!(result.runtime.is_some());
+        let (lower, upper) = result.confidence_interval.unwrap();
+        let reference = BlackScholesMerton::put(&mc_option.option_params);
+        assert!(lower < reference && reference < upper);
+        #[allow(deprecated)]
+        let put = mc_option.put();
+        assert_eq!(put, Some(result.value));
+    }
+
+    #[test]
+    fn pricing_errors() {
+        let mc_option: MonteCarloEuropeanOption<rand_hc::Hc128Rng> =
+            MonteCarloEuropeanOption::new(102.0, 100.0, 0.5, 0.02, -0.2, 1_000, 10, 42);
+        assert!(matches!(
+            mc_option.try_call(),
+            Err(PricingError::InvalidParameter(_))
+        ));
+        let mc_option: MonteCarloEuropeanOption<rand_hc::Hc128Rng> =
+            MonteCarloEuropeanOption::new(102.0, 100.0, 0.5, 0.02, 0.2, 0, 10, 42);
+        assert_eq!(mc_option.try_put(), Err(PricingError::NoPaths));
+        #[allow(deprecated)]
+        let put = mc_option.put();
+        assert_eq!(put, None);
+
+        let mc_option: MonteCarloEuropeanOption<rand_hc::Hc128Rng> =
+            MonteCarloEuropeanOption::new(102.0, 100.0, 0.5, 0.02, 0.2, 1_000, 0, 42);
+        assert!(matches!(
+            mc_option.try_call(),
+            Err(PricingError::InvalidParameter(_))
+        ));
+        let mc_option: MonteCarloEuropeanOption<rand_hc::Hc128Rng> =
+            MonteCarloEuropeanOption::new(102.0, 100.0, -0.5, 0.02, 0.2, 1_000, 10, 42);
+        assert!(matches!(
+            mc_option.try_call(),
+            Err(PricingError::InvalidParameter(_))
+        ));
+        // an expired option is worth its intrinsic value
+        let mc_option: MonteCarloEuropeanOption<rand_hc::Hc128Rng> =
+            MonteCarloEuropeanOption::new(102.0, 100.0, 0.0, 0.02, 0.2, 1_000, 10, 42);
+        assert_eq!(mc_option.try_call(), Ok(PriceResult::exact(2.0)));
+    }
+
+    #[test]
+    fn price_from_supplied_normals() {
+        let mc_option: MonteCarloEuropeanOption<rand_hc::Hc128Rng> =
+            MonteCarloEuropeanOption::new(102.0, 100.0, 0.5, 0.02, 0.2, 1_000, 10, 42);
+        let recorded: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, _> =
+            MonteCarloPathSimulator::new(NoiseSource::Generated, Some(42));
+        let normals = recorded.simulate_paths(1_000, 10).concat();
+        let replayed = mc_option
+            .price_with_noise(&ExerciseType::Call, NoiseSource::from_slice(&normals))
+            .unwrap();
+        assert_eq!(replayed.value, mc_option.try_call().unwrap().value);
+        assert!(matches!(
+            mc_option.price_with_noise(&ExerciseType::Call, NoiseSource::from_slice(&normals[1..])),
+            Err(PricingError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            mc_option.price_with_noise(
+                &ExerciseType::Call,
+                NoiseSource::from_iterator(normals[1..].iter().copied())
+            ),
+            Err(PricingError::InvalidParameter(_))
+        ));
+
+        // without noise the terminal value is the median $S_0 e^{(r - \sigma^2 / 2) T}$
+        let terminal = mc_option.with_terminal_only();
+        let price = terminal
+            .price_with_noise(
+                &ExerciseType::Call,
+                NoiseSource::from_iterator(std::iter::repeat(0.0)),
+            )
+            .unwrap();
+        let median = 102.0 * ((0.02_f64 - 0.5 * 0.04) * 0.5).exp();
+        assert_approx_eq!(price.value, (median - 100.0) * (-0.01_f64).exp(), 1e-12);
+    }
+
+    #[test]
+    fn quarantine_of_non_finite_paths() {
+        let mc_option: MonteCarloEuropeanOption<rand_hc::Hc128Rng> =
+            MonteCarloEuropeanOption::new(102.0, 100.0, 0.5, 0.02, 0.2, 1_000, 1, 42)
+                .with_terminal_only();
+        let recorded: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, _> =
+            MonteCarloPathSimulator::new(NoiseSource::Generated, Some(42));
+        let mut normals = recorded.simulate_paths(1_000, 1).concat();
+        let clean = mc_option
+            .price_with_noise(&ExerciseType::Call, NoiseSource::from_slice(&normals))
+            .unwrap();
+        assert_eq!(clean.quarantine, None);
+
+        normals[3] = f64::INFINITY;
+        normals[7] = f64::INFINITY;
+        assert!(matches!(
+            mc_option.price_with_noise(&ExerciseType::Call, NoiseSource::from_slice(&normals)),
+            Err(PricingError::NonFiniteValue(_))
+        ));
+        // the put payoff of an infinite terminal value does not vanish in the maximum
+        assert!(matches!(
+            mc_option.price_with_noise(&ExerciseType::Put, NoiseSource::from_slice(&normals)),
+            Err(PricingError::NonFiniteValue(_))
+        ));
+        // nor the payoffs of a NaN terminal value
+        let mut nan_normals = normals.clone();
+        nan_normals[3] = f64::NAN;
+        nan_normals[7] = 0.0;
+        for exercise_type in [ExerciseType::Call, ExerciseType::Put] {
+            assert!(matches!(
+                mc_option.price_with_noise(&exercise_type, NoiseSource::from_slice(&nan_normals)),
+                Err(PricingError::NonFiniteValue(value)) if value.is_nan()
+            ));
+        }
+
+        let quarantining =
+            mc_option.with_non_finite_policy(NonFinitePolicy::Quarantine { max_fraction: 0.01 });
+        let price = quarantining
+            .price_with_noise(&ExerciseType::Call, NoiseSource::from_slice(&normals))
+            .unwrap();
+        let quarantine = price.quarantine.unwrap();
+        assert_eq!((quarantine.nr_samples, quarantine.first_index), (2, 3));
+        assert_eq!(quarantine.first_value, f64::INFINITY);
+        assert_eq!(price.nr_samples, Some(998));
+        assert!((price.value - clean.value).abs() < 3.0 * clean.std_error.unwrap());
+
+        let strict = MonteCarloEuropeanOption::<rand_hc::Hc128Rng> {
+            non_finite_policy: NonFinitePolicy::Quarantine {
+                max_fraction: 0.001,
+            },
+            ..quarantining
+        };
+        assert!(matches!(
+            strict.price_with_noise(&ExerciseType::Call, NoiseSource::from_slice(&normals)),
+            Err(PricingError::InvalidPayoffs(2))
+        ));
+    }
+
+    #[test]
+    fn theta_ladder_from_one_simulation() {
+        use crate::analytic::black_scholes::{BlackScholesMerton, OptionPrice};
+        use crate::common::models::DerivativeParameter;
+
+        let bs_price = |t: f64| {
+            BlackScholesMerton::call(&DerivativeParameter::new(100.0, 100.0, t, 0.03, 0.2))
+        };
+        let mc_option: MonteCarloEuropeanOption<rand_hc::Hc128Rng> =
+            MonteCarloEuropeanOption::new(100.0, 100.0, 1.0, 0.03, 0.2, 50_000, 365, 7);
+        let shifts = [1.0 / 365.0, 7.0 / 365.0, 30.0 / 365.0, 180.0 / 365.0];
+        let ladder = mc_option
+            .theta_ladder(&ExerciseType::Call, &shifts)
+            .unwrap();
+        assert_approx_eq!(
+            ladder.base.value,
+            mc_option.try_call().unwrap().value,
+            1e-12
+        );
+
+        for (point, shift) in ladder.points.iter().zip(shifts) {
+            assert_approx_eq!(point.time_shift, shift, 1e-12);
+            assert_approx_eq!(point.price.value, bs_price(1.0 - shift), 0.15);
+        }
+        // the common paths give a smooth decay profile
+        let decay = ladder.time_decay();
+        assert!(decay.windows(2).all(|w| w[1] < w[0]) && decay[0] < 0.0);
+        let bs_theta = (bs_price(1.0 - shifts[2]) - bs_price(1.0)) / shifts[2];
+        assert_approx_eq!(ladder.thetas()[2], bs_theta, 0.5);
+
+        assert!(mc_option.theta_ladder(&ExerciseType::Call, &[1.0]).is_err());
     }
 }
@@ -1,2 +1,6 @@
+#[cfg(feature = "multi-asset")]
 pub mod basket_option;
 pub mod european_option;
+#[cfg(feature = "multi-asset")]
+pub mod reverse_convertible;
+pub mod variance_swap;
@@ -0,0 +1,281 @@
+use std::marker::PhantomData;
+
+use ndarray::prelude::*;
+
+use crate::common::results::{PriceResult, PricingError};
+use crate::common::time_grid::TimeGrid;
+use crate::simulation::correlation_greeks::{cega, pairwise_cega, CorrelationBump};
+use crate::simulation::path_layout::AssetMajorPath;
+use crate::simulation::sde::multivariate_gbm::MultivariateGeometricBrownianMotion;
+use crate::simulation::PathEvaluator;
+
+/// Worst-of barrier reverse convertible: the note pays fixed coupons and redeems the notional at expiration,
+/// unless the down-and-in put on the worst performing asset is knocked in, i.e. any asset's performance
+/// $S_i(t) / S_i(0)$ closes below the barrier level on a monitoring date (each time step),
+/// and the worst final performance ends below the strike level. The redemption is then reduced to
+/// notional * worst performance / strike level.
+/// See https://en.wikipedia.org/wiki/Reverse_convertible_securities
+pub struct WorstOfReverseConvertible<SeedRng>
+where
+    SeedRng: rand::SeedableRng + rand::RngCore,
+{
+    asset_prices: Array1<f64>,
+    rfr: f64,
+    cholesky_factor: Array2<f64>,
+    /// (T - t) in years, where T is the time of the note's expiration and t is the current time
+    time_to_expiration: f64,
+
+    notional: f64,
+    /// the strike of the put relative to the initial asset prices
+    strike_level: f64,
+    /// the knock-in barrier relative to the initial asset prices
+    barrier_level: f64,
+    /// the coupon per payment date relative to the notional
+    coupon_rate: f64,
+    coupon_times: Vec<f64>,
+
+    seed_nr: u64,
+    nr_paths: usize,
+    nr_steps: usize,
+    _phantom_rng: PhantomData<SeedRng>,
+}
+
+impl<SeedRng> WorstOfReverseConvertible<SeedRng>
+where
+    SeedRng: rand::SeedableRng + rand::RngCore,
+{
+    pub fn new(
+        asset_prices: Array1<f64>,
+        rfr: f64,
+        cholesky_factor: Array2<f64>,
+        time_to_expiration: f64,
+        nr_paths: usize,
+        nr_steps: usize,
+        seed_nr: u64,
+    ) -> Self {
+        assert_eq!(cholesky_factor.nrows(), asset_prices.len());
+        Self {
+            asset_prices,
+            rfr,
+            cholesky_factor,
+            time_to_expiration,
+            notional: 1.0,
+            strike_level: 1.0,
+            barrier_level: 0.6,
+            coupon_rate: 0.0,
+            coupon_times: Vec::new(),
+            seed_nr,
+            nr_paths,
+            nr_steps,
+            _phantom_rng: PhantomData::<SeedRng>,
+        }
+    }
+
+    pub fn with_notional(self, notional: f64) -> Self {
+        Self { notional, ..self }
+    }
+
+    /// The strike and barrier levels relative to the initial asset prices, e.g. 1.0 and 0.6.
+    pub fn with_levels(self, strike_level: f64, barrier_level: f64) -> Self {
+        assert!(strike_level > 0.0 && barrier_level >= 0.0);
+        Self {
+            strike_level,
+            barrier_level,
+            ..self
+        }
+    }
+
+    /// Unconditional coupons of `coupon_rate` times the notional paid at the `coupon_times`.
+    pub fn with_coupons(self, coupon_rate: f64, coupon_times: Vec<f64>) -> Self {
+        Self {
+            coupon_rate,
+            coupon_times,
+            ..self
+        }
+    }
+
+    pub fn dt(&self) -> f64 {
+        self.time_to_expiration / self.nr_steps as f64
+    }
+
+    fn discount_factor(&self, t: f64) -> f64 {
+        (-self.rfr * t).exp()
+    }
+
+    /// The present value of the coupon leg.
+    pub fn coupon_leg(&self) -> f64 {
+        self.coupon_times
+            .iter()
+            .map(|t| self.notional * self.coupon_rate * self.discount_factor(*t))
+            .sum()
+    }
+
+    /// The payoff at expiration of the down-and-in put on the worst performance, which the investor is short.
+    fn down_and_in_put_payoff(&self, path: &AssetMajorPath) -> Option<f64> {
+        // the initial values of the simulated paths are the asset prices
+        let knocked_in = path.worst_of_minimum() < self.barrier_level;
+        let worst = *path.worst_performances().last()?;
+        Some(if knocked_in {
+            self.notional * (self.strike_level - worst).max(0.0) / self.strike_level
+        } else {
+            0.0
+        })
+    }
+
+    /// The price of the down-and-in put on the worst performance.
+    #[deprecated(note = "use `try_down_and_in_put`, which reports why the pricing fails")]
+    pub fn down_and_in_put(&self) -> Option<f64> {
+        self.try_down_and_in_put().ok().map(|result| result.value)
+    }
+
+    /// The price of the note: the coupons and the redeemed notional less the down-and-in put.
+    #[deprecated(note = "use `try_price`, which reports why the pricing fails")]
+    pub fn price(&self) -> Option<f64> {
+        self.try_price().ok().map(|result| result.value)
+    }
+
+    /// The price of the down-and-in put on the worst performance with its standard error,
+    /// confidence interval and runtime.
+    pub fn try_down_and_in_put(&self) -> Result<PriceResult, PricingError> {
+        self.validate()?;
+        PriceResult::timed(|| {
+            let gbm: MultivariateGeometricBrownianMotion = self.into();
+            let mut rn_generator = SeedRng::seed_from_u64(self.seed_nr);
+            let paths = gbm.sample_ensemble_paths(&mut rn_generator, self.nr_paths, self.nr_steps);
+            let disc_factor = self.discount_factor(self.time_to_expiration);
+            PathEvaluator::new(&paths)
+                .price(|path| self.down_and_in_put_payoff(path).map(|p| p * disc_factor))
+        })
+    }
+
+    /// The price of the note, whose estimation error is the one of the down-and-in put.
+    pub fn try_price(&self) -> Result<PriceResult, PricingError> {
+        let bond = self.notional * self.discount_factor(self.time_to_expiration);
+        Ok(self
+            .try_down_and_in_put()?
+            .affine(self.coupon_leg() + bond, -1.0))
+    }
+
+    /// The correlation sensitivity of the note to the bump, with common random numbers of the seed.
+    pub fn cega(&self, bump: CorrelationBump, bump_size: f64) -> Result<f64, PricingError> {
+        cega(&self.cholesky_factor, bump, bump_size, |factor| {
+            Ok(self
+                .with_cholesky_factor(factor.to_owned())
+                .try_price()?
+                .value)
+        })
+    }
+
+    /// The correlation sensitivities of the note per pair of assets.
+    pub fn pairwise_cega(&self, bump_size: f64) -> Result<Array2<f64>, PricingError> {
+        pairwise_cega(&self.cholesky_factor, bump_size, |factor| {
+            Ok(self
+                .with_cholesky_factor(factor.to_owned())
+                .try_price()?
+                .value)
+        })
+    }
+
+    /// The same note on the assets with the covariance of the Cholesky factor.
+    fn with_cholesky_factor(&self, cholesky_factor: Array2<f64>) -> Self {
+        Self {
+            asset_prices: self.asset_prices.to_owned(),
+            cholesky_factor,
+            coupon_times: self.coupon_times.clone(),
+            _phantom_rng: PhantomData::<SeedRng>,
+            ..*self
+        }
+    }
+
+    fn validate(&self) -> Result<(), PricingError> {
+        for asset_price in &self.asset_prices {
+            PricingError::check(*asset_price > 0.0, "asset price", *asset_price)?;
+        }
+        TimeGrid::step_size(self.time_to_expiration, self.nr_steps)?;
+        if self.nr_paths == 0 {
+            return Err(PricingError::NoPaths);
+        }
+        Ok(())
+    }
+}
+
+impl<R> From<&WorstOfReverseConvertible<R>> for MultivariateGeometricBrownianMotion
+where
+    R: rand::SeedableRng + rand::RngCore,
+{
+    fn from(note: &WorstOfReverseConvertible<R>) -> Self {
+        MultivariateGeometricBrownianMotion::new(
+            note.asset_prices.to_owned(),
+            Array1::from_elem(note.asset_prices.len(), note.rfr),
+            note.cholesky_factor.to_owned(),
+            note.dt(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::BsmComputation;
+    use crate::common::models::DerivativeParameter;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn single_asset_limits() {
+        let note = |barrier_level: f64| -> WorstOfReverseConvertible<rand_hc::Hc128Rng> {
+            WorstOfReverseConvertible::new(arr1(&[50.0]), 0.02, arr2(&[[0.25]]), 1.0, 2_000, 20, 42)
+                .with_notional(1000.0)
+                .with_levels(0.9, barrier_level)
+                .with_coupons(0.02, vec![0.5, 1.0])
+        };
+
+        // a barrier above the initial level is always knocked in: a European put on the performance
+        let put = BsmComputation::new(&DerivativeParameter::new(1.0, 0.9, 1.0, 0.02, 0.25)).put();
+        assert_approx_eq!(
+            note(1.1).try_down_and_in_put().unwrap().value,
+            1000.0 * put / 0.9,
+            8.0
+        );
+        // a zero barrier is never knocked in: coupons and notional
+        let bond = note(0.0);
+        assert_eq!(bond.try_down_and_in_put().unwrap().value, 0.0);
+        assert_approx_eq!(
+            bond.try_price().unwrap().value,
+            20.0 * ((-0.01_f64).exp() + (-0.02_f64).exp()) + 1000.0 * (-0.02_f64).exp(),
+            1e-10
+        );
+        assert!(note(0.6).try_price().unwrap().value < bond.try_price().unwrap().value);
+    }
+
+    #[test]
+    fn worst_of_is_riskier_with_lower_correlation() {
+        let note = |correlation: f64| -> WorstOfReverseConvertible<rand_hc::Hc128Rng> {
+            let vola = 0.3;
+            let cholesky_factor = arr2(&[
+                [vola, 0.0],
+                [
+                    vola * correlation,
+                    vola * (1.0 - correlation * correlation).sqrt(),
+                ],
+            ]);
+            WorstOfReverseConvertible::new(
+                arr1(&[100.0, 20.0]),
+                0.01,
+                cholesky_factor,
+                1.0,
+                2_000,
+                20,
+                7,
+            )
+        };
+        let correlated = note(0.9).try_down_and_in_put().unwrap().value;
+        let uncorrelated = note(0.0).try_down_and_in_put().unwrap().value;
+        assert!(uncorrelated > correlated);
+
+        // the note is long correlation
+        let cega = note(0.5).cega(CorrelationBump::Parallel, 0.05).unwrap();
+        assert!(cega > 0.0);
+        let pairwise = note(0.5).pairwise_cega(0.05).unwrap();
+        assert_eq!(pairwise[[0, 1]], cega);
+    }
+}
@@ -0,0 +1,234 @@
+use std::marker::PhantomData;
+
+use crate::common::results::{PriceResult, PricingError};
+use crate::common::time_grid::TimeGrid;
+use crate::simulation::monte_carlo::{MonteCarloPathSimulator, PathEvaluator};
+use crate::simulation::path_statistics::CorridorVariance;
+use crate::simulation::sde::gbm::GeometricBrownianMotion;
+
+/// The steps on which the variance of a variance swap accrues, by the spot at the start of the step.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VarianceAccrual {
+    /// every step, i.e. the plain variance swap
+    Plain,
+    /// the steps inside the corridor $[L, U]$, annualized over all steps, such that the corridor
+    /// variances of adjacent corridors add up to the plain variance
+    Corridor { lower: f64, upper: f64 },
+    /// the steps inside the corridor $[L, U]$, annualized over the steps inside, with the payoff
+    /// scaled by the fraction of the steps inside, e.g. up and down variance swaps
+    Conditional { lower: f64, upper: f64 },
+}
+
+impl VarianceAccrual {
+    /// The variance accrued while the spot is above the barrier.
+    pub fn up(barrier: f64) -> Self {
+        VarianceAccrual::Conditional {
+            lower: barrier,
+            upper: f64::INFINITY,
+        }
+    }
+
+    /// The variance accrued while the spot is below the barrier.
+    pub fn down(barrier: f64) -> Self {
+        VarianceAccrual::Conditional {
+            lower: 0.0,
+            upper: barrier,
+        }
+    }
+
+    fn corridor(&self) -> (f64, f64) {
+        match *self {
+            VarianceAccrual::Plain => (0.0, f64::INFINITY),
+            VarianceAccrual::Corridor { lower, upper }
+            | VarianceAccrual::Conditional { lower, upper } => (lower, upper),
+        }
+    }
+}
+
+/// Variance swap paying `variance_notional` times the annualized realized variance of the daily
+/// (per step) log returns less the variance strike $K^2$ at expiration, priced on GBM paths under the
+/// risk neutral measure. Corridor and conditional swaps accrue the variance only while the spot is
+/// inside a range, where a conditional swap pays $\frac{N_{in}}{N} (\sigma^2_{in} - K^2)$.
+/// See https://en.wikipedia.org/wiki/Variance_swap
+pub struct MonteCarloVarianceSwap<SeedRng>
+where
+    SeedRng: rand::SeedableRng + rand::RngCore,
+{
+    pub asset_price: f64,
+    pub rfr: f64,
+    pub vola: f64,
+    pub time_to_expiration: f64,
+    /// the strike in volatility terms, i.e. the square root of the variance strike
+    pub strike_vola: f64,
+    pub variance_notional: f64,
+    pub accrual: VarianceAccrual,
+    pub seed_nr: u64,
+    pub nr_paths: usize,
+    pub nr_steps: usize,
+    _phantom_rng: PhantomData<SeedRng>,
+}
+
+impl<SeedRng> MonteCarloVarianceSwap<SeedRng>
+where
+    SeedRng: rand::SeedableRng + rand::RngCore,
+{
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        asset_price: f64,
+        rfr: f64,
+        vola: f64,
+        time_to_expiration: f64,
+        strike_vola: f64,
+        nr_paths: usize,
+        nr_steps: usize,
+        seed_nr: u64,
+    ) -> Self {
+        Self {
+            asset_price,
+            rfr,
+            vola,
+            time_to_expiration,
+            strike_vola,
+            variance_notional: 1.0,
+            accrual: VarianceAccrual::Plain,
+            seed_nr,
+            nr_paths,
+            nr_steps,
+            _phantom_rng: PhantomData::<SeedRng>,
+        }
+    }
+
+    pub fn with_variance_notional(self, variance_notional: f64) -> Self {
+        Self {
+            variance_notional,
+            ..self
+        }
+    }
+
+    /// The swap accruing the variance inside a range, e.g. `VarianceAccrual::down(90.0)`.
+    pub fn with_accrual(self, accrual: VarianceAccrual) -> Self {
+        if let VarianceAccrual::Corridor { lower, upper }
+        | VarianceAccrual::Conditional { lower, upper } = accrual
+        {
+            assert!(lower < upper);
+        }
+        Self { accrual, ..self }
+    }
+
+    pub fn dt(&self) -> f64 {
+        self.time_to_expiration / self.nr_steps as f64
+    }
+
+    fn validate(&self) -> Result<(), PricingError> {
+        PricingError::check(self.asset_price > 0.0, "asset price", self.asset_price)?;
+        PricingError::check(self.vola >= 0.0, "vola", self.vola)?;
+        PricingError::check(self.strike_vola >= 0.0, "strike vola", self.strike_vola)?;
+        TimeGrid::step_size(self.time_to_expiration, self.nr_steps)?;
+        if self.nr_paths == 0 {
+            return Err(PricingError::NoPaths);
+        }
+        Ok(())
+    }
+
+    /// The corridor variances of the simulated paths.
+    fn sample_corridor_variances(&self) -> Vec<CorridorVariance> {
+        let gbm = GeometricBrownianMotion::new(self.asset_price, self.rfr, self.vola, self.dt());
+        let mc_simulator: MonteCarloPathSimulator<_, SeedRng, _> =
+            MonteCarloPathSimulator::new(gbm, Some(self.seed_nr));
+        let (lower, upper) = self.accrual.corridor();
+        mc_simulator
+            .simulate_paths(self.nr_paths, self.nr_steps)
+            .iter()
+            .map(|path| CorridorVariance::from_path(lower, upper, path))
+            .collect()
+    }
+
+    /// The annualized variance accrued on the path with the weight of the variance strike, i.e. the
+    /// undiscounted payoff per unit variance notional is `variance - weight * strike_vola^2`.
+    fn accrued_variance(&self, corridor_variance: &CorridorVariance) -> Option<(f64, f64)> {
+        let fraction_inside = corridor_variance.fraction_inside()?;
+        let variance = corridor_variance.realized_variance() / self.time_to_expiration;
+        Some(match self.accrual {
+            VarianceAccrual::Plain | VarianceAccrual::Corridor { .. } => (variance, 1.0),
+            // $\frac{N_{in}}{N} \sigma^2_{in}$ equals the corridor variance
+            VarianceAccrual::Conditional { .. } => (variance, fraction_inside),
+        })
+    }
+
+    /// The price of the swap with its standard error, confidence interval and runtime.
+    pub fn price(&self) -> Result<PriceResult, PricingError> {
+        self.validate()?;
+        PriceResult::timed(|| {
+            let disc_factor = (-self.rfr * self.time_to_expiration).exp();
+            let strike_variance = self.strike_vola.powi(2);
+            let corridor_variances = self.sample_corridor_variances();
+            PathEvaluator::new(&corridor_variances).price(|corridor_variance| {
+                let (variance, weight) = self.accrued_variance(corridor_variance)?;
+                Some(disc_factor * self.variance_notional * (variance - weight * strike_variance))
+            })
+        })
+    }
+
+    /// The fair strike in volatility terms, i.e. the strike vola of a swap worth zero.
+    pub fn fair_strike(&self) -> Result<f64, PricingError> {
+        self.validate()?;
+        let (variance, weight) = self
+            .sample_corridor_variances()
+            .iter()
+            .filter_map(|corridor_variance| self.accrued_variance(corridor_variance))
+            .fold(
+                (0.0, 0.0),
+                |(sum_variance, sum_weight), (variance, weight)| {
+                    (sum_variance + variance, sum_weight + weight)
+                },
+            );
+        // the spot never entered the range of a conditional swap
+        PricingError::check(weight > 0.0, "fraction inside", weight)?;
+        Ok((variance / weight).sqrt())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn corridor_and_conditional_variance_swaps() {
+        let swap = |accrual| {
+            MonteCarloVarianceSwap::<rand_hc::Hc128Rng>::new(
+                100.0, 0.02, 0.25, 1.0, 0.25, 2_000, 250, 42,
+            )
+            .with_variance_notional(100.0)
+            .with_accrual(accrual)
+        };
+        let plain = swap(VarianceAccrual::Plain);
+        assert_approx_eq!(plain.fair_strike().unwrap(), 0.25, 0.005);
+        let price = plain.price().unwrap();
+        assert!(price.value.abs() < 3.0 * price.std_error.unwrap() + 0.1);
+
+        // the corridor variances below and above the spot add up to the plain variance
+        let below = swap(VarianceAccrual::Corridor {
+            lower: 0.0,
+            upper: 100.0,
+        })
+        .price()
+        .unwrap();
+        let above = swap(VarianceAccrual::Corridor {
+            lower: 100.0 + 1e-9,
+            upper: f64::INFINITY,
+        })
+        .price()
+        .unwrap();
+        // each corridor swap pays the full variance strike
+        let strike_leg = 100.0 * 0.25_f64.powi(2) * (-0.02_f64).exp();
+        assert_approx_eq!(below.value + above.value, price.value - strike_leg, 1e-10);
+
+        // the conditional variance of constant volatility is the volatility, unlike the corridor variance
+        let down = swap(VarianceAccrual::down(100.0));
+        assert_approx_eq!(down.fair_strike().unwrap(), 0.25, 0.01);
+        assert!(down.price().unwrap().value.abs() < 0.5);
+        assert!(below.value < -2.0);
+        assert!(swap(VarianceAccrual::up(1_000.0)).fair_strike().is_err());
+    }
+}
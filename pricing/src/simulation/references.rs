@@ -0,0 +1,381 @@
+use std::fmt;
+
+use crate::common::models::{DerivativeParameter, ExerciseType};
+use crate::common::results::PriceResult;
+use crate::simulation::sde::heston::HestonParameters;
+
+/// The product and model of a published benchmark case.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReferenceProduct {
+    /// a vanilla in the Black-Scholes model, with early exercise if American
+    Vanilla {
+        params: DerivativeParameter,
+        exercise_type: ExerciseType,
+        is_american: bool,
+    },
+    /// a European vanilla in the Heston model
+    HestonVanilla {
+        spot: f64,
+        strike: f64,
+        maturity: f64,
+        rate: f64,
+        exercise_type: ExerciseType,
+        parameters: HestonParameters,
+    },
+    /// a Bermudan call on the maximum of uncorrelated assets of equal volatility and dividend yield,
+    /// exercisable at equidistant dates up to the maturity
+    MaxCall {
+        spots: Vec<f64>,
+        strike: f64,
+        maturity: f64,
+        rate: f64,
+        dividend_yield: f64,
+        vola: f64,
+        nr_exercise_dates: usize,
+    },
+}
+
+/// A published reference value of a product with the source of the value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReferenceCase {
+    pub name: &'static str,
+    pub source: &'static str,
+    pub product: ReferenceProduct,
+    pub value: f64,
+    /// the precision of the published value, e.g. 0.005 for values rounded to cents
+    pub precision: f64,
+}
+
+/// The European vanillas of Hull, Options, Futures, and Other Derivatives (Example 15.6 of the 8th edition).
+pub fn hull_vanillas() -> Vec<ReferenceCase> {
+    let params = DerivativeParameter::new(42.0, 40.0, 0.5, 0.1, 0.2);
+    let vanilla = |exercise_type| ReferenceProduct::Vanilla {
+        params,
+        exercise_type,
+        is_american: false,
+    };
+    vec![
+        ReferenceCase {
+            name: "hull call",
+            source: "Hull, Options, Futures, and Other Derivatives, Example 15.6",
+            product: vanilla(ExerciseType::Call),
+            value: 4.76,
+            precision: 0.005,
+        },
+        ReferenceCase {
+            name: "hull put",
+            source: "Hull, Options, Futures, and Other Derivatives, Example 15.6",
+            product: vanilla(ExerciseType::Put),
+            value: 0.81,
+            precision: 0.005,
+        },
+    ]
+}
+
+/// The finite-difference values of American puts with the strike 40 and the rate 6% of Longstaff and
+/// Schwartz, Valuing American Options by Simulation, Review of Financial Studies 14 (2001), Table 1,
+/// which are below the converged lattice values by up to 0.01.
+pub fn longstaff_schwartz_american_puts() -> Vec<ReferenceCase> {
+    [
+        ("american put 36/0.2/1", 36.0, 0.2, 1.0, 4.478),
+        ("american put 36/0.4/1", 36.0, 0.4, 1.0, 7.101),
+        ("american put 40/0.2/2", 40.0, 0.2, 2.0, 2.885),
+        ("american put 44/0.2/1", 44.0, 0.2, 1.0, 1.110),
+    ]
+    .into_iter()
+    .map(|(name, spot, vola, maturity, value)| ReferenceCase {
+        name,
+        source: "Longstaff, Schwartz (2001), Table 1",
+        product: ReferenceProduct::Vanilla {
+            params: DerivativeParameter::new(spot, 40.0, maturity, 0.06, vola),
+            exercise_type: ExerciseType::Put,
+            is_american: true,
+        },
+        value,
+        precision: 0.01,
+    })
+    .collect()
+}
+
+/// The Bermudan max-calls on two assets with the strike 100, the rate 5%, the dividend yield 10%,
+/// the volatility 20% and 9 exercise dates over 3 years, whose values lie between the lower and upper
+/// bounds of Broadie and Glasserman, A Stochastic Mesh Method for Pricing High-Dimensional American
+/// Options, Journal of Computational Finance 7 (2004). No engine of the crate prices them yet.
+pub fn broadie_glasserman_max_calls() -> Vec<ReferenceCase> {
+    [
+        ("max call 90", 90.0, 8.08),
+        ("max call 100", 100.0, 13.90),
+        ("max call 110", 110.0, 21.34),
+    ]
+    .into_iter()
+    .map(|(name, spot, value)| ReferenceCase {
+        name,
+        source: "Broadie, Glasserman (2004)",
+        product: ReferenceProduct::MaxCall {
+            spots: vec![spot; 2],
+            strike: 100.0,
+            maturity: 3.0,
+            rate: 0.05,
+            dividend_yield: 0.1,
+            vola: 0.2,
+            nr_exercise_dates: 9,
+        },
+        value,
+        precision: 0.005,
+    })
+    .collect()
+}
+
+/// The at-the-money Heston calls of Broadie and Kaya, Exact Simulation of Stochastic Volatility and
+/// Other Affine Jump Diffusion Processes, Operations Research 54 (2006), by Fourier inversion.
+pub fn broadie_kaya_heston_calls() -> Vec<ReferenceCase> {
+    let call = |maturity, rate, parameters| ReferenceProduct::HestonVanilla {
+        spot: 100.0,
+        strike: 100.0,
+        maturity,
+        rate,
+        exercise_type: ExerciseType::Call,
+        parameters,
+    };
+    vec![
+        ReferenceCase {
+            name: "heston call, Feller condition violated",
+            source: "Broadie, Kaya (2006)",
+            product: call(
+                1.0,
+                0.0319,
+                HestonParameters {
+                    initial_variance: 0.010201,
+                    mean_reversion: 6.21,
+                    long_term_variance: 0.019,
+                    vol_of_vol: 0.61,
+                    correlation: -0.7,
+                },
+            ),
+            value: 6.8061,
+            precision: 0.00005,
+        },
+        ReferenceCase {
+            name: "heston call, large vol of vol",
+            source: "Broadie, Kaya (2006)",
+            product: call(
+                5.0,
+                0.05,
+                HestonParameters {
+                    initial_variance: 0.09,
+                    mean_reversion: 2.0,
+                    long_term_variance: 0.09,
+                    vol_of_vol: 1.0,
+                    correlation: -0.3,
+                },
+            ),
+            value: 34.9998,
+            precision: 0.00005,
+        },
+    ]
+}
+
+/// All reference cases.
+pub fn all_references() -> Vec<ReferenceCase> {
+    [
+        hull_vanillas(),
+        longstaff_schwartz_american_puts(),
+        broadie_glasserman_max_calls(),
+        broadie_kaya_heston_calls(),
+    ]
+    .concat()
+}
+
+/// The outcome of the validation of an engine against a reference case.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReferenceOutcome {
+    Passed,
+    Failed,
+    /// the engine does not price the product
+    Skipped,
+}
+
+#[derive(Clone, Debug)]
+pub struct ReferenceCheck {
+    pub name: &'static str,
+    pub reference: f64,
+    pub result: Option<PriceResult>,
+    pub outcome: ReferenceOutcome,
+}
+
+/// The checks of an engine against the reference cases.
+#[derive(Clone, Debug, Default)]
+pub struct ValidationReport {
+    pub checks: Vec<ReferenceCheck>,
+}
+
+impl ValidationReport {
+    /// Whether no check failed and at least one passed.
+    pub fn passed(&self) -> bool {
+        self.checks
+            .iter()
+            .all(|check| check.outcome != ReferenceOutcome::Failed)
+            && self.nr_checks(ReferenceOutcome::Passed) > 0
+    }
+
+    pub fn nr_checks(&self, outcome: ReferenceOutcome) -> usize {
+        self.checks
+            .iter()
+            .filter(|check| check.outcome == outcome)
+            .count()
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &ReferenceCheck> {
+        self.checks
+            .iter()
+            .filter(|check| check.outcome == ReferenceOutcome::Failed)
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            let value = check
+                .result
+                .as_ref()
+                .map_or("-".to_string(), |result| format!("{:.6}", result.value));
+            writeln!(
+                f,
+                "{:<40} {:>12.6} {:>12} {:?}",
+                check.name, check.reference, value, check.outcome
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Validates the engine against the reference cases, where a value passes if it deviates from the
+/// reference by at most the precision of the reference, the (discretization) tolerance of the engine
+/// and `nr_std_errors` standard errors of a Monte Carlo value. Products the engine does not price,
+/// i.e. for which it returns None, are skipped.
+pub fn validate(
+    cases: &[ReferenceCase],
+    engine: impl Fn(&ReferenceProduct) -> Option<PriceResult>,
+    tolerance: f64,
+    nr_std_errors: f64,
+) -> ValidationReport {
+    let checks = cases
+        .iter()
+        .map(|case| {
+            let result = engine(&case.product);
+            let outcome = match &result {
+                None => ReferenceOutcome::Skipped,
+                Some(result) => {
+                    let bound = case.precision
+                        + tolerance
+                        + nr_std_errors * result.std_error.unwrap_or(0.0);
+                    if (result.value - case.value).abs() <= bound {
+                        ReferenceOutcome::Passed
+                    } else {
+                        ReferenceOutcome::Failed
+                    }
+                }
+            };
+            ReferenceCheck {
+                name: case.name,
+                reference: case.value,
+                result,
+                outcome,
+            }
+        })
+        .collect();
+    ValidationReport { checks }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::{BlackScholesMerton, OptionPrice};
+    use crate::lattice::trinomial_tree::TrinomialTree;
+    use crate::simulation::monte_carlo::MonteCarloPathSimulator;
+    use crate::simulation::sde::heston::{Heston, HestonScheme};
+
+    #[test]
+    fn validate_engines_against_references() {
+        let analytic = |product: &ReferenceProduct| match product {
+            ReferenceProduct::Vanilla {
+                params,
+                exercise_type,
+                is_american: false,
+            } => Some(PriceResult::exact(match exercise_type {
+                ExerciseType::Call => BlackScholesMerton::call(params),
+                ExerciseType::Put => BlackScholesMerton::put(params),
+            })),
+            _ => None,
+        };
+        let report = validate(&all_references(), analytic, 0.0, 0.0);
+        assert!(report.passed(), "{report}");
+        assert_eq!(report.nr_checks(ReferenceOutcome::Passed), 2);
+
+        let tree = |product: &ReferenceProduct| match product {
+            ReferenceProduct::Vanilla {
+                params,
+                exercise_type,
+                is_american,
+            } => Some(PriceResult::exact(TrinomialTree::new(500).price(
+                params,
+                exercise_type,
+                *is_american,
+            ))),
+            _ => None,
+        };
+        let report = validate(&all_references(), tree, 0.005, 0.0);
+        assert!(report.passed(), "{report}");
+        assert_eq!(report.nr_checks(ReferenceOutcome::Passed), 6);
+        // the European value misses the early exercise premium of the American puts
+        let report = validate(
+            &longstaff_schwartz_american_puts(),
+            european_vanilla,
+            0.005,
+            0.0,
+        );
+        assert_eq!(report.failures().count(), 4);
+
+        // the exact Heston scheme at a single step
+        let heston = |product: &ReferenceProduct| match product {
+            ReferenceProduct::HestonVanilla {
+                spot,
+                strike,
+                maturity,
+                rate,
+                exercise_type: ExerciseType::Call,
+                parameters,
+            } => {
+                let model = Heston::new(*spot, *rate, *parameters, *maturity)
+                    .ok()?
+                    .with_scheme(HestonScheme::Exact);
+                let simulator: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, Vec<f64>> =
+                    MonteCarloPathSimulator::new(model, Some(2));
+                let discounted: Vec<f64> = simulator
+                    .simulate_paths(4_000, 1)
+                    .iter()
+                    .map(|path| (-rate * maturity).exp() * (path[1] - strike).max(0.0))
+                    .collect();
+                PriceResult::from_samples(&discounted).ok()
+            }
+            _ => None,
+        };
+        let report = validate(&broadie_kaya_heston_calls(), heston, 0.0, 3.0);
+        assert!(report.passed(), "{report}");
+    }
+
+    /// The Black-Scholes value of the vanilla call or put, ignoring any early exercise.
+    fn european_vanilla(product: &ReferenceProduct) -> Option<PriceResult> {
+        match product {
+            ReferenceProduct::Vanilla {
+                params,
+                exercise_type,
+                ..
+            } => Some(PriceResult::exact(match exercise_type {
+                ExerciseType::Call => BlackScholesMerton::call(params),
+                ExerciseType::Put => BlackScholesMerton::put(params),
+            })),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+/// The risk factors of multi-factor models with their own random number streams.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RiskFactor {
+    Equity,
+    Volatility,
+    Rates,
+    Jumps,
+    /// further factors by number
+    Other(u32),
+}
+
+impl RiskFactor {
+    fn id(&self) -> u64 {
+        match self {
+            RiskFactor::Equity => 1,
+            RiskFactor::Volatility => 2,
+            RiskFactor::Rates => 3,
+            RiskFactor::Jumps => 4,
+            RiskFactor::Other(nr) => (1 << 32) + *nr as u64,
+        }
+    }
+}
+
+/// Independent random number streams per risk factor, whose seeds are derived from the master seed
+/// and the factor only. Adding a factor or changing the number of draws of one factor,
+/// e.g. by its number of steps or jumps, does not perturb the random numbers of the others.
+#[derive(Clone, Debug)]
+pub struct RngStreams<SeedRng>
+where
+    SeedRng: rand::SeedableRng + rand::RngCore,
+{
+    master_seed: u64,
+    streams: HashMap<(RiskFactor, usize), SeedRng>,
+}
+
+impl<SeedRng> RngStreams<SeedRng>
+where
+    SeedRng: rand::SeedableRng + rand::RngCore,
+{
+    pub fn new(master_seed: u64) -> Self {
+        Self {
+            master_seed,
+            streams: HashMap::new(),
+        }
+    }
+
+    /// The seed of the sub-stream of the factor, e.g. of an asset of the equity factor.
+    pub fn seed(&self, factor: RiskFactor, sub_stream: usize) -> u64 {
+        split_mix(split_mix(self.master_seed ^ split_mix(factor.id())) ^ sub_stream as u64)
+    }
+
+    /// The stream of the factor, which continues where the previous draws of the factor stopped.
+    pub fn stream(&mut self, factor: RiskFactor) -> &mut SeedRng {
+        self.sub_stream(factor, 0)
+    }
+
+    /// The numbered sub-stream of the factor.
+    pub fn sub_stream(&mut self, factor: RiskFactor, sub_stream: usize) -> &mut SeedRng {
+        let seed = self.seed(factor, sub_stream);
+        self.streams
+            .entry((factor, sub_stream))
+            .or_insert_with(|| SeedRng::seed_from_u64(seed))
+    }
+
+    /// Samples the paths of the generator, which draws each factor from its stream.
+    pub fn simulate_paths<PathGen, Path>(
+        &mut self,
+        path_generator: &PathGen,
+        nr_paths: usize,
+        nr_steps: usize,
+    ) -> Vec<Path>
+    where
+        PathGen: MultiStreamPathGenerator<Path>,
+    {
+        (0..nr_paths)
+            .map(|_| path_generator.sample_path_from_streams(self, nr_steps))
+            .collect()
+    }
+}
+
+/// Path generators of multi-factor models, which draw the random numbers of each factor from its stream.
+pub trait MultiStreamPathGenerator<Path> {
+    fn sample_path_from_streams<SeedRng>(
+        &self,
+        streams: &mut RngStreams<SeedRng>,
+        nr_samples: usize,
+    ) -> Path
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore;
+}
+
+/// The SplitMix64 mixing function, which maps consecutive inputs to decorrelated seeds.
+/// See https://prng.di.unimi.it/splitmix64.c
+fn split_mix(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn streams_are_independent_of_other_factors() {
+        let mut streams: RngStreams<rand_hc::Hc128Rng> = RngStreams::new(42);
+        let equity: Vec<f64> = (0..3)
+            .map(|_| streams.stream(RiskFactor::Equity).gen())
+            .collect();
+
+        // draws of other factors in between do not change the equity stream
+        let mut other_streams: RngStreams<rand_hc::Hc128Rng> = RngStreams::new(42);
+        let mut interleaved: Vec<f64> = Vec::new();
+        for _ in 0..3 {
+            let _: f64 = other_streams.stream(RiskFactor::Jumps).gen();
+            let _: u64 = other_streams.sub_stream(RiskFactor::Other(7), 2).gen();
+            interleaved.push(other_streams.stream(RiskFactor::Equity).gen());
+        }
+        assert_eq!(equity, interleaved);
+
+        assert_ne!(
+            streams.seed(RiskFactor::Equity, 0),
+            streams.seed(RiskFactor::Equity, 1)
+        );
+        assert_ne!(
+            streams.seed(RiskFactor::Equity, 0),
+            streams.seed(RiskFactor::Rates, 0)
+        );
+        assert_ne!(
+            streams.seed(RiskFactor::Equity, 0),
+            RngStreams::<rand_hc::Hc128Rng>::new(43).seed(RiskFactor::Equity, 0)
+        );
+    }
+}
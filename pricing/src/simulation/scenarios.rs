@@ -0,0 +1,323 @@
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+use rayon::prelude::*;
+
+use crate::common::market_context::MarketSnapshot;
+use crate::common::market_shifts::Scenario;
+use crate::common::results::PricingError;
+use crate::simulation::greeks::SharedNormals;
+use crate::simulation::sde::gbm::GeometricBrownianMotion;
+
+/// The market state of a scenario for a single underlying.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarketScenario {
+    pub name: String,
+    pub spot: f64,
+    pub vola: f64,
+    pub rfr: f64,
+}
+
+impl MarketScenario {
+    pub fn new(name: impl Into<String>, spot: f64, vola: f64, rfr: f64) -> Self {
+        Self {
+            name: name.into(),
+            spot,
+            vola,
+            rfr,
+        }
+    }
+
+    /// The flat scenario of an option on the underlying with the maturity and strike in the market
+    /// deformed by the curve and surface scenario: the zero rate of the curve and the effective vola
+    /// of the surface.
+    pub fn from_scenario(
+        scenario: &Scenario,
+        market: &MarketSnapshot,
+        underlying: &str,
+        curve: &str,
+        maturity: f64,
+        strike: f64,
+    ) -> Result<Self, PricingError> {
+        let market_data = scenario
+            .apply(market)?
+            .market_data_set(underlying, curve, maturity, strike)
+            .ok_or_else(|| {
+                PricingError::MissingMarketData(format!("{underlying} with curve {curve}"))
+            })?;
+        Ok(Self::new(
+            scenario.name.clone(),
+            market_data.spot,
+            market_data.vola,
+            market_data.rfr,
+        ))
+    }
+
+    /// The scenario with the spot shifted relatively and the volatility and the rate shifted absolutely.
+    pub fn shifted(
+        &self,
+        name: impl Into<String>,
+        spot_shift: f64,
+        vola_shift: f64,
+        rate_shift: f64,
+    ) -> Self {
+        Self::new(
+            name,
+            self.spot * (1.0 + spot_shift),
+            self.vola + vola_shift,
+            self.rfr + rate_shift,
+        )
+    }
+}
+
+/// A product priced on GBM paths of the scenario's underlying by its (undiscounted) payoff at expiration.
+pub trait ScenarioProduct: Sync {
+    fn time_to_expiration(&self) -> f64;
+
+    fn payoff(&self, path: &[f64]) -> Option<f64>;
+}
+
+/// The failure of a single (product, scenario) cell.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScenarioError {
+    /// no path yields a payoff
+    NoValue,
+    /// the valuation panicked with the given message
+    Panicked(String),
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScenarioError::NoValue => write!(f, "no path yields a payoff"),
+            ScenarioError::Panicked(message) => write!(f, "valuation panicked: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ScenarioError {}
+
+/// The values per product (rows) and scenario (columns).
+#[derive(Clone, Debug)]
+pub struct ScenarioResults {
+    pub cells: Vec<Vec<Result<f64, ScenarioError>>>,
+}
+
+impl ScenarioResults {
+    pub fn value(&self, product_idx: usize, scenario_idx: usize) -> &Result<f64, ScenarioError> {
+        &self.cells[product_idx][scenario_idx]
+    }
+
+    /// The value changes against the base scenario per product and scenario.
+    pub fn deltas(&self, base_scenario_idx: usize) -> Vec<Vec<Result<f64, ScenarioError>>> {
+        self.cells
+            .iter()
+            .map(|row| {
+                let base = &row[base_scenario_idx];
+                row.iter()
+                    .map(|cell| match (cell, base) {
+                        (Ok(value), Ok(base_value)) => Ok(value - base_value),
+                        (Err(err), _) | (_, Err(err)) => Err(err.clone()),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The failed cells as (product index, scenario index, error).
+    pub fn errors(&self) -> Vec<(usize, usize, &ScenarioError)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .flat_map(|(p, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter_map(move |(s, cell)| cell.as_ref().err().map(|err| (p, s, err)))
+            })
+            .collect()
+    }
+}
+
+/// Evaluates products under market scenarios in parallel, where all cells use the same standard normals,
+/// such that scenario deltas are free of sampling noise.
+pub struct ScenarioEngine {
+    nr_steps: usize,
+    shared_normals: SharedNormals,
+}
+
+impl ScenarioEngine {
+    pub fn new<SeedRng>(nr_paths: usize, nr_steps: usize, seed_nr: u64) -> Self
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        Self {
+            nr_steps,
+            shared_normals: SharedNormals::new::<SeedRng>(nr_paths, nr_steps, seed_nr),
+        }
+    }
+
+    fn evaluate_cell(
+        &self,
+        product: &dyn ScenarioProduct,
+        scenario: &MarketScenario,
+    ) -> Result<f64, ScenarioError> {
+        let time_to_expiration = product.time_to_expiration();
+        let dt = time_to_expiration / self.nr_steps as f64;
+        let gbm = GeometricBrownianMotion::new(scenario.spot, scenario.rfr, scenario.vola, dt);
+
+        let payoffs: Vec<f64> = self
+            .shared_normals
+            .paths()
+            .iter()
+            .filter_map(|normals| product.payoff(&gbm.generate_path(scenario.spot, normals)))
+            .collect();
+        if payoffs.is_empty() {
+            return Err(ScenarioError::NoValue);
+        }
+        let disc_factor = (-scenario.rfr * time_to_expiration).exp();
+        Ok(disc_factor * payoffs.iter().sum::<f64>() / self.shared_normals.nr_paths() as f64)
+    }
+
+    /// Evaluates all (product, scenario) cells in parallel; a failing cell yields an error entry.
+    pub fn evaluate(
+        &self,
+        products: &[&dyn ScenarioProduct],
+        scenarios: &[MarketScenario],
+    ) -> ScenarioResults {
+        let nr_scenarios = scenarios.len();
+        let values: Vec<Result<f64, ScenarioError>> = (0..products.len() * nr_scenarios)
+            .into_par_iter()
+            .map(|cell| {
+                let (product, scenario) = (
+                    products[cell / nr_scenarios],
+                    &scenarios[cell % nr_scenarios],
+                );
+                panic::catch_unwind(AssertUnwindSafe(|| self.evaluate_cell(product, scenario)))
+                    .unwrap_or_else(|payload| {
+                        let message = payload
+                            .downcast_ref::<&str>()
+                            .map(|s| s.to_string())
+                            .or_else(|| payload.downcast_ref::<String>().cloned())
+                            .unwrap_or_default();
+                        Err(ScenarioError::Panicked(message))
+                    })
+            })
+            .collect();
+
+        ScenarioResults {
+            cells: values
+                .chunks(nr_scenarios.max(1))
+                .map(|row| row.to_vec())
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::BsmComputation;
+    use crate::common::market_shifts::CurveShift;
+    use crate::common::models::DerivativeParameter;
+    use crate::common::vol_surface::VolatilitySurface;
+    use crate::curves::yield_curve::FlatCurve;
+    use assert_approx_eq::assert_approx_eq;
+
+    struct Call {
+        strike: f64,
+        time_to_expiration: f64,
+    }
+
+    impl ScenarioProduct for Call {
+        fn time_to_expiration(&self) -> f64 {
+            self.time_to_expiration
+        }
+
+        fn payoff(&self, path: &[f64]) -> Option<f64> {
+            path.last().map(|s| (s - self.strike).max(0.0))
+        }
+    }
+
+    /// A product which fails for high spots.
+    struct Faulty;
+
+    impl ScenarioProduct for Faulty {
+        fn time_to_expiration(&self) -> f64 {
+            1.0
+        }
+
+        fn payoff(&self, path: &[f64]) -> Option<f64> {
+            if path[0] > 105.0 {
+                panic!("spot out of range");
+            }
+            None
+        }
+    }
+
+    #[test]
+    fn scenario_grid() {
+        let base = MarketScenario::new("base", 100.0, 0.2, 0.03);
+        let scenarios = vec![
+            base.clone(),
+            base.shifted("spot up", 0.1, 0.0, 0.0),
+            base.shifted("vol up", 0.0, 0.05, 0.0),
+        ];
+        let call = Call {
+            strike: 100.0,
+            time_to_expiration: 1.0,
+        };
+        let engine = ScenarioEngine::new::<rand_hc::Hc128Rng>(10_000, 20, 42);
+        let results = engine.evaluate(&[&call, &Faulty], &scenarios);
+
+        let bsm = |scenario: &MarketScenario| {
+            BsmComputation::new(&DerivativeParameter::new(
+                scenario.spot,
+                100.0,
+                1.0,
+                scenario.rfr,
+                scenario.vola,
+            ))
+        };
+        for (s, scenario) in scenarios.iter().enumerate() {
+            assert_approx_eq!(
+                results.value(0, s).clone().unwrap(),
+                bsm(scenario).call(),
+                0.5
+            );
+        }
+        // common random numbers give smooth scenario deltas
+        let deltas = results.deltas(0);
+        let expected_delta = bsm(&scenarios[1]).call() - bsm(&base).call();
+        assert_approx_eq!(deltas[0][1].clone().unwrap(), expected_delta, 0.1);
+        assert!(deltas[0][2].clone().unwrap() > 0.0);
+
+        // failing cells are isolated
+        assert_eq!(results.value(1, 0), &Err(ScenarioError::NoValue));
+        assert_eq!(
+            results.value(1, 1),
+            &Err(ScenarioError::Panicked("spot out of range".to_string()))
+        );
+        assert_eq!(results.errors().len(), 3);
+
+        // a parallel curve shift raises the rate of the flat scenario
+        let market = MarketSnapshot::new(0.0)
+            .with_spot("ABC", 100.0)
+            .with_curve("USD", FlatCurve::new(0.03))
+            .with_vol_surface("ABC", VolatilitySurface::flat(0.2));
+        let rates_up = MarketScenario::from_scenario(
+            &Scenario::new("rates up").with_curve_shift("USD", CurveShift::parallel(100.0)),
+            &market,
+            "ABC",
+            "USD",
+            1.0,
+            100.0,
+        )
+        .unwrap();
+        assert_approx_eq!(rates_up.rfr, 0.04, 1e-15);
+        assert_eq!((rates_up.spot, rates_up.vola), (100.0, 0.2));
+
+        // deterministic across runs
+        let rerun = engine.evaluate(&[&call], &scenarios);
+        assert_eq!(rerun.value(0, 2), results.value(0, 2));
+    }
+}
@@ -1,13 +1,23 @@
 use rand::Rng;
 use rand_distr::{Distribution, StandardNormal};
 
-use crate::simulation::monte_carlo::{Dynamics, PathGenerator};
+use crate::common::results::PricingError;
+use crate::common::time_grid::TimeGrid;
+use crate::curves::yield_curve::YieldCurve;
+use crate::simulation::monte_carlo::{
+    ControlledPathGenerator, Dynamics, PathGenerator, StepControl, StepController, WarmStart,
+};
+use crate::simulation::path_statistics::{
+    PathWithStatistics, RunningStatistics, StatisticsPathGenerator,
+};
+use crate::simulation::pipeline::PathStage;
 
 /// Model params for the SDE
 /// '''math
 /// dS_t / S_t = mu dt + sigma dW_t
 /// ''', where $dW_t ~ N(0, sqrt(dt))$
 /// https://en.wikipedia.org/wiki/Geometric_Brownian_motion
+#[derive(Clone, Debug)]
 pub struct GeometricBrownianMotion {
     initial_value: f64,
     /// drift term
@@ -16,6 +26,8 @@ pub struct GeometricBrownianMotion {
     sigma: f64,
     /// change in time
     dt: f64,
+    /// whether sampled paths start with the initial value, see `PathGenerator`
+    include_initial_value: bool,
 }
 
 impl GeometricBrownianMotion {
@@ -25,6 +37,30 @@ impl GeometricBrownianMotion {
             mu: drift,
             dt,
             sigma: vola,
+            include_initial_value: true,
+        }
+    }
+
+    /// The process, or the invalid parameter: a non-positive initial value, a negative volatility or a
+    /// non-positive step.
+    pub fn try_new(
+        initial_value: f64,
+        drift: f64,
+        vola: f64,
+        dt: f64,
+    ) -> Result<Self, PricingError> {
+        PricingError::check(initial_value > 0.0, "initial value", initial_value)?;
+        PricingError::check(drift.is_finite(), "drift", drift)?;
+        PricingError::check(vola >= 0.0 && vola.is_finite(), "vola", vola)?;
+        PricingError::check(dt > 0.0 && dt.is_finite(), "dt", dt)?;
+        Ok(Self::new(initial_value, drift, vola, dt))
+    }
+
+    /// Whether the generated paths start with the initial value (default) or with the value after the first step.
+    pub fn with_initial_value_in_path(self, include_initial_value: bool) -> Self {
+        Self {
+            include_initial_value,
+            ..self
         }
     }
 
@@ -41,15 +77,83 @@ impl GeometricBrownianMotion {
     }
 
     pub fn step_analytic(&self, st: f64, z: f64) -> f64 {
-        let ret = self.dt * (self.mu - self.sigma.powi(2) / 2.0) + self.dt.sqrt() * self.sigma * z;
+        self.step_analytic_over(st, self.dt, z)
+    }
+
+    /// The exact step over the time step dt instead of the generator's.
+    fn step_analytic_over(&self, st: f64, dt: f64, z: f64) -> f64 {
+        let ret = dt * (self.mu - self.sigma.powi(2) / 2.0) + dt.sqrt() * self.sigma * z;
         st * ret.exp()
     }
 
+    /// The path on the time grid (one standard normal per step), where the spot drops by the dividends
+    /// at their ex-dates. A dividend whose ex-date is not a grid point goes ex at the next grid point,
+    /// and dividends after the last grid point are not paid. The drift is the one without dividend yield.
+    pub fn generate_path_with_dividends(
+        &self,
+        initial_value: f64,
+        grid: &TimeGrid,
+        dividends: &[ExDividend],
+        standard_normals: &[f64],
+    ) -> DividendPath {
+        assert_eq!(standard_normals.len(), grid.nr_steps());
+        let mut ex = Vec::with_capacity(grid.nr_steps() + 1);
+        let mut cum = Vec::with_capacity(grid.nr_steps() + 1);
+
+        // the dividends by the grid index they go ex at, merged with the steps in one pass
+        let mut ex_dates: Vec<(usize, &ExDividend)> = dividends
+            .iter()
+            .filter_map(|d| grid.index_at_or_after(d.time).map(|index| (index, d)))
+            .collect();
+        ex_dates.sort_by(|(_, a), (_, b)| a.time.total_cmp(&b.time));
+        let mut ex_dates = ex_dates.into_iter().peekable();
+
+        let mut curr_p = initial_value;
+        ex.push(curr_p);
+        cum.push(curr_p);
+        for (step, (dt, z)) in grid.dts().into_iter().zip(standard_normals).enumerate() {
+            curr_p = self.step_analytic_over(curr_p, dt, *z);
+            cum.push(curr_p);
+            while let Some((_, dividend)) = ex_dates.next_if(|(index, _)| *index <= step + 1) {
+                curr_p = dividend.ex_price(curr_p);
+            }
+            ex.push(curr_p);
+        }
+        DividendPath { cum, ex }
+    }
+
+    /// The path on the time grid (one standard normal per step, starting with the initial value), where the
+    /// drift of each step is the curve's forward rate over the step instead of the constant drift, such that
+    /// the discounted spot is a martingale under the curve's discount factors.
+    pub fn generate_path_on_curve(
+        &self,
+        initial_value: f64,
+        grid: &TimeGrid,
+        curve: &impl YieldCurve,
+        standard_normals: &[f64],
+    ) -> Vec<f64> {
+        assert_eq!(standard_normals.len(), grid.nr_steps());
+        let mut path = Vec::with_capacity(grid.nr_steps() + 1);
+        let mut curr_p = initial_value;
+        path.push(curr_p);
+        for (t, z) in grid.times().windows(2).zip(standard_normals) {
+            let dt = t[1] - t[0];
+            let ret = curve.forward_rate(t[0], t[1]) * dt - 0.5 * self.sigma.powi(2) * dt
+                + dt.sqrt() * self.sigma * z;
+            curr_p *= ret.exp();
+            path.push(curr_p);
+        }
+        path
+    }
+
+    /// The path of one step per standard normal, starting at the initial value if configured.
     pub fn generate_path(&self, initial_value: f64, standard_normals: &[f64]) -> Vec<f64> {
         let mut path = Vec::with_capacity(standard_normals.len() + 1);
 
         let mut curr_p = initial_value;
-        path.push(curr_p);
+        if self.include_initial_value {
+            path.push(curr_p);
+        }
 
         for z in standard_normals {
             curr_p = self.step(curr_p, *z);
@@ -59,6 +163,7 @@ impl GeometricBrownianMotion {
         path
     }
 
+    /// Overwrites the standard normals by the path, which hence never contains the initial value.
     pub fn generate_in_place(&self, standard_normals: &mut [f64]) {
         let mut curr_p = self.initial_value;
 
@@ -69,6 +174,73 @@ impl GeometricBrownianMotion {
     }
 }
 
+/// The amount of a discrete dividend.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DividendAmount {
+    /// a cash amount, capped at the spot
+    Cash(f64),
+    /// a fraction of the spot
+    Proportional(f64),
+}
+
+/// A dividend going ex at `time` (in years).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExDividend {
+    pub time: f64,
+    pub amount: DividendAmount,
+}
+
+impl ExDividend {
+    pub fn new(time: f64, amount: DividendAmount) -> Self {
+        assert!(time > 0.0);
+        Self { time, amount }
+    }
+
+    /// The spot after the dividend for the spot `cum` before.
+    pub fn ex_price(&self, cum: f64) -> f64 {
+        match self.amount {
+            DividendAmount::Cash(amount) => (cum - amount).max(0.0),
+            DividendAmount::Proportional(fraction) => cum * (1.0 - fraction),
+        }
+    }
+}
+
+/// The spots on a time grid before (cum) and after (ex) the dividends, which only differ on ex-dates.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DividendPath {
+    pub cum: Vec<f64>,
+    pub ex: Vec<f64>,
+}
+
+impl StatisticsPathGenerator for GeometricBrownianMotion {
+    /// The path (starting at the initial value if configured), with the statistics updated within the step loop.
+    #[inline]
+    fn sample_path_with_statistics<SeedRng>(
+        &self,
+        rn_generator: &mut SeedRng,
+        nr_samples: usize,
+    ) -> PathWithStatistics
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        let mut path = Vec::with_capacity(nr_samples + 1);
+        let mut statistics = RunningStatistics::new(self.initial_value);
+
+        let mut curr_p = self.initial_value;
+        if self.include_initial_value {
+            path.push(curr_p);
+        }
+        for z in rn_generator.sample_iter(StandardNormal).take(nr_samples) {
+            let next_p = self.step(curr_p, z);
+            statistics.update(curr_p, next_p);
+            path.push(next_p);
+            curr_p = next_p;
+        }
+
+        PathWithStatistics { path, statistics }
+    }
+}
+
 impl Distribution<f64> for GeometricBrownianMotion {
     #[inline]
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
@@ -84,14 +256,166 @@ impl PathGenerator<Vec<f64>> for GeometricBrownianMotion {
         SeedRng: rand::SeedableRng + rand::RngCore,
     {
         let mut standard_normals = StandardNormal.sample_path(rn_generator, nr_samples);
+        if self.include_initial_value {
+            return self.generate_path(self.initial_value, &standard_normals);
+        }
         self.generate_in_place(&mut standard_normals);
         standard_normals
     }
 }
 
+impl ControlledPathGenerator<Vec<f64>> for GeometricBrownianMotion {
+    type StepValue = f64;
+
+    fn sample_path_controlled<SeedRng>(
+        &self,
+        rn_generator: &mut SeedRng,
+        nr_samples: usize,
+        controller: &impl StepController<f64>,
+    ) -> Vec<f64>
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        let mut path = Vec::with_capacity(nr_samples + 1);
+        let mut curr_p = self.initial_value;
+        if self.include_initial_value {
+            path.push(curr_p);
+        }
+        for step in 1..=nr_samples {
+            curr_p = self.step(curr_p, rn_generator.sample(StandardNormal));
+            path.push(curr_p);
+            if controller.after_step(step, &curr_p) == StepControl::Stop {
+                break;
+            }
+        }
+        path
+    }
+}
+
 impl Dynamics<f64, &[f64], Vec<f64>> for GeometricBrownianMotion {
     #[inline]
     fn transform(&self, initial_value: f64, std_normals: &[f64]) -> Vec<f64> {
         self.generate_path(initial_value, std_normals)
     }
 }
+
+/// Transforms a path of standard normals into the price path, starting at the initial value if configured.
+impl PathStage<Vec<f64>> for GeometricBrownianMotion {
+    type Output = Vec<f64>;
+
+    #[inline]
+    fn apply(&self, std_normals: Vec<f64>) -> Vec<f64> {
+        self.generate_path(self.initial_value, &std_normals)
+    }
+}
+
+/// Intermediate state of the GBM to warm start paths from.
+/// Drift and volatility are kept from the original dynamics unless given.
+#[derive(Clone, Debug)]
+pub struct GbmState {
+    pub spot: f64,
+    pub drift: Option<f64>,
+    pub vola: Option<f64>,
+}
+
+impl GbmState {
+    pub fn new(spot: f64, drift: Option<f64>, vola: Option<f64>) -> Self {
+        Self { spot, drift, vola }
+    }
+}
+
+impl WarmStart for GeometricBrownianMotion {
+    type State = GbmState;
+
+    fn dt(&self) -> f64 {
+        self.dt
+    }
+
+    fn warm_start(&self, _t0: f64, state: &GbmState) -> Self {
+        GeometricBrownianMotion::new(
+            state.spot,
+            state.drift.unwrap_or(self.mu),
+            state.vola.unwrap_or(self.sigma),
+            self.dt,
+        )
+        .with_initial_value_in_path(self.include_initial_value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use rand::SeedableRng;
+
+    #[test]
+    fn initial_value_inclusion() {
+        let gbm = GeometricBrownianMotion::new(100.0, 0.05, 0.2, 0.1);
+        let normals = [0.1, -0.2, 0.3];
+        let path: Vec<f64> = gbm.sample_path(&mut rand_hc::Hc128Rng::seed_from_u64(1), 3);
+        assert_eq!(path.len(), 4);
+        assert_eq!(path[0], 100.0);
+        assert_eq!(gbm.generate_path(100.0, &normals).len(), 4);
+
+        let gbm = gbm.with_initial_value_in_path(false);
+        let steps: Vec<f64> = gbm.sample_path(&mut rand_hc::Hc128Rng::seed_from_u64(1), 3);
+        assert_eq!(steps, path[1..]);
+        let mut in_place = normals;
+        gbm.generate_in_place(&mut in_place);
+        assert_eq!(gbm.generate_path(100.0, &normals), in_place);
+        assert_eq!(
+            gbm.sample_path_with_statistics(&mut rand_hc::Hc128Rng::seed_from_u64(1), 3)
+                .path,
+            steps
+        );
+    }
+
+    #[test]
+    fn path_with_discrete_dividends() {
+        let (spot, rfr, vola, t) = (100.0, 0.05, 0.2, 1.0);
+        let dividends = [
+            ExDividend::new(0.3, DividendAmount::Cash(2.0)),
+            ExDividend::new(0.7, DividendAmount::Proportional(0.01)),
+        ];
+        let grid = TimeGrid::uniform(t, 4).with_event_times(&[0.3, 0.7]);
+        assert_eq!(grid.nr_steps(), 6);
+
+        let gbm = GeometricBrownianMotion::new(spot, rfr, vola, t / 4.0);
+        let path = gbm.generate_path_with_dividends(spot, &grid, &dividends, &[0.0; 6]);
+        let ex_date = grid.index_of(0.3).unwrap();
+        assert_approx_eq!(path.cum[ex_date] - path.ex[ex_date], 2.0, 1e-12);
+        // the step after the ex-date starts from the ex price
+        let drift = (rfr - vola * vola / 2.0) * 0.2;
+        assert_approx_eq!(path.cum[ex_date + 1], path.ex[ex_date] * drift.exp(), 1e-12);
+        assert_eq!(path.cum[1], path.ex[1]);
+
+        let mut rn_generator = rand_hc::Hc128Rng::seed_from_u64(5);
+        let nr_paths = 20_000;
+        let forward = (0..nr_paths)
+            .map(|_| {
+                let normals = StandardNormal.sample_path(&mut rn_generator, grid.nr_steps());
+                *gbm.generate_path_with_dividends(spot, &grid, &dividends, &normals)
+                    .ex
+                    .last()
+                    .unwrap()
+            })
+            .sum::<f64>()
+            / nr_paths as f64;
+        let expected = (spot * (rfr * t).exp() - 2.0 * (rfr * (t - 0.3)).exp()) * 0.99;
+        assert_approx_eq!(forward, expected, 0.5);
+
+        // the dividends off the grid go ex at the next grid point in the order of their ex-dates,
+        // and the dividends after the maturity are not paid
+        let coarse = TimeGrid::uniform(t, 4);
+        let unsorted = [
+            ExDividend::new(0.7, DividendAmount::Proportional(0.01)),
+            ExDividend::new(1.5, DividendAmount::Cash(5.0)),
+            ExDividend::new(0.3, DividendAmount::Cash(2.0)),
+            ExDividend::new(0.6, DividendAmount::Cash(1.0)),
+        ];
+        let path = gbm.generate_path_with_dividends(spot, &coarse, &unsorted, &[0.0; 4]);
+        assert_approx_eq!(path.cum[2] - path.ex[2], 2.0, 1e-12);
+        assert_approx_eq!(path.ex[3], (path.cum[3] - 1.0) * 0.99, 1e-12);
+        assert_eq!(path.cum[4], path.ex[4]);
+    }
+}
@@ -0,0 +1,472 @@
+use std::ops::{Add, Div, Mul, Sub};
+
+use rand::Rng;
+use rand_distr::{ChiSquared, Distribution, Poisson, StandardNormal};
+
+pub use crate::common::models::HestonParameters;
+use crate::common::results::PricingError;
+use crate::numerics::solvers::{brent, newton, Solution, SolverOptions};
+use crate::simulation::monte_carlo::PathGenerator;
+
+/// The discretization of the steps of the Heston model.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HestonScheme {
+    /// log-Euler steps of the spot with the full truncation of the variance at 0, biased at coarse steps
+    #[default]
+    Euler,
+    /// the exact steps of Broadie and Kaya: the variance by its noncentral chi-squared law, the integrated
+    /// variance by the Fourier inversion of its law conditional on the variances at both ends of the step,
+    /// and the log-spot by its normal law conditional on both, without bias for any step size
+    Exact,
+}
+
+/// The Heston stochastic volatility model
+/// '''math
+/// dS_t / S_t = r dt + \sqrt{v_t} dW^S_t, \quad dv_t = \kappa (\theta - v_t) dt + \sigma \sqrt{v_t} dW^v_t
+/// ''', with $d\langle W^S, W^v \rangle_t = \rho dt$, whose paths are the spots on steps of dt.
+/// See https://en.wikipedia.org/wiki/Heston_model
+#[derive(Clone, Debug)]
+pub struct Heston {
+    initial_value: f64,
+    rate: f64,
+    parameters: HestonParameters,
+    dt: f64,
+    scheme: HestonScheme,
+}
+
+impl Heston {
+    pub fn new(
+        initial_value: f64,
+        rate: f64,
+        parameters: HestonParameters,
+        dt: f64,
+    ) -> Result<Self, PricingError> {
+        PricingError::check(initial_value > 0.0, "initial value", initial_value)?;
+        parameters.validate()?;
+        PricingError::check(dt > 0.0, "dt", dt)?;
+        Ok(Self {
+            initial_value,
+            rate,
+            parameters,
+            dt,
+            scheme: HestonScheme::default(),
+        })
+    }
+
+    pub fn with_scheme(self, scheme: HestonScheme) -> Self {
+        Self { scheme, ..self }
+    }
+
+    pub fn parameters(&self) -> &HestonParameters {
+        &self.parameters
+    }
+
+    /// The Euler step over dt of the spot and the variance with the independent standard normals.
+    pub fn step_euler(
+        &self,
+        (st, vt): (f64, f64),
+        dt: f64,
+        z: f64,
+        z_independent: f64,
+    ) -> (f64, f64) {
+        let p = &self.parameters;
+        let v = vt.max(0.0);
+        let z_spot = p.correlation * z + (1.0 - p.correlation.powi(2)).sqrt() * z_independent;
+        let spot = st * ((self.rate - 0.5 * v) * dt + (v * dt).sqrt() * z_spot).exp();
+        let variance = vt
+            + p.mean_reversion * (p.long_term_variance - v) * dt
+            + p.vol_of_vol * (v * dt).sqrt() * z;
+        (spot, variance)
+    }
+
+    /// The exact step over dt of the spot and the variance (Broadie and Kaya).
+    pub fn step_exact<R: Rng>(
+        &self,
+        (st, vt): (f64, f64),
+        dt: f64,
+        rn_generator: &mut R,
+    ) -> (f64, f64) {
+        let p = &self.parameters;
+        let variance = self.sample_variance(vt, dt, rn_generator);
+        // a failed inversion makes the path non-finite, which the evaluation of the paths reports
+        let integrated = self
+            .sample_integrated_variance(vt, variance, dt, rn_generator)
+            .unwrap_or(f64::NAN);
+        // the integral of the volatility against the variance's Brownian motion
+        let vol_integral = (variance - vt - p.mean_reversion * p.long_term_variance * dt
+            + p.mean_reversion * integrated)
+            / p.vol_of_vol;
+        let z: f64 = rn_generator.sample(StandardNormal);
+        let log_return = self.rate * dt - 0.5 * integrated
+            + p.correlation * vol_integral
+            + ((1.0 - p.correlation.powi(2)) * integrated).sqrt() * z;
+        (st * log_return.exp(), variance)
+    }
+
+    /// Samples the variance after dt from the variance vt, a scaled noncentral chi-squared variable
+    /// with $4 \kappa \theta / \sigma^2$ degrees of freedom, as a Poisson mixture of chi-squared variables.
+    /// See https://en.wikipedia.org/wiki/Noncentral_chi-squared_distribution
+    pub fn sample_variance<R: Rng>(&self, vt: f64, dt: f64, rn_generator: &mut R) -> f64 {
+        let p = &self.parameters;
+        let decay = (-p.mean_reversion * dt).exp();
+        let scale = p.vol_of_vol.powi(2) * (1.0 - decay) / (4.0 * p.mean_reversion);
+        let noncentrality = vt.max(0.0) * decay / scale;
+        let nr_poisson = if noncentrality > 0.0 {
+            Poisson::new(0.5 * noncentrality)
+                .expect("positive noncentrality")
+                .sample(rn_generator)
+        } else {
+            0.0
+        };
+        let chi_squared = ChiSquared::new(self.degrees_of_freedom() + 2.0 * nr_poisson)
+            .expect("positive degrees of freedom");
+        scale * chi_squared.sample(rn_generator)
+    }
+
+    /// Samples the integrated variance $\int_t^{t + dt} v_s ds$ conditional on the variances at both ends
+    /// of the step by inverting its cdf, which is the Fourier inversion of its characteristic function.
+    /// Fails if the inversion does not converge, e.g. if the truncated cdf misses the uniform sample.
+    pub fn sample_integrated_variance<R: Rng>(
+        &self,
+        v_start: f64,
+        v_end: f64,
+        dt: f64,
+        rn_generator: &mut R,
+    ) -> Result<f64, PricingError> {
+        let (v_start, v_end) = (v_start.max(0.0), v_end.max(0.0));
+        let characteristic = |a: f64| self.integrated_variance_cf(a, v_start, v_end, dt);
+
+        // the mean and the variance from the derivatives of the characteristic function at 0
+        let scale =
+            (0.5 * (v_start + v_end) * dt).max(1e-2 * self.parameters.long_term_variance * dt);
+        let a = 1e-4 / scale;
+        let phi = characteristic(a);
+        let mean = phi.im / a;
+        let std_dev = (2.0 * (1.0 - phi.re) / (a * a) - mean * mean)
+            .max(0.0)
+            .sqrt();
+        let upper = mean + 12.0 * std_dev;
+
+        // the cdf $F(x) = h x / \pi + 2 / \pi \sum_j \sin(h j x) / j Re \phi(h j)$ by the trapezoidal rule,
+        // truncated when the characteristic function is negligible
+        let h = std::f64::consts::PI / upper;
+        let mut weights = Vec::new();
+        for j in 1..=MAX_NR_FOURIER_TERMS {
+            let phi = characteristic(h * j as f64);
+            if phi.norm() / (j as f64) < 0.5 * std::f64::consts::PI * FOURIER_TOLERANCE {
+                break;
+            }
+            weights.push(phi.re);
+        }
+        let cdf_pdf = |x: f64| {
+            let (mut cdf, mut pdf) = (h * x, h);
+            for (j, weight) in weights.iter().enumerate() {
+                let hj = h * (j + 1) as f64;
+                cdf += 2.0 * (hj * x).sin() / (j + 1) as f64 * weight;
+                pdf += 2.0 * h * (hj * x).cos() * weight;
+            }
+            (cdf / std::f64::consts::PI, pdf / std::f64::consts::PI)
+        };
+
+        let u: f64 = rn_generator.gen();
+        invert_cdf(cdf_pdf, u, mean, upper)
+    }
+
+    /// The degrees of freedom $4 \kappa \theta / \sigma^2$ of the variance.
+    fn degrees_of_freedom(&self) -> f64 {
+        let p = &self.parameters;
+        4.0 * p.mean_reversion * p.long_term_variance / p.vol_of_vol.powi(2)
+    }
+
+    /// The characteristic function $E[e^{i a V} | v_{start}, v_{end}]$ of the integrated variance V over
+    /// dt conditional on the variances at both ends, a ratio of modified Bessel functions of the first kind.
+    fn integrated_variance_cf(&self, a: f64, v_start: f64, v_end: f64, dt: f64) -> Complex {
+        let p = &self.parameters;
+        let (kappa, sigma2) = (p.mean_reversion, p.vol_of_vol.powi(2));
+        let nu = 0.5 * self.degrees_of_freedom() - 1.0;
+        let gamma = Complex::new(kappa * kappa, -2.0 * sigma2 * a).sqrt();
+        let decay_kappa = (-kappa * dt).exp();
+        let decay_gamma = (gamma * -dt).exp();
+        let one = Complex::new(1.0, 0.0);
+
+        // the logarithm of the ratio of the Bessel arguments, continuous in a unlike their phases
+        let ln_ratio = gamma.ln() - Complex::new(kappa.ln(), 0.0) - (gamma - kappa) * (0.5 * dt)
+            + Complex::new((1.0 - decay_kappa).ln(), 0.0)
+            - (one - decay_gamma).ln();
+        let coth_kappa = kappa * (1.0 + decay_kappa) / (1.0 - decay_kappa);
+        let coth_gamma = gamma * (one + decay_gamma) / (one - decay_gamma);
+        let exponent = ln_ratio * (nu + 1.0)
+            + (Complex::new(coth_kappa, 0.0) - coth_gamma) * ((v_start + v_end) / sigma2);
+
+        let root = 4.0 * (v_start * v_end).sqrt() / sigma2;
+        let z = gamma * (gamma * (-0.5 * dt)).exp() / (one - decay_gamma) * root;
+        let x = root * kappa * (-0.5 * kappa * dt).exp() / (1.0 - decay_kappa);
+        let (series_z, scalings_z) = bessel_series(z, nu);
+        let (series_x, scalings_x) = bessel_series(Complex::new(x, 0.0), nu);
+        exponent.exp() * (series_z / series_x) * SERIES_RESCALING.powi(scalings_z - scalings_x)
+    }
+}
+
+/// The quantile of u in [0, upper] by Newton's method from the initial guess, and by Brent's method on
+/// the bracket if Newton's method does not converge.
+fn invert_cdf(
+    cdf_pdf: impl Fn(f64) -> (f64, f64),
+    u: f64,
+    initial_guess: f64,
+    upper: f64,
+) -> Result<f64, PricingError> {
+    let options = SolverOptions::new(1e-10 * upper, 1e-10, 100);
+    let f_df = |x: f64| {
+        let (cdf, pdf) = cdf_pdf(x);
+        (cdf - u, pdf)
+    };
+    newton(f_df, initial_guess, 0.0, upper, &options)
+        .and_then(Solution::converged)
+        .or_else(|| brent(|x| f_df(x).0, 0.0, upper, &options).and_then(Solution::converged))
+        .ok_or_else(|| {
+            PricingError::InvalidParameter(format!(
+                "integrated variance quantile {u} beyond the truncation at {upper}"
+            ))
+        })
+}
+
+/// The maximal number of terms of the Fourier inversion of the cdf of the integrated variance.
+const MAX_NR_FOURIER_TERMS: usize = 10_000;
+/// The discretization error of the Fourier inversion of the cdf of the integrated variance.
+const FOURIER_TOLERANCE: f64 = 1e-5;
+/// The factor by which the Bessel series is rescaled to prevent overflows of large arguments.
+const SERIES_RESCALING: f64 = 1e100;
+
+/// The series $\sum_k (z^2 / 4)^k / (k! (\nu + 1)_k) = \Gamma(\nu + 1) I_\nu(z) / (z / 2)^\nu$ of the
+/// modified Bessel function of the first kind for $\nu > -1$, rescaled by the returned power of
+/// `SERIES_RESCALING`.
+/// See https://en.wikipedia.org/wiki/Bessel_function#Modified_Bessel_functions:_I%CE%B1,_K%CE%B1
+fn bessel_series(z: Complex, nu: f64) -> (Complex, i32) {
+    let quarter_z2 = z * z * 0.25;
+    let (mut term, mut sum) = (Complex::new(1.0, 0.0), Complex::new(1.0, 0.0));
+    let mut scalings = 0;
+    // the terms grow until k exceeds |z| / 2
+    let min_nr_terms = 0.5 * z.norm();
+    for k in 1..100_000 {
+        term = term * quarter_z2 / (k as f64 * (k as f64 + nu));
+        sum = sum + term;
+        if sum.norm() > SERIES_RESCALING {
+            sum = sum / SERIES_RESCALING;
+            term = term / SERIES_RESCALING;
+            scalings += 1;
+        }
+        if k as f64 > min_nr_terms && term.norm() <= f64::EPSILON * sum.norm() {
+            break;
+        }
+    }
+    (sum, scalings)
+}
+
+/// A complex number for the characteristic function of the integrated variance.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    fn norm(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    fn exp(self) -> Self {
+        let modulus = self.re.exp();
+        Self::new(modulus * self.im.cos(), modulus * self.im.sin())
+    }
+
+    /// The principal branch of the logarithm.
+    fn ln(self) -> Self {
+        Self::new(self.norm().ln(), self.im.atan2(self.re))
+    }
+
+    /// The principal square root, with a non-negative real part.
+    fn sqrt(self) -> Self {
+        let norm = self.norm();
+        let re = (0.5 * (norm + self.re)).sqrt();
+        let im = (0.5 * (norm - self.re)).sqrt();
+        Self::new(re, if self.im < 0.0 { -im } else { im })
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Sub<f64> for Complex {
+    type Output = Self;
+
+    fn sub(self, other: f64) -> Self {
+        Self::new(self.re - other, self.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+impl Mul<f64> for Complex {
+    type Output = Self;
+
+    fn mul(self, other: f64) -> Self {
+        Self::new(self.re * other, self.im * other)
+    }
+}
+
+impl Div for Complex {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        let norm2 = other.re * other.re + other.im * other.im;
+        Self::new(
+            (self.re * other.re + self.im * other.im) / norm2,
+            (self.im * other.re - self.re * other.im) / norm2,
+        )
+    }
+}
+
+impl Div<f64> for Complex {
+    type Output = Self;
+
+    fn div(self, other: f64) -> Self {
+        Self::new(self.re / other, self.im / other)
+    }
+}
+
+impl PathGenerator<Vec<f64>> for Heston {
+    fn sample_path<SeedRng>(&self, rn_generator: &mut SeedRng, nr_samples: usize) -> Vec<f64>
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        let mut path = Vec::with_capacity(nr_samples + 1);
+        let mut state = (self.initial_value, self.parameters.initial_variance);
+        path.push(state.0);
+        for _ in 0..nr_samples {
+            state = match self.scheme {
+                HestonScheme::Euler => {
+                    let z = rn_generator.sample(StandardNormal);
+                    let z_independent = rn_generator.sample(StandardNormal);
+                    self.step_euler(state, self.dt, z, z_independent)
+                }
+                HestonScheme::Exact => self.step_exact(state, self.dt, rn_generator),
+            };
+            path.push(state.0);
+        }
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::monte_carlo::MonteCarloPathSimulator;
+    use crate::simulation::PathEvaluator;
+    use rand::SeedableRng;
+
+    #[test]
+    fn conditional_integrated_variance() {
+        let parameters = HestonParameters {
+            initial_variance: 0.04,
+            mean_reversion: 1.5,
+            long_term_variance: 0.04,
+            vol_of_vol: 0.5,
+            correlation: -0.7,
+        };
+        let heston = Heston::new(100.0, 0.02, parameters, 1.0).unwrap();
+        assert!(!parameters.feller_condition());
+        let phi = heston.integrated_variance_cf(0.0, 0.04, 0.03, 1.0);
+        assert!((phi.re - 1.0).abs() < 1e-12 && phi.im.abs() < 1e-12);
+
+        // the unconditional mean of the integrated variance over many steps from the stationary mean
+        let mut rng = rand_hc::Hc128Rng::seed_from_u64(4);
+        let n = 2_000;
+        let (mut sum, mut sum_terminal) = (0.0, 0.0);
+        for _ in 0..n {
+            let terminal = heston.sample_variance(0.04, 0.5, &mut rng);
+            sum_terminal += terminal;
+            sum += heston
+                .sample_integrated_variance(0.04, terminal, 0.5, &mut rng)
+                .unwrap();
+        }
+        assert!((sum_terminal / n as f64 - 0.04).abs() < 0.002);
+        assert!((sum / n as f64 - 0.02).abs() < 5e-4, "{}", sum / n as f64);
+        // the inversion of a cdf missing the uniform sample fails instead of returning the bound
+        let uniform = |x: f64| (x, 1.0);
+        assert!((invert_cdf(uniform, 0.3, 0.9, 1.0).unwrap() - 0.3).abs() < 1e-10);
+        let truncated = |x: f64| (0.5 * x, 0.5);
+        assert!(invert_cdf(truncated, 0.9, 0.5, 1.0).is_err());
+        assert!(Heston::new(
+            100.0,
+            0.02,
+            HestonParameters {
+                correlation: -1.5,
+                ..parameters
+            },
+            1.0
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn exact_scheme_at_a_single_step() {
+        // the reference price of Broadie and Kaya by the Fourier inversion of the Heston characteristic function
+        let (strike, rate, maturity) = (100.0, 0.0319, 1.0);
+        let parameters = HestonParameters {
+            initial_variance: 0.010201,
+            mean_reversion: 6.21,
+            long_term_variance: 0.019,
+            vol_of_vol: 0.61,
+            correlation: -0.7,
+        };
+        let reference = 6.8061;
+        let call = |scheme: HestonScheme, nr_steps: usize| {
+            let heston = Heston::new(100.0, rate, parameters, maturity / nr_steps as f64)
+                .unwrap()
+                .with_scheme(scheme);
+            let simulator: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, Vec<f64>> =
+                MonteCarloPathSimulator::new(heston, Some(11));
+            let paths = simulator.simulate_paths(10_000, nr_steps);
+            PathEvaluator::new(&paths)
+                .evaluate_average(|path| {
+                    path.last()
+                        .map(|s| (-rate * maturity).exp() * (s - strike).max(0.0))
+                })
+                .unwrap()
+        };
+        let exact = call(HestonScheme::Exact, 1);
+        assert!((exact - reference).abs() < 0.25, "{exact}");
+        // the Euler scheme is biased at a single step
+        let euler = call(HestonScheme::Euler, 1);
+        assert!(
+            (euler - reference).abs() > 2.0 * (exact - reference).abs(),
+            "{euler}"
+        );
+    }
+}
@@ -1,2 +1,9 @@
 pub mod gbm;
+pub mod heston;
+#[cfg(feature = "multi-asset")]
 pub mod multivariate_gbm;
+#[cfg(feature = "multi-asset")]
+pub mod multivariate_jump_diffusion;
+pub mod rough_bergomi;
+pub mod shifted_lognormal;
+pub mod stochastic_dividend;
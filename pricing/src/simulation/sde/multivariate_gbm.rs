@@ -4,16 +4,30 @@ use ndarray_rand::RandomExt;
 use rand::Rng;
 use rand_distr::{Distribution, StandardNormal};
 
-use crate::simulation::monte_carlo::PathGenerator;
+use crate::common::term_structure::PiecewiseConstantTermStructure;
+use crate::numerics::linalg::cholesky;
+use crate::simulation::distributions::CorrelationStructure;
+use crate::simulation::monte_carlo::{PathGenerator, WarmStart};
+use crate::simulation::path_layout::AssetMajorPath;
 
+#[derive(Clone, Debug)]
 pub struct MultivariateGeometricBrownianMotion {
     initial_values: Array1<f64>,
     /// drift term
     drifts: Array1<f64>,
-    /// volatility
-    cholesky_factor: Array2<f64>,
+    /// volatility and correlation, e.g. via the cholesky factor or a factor model
+    correlation: CorrelationStructure,
     /// change in time
     dt: f64,
+    /// time (in years) of the initial values, aligning the paths with the term structures
+    start_time: f64,
+    /// per-asset drifts over time, replacing the constant drifts if given
+    drift_term_structure: Option<PiecewiseConstantTermStructure>,
+    /// per-asset volatilities over time; if given, the correlation structure is expected
+    /// to be normalized to unit variances and is scaled by these volatilities
+    vola_term_structure: Option<PiecewiseConstantTermStructure>,
+    /// whether sampled paths start with the initial values, see `PathGenerator`
+    include_initial_value: bool,
 }
 
 impl MultivariateGeometricBrownianMotion {
@@ -30,58 +44,221 @@ impl MultivariateGeometricBrownianMotion {
         assert_eq!(iv_shape, drifts_shape);
         assert_eq!(matrix_shape, &[drifts_shape[0], drifts_shape[0]]);
 
-        // TODO: add a check that cholesky_factor is triangular; oR provide only a constructor using the correlation matrix
-        // https://docs.rs/ndarray-linalg/0.9.0/ndarray_linalg/cholesky/index.html
-        // use ndarray_linalg::cholesky::*;
+        // TODO: add a check that cholesky_factor is triangular; or use `from_covariance`
 
         Self {
             initial_values,
             drifts,
-            cholesky_factor,
+            correlation: CorrelationStructure::Cholesky(cholesky_factor),
+            dt,
+            start_time: 0.0,
+            drift_term_structure: None,
+            vola_term_structure: None,
+            include_initial_value: true,
+        }
+    }
+
+    /// Multivariate GBM with the given covariance matrix of the returns, factorized by Cholesky,
+    /// or None if the covariance is not positive definite.
+    pub fn from_covariance(
+        initial_values: Array1<f64>,
+        drifts: Array1<f64>,
+        covariance: &Array2<f64>,
+        dt: f64,
+    ) -> Option<Self> {
+        cholesky(covariance)
+            .map(|cholesky_factor| Self::new(initial_values, drifts, cholesky_factor, dt))
+    }
+
+    /// Multivariate GBM with the covariance $B B^T + diag(d^2)$ of a factor model with loadings $B$
+    /// and idiosyncratic volatilities $d$, such that a step costs $O(nk)$ for $k$ factors.
+    pub fn with_factor_model(
+        initial_values: Array1<f64>,
+        drifts: Array1<f64>,
+        loadings: Array2<f64>,
+        idiosyncratic: Array1<f64>,
+        dt: f64,
+    ) -> Self {
+        assert_eq!(initial_values.shape(), drifts.shape());
+        assert_eq!(loadings.nrows(), drifts.len());
+
+        Self {
+            initial_values,
+            drifts,
+            correlation: CorrelationStructure::factor_model(loadings, idiosyncratic),
             dt,
+            start_time: 0.0,
+            drift_term_structure: None,
+            vola_term_structure: None,
+            include_initial_value: true,
         }
     }
 
-    fn dim(&self) -> usize {
+    /// Whether the generated paths start with the initial values (default) or with the values after the first step.
+    pub fn with_initial_value_in_path(self, include_initial_value: bool) -> Self {
+        Self {
+            include_initial_value,
+            ..self
+        }
+    }
+
+    /// Use per-asset piecewise-constant drifts, e.g. from calibrated forward curves.
+    pub fn with_drift_term_structure(mut self, drifts: PiecewiseConstantTermStructure) -> Self {
+        assert_eq!(drifts.dim(), self.dim());
+        self.drift_term_structure = Some(drifts);
+        self
+    }
+
+    /// Use per-asset piecewise-constant volatilities, where the correlation structure
+    /// (e.g. the cholesky factor of the correlation matrix) must have unit variances.
+    pub fn with_vola_term_structure(mut self, volas: PiecewiseConstantTermStructure) -> Self {
+        assert_eq!(volas.dim(), self.dim());
+        self.vola_term_structure = Some(volas);
+        self
+    }
+
+    pub(crate) fn dim(&self) -> usize {
         self.initial_values.shape()[0]
     }
 
+    fn drifts_at(&self, t: f64) -> Array1<f64> {
+        match &self.drift_term_structure {
+            Some(term_structure) => term_structure.values_at(t),
+            None => self.drifts.to_owned(),
+        }
+    }
+
     /// See https://en.wikipedia.org/wiki/Geometric_Brownian_motion
     pub(crate) fn step(&self, st: &Array1<f64>, std_normal_vec: &Array1<f64>) -> Array1<f64> {
-        let d_st_s0: Array1<f64> =
-            self.dt * &self.drifts + self.dt.sqrt() * self.cholesky_factor.dot(std_normal_vec);
+        self.step_at(self.start_time, st, std_normal_vec)
+    }
+
+    /// The step from time t to t + dt.
+    pub(crate) fn step_at(
+        &self,
+        t: f64,
+        st: &Array1<f64>,
+        std_normal_vec: &Array1<f64>,
+    ) -> Array1<f64> {
+        let mut rnd = self.dt.sqrt() * self.correlation.correlate(std_normal_vec);
+        if let Some(term_structure) = &self.vola_term_structure {
+            rnd = rnd * term_structure.values_at(t);
+        }
+        let d_st_s0: Array1<f64> = self.dt * self.drifts_at(t) + rnd;
 
         st + st * &d_st_s0
     }
 
     pub fn transform_path(&self, sample_matrix: &Array2<f64>, nr_samples: usize) -> Array2<f64> {
-        let mut multivariate_normals = self.dt.sqrt() * self.cholesky_factor.dot(sample_matrix);
+        let mut multivariate_normals =
+            self.dt.sqrt() * self.correlation.correlate_matrix(sample_matrix);
         let dim = self.dim();
-
-        //TODO: possible to use multivariate_normals.axis_windows(Axis(0), 2)?
+        let nr_steps = nr_samples.saturating_sub(1);
+
+        // column j of the grids applies to the step from column j to j + 1
+        let drift_grid = match &self.drift_term_structure {
+            Some(term_structure) => term_structure.on_grid(self.start_time, self.dt, nr_steps),
+            None => self
+                .drifts
+                .broadcast((nr_steps, dim))
+                .unwrap()
+                .t()
+                .to_owned(),
+        };
+        let vola_grid = self
+            .vola_term_structure
+            .as_ref()
+            .map(|term_structure| term_structure.on_grid(self.start_time, self.dt, nr_steps));
 
         // overwrite the first column by initial prices
-        for idx in 0..dim {
-            multivariate_normals[[idx, 0]] = self.initial_values[idx];
-        }
+        multivariate_normals
+            .column_mut(0)
+            .assign(&self.initial_values);
 
         for idx in 1..nr_samples {
             let st = multivariate_normals.column(idx - 1);
-            let rnd = multivariate_normals.column(idx);
-            let d_st_s0: Array1<f64> = self.dt * &self.drifts + rnd;
-            let stn = &st + &st * &d_st_s0;
-            for i in 0..dim {
-                multivariate_normals[[i, idx]] = stn[i];
+            let mut rnd = multivariate_normals.column(idx).to_owned();
+            if let Some(vola_grid) = &vola_grid {
+                rnd = rnd * vola_grid.column(idx - 1);
             }
+            let d_st_s0: Array1<f64> = self.dt * &drift_grid.column(idx - 1) + rnd;
+            let stn = &st + &st * &d_st_s0;
+            multivariate_normals.column_mut(idx).assign(&stn);
         }
         multivariate_normals
     }
 }
 
+impl MultivariateGeometricBrownianMotion {
+    /// Simulates all paths simultaneously with shape (nr_paths, dim, nr_steps + 1), i.e. `paths.outer_iter()`
+    /// yields paths in the layout of `transform_path`. Each step correlates the normals of the whole
+    /// ensemble by one matrix product and updates all paths by array operations. The paths always
+    /// start with the initial values. The single paths of `PathGenerator` are ensembles of one path.
+    pub fn sample_ensemble<R>(
+        &self,
+        rn_generator: &mut R,
+        nr_paths: usize,
+        nr_steps: usize,
+    ) -> Array3<f64>
+    where
+        R: Rng + ?Sized,
+    {
+        let dim = self.dim();
+        let distr = ndarray_rand::rand_distr::StandardNormal;
+        let mut paths = Array3::zeros((nr_paths, dim, nr_steps + 1));
+        // the current values per asset (rows) and path (columns)
+        let mut st: Array2<f64> = self
+            .initial_values
+            .broadcast((nr_paths, dim))
+            .unwrap()
+            .t()
+            .to_owned();
+        paths.slice_mut(s![.., .., 0]).assign(&st.t());
+
+        for step in 0..nr_steps {
+            let t = self.start_time + step as f64 * self.dt;
+            let normals = Array2::random_using(
+                (self.correlation.nr_factors(), nr_paths),
+                distr,
+                rn_generator,
+            );
+            let mut rnd = self.dt.sqrt() * self.correlation.correlate_matrix(&normals);
+            if let Some(term_structure) = &self.vola_term_structure {
+                rnd *= &term_structure.values_at(t).insert_axis(Axis(1));
+            }
+            let d_st_s0 = rnd + &(self.dt * self.drifts_at(t)).insert_axis(Axis(1));
+            st = &st + &st * &d_st_s0;
+            paths.slice_mut(s![.., .., step + 1]).assign(&st.t());
+        }
+        paths
+    }
+
+    /// The paths of `sample_ensemble` one by one, e.g. for a `PathEvaluator`, where the paths start
+    /// with the initial values if configured.
+    pub fn sample_ensemble_paths<R>(
+        &self,
+        rn_generator: &mut R,
+        nr_paths: usize,
+        nr_steps: usize,
+    ) -> Vec<AssetMajorPath>
+    where
+        R: Rng + ?Sized,
+    {
+        let first_time = usize::from(!self.include_initial_value);
+        self.sample_ensemble(rn_generator, nr_paths, nr_steps)
+            .outer_iter()
+            .map(|path| AssetMajorPath::new(path.slice(s![.., first_time..]).to_owned()))
+            .collect()
+    }
+}
+
 impl Distribution<Array1<f64>> for MultivariateGeometricBrownianMotion {
     #[inline]
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Array1<f64> {
-        let standard_normals: Vec<f64> = rng.sample_iter(StandardNormal).take(self.dim()).collect();
+        let standard_normals: Vec<f64> = rng
+            .sample_iter(StandardNormal)
+            .take(self.correlation.nr_factors())
+            .collect();
 
         // NOTE: be careful of fixed initial value!
         self.step(&self.initial_values, &Array1::from(standard_normals))
@@ -94,13 +271,14 @@ impl PathGenerator<Array2<f64>> for MultivariateGeometricBrownianMotion {
     where
         R: Rng + ?Sized,
     {
-        let dim = self.dim();
-        let distr = ndarray_rand::rand_distr::StandardNormal;
-        // create one extra dummy column
-        let sample_matrix =
-            ndarray::Array::random_using((dim, 1 + nr_samples), distr, rn_generator);
-
-        self.transform_path(&sample_matrix, 1 + nr_samples)
+        let path = self
+            .sample_ensemble(rn_generator, 1, nr_samples)
+            .index_axis_move(Axis(0), 0);
+        if self.include_initial_value {
+            path
+        } else {
+            path.slice_move(s![.., 1..])
+        }
     }
 }
 
@@ -111,7 +289,7 @@ impl PathGenerator<Vec<Array1<f64>>> for MultivariateGeometricBrownianMotion {
     where
         R: Rng,
     {
-        let dim = self.dim();
+        let nr_factors = self.correlation.nr_factors();
 
         let mut path = Vec::with_capacity(nr_samples + 1);
 
@@ -120,26 +298,65 @@ impl PathGenerator<Vec<Array1<f64>>> for MultivariateGeometricBrownianMotion {
         // create the random normal numbers for the whole path and all dimensions
         let path_std_normals: Vec<f64> = rn_generator
             .sample_iter(StandardNormal)
-            .take(nr_samples * dim)
+            .take(nr_samples * nr_factors)
             .collect();
 
-        for (idx, _) in path_std_normals.iter().enumerate().step_by(dim) {
-            let zs_slice = arr1(&path_std_normals[idx..idx + dim]);
+        for (step_idx, idx) in (0..path_std_normals.len()).step_by(nr_factors).enumerate() {
+            let zs_slice = arr1(&path_std_normals[idx..idx + nr_factors]);
             let curr_p = path.last().unwrap();
-            let sample = self.step(curr_p, &zs_slice);
+            let t = self.start_time + step_idx as f64 * self.dt;
+            let sample = self.step_at(t, curr_p, &zs_slice);
             path.push(sample);
         }
 
+        if !self.include_initial_value {
+            path.remove(0);
+        }
         path
     }
 }
 
+/// Intermediate state of the multivariate GBM to warm start paths from.
+/// The drifts are kept from the original dynamics unless given.
+#[derive(Clone, Debug)]
+pub struct MultivariateGbmState {
+    pub spots: Array1<f64>,
+    pub drifts: Option<Array1<f64>>,
+}
+
+impl MultivariateGbmState {
+    pub fn new(spots: Array1<f64>, drifts: Option<Array1<f64>>) -> Self {
+        Self { spots, drifts }
+    }
+}
+
+impl WarmStart for MultivariateGeometricBrownianMotion {
+    type State = MultivariateGbmState;
+
+    fn dt(&self) -> f64 {
+        self.dt
+    }
+
+    fn warm_start(&self, t0: f64, state: &MultivariateGbmState) -> Self {
+        let mut warm_started = self.clone();
+        warm_started.initial_values = state.spots.to_owned();
+        warm_started.start_time = t0;
+        if let Some(drifts) = &state.drifts {
+            warm_started.drifts = drifts.to_owned();
+            warm_started.drift_term_structure = None;
+        }
+        warm_started
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::simulation::{monte_carlo::MonteCarloPathSimulator, PathEvaluator};
 
     use super::*;
+    use assert_approx_eq::assert_approx_eq;
     use ndarray::{arr1, arr2};
+    use rand::SeedableRng;
 
     #[test]
     fn sample() {
@@ -156,6 +373,143 @@ mod tests {
         assert_eq!(sample, arr1(&[1.51, 3.5, 6.84]));
     }
 
+    #[test]
+    fn from_covariance() {
+        let covariance = arr2(&[[0.04, 0.01], [0.01, 0.09]]);
+        let mv_gbm = MultivariateGeometricBrownianMotion::from_covariance(
+            arr1(&[1.0, 1.0]),
+            arr1(&[0.0, 0.0]),
+            &covariance,
+            1.0,
+        )
+        .unwrap();
+        // the second asset's return loads on both normals
+        let sample = mv_gbm.step(&arr1(&[1.0, 1.0]), &arr1(&[1.0, 1.0]));
+        assert_approx_eq!(sample[0], 1.2, 1e-12);
+        assert_approx_eq!(sample[1], 1.0 + 0.05 + (0.09_f64 - 0.0025).sqrt(), 1e-12);
+    }
+
+    #[test]
+    fn ensemble_simulation() {
+        let initial_values = arr1(&[100.0, 50.0]);
+        let drifts = arr1(&[0.02, 0.05]);
+        let cholesky_factor = arr2(&[[0.2, 0.0], [0.15, 0.2]]);
+        let (nr_paths, nr_steps) = (20_000, 10);
+        let mv_gbm =
+            MultivariateGeometricBrownianMotion::new(initial_values, drifts, cholesky_factor, 0.1);
+
+        let mut rn_generator = rand_hc::Hc128Rng::seed_from_u64(3);
+        let paths = mv_gbm.sample_ensemble(&mut rn_generator, nr_paths, nr_steps);
+        assert_eq!(paths.shape(), &[nr_paths, 2, nr_steps + 1]);
+        assert_eq!(paths[[7, 1, 0]], 50.0);
+
+        let terminal_mean = paths
+            .slice(s![.., .., nr_steps])
+            .mean_axis(Axis(0))
+            .unwrap();
+        assert_approx_eq!(terminal_mean[0], 100.0 * 1.002_f64.powi(10), 0.5);
+        assert_approx_eq!(terminal_mean[1], 50.0 * 1.005_f64.powi(10), 0.25);
+    }
+
+    #[test]
+    fn factor_model_basket_simulation() {
+        let nr_assets = 50;
+        let initial_values = Array1::from_elem(nr_assets, 100.0);
+        let drifts = Array1::from_elem(nr_assets, 0.02);
+        // two common factors: market and sector
+        let loadings = Array2::from_shape_fn((nr_assets, 2), |(i, j)| {
+            if j == 0 {
+                0.15
+            } else if i < nr_assets / 2 {
+                0.1
+            } else {
+                -0.1
+            }
+        });
+        let idiosyncratic = Array1::from_elem(nr_assets, 0.1);
+        let mv_gbm = MultivariateGeometricBrownianMotion::with_factor_model(
+            initial_values,
+            drifts,
+            loadings,
+            idiosyncratic,
+            0.02,
+        );
+
+        let mc_simulator: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, Array2<f64>> =
+            MonteCarloPathSimulator::new(mv_gbm, Some(42));
+        let paths = mc_simulator.simulate_paths(1_000, 50);
+        assert_eq!(&paths[0].shape(), &[nr_assets, 51]);
+
+        // the expected terminal value is S_0 exp(mu T)
+        let path_eval = PathEvaluator::new(&paths);
+        let avg_price = path_eval.evaluate_average(|path| {
+            path.axis_iter(Axis(1))
+                .last()
+                .map(|p| p.sum() / nr_assets as f64)
+        });
+        assert_approx_eq!(avg_price.unwrap(), 100.0 * 0.02_f64.exp(), 1.5);
+    }
+
+    #[test]
+    fn term_structure_basket_simulation() {
+        let initial_values = arr1(&[100.0, 100.0]);
+        let cholesky_factor = arr2(&[[1.0, 0.0], [0.0, 1.0]]);
+        // the second asset has no drift and vanishing volatility on the first half year
+        let drifts =
+            PiecewiseConstantTermStructure::new(vec![0.5, 1.0], arr2(&[[0.02, 0.02], [0.0, 0.04]]));
+        let volas =
+            PiecewiseConstantTermStructure::new(vec![0.5, 1.0], arr2(&[[0.2, 0.2], [0.0, 0.1]]));
+        let mv_gbm = MultivariateGeometricBrownianMotion::new(
+            initial_values,
+            arr1(&[0.0, 0.0]),
+            cholesky_factor,
+            0.1,
+        )
+        .with_drift_term_structure(drifts)
+        .with_vola_term_structure(volas);
+
+        let path: Array2<f64> = mv_gbm.sample_path(&mut rand_hc::Hc128Rng::seed_from_u64(42), 10);
+        assert_eq!(path.shape(), &[2, 11]);
+        for idx in 0..=5 {
+            assert_eq!(path[[1, idx]], 100.0);
+        }
+        assert_ne!(path[[1, 6]], 100.0);
+
+        // the same time step sequence for the other path representation
+        let path_vec: Vec<Array1<f64>> =
+            mv_gbm.sample_path(&mut rand_hc::Hc128Rng::seed_from_u64(42), 10);
+        assert_eq!(path_vec.len(), 11);
+        assert_eq!(path_vec[5][1], 100.0);
+        assert_ne!(path_vec[6][1], 100.0);
+
+        // restarting at t0 = 0.5 immediately uses the second bucket
+        let warm_started =
+            mv_gbm.warm_start(0.5, &MultivariateGbmState::new(arr1(&[100.0, 100.0]), None));
+        let path: Array2<f64> =
+            warm_started.sample_path(&mut rand_hc::Hc128Rng::seed_from_u64(42), 5);
+        assert_ne!(path[[1, 1]], 100.0);
+    }
+
+    #[test]
+    fn warm_started_basket_simulation() {
+        let initial_values = arr1(&[110.0, 120.0]);
+        let drifts = arr1(&[0.01, 0.02]);
+        let cholesky_factor = arr2(&[[0.2, 0.05], [0.0, 0.3]]);
+        let mv_gbm =
+            MultivariateGeometricBrownianMotion::new(initial_values, drifts, cholesky_factor, 0.1);
+
+        let mc_simulator: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, Array2<f64>> =
+            MonteCarloPathSimulator::new(mv_gbm, Some(42));
+        let state = MultivariateGbmState::new(arr1(&[90.0, 100.0]), None);
+        let paths = mc_simulator
+            .simulate_paths_from(0.5, &state, 3, 1.0)
+            .unwrap();
+
+        assert_eq!(paths.len(), 3);
+        assert_eq!(&paths[0].shape(), &[2, 6]);
+        assert_eq!(paths[0].column(0), arr1(&[90.0, 100.0]));
+    }
+
     #[test]
     fn basket_stock_price_simulation() {
         let nr_paths = 5_000;
@@ -0,0 +1,306 @@
+use ndarray::prelude::*;
+use rand::Rng;
+use rand_distr::{Distribution, Poisson, StandardNormal};
+
+use crate::simulation::monte_carlo::{PathGenerator, WarmStart};
+use crate::simulation::path_layout::AssetMajorPath;
+use crate::simulation::rng_streams::{MultiStreamPathGenerator, RiskFactor, RngStreams};
+use crate::simulation::sde::multivariate_gbm::MultivariateGeometricBrownianMotion;
+
+/// Jumps $S \to S e^J$ with normally distributed log jump sizes $J \sim N(mean, std^2)$.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LogNormalJumps {
+    pub mean: f64,
+    pub std: f64,
+}
+
+impl LogNormalJumps {
+    pub fn new(mean: f64, std: f64) -> Self {
+        assert!(std >= 0.0);
+        Self { mean, std }
+    }
+
+    /// The expected relative jump $\kappa = E[e^J - 1]$, which compensates the drift.
+    pub fn expected_jump(&self) -> f64 {
+        (self.mean + 0.5 * self.std * self.std).exp() - 1.0
+    }
+
+    fn sample_log_jumps<R: Rng + ?Sized>(&self, rn_generator: &mut R, nr_jumps: u64) -> f64 {
+        (0..nr_jumps)
+            .map(|_| {
+                let z: f64 = rn_generator.sample(StandardNormal);
+                self.mean + self.std * z
+            })
+            .sum()
+    }
+}
+
+/// Jump arrivals with the intensity (per year) and the jump sizes per asset.
+#[derive(Clone, Debug, PartialEq)]
+struct JumpComponent {
+    intensity: f64,
+    jumps: Vec<LogNormalJumps>,
+}
+
+impl JumpComponent {
+    fn nr_jumps<R: Rng + ?Sized>(&self, rn_generator: &mut R, dt: f64) -> u64 {
+        if self.intensity == 0.0 {
+            return 0;
+        }
+        let poisson = Poisson::new(self.intensity * dt).unwrap();
+        poisson.sample(rn_generator) as u64
+    }
+}
+
+/// Multivariate jump-diffusion: the correlated GBM diffusion times compound Poisson jumps from a
+/// systemic component, whose arrivals hit all assets simultaneously, and idiosyncratic components per asset.
+/// The common arrivals cluster the jumps across the assets (Marshall-Olkin), which the Gaussian
+/// dependence of the diffusion cannot produce. The drifts are compensated, such that the expected
+/// asset values are the ones of the diffusion.
+/// See Marshall and Olkin, A multivariate exponential distribution (1967)
+#[derive(Clone, Debug)]
+pub struct MultivariateJumpDiffusion {
+    diffusion: MultivariateGeometricBrownianMotion,
+    systemic: Option<JumpComponent>,
+    idiosyncratic: Vec<Option<JumpComponent>>,
+}
+
+impl MultivariateJumpDiffusion {
+    pub fn new(diffusion: MultivariateGeometricBrownianMotion) -> Self {
+        let dim = diffusion.dim();
+        Self {
+            diffusion,
+            systemic: None,
+            idiosyncratic: vec![None; dim],
+        }
+    }
+
+    /// Systemic jumps arriving with the intensity at all assets, with the jump sizes per asset.
+    pub fn with_systemic_jumps(self, intensity: f64, jumps: Vec<LogNormalJumps>) -> Self {
+        assert!(intensity >= 0.0);
+        assert_eq!(jumps.len(), self.diffusion.dim());
+        Self {
+            systemic: Some(JumpComponent { intensity, jumps }),
+            ..self
+        }
+    }
+
+    /// Idiosyncratic jumps of each asset, arriving independently with the intensities.
+    pub fn with_idiosyncratic_jumps(self, intensities: &[f64], jumps: Vec<LogNormalJumps>) -> Self {
+        assert_eq!(intensities.len(), self.diffusion.dim());
+        assert_eq!(jumps.len(), self.diffusion.dim());
+        assert!(intensities.iter().all(|intensity| *intensity >= 0.0));
+        let idiosyncratic = intensities
+            .iter()
+            .zip(jumps)
+            .map(|(intensity, jump)| {
+                Some(JumpComponent {
+                    intensity: *intensity,
+                    jumps: vec![jump],
+                })
+            })
+            .collect();
+        Self {
+            idiosyncratic,
+            ..self
+        }
+    }
+
+    /// The intensity of all jumps of the asset, systemic and idiosyncratic.
+    pub fn jump_intensity(&self, asset_idx: usize) -> f64 {
+        self.systemic.as_ref().map_or(0.0, |c| c.intensity)
+            + self.idiosyncratic[asset_idx]
+                .as_ref()
+                .map_or(0.0, |c| c.intensity)
+    }
+
+    /// The correlation $\lambda_s / \sqrt{\lambda_i \lambda_j}$ of the numbers of jumps of two assets
+    /// over any period, for the systemic intensity $\lambda_s$ and the total intensities $\lambda_i, \lambda_j$.
+    pub fn jump_count_correlation(&self, asset_i: usize, asset_j: usize) -> f64 {
+        if asset_i == asset_j {
+            return 1.0;
+        }
+        let systemic = self.systemic.as_ref().map_or(0.0, |c| c.intensity);
+        let total = self.jump_intensity(asset_i) * self.jump_intensity(asset_j);
+        if total == 0.0 {
+            return 0.0;
+        }
+        systemic / total.sqrt()
+    }
+
+    /// The drift compensation $\sum_c \lambda_c \kappa_{c,i}$ of the jumps per asset.
+    fn compensators(&self) -> Array1<f64> {
+        Array1::from_shape_fn(self.diffusion.dim(), |i| {
+            let systemic = self
+                .systemic
+                .as_ref()
+                .map_or(0.0, |c| c.intensity * c.jumps[i].expected_jump());
+            let idiosyncratic = self.idiosyncratic[i]
+                .as_ref()
+                .map_or(0.0, |c| c.intensity * c.jumps[0].expected_jump());
+            systemic + idiosyncratic
+        })
+    }
+
+    /// The cumulative compensated log jump factors per asset (rows) after each step (columns).
+    fn log_jump_factors<R>(&self, rn_generator: &mut R, nr_steps: usize) -> Array2<f64>
+    where
+        R: Rng + ?Sized,
+    {
+        let dt = self.diffusion.dt();
+        let compensators = self.compensators();
+        let mut log_factors = Array2::zeros((self.diffusion.dim(), nr_steps));
+        let mut cumulative: Array1<f64> = Array1::zeros(self.diffusion.dim());
+        for step in 0..nr_steps {
+            cumulative -= &(dt * &compensators);
+            if let Some(systemic) = &self.systemic {
+                let nr_jumps = systemic.nr_jumps(rn_generator, dt);
+                for (i, jumps) in systemic.jumps.iter().enumerate() {
+                    cumulative[i] += jumps.sample_log_jumps(rn_generator, nr_jumps);
+                }
+            }
+            for (i, component) in self.idiosyncratic.iter().enumerate() {
+                if let Some(component) = component {
+                    let nr_jumps = component.nr_jumps(rn_generator, dt);
+                    cumulative[i] += component.jumps[0].sample_log_jumps(rn_generator, nr_jumps);
+                }
+            }
+            log_factors.column_mut(step).assign(&cumulative);
+        }
+        log_factors
+    }
+}
+
+impl MultivariateJumpDiffusion {
+    /// The diffusion path times the jump factors.
+    fn apply_jumps(diffusion_path: AssetMajorPath, log_factors: &Array2<f64>) -> AssetMajorPath {
+        let nr_samples = log_factors.ncols();
+        let mut values = diffusion_path.values().to_owned();
+        // the columns after the initial values, if included
+        let offset = values.ncols() - nr_samples;
+        values
+            .slice_mut(s![.., offset..])
+            .zip_mut_with(log_factors, |s, log_factor| *s *= log_factor.exp());
+        AssetMajorPath::new(values)
+    }
+}
+
+impl PathGenerator<AssetMajorPath> for MultivariateJumpDiffusion {
+    /// The diffusion path times the jump factors, where the jumps are sampled after the diffusion.
+    fn sample_path<SeedRng>(&self, rn_generator: &mut SeedRng, nr_samples: usize) -> AssetMajorPath
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        let diffusion_path: AssetMajorPath = self.diffusion.sample_path(rn_generator, nr_samples);
+        let log_factors = self.log_jump_factors(rn_generator, nr_samples);
+        Self::apply_jumps(diffusion_path, &log_factors)
+    }
+}
+
+impl MultiStreamPathGenerator<AssetMajorPath> for MultivariateJumpDiffusion {
+    /// The diffusion from the equity stream and the jumps from the jumps stream, such that the diffusion
+    /// does not depend on the (random) number of draws of the jumps.
+    fn sample_path_from_streams<SeedRng>(
+        &self,
+        streams: &mut RngStreams<SeedRng>,
+        nr_samples: usize,
+    ) -> AssetMajorPath
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        let diffusion_path: AssetMajorPath = self
+            .diffusion
+            .sample_path(streams.stream(RiskFactor::Equity), nr_samples);
+        let log_factors = self.log_jump_factors(streams.stream(RiskFactor::Jumps), nr_samples);
+        Self::apply_jumps(diffusion_path, &log_factors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::monte_carlo::MonteCarloPathSimulator;
+    use ndarray::{arr1, arr2};
+
+    fn jump_diffusion(systemic: f64, idiosyncratic: f64) -> MultivariateJumpDiffusion {
+        let diffusion = MultivariateGeometricBrownianMotion::new(
+            arr1(&[100.0, 100.0]),
+            arr1(&[0.02, 0.02]),
+            arr2(&[[0.15, 0.0], [0.0, 0.15]]),
+            0.05,
+        );
+        let crash = LogNormalJumps::new(-0.2, 0.05);
+        MultivariateJumpDiffusion::new(diffusion)
+            .with_systemic_jumps(systemic, vec![crash; 2])
+            .with_idiosyncratic_jumps(&[idiosyncratic; 2], vec![crash; 2])
+    }
+
+    fn simulate(model: MultivariateJumpDiffusion) -> Vec<AssetMajorPath> {
+        let simulator: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, AssetMajorPath> =
+            MonteCarloPathSimulator::new(model, Some(42));
+        simulator.simulate_paths(10_000, 20)
+    }
+
+    #[test]
+    fn compensated_drift() {
+        let paths = simulate(jump_diffusion(0.5, 0.5));
+        for asset_idx in 0..2 {
+            let mean = paths
+                .iter()
+                .map(|path| path.terminal()[asset_idx])
+                .sum::<f64>()
+                / paths.len() as f64;
+            assert!(
+                (mean / (100.0 * 0.02_f64.exp()) - 1.0).abs() < 0.01,
+                "{mean}"
+            );
+        }
+    }
+
+    #[test]
+    fn systemic_jumps_cluster_crashes() {
+        let systemic = jump_diffusion(1.0, 0.0);
+        let idiosyncratic = jump_diffusion(0.0, 1.0);
+        assert_eq!(systemic.jump_count_correlation(0, 1), 1.0);
+        assert_eq!(idiosyncratic.jump_count_correlation(0, 1), 0.0);
+        assert_eq!(systemic.jump_intensity(1), idiosyncratic.jump_intensity(1));
+
+        // the same marginal laws, but joint crashes of both assets are frequent with systemic jumps only
+        let joint_crashes = |paths: &[AssetMajorPath]| {
+            paths
+                .iter()
+                .filter(|path| path.terminal().iter().all(|s| *s < 85.0))
+                .count() as f64
+                / paths.len() as f64
+        };
+        let systemic_crashes = joint_crashes(&simulate(systemic));
+        let idiosyncratic_crashes = joint_crashes(&simulate(idiosyncratic));
+        assert!(
+            systemic_crashes > 1.5 * idiosyncratic_crashes,
+            "{systemic_crashes} {idiosyncratic_crashes}"
+        );
+    }
+
+    #[test]
+    fn diffusion_stream_independent_of_jumps() {
+        let diffusion = jump_diffusion(0.0, 0.0).diffusion;
+        // jumps of size zero draw random numbers but do not move the assets
+        let zero_jumps = MultivariateJumpDiffusion::new(diffusion.clone())
+            .with_systemic_jumps(2.0, vec![LogNormalJumps::new(0.0, 0.0); 2]);
+        let no_jumps = MultivariateJumpDiffusion::new(diffusion);
+
+        let mut streams: RngStreams<rand_hc::Hc128Rng> = RngStreams::new(42);
+        let with_jump_draws: Vec<AssetMajorPath> = streams.simulate_paths(&zero_jumps, 50, 20);
+        let mut streams: RngStreams<rand_hc::Hc128Rng> = RngStreams::new(42);
+        let without_jump_draws: Vec<AssetMajorPath> = streams.simulate_paths(&no_jumps, 50, 20);
+        assert_eq!(with_jump_draws, without_jump_draws);
+
+        // whereas with a single generator the jump draws shift the diffusion of the subsequent paths
+        let single_stream = |model: MultivariateJumpDiffusion| {
+            let simulator: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, AssetMajorPath> =
+                MonteCarloPathSimulator::new(model, Some(42));
+            simulator.simulate_paths(50, 20)
+        };
+        assert_ne!(single_stream(zero_jumps), single_stream(no_jumps));
+    }
+}
@@ -0,0 +1,213 @@
+use rand::Rng;
+use rand_distr::StandardNormal;
+
+use crate::common::results::PricingError;
+use crate::simulation::monte_carlo::PathGenerator;
+
+/// The spots and the instantaneous variances of a rough Bergomi path, both starting at time 0.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoughBergomiPath {
+    pub spots: Vec<f64>,
+    pub variances: Vec<f64>,
+}
+
+/// The rough Bergomi model (Bayer, Friz and Gatheral) of the spot and the instantaneous variance
+/// '''math
+/// dS_t / S_t = r dt + \sqrt{v_t} dB_t, \quad v_t = \xi_0 \exp(\eta \tilde W_t - \eta^2 t^{2H} / 2)
+/// ''', driven by the Riemann-Liouville fractional Brownian motion $\tilde W_t = \sqrt{2H} \int_0^t (t - s)^{H - 1/2} dW_s$
+/// of the Hurst parameter $H < 1/2$ with $d\langle B, W \rangle_t = \rho dt$, for a flat forward variance $\xi_0$.
+/// The Volterra process is simulated by the hybrid scheme of Bennedsen, Lunde and Pakkanen, which integrates
+/// the singular kernel exactly over the latest step and approximates it by step functions at the optimal
+/// points before. As the process is not Markovian, each value depends on all past increments, such that
+/// a path costs $O(n^2)$ for n steps.
+/// See https://en.wikipedia.org/wiki/Fractional_Brownian_motion
+#[derive(Clone, Debug)]
+pub struct RoughBergomi {
+    initial_value: f64,
+    rate: f64,
+    forward_variance: f64,
+    hurst: f64,
+    /// the volatility η of the variance
+    eta: f64,
+    /// the correlation ρ of the spot and the variance
+    rho: f64,
+    dt: f64,
+}
+
+impl RoughBergomi {
+    pub fn new(
+        initial_value: f64,
+        rate: f64,
+        forward_variance: f64,
+        hurst: f64,
+        eta: f64,
+        rho: f64,
+        dt: f64,
+    ) -> Result<Self, PricingError> {
+        PricingError::check(initial_value > 0.0, "initial value", initial_value)?;
+        PricingError::check(forward_variance > 0.0, "forward variance", forward_variance)?;
+        PricingError::check(0.0 < hurst && hurst < 0.5, "hurst", hurst)?;
+        PricingError::check(eta >= 0.0, "eta", eta)?;
+        PricingError::check(rho.abs() <= 1.0, "rho", rho)?;
+        PricingError::check(dt > 0.0, "dt", dt)?;
+        Ok(Self {
+            initial_value,
+            rate,
+            forward_variance,
+            hurst,
+            eta,
+            rho,
+            dt,
+        })
+    }
+
+    pub fn hurst(&self) -> f64 {
+        self.hurst
+    }
+
+    /// The weights $(b_k dt)^{H - 1/2}$ of the Brownian increments k steps in the past, for k >= 2,
+    /// at the optimal evaluation points $b_k = ((k^{\alpha + 1} - (k - 1)^{\alpha + 1}) / (\alpha + 1))^{1 / \alpha}$
+    /// of the kernel with $\alpha = H - 1/2$.
+    fn kernel_weights(&self, nr_steps: usize) -> Vec<f64> {
+        let alpha = self.hurst - 0.5;
+        (0..=nr_steps)
+            .map(|k| {
+                if k < 2 {
+                    return 0.0;
+                }
+                let k = k as f64;
+                let b = ((k.powf(alpha + 1.0) - (k - 1.0).powf(alpha + 1.0)) / (alpha + 1.0))
+                    .powf(1.0 / alpha);
+                (b * self.dt).powf(alpha)
+            })
+            .collect()
+    }
+
+    /// The path over the steps with the three standard normals of each step, the first two for the
+    /// increment of W and the kernel integral over the step, the third for the independent part of B.
+    pub fn generate_path(&self, standard_normals: &[[f64; 3]]) -> RoughBergomiPath {
+        let nr_steps = standard_normals.len();
+        let alpha = self.hurst - 0.5;
+        let dt = self.dt;
+
+        // the increments $\Delta W_j$ and the exact integrals $\int_{t_j}^{t_{j+1}} (t_{j+1} - s)^\alpha dW_s$,
+        // jointly normal by the Cholesky factor of their covariance
+        let covariance = dt.powf(alpha + 1.0) / (alpha + 1.0);
+        let variance = dt.powf(2.0 * alpha + 1.0) / (2.0 * alpha + 1.0);
+        let l21 = covariance / dt.sqrt();
+        let l22 = (variance - l21 * l21).max(0.0).sqrt();
+        let increments: Vec<f64> = standard_normals.iter().map(|z| dt.sqrt() * z[0]).collect();
+        let latest_integrals: Vec<f64> = standard_normals
+            .iter()
+            .map(|z| l21 * z[0] + l22 * z[1])
+            .collect();
+
+        let weights = self.kernel_weights(nr_steps);
+        let scaling = (2.0 * self.hurst).sqrt();
+        let mut variances = Vec::with_capacity(nr_steps + 1);
+        variances.push(self.forward_variance);
+        for j in 1..=nr_steps {
+            let past: f64 = (2..=j).map(|k| weights[k] * increments[j - k]).sum::<f64>();
+            let volterra = scaling * (latest_integrals[j - 1] + past);
+            let t = j as f64 * dt;
+            variances.push(
+                self.forward_variance
+                    * (self.eta * volterra - 0.5 * self.eta.powi(2) * t.powf(2.0 * self.hurst))
+                        .exp(),
+            );
+        }
+
+        let mut spots = Vec::with_capacity(nr_steps + 1);
+        let mut log_spot = self.initial_value.ln();
+        spots.push(self.initial_value);
+        let orthogonal = (1.0 - self.rho * self.rho).sqrt();
+        for ((z, increment), v) in standard_normals.iter().zip(&increments).zip(&variances) {
+            let spot_increment = self.rho * increment + orthogonal * dt.sqrt() * z[2];
+            log_spot += (self.rate - 0.5 * v) * dt + v.sqrt() * spot_increment;
+            spots.push(log_spot.exp());
+        }
+        RoughBergomiPath { spots, variances }
+    }
+
+    /// Samples the spots and the variances over the steps.
+    pub fn sample_path_with_variances<R: Rng>(
+        &self,
+        rn_generator: &mut R,
+        nr_steps: usize,
+    ) -> RoughBergomiPath {
+        let standard_normals: Vec<[f64; 3]> = (0..nr_steps)
+            .map(|_| {
+                [
+                    rn_generator.sample(StandardNormal),
+                    rn_generator.sample(StandardNormal),
+                    rn_generator.sample(StandardNormal),
+                ]
+            })
+            .collect();
+        self.generate_path(&standard_normals)
+    }
+}
+
+impl PathGenerator<Vec<f64>> for RoughBergomi {
+    fn sample_path<SeedRng>(&self, rn_generator: &mut SeedRng, nr_samples: usize) -> Vec<f64>
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        self.sample_path_with_variances(rn_generator, nr_samples)
+            .spots
+    }
+}
+
+impl PathGenerator<RoughBergomiPath> for RoughBergomi {
+    fn sample_path<SeedRng>(
+        &self,
+        rn_generator: &mut SeedRng,
+        nr_samples: usize,
+    ) -> RoughBergomiPath
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        self.sample_path_with_variances(rn_generator, nr_samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::monte_carlo::MonteCarloPathSimulator;
+
+    #[test]
+    fn rough_bergomi_moments() {
+        let (hurst, eta, xi, t, nr_steps) = (0.1, 1.9, 0.04, 1.0, 50);
+        let model =
+            RoughBergomi::new(100.0, 0.0, xi, hurst, eta, -0.9, t / nr_steps as f64).unwrap();
+        let simulator: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, RoughBergomiPath> =
+            MonteCarloPathSimulator::new(model, Some(5));
+        let paths = simulator.simulate_paths(10_000, nr_steps);
+        let n = paths.len() as f64;
+
+        // the spot is a martingale and the variance has the forward variance as mean
+        let mean_spot = paths.iter().map(|p| p.spots[nr_steps]).sum::<f64>() / n;
+        assert!((mean_spot - 100.0).abs() < 0.6, "{mean_spot}");
+        let mean_variance = paths.iter().map(|p| p.variances[nr_steps / 2]).sum::<f64>() / n;
+        assert!((mean_variance - xi).abs() < 0.006, "{mean_variance}");
+
+        // the Volterra process has the variance $t^{2H}$
+        let volterra: Vec<f64> = paths
+            .iter()
+            .map(|p| {
+                ((p.variances[nr_steps] / xi).ln() + 0.5 * eta * eta * t.powf(2.0 * hurst)) / eta
+            })
+            .collect();
+        let variance = volterra.iter().map(|y| y * y).sum::<f64>() / n;
+        assert!((variance - t.powf(2.0 * hurst)).abs() < 0.05, "{variance}");
+
+        // the negative correlation skews the returns to the left
+        let mean_cubed = paths
+            .iter()
+            .map(|p| (p.spots[nr_steps] / 100.0).ln().powi(3))
+            .sum::<f64>();
+        assert!(mean_cubed < 0.0);
+        assert!(RoughBergomi::new(100.0, 0.0, xi, 0.5, eta, -0.9, 0.01).is_err());
+    }
+}
@@ -0,0 +1,100 @@
+use rand::Rng;
+use rand_distr::StandardNormal;
+
+use crate::common::results::PricingError;
+use crate::simulation::monte_carlo::PathGenerator;
+
+/// Driftless shifted lognormal (displaced diffusion) forward rate
+/// '''math
+/// d(F_t + s) = \sigma (F_t + s) dW_t
+/// ''', i.e. a martingale under its forward measure, which stays above $-s$ and thus allows negative rates.
+/// The steps are exact, such that the terminal law is the one of the shifted Black76 model.
+#[derive(Clone, Debug)]
+pub struct ShiftedLognormalForward {
+    initial_value: f64,
+    shift: f64,
+    vola: f64,
+    dt: f64,
+}
+
+impl ShiftedLognormalForward {
+    /// The forward process, or an error if the shifted initial forward is not positive.
+    pub fn new(initial_value: f64, shift: f64, vola: f64, dt: f64) -> Result<Self, PricingError> {
+        PricingError::check(
+            initial_value + shift > 0.0,
+            "shifted forward",
+            initial_value + shift,
+        )?;
+        PricingError::check(vola >= 0.0, "vola", vola)?;
+        PricingError::check(dt > 0.0, "dt", dt)?;
+        Ok(Self {
+            initial_value,
+            shift,
+            vola,
+            dt,
+        })
+    }
+
+    pub fn shift(&self) -> f64 {
+        self.shift
+    }
+
+    /// The exact step of the forward with the standard normal z.
+    pub fn step(&self, ft: f64, z: f64) -> f64 {
+        let sigma_sqrt_dt = self.vola * self.dt.sqrt();
+        (ft + self.shift) * (sigma_sqrt_dt * z - 0.5 * sigma_sqrt_dt * sigma_sqrt_dt).exp()
+            - self.shift
+    }
+}
+
+impl PathGenerator<Vec<f64>> for ShiftedLognormalForward {
+    fn sample_path<SeedRng>(&self, rn_generator: &mut SeedRng, nr_samples: usize) -> Vec<f64>
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        let mut path = Vec::with_capacity(nr_samples + 1);
+        let mut ft = self.initial_value;
+        path.push(ft);
+        for z in rn_generator.sample_iter(StandardNormal).take(nr_samples) {
+            ft = self.step(ft, z);
+            path.push(ft);
+        }
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytic::black_scholes::ShiftedBlack76;
+    use crate::common::models::DerivativeParameter;
+    use crate::simulation::monte_carlo::MonteCarloPathSimulator;
+    use crate::simulation::PathEvaluator;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn negative_forward_rate() {
+        let (forward, strike, shift, vola, t) = (-0.004, -0.001, 0.02, 0.2, 2.0);
+        let process = ShiftedLognormalForward::new(forward, shift, vola, t / 10.0).unwrap();
+        let simulator: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, Vec<f64>> =
+            MonteCarloPathSimulator::new(process, Some(3));
+        let paths = simulator.simulate_paths(50_000, 10);
+        assert!(paths.iter().flatten().all(|f| f.is_finite() && *f > -shift));
+
+        let evaluator = PathEvaluator::new(&paths);
+        let mean = evaluator
+            .evaluate_average(|path| path.last().copied())
+            .unwrap();
+        assert_approx_eq!(mean, forward, 2e-5);
+        let call = evaluator
+            .evaluate_average(|path| path.last().map(|f| (f - strike).max(0.0)))
+            .unwrap();
+        let dp = DerivativeParameter::new(forward, strike, t, 0.0, vola);
+        assert_approx_eq!(call, ShiftedBlack76::new(shift).call(&dp).unwrap(), 2e-5);
+
+        assert!(matches!(
+            ShiftedLognormalForward::new(-0.03, shift, vola, 0.1),
+            Err(PricingError::InvalidParameter(_))
+        ));
+    }
+}
@@ -0,0 +1,207 @@
+use ndarray::{s, Array2};
+use rand::Rng;
+use rand_distr::StandardNormal;
+
+use crate::simulation::monte_carlo::PathGenerator;
+
+/// Mean-reverting (Ornstein-Uhlenbeck) dividend yield or repo spread, or (Vasicek) short rate or
+/// inflation rate, which is a Brownian motion without mean reversion
+/// '''math
+/// dq_t = kappa (theta - q_t) dt + eta dW_t
+/// '''
+/// See https://en.wikipedia.org/wiki/Ornstein%E2%80%93Uhlenbeck_process
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MeanRevertingSpread {
+    pub initial_value: f64,
+    /// speed of mean reversion kappa
+    pub mean_reversion: f64,
+    /// long term mean theta
+    pub long_term_mean: f64,
+    /// volatility eta
+    pub vola: f64,
+}
+
+impl MeanRevertingSpread {
+    pub fn new(initial_value: f64, mean_reversion: f64, long_term_mean: f64, vola: f64) -> Self {
+        assert!(mean_reversion >= 0.0);
+        Self {
+            initial_value,
+            mean_reversion,
+            long_term_mean,
+            vola,
+        }
+    }
+
+    /// $B(t) = (1 - e^{-kappa t}) / kappa$, or t without mean reversion
+    fn b(&self, t: f64) -> f64 {
+        if self.mean_reversion == 0.0 {
+            return t;
+        }
+        (1.0 - (-self.mean_reversion * t).exp()) / self.mean_reversion
+    }
+
+    /// The exact step over dt with the standard normal z.
+    pub fn step(&self, qt: f64, dt: f64, z: f64) -> f64 {
+        let kappa = self.mean_reversion;
+        if kappa == 0.0 {
+            return qt + self.vola * dt.sqrt() * z;
+        }
+        let decay = (-kappa * dt).exp();
+        let std_dev = self.vola * ((1.0 - decay * decay) / (2.0 * kappa)).sqrt();
+        self.long_term_mean + (qt - self.long_term_mean) * decay + std_dev * z
+    }
+
+    /// The expectation at the time t.
+    pub fn mean(&self, t: f64) -> f64 {
+        self.long_term_mean
+            + (self.initial_value - self.long_term_mean) * (-self.mean_reversion * t).exp()
+    }
+
+    /// The mean of the integral $\int_0^t q_s ds$.
+    pub fn integral_mean(&self, t: f64) -> f64 {
+        self.long_term_mean * t + (self.initial_value - self.long_term_mean) * self.b(t)
+    }
+
+    /// The variance of the integral $\int_0^t q_s ds$.
+    pub fn integral_variance(&self, t: f64) -> f64 {
+        let kappa = self.mean_reversion;
+        if kappa == 0.0 {
+            return self.vola.powi(2) * t.powi(3) / 3.0;
+        }
+        let b = self.b(t);
+        self.vola.powi(2) / kappa.powi(2) * (t - b - kappa * b * b / 2.0)
+    }
+}
+
+/// Equity with a stochastic dividend yield (or repo spread) correlated to the equity's returns
+/// '''math
+/// dS_t / S_t = (r - q_t) dt + sigma dW^S_t, \quad d<W^S, W^q>_t = rho dt
+/// '''
+/// The paths have the spots in the first and the dividend yields in the second row.
+#[derive(Clone, Debug)]
+pub struct StochasticDividendEquity {
+    initial_value: f64,
+    rfr: f64,
+    vola: f64,
+    dividend: MeanRevertingSpread,
+    correlation: f64,
+    dt: f64,
+    /// whether sampled paths start with the initial values, see `PathGenerator`
+    include_initial_value: bool,
+}
+
+impl StochasticDividendEquity {
+    pub fn new(
+        initial_value: f64,
+        rfr: f64,
+        vola: f64,
+        dividend: MeanRevertingSpread,
+        correlation: f64,
+        dt: f64,
+    ) -> Self {
+        assert!((-1.0..=1.0).contains(&correlation));
+        Self {
+            initial_value,
+            rfr,
+            vola,
+            dividend,
+            correlation,
+            dt,
+            include_initial_value: true,
+        }
+    }
+
+    /// Whether the generated paths start with the initial values (default) or with the values after the first step.
+    pub fn with_initial_value_in_path(self, include_initial_value: bool) -> Self {
+        Self {
+            include_initial_value,
+            ..self
+        }
+    }
+
+    /// The step of spot and dividend yield from the independent standard normals (z1, z2), where the
+    /// spot's drift uses the dividend yield at the beginning of the step.
+    pub fn step(&self, st: f64, qt: f64, z1: f64, z2: f64) -> (f64, f64) {
+        let zq = self.correlation * z1 + (1.0 - self.correlation.powi(2)).sqrt() * z2;
+        let log_return =
+            (self.rfr - qt - self.vola.powi(2) / 2.0) * self.dt + self.vola * self.dt.sqrt() * z1;
+        (st * log_return.exp(), self.dividend.step(qt, self.dt, zq))
+    }
+
+    /// The forward $E[S_T]$, including the convexity of the stochastic dividends and their
+    /// covariance with the equity.
+    pub fn forward(&self, t: f64) -> f64 {
+        let q = &self.dividend;
+        let covariance = self.correlation * self.vola * q.vola / q.mean_reversion * (t - q.b(t));
+        self.initial_value
+            * (self.rfr * t - q.integral_mean(t) + q.integral_variance(t) / 2.0 - covariance).exp()
+    }
+}
+
+impl PathGenerator<Array2<f64>> for StochasticDividendEquity {
+    fn sample_path<SeedRng>(&self, rn_generator: &mut SeedRng, nr_samples: usize) -> Array2<f64>
+    where
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        let mut path = Array2::zeros((2, nr_samples + 1));
+        let (mut st, mut qt) = (self.initial_value, self.dividend.initial_value);
+        path[[0, 0]] = st;
+        path[[1, 0]] = qt;
+        for idx in 1..=nr_samples {
+            let (z1, z2) = (
+                rn_generator.sample(StandardNormal),
+                rn_generator.sample(StandardNormal),
+            );
+            (st, qt) = self.step(st, qt, z1, z2);
+            path[[0, idx]] = st;
+            path[[1, idx]] = qt;
+        }
+        if self.include_initial_value {
+            path
+        } else {
+            path.slice_move(s![.., 1..])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::monte_carlo::{MonteCarloPathSimulator, PathEvaluator};
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn forward_with_stochastic_dividends() {
+        let (t, nr_steps) = (10.0, 100);
+        let dividend = MeanRevertingSpread::new(0.02, 0.5, 0.03, 0.01);
+        let equity =
+            StochasticDividendEquity::new(100.0, 0.03, 0.2, dividend, -0.5, t / nr_steps as f64);
+
+        // the deterministic dividends' forward without convexity and covariance
+        let deterministic = 100.0 * (0.03 * t - dividend.integral_mean(t)).exp();
+        assert!(equity.forward(t) > deterministic);
+
+        let mc_simulator: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, _> =
+            MonteCarloPathSimulator::new(equity.clone(), Some(42));
+        let paths = mc_simulator.simulate_paths(20_000, nr_steps);
+        let evaluator = PathEvaluator::new(&paths);
+        let forward = evaluator.evaluate_average(|path: &Array2<f64>| Some(path[[0, nr_steps]]));
+        assert_approx_eq!(forward.unwrap(), equity.forward(t), 1.5);
+
+        let dividend_mean =
+            evaluator.evaluate_average(|path: &Array2<f64>| Some(path[[1, nr_steps]]));
+        assert_approx_eq!(dividend_mean.unwrap(), dividend.mean(t), 1e-3);
+
+        // without mean reversion, the limit of a vanishing mean reversion
+        let random_walk = MeanRevertingSpread::new(0.02, 0.0, 0.03, 0.01);
+        let slow = MeanRevertingSpread::new(0.02, 1e-4, 0.03, 0.01);
+        assert_eq!(random_walk.mean(t), 0.02);
+        assert_approx_eq!(random_walk.integral_mean(t), slow.integral_mean(t), 1e-4);
+        assert_approx_eq!(
+            random_walk.integral_variance(t),
+            slow.integral_variance(t),
+            1e-4
+        );
+        assert_eq!(random_walk.step(0.02, 0.25, 1.0), 0.02 + 0.01 * 0.5);
+    }
+}
@@ -0,0 +1,343 @@
+use ndarray::{Array1, Array2, Axis};
+use rand::Rng;
+use rand_distr::StandardNormal;
+
+use crate::common::results::PricingError;
+use crate::numerics::linalg::{cholesky, cholesky_solve};
+use crate::simulation::sde::stochastic_dividend::MeanRevertingSpread;
+
+/// The filtered states $E[x_t | y_1, ..., y_t]$ with their covariances, one per observation,
+/// and the log-likelihood of the observations under the model.
+#[derive(Clone, Debug)]
+pub struct FilteredStates {
+    pub states: Vec<Array1<f64>>,
+    pub covariances: Vec<Array2<f64>>,
+    pub log_likelihood: f64,
+}
+
+impl FilteredStates {
+    /// The state filtered from all observations, e.g. the initial value of a scenario generator.
+    pub fn latest_state(&self) -> Option<&Array1<f64>> {
+        self.states.last()
+    }
+}
+
+/// The simulated latent states and their observations, one per step.
+#[derive(Clone, Debug)]
+pub struct SimulatedStates {
+    pub states: Vec<Array1<f64>>,
+    pub observations: Vec<Array1<f64>>,
+}
+
+/// A linear-Gaussian state-space model of latent states $x_t = F x_{t-1} + c + w_t$ observed as
+/// $y_t = H x_t + d + v_t$ with independent noises $w_t \sim N(0, Q)$ and $v_t \sim N(0, R)$,
+/// e.g. a stochastic drift or term-structure factors observed with measurement noise.
+/// See https://en.wikipedia.org/wiki/Kalman_filter
+#[derive(Clone, Debug)]
+pub struct LinearGaussianModel {
+    transition: Array2<f64>,
+    transition_offset: Array1<f64>,
+    transition_noise: Array2<f64>,
+    observation: Array2<f64>,
+    observation_offset: Array1<f64>,
+    observation_noise: Array2<f64>,
+}
+
+impl LinearGaussianModel {
+    /// The model of the transition F with the noise covariance Q and of the observation H with the
+    /// noise covariance R, without offsets.
+    pub fn new(
+        transition: Array2<f64>,
+        transition_noise: Array2<f64>,
+        observation: Array2<f64>,
+        observation_noise: Array2<f64>,
+    ) -> Result<Self, PricingError> {
+        let (nr_states, nr_observed) = (transition.nrows(), observation.nrows());
+        PricingError::check(
+            transition.shape() == [nr_states, nr_states]
+                && transition_noise.shape() == [nr_states, nr_states],
+            "state dimension",
+            nr_states as f64,
+        )?;
+        PricingError::check(
+            observation.shape() == [nr_observed, nr_states]
+                && observation_noise.shape() == [nr_observed, nr_observed],
+            "observation dimension",
+            nr_observed as f64,
+        )?;
+        Ok(Self {
+            transition,
+            transition_offset: Array1::zeros(nr_states),
+            transition_noise,
+            observation,
+            observation_offset: Array1::zeros(nr_observed),
+            observation_noise,
+        })
+    }
+
+    /// The offsets c of the transition and d of the observation.
+    pub fn with_offsets(
+        self,
+        transition_offset: Array1<f64>,
+        observation_offset: Array1<f64>,
+    ) -> Result<Self, PricingError> {
+        self.check_state_dim(transition_offset.len(), "transition offset")?;
+        self.check_observation_dim(observation_offset.len(), "observation offset")?;
+        Ok(Self {
+            transition_offset,
+            observation_offset,
+            ..self
+        })
+    }
+
+    /// The exact discretization over dt of the mean-reverting process, observed with the noise
+    /// of the standard deviation, e.g. of a short rate observed through a noisy money-market rate.
+    pub fn mean_reverting(
+        process: &MeanRevertingSpread,
+        dt: f64,
+        observation_std_dev: f64,
+    ) -> Result<Self, PricingError> {
+        PricingError::check(
+            process.mean_reversion.is_finite() && process.mean_reversion >= 0.0,
+            "mean reversion",
+            process.mean_reversion,
+        )?;
+        PricingError::check(
+            process.long_term_mean.is_finite(),
+            "long-term mean",
+            process.long_term_mean,
+        )?;
+        PricingError::check(
+            process.vola.is_finite() && process.vola >= 0.0,
+            "volatility",
+            process.vola,
+        )?;
+        PricingError::check(dt.is_finite() && dt > 0.0, "time step", dt)?;
+        PricingError::check(
+            observation_std_dev.is_finite() && observation_std_dev >= 0.0,
+            "observation standard deviation",
+            observation_std_dev,
+        )?;
+        let decay = (-process.mean_reversion * dt).exp();
+        let variance = if process.mean_reversion == 0.0 {
+            process.vola * process.vola * dt
+        } else {
+            process.vola * process.vola * (1.0 - decay * decay) / (2.0 * process.mean_reversion)
+        };
+        Ok(Self {
+            transition: Array2::from_elem((1, 1), decay),
+            transition_offset: Array1::from_elem(1, process.long_term_mean * (1.0 - decay)),
+            transition_noise: Array2::from_elem((1, 1), variance),
+            observation: Array2::eye(1),
+            observation_offset: Array1::zeros(1),
+            observation_noise: Array2::from_elem((1, 1), observation_std_dev.powi(2)),
+        })
+    }
+
+    pub fn state_dim(&self) -> usize {
+        self.transition.nrows()
+    }
+
+    pub fn observation_dim(&self) -> usize {
+        self.observation.nrows()
+    }
+
+    fn check_state_dim(&self, dim: usize, name: &str) -> Result<(), PricingError> {
+        PricingError::check(dim == self.state_dim(), name, dim as f64)
+    }
+
+    fn check_observation_dim(&self, dim: usize, name: &str) -> Result<(), PricingError> {
+        PricingError::check(dim == self.observation_dim(), name, dim as f64)
+    }
+
+    /// Filters the states from the observations, starting from the prior of the initial state.
+    /// Observations with NaN entries, e.g. missing quotes, only propagate the states.
+    /// Fails if the dimensions of the prior or of an observation differ from the model, or if an
+    /// innovation covariance $H P H^T + R$ is not positive definite.
+    pub fn filter(
+        &self,
+        observations: &[Array1<f64>],
+        initial_state: Array1<f64>,
+        initial_covariance: Array2<f64>,
+    ) -> Result<FilteredStates, PricingError> {
+        self.check_state_dim(initial_state.len(), "initial state")?;
+        PricingError::check(
+            initial_covariance.shape() == [self.state_dim(), self.state_dim()],
+            "initial covariance",
+            initial_covariance.nrows() as f64,
+        )?;
+        let (f, h) = (&self.transition, &self.observation);
+        let (mut state, mut covariance) = (initial_state, initial_covariance);
+        let mut filtered = FilteredStates {
+            states: Vec::with_capacity(observations.len()),
+            covariances: Vec::with_capacity(observations.len()),
+            log_likelihood: 0.0,
+        };
+        for y in observations {
+            self.check_observation_dim(y.len(), "observation")?;
+            state = f.dot(&state) + &self.transition_offset;
+            covariance = f.dot(&covariance).dot(&f.t()) + &self.transition_noise;
+
+            if y.iter().all(|y| y.is_finite()) {
+                let innovation = y - &(h.dot(&state) + &self.observation_offset);
+                let innovation_covariance =
+                    h.dot(&covariance).dot(&h.t()) + &self.observation_noise;
+                let factor = cholesky(&innovation_covariance).ok_or_else(|| {
+                    PricingError::InvalidParameter("innovation covariance".to_string())
+                })?;
+                // the transposed gain $K^T = S^{-1} H P$ column by column
+                let hp = h.dot(&covariance);
+                let mut gain_t = Array2::zeros(hp.raw_dim());
+                for (column, mut gain_column) in
+                    hp.axis_iter(Axis(1)).zip(gain_t.axis_iter_mut(Axis(1)))
+                {
+                    gain_column.assign(&cholesky_solve(&factor, &column.to_owned()));
+                }
+                state = state + gain_t.t().dot(&innovation);
+                covariance = &covariance - &gain_t.t().dot(&hp);
+                covariance = 0.5 * (&covariance + &covariance.t());
+
+                let ln_det = 2.0 * factor.diag().mapv(f64::ln).sum();
+                let mahalanobis = innovation.dot(&cholesky_solve(&factor, &innovation));
+                filtered.log_likelihood -= 0.5
+                    * (y.len() as f64 * (2.0 * std::f64::consts::PI).ln() + ln_det + mahalanobis);
+            }
+            filtered.states.push(state.clone());
+            filtered.covariances.push(covariance.clone());
+        }
+        Ok(filtered)
+    }
+
+    /// Simulates the states and their observations over the steps from the initial state.
+    /// Fails if a noise covariance is not positive definite.
+    pub fn simulate<R: Rng>(
+        &self,
+        initial_state: Array1<f64>,
+        nr_steps: usize,
+        rn_generator: &mut R,
+    ) -> Result<SimulatedStates, PricingError> {
+        let noise_factor = |covariance: &Array2<f64>, name: &str| {
+            cholesky(covariance).ok_or_else(|| PricingError::InvalidParameter(name.to_string()))
+        };
+        let transition_factor = noise_factor(&self.transition_noise, "transition noise")?;
+        let observation_factor = noise_factor(&self.observation_noise, "observation noise")?;
+        let mut normals = |dim: usize| -> Array1<f64> {
+            (0..dim)
+                .map(|_| rn_generator.sample::<f64, _>(StandardNormal))
+                .collect()
+        };
+
+        let mut state = initial_state;
+        let (mut states, mut observations) = (Vec::new(), Vec::new());
+        for _ in 0..nr_steps {
+            state = self.transition.dot(&state)
+                + &self.transition_offset
+                + transition_factor.dot(&normals(self.state_dim()));
+            observations.push(
+                self.observation.dot(&state)
+                    + &self.observation_offset
+                    + observation_factor.dot(&normals(self.observation_dim())),
+            );
+            states.push(state.clone());
+        }
+        Ok(SimulatedStates {
+            states,
+            observations,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::{arr1, arr2};
+    use rand::SeedableRng;
+
+    #[test]
+    fn filter_noisy_short_rate() {
+        let process = MeanRevertingSpread::new(0.03, 0.5, 0.04, 0.01);
+        let model = LinearGaussianModel::mean_reverting(&process, 1.0 / 52.0, 0.005).unwrap();
+        let mut rng = rand_hc::Hc128Rng::seed_from_u64(3);
+        let SimulatedStates {
+            states,
+            mut observations,
+        } = model.simulate(arr1(&[0.03]), 520, &mut rng).unwrap();
+        observations[100] = arr1(&[f64::NAN]);
+
+        let filtered = model
+            .filter(&observations, arr1(&[0.03]), arr2(&[[1e-4]]))
+            .unwrap();
+        let rmse = |estimates: &[Array1<f64>]| {
+            let sum: f64 = (0..states.len())
+                .filter(|t| *t != 100)
+                .map(|t| (estimates[t][0] - states[t][0]).powi(2))
+                .sum();
+            (sum / (states.len() - 1) as f64).sqrt()
+        };
+        assert!(rmse(&filtered.states) < 0.6 * rmse(&observations));
+        assert!(filtered.states[100][0].is_finite());
+        // the filtered variance settles below the observation variance
+        assert!(filtered.covariances.last().unwrap()[[0, 0]] < 0.005 * 0.005);
+
+        // the likelihood prefers the true observation noise
+        let misspecified = LinearGaussianModel::mean_reverting(&process, 1.0 / 52.0, 0.02).unwrap();
+        let wrong = misspecified
+            .filter(&observations, arr1(&[0.03]), arr2(&[[1e-4]]))
+            .unwrap();
+        assert!(filtered.log_likelihood > wrong.log_likelihood);
+
+        // the filtered rate starts a scenario generator of the short rate
+        let current = MeanRevertingSpread {
+            initial_value: filtered.latest_state().unwrap()[0],
+            ..process
+        };
+        assert!((current.initial_value - states.last().unwrap()[0]).abs() < 0.01);
+    }
+
+    #[test]
+    fn two_factor_model() {
+        // a level and a slope factor observed through three noisy yields
+        let model = LinearGaussianModel::new(
+            arr2(&[[0.99, 0.0], [0.0, 0.95]]),
+            arr2(&[[1e-6, 0.0], [0.0, 4e-6]]),
+            arr2(&[[1.0, -0.5], [1.0, 0.0], [1.0, 0.5]]),
+            Array2::eye(3) * 1e-8,
+        )
+        .unwrap()
+        .with_offsets(arr1(&[0.0003, 0.0]), arr1(&[0.0, 0.0, 0.0]))
+        .unwrap();
+        let mut rng = rand_hc::Hc128Rng::seed_from_u64(8);
+        let SimulatedStates {
+            states,
+            observations,
+        } = model.simulate(arr1(&[0.03, 0.01]), 200, &mut rng).unwrap();
+        let filtered = model
+            .filter(&observations, arr1(&[0.0, 0.0]), Array2::eye(2))
+            .unwrap();
+        // precise observations pin down both factors
+        let (state, truth) = (filtered.latest_state().unwrap(), states.last().unwrap());
+        assert!((state - truth).iter().all(|e| e.abs() < 5e-4));
+        assert!(LinearGaussianModel::new(
+            Array2::eye(2),
+            Array2::eye(2),
+            Array2::eye(3),
+            Array2::eye(3)
+        )
+        .is_err());
+
+        // inputs of other dimensions are rejected
+        assert!(model
+            .clone()
+            .with_offsets(arr1(&[0.0]), arr1(&[0.0, 0.0, 0.0]))
+            .is_err());
+        assert!(model
+            .filter(&observations, arr1(&[0.0, 0.0]), Array2::eye(3))
+            .is_err());
+        assert!(model
+            .filter(&[arr1(&[0.03, 0.03])], arr1(&[0.0, 0.0]), Array2::eye(2))
+            .is_err());
+        let process = MeanRevertingSpread::new(0.03, 0.5, 0.04, 0.01);
+        assert!(LinearGaussianModel::mean_reverting(&process, 0.0, 0.005).is_err());
+        assert!(LinearGaussianModel::mean_reverting(&process, 0.1, f64::NAN).is_err());
+    }
+}
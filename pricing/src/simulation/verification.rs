@@ -0,0 +1,263 @@
+use crate::common::results::PriceResult;
+use crate::numerics::normal::cdf;
+use crate::simulation::monte_carlo::{MonteCarloPathSimulator, PathGenerator};
+
+/// The first four moments of a distribution.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Moments {
+    pub mean: f64,
+    pub variance: f64,
+    pub skewness: f64,
+    pub excess_kurtosis: f64,
+}
+
+impl Moments {
+    /// Sample moments (biased estimators) of the given samples.
+    pub fn from_samples(samples: &[f64]) -> Self {
+        let n = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / n;
+        let central_moment = |k: i32| samples.iter().map(|x| (x - mean).powi(k)).sum::<f64>() / n;
+
+        let variance = central_moment(2);
+        Self {
+            mean,
+            variance,
+            skewness: central_moment(3) / variance.powf(1.5),
+            excess_kurtosis: central_moment(4) / variance.powi(2) - 3.0,
+        }
+    }
+
+    /// Moments of the normal distribution.
+    pub fn normal(mean: f64, variance: f64) -> Self {
+        Self {
+            mean,
+            variance,
+            skewness: 0.0,
+            excess_kurtosis: 0.0,
+        }
+    }
+
+    /// Moments of the log return $ln(S_T / S_0)$ of a GBM with the given drift and volatility.
+    pub fn gbm_log_return(drift: f64, vola: f64, t: f64) -> Self {
+        Self::normal((drift - vola.powi(2) / 2.0) * t, vola.powi(2) * t)
+    }
+}
+
+/// z-scores of the sample moments against the theoretical moments,
+/// using the asymptotic standard errors under normality for skewness and kurtosis.
+#[derive(Clone, Copy, Debug)]
+pub struct MomentZScores {
+    pub mean: f64,
+    pub variance: f64,
+    pub skewness: f64,
+    pub excess_kurtosis: f64,
+}
+
+impl MomentZScores {
+    /// Whether all absolute z-scores are below the threshold, e.g. 3.0.
+    pub fn passes(&self, threshold: f64) -> bool {
+        [
+            self.mean,
+            self.variance,
+            self.skewness,
+            self.excess_kurtosis,
+        ]
+        .iter()
+        .all(|z| z.abs() < threshold)
+    }
+}
+
+/// Statistical tests of samples (e.g. terminal values of generated paths) against a theoretical distribution.
+pub struct DistributionVerifier {
+    samples: Vec<f64>,
+}
+
+impl DistributionVerifier {
+    pub fn new(samples: Vec<f64>) -> Self {
+        assert!(samples.len() > 1);
+        Self { samples }
+    }
+
+    /// Samples `nr_paths` paths of the simulator and maps them by `path_fn`, e.g. to the terminal log return.
+    pub fn from_simulation<PathGen, SeedRng, Path>(
+        simulator: &MonteCarloPathSimulator<PathGen, SeedRng, Path>,
+        nr_paths: usize,
+        nr_steps: usize,
+        path_fn: impl Fn(&Path) -> Option<f64>,
+    ) -> Self
+    where
+        PathGen: PathGenerator<Path>,
+        SeedRng: rand::SeedableRng + rand::RngCore,
+    {
+        let samples = simulator
+            .simulate_paths(nr_paths, nr_steps)
+            .iter()
+            .filter_map(path_fn)
+            .collect();
+        Self::new(samples)
+    }
+
+    pub fn nr_samples(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn moments(&self) -> Moments {
+        Moments::from_samples(&self.samples)
+    }
+
+    /// z-tests of the sample moments, see https://en.wikipedia.org/wiki/Z-test
+    pub fn moment_z_scores(&self, expected: &Moments) -> MomentZScores {
+        let n = self.nr_samples() as f64;
+        let sample = self.moments();
+        // the variance of the sample variance is (mu_4 - sigma^4) / n
+        let mu_4 = (expected.excess_kurtosis + 3.0) * expected.variance.powi(2);
+        let variance_std_error = ((mu_4 - expected.variance.powi(2)) / n).sqrt();
+
+        MomentZScores {
+            mean: (sample.mean - expected.mean) / (expected.variance / n).sqrt(),
+            variance: (sample.variance - expected.variance) / variance_std_error,
+            skewness: (sample.skewness - expected.skewness) / (6.0 / n).sqrt(),
+            excess_kurtosis: (sample.excess_kurtosis - expected.excess_kurtosis)
+                / (24.0 / n).sqrt(),
+        }
+    }
+
+    /// The Kolmogorov-Smirnov statistic $D_n = sup_x |F_n(x) - F(x)|$ against the theoretical cdf.
+    /// See https://en.wikipedia.org/wiki/Kolmogorov-Smirnov_test
+    pub fn ks_statistic(&self, theoretical_cdf: impl Fn(f64) -> f64) -> f64 {
+        let mut sorted = self.samples.to_owned();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let n = sorted.len() as f64;
+
+        sorted
+            .iter()
+            .enumerate()
+            .map(|(idx, x)| {
+                let f = theoretical_cdf(*x);
+                (f - idx as f64 / n).max((idx + 1) as f64 / n - f)
+            })
+            .fold(0.0, f64::max)
+    }
+
+    /// Whether the Kolmogorov-Smirnov test does not reject the theoretical distribution
+    /// at the significance level `alpha`, using the asymptotic critical value.
+    pub fn ks_test(&self, theoretical_cdf: impl Fn(f64) -> f64, alpha: f64) -> bool {
+        let critical_value = (-0.5 * (alpha / 2.0).ln()).sqrt() / (self.nr_samples() as f64).sqrt();
+        self.ks_statistic(theoretical_cdf) <= critical_value
+    }
+}
+
+/// The cdf of the terminal value $S_T$ of a GBM, which is log-normally distributed.
+pub fn gbm_terminal_cdf(s0: f64, drift: f64, vola: f64, t: f64) -> impl Fn(f64) -> f64 {
+    let log_return = Moments::gbm_log_return(drift, vola, t);
+    move |s: f64| {
+        if s <= 0.0 {
+            return 0.0;
+        }
+        cdf(((s / s0).ln() - log_return.mean) / log_return.variance.sqrt())
+    }
+}
+
+/// The z-score $(\hat{V} - V) / SE$ of the Monte Carlo price against the reference value,
+/// or None if the price has no standard error.
+pub fn z_score(result: &PriceResult, reference: f64) -> Option<f64> {
+    result
+        .std_error
+        .map(|std_error| (result.value - reference) / std_error)
+}
+
+/// The tolerance of `nr_std_errors` standard errors to compare the Monte Carlo price with a reference,
+/// e.g. 3 standard errors fail a correct engine in about 0.3% of the seeds.
+/// None if the price has no standard error.
+pub fn mc_tolerance(result: &PriceResult, nr_std_errors: f64) -> Option<f64> {
+    result.std_error.map(|std_error| nr_std_errors * std_error)
+}
+
+/// The tolerance of `nr_std_errors` standard errors for the configuration with `nr_paths` paths,
+/// estimated from a (smaller) pilot run, as the standard error decays with $1 / \sqrt{n}$.
+pub fn scaled_tolerance(pilot: &PriceResult, nr_paths: usize, nr_std_errors: f64) -> Option<f64> {
+    let nr_pilot_paths = pilot.nr_samples? as f64;
+    mc_tolerance(pilot, nr_std_errors)
+        .map(|tolerance| tolerance * (nr_pilot_paths / nr_paths as f64).sqrt())
+}
+
+/// Asserts that the Monte Carlo price is within `nr_std_errors` standard errors of the reference,
+/// instead of a hard-coded absolute tolerance.
+#[track_caller]
+pub fn assert_within_std_errors(result: &PriceResult, reference: f64, nr_std_errors: f64) {
+    let z = z_score(result, reference).expect("the price has no standard error");
+    assert!(
+        z.abs() <= nr_std_errors,
+        "price {} deviates from the reference {reference} by {z:.2} standard errors (tolerance {nr_std_errors})",
+        result.value
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::sde::gbm::GeometricBrownianMotion;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn sample_moments() {
+        let moments = Moments::from_samples(&[1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(moments.mean, 2.5);
+        assert_eq!(moments.variance, 1.25);
+        assert_eq!(moments.skewness, 0.0);
+        assert_approx_eq!(moments.excess_kurtosis, 1.64 - 3.0, 1e-12);
+    }
+
+    #[test]
+    fn ks_statistic_uniform() {
+        let verifier = DistributionVerifier::new(vec![0.1, 0.3, 0.5, 0.7, 0.9]);
+        assert_approx_eq!(verifier.ks_statistic(|x| x), 0.1, 1e-12);
+        assert!(verifier.ks_test(|x| x, 0.05));
+        assert!(!verifier.ks_test(|x| x.powi(8), 0.05));
+    }
+
+    #[test]
+    fn gbm_terminal_distribution() {
+        let (s0, drift, vola, t, nr_steps) = (100.0, 0.05, 0.3, 1.0, 50);
+        let gbm = GeometricBrownianMotion::new(s0, drift, vola, t / nr_steps as f64);
+        let simulator: MonteCarloPathSimulator<_, rand_hc::Hc128Rng, Vec<f64>> =
+            MonteCarloPathSimulator::new(gbm, Some(42));
+
+        let log_returns = DistributionVerifier::from_simulation(&simulator, 5_000, nr_steps, |p| {
+            p.last().map(|s| (s / s0).ln())
+        });
+        let z_scores = log_returns.moment_z_scores(&Moments::gbm_log_return(drift, vola, t));
+        assert!(z_scores.passes(3.0));
+        // a misspecified volatility is detected
+        let z_scores = log_returns.moment_z_scores(&Moments::gbm_log_return(drift, 0.25, t));
+        assert!(!z_scores.passes(3.0));
+
+        let terminal_values =
+            DistributionVerifier::from_simulation(&simulator, 5_000, nr_steps, |p| {
+                p.last().cloned()
+            });
+        assert!(terminal_values.ks_test(gbm_terminal_cdf(s0, drift, vola, t), 0.01));
+        assert!(!terminal_values.ks_test(gbm_terminal_cdf(s0, 0.2, vola, t), 0.01));
+    }
+
+    #[test]
+    fn tolerance_in_std_errors() {
+        let result = PriceResult::from_samples(&[1.0, 2.0, 3.0, 4.0, 5.0]).unwrap();
+        let std_error = result.std_error.unwrap();
+        assert_approx_eq!(mc_tolerance(&result, 3.0).unwrap(), 3.0 * std_error, 1e-15);
+        assert_approx_eq!(z_score(&result, 2.0).unwrap(), 1.0 / std_error, 1e-12);
+        // 4 times the paths halve the tolerance
+        assert_approx_eq!(
+            scaled_tolerance(&result, 20, 3.0).unwrap(),
+            1.5 * std_error,
+            1e-15
+        );
+        assert_eq!(mc_tolerance(&PriceResult::exact(1.0), 3.0), None);
+
+        assert_within_std_errors(&result, 3.0 + 2.9 * std_error, 3.0);
+        let outside = std::panic::catch_unwind(|| {
+            assert_within_std_errors(&result, 3.0 + 3.1 * std_error, 3.0)
+        });
+        assert!(outside.is_err());
+    }
+}
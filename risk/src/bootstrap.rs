@@ -0,0 +1,539 @@
+use rand::Rng;
+
+use crate::drawdown::Drawdown;
+use crate::error::RiskError;
+use crate::risk_figures::sharpe_ratio;
+use crate::value_at_risk::EmpiricalLosses;
+
+/// How historical observations are resampled into scenario paths.
+/// See https://en.wikipedia.org/wiki/Bootstrapping_(statistics)#Block_bootstrap
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlockScheme {
+    /// independent draws of single observations, which destroys any serial dependence
+    Iid,
+    /// overlapping blocks of fixed length (moving block bootstrap)
+    MovingBlock { block_length: usize },
+    /// blocks of geometrically distributed length wrapping around the history (Politis-Romano),
+    /// such that the resampled series is stationary
+    Stationary { mean_block_length: f64 },
+    /// overlapping blocks of fixed length starting in the same season, e.g. the same weekday for a
+    /// period of 5, as the position they fill; the scenario continues the season of the history's end
+    Seasonal { period: usize, block_length: usize },
+}
+
+/// Block bootstrap of historical risk factor returns, one vector of all risk factors per date,
+/// such that the scenarios keep the cross-sectional dependence and, within blocks, the
+/// autocorrelation and volatility clustering of the history.
+#[derive(Clone, Debug)]
+pub struct BlockBootstrap {
+    /// observations per date (rows) and risk factor (columns)
+    history: Vec<Vec<f64>>,
+    scheme: BlockScheme,
+}
+
+impl BlockBootstrap {
+    pub fn new(history: Vec<Vec<f64>>) -> Result<Self, RiskError> {
+        let nr_factors = history.first().ok_or(RiskError::EmptySample)?.len();
+        if nr_factors == 0 {
+            return Err(RiskError::EmptySample);
+        }
+        if history.iter().any(|obs| obs.len() != nr_factors) {
+            return Err(RiskError::InvalidParameter(
+                "observations of different dimensions".to_string(),
+            ));
+        }
+        if history.iter().flatten().any(|x| x.is_nan()) {
+            return Err(RiskError::NaNSample);
+        }
+        Ok(Self {
+            history,
+            scheme: BlockScheme::Iid,
+        })
+    }
+
+    /// From the history of a single risk factor.
+    pub fn from_series(series: &[f64]) -> Result<Self, RiskError> {
+        Self::new(series.iter().map(|x| vec![*x]).collect())
+    }
+
+    pub fn with_scheme(self, scheme: BlockScheme) -> Result<Self, RiskError> {
+        let n = self.history.len();
+        let valid = match scheme {
+            BlockScheme::Iid => true,
+            BlockScheme::MovingBlock { block_length } => (1..=n).contains(&block_length),
+            BlockScheme::Stationary { mean_block_length } => mean_block_length >= 1.0,
+            BlockScheme::Seasonal {
+                period,
+                block_length,
+            } => period >= 1 && block_length >= 1 && period + block_length <= n + 1,
+        };
+        if !valid {
+            return Err(RiskError::InvalidParameter(format!(
+                "{scheme:?} for a history of length {n}"
+            )));
+        }
+        Ok(Self { scheme, ..self })
+    }
+
+    /// The stationary bootstrap with the Politis-White mean block length averaged over the risk factors.
+    pub fn with_optimal_stationary_blocks(self) -> Result<Self, RiskError> {
+        let mean_block_length = self.optimal_block_lengths()?.stationary;
+        self.with_scheme(BlockScheme::Stationary { mean_block_length })
+    }
+
+    pub fn len(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.history.is_empty()
+    }
+
+    pub fn nr_factors(&self) -> usize {
+        self.history[0].len()
+    }
+
+    /// The Politis-White block lengths averaged over the risk factors.
+    pub fn optimal_block_lengths(&self) -> Result<OptimalBlockLengths, RiskError> {
+        let nr_factors = self.nr_factors();
+        let mut average = OptimalBlockLengths {
+            stationary: 0.0,
+            circular: 0.0,
+        };
+        for factor in 0..nr_factors {
+            let series: Vec<f64> = self.history.iter().map(|obs| obs[factor]).collect();
+            let lengths = politis_white_block_length(&series)?;
+            average.stationary += lengths.stationary / nr_factors as f64;
+            average.circular += lengths.circular / nr_factors as f64;
+        }
+        Ok(average)
+    }
+
+    /// The indices into the history of a resampled path of the given length.
+    pub fn indices<R: Rng + ?Sized>(&self, rng: &mut R, length: usize) -> Vec<usize> {
+        let n = self.len();
+        let mut indices = Vec::with_capacity(length);
+        while indices.len() < length {
+            let (start, block_length) = match self.scheme {
+                BlockScheme::Iid => (rng.gen_range(0..n), 1),
+                BlockScheme::MovingBlock { block_length } => {
+                    (rng.gen_range(0..=n - block_length), block_length)
+                }
+                BlockScheme::Stationary { mean_block_length } => {
+                    // geometric block length with success probability 1 / mean_block_length
+                    let u: f64 = 1.0 - rng.gen::<f64>();
+                    let failures = u.ln() / (1.0 - 1.0 / mean_block_length).ln();
+                    (
+                        rng.gen_range(0..n),
+                        (failures.floor() as usize).saturating_add(1),
+                    )
+                }
+                BlockScheme::Seasonal {
+                    period,
+                    block_length,
+                } => {
+                    let phase = (n + indices.len()) % period;
+                    let nr_starts = (n - block_length - phase) / period + 1;
+                    (phase + period * rng.gen_range(0..nr_starts), block_length)
+                }
+            };
+            let remaining = length - indices.len();
+            indices.extend((0..block_length.min(remaining)).map(|i| (start + i) % n));
+        }
+        indices
+    }
+
+    /// A scenario path of the given length, i.e. the resampled observations per date.
+    pub fn scenario<R: Rng + ?Sized>(&self, rng: &mut R, length: usize) -> Vec<Vec<f64>> {
+        self.indices(rng, length)
+            .into_iter()
+            .map(|idx| self.history[idx].clone())
+            .collect()
+    }
+
+    /// The scenarios of the sums of the resampled observations over the horizon,
+    /// e.g. the multi-day log returns per risk factor.
+    pub fn aggregated_scenarios<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+        nr_scenarios: usize,
+        horizon: usize,
+    ) -> Vec<Vec<f64>> {
+        (0..nr_scenarios)
+            .map(|_| {
+                self.indices(rng, horizon).into_iter().fold(
+                    vec![0.0; self.nr_factors()],
+                    |mut acc, idx| {
+                        acc.iter_mut()
+                            .zip(&self.history[idx])
+                            .for_each(|(a, x)| *a += x);
+                        acc
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// The estimate of the statistic on the series of a single risk factor with the percentile
+    /// interval at the confidence level of its estimates on the resampled series of the same length.
+    /// Resamples on which the statistic fails, e.g. without downside, are skipped.
+    /// Use a block scheme for serially dependent returns, whose drawdowns iid resampling understates.
+    pub fn confidence_interval<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+        statistic: &ReturnStatistic,
+        confidence: f64,
+        nr_resamples: usize,
+    ) -> Result<IntervalEstimate, RiskError> {
+        if !(0.0 < confidence && confidence < 1.0) {
+            return Err(RiskError::InvalidLevel(confidence));
+        }
+        if self.nr_factors() != 1 {
+            return Err(RiskError::InvalidParameter(format!(
+                "{} risk factors for a statistic of one series",
+                self.nr_factors()
+            )));
+        }
+        let series: Vec<f64> = self.history.iter().map(|obs| obs[0]).collect();
+        let estimate = statistic.estimate(&series)?;
+
+        let mut resampled: Vec<f64> = (0..nr_resamples)
+            .filter_map(|_| {
+                let path: Vec<f64> = self
+                    .indices(rng, series.len())
+                    .into_iter()
+                    .map(|idx| series[idx])
+                    .collect();
+                statistic.estimate(&path).ok()
+            })
+            .collect();
+        if resampled.len() < 2 {
+            return Err(RiskError::EmptySample);
+        }
+        resampled.sort_by(f64::total_cmp);
+        let m = resampled.len();
+        let quantile = |level: f64| resampled[((level * m as f64).ceil() as usize).clamp(1, m) - 1];
+        let mean = resampled.iter().sum::<f64>() / m as f64;
+        let variance = resampled.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (m - 1) as f64;
+        Ok(IntervalEstimate {
+            estimate,
+            lower: quantile(0.5 * (1.0 - confidence)),
+            upper: quantile(0.5 * (1.0 + confidence)),
+            std_error: variance.sqrt(),
+            nr_resamples: m,
+        })
+    }
+}
+
+/// A risk figure of a return series.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReturnStatistic {
+    /// the mean excess return over the rate per period over its standard deviation
+    SharpeRatio { riskfree_rate: f64 },
+    /// the mean excess return over the target over the downside deviation below the target
+    /// See https://en.wikipedia.org/wiki/Sortino_ratio
+    SortinoRatio { target: f64 },
+    /// the maximal drawdown of the compounded returns
+    MaxDrawdown,
+    /// the VaR of the returns at the confidence level
+    ValueAtRisk { level: f64 },
+}
+
+impl ReturnStatistic {
+    pub fn estimate(&self, returns: &[f64]) -> Result<f64, RiskError> {
+        if returns.len() < 2 {
+            return Err(RiskError::EmptySample);
+        }
+        let n = returns.len() as f64;
+        match *self {
+            ReturnStatistic::SharpeRatio { riskfree_rate } => {
+                let mean = returns.iter().sum::<f64>() / n;
+                let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+                sharpe_ratio(mean, riskfree_rate, variance.sqrt(), None)
+            }
+            ReturnStatistic::SortinoRatio { target } => {
+                let excess = returns.iter().map(|r| r - target).sum::<f64>() / n;
+                let downside = returns
+                    .iter()
+                    .map(|r| (r - target).min(0.0).powi(2))
+                    .sum::<f64>()
+                    / n;
+                sharpe_ratio(excess, 0.0, downside.sqrt(), None)
+            }
+            ReturnStatistic::MaxDrawdown => Ok(Drawdown::from_returns(returns)?.max_drawdown()),
+            ReturnStatistic::ValueAtRisk { level } => {
+                EmpiricalLosses::from_pnl(returns)?.value_at_risk(level)
+            }
+        }
+    }
+}
+
+/// A point estimate with the percentile interval of its bootstrap distribution.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IntervalEstimate {
+    /// the estimate of the historical sample
+    pub estimate: f64,
+    pub lower: f64,
+    pub upper: f64,
+    /// the standard deviation of the resampled estimates
+    pub std_error: f64,
+    pub nr_resamples: usize,
+}
+
+impl IntervalEstimate {
+    pub fn contains(&self, value: f64) -> bool {
+        self.lower <= value && value <= self.upper
+    }
+}
+
+/// Estimated optimal (mean) block lengths of the stationary and the circular block bootstrap.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OptimalBlockLengths {
+    pub stationary: f64,
+    pub circular: f64,
+}
+
+/// The automatic block length selection of Politis and White (2004) with the correction of
+/// Patton, Politis and White (2009), based on a flat-top lag window whose bandwidth is chosen by
+/// the first run of insignificant autocorrelations. The lengths are capped at $min(3 \sqrt{n}, n / 3)$.
+pub fn politis_white_block_length(series: &[f64]) -> Result<OptimalBlockLengths, RiskError> {
+    let n = series.len();
+    if n < 9 {
+        return Err(RiskError::InvalidParameter(format!(
+            "series of length {n} too short for the block length selection"
+        )));
+    }
+    if series.iter().any(|x| x.is_nan()) {
+        return Err(RiskError::NaNSample);
+    }
+    let nf = n as f64;
+    let mean = series.iter().sum::<f64>() / nf;
+    let autocovariance = |k: usize| {
+        (0..n - k)
+            .map(|t| (series[t] - mean) * (series[t + k] - mean))
+            .sum::<f64>()
+            / nf
+    };
+
+    let k_n = 5.max(nf.log10().sqrt().ceil() as usize);
+    let m_max = ((nf.sqrt().ceil() as usize) + k_n).min(n - 1);
+    let threshold = 2.0 * (nf.log10() / nf).sqrt();
+
+    let variance = autocovariance(0);
+    if variance == 0.0 {
+        return Err(RiskError::ZeroDivision);
+    }
+    let autocovariances: Vec<f64> = (0..=m_max).map(autocovariance).collect();
+
+    // the first lag starting k_n consecutive insignificant autocorrelations
+    let mut run_length = 0;
+    let mut m_hat = None;
+    for (lag, r) in autocovariances.iter().enumerate().skip(1) {
+        if (r / variance).abs() < threshold {
+            run_length += 1;
+            if run_length == k_n {
+                m_hat = Some(lag - k_n);
+                break;
+            }
+        } else {
+            run_length = 0;
+        }
+    }
+    let bandwidth = match m_hat {
+        Some(m_hat) => (2 * m_hat.max(1)).min(m_max),
+        None => m_max,
+    };
+
+    let flat_top = |s: f64| match s.abs() {
+        s if s <= 0.5 => 1.0,
+        s if s <= 1.0 => 2.0 * (1.0 - s),
+        _ => 0.0,
+    };
+    let (mut g, mut spectral_zero) = (0.0, variance);
+    for (k, r) in autocovariances
+        .iter()
+        .enumerate()
+        .take(bandwidth + 1)
+        .skip(1)
+    {
+        let weight = flat_top(k as f64 / bandwidth as f64);
+        g += 2.0 * weight * k as f64 * r;
+        spectral_zero += 2.0 * weight * r;
+    }
+
+    let block_length = |d: f64| {
+        let b_max = (3.0 * nf.sqrt()).min(nf / 3.0).ceil();
+        ((2.0 * g * g / d).cbrt() * nf.cbrt()).clamp(1.0, b_max)
+    };
+    Ok(OptimalBlockLengths {
+        stationary: block_length(2.0 * spectral_zero.powi(2)),
+        circular: block_length(4.0 / 3.0 * spectral_zero.powi(2)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    /// AR(1) series with uniform innovations.
+    fn ar1(phi: f64, n: usize, seed: u64) -> Vec<f64> {
+        let mut rng = rand_hc::Hc128Rng::seed_from_u64(seed);
+        let mut x = 0.0;
+        (0..n)
+            .map(|_| {
+                x = phi * x + rng.gen::<f64>() - 0.5;
+                x
+            })
+            .collect()
+    }
+
+    fn lag_one_autocorrelation(series: &[f64]) -> f64 {
+        let n = series.len() as f64;
+        let mean = series.iter().sum::<f64>() / n;
+        let variance = series.iter().map(|x| (x - mean).powi(2)).sum::<f64>();
+        series
+            .windows(2)
+            .map(|w| (w[0] - mean) * (w[1] - mean))
+            .sum::<f64>()
+            / variance
+    }
+
+    #[test]
+    fn block_schemes() {
+        let mut rng = rand_hc::Hc128Rng::seed_from_u64(1);
+        let history: Vec<f64> = (0..20).map(|i| i as f64).collect();
+        let bootstrap = BlockBootstrap::from_series(&history).unwrap();
+
+        let moving = bootstrap
+            .clone()
+            .with_scheme(BlockScheme::MovingBlock { block_length: 5 })
+            .unwrap();
+        let indices = moving.indices(&mut rng, 23);
+        assert_eq!(indices.len(), 23);
+        for block in indices.chunks(5) {
+            assert!(block.windows(2).all(|w| w[1] == w[0] + 1));
+        }
+
+        let seasonal = bootstrap
+            .clone()
+            .with_scheme(BlockScheme::Seasonal {
+                period: 7,
+                block_length: 3,
+            })
+            .unwrap();
+        let indices = seasonal.indices(&mut rng, 50);
+        for (position, idx) in indices.iter().enumerate() {
+            assert_eq!(idx % 7, (20 + position) % 7);
+        }
+
+        let scenario = seasonal.scenario(&mut rng, 4);
+        assert_eq!(scenario.len(), 4);
+        assert_eq!(scenario[0].len(), 1);
+
+        assert!(bootstrap
+            .clone()
+            .with_scheme(BlockScheme::MovingBlock { block_length: 21 })
+            .is_err());
+        assert!(BlockBootstrap::new(vec![vec![1.0], vec![1.0, 2.0]]).is_err());
+    }
+
+    #[test]
+    fn stationary_bootstrap_keeps_autocorrelation() {
+        let mut rng = rand_hc::Hc128Rng::seed_from_u64(2);
+        let series = ar1(0.8, 2_000, 3);
+        let history_rho = lag_one_autocorrelation(&series);
+        assert!(history_rho > 0.7);
+
+        let bootstrap = BlockBootstrap::from_series(&series).unwrap();
+        let resampled_rho = |bootstrap: &BlockBootstrap, rng: &mut rand_hc::Hc128Rng| {
+            let path: Vec<f64> = bootstrap
+                .scenario(rng, 2_000)
+                .into_iter()
+                .map(|obs| obs[0])
+                .collect();
+            lag_one_autocorrelation(&path)
+        };
+        assert!(resampled_rho(&bootstrap, &mut rng).abs() < 0.1);
+
+        let stationary = bootstrap.with_optimal_stationary_blocks().unwrap();
+        assert!((resampled_rho(&stationary, &mut rng) - history_rho).abs() < 0.1);
+
+        let aggregated = stationary.aggregated_scenarios(&mut rng, 10, 5);
+        assert_eq!(aggregated.len(), 10);
+    }
+
+    #[test]
+    fn risk_figure_intervals() {
+        let mut rng = rand_hc::Hc128Rng::seed_from_u64(5);
+        // daily returns with a Sharpe ratio of 0.1 per period
+        let returns: Vec<f64> = ar1(0.0, 500, 6)
+            .iter()
+            .map(|u| 0.001 + 0.0346 * u)
+            .collect();
+        let bootstrap = BlockBootstrap::from_series(&returns).unwrap();
+
+        let sharpe = bootstrap
+            .confidence_interval(
+                &mut rng,
+                &ReturnStatistic::SharpeRatio { riskfree_rate: 0.0 },
+                0.95,
+                1_000,
+            )
+            .unwrap();
+        assert!(sharpe.contains(sharpe.estimate));
+        // about the asymptotic standard error $\sqrt{(1 + SR^2 / 2) / n}$
+        assert!((sharpe.std_error - (1.005_f64 / 500.0).sqrt()).abs() < 0.01);
+        assert!((sharpe.upper - sharpe.lower - 2.0 * 1.96 * sharpe.std_error).abs() < 0.02);
+
+        let sortino = ReturnStatistic::SortinoRatio { target: 0.0 };
+        let var = ReturnStatistic::ValueAtRisk { level: 0.95 };
+        for statistic in [sortino, var, ReturnStatistic::MaxDrawdown] {
+            let interval = bootstrap
+                .confidence_interval(&mut rng, &statistic, 0.9, 500)
+                .unwrap();
+            assert!(interval.lower < interval.upper, "{statistic:?}");
+            assert!(interval.estimate > 0.0 && interval.std_error > 0.0);
+        }
+
+        // blocks keep the trends of persistent returns, which deepen the drawdowns
+        let persistent: Vec<f64> = ar1(0.9, 500, 7).iter().map(|x| 0.01 * x).collect();
+        let drawdown = |scheme| {
+            BlockBootstrap::from_series(&persistent)
+                .unwrap()
+                .with_scheme(scheme)
+                .unwrap()
+                .confidence_interval(
+                    &mut rand_hc::Hc128Rng::seed_from_u64(8),
+                    &ReturnStatistic::MaxDrawdown,
+                    0.9,
+                    500,
+                )
+                .unwrap()
+        };
+        let iid = drawdown(BlockScheme::Iid);
+        let blocks = drawdown(BlockScheme::Stationary {
+            mean_block_length: 20.0,
+        });
+        assert!(blocks.upper > iid.upper);
+        assert!(bootstrap
+            .confidence_interval(&mut rng, &sortino, 1.0, 10)
+            .is_err());
+    }
+
+    #[test]
+    fn politis_white_lengths() {
+        let persistent = politis_white_block_length(&ar1(0.8, 1_000, 4)).unwrap();
+        let noise = politis_white_block_length(&ar1(0.0, 1_000, 4)).unwrap();
+        assert!(persistent.stationary > 10.0);
+        assert!(noise.stationary < 3.0);
+        // the circular bootstrap uses longer blocks
+        assert!(persistent.circular > persistent.stationary);
+
+        assert!(politis_white_block_length(&[1.0; 5]).is_err());
+        assert!(matches!(
+            politis_white_block_length(&[1.0; 50]),
+            Err(RiskError::ZeroDivision)
+        ));
+    }
+}
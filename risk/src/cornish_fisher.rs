@@ -0,0 +1,247 @@
+use std::fmt;
+
+use probability::distribution::{Continuous, Gaussian, Inverse};
+
+use crate::error::RiskError;
+use crate::value_at_risk::check_level;
+
+/// The minimal number of samples for reliable estimates of skewness and kurtosis.
+const MIN_SAMPLES: usize = 250;
+
+/// Reasons why a Cornish-Fisher estimate may be unreliable.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CornishFisherWarning {
+    /// the expansion is not monotone up to the level for these moments, i.e. outside its domain of
+    /// validity, such that quantiles can cross
+    OutsideValidityDomain { skewness: f64, excess_kurtosis: f64 },
+    /// too few samples for reliable estimates of the higher moments
+    SmallSample { nr_samples: usize },
+}
+
+impl fmt::Display for CornishFisherWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutsideValidityDomain {
+                skewness,
+                excess_kurtosis,
+            } => write!(
+                f,
+                "skewness {skewness} and excess kurtosis {excess_kurtosis} outside the domain of validity"
+            ),
+            Self::SmallSample { nr_samples } => write!(
+                f,
+                "{nr_samples} samples, at least {MIN_SAMPLES} recommended for the higher moments"
+            ),
+        }
+    }
+}
+
+/// VaR and ES of the losses by the Cornish-Fisher expansion, with the warnings on its reliability.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CornishFisherEstimate {
+    pub value_at_risk: f64,
+    pub expected_shortfall: f64,
+    pub warnings: Vec<CornishFisherWarning>,
+}
+
+/// Modified (Cornish-Fisher) VaR: the Gaussian quantile $z$ of the losses is adjusted by the sample
+/// skewness $S$ and excess kurtosis $K$,
+/// '''math
+/// z_{cf} = z + (z^2 - 1) S / 6 + (z^3 - 3z) K / 24 - (2z^3 - 5z) S^2 / 36
+/// ''', which sits between the parametric (Gaussian) and the historical VaR.
+/// See https://en.wikipedia.org/wiki/Cornish%E2%80%93Fisher_expansion
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CornishFisher {
+    pub mean: f64,
+    pub std_dev: f64,
+    pub skewness: f64,
+    pub excess_kurtosis: f64,
+    pub nr_samples: usize,
+}
+
+impl CornishFisher {
+    /// From the sample moments (biased estimators) of the losses.
+    pub fn from_losses(losses: &[f64]) -> Result<Self, RiskError> {
+        if losses.is_empty() {
+            return Err(RiskError::EmptySample);
+        }
+        if losses.iter().any(|l| l.is_nan()) {
+            return Err(RiskError::NaNSample);
+        }
+        let n = losses.len() as f64;
+        let mean = losses.iter().sum::<f64>() / n;
+        let central_moment = |k: i32| losses.iter().map(|x| (x - mean).powi(k)).sum::<f64>() / n;
+        let variance = central_moment(2);
+        if variance == 0.0 {
+            return Err(RiskError::ZeroDivision);
+        }
+        Ok(Self {
+            mean,
+            std_dev: variance.sqrt(),
+            skewness: central_moment(3) / variance.powf(1.5),
+            excess_kurtosis: central_moment(4) / variance.powi(2) - 3.0,
+            nr_samples: losses.len(),
+        })
+    }
+
+    /// From P&L samples, where a loss is the negative P&L.
+    pub fn from_pnl(pnl: &[f64]) -> Result<Self, RiskError> {
+        Self::from_losses(&pnl.iter().map(|p| -p).collect::<Vec<f64>>())
+    }
+
+    /// The adjusted quantile $z_{cf}$ of the standard normal quantile $z$.
+    pub fn adjusted_quantile(&self, z: f64) -> f64 {
+        let (s, k) = (self.skewness, self.excess_kurtosis);
+        z + (z * z - 1.0) * s / 6.0 + (z.powi(3) - 3.0 * z) * k / 24.0
+            - (2.0 * z.powi(3) - 5.0 * z) * s * s / 36.0
+    }
+
+    /// Whether the expansion is monotone, i.e. $z_{cf}'(z) > 0$, on $[-z, z]$ for the standard normal
+    /// quantile $z$ of the level; globally this holds only on the domain of validity of Maillard (2012).
+    pub fn is_valid(&self, level: f64) -> bool {
+        // z_cf'(x) = a x^2 + b x + c
+        let (s, k) = (self.skewness, self.excess_kurtosis);
+        let a = k / 8.0 - s * s / 6.0;
+        let b = s / 3.0;
+        let c = 1.0 - k / 8.0 + 5.0 * s * s / 36.0;
+        let derivative = |x: f64| (a * x + b) * x + c;
+
+        let z = Gaussian::new(0.0, 1.0).inverse(level.max(1.0 - level));
+        let mut minimum = derivative(-z).min(derivative(z));
+        if a > 0.0 && (-b / (2.0 * a)).abs() <= z {
+            minimum = minimum.min(derivative(-b / (2.0 * a)));
+        }
+        minimum > 0.0
+    }
+
+    /// The warnings on the reliability of the estimates at the confidence level.
+    pub fn warnings(&self, level: f64) -> Vec<CornishFisherWarning> {
+        let mut warnings = Vec::new();
+        if !self.is_valid(level) {
+            warnings.push(CornishFisherWarning::OutsideValidityDomain {
+                skewness: self.skewness,
+                excess_kurtosis: self.excess_kurtosis,
+            });
+        }
+        if self.nr_samples < MIN_SAMPLES {
+            warnings.push(CornishFisherWarning::SmallSample {
+                nr_samples: self.nr_samples,
+            });
+        }
+        warnings
+    }
+
+    /// The Gaussian VaR at the confidence level, ignoring the higher moments.
+    pub fn gaussian_value_at_risk(&self, level: f64) -> Result<f64, RiskError> {
+        check_level(level)?;
+        Ok(self.mean + self.std_dev * Gaussian::new(0.0, 1.0).inverse(level))
+    }
+
+    pub fn value_at_risk(&self, level: f64) -> Result<f64, RiskError> {
+        check_level(level)?;
+        let z = Gaussian::new(0.0, 1.0).inverse(level);
+        Ok(self.mean + self.std_dev * self.adjusted_quantile(z))
+    }
+
+    /// The ES as the average of the Cornish-Fisher VaR beyond the level, in closed form by the
+    /// Gaussian tail moments $\int_z^\infty x^k \phi(x) dx$.
+    pub fn expected_shortfall(&self, level: f64) -> Result<f64, RiskError> {
+        check_level(level)?;
+        let normal = Gaussian::new(0.0, 1.0);
+        let z = normal.inverse(level);
+        let (density, tail) = (normal.density(z), 1.0 - level);
+        let m1 = density;
+        let m2 = z * density + tail;
+        let m3 = (z * z + 2.0) * density;
+
+        let (s, k) = (self.skewness, self.excess_kurtosis);
+        let tail_integral = m1 + (m2 - tail) * s / 6.0 + (m3 - 3.0 * m1) * k / 24.0
+            - (2.0 * m3 - 5.0 * m1) * s * s / 36.0;
+        Ok(self.mean + self.std_dev * tail_integral / tail)
+    }
+
+    pub fn estimate(&self, level: f64) -> Result<CornishFisherEstimate, RiskError> {
+        Ok(CornishFisherEstimate {
+            value_at_risk: self.value_at_risk(level)?,
+            expected_shortfall: self.expected_shortfall(level)?,
+            warnings: self.warnings(level),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value_at_risk::EmpiricalLosses;
+    use rand::{Rng, SeedableRng};
+
+    fn assert_close(a: f64, b: f64, tolerance: f64) {
+        assert!((a - b).abs() < tolerance, "{a} != {b}");
+    }
+
+    #[test]
+    fn gaussian_losses() {
+        let normal = Gaussian::new(0.0, 1.0);
+        let losses: Vec<f64> = (1..2000)
+            .map(|i| 1.0 + 2.0 * normal.inverse(i as f64 / 2000.0))
+            .collect();
+        let cf = CornishFisher::from_losses(&losses).unwrap();
+        assert_close(cf.skewness, 0.0, 1e-10);
+        assert!(cf.is_valid(0.999));
+
+        let estimate = cf.estimate(0.99).unwrap();
+        assert!(estimate.warnings.is_empty());
+        assert_close(
+            estimate.value_at_risk,
+            cf.gaussian_value_at_risk(0.99).unwrap(),
+            0.05,
+        );
+        let z = normal.inverse(0.99);
+        let gaussian_es = cf.mean + cf.std_dev * normal.density(z) / 0.01;
+        assert_close(estimate.expected_shortfall, gaussian_es, 0.1);
+    }
+
+    #[test]
+    fn skewed_losses() {
+        // lognormal losses are right skewed, such that the Gaussian VaR is too low
+        let mut rng = rand_hc::Hc128Rng::seed_from_u64(3);
+        let normal = Gaussian::new(0.0, 0.25);
+        let losses: Vec<f64> = (0..20_000)
+            .map(|_| normal.inverse(rng.gen::<f64>()).exp())
+            .collect();
+        let cf = CornishFisher::from_losses(&losses).unwrap();
+        assert!(cf.skewness > 0.5);
+        assert!(cf.warnings(0.99).is_empty());
+
+        let empirical = EmpiricalLosses::from_losses(losses).unwrap();
+        let historical = empirical.value_at_risk(0.99).unwrap();
+        let gaussian = cf.gaussian_value_at_risk(0.99).unwrap();
+        let modified = cf.value_at_risk(0.99).unwrap();
+        assert!(gaussian < modified);
+        assert!((modified - historical).abs() < (gaussian - historical).abs());
+        assert!(cf.expected_shortfall(0.99).unwrap() > modified);
+        assert_close(
+            cf.expected_shortfall(0.99).unwrap(),
+            empirical.expected_shortfall(0.99).unwrap(),
+            0.05,
+        );
+    }
+
+    #[test]
+    fn validity_warnings() {
+        let cf = CornishFisher {
+            mean: 0.0,
+            std_dev: 1.0,
+            skewness: 2.0,
+            excess_kurtosis: 1.0,
+            nr_samples: 100,
+        };
+        assert!(!cf.is_valid(0.99));
+        let warnings = cf.estimate(0.99).unwrap().warnings;
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0]
+            .to_string()
+            .contains("outside the domain of validity"));
+        assert!(CornishFisher::from_losses(&[1.0; 10]).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+use crate::error::RiskError;
+use crate::value_at_risk::{check_level, EmpiricalLosses};
+use probability::distribution::{Distribution, Gaussian, Inverse};
+
+/// A distortion function $g: [0, 1] -> [0, 1]$, increasing with $g(0) = 0$ and $g(1) = 1$,
+/// applied to the survival function of the losses.
+/// See https://en.wikipedia.org/wiki/Distortion_risk_measure
+pub trait Distortion {
+    fn distort(&self, survival_probability: f64) -> f64;
+}
+
+/// The Wang transform $g(u) = \Phi(\Phi^{-1}(u) + \lambda)$ for the market price of risk $\lambda$.
+/// See https://en.wikipedia.org/wiki/Wang_transform
+#[derive(Clone, Copy, Debug)]
+pub struct WangTransform {
+    lambda: f64,
+}
+
+impl WangTransform {
+    pub fn new(lambda: f64) -> Self {
+        Self { lambda }
+    }
+}
+
+impl Distortion for WangTransform {
+    fn distort(&self, survival_probability: f64) -> f64 {
+        if survival_probability <= 0.0 {
+            return 0.0;
+        }
+        if survival_probability >= 1.0 {
+            return 1.0;
+        }
+        let normal = Gaussian::new(0.0, 1.0);
+        normal.distribution(normal.inverse(survival_probability) + self.lambda)
+    }
+}
+
+/// The proportional hazard transform $g(u) = u^{1 / \gamma}$ for $\gamma >= 1$.
+#[derive(Clone, Copy, Debug)]
+pub struct ProportionalHazard {
+    gamma: f64,
+}
+
+impl ProportionalHazard {
+    pub fn new(gamma: f64) -> Result<Self, RiskError> {
+        if gamma < 1.0 {
+            return Err(RiskError::InvalidParameter(
+                "gamma must be at least 1".to_string(),
+            ));
+        }
+        Ok(Self { gamma })
+    }
+}
+
+impl Distortion for ProportionalHazard {
+    fn distort(&self, survival_probability: f64) -> f64 {
+        survival_probability.clamp(0.0, 1.0).powf(1.0 / self.gamma)
+    }
+}
+
+/// The distortion $g(u) = min(u / (1 - level), 1)$ of the Expected Shortfall.
+#[derive(Clone, Copy, Debug)]
+pub struct TailValueAtRisk {
+    level: f64,
+}
+
+impl TailValueAtRisk {
+    pub fn new(level: f64) -> Result<Self, RiskError> {
+        check_level(level)?;
+        Ok(Self { level })
+    }
+}
+
+impl Distortion for TailValueAtRisk {
+    fn distort(&self, survival_probability: f64) -> f64 {
+        (survival_probability / (1.0 - self.level)).min(1.0)
+    }
+}
+
+/// The distortion risk measure $\rho_g(L) = \int g(S_L(x)) dx$ of the empirical losses,
+/// i.e. the weighted sum $\sum_i L_{(i)} (g(i/n) - g((i-1)/n))$ over the losses in descending order.
+pub fn distortion_risk_measure(losses: &EmpiricalLosses, distortion: &impl Distortion) -> f64 {
+    let n = losses.len() as f64;
+    losses
+        .sorted_losses()
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(idx, loss)| {
+            let weight =
+                distortion.distort((idx + 1) as f64 / n) - distortion.distort(idx as f64 / n);
+            loss * weight
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOLERANCE: f64 = 1e-10;
+
+    fn losses() -> EmpiricalLosses {
+        EmpiricalLosses::from_losses((1..=100).map(|i| i as f64 - 50.0).collect()).unwrap()
+    }
+
+    #[test]
+    fn expected_shortfall_as_distortion() {
+        let losses = losses();
+        let es = distortion_risk_measure(&losses, &TailValueAtRisk::new(0.95).unwrap());
+        assert!((es - losses.expected_shortfall(0.95).unwrap()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn wang_transform() {
+        let losses = losses();
+        let mean = losses.sorted_losses().iter().sum::<f64>() / 100.0;
+
+        // no distortion yields the expected loss
+        let rho = distortion_risk_measure(&losses, &WangTransform::new(0.0));
+        assert!((rho - mean).abs() < TOLERANCE);
+
+        // a positive market price of risk loads the expected loss
+        let rho_loaded = distortion_risk_measure(&losses, &WangTransform::new(0.5));
+        assert!(rho_loaded > mean);
+        assert!(rho_loaded < 50.0);
+    }
+
+    #[test]
+    fn proportional_hazard() {
+        let losses = losses();
+        let mean = losses.sorted_losses().iter().sum::<f64>() / 100.0;
+
+        let rho = distortion_risk_measure(&losses, &ProportionalHazard::new(1.0).unwrap());
+        assert!((rho - mean).abs() < TOLERANCE);
+
+        let rho_1 = distortion_risk_measure(&losses, &ProportionalHazard::new(1.5).unwrap());
+        let rho_2 = distortion_risk_measure(&losses, &ProportionalHazard::new(3.0).unwrap());
+        assert!(mean < rho_1 && rho_1 < rho_2);
+
+        assert!(ProportionalHazard::new(0.5).is_err());
+    }
+}
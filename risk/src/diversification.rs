@@ -0,0 +1,246 @@
+use crate::error::RiskError;
+
+/// The diversification ratio $w^T \sigma / \sqrt{w^T \Sigma w}$ of the weighted volatilities over the
+/// portfolio volatility, 1 for perfectly correlated assets and $\sqrt{n}$ for n uncorrelated assets
+/// of equal risk (Choueifaty and Coignard).
+pub fn diversification_ratio(covariance: &[Vec<f64>], weights: &[f64]) -> Result<f64, RiskError> {
+    let variance = portfolio_variance(covariance, weights)?;
+    let weighted_volatility: f64 = weights
+        .iter()
+        .zip(covariance)
+        .enumerate()
+        .map(|(i, (w, row))| w * row[i].sqrt())
+        .sum();
+    Ok(weighted_volatility / variance.sqrt())
+}
+
+/// The fractions $p_k = \lambda_k \tilde w_k^2 / w^T \Sigma w$ of the portfolio variance of the
+/// uncorrelated principal portfolios, for the exposures $\tilde w = E^T w$ to the eigenvectors E
+/// of the covariance with the eigenvalues λ, in decreasing order of the eigenvalues.
+pub fn principal_risk_contributions(
+    covariance: &[Vec<f64>],
+    weights: &[f64],
+) -> Result<Vec<f64>, RiskError> {
+    portfolio_variance(covariance, weights)?;
+    let (eigenvalues, eigenvectors) = symmetric_eigen(covariance);
+    let contributions: Vec<f64> = eigenvalues
+        .iter()
+        .enumerate()
+        .map(|(k, lambda)| {
+            let exposure: f64 = weights
+                .iter()
+                .zip(&eigenvectors)
+                .map(|(w, row)| w * row[k])
+                .sum();
+            lambda.max(0.0) * exposure * exposure
+        })
+        .collect();
+    let variance: f64 = contributions.iter().sum();
+    if variance <= 0.0 {
+        return Err(RiskError::ZeroDivision);
+    }
+    Ok(contributions.iter().map(|c| c / variance).collect())
+}
+
+/// The effective number of (uncorrelated) bets $\exp(-\sum_k p_k \ln p_k)$ of the portfolio, the
+/// exponential entropy of its principal risk contributions (Meucci), between 1 for a single source
+/// of risk and n for risk spread evenly over all principal portfolios.
+pub fn effective_number_of_bets(
+    covariance: &[Vec<f64>],
+    weights: &[f64],
+) -> Result<f64, RiskError> {
+    let entropy: f64 = principal_risk_contributions(covariance, weights)?
+        .iter()
+        .filter(|p| **p > 0.0)
+        .map(|p| -p * p.ln())
+        .sum();
+    Ok(entropy.exp())
+}
+
+/// The variance $w^T \Sigma w$ of the portfolio, which fails for a covariance not of the dimension of
+/// the weights and for a portfolio without risk.
+fn portfolio_variance(covariance: &[Vec<f64>], weights: &[f64]) -> Result<f64, RiskError> {
+    if weights.is_empty() {
+        return Err(RiskError::EmptySample);
+    }
+    if covariance.len() != weights.len() || covariance.iter().any(|row| row.len() != weights.len())
+    {
+        return Err(RiskError::InvalidParameter(format!(
+            "covariance not of the dimension {} of the weights",
+            weights.len()
+        )));
+    }
+    if weights
+        .iter()
+        .chain(covariance.iter().flatten())
+        .any(|x| x.is_nan())
+    {
+        return Err(RiskError::NaNSample);
+    }
+    if let Some(i) = (0..weights.len()).find(|i| covariance[*i][*i] < 0.0) {
+        return Err(RiskError::InvalidParameter(format!(
+            "negative variance {} of asset {i}",
+            covariance[i][i]
+        )));
+    }
+    let variance: f64 = weights
+        .iter()
+        .zip(covariance)
+        .map(|(w, row)| w * row.iter().zip(weights).map(|(c, v)| c * v).sum::<f64>())
+        .sum();
+    if variance <= 0.0 {
+        return Err(RiskError::ZeroDivision);
+    }
+    Ok(variance)
+}
+
+/// Eigenvalues and eigenvectors (as columns) of a symmetric matrix by the cyclic Jacobi method,
+/// sorted by decreasing eigenvalue. See https://en.wikipedia.org/wiki/Jacobi_eigenvalue_algorithm
+fn symmetric_eigen(matrix: &[Vec<f64>]) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let n = matrix.len();
+    let mut a = matrix.to_vec();
+    let mut v: Vec<Vec<f64>> = (0..n)
+        .map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+        .collect();
+    let scale = a
+        .iter()
+        .flatten()
+        .map(|x| x * x)
+        .sum::<f64>()
+        .max(f64::MIN_POSITIVE);
+
+    for _sweep in 0..100 {
+        let off_diagonal: f64 = (0..n)
+            .flat_map(|p| (p + 1..n).map(move |q| (p, q)))
+            .map(|(p, q)| a[p][q].powi(2))
+            .sum();
+        if off_diagonal <= 1e-30 * scale {
+            break;
+        }
+
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[p][q] == 0.0 {
+                    continue;
+                }
+                // the rotation angle annihilating a[p][q]
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+
+                for row in a.iter_mut() {
+                    let (akp, akq) = (row[p], row[q]);
+                    row[p] = c * akp - s * akq;
+                    row[q] = s * akp + c * akq;
+                }
+                let (upper, lower) = a.split_at_mut(q);
+                for (apk, aqk) in upper[p].iter_mut().zip(lower[0].iter_mut()) {
+                    (*apk, *aqk) = (c * *apk - s * *aqk, s * *apk + c * *aqk);
+                }
+                for row in v.iter_mut() {
+                    let (vkp, vkq) = (row[p], row[q]);
+                    row[p] = c * vkp - s * vkq;
+                    row[q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|i, j| a[*j][*j].total_cmp(&a[*i][*i]));
+
+    let eigenvalues = order.iter().map(|i| a[*i][*i]).collect();
+    let eigenvectors = v
+        .iter()
+        .map(|row| order.iter().map(|i| row[*i]).collect())
+        .collect();
+    (eigenvalues, eigenvectors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64, tolerance: f64) {
+        assert!((a - b).abs() < tolerance, "{a} != {b}");
+    }
+
+    fn diagonal(n: usize, value: f64) -> Vec<Vec<f64>> {
+        (0..n)
+            .map(|i| (0..n).map(|j| if i == j { value } else { 0.0 }).collect())
+            .collect()
+    }
+
+    #[test]
+    fn independent_and_correlated_assets() {
+        let weights = vec![0.25; 4];
+        let independent = diagonal(4, 0.04);
+        assert_close(
+            diversification_ratio(&independent, &weights).unwrap(),
+            2.0,
+            1e-12,
+        );
+        assert_close(
+            effective_number_of_bets(&independent, &weights).unwrap(),
+            4.0,
+            1e-10,
+        );
+
+        let correlated = vec![vec![0.04; 4]; 4];
+        assert_close(
+            diversification_ratio(&correlated, &weights).unwrap(),
+            1.0,
+            1e-12,
+        );
+        assert_close(
+            effective_number_of_bets(&correlated, &weights).unwrap(),
+            1.0,
+            1e-6,
+        );
+
+        // a principal portfolio is a single bet, while mixing the assets spreads the risk
+        let covariance = vec![vec![0.04, 0.018], vec![0.018, 0.09]];
+        let (_, eigenvectors) = symmetric_eigen(&covariance);
+        let principal: Vec<f64> = eigenvectors.iter().map(|row| row[0]).collect();
+        assert_close(
+            effective_number_of_bets(&covariance, &principal).unwrap(),
+            1.0,
+            1e-10,
+        );
+        let mixed = effective_number_of_bets(&covariance, &[0.6, 0.4]).unwrap();
+        assert!(1.0 < mixed && mixed < 2.0);
+        let contributions = principal_risk_contributions(&covariance, &[0.6, 0.4]).unwrap();
+        assert_close(contributions.iter().sum(), 1.0, 1e-12);
+    }
+
+    #[test]
+    fn invalid_portfolios() {
+        let covariance = vec![vec![0.04, 0.018], vec![0.018, 0.09]];
+        assert!(matches!(
+            diversification_ratio(&covariance, &[0.5, 0.3, 0.2]),
+            Err(RiskError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            effective_number_of_bets(&[vec![0.04, 0.0], vec![0.0]], &[0.5, 0.5]),
+            Err(RiskError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            diversification_ratio(&covariance, &[]),
+            Err(RiskError::EmptySample)
+        ));
+        // portfolios without risk
+        assert!(matches!(
+            diversification_ratio(&covariance, &[0.0, 0.0]),
+            Err(RiskError::ZeroDivision)
+        ));
+        assert!(matches!(
+            effective_number_of_bets(&diagonal(2, 0.0), &[0.5, 0.5]),
+            Err(RiskError::ZeroDivision)
+        ));
+        assert!(matches!(
+            principal_risk_contributions(&covariance, &[f64::NAN, 0.5]),
+            Err(RiskError::NaNSample)
+        ));
+    }
+}
@@ -0,0 +1,171 @@
+use crate::error::RiskError;
+
+/// A drawdown from a peak to the trough and the recovery to the peak level, by indices of the series.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DrawdownPeriod {
+    pub peak: usize,
+    pub trough: usize,
+    /// None if the series has not recovered (yet)
+    pub recovery: Option<usize>,
+    /// the relative loss from the peak to the trough (non-negative)
+    pub depth: f64,
+}
+
+/// The drawdowns of a (positive) wealth series, e.g. the values of a portfolio or, relative to a
+/// benchmark, the ratios of the portfolio and the benchmark values.
+/// See https://en.wikipedia.org/wiki/Drawdown_(economics)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Drawdown {
+    /// the current drawdowns $V_t / max_{s \le t} V_s - 1$
+    underwater: Vec<f64>,
+}
+
+impl Drawdown {
+    pub fn from_values(values: &[f64]) -> Result<Self, RiskError> {
+        if values.is_empty() {
+            return Err(RiskError::EmptySample);
+        }
+        if values.iter().any(|v| v.is_nan()) {
+            return Err(RiskError::NaNSample);
+        }
+        if values.iter().any(|v| *v <= 0.0) {
+            return Err(RiskError::InvalidParameter(
+                "values must be positive".to_string(),
+            ));
+        }
+        let mut peak = f64::NEG_INFINITY;
+        let underwater = values
+            .iter()
+            .map(|v| {
+                peak = peak.max(*v);
+                v / peak - 1.0
+            })
+            .collect();
+        Ok(Self { underwater })
+    }
+
+    /// The drawdowns of the wealth compounded from the period returns, starting at 1.
+    pub fn from_returns(returns: &[f64]) -> Result<Self, RiskError> {
+        let mut wealth = 1.0;
+        let values: Vec<f64> = std::iter::once(1.0)
+            .chain(returns.iter().map(|r| {
+                wealth *= 1.0 + r;
+                wealth
+            }))
+            .collect();
+        Self::from_values(&values)
+    }
+
+    /// The drawdowns of the relative wealth $V_t / B_t$ of the portfolio over the benchmark values,
+    /// i.e. of the compounded excess returns.
+    pub fn relative(values: &[f64], benchmark: &[f64]) -> Result<Self, RiskError> {
+        if values.len() != benchmark.len() {
+            return Err(RiskError::InvalidParameter(
+                "values and benchmark differ in length".to_string(),
+            ));
+        }
+        if benchmark.iter().any(|b| *b <= 0.0) {
+            return Err(RiskError::InvalidParameter(
+                "benchmark values must be positive".to_string(),
+            ));
+        }
+        let ratios: Vec<f64> = values.iter().zip(benchmark).map(|(v, b)| v / b).collect();
+        Self::from_values(&ratios)
+    }
+
+    /// The underwater curve, i.e. the time series of the current drawdowns (non-positive).
+    pub fn underwater(&self) -> &[f64] {
+        &self.underwater
+    }
+
+    /// The maximal drawdown as a (non-negative) fraction of the peak.
+    pub fn max_drawdown(&self) -> f64 {
+        -self.underwater.iter().copied().fold(0.0, f64::min)
+    }
+
+    /// The current drawdown at the end of the series (non-negative).
+    pub fn current_drawdown(&self) -> f64 {
+        -self.underwater.last().copied().unwrap_or_default()
+    }
+
+    /// The drawdown periods in chronological order, each from a peak until its recovery.
+    pub fn periods(&self) -> Vec<DrawdownPeriod> {
+        let mut periods = Vec::new();
+        let mut current: Option<DrawdownPeriod> = None;
+        for (t, dd) in self.underwater.iter().enumerate() {
+            match current.as_mut() {
+                None if *dd < 0.0 => {
+                    current = Some(DrawdownPeriod {
+                        peak: t - 1,
+                        trough: t,
+                        recovery: None,
+                        depth: -dd,
+                    })
+                }
+                Some(period) if *dd == 0.0 => {
+                    period.recovery = Some(t);
+                    periods.push(*period);
+                    current = None;
+                }
+                Some(period) if -dd > period.depth => {
+                    period.trough = t;
+                    period.depth = -dd;
+                }
+                _ => {}
+            }
+        }
+        periods.extend(current);
+        periods
+    }
+
+    /// The longest time (in periods) spent below a previous peak, including an ongoing drawdown.
+    pub fn max_duration(&self) -> usize {
+        let end = self.underwater.len() - 1;
+        self.periods()
+            .iter()
+            .map(|p| p.recovery.unwrap_or(end) - p.peak)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn absolute_drawdown() {
+        let values = [100.0, 110.0, 99.0, 88.0, 110.0, 120.0, 108.0];
+        let drawdown = Drawdown::from_values(&values).unwrap();
+        let underwater = drawdown.underwater();
+        assert_eq!(underwater[1], 0.0);
+        assert!((underwater[3] + 0.2).abs() < 1e-15);
+        assert!((drawdown.max_drawdown() - 0.2).abs() < 1e-15);
+        assert!((drawdown.current_drawdown() - 0.1).abs() < 1e-15);
+
+        let periods = drawdown.periods();
+        assert_eq!(periods.len(), 2);
+        assert_eq!(
+            (periods[0].peak, periods[0].trough, periods[0].recovery),
+            (1, 3, Some(4))
+        );
+        assert_eq!(periods[1].recovery, None);
+        assert_eq!(drawdown.max_duration(), 3);
+
+        let from_returns = Drawdown::from_returns(&[0.1, -0.1, -1.0 / 9.0, 0.25]).unwrap();
+        assert!((from_returns.max_drawdown() - 0.2).abs() < 1e-15);
+        assert!(Drawdown::from_values(&[]).is_err());
+    }
+
+    #[test]
+    fn benchmark_relative_drawdown() {
+        // the portfolio rises but lags the benchmark in the middle
+        let values = [100.0, 105.0, 110.0, 121.0];
+        let benchmark = [100.0, 100.0, 125.0, 110.0];
+        assert_eq!(Drawdown::from_values(&values).unwrap().max_drawdown(), 0.0);
+        let relative = Drawdown::relative(&values, &benchmark).unwrap();
+        assert!((relative.max_drawdown() - (1.0 - 0.88 / 1.05)).abs() < 1e-15);
+        assert_eq!(relative.periods()[0].recovery, Some(3));
+        assert!(Drawdown::relative(&values, &benchmark[1..]).is_err());
+    }
+}
@@ -4,4 +4,12 @@ use thiserror::Error;
 pub enum RiskError {
     #[error("division by 0")]
     ZeroDivision,
+    #[error("empty sample")]
+    EmptySample,
+    #[error("sample contains NaN values")]
+    NaNSample,
+    #[error("confidence level {0} not in (0, 1)")]
+    InvalidLevel(f64),
+    #[error("invalid parameter: {0}")]
+    InvalidParameter(String),
 }
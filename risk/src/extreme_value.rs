@@ -0,0 +1,283 @@
+use crate::error::RiskError;
+use crate::value_at_risk::{check_level, EmpiricalLosses};
+
+/// The minimal number of exceedances for a tail fit.
+const MIN_EXCEEDANCES: usize = 10;
+
+/// Generalized Pareto Distribution of the excesses $y = x - u$ over the threshold $u$, with cdf
+/// '''math
+/// G(y) = 1 - (1 + \xi y / \sigma)^{-1 / \xi}
+/// ''', and $G(y) = 1 - e^{-y / \sigma}$ for $\xi = 0$.
+/// See https://en.wikipedia.org/wiki/Generalized_Pareto_distribution
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GeneralizedPareto {
+    pub shape: f64,
+    pub scale: f64,
+}
+
+impl GeneralizedPareto {
+    pub fn new(shape: f64, scale: f64) -> Self {
+        assert!(scale > 0.0);
+        Self { shape, scale }
+    }
+
+    /// Probability weighted moments estimator of Hosking and Wallis (1987), which exists for $\xi < 1$,
+    /// from the excesses in ascending order.
+    pub fn fit(sorted_excesses: &[f64]) -> Result<Self, RiskError> {
+        let n = sorted_excesses.len();
+        if n < 2 {
+            return Err(RiskError::EmptySample);
+        }
+        let a0 = sorted_excesses.iter().sum::<f64>() / n as f64;
+        let a1 = sorted_excesses
+            .iter()
+            .enumerate()
+            .map(|(i, y)| y * (n - 1 - i) as f64 / (n - 1) as f64)
+            .sum::<f64>()
+            / n as f64;
+        let denominator = a0 - 2.0 * a1;
+        if denominator <= 0.0 {
+            return Err(RiskError::ZeroDivision);
+        }
+        Ok(Self::new(
+            2.0 - a0 / denominator,
+            2.0 * a0 * a1 / denominator,
+        ))
+    }
+
+    pub fn cdf(&self, y: f64) -> f64 {
+        if y <= 0.0 {
+            return 0.0;
+        }
+        if self.shape == 0.0 {
+            return 1.0 - (-y / self.scale).exp();
+        }
+        let base = 1.0 + self.shape * y / self.scale;
+        if base <= 0.0 {
+            // beyond the upper end point for a negative shape
+            return 1.0;
+        }
+        1.0 - base.powf(-1.0 / self.shape)
+    }
+
+    /// The excess $y$ with survival probability $P(Y > y) = p$.
+    fn excess_with_survival(&self, p: f64) -> f64 {
+        if self.shape == 0.0 {
+            -self.scale * p.ln()
+        } else {
+            self.scale / self.shape * (p.powf(-self.shape) - 1.0)
+        }
+    }
+}
+
+/// Peaks-over-threshold tail model: the losses beyond the threshold follow a GPD, which extrapolates
+/// VaR and ES to confidence levels beyond the historical sample, e.g. 99.9%.
+/// See https://en.wikipedia.org/wiki/Peaks_over_threshold
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GpdTail {
+    pub threshold: f64,
+    pub distribution: GeneralizedPareto,
+    pub nr_exceedances: usize,
+    pub nr_samples: usize,
+}
+
+impl GpdTail {
+    /// Fits the GPD to the losses strictly above the threshold.
+    pub fn fit(losses: &EmpiricalLosses, threshold: f64) -> Result<Self, RiskError> {
+        let excesses = excesses(losses, threshold);
+        if excesses.len() < MIN_EXCEEDANCES {
+            return Err(RiskError::InvalidParameter(format!(
+                "{} exceedances of the threshold {threshold}, at least {MIN_EXCEEDANCES} required",
+                excesses.len()
+            )));
+        }
+        Ok(Self {
+            threshold,
+            distribution: GeneralizedPareto::fit(&excesses)?,
+            nr_exceedances: excesses.len(),
+            nr_samples: losses.len(),
+        })
+    }
+
+    /// Fits the GPD at the lowest candidate threshold whose fit is not rejected by the
+    /// Kolmogorov-Smirnov test at 5%, or else at the best fitting candidate, see `threshold_diagnostics`.
+    pub fn fit_automatic(losses: &EmpiricalLosses) -> Result<Self, RiskError> {
+        let candidate_levels: Vec<f64> = (0..19).map(|i| 0.80 + 0.01 * i as f64).collect();
+        let diagnostics = threshold_diagnostics(losses, &candidate_levels)?;
+        let selected = diagnostics
+            .iter()
+            .find(|d| d.ks_statistic < 1.36 / (d.nr_exceedances as f64).sqrt())
+            .or_else(|| {
+                diagnostics.iter().min_by(|a, b| {
+                    let scaled =
+                        |d: &ThresholdDiagnostic| d.ks_statistic * (d.nr_exceedances as f64).sqrt();
+                    scaled(a).total_cmp(&scaled(b))
+                })
+            })
+            .ok_or(RiskError::EmptySample)?;
+        Self::fit(losses, selected.threshold)
+    }
+
+    /// The probability of a loss beyond the threshold.
+    pub fn exceedance_probability(&self) -> f64 {
+        self.nr_exceedances as f64 / self.nr_samples as f64
+    }
+
+    /// The VaR at the confidence level, which must lie in the tail beyond the threshold,
+    /// '''math
+    /// VaR_q = u + \sigma / \xi ((n / N_u (1 - q))^{-\xi} - 1)
+    /// '''
+    pub fn value_at_risk(&self, level: f64) -> Result<f64, RiskError> {
+        check_level(level)?;
+        let tail_probability = (1.0 - level) / self.exceedance_probability();
+        if tail_probability > 1.0 {
+            return Err(RiskError::InvalidLevel(level));
+        }
+        Ok(self.threshold + self.distribution.excess_with_survival(tail_probability))
+    }
+
+    /// The ES at the confidence level, which is finite for a shape $\xi < 1$,
+    /// '''math
+    /// ES_q = VaR_q / (1 - \xi) + (\sigma - \xi u) / (1 - \xi)
+    /// '''
+    pub fn expected_shortfall(&self, level: f64) -> Result<f64, RiskError> {
+        let value_at_risk = self.value_at_risk(level)?;
+        let GeneralizedPareto { shape, scale } = self.distribution;
+        if shape >= 1.0 {
+            return Err(RiskError::InvalidParameter(format!(
+                "infinite expected shortfall for the shape {shape}"
+            )));
+        }
+        Ok((value_at_risk + scale - shape * self.threshold) / (1.0 - shape))
+    }
+}
+
+/// Diagnostics of a candidate threshold: a stable shape and a linear mean excess function above
+/// the threshold indicate a good GPD fit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThresholdDiagnostic {
+    /// the confidence level of the empirical quantile used as threshold
+    pub level: f64,
+    pub threshold: f64,
+    pub nr_exceedances: usize,
+    /// the mean excess $e(u) = E[X - u | X > u]$
+    pub mean_excess: f64,
+    pub shape: f64,
+    pub scale: f64,
+    /// the Kolmogorov-Smirnov distance of the excesses to the fitted GPD
+    pub ks_statistic: f64,
+}
+
+/// The diagnostics of the thresholds at the empirical quantiles of the levels, skipping the ones
+/// with too few exceedances or without a fit.
+pub fn threshold_diagnostics(
+    losses: &EmpiricalLosses,
+    levels: &[f64],
+) -> Result<Vec<ThresholdDiagnostic>, RiskError> {
+    let mut diagnostics = Vec::with_capacity(levels.len());
+    for level in levels {
+        let threshold = losses.quantile(*level)?;
+        let excesses = excesses(losses, threshold);
+        if excesses.len() < MIN_EXCEEDANCES {
+            continue;
+        }
+        let Ok(distribution) = GeneralizedPareto::fit(&excesses) else {
+            continue;
+        };
+        let n = excesses.len() as f64;
+        let ks_statistic = excesses
+            .iter()
+            .enumerate()
+            .map(|(i, y)| {
+                let cdf = distribution.cdf(*y);
+                (cdf - i as f64 / n).max((i + 1) as f64 / n - cdf)
+            })
+            .fold(0.0, f64::max);
+        diagnostics.push(ThresholdDiagnostic {
+            level: *level,
+            threshold,
+            nr_exceedances: excesses.len(),
+            mean_excess: excesses.iter().sum::<f64>() / n,
+            shape: distribution.shape,
+            scale: distribution.scale,
+            ks_statistic,
+        });
+    }
+    Ok(diagnostics)
+}
+
+/// The excesses of the losses strictly above the threshold in ascending order.
+fn excesses(losses: &EmpiricalLosses, threshold: f64) -> Vec<f64> {
+    losses
+        .sorted_losses()
+        .iter()
+        .filter(|l| **l > threshold)
+        .map(|l| l - threshold)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    /// Losses following the GPD of shape 0.25 and scale 1 over the threshold 0.
+    fn pareto_losses(nr_samples: usize) -> EmpiricalLosses {
+        let gpd = GeneralizedPareto::new(0.25, 1.0);
+        let mut rng = rand_hc::Hc128Rng::seed_from_u64(11);
+        let losses = (0..nr_samples)
+            .map(|_| gpd.excess_with_survival(1.0 - rng.gen::<f64>()))
+            .collect();
+        EmpiricalLosses::from_losses(losses).unwrap()
+    }
+
+    #[test]
+    fn gpd_fit() {
+        let losses = pareto_losses(20_000);
+        let gpd = GeneralizedPareto::fit(losses.sorted_losses()).unwrap();
+        assert!((gpd.shape - 0.25).abs() < 0.05);
+        assert!((gpd.scale - 1.0).abs() < 0.05);
+        assert!((gpd.cdf(gpd.excess_with_survival(0.1)) - 0.9).abs() < 1e-12);
+    }
+
+    #[test]
+    fn tail_extrapolation() {
+        let losses = pareto_losses(20_000);
+        let threshold = losses.quantile(0.9).unwrap();
+        let tail = GpdTail::fit(&losses, threshold).unwrap();
+        assert_eq!(tail.nr_exceedances, 2_000);
+        assert!((tail.exceedance_probability() - 0.1).abs() < 1e-12);
+
+        // the exact tail of the generating distribution
+        let gpd = GeneralizedPareto::new(0.25, 1.0);
+        let level: f64 = 0.99999;
+        let exact_var = gpd.excess_with_survival(1.0 - level);
+        let exact_es = (exact_var + 1.0) / 0.75;
+        let var = tail.value_at_risk(level).unwrap();
+        assert!((var / exact_var - 1.0).abs() < 0.15);
+        assert!((tail.expected_shortfall(level).unwrap() / exact_es - 1.0).abs() < 0.2);
+
+        // within the sample, the tail model agrees with the historical simulation
+        let empirical = losses.value_at_risk(0.99).unwrap();
+        assert!((tail.value_at_risk(0.99).unwrap() / empirical - 1.0).abs() < 0.1);
+
+        // levels below the threshold are not covered by the tail
+        assert!(tail.value_at_risk(0.8).is_err());
+        assert!(GpdTail::fit(&losses, losses.quantile(0.9996).unwrap()).is_err());
+    }
+
+    #[test]
+    fn automatic_threshold() {
+        let losses = pareto_losses(5_000);
+        let diagnostics = threshold_diagnostics(&losses, &[0.9, 0.95, 0.999]).unwrap();
+        // too few exceedances for the last level
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics[0].threshold < diagnostics[1].threshold);
+        // the mean excess of a GPD increases linearly with slope shape / (1 - shape)
+        assert!(diagnostics[0].mean_excess < diagnostics[1].mean_excess);
+
+        let tail = GpdTail::fit_automatic(&losses).unwrap();
+        assert!((tail.distribution.shape - 0.25).abs() < 0.15);
+        assert!(tail.exceedance_probability() <= 0.2 + 1e-12);
+    }
+}
@@ -1,5 +1,21 @@
 #[cfg(feature = "big-decimal")]
 extern crate bigdecimal;
 
+#[cfg(feature = "bootstrap")]
+pub mod bootstrap;
+pub mod cornish_fisher;
+pub mod distortion;
+pub mod diversification;
+pub mod drawdown;
 mod error;
+pub mod extreme_value;
+pub mod performance;
+pub mod prelude;
+pub mod realized_volatility;
 pub mod risk_figures;
+pub mod robust_estimators;
+pub mod sharpe_significance;
+pub mod turnover;
+pub mod value_at_risk;
+
+pub use crate::error::RiskError;
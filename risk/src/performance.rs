@@ -0,0 +1,232 @@
+use crate::error::RiskError;
+
+/// The valuation of a position at the end of a period (time in years), with the external cashflow
+/// into the position during the period, e.g. a deposit (positive) or a withdrawal (negative),
+/// assumed at the start of the period.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Valuation {
+    pub time: f64,
+    pub value: f64,
+    pub cashflow: f64,
+}
+
+impl Valuation {
+    pub fn new(time: f64, value: f64, cashflow: f64) -> Self {
+        Self {
+            time,
+            value,
+            cashflow,
+        }
+    }
+}
+
+/// The history of a position from the initial value at time 0 by its valuations, in increasing time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PositionHistory {
+    initial_value: f64,
+    valuations: Vec<Valuation>,
+}
+
+impl PositionHistory {
+    pub fn new(initial_value: f64, valuations: Vec<Valuation>) -> Result<Self, RiskError> {
+        if valuations.is_empty() {
+            return Err(RiskError::EmptySample);
+        }
+        if valuations
+            .iter()
+            .any(|v| v.time.is_nan() || v.value.is_nan() || v.cashflow.is_nan())
+        {
+            return Err(RiskError::NaNSample);
+        }
+        if valuations[0].time <= 0.0 || !valuations.windows(2).all(|w| w[0].time < w[1].time) {
+            return Err(RiskError::InvalidParameter(
+                "valuation times must increase from 0".to_string(),
+            ));
+        }
+        Ok(Self {
+            initial_value,
+            valuations,
+        })
+    }
+
+    /// The returns of the periods $r_k = V_k / (V_{k-1} + C_k) - 1$, which are free of the cashflows.
+    pub fn period_returns(&self) -> Result<Vec<f64>, RiskError> {
+        let mut previous = self.initial_value;
+        self.valuations
+            .iter()
+            .map(|v| {
+                let invested = previous + v.cashflow;
+                if invested == 0.0 {
+                    return Err(RiskError::ZeroDivision);
+                }
+                previous = v.value;
+                Ok(v.value / invested - 1.0)
+            })
+            .collect()
+    }
+
+    /// The time-weighted return $\prod_k (1 + r_k) - 1$ of the whole history by geometric linking.
+    /// See https://en.wikipedia.org/wiki/Time-weighted_return
+    pub fn time_weighted_return(&self) -> Result<f64, RiskError> {
+        Ok(link_returns(&self.period_returns()?))
+    }
+
+    /// The annualized time-weighted return.
+    pub fn annualized_time_weighted_return(&self) -> Result<f64, RiskError> {
+        let horizon = self.valuations.last().unwrap().time;
+        Ok((1.0 + self.time_weighted_return()?).powf(1.0 / horizon) - 1.0)
+    }
+
+    /// The (annual) money-weighted return, i.e. the internal rate of return of the initial value and the
+    /// cashflows as investments and the final value as proceeds.
+    /// See https://en.wikipedia.org/wiki/Money-weighted_return
+    pub fn money_weighted_return(&self) -> Result<f64, RiskError> {
+        // the cashflow of a period is invested at the previous valuation time
+        let mut cashflows = vec![(0.0, -self.initial_value)];
+        let mut previous_time = 0.0;
+        for v in &self.valuations {
+            cashflows.push((previous_time, -v.cashflow));
+            previous_time = v.time;
+        }
+        let last = self.valuations.last().unwrap();
+        cashflows.push((last.time, last.value));
+        internal_rate_of_return(&cashflows)
+    }
+}
+
+/// The return of consecutive periods by geometric linking $\prod_k (1 + r_k) - 1$.
+pub fn link_returns(returns: &[f64]) -> f64 {
+    returns.iter().map(|r| 1.0 + r).product::<f64>() - 1.0
+}
+
+/// The geometric mean return $(\prod_k (1 + r_k))^{1/n} - 1$ per period, which compounds to the linked
+/// return unlike the arithmetic mean.
+pub fn geometric_mean_return(returns: &[f64]) -> Result<f64, RiskError> {
+    if returns.is_empty() {
+        return Err(RiskError::EmptySample);
+    }
+    if returns.iter().any(|r| *r <= -1.0) {
+        return Err(RiskError::InvalidParameter(
+            "returns must exceed -100%".to_string(),
+        ));
+    }
+    let mean_log = returns.iter().map(|r| r.ln_1p()).sum::<f64>() / returns.len() as f64;
+    Ok(mean_log.exp_m1())
+}
+
+/// The annual rate r with $\sum_i C_i (1 + r)^{-t_i} = 0$ for the cashflows $(t_i, C_i)$, found by
+/// bisection on the bracket of the sign change of the net present value in (-100%, 1000%].
+/// See https://en.wikipedia.org/wiki/Internal_rate_of_return
+pub fn internal_rate_of_return(cashflows: &[(f64, f64)]) -> Result<f64, RiskError> {
+    if cashflows.is_empty() {
+        return Err(RiskError::EmptySample);
+    }
+    let has_sign_change =
+        cashflows.iter().any(|(_, c)| *c > 0.0) && cashflows.iter().any(|(_, c)| *c < 0.0);
+    if !has_sign_change {
+        return Err(RiskError::InvalidParameter(
+            "cashflows need both signs for an internal rate of return".to_string(),
+        ));
+    }
+    let npv = |rate: f64| -> f64 {
+        cashflows
+            .iter()
+            .map(|(t, c)| c * (1.0 + rate).powf(-t))
+            .sum()
+    };
+
+    // scan for the first sign change from the upper end, where the npv tends to the cashflows at t = 0
+    const GRID: [f64; 12] = [
+        10.0, 5.0, 2.0, 1.0, 0.5, 0.2, 0.1, 0.0, -0.2, -0.5, -0.8, -0.99,
+    ];
+    let (mut lower, mut upper) = GRID
+        .windows(2)
+        .map(|w| (w[1], w[0]))
+        .find(|(l, u)| npv(*l) * npv(*u) <= 0.0)
+        .ok_or_else(|| {
+            RiskError::InvalidParameter("no internal rate of return in (-99%, 1000%]".to_string())
+        })?;
+
+    let f_lower = npv(lower);
+    for _ in 0..200 {
+        let mid = 0.5 * (lower + upper);
+        let f_mid = npv(mid);
+        if f_mid == 0.0 || upper - lower < 1e-14 {
+            return Ok(mid);
+        }
+        if f_mid.signum() == f_lower.signum() {
+            lower = mid;
+        } else {
+            upper = mid;
+        }
+    }
+    Ok(0.5 * (lower + upper))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linking() {
+        let returns = [0.1, -0.1, 0.05];
+        let linked = link_returns(&returns);
+        assert!((linked - (1.1 * 0.9 * 1.05 - 1.0)).abs() < 1e-15);
+        let g = geometric_mean_return(&returns).unwrap();
+        assert!(((1.0 + g).powi(3) - 1.0 - linked).abs() < 1e-14);
+        // the arithmetic mean overstates the compounded return
+        assert!(g < returns.iter().sum::<f64>() / 3.0);
+        assert!(geometric_mean_return(&[-1.0]).is_err());
+    }
+
+    #[test]
+    fn time_and_money_weighted_returns() {
+        // +10% in the first year, a deposit of 100 and -10% in the second year
+        let history = PositionHistory::new(
+            100.0,
+            vec![
+                Valuation::new(1.0, 110.0, 0.0),
+                Valuation::new(2.0, 189.0, 100.0),
+            ],
+        )
+        .unwrap();
+        let returns = history.period_returns().unwrap();
+        assert!((returns[0] - 0.1).abs() < 1e-15);
+        assert!((returns[1] + 0.1).abs() < 1e-15);
+        let twr = history.time_weighted_return().unwrap();
+        assert!((twr - (1.1 * 0.9 - 1.0)).abs() < 1e-15);
+        assert!(
+            (history.annualized_time_weighted_return().unwrap() - 0.99_f64.sqrt() + 1.0).abs()
+                < 1e-14
+        );
+
+        // more money was invested in the losing year, such that the money-weighted return is lower
+        let mwr = history.money_weighted_return().unwrap();
+        let npv = -100.0 - 100.0 / (1.0 + mwr) + 189.0 / (1.0 + mwr).powi(2);
+        assert!(npv.abs() < 1e-9);
+        assert!(mwr < history.annualized_time_weighted_return().unwrap());
+
+        // without cashflows both agree
+        let history = PositionHistory::new(
+            100.0,
+            vec![
+                Valuation::new(1.0, 105.0, 0.0),
+                Valuation::new(2.0, 110.25, 0.0),
+            ],
+        )
+        .unwrap();
+        assert!((history.money_weighted_return().unwrap() - 0.05).abs() < 1e-12);
+        assert!((history.annualized_time_weighted_return().unwrap() - 0.05).abs() < 1e-12);
+    }
+
+    #[test]
+    fn irr_solver() {
+        // a bond with 5% annual coupons bought at par
+        let bond = [(0.0, -100.0), (1.0, 5.0), (2.0, 5.0), (3.0, 105.0)];
+        assert!((internal_rate_of_return(&bond).unwrap() - 0.05).abs() < 1e-12);
+        let loss = [(0.0, -100.0), (1.0, 50.0)];
+        assert!((internal_rate_of_return(&loss).unwrap() + 0.5).abs() < 1e-12);
+        assert!(internal_rate_of_return(&[(0.0, 100.0), (1.0, 5.0)]).is_err());
+        assert!(PositionHistory::new(100.0, vec![]).is_err());
+    }
+}
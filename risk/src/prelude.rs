@@ -0,0 +1,15 @@
+//! The commonly used types and functions of the crate in one import, `use risk::prelude::*;`.
+
+#[cfg(feature = "bootstrap")]
+pub use crate::bootstrap::{BlockBootstrap, BlockScheme, IntervalEstimate, ReturnStatistic};
+pub use crate::cornish_fisher::CornishFisher;
+pub use crate::distortion::{distortion_risk_measure, Distortion};
+pub use crate::diversification::{diversification_ratio, effective_number_of_bets};
+pub use crate::drawdown::Drawdown;
+pub use crate::error::RiskError;
+pub use crate::extreme_value::{GeneralizedPareto, GpdTail};
+pub use crate::performance::{internal_rate_of_return, PositionHistory};
+pub use crate::risk_figures::{information_ratio, sharpe_ratio};
+pub use crate::sharpe_significance::SharpeRatioSignificance;
+pub use crate::turnover::{RebalancingHistory, TransactionCosts};
+pub use crate::value_at_risk::EmpiricalLosses;
@@ -0,0 +1,205 @@
+use std::f64::consts::PI;
+
+use crate::error::RiskError;
+
+/// The weight function of a realized kernel on [0, 1].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RealizedKernelType {
+    /// $k(x) = 1 - x$
+    Bartlett,
+    /// $k(x) = 1 - 6x^2 + 6x^3$ for $x \le 1/2$ and $2(1 - x)^3$ else, whose Fourier transform is
+    /// non-negative, such that the kernel is positive semi-definite and the estimates are non-negative
+    Parzen,
+}
+
+impl RealizedKernelType {
+    fn weight(&self, x: f64) -> f64 {
+        match self {
+            Self::Bartlett => 1.0 - x,
+            Self::Parzen if x <= 0.5 => 1.0 - 6.0 * x * x + 6.0 * x * x * x,
+            Self::Parzen => 2.0 * (1.0 - x).powi(3),
+        }
+    }
+}
+
+/// The log returns of the intraday prices of a single day (or any other period), whose realized
+/// measures estimate the integrated variance of the period.
+/// See https://en.wikipedia.org/wiki/Realized_variance
+#[derive(Clone, Debug, PartialEq)]
+pub struct IntradayReturns {
+    returns: Vec<f64>,
+}
+
+impl IntradayReturns {
+    /// The log returns of consecutive (positive) prices.
+    pub fn from_prices(prices: &[f64]) -> Result<Self, RiskError> {
+        if prices.len() < 2 {
+            return Err(RiskError::EmptySample);
+        }
+        if prices.iter().any(|p| p.is_nan()) {
+            return Err(RiskError::NaNSample);
+        }
+        if prices.iter().any(|p| *p <= 0.0) {
+            return Err(RiskError::InvalidParameter(
+                "prices must be positive".to_string(),
+            ));
+        }
+        let returns = prices.windows(2).map(|w| (w[1] / w[0]).ln()).collect();
+        Ok(Self { returns })
+    }
+
+    pub fn returns(&self) -> &[f64] {
+        &self.returns
+    }
+
+    pub fn len(&self) -> usize {
+        self.returns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.returns.is_empty()
+    }
+
+    /// The realized variance $\sum_i r_i^2$, which is biased by microstructure noise at high frequencies.
+    pub fn realized_variance(&self) -> f64 {
+        self.autocovariance(0)
+    }
+
+    /// The bipower variation
+    /// '''math
+    /// \frac{\pi}{2} \frac{n}{n - 1} \sum_{i=2}^n |r_i| |r_{i-1}|
+    /// ''', which is robust to jumps.
+    /// See https://en.wikipedia.org/wiki/Bipower_variation
+    pub fn bipower_variation(&self) -> Result<f64, RiskError> {
+        let n = self.returns.len();
+        if n < 2 {
+            return Err(RiskError::ZeroDivision);
+        }
+        let sum: f64 = self
+            .returns
+            .windows(2)
+            .map(|w| w[0].abs() * w[1].abs())
+            .sum();
+        Ok(PI / 2.0 * n as f64 / (n - 1) as f64 * sum)
+    }
+
+    /// The jump contribution $max(RV - BV, 0)$ to the realized variance.
+    pub fn jump_variation(&self) -> Result<f64, RiskError> {
+        Ok((self.realized_variance() - self.bipower_variation()?).max(0.0))
+    }
+
+    /// The realized autocovariance $\gamma_h = \sum_i r_i r_{i-h}$.
+    fn autocovariance(&self, lag: usize) -> f64 {
+        self.returns[lag..]
+            .iter()
+            .zip(&self.returns)
+            .map(|(r, r_lagged)| r * r_lagged)
+            .sum()
+    }
+
+    /// The realized kernel
+    /// '''math
+    /// \gamma_0 + 2 \sum_{h=1}^H k(\frac{h - 1}{H}) \gamma_h
+    /// ''' with the bandwidth H, which corrects the realized variance for the autocorrelation of the
+    /// returns induced by i.i.d. microstructure noise.
+    /// See Barndorff-Nielsen et al., Designing realized kernels to measure the ex post variation of equity prices in the presence of noise (2008)
+    pub fn realized_kernel(
+        &self,
+        kernel: RealizedKernelType,
+        bandwidth: usize,
+    ) -> Result<f64, RiskError> {
+        if bandwidth == 0 || bandwidth >= self.returns.len() {
+            return Err(RiskError::InvalidParameter(format!(
+                "bandwidth {bandwidth} not in [1, {})",
+                self.returns.len()
+            )));
+        }
+        let h = bandwidth as f64;
+        let kernel_sum: f64 = (1..=bandwidth)
+            .map(|lag| kernel.weight((lag - 1) as f64 / h) * self.autocovariance(lag))
+            .sum();
+        Ok(self.autocovariance(0) + 2.0 * kernel_sum)
+    }
+
+    /// The estimate $RV / 2n$ of the variance of i.i.d. microstructure noise, which dominates the
+    /// realized variance of returns at high frequencies.
+    pub fn noise_variance(&self) -> f64 {
+        self.realized_variance() / (2.0 * self.returns.len() as f64)
+    }
+}
+
+/// The annualized volatility of the variance of a period, e.g. 252 periods for a daily realized variance.
+pub fn annualized_volatility(variance: f64, periods_per_year: f64) -> f64 {
+    (variance * periods_per_year).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    /// The intraday prices of a day with the daily vola, the jump and the noise of the log prices.
+    fn intraday_prices(nr_returns: usize, daily_vola: f64, jump: f64, noise: f64) -> Vec<f64> {
+        let mut rng = rand_hc::Hc128Rng::seed_from_u64(42);
+        let mut normal = || {
+            let (u1, u2): (f64, f64) = (rng.gen_range(f64::EPSILON..1.0), rng.gen());
+            (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+        };
+        let dt = 1.0 / nr_returns as f64;
+        let mut log_price = 100.0_f64.ln();
+        let mut prices = Vec::with_capacity(nr_returns + 1);
+        for i in 0..=nr_returns {
+            if i > 0 {
+                log_price += daily_vola * dt.sqrt() * normal();
+            }
+            if i == nr_returns / 2 {
+                log_price += jump;
+            }
+            prices.push((log_price + noise * normal()).exp());
+        }
+        prices
+    }
+
+    #[test]
+    fn realized_measures() {
+        let daily_vola = 0.02;
+        let returns =
+            IntradayReturns::from_prices(&intraday_prices(2_000, daily_vola, 0.0, 0.0)).unwrap();
+        assert_eq!(returns.len(), 2_000);
+        let rv = returns.realized_variance();
+        assert!((rv / daily_vola.powi(2) - 1.0).abs() < 0.1);
+        assert!((returns.bipower_variation().unwrap() / rv - 1.0).abs() < 0.1);
+        assert!((annualized_volatility(rv, 252.0) - daily_vola * 252.0_f64.sqrt()).abs() < 0.02);
+
+        // the bipower variation is robust to a jump
+        let jumpy =
+            IntradayReturns::from_prices(&intraday_prices(2_000, daily_vola, 0.02, 0.0)).unwrap();
+        let jump_variation = jumpy.jump_variation().unwrap();
+        assert!((jump_variation / 0.02_f64.powi(2) - 1.0).abs() < 0.2);
+        let bipower = returns.bipower_variation().unwrap();
+        assert!((jumpy.bipower_variation().unwrap() / bipower - 1.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn realized_kernel_under_noise() {
+        let (daily_vola, noise) = (0.02, 0.0005);
+        let returns =
+            IntradayReturns::from_prices(&intraday_prices(10_000, daily_vola, 0.0, noise)).unwrap();
+        // the noise adds 2 n noise^2 = 0.005 to the integrated variance of 0.0004
+        let rv = returns.realized_variance();
+        assert!(rv > 10.0 * daily_vola.powi(2));
+        assert!((returns.noise_variance() / noise.powi(2) - 1.0).abs() < 0.1);
+
+        for kernel in [RealizedKernelType::Parzen, RealizedKernelType::Bartlett] {
+            let rk = returns.realized_kernel(kernel, 50).unwrap();
+            assert!((rk / daily_vola.powi(2) - 1.0).abs() < 0.25);
+        }
+        assert!(returns
+            .realized_kernel(RealizedKernelType::Parzen, 0)
+            .is_err());
+        assert!(matches!(
+            IntradayReturns::from_prices(&[1.0, -1.0]),
+            Err(RiskError::InvalidParameter(_))
+        ));
+    }
+}
@@ -0,0 +1,219 @@
+use crate::error::RiskError;
+use crate::risk_figures::{information_ratio, sharpe_ratio};
+
+/// The consistency factor $1 / \Phi^{-1}(3/4)$ of the MAD for the standard deviation of normal samples.
+const MAD_SCALE: f64 = 1.482_602_218_505_602;
+
+/// The policy to estimate the location ('return') and the scale ('risk') of the returns in a ratio.
+/// The classical mean and standard deviation break down with a single outlier, the robust alternatives
+/// estimate the standard deviation for normal samples.
+/// See https://en.wikipedia.org/wiki/Robust_statistics
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EstimatorPolicy {
+    /// the mean and the sample standard deviation
+    Classical,
+    /// the median and the scaled median absolute deviation
+    MedianMad,
+    /// the mean and the standard deviation of the sample winsorized at the fraction on both tails
+    Trimmed { fraction: f64 },
+    /// the Huber M-estimator of the location with the tuning constant k (in units of the scale),
+    /// and the scaled median absolute deviation
+    Huber { k: f64 },
+}
+
+impl EstimatorPolicy {
+    /// The estimates of the location and the scale of the samples.
+    pub fn location_scale(&self, samples: &[f64]) -> Result<(f64, f64), RiskError> {
+        if samples.len() < 2 {
+            return Err(RiskError::EmptySample);
+        }
+        if samples.iter().any(|x| x.is_nan()) {
+            return Err(RiskError::NaNSample);
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+
+        match *self {
+            Self::Classical => Ok(mean_std(&sorted)),
+            Self::MedianMad => Ok(median_mad(&sorted)),
+            Self::Trimmed { fraction } => {
+                if !(0.0..0.5).contains(&fraction) {
+                    return Err(RiskError::InvalidParameter(format!(
+                        "trimming fraction {fraction} not in [0, 0.5)"
+                    )));
+                }
+                let n = sorted.len();
+                let cut = (fraction * n as f64).floor() as usize;
+                let trimmed = &sorted[cut..n - cut];
+                let mean = trimmed.iter().sum::<f64>() / trimmed.len() as f64;
+                let winsorized: Vec<f64> = sorted
+                    .iter()
+                    .map(|x| x.clamp(sorted[cut], sorted[n - 1 - cut]))
+                    .collect();
+                Ok((mean, mean_std(&winsorized).1))
+            }
+            Self::Huber { k } => {
+                if k <= 0.0 {
+                    return Err(RiskError::InvalidParameter(format!(
+                        "Huber constant {k} not positive"
+                    )));
+                }
+                let (median, scale) = median_mad(&sorted);
+                if scale == 0.0 {
+                    return Ok((median, scale));
+                }
+                Ok((huber_location(&sorted, median, k * scale), scale))
+            }
+        }
+    }
+}
+
+fn mean_std(samples: &[f64]) -> (f64, f64) {
+    let n = samples.len() as f64;
+    let mean = samples.iter().sum::<f64>() / n;
+    let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (mean, variance.sqrt())
+}
+
+fn median(sorted: &[f64]) -> f64 {
+    let n = sorted.len();
+    if n % 2 == 1 {
+        sorted[n / 2]
+    } else {
+        0.5 * (sorted[n / 2 - 1] + sorted[n / 2])
+    }
+}
+
+/// The median and the scaled median absolute deviation of the sorted samples.
+fn median_mad(sorted: &[f64]) -> (f64, f64) {
+    let m = median(sorted);
+    let mut deviations: Vec<f64> = sorted.iter().map(|x| (x - m).abs()).collect();
+    deviations.sort_by(f64::total_cmp);
+    (m, MAD_SCALE * median(&deviations))
+}
+
+/// The Huber M-estimate of the location by iteratively reweighted means, with the weights
+/// $min(1, c / |x - \mu|)$ for the threshold c.
+/// See https://en.wikipedia.org/wiki/Huber_loss
+fn huber_location(samples: &[f64], initial: f64, threshold: f64) -> f64 {
+    const MAX_ITERATIONS: usize = 100;
+    let mut location = initial;
+    for _ in 0..MAX_ITERATIONS {
+        let (weighted_sum, weights) = samples.iter().fold((0.0, 0.0), |(sum, weights), x| {
+            let distance = (x - location).abs();
+            let w = if distance <= threshold {
+                1.0
+            } else {
+                threshold / distance
+            };
+            (sum + w * x, weights + w)
+        });
+        let updated = weighted_sum / weights;
+        if (updated - location).abs() <= 1e-12 * threshold {
+            return updated;
+        }
+        location = updated;
+    }
+    location
+}
+
+/// The Sharpe ratio of the return samples, where the estimator policy determines the expected
+/// return and the risk. Use the threshold for the division by 'risk'.
+pub fn robust_sharpe_ratio(
+    returns: &[f64],
+    riskfree_rate: f64,
+    policy: EstimatorPolicy,
+    threshold: Option<f64>,
+) -> Result<f64, RiskError> {
+    let excess: Vec<f64> = returns.iter().map(|r| r - riskfree_rate).collect();
+    let (location, scale) = policy.location_scale(&excess)?;
+    sharpe_ratio(location + riskfree_rate, riskfree_rate, scale, threshold)
+}
+
+/// The information ratio of the asset over the benchmark return samples, where the estimator policy
+/// determines the location and scale of the excess returns. Use the threshold for the division by 'risk'.
+pub fn robust_information_ratio(
+    asset_returns: &[f64],
+    benchmark_returns: &[f64],
+    policy: EstimatorPolicy,
+    threshold: Option<f64>,
+) -> Result<f64, RiskError> {
+    if asset_returns.len() != benchmark_returns.len() {
+        return Err(RiskError::InvalidParameter(
+            "asset and benchmark returns differ in length".to_string(),
+        ));
+    }
+    let excess: Vec<f64> = asset_returns
+        .iter()
+        .zip(benchmark_returns)
+        .map(|(a, b)| a - b)
+        .collect();
+    let (location, scale) = policy.location_scale(&excess)?;
+    information_ratio(location, 0.0, scale, threshold)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICIES: [EstimatorPolicy; 4] = [
+        EstimatorPolicy::Classical,
+        EstimatorPolicy::MedianMad,
+        EstimatorPolicy::Trimmed { fraction: 0.05 },
+        EstimatorPolicy::Huber { k: 1.345 },
+    ];
+
+    /// Deterministic returns with mean 0.01 and a symmetric spread.
+    fn returns() -> Vec<f64> {
+        (0..100)
+            .map(|i| 0.01 + 0.02 * (((i * 37) % 100) as f64 / 99.0 - 0.5))
+            .collect()
+    }
+
+    #[test]
+    fn estimates_of_symmetric_samples() {
+        for policy in POLICIES {
+            let (location, scale) = policy.location_scale(&returns()).unwrap();
+            assert!((location - 0.01).abs() < 1e-12, "{policy:?}");
+            assert!(scale > 0.0045 && scale < 0.0075, "{policy:?} {scale}");
+        }
+        assert!(EstimatorPolicy::Trimmed { fraction: 0.5 }
+            .location_scale(&returns())
+            .is_err());
+        assert!(matches!(
+            EstimatorPolicy::MedianMad.location_scale(&[0.1]),
+            Err(RiskError::EmptySample)
+        ));
+    }
+
+    #[test]
+    fn ratios_robust_to_an_outlier() {
+        let clean = returns();
+        let mut outlier = clean.clone();
+        outlier[10] = -0.5;
+
+        for policy in POLICIES {
+            let before = robust_sharpe_ratio(&clean, 0.002, policy, None).unwrap();
+            let after = robust_sharpe_ratio(&outlier, 0.002, policy, None).unwrap();
+            let relative_change = (after / before - 1.0).abs();
+            match policy {
+                EstimatorPolicy::Classical => assert!(relative_change > 0.8),
+                _ => assert!(relative_change < 0.1, "{policy:?} {relative_change}"),
+            }
+        }
+
+        let benchmark = vec![0.002; 100];
+        assert_eq!(
+            robust_information_ratio(&outlier, &benchmark, EstimatorPolicy::MedianMad, None)
+                .unwrap(),
+            robust_sharpe_ratio(&outlier, 0.002, EstimatorPolicy::MedianMad, None).unwrap()
+        );
+        assert!(robust_information_ratio(
+            &outlier,
+            &benchmark[1..],
+            EstimatorPolicy::Classical,
+            None
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,178 @@
+use probability::distribution::{Distribution, Gaussian, Inverse};
+
+use crate::cornish_fisher::CornishFisher;
+use crate::error::RiskError;
+use crate::value_at_risk::check_level;
+
+/// The Euler-Mascheroni constant.
+const EULER_GAMMA: f64 = 0.577_215_664_901_532_9;
+
+/// The expected maximum $E[\max_n SR_n] \approx \sqrt{V} ((1 - \gamma) \Phi^{-1}(1 - 1/N) + \gamma \Phi^{-1}(1 - 1/(N e)))$
+/// of the Sharpe ratios of N independent trials without skill, whose Sharpe ratios have the variance V
+/// across the trials, and 0 for a single trial.
+pub fn expected_maximum_sharpe_ratio(nr_trials: usize, sharpe_variance: f64) -> f64 {
+    if nr_trials <= 1 {
+        return 0.0;
+    }
+    let normal = Gaussian::new(0.0, 1.0);
+    let n = nr_trials as f64;
+    sharpe_variance.sqrt()
+        * ((1.0 - EULER_GAMMA) * normal.inverse(1.0 - 1.0 / n)
+            + EULER_GAMMA * normal.inverse(1.0 - 1.0 / (n * std::f64::consts::E)))
+}
+
+/// The significance of the (per period, not annualized) Sharpe ratio of a backtest, whose estimation
+/// error grows with the skewness and the kurtosis of the returns (Bailey and López de Prado).
+/// See https://en.wikipedia.org/wiki/Sharpe_ratio
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SharpeRatioSignificance {
+    pub sharpe_ratio: f64,
+    pub skewness: f64,
+    /// the (non-excess) kurtosis, 3 for normal returns
+    pub kurtosis: f64,
+    pub nr_observations: usize,
+}
+
+impl SharpeRatioSignificance {
+    /// From the period returns and the risk-free rate per period, with the higher moments of the
+    /// Cornish-Fisher estimates.
+    pub fn from_returns(returns: &[f64], riskfree_rate: f64) -> Result<Self, RiskError> {
+        if returns.len() < 2 {
+            return Err(RiskError::EmptySample);
+        }
+        // the moments of the losses, i.e. of the negative returns
+        let moments = CornishFisher::from_pnl(returns)?;
+        Ok(Self {
+            sharpe_ratio: (-moments.mean - riskfree_rate) / moments.std_dev,
+            skewness: -moments.skewness,
+            kurtosis: moments.excess_kurtosis + 3.0,
+            nr_observations: returns.len(),
+        })
+    }
+
+    /// The standard error of the Sharpe ratio,
+    /// $\sqrt{(1 - \gamma_3 SR + (\gamma_4 - 1) SR^2 / 4) / (n - 1)}$.
+    /// Fails for fewer than two observations or if the variance is not positive, which the moments
+    /// of a distribution with $\gamma_4 \geq \gamma_3^2 + 1$ rule out.
+    pub fn std_error(&self) -> Result<f64, RiskError> {
+        if self.nr_observations < 2 {
+            return Err(RiskError::EmptySample);
+        }
+        let sr = self.sharpe_ratio;
+        let variance = (1.0 - self.skewness * sr + 0.25 * (self.kurtosis - 1.0) * sr * sr)
+            / (self.nr_observations - 1) as f64;
+        if !(variance > 0.0 && variance.is_finite()) {
+            return Err(RiskError::InvalidParameter(format!(
+                "variance {variance} of the Sharpe ratio of the skewness {} and kurtosis {}",
+                self.skewness, self.kurtosis
+            )));
+        }
+        Ok(variance.sqrt())
+    }
+
+    /// The probabilistic Sharpe ratio, the probability that the true Sharpe ratio exceeds the benchmark.
+    pub fn probabilistic_sharpe_ratio(
+        &self,
+        benchmark_sharpe_ratio: f64,
+    ) -> Result<f64, RiskError> {
+        Ok(Gaussian::new(0.0, 1.0)
+            .distribution((self.sharpe_ratio - benchmark_sharpe_ratio) / self.std_error()?))
+    }
+
+    /// The deflated Sharpe ratio, the probabilistic Sharpe ratio over the expected maximum Sharpe ratio
+    /// of the trials run to select the strategy, with the variance of their Sharpe ratios.
+    pub fn deflated_sharpe_ratio(
+        &self,
+        nr_trials: usize,
+        sharpe_variance: f64,
+    ) -> Result<f64, RiskError> {
+        self.probabilistic_sharpe_ratio(expected_maximum_sharpe_ratio(nr_trials, sharpe_variance))
+    }
+
+    /// The minimum track record length, i.e. the number of observations for which the Sharpe ratio
+    /// exceeds the benchmark at the confidence level, or None if it does not exceed the benchmark.
+    pub fn min_track_record_length(
+        &self,
+        benchmark_sharpe_ratio: f64,
+        confidence: f64,
+    ) -> Result<Option<f64>, RiskError> {
+        check_level(confidence)?;
+        let excess = self.sharpe_ratio - benchmark_sharpe_ratio;
+        if excess <= 0.0 {
+            return Ok(None);
+        }
+        let z = Gaussian::new(0.0, 1.0).inverse(confidence);
+        let variance_per_observation =
+            self.std_error()?.powi(2) * (self.nr_observations - 1) as f64;
+        Ok(Some(1.0 + variance_per_observation * (z / excess).powi(2)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64, tolerance: f64) {
+        assert!((a - b).abs() < tolerance, "{a} != {b}");
+    }
+
+    #[test]
+    fn deflating_many_trials() {
+        // normal returns with a Sharpe ratio of 0.1 per day over four years
+        let normal = Gaussian::new(0.0, 1.0);
+        let n = 1_000;
+        let returns: Vec<f64> = (1..=n)
+            .map(|i| 0.001 + 0.01 * normal.inverse(i as f64 / (n + 1) as f64))
+            .collect();
+        let significance = SharpeRatioSignificance::from_returns(&returns, 0.0).unwrap();
+        assert_close(significance.skewness, 0.0, 1e-10);
+        assert_close(significance.kurtosis, 3.0, 0.1);
+        let sr = significance.sharpe_ratio;
+        assert_close(
+            significance.std_error().unwrap(),
+            ((1.0 + 0.5 * sr * sr) / 999.0).sqrt(),
+            1e-3,
+        );
+        assert_close(
+            significance.probabilistic_sharpe_ratio(sr).unwrap(),
+            0.5,
+            1e-12,
+        );
+        let psr = significance.probabilistic_sharpe_ratio(0.0).unwrap();
+        assert!(psr > 0.99);
+
+        // a single trial is not deflated, more trials raise the bar
+        assert_eq!(significance.deflated_sharpe_ratio(1, 0.01).unwrap(), psr);
+        let few = significance.deflated_sharpe_ratio(10, 0.001).unwrap();
+        let many = significance.deflated_sharpe_ratio(1_000, 0.001).unwrap();
+        assert!(psr > few && few > many && many < 0.5);
+        assert_close(expected_maximum_sharpe_ratio(1_000, 1.0), 3.26, 0.01);
+
+        // negative skewness and fat tails widen the error
+        let skewed = SharpeRatioSignificance {
+            skewness: -1.0,
+            kurtosis: 6.0,
+            ..significance
+        };
+        assert!(skewed.std_error().unwrap() > significance.std_error().unwrap());
+        let track_record = significance.min_track_record_length(0.0, 0.95).unwrap();
+        assert!(track_record.unwrap() < n as f64);
+        assert_eq!(
+            significance.min_track_record_length(0.2, 0.95).unwrap(),
+            None
+        );
+
+        // moments violating the kurtosis bound make a negative variance
+        let inconsistent = SharpeRatioSignificance {
+            sharpe_ratio: 1.0,
+            skewness: 3.0,
+            kurtosis: 1.0,
+            ..significance
+        };
+        assert!(matches!(
+            inconsistent.std_error(),
+            Err(RiskError::InvalidParameter(_))
+        ));
+        assert!(inconsistent.probabilistic_sharpe_ratio(0.0).is_err());
+    }
+}
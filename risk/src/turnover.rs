@@ -0,0 +1,181 @@
+use crate::error::RiskError;
+use crate::risk_figures::sharpe_ratio;
+
+/// Costs proportional to the traded value: a linear fee (commission, market impact) plus half the
+/// bid-ask spread, both as fractions of the traded value.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TransactionCosts {
+    pub linear: f64,
+    pub spread: f64,
+}
+
+impl TransactionCosts {
+    pub fn new(linear: f64, spread: f64) -> Self {
+        Self { linear, spread }
+    }
+
+    /// The cost per unit of traded value.
+    pub fn rate(&self) -> f64 {
+        self.linear + 0.5 * self.spread
+    }
+}
+
+/// The target weights of a strategy at the starts of the periods and the asset returns over the
+/// periods, where the weights drift with the returns until the next rebalancing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RebalancingHistory {
+    weights: Vec<Vec<f64>>,
+    returns: Vec<Vec<f64>>,
+}
+
+impl RebalancingHistory {
+    pub fn new(weights: Vec<Vec<f64>>, returns: Vec<Vec<f64>>) -> Result<Self, RiskError> {
+        let nr_assets = weights.first().ok_or(RiskError::EmptySample)?.len();
+        if weights.len() != returns.len()
+            || weights
+                .iter()
+                .chain(&returns)
+                .any(|row| row.len() != nr_assets)
+        {
+            return Err(RiskError::InvalidParameter(
+                "weights and returns of different dimensions".to_string(),
+            ));
+        }
+        if weights.iter().chain(&returns).flatten().any(|x| x.is_nan()) {
+            return Err(RiskError::NaNSample);
+        }
+        Ok(Self { weights, returns })
+    }
+
+    pub fn len(&self) -> usize {
+        self.weights.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.weights.is_empty()
+    }
+
+    /// The returns of the portfolio before costs, $\sum_i w_i r_i$ per period.
+    pub fn gross_returns(&self) -> Vec<f64> {
+        self.weights
+            .iter()
+            .zip(&self.returns)
+            .map(|(w, r)| w.iter().zip(r).map(|(w, r)| w * r).sum())
+            .collect()
+    }
+
+    /// The traded value per unit of wealth at the start of each period, $\sum_i |w_{t,i} - \tilde w_{t,i}|$
+    /// for the weights $\tilde w_t$ drifted from the previous period, where the first period builds
+    /// the portfolio from cash. Half of it is the one-way turnover.
+    pub fn turnover(&self) -> Vec<f64> {
+        let gross = self.gross_returns();
+        let mut drifted = vec![0.0; self.weights[0].len()];
+        let mut turnover = Vec::with_capacity(self.len());
+        for ((weights, returns), portfolio_return) in
+            self.weights.iter().zip(&self.returns).zip(gross)
+        {
+            turnover.push(
+                weights
+                    .iter()
+                    .zip(&drifted)
+                    .map(|(w, d)| (w - d).abs())
+                    .sum(),
+            );
+            drifted = weights
+                .iter()
+                .zip(returns)
+                .map(|(w, r)| w * (1.0 + r) / (1.0 + portfolio_return))
+                .collect();
+        }
+        turnover
+    }
+
+    /// The mean turnover per period, excluding the initial build-up of the portfolio.
+    pub fn mean_turnover(&self) -> f64 {
+        let turnover = self.turnover();
+        if turnover.len() < 2 {
+            return 0.0;
+        }
+        turnover[1..].iter().sum::<f64>() / (turnover.len() - 1) as f64
+    }
+
+    /// The returns after the costs of the trades at the starts of the periods.
+    pub fn net_returns(&self, costs: &TransactionCosts) -> Vec<f64> {
+        self.gross_returns()
+            .into_iter()
+            .zip(self.turnover())
+            .map(|(gross, traded)| gross - costs.rate() * traded)
+            .collect()
+    }
+
+    /// The Sharpe ratio per period of the net returns over the risk-free rate per period.
+    pub fn net_sharpe_ratio(
+        &self,
+        costs: &TransactionCosts,
+        riskfree_rate: f64,
+    ) -> Result<f64, RiskError> {
+        let net = self.net_returns(costs);
+        if net.len() < 2 {
+            return Err(RiskError::EmptySample);
+        }
+        let n = net.len() as f64;
+        let mean = net.iter().sum::<f64>() / n;
+        let variance = net.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        sharpe_ratio(mean, riskfree_rate, variance.sqrt(), None)
+    }
+
+    /// The cost per unit of traded value at which the mean net return equals the risk-free rate,
+    /// a measure of the capacity of the strategy to absorb costs and market impact.
+    pub fn break_even_cost(&self, riskfree_rate: f64) -> Result<f64, RiskError> {
+        let total_turnover: f64 = self.turnover().iter().sum();
+        if total_turnover == 0.0 {
+            return Err(RiskError::ZeroDivision);
+        }
+        let excess: f64 = self.gross_returns().iter().map(|r| r - riskfree_rate).sum();
+        Ok(excess / total_turnover)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64, tolerance: f64) {
+        assert!((a - b).abs() < tolerance, "{a} != {b}");
+    }
+
+    #[test]
+    fn costs_of_rebalancing() {
+        // a 50/50 portfolio rebalanced after one asset gains 10% and the other loses 10%
+        let weights = vec![vec![0.5, 0.5]; 3];
+        let returns = vec![vec![0.1, -0.1], vec![0.02, 0.01], vec![0.0, 0.0]];
+        let history = RebalancingHistory::new(weights, returns).unwrap();
+        assert_eq!(history.gross_returns()[0], 0.0);
+        let turnover = history.turnover();
+        assert_close(turnover[0], 1.0, 1e-12);
+        // the drifted weights 0.55 and 0.45 trade back to 0.5 each
+        assert_close(turnover[1], 0.1, 1e-12);
+        assert!(turnover[2] > 0.0 && turnover[2] < 0.01);
+        assert_close(
+            history.mean_turnover(),
+            0.5 * (turnover[1] + turnover[2]),
+            1e-12,
+        );
+
+        let costs = TransactionCosts::new(0.001, 0.002);
+        let net = history.net_returns(&costs);
+        assert_close(net[1], 0.015 - 0.002 * 0.1, 1e-12);
+        assert!(
+            history.net_sharpe_ratio(&costs, 0.0).unwrap()
+                < history
+                    .net_sharpe_ratio(&TransactionCosts::default(), 0.0)
+                    .unwrap()
+        );
+        // at the break-even cost the mean net return vanishes
+        let break_even = history.break_even_cost(0.0).unwrap();
+        let net = history.net_returns(&TransactionCosts::new(break_even, 0.0));
+        assert_close(net.iter().sum::<f64>(), 0.0, 1e-12);
+
+        assert!(RebalancingHistory::new(vec![vec![1.0]], vec![vec![0.1, 0.2]]).is_err());
+    }
+}
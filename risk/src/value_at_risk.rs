@@ -0,0 +1,262 @@
+use crate::error::RiskError;
+
+/// Sorted losses of an empirical P&L distribution, where a loss is the negative P&L.
+/// The empirical quantiles are shared by the VaR, ES and distortion risk measures.
+#[derive(Clone, Debug)]
+pub struct EmpiricalLosses {
+    /// losses in ascending order
+    sorted_losses: Vec<f64>,
+}
+
+impl EmpiricalLosses {
+    /// From P&L samples, e.g. the simulated path values of a `PathEvaluator`.
+    pub fn from_pnl(pnl: &[f64]) -> Result<Self, RiskError> {
+        Self::from_losses(pnl.iter().map(|p| -p).collect())
+    }
+
+    pub fn from_losses(mut losses: Vec<f64>) -> Result<Self, RiskError> {
+        if losses.is_empty() {
+            return Err(RiskError::EmptySample);
+        }
+        if losses.iter().any(|l| l.is_nan()) {
+            return Err(RiskError::NaNSample);
+        }
+        losses.sort_by(|a, b| a.total_cmp(b));
+        Ok(Self {
+            sorted_losses: losses,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.sorted_losses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sorted_losses.is_empty()
+    }
+
+    /// The losses in ascending order.
+    pub fn sorted_losses(&self) -> &[f64] {
+        &self.sorted_losses
+    }
+
+    /// The empirical (lower) quantile $inf \{x : F_n(x) >= level\}$ of the losses.
+    pub fn quantile(&self, level: f64) -> Result<f64, RiskError> {
+        check_level(level)?;
+        let n = self.len();
+        let idx = ((level * n as f64).ceil() as usize).clamp(1, n) - 1;
+        Ok(self.sorted_losses[idx])
+    }
+
+    /// The Value at Risk at the confidence level, e.g. 0.99, as quantile of the losses.
+    /// See https://en.wikipedia.org/wiki/Value_at_risk
+    pub fn value_at_risk(&self, level: f64) -> Result<f64, RiskError> {
+        self.quantile(level)
+    }
+
+    /// The Expected Shortfall at the confidence level, i.e. the average of the worst $n (1 - level)$ losses,
+    /// where the boundary loss enters with its fractional weight.
+    /// See https://en.wikipedia.org/wiki/Expected_shortfall
+    pub fn expected_shortfall(&self, level: f64) -> Result<f64, RiskError> {
+        check_level(level)?;
+        let tail_size = (1.0 - level) * self.len() as f64;
+
+        let mut remaining = tail_size;
+        let mut tail_sum = 0.0;
+        for loss in self.sorted_losses.iter().rev() {
+            if remaining <= 0.0 {
+                break;
+            }
+            let weight = remaining.min(1.0);
+            tail_sum += weight * loss;
+            remaining -= weight;
+        }
+        Ok(tail_sum / tail_size)
+    }
+}
+
+pub(crate) fn check_level(level: f64) -> Result<(), RiskError> {
+    if !(0.0 < level && level < 1.0) {
+        return Err(RiskError::InvalidLevel(level));
+    }
+    Ok(())
+}
+
+/// Scaling of P&L from the base holding period to a longer liquidity horizon.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HoldingPeriodScaling {
+    /// scale the base period P&L by $\sqrt{h / h_{base}}$ (assuming i.i.d. returns)
+    SquareRootOfTime,
+    /// aggregate the base period P&L series to overlapping sums over the horizon
+    OverlappingReturns,
+}
+
+/// A position with its historical P&L per base holding period and the number of base periods
+/// required to liquidate it.
+#[derive(Clone, Debug)]
+pub struct LiquidityPosition {
+    pub name: String,
+    pub pnl: Vec<f64>,
+    pub liquidity_horizon: usize,
+}
+
+impl LiquidityPosition {
+    pub fn new(name: &str, pnl: Vec<f64>, liquidity_horizon: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            pnl,
+            liquidity_horizon,
+        }
+    }
+
+    fn scaled_pnl(&self, scaling: HoldingPeriodScaling) -> Vec<f64> {
+        let horizon = self.liquidity_horizon.max(1);
+        match scaling {
+            HoldingPeriodScaling::SquareRootOfTime => {
+                let factor = (horizon as f64).sqrt();
+                self.pnl.iter().map(|p| p * factor).collect()
+            }
+            HoldingPeriodScaling::OverlappingReturns => self
+                .pnl
+                .windows(horizon)
+                .map(|window| window.iter().sum())
+                .collect(),
+        }
+    }
+}
+
+/// Per position entry of the liquidity-adjusted VaR report.
+#[derive(Clone, Debug)]
+pub struct ScaledPositionReport {
+    pub name: String,
+    pub liquidity_horizon: usize,
+    /// whether the position's P&L was scaled beyond the base holding period
+    pub is_scaled: bool,
+    /// the stand-alone VaR over the base holding period
+    pub base_value_at_risk: f64,
+    /// the stand-alone VaR over the liquidity horizon
+    pub scaled_value_at_risk: f64,
+}
+
+/// The VaR of the portfolio with P&L scaled to the liquidity horizon of each position.
+#[derive(Clone, Debug)]
+pub struct LiquidityAdjustedVar {
+    pub value_at_risk: f64,
+    pub positions: Vec<ScaledPositionReport>,
+}
+
+impl LiquidityAdjustedVar {
+    /// The names of the positions which have been scaled beyond the base holding period.
+    pub fn scaled_positions(&self) -> Vec<&str> {
+        self.positions
+            .iter()
+            .filter(|p| p.is_scaled)
+            .map(|p| p.name.as_str())
+            .collect()
+    }
+}
+
+/// The liquidity-adjusted VaR of the portfolio, where the P&L scenarios of each position are scaled
+/// to its liquidity horizon and aggregated per scenario. For overlapping returns, the scenarios are
+/// aligned to the most recent ones available for all positions.
+pub fn liquidity_adjusted_var(
+    positions: &[LiquidityPosition],
+    level: f64,
+    scaling: HoldingPeriodScaling,
+) -> Result<LiquidityAdjustedVar, RiskError> {
+    check_level(level)?;
+    let scaled: Vec<Vec<f64>> = positions.iter().map(|p| p.scaled_pnl(scaling)).collect();
+    let nr_scenarios = scaled.iter().map(|pnl| pnl.len()).min().unwrap_or(0);
+    if nr_scenarios == 0 {
+        return Err(RiskError::EmptySample);
+    }
+
+    let portfolio_pnl: Vec<f64> = (0..nr_scenarios)
+        .map(|idx| {
+            scaled
+                .iter()
+                .map(|pnl| pnl[pnl.len() - nr_scenarios + idx])
+                .sum()
+        })
+        .collect();
+
+    let reports = positions
+        .iter()
+        .zip(scaled.iter())
+        .map(|(position, scaled_pnl)| {
+            Ok(ScaledPositionReport {
+                name: position.name.to_owned(),
+                liquidity_horizon: position.liquidity_horizon,
+                is_scaled: position.liquidity_horizon > 1,
+                base_value_at_risk: EmpiricalLosses::from_pnl(&position.pnl)?
+                    .value_at_risk(level)?,
+                scaled_value_at_risk: EmpiricalLosses::from_pnl(scaled_pnl)?
+                    .value_at_risk(level)?,
+            })
+        })
+        .collect::<Result<Vec<_>, RiskError>>()?;
+
+    Ok(LiquidityAdjustedVar {
+        value_at_risk: EmpiricalLosses::from_pnl(&portfolio_pnl)?.value_at_risk(level)?,
+        positions: reports,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn var_and_es() {
+        let pnl: Vec<f64> = (1..=100).map(|i| 50.0 - i as f64).collect();
+        let losses = EmpiricalLosses::from_pnl(&pnl).unwrap();
+        assert_eq!(losses.len(), 100);
+
+        // losses are -49, ..., 50
+        assert_eq!(losses.value_at_risk(0.95).unwrap(), 45.0);
+        assert_eq!(losses.value_at_risk(0.99).unwrap(), 49.0);
+        assert_eq!(losses.expected_shortfall(0.95).unwrap(), 48.0);
+        let es = losses.expected_shortfall(0.985).unwrap();
+        assert!((es - (50.0 + 0.5 * 49.0) / 1.5).abs() < 1e-12);
+        assert_eq!(losses.quantile(0.001).unwrap(), -49.0);
+
+        assert!(losses.value_at_risk(1.0).is_err());
+        assert!(EmpiricalLosses::from_pnl(&[]).is_err());
+        assert!(EmpiricalLosses::from_pnl(&[1.0, f64::NAN]).is_err());
+    }
+
+    #[test]
+    fn liquidity_adjusted() {
+        let pnl: Vec<f64> = (0..100).map(|i| ((i * 37) % 100) as f64 - 50.0).collect();
+        let positions = vec![
+            LiquidityPosition::new("liquid", pnl.to_owned(), 1),
+            LiquidityPosition::new("illiquid", pnl.to_owned(), 4),
+        ];
+
+        let sqrt_time =
+            liquidity_adjusted_var(&positions, 0.95, HoldingPeriodScaling::SquareRootOfTime)
+                .unwrap();
+        assert_eq!(sqrt_time.scaled_positions(), vec!["illiquid"]);
+        let illiquid = &sqrt_time.positions[1];
+        assert_eq!(
+            illiquid.scaled_value_at_risk,
+            2.0 * illiquid.base_value_at_risk
+        );
+        // the positions are perfectly correlated
+        assert_eq!(sqrt_time.value_at_risk, 3.0 * illiquid.base_value_at_risk);
+
+        let overlapping =
+            liquidity_adjusted_var(&positions, 0.95, HoldingPeriodScaling::OverlappingReturns)
+                .unwrap();
+        assert_eq!(overlapping.positions[0].scaled_value_at_risk, 45.0);
+        assert!(overlapping.value_at_risk > 0.0);
+
+        let no_history = vec![LiquidityPosition::new("new", vec![1.0, 2.0], 4)];
+        assert!(liquidity_adjusted_var(
+            &no_history,
+            0.95,
+            HoldingPeriodScaling::OverlappingReturns
+        )
+        .is_err());
+    }
+}